@@ -14,6 +14,7 @@ struct BenchConfig {
     data_modifier: DataModifier,
     size: Size,
     name: String,
+    options: DecodeOptions,
 }
 impl Default for BenchConfig {
     fn default() -> Self {
@@ -21,6 +22,7 @@ impl Default for BenchConfig {
             data_modifier: Box::new(|_| {}),
             size: (4096, 4096).into(),
             name: String::new(),
+            options: DecodeOptions::default(),
         }
     }
 }
@@ -61,7 +63,7 @@ fn bench_decoder_with(
                 black_box(&mut bytes.as_ref()),
                 black_box(image),
                 format,
-                &DecodeOptions::default(),
+                &config.options,
             );
             black_box(result).unwrap();
         });
@@ -159,6 +161,13 @@ pub fn uncompressed(c: &mut Criterion) {
     bench_decoder(c, Format::R11G11B10_FLOAT, Rgba, U16);
     bench_decoder(c, Format::R11G11B10_FLOAT, Rgba, F32);
 
+    bench_decoder(c, Format::B5G6R5_UNORM, Rgb, U8);
+    bench_decoder(c, Format::B5G6R5_UNORM, Rgb, U16);
+    bench_decoder(c, Format::B5G5R5A1_UNORM, Rgba, U8);
+    bench_decoder(c, Format::B5G5R5A1_UNORM, Rgba, U16);
+    bench_decoder(c, Format::R10G10B10A2_UNORM, Rgba, U8);
+    bench_decoder(c, Format::R10G10B10A2_UNORM, Rgba, U16);
+
     // sub-sampled formats
     bench_decoder(c, Format::R8G8_B8G8_UNORM, Rgb, U8);
 
@@ -207,16 +216,39 @@ pub fn bc7_modes(c: &mut Criterion) {
     use Precision::*;
 
     let modes = [0, 1, 2, 3, 4, 5, 6, 7];
-    for mode in modes {
-        bench_decoder_with(c, Format::BC7_UNORM, Rgba, U8, |c| {
-            c.data_modifier = Box::new(move |data| {
-                set_bc7_modes(data, mode);
+    for decoder in [Bc7Decoder::TableDriven, Bc7Decoder::Reference] {
+        for mode in modes {
+            bench_decoder_with(c, Format::BC7_UNORM, Rgba, U8, |c| {
+                c.data_modifier = Box::new(move |data| {
+                    set_bc7_modes(data, mode);
+                });
+                c.name = format!("mode {mode} {decoder:?}");
+                c.size = (1024, 1024).into();
+                c.options.bc7_decoder = decoder;
             });
-            c.name = format!("mode {mode}");
-            c.size = (1024, 1024).into();
+        }
+    }
+}
+
+/// BC6H and BC7 blocks are expensive to decode and write to 4 rows of the
+/// output at once. Wide surfaces show whether those scattered writes matter.
+pub fn bc_wide(c: &mut Criterion) {
+    use Channels::*;
+    use Precision::*;
+
+    for size in [(256, 4096), (4096, 256)] {
+        bench_decoder_with(c, Format::BC7_UNORM, Rgba, U8, |c| {
+            c.data_modifier = Box::new(random_bc7_modes);
+            c.name = format!("{}x{}", size.0, size.1);
+            c.size = size.into();
+        });
+        bench_decoder_with(c, Format::BC6H_UF16, Rgb, F32, |c| {
+            c.data_modifier = Box::new(random_bc6_modes);
+            c.name = format!("{}x{}", size.0, size.1);
+            c.size = size.into();
         });
     }
 }
 
-criterion_group!(benches, uncompressed, bc7_modes);
+criterion_group!(benches, uncompressed, bc7_modes, bc_wide);
 criterion_main!(benches);
@@ -6,15 +6,13 @@ struct Image<T> {
     data: Vec<T>,
     size: Size,
     channels: Channels,
-    name: String,
 }
 impl<T: 'static> Image<T> {
-    fn new(data: Vec<T>, size: Size, channels: Channels, name: impl Into<String>) -> Self {
+    fn new(data: Vec<T>, size: Size, channels: Channels) -> Self {
         Self {
             data,
             size,
             channels,
-            name: name.into(),
         }
     }
 
@@ -38,13 +36,13 @@ impl<T: 'static> Image<T> {
         let mut data = vec![T::default(); size.pixels() as usize * channels.count() as usize];
         let mut rng = rand::thread_rng();
         rng.fill(data.as_mut_slice());
-        Image::new(data, size, channels, "random_u8")
+        Image::new(data, size, channels)
     }
 }
 trait ImageAsBytes {
     fn color(&self) -> ColorFormat;
     fn as_bytes(&self) -> &[u8];
-    fn view(&self) -> ImageView;
+    fn view(&self) -> ImageView<'_>;
 }
 impl ImageAsBytes for Image<u8> {
     fn color(&self) -> ColorFormat {
@@ -53,7 +51,7 @@ impl ImageAsBytes for Image<u8> {
     fn as_bytes(&self) -> &[u8] {
         &self.data
     }
-    fn view(&self) -> ImageView {
+    fn view(&self) -> ImageView<'_> {
         ImageView::new(self.as_bytes(), self.size, self.color()).unwrap()
     }
 }
@@ -64,7 +62,7 @@ impl ImageAsBytes for Image<u16> {
     fn as_bytes(&self) -> &[u8] {
         zerocopy::IntoBytes::as_bytes(self.data.as_slice())
     }
-    fn view(&self) -> ImageView {
+    fn view(&self) -> ImageView<'_> {
         ImageView::new(self.as_bytes(), self.size, self.color()).unwrap()
     }
 }
@@ -75,7 +73,7 @@ impl ImageAsBytes for Image<f32> {
     fn as_bytes(&self) -> &[u8] {
         zerocopy::IntoBytes::as_bytes(self.data.as_slice())
     }
-    fn view(&self) -> ImageView {
+    fn view(&self) -> ImageView<'_> {
         ImageView::new(self.as_bytes(), self.size, self.color()).unwrap()
     }
 }
@@ -165,7 +163,6 @@ pub fn encode_compressed(c: &mut Criterion) {
     use Channels::*;
 
     // images
-    let random: Image<f32> = Image::random(Size::new(128, 128), Rgba);
     let random_rgb: Image<f32> = Image::random(Size::new(128, 128), Rgb);
     let random_tiny: Image<f32> = Image::random(Size::new(16, 16), Rgba);
 
@@ -203,6 +200,19 @@ pub fn encode_compressed(c: &mut Criterion) {
     bench_encoder(c, Format::BC4_UNORM, high, &random_rgb);
     bench_encoder(c, Format::BC4_UNORM, dither, &random_rgb);
     bench_encoder(c, Format::BC4_UNORM, unreasonable, &random_tiny);
+
+    let mut bc7_fast_rgb = EncodeOptions::default();
+    bc7_fast_rgb.bc7 = Some(Bc7Options::FAST_RGB);
+    bench_encoder(c, Format::BC7_UNORM, fast, &random_rgb);
+    bench_encoder(c, Format::BC7_UNORM, normal, &random_rgb);
+    bench_encoder(c, Format::BC7_UNORM, high, &random_rgb);
+    bench_encoder(c, Format::BC7_UNORM, &bc7_fast_rgb, &random_rgb);
+    bench_encoder(c, Format::BC7_UNORM, unreasonable, &random_tiny);
+
+    bench_encoder(c, Format::BC6H_UF16, fast, &random_rgb);
+    bench_encoder(c, Format::BC6H_UF16, normal, &random_rgb);
+    bench_encoder(c, Format::BC6H_UF16, high, &random_rgb);
+    bench_encoder(c, Format::BC6H_SF16, normal, &random_rgb);
 }
 
 pub fn generate_mipmaps(c: &mut Criterion) {
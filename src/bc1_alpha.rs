@@ -0,0 +1,144 @@
+use std::io::Read;
+
+use crate::iter::SurfaceIterator;
+use crate::{util::div_ceil, Channels, DdsInfo, DecodeError, Format, Size};
+
+/// An analysis of how the blocks of BC1 surfaces use 1-bit alpha.
+///
+/// BC1 blocks whose first endpoint is less than or equal to the second
+/// endpoint use a 3-color mode, in which index 3 denotes a transparent black
+/// pixel. BC1 textures that contain transparent pixels are sometimes called
+/// "DXT1a". Since BC1 textures without any transparent pixels are opaque,
+/// [`Self::channels`] can be used to determine whether the alpha channel of
+/// a BC1 texture is meaningful.
+///
+/// ```
+/// # use dds::*;
+/// // color0 <= color1, all pixels use index 3
+/// let block = [0, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+/// let usage = Bc1AlphaUsage::from_surface(&block, Size::new(4, 4));
+/// assert_eq!(usage.transparent_pixels, 16);
+/// assert_eq!(usage.channels(), Channels::Rgba);
+///
+/// // color0 > color1, 4-color mode
+/// let block = [0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+/// let usage = Bc1AlphaUsage::from_surface(&block, Size::new(4, 4));
+/// assert!(!usage.uses_alpha());
+/// assert_eq!(Format::BC1_UNORM.channels_with_bc1_alpha(&usage), Channels::Rgb);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Bc1AlphaUsage {
+    /// The number of analyzed blocks.
+    pub blocks: u64,
+    /// The number of blocks that use the 3-color mode.
+    ///
+    /// Blocks in 3-color mode may still be opaque if none of their pixels use
+    /// index 3.
+    pub three_color_blocks: u64,
+    /// The number of blocks with at least one transparent pixel.
+    pub transparent_blocks: u64,
+    /// The number of transparent pixels.
+    ///
+    /// Pixels of blocks at the right and bottom edges of a surface that lie
+    /// outside the surface are not counted.
+    pub transparent_pixels: u64,
+}
+
+impl Bc1AlphaUsage {
+    /// Analyzes the blocks of a single BC1 surface of the given size.
+    ///
+    /// If `data` is shorter than the surface, only the blocks in `data` are
+    /// analyzed. Bytes after the surface are ignored.
+    pub fn from_surface(data: &[u8], size: Size) -> Self {
+        let mut usage = Self::default();
+        usage.add_surface(data, size);
+        usage
+    }
+
+    /// Analyzes all surfaces in the data section of a BC1 DDS file.
+    ///
+    /// The reader is expected to be positioned at the start of the data
+    /// section. If this operation succeeds, the reader will be positioned at
+    /// the end of the data section.
+    ///
+    /// If the format of the file is not [`Format::BC1_UNORM`], nothing is
+    /// read and the returned usage contains no blocks.
+    pub fn from_reader<R: Read>(reader: &mut R, info: &DdsInfo) -> Result<Self, DecodeError> {
+        let mut usage = Self::default();
+        if info.format() != Format::BC1_UNORM {
+            return Ok(usage);
+        }
+
+        let mut data = Vec::new();
+        let mut iter = SurfaceIterator::new_with_order(info.layout(), info.mip_order());
+        while let Some(surface) = iter.current() {
+            let len = usize::try_from(surface.data_len())
+                .map_err(|_| DecodeError::MemoryLimitExceeded)?;
+            data.resize(len, 0);
+            reader.read_exact(&mut data)?;
+            usage.add_surface(&data, surface.size());
+            iter.advance();
+        }
+
+        Ok(usage)
+    }
+
+    fn add_surface(&mut self, data: &[u8], size: Size) {
+        let blocks_x = div_ceil(size.width, 4) as usize;
+        let blocks_y = div_ceil(size.height, 4) as usize;
+        if blocks_x == 0 {
+            return;
+        }
+
+        for (i, block) in data.chunks_exact(8).take(blocks_x * blocks_y).enumerate() {
+            self.blocks += 1;
+
+            let color0 = u16::from_le_bytes([block[0], block[1]]);
+            let color1 = u16::from_le_bytes([block[2], block[3]]);
+            if color0 > color1 {
+                continue;
+            }
+            self.three_color_blocks += 1;
+
+            // only pixels inside the surface count
+            let x = (i % blocks_x) as u32 * 4;
+            let y = (i / blocks_x) as u32 * 4;
+            let width = (size.width - x).min(4);
+            let height = (size.height - y).min(4);
+
+            let indexes = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+            let mut transparent = 0;
+            for py in 0..height {
+                for px in 0..width {
+                    let index = (indexes >> ((py * 4 + px) * 2)) & 0b11;
+                    if index == 3 {
+                        transparent += 1;
+                    }
+                }
+            }
+
+            if transparent > 0 {
+                self.transparent_blocks += 1;
+                self.transparent_pixels += transparent;
+            }
+        }
+    }
+
+    /// Whether any pixel is transparent.
+    pub fn uses_alpha(&self) -> bool {
+        self.transparent_pixels > 0
+    }
+
+    /// The channels of BC1 surfaces with this alpha usage.
+    ///
+    /// This is [`Channels::Rgba`] if any pixel is transparent and
+    /// [`Channels::Rgb`] otherwise.
+    pub fn channels(&self) -> Channels {
+        if self.uses_alpha() {
+            Channels::Rgba
+        } else {
+            Channels::Rgb
+        }
+    }
+}
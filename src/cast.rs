@@ -27,15 +27,6 @@ pub(crate) fn as_bytes_mut<T: Castable>(buffer: &mut [T]) -> &mut [u8] {
     buffer.as_mut_bytes()
 }
 
-/// An implementation of `slice::as_flattened` for more Rust versions.
-pub(crate) fn as_flattened<const N: usize, T>(buffer: &[[T; N]]) -> &[T]
-where
-    T: Castable,
-    [T; N]: Castable,
-{
-    // PANIC SAFETY: This unwrap can never fail, because T isn't a ZST.
-    from_bytes(as_bytes(buffer)).unwrap()
-}
 /// An implementation of `slice::as_flattened_mut` for more Rust versions.
 pub(crate) fn as_flattened_mut<const N: usize, T>(buffer: &mut [[T; N]]) -> &mut [T]
 where
@@ -205,7 +196,7 @@ pub(crate) fn slice_ne_to_le(precision: Precision, buffer: &mut [u8]) {
     match precision {
         Precision::U8 => {}
         Precision::U16 => slice_ne_to_le_16(buffer),
-        Precision::F32 => slice_ne_to_le_32(buffer),
+        Precision::F32 | Precision::U32 => slice_ne_to_le_32(buffer),
     }
 }
 
@@ -2,9 +2,30 @@
 //!
 //! Most magic constants for the U/SNorm conversion are from:
 //! <https://rundevelopment.github.io/projects/multiply-add-constants-finder>
+//!
+//! The 5-, 6-, and 10-bit to 16-bit conversions are also available as lookup
+//! tables (`n5::N16_LUT` etc.) generated at compile time from the same
+//! formulas. Which one is faster depends on the format: decoding `B5G6R5` and
+//! `R10G10B10A2` to U16 is faster with tables, while `B5G5R5A1` to U16 and all
+//! U8 outputs are faster with multiply-add-shift, because the compiler can
+//! vectorize those row loops (tables need gathers). See the `decode` benches.
 
 use super::Norm;
 
+/// Generates a lookup table at compile time by evaluating the given `const fn`
+/// for every possible input.
+macro_rules! lut {
+    ($f:path, $ty:ty, $len:expr) => {{
+        let mut table: [$ty; $len] = [0; $len];
+        let mut i = 0;
+        while i < $len {
+            table[i] = $f(i as _);
+            i += 1;
+        }
+        table
+    }};
+}
+
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct B5G6R5 {
     pub r5: u16,
@@ -31,9 +52,9 @@ impl B5G6R5 {
     #[inline(always)]
     pub fn to_n16(self) -> [u16; 3] {
         [
-            n5::n16(self.r5 as u8),
-            n6::n16(self.g6 as u8),
-            n5::n16(self.b5 as u8),
+            n5::N16_LUT[self.r5 as usize],
+            n6::N16_LUT[self.g6 as usize],
+            n5::N16_LUT[self.b5 as usize],
         ]
     }
     #[inline(always)]
@@ -197,6 +218,7 @@ pub(crate) mod n4 {
         const F: f32 = 1.0 / 15.0;
         x as f32 * F
     }
+    #[cfg(test)]
     #[inline(always)]
     pub fn f32_exact(x: u8) -> f32 {
         debug_assert!(x <= 15);
@@ -214,13 +236,16 @@ pub(crate) mod n4 {
 
 /// Functions for converting **FROM Unorm5** values to other formats.
 pub(crate) mod n5 {
+    /// Lookup table for [`n16`].
+    pub static N16_LUT: [u16; 32] = lut!(n16, u16, 32);
+
     #[inline(always)]
     pub fn n8(x: u8) -> u8 {
         debug_assert!(x <= 31);
         ((x as u16 * 2108 + 92) >> 8) as u8
     }
     #[inline(always)]
-    pub fn n16(x: u8) -> u16 {
+    pub const fn n16(x: u8) -> u16 {
         debug_assert!(x <= 31);
         ((x as u32 * 138547200) >> 16) as u16
     }
@@ -230,6 +255,7 @@ pub(crate) mod n5 {
         const F: f32 = 1.0 / 31.0;
         x as f32 * F
     }
+    #[cfg(test)]
     #[inline(always)]
     pub fn f32_exact(x: u8) -> f32 {
         debug_assert!(x <= 31);
@@ -247,13 +273,16 @@ pub(crate) mod n5 {
 
 /// Functions for converting **FROM Unorm5** values to other formats.
 pub(crate) mod n6 {
+    /// Lookup table for [`n16`].
+    pub static N16_LUT: [u16; 64] = lut!(n16, u16, 64);
+
     #[inline(always)]
     pub fn n8(x: u8) -> u8 {
         debug_assert!(x <= 63);
         ((x as u16 * 1036 + 132) >> 8) as u8
     }
     #[inline(always)]
-    pub fn n16(x: u8) -> u16 {
+    pub const fn n16(x: u8) -> u16 {
         debug_assert!(x <= 63);
         ((x as u32 * 68173056 + 30976) >> 16) as u16
     }
@@ -263,6 +292,7 @@ pub(crate) mod n6 {
         const F: f32 = 1.0 / 63.0;
         x as f32 * F
     }
+    #[cfg(test)]
     #[inline(always)]
     pub fn f32_exact(x: u8) -> f32 {
         debug_assert!(x <= 63);
@@ -304,13 +334,16 @@ pub(crate) mod n8 {
 
 /// Functions for converting **FROM Unorm10** values to other formats.
 pub(crate) mod n10 {
+    /// Lookup table for [`n16`].
+    pub static N16_LUT: [u16; 1024] = lut!(n16, u16, 1024);
+
     #[inline(always)]
     pub fn n8(x: u16) -> u8 {
         debug_assert!(x <= 1023);
         ((x as u32 * 16336 + 32656) >> 16) as u8
     }
     #[inline(always)]
-    pub fn n16(x: u16) -> u16 {
+    pub const fn n16(x: u16) -> u16 {
         debug_assert!(x <= 1023);
         ((x as u32 * 4198340 + 32660) >> 16) as u16
     }
@@ -320,6 +353,7 @@ pub(crate) mod n10 {
         const F: f32 = 1.0 / 1023.0;
         x as f32 * F
     }
+    #[cfg(test)]
     #[inline(always)]
     pub fn f32_exact(x: u16) -> f32 {
         debug_assert!(x <= 1023);
@@ -360,6 +394,17 @@ pub(crate) mod n16 {
     pub fn from_f32(x: f32) -> u16 {
         (x * 65535.0 + 0.5) as u16
     }
+    #[inline(always)]
+    pub fn n32(x: u16) -> u32 {
+        x as u32 * 65537
+    }
+}
+
+/// Functions for converting **FROM Unorm32** values to other formats.
+pub(crate) mod n32 {
+    pub fn f32_exact(x: u32) -> f32 {
+        (x as f64 / u32::MAX as f64) as f32
+    }
 }
 
 /// Functions for converting **FROM Snorm8** values to other formats.
@@ -391,6 +436,7 @@ pub(crate) mod s8 {
         x as f32 * F
     }
     /// Unsigned f32.
+    #[cfg(test)]
     #[inline(always)]
     pub fn uf32_exact(mut x: u8) -> f32 {
         x = norm(x);
@@ -445,6 +491,7 @@ pub(crate) mod s16 {
         x as f32 * F
     }
     /// Unsigned f32.
+    #[cfg(test)]
     #[inline(always)]
     pub fn uf32_exact(mut x: u16) -> f32 {
         x = norm(x);
@@ -547,6 +594,11 @@ pub(crate) mod fp {
     pub fn n16(x: f32) -> u16 {
         (x * 65535.0 + 0.5) as u16
     }
+    #[inline(always)]
+    pub fn n32(x: f32) -> u32 {
+        // f32 doesn't have enough precision for the multiplication
+        (x as f64 * u32::MAX as f64 + 0.5) as u32
+    }
 }
 
 /// Functions for converting `f16` values to other formats.
@@ -1164,6 +1216,17 @@ pub(crate) mod yuv8 {
         [r, g, b]
     }
 
+    /// Returns the YUV values as is, without converting to RGB.
+    pub fn raw_n8(yuv: [u8; 3]) -> [u8; 3] {
+        yuv
+    }
+    pub fn raw_n16(yuv: [u8; 3]) -> [u16; 3] {
+        yuv.map(super::n8::n16)
+    }
+    pub fn raw_f32(yuv: [u8; 3]) -> [f32; 3] {
+        yuv.map(super::n8::f32)
+    }
+
     pub fn from_rgb_f32(rgb: [f32; 3]) -> [u8; 3] {
         let [r, g, b] = rgb.map(|c| c * 255.);
 
@@ -1202,6 +1265,17 @@ pub(crate) mod yuv10 {
         [r, g, b]
     }
 
+    /// Returns the YUV values as is, without converting to RGB.
+    pub fn raw_n8(yuv: [u16; 3]) -> [u8; 3] {
+        yuv.map(|c| super::n10::n8(c.min(1023)))
+    }
+    pub fn raw_n16(yuv: [u16; 3]) -> [u16; 3] {
+        yuv.map(|c| super::n10::n16(c.min(1023)))
+    }
+    pub fn raw_f32(yuv: [u16; 3]) -> [f32; 3] {
+        yuv.map(|c| super::n10::f32(c.min(1023)))
+    }
+
     pub fn from_rgb_f32(rgb: [f32; 3]) -> [u16; 3] {
         let [r, g, b] = rgb.map(|c| c * 1023.);
 
@@ -1240,6 +1314,17 @@ pub(crate) mod yuv16 {
         [r, g, b]
     }
 
+    /// Returns the YUV values as is, without converting to RGB.
+    pub fn raw_n8(yuv: [u16; 3]) -> [u8; 3] {
+        yuv.map(super::n16::n8)
+    }
+    pub fn raw_n16(yuv: [u16; 3]) -> [u16; 3] {
+        yuv
+    }
+    pub fn raw_f32(yuv: [u16; 3]) -> [f32; 3] {
+        yuv.map(super::n16::f32)
+    }
+
     pub fn from_rgb_f32(rgb: [f32; 3]) -> [u16; 3] {
         let [r, g, b] = rgb.map(|c| c * 65535.);
 
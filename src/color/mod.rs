@@ -1,4 +1,4 @@
-use std::mem::size_of;
+use std::{mem::size_of, num::NonZeroU32};
 
 use crate::{cast, Size};
 
@@ -11,6 +11,7 @@ pub(crate) use oklab::*;
 
 /// The number and semantics of the color channels in a surface.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Channels {
     /// The image only contains a single (color) channel.
     ///
@@ -39,6 +40,7 @@ impl Channels {
 
 /// The precision/bit depth of the values in a surface.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Precision {
     /// 8-bit unsigned integer.
     ///
@@ -52,6 +54,16 @@ pub enum Precision {
     ///
     /// Values **might not** be normalized to the range `[0, 1]`.
     F32,
+    /// 32-bit unsigned integer.
+    ///
+    /// This represents normalized values in the range `[0, 4294967295]`.
+    ///
+    /// No format currently stores more than 16 bits of integer precision, so
+    /// all formats are decoded to this precision by widening [`Self::U16`]
+    /// values (integer formats) or converting [`Self::F32`] values (HDR
+    /// formats). Images with this precision are converted to [`Self::F32`]
+    /// before encoding.
+    U32,
 }
 impl Precision {
     /// Returns the size of a single value of this precision in bytes.
@@ -59,13 +71,14 @@ impl Precision {
         match self {
             Self::U8 => 1,
             Self::U16 => 2,
-            Self::F32 => 4,
+            Self::F32 | Self::U32 => 4,
         }
     }
 }
 
 /// A color format with a specific number of channels and precision.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ColorFormat {
     pub channels: Channels,
     pub precision: Precision,
@@ -100,6 +113,39 @@ impl ColorFormat {
         }
     }
 
+    /// The number of bytes between the start of two rows of a decoded
+    /// surface whose rows are padded to a multiple of `row_alignment` bytes.
+    ///
+    /// E.g. D3D12 requires a row alignment of 256 bytes for texture uploads.
+    ///
+    /// If the number of bytes is larger than `isize::MAX`, `None` is returned.
+    pub fn aligned_row_pitch(&self, width: u32, row_alignment: NonZeroU32) -> Option<usize> {
+        let row_bytes = width as u64 * self.bytes_per_pixel() as u64;
+        let alignment = row_alignment.get() as u64;
+        let pitch = row_bytes.checked_add(alignment - 1)? / alignment * alignment;
+        if pitch < isize::MAX as u64 {
+            Some(pitch as usize)
+        } else {
+            None
+        }
+    }
+    /// The number of bytes of a decoded surface whose rows are padded to a
+    /// multiple of `row_alignment` bytes.
+    ///
+    /// This is the row pitch times the height of the surface. See
+    /// [`Self::aligned_row_pitch`].
+    ///
+    /// If the number of bytes is larger than `isize::MAX`, `None` is returned.
+    pub fn aligned_buffer_size(&self, size: Size, row_alignment: NonZeroU32) -> Option<usize> {
+        let pitch = self.aligned_row_pitch(size.width, row_alignment)?;
+        let bytes = (pitch as u64).checked_mul(size.height as u64)?;
+        if bytes < isize::MAX as u64 {
+            Some(bytes as usize)
+        } else {
+            None
+        }
+    }
+
     /// Returns a unique key for this color format.
     ///
     /// The key is guaranteed to be less than 32.
@@ -124,6 +170,11 @@ impl ColorFormat {
     pub const RGBA_U8: Self = Self::new(Channels::Rgba, Precision::U8);
     pub const RGBA_U16: Self = Self::new(Channels::Rgba, Precision::U16);
     pub const RGBA_F32: Self = Self::new(Channels::Rgba, Precision::F32);
+
+    pub const GRAYSCALE_U32: Self = Self::new(Channels::Grayscale, Precision::U32);
+    pub const ALPHA_U32: Self = Self::new(Channels::Alpha, Precision::U32);
+    pub const RGB_U32: Self = Self::new(Channels::Rgb, Precision::U32);
+    pub const RGBA_U32: Self = Self::new(Channels::Rgba, Precision::U32);
 }
 impl core::fmt::Display for ColorFormat {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -154,9 +205,22 @@ impl ColorFormatSet {
         ColorFormat::RGB_F32,
         ColorFormat::RGBA_F32,
     ]);
+    pub const U32: Self = Self::from_slice(&[
+        ColorFormat::GRAYSCALE_U32,
+        ColorFormat::ALPHA_U32,
+        ColorFormat::RGB_U32,
+        ColorFormat::RGBA_U32,
+    ]);
 
     pub const EMPTY: Self = Self { data: 0 };
     pub const ALL: Self = Self {
+        data: Self::U8.data | Self::U16.data | Self::F32.data | Self::U32.data,
+    };
+    /// The color formats that all decoder and encoder sets have to support.
+    ///
+    /// [`Precision::U32`] is derived from [`Precision::U16`] and
+    /// [`Precision::F32`], see [`widen_to_u32`].
+    pub const REQUIRED: Self = Self {
         data: Self::U8.data | Self::U16.data | Self::F32.data,
     };
 
@@ -165,6 +229,7 @@ impl ColorFormatSet {
             Precision::U8 => Self::U8,
             Precision::U16 => Self::U16,
             Precision::F32 => Self::F32,
+            Precision::U32 => Self::U32,
         }
     }
     pub const fn from_single(format: ColorFormat) -> Self {
@@ -184,12 +249,6 @@ impl ColorFormatSet {
         Self { data }
     }
 
-    pub const fn is_all(self) -> bool {
-        self.data == Self::ALL.data
-    }
-    pub const fn is_empty(self) -> bool {
-        self.data == 0
-    }
     pub const fn len(self) -> u8 {
         self.data.count_ones() as u8
     }
@@ -200,9 +259,6 @@ impl ColorFormatSet {
     pub const fn contains_all(&self, other: Self) -> bool {
         (self.data & other.data) == other.data
     }
-    pub const fn contains_any(&self, other: Self) -> bool {
-        (self.data & other.data) != 0
-    }
 
     pub const fn union(self, other: Self) -> Self {
         Self {
@@ -232,13 +288,23 @@ impl Norm for u16 {
     const HALF: Self = 32768;
     const ONE: Self = u16::MAX;
 }
+impl Norm for u32 {
+    const ZERO: Self = 0;
+    const HALF: Self = 2147483648;
+    const ONE: Self = u32::MAX;
+}
 impl Norm for f32 {
     const ZERO: Self = 0.0;
     const HALF: Self = 0.5;
     const ONE: Self = 1.0;
 }
 
-pub(crate) trait WithPrecision {
+/// A type that can hold the channel values of a decoded image.
+///
+/// This is implemented for `u8`, `u16`, `u32`, and `f32`, which correspond to
+/// the variants of [`Precision`].
+pub trait WithPrecision: Copy + Default {
+    /// The precision of values of this type.
     const PRECISION: Precision;
 }
 impl WithPrecision for u8 {
@@ -250,6 +316,9 @@ impl WithPrecision for u16 {
 impl WithPrecision for f32 {
     const PRECISION: Precision = Precision::F32;
 }
+impl WithPrecision for u32 {
+    const PRECISION: Precision = Precision::U32;
+}
 
 pub(crate) fn convert_channels<Precision>(
     from: Channels,
@@ -326,6 +395,40 @@ pub(crate) fn convert_channels_for(
         Precision::U8 => convert_channels::<u8>(from.channels, to, from_buffer, to_buffer),
         Precision::U16 => convert_channels::<u16>(from.channels, to, from_buffer, to_buffer),
         Precision::F32 => convert_channels::<f32>(from.channels, to, from_buffer, to_buffer),
+        Precision::U32 => convert_channels::<u32>(from.channels, to, from_buffer, to_buffer),
+    }
+}
+
+/// The precision that is decoded before widening to [`Precision::U32`].
+pub(crate) const fn u32_source_precision(native: Precision) -> Precision {
+    match native {
+        Precision::F32 | Precision::U32 => Precision::F32,
+        Precision::U8 | Precision::U16 => Precision::U16,
+    }
+}
+/// Converts the values of the given precision at the start of the buffer to
+/// [`Precision::U32`] in place.
+///
+/// The buffer must be exactly large enough to hold the U32 values.
+pub(crate) fn widen_to_u32(from: Precision, buffer: &mut [u8]) {
+    debug_assert!(buffer.len() % 4 == 0);
+    let count = buffer.len() / 4;
+
+    match from {
+        Precision::U16 => {
+            // go back to front, so values aren't overwritten before they are read
+            for i in (0..count).rev() {
+                let value = u16::from_ne_bytes([buffer[i * 2], buffer[i * 2 + 1]]);
+                buffer[i * 4..i * 4 + 4].copy_from_slice(&n16::n32(value).to_ne_bytes());
+            }
+        }
+        Precision::F32 => {
+            for chunk in buffer.chunks_exact_mut(4) {
+                let value = f32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                chunk.copy_from_slice(&fp::n32(value).to_ne_bytes());
+            }
+        }
+        Precision::U8 | Precision::U32 => unreachable!("not a source precision"),
     }
 }
 
@@ -363,6 +466,7 @@ pub(crate) fn convert_to_rgba_f32(
         // lot easier.
         Precision::U8 => convert_t_to_rgba_f32(channels, from_buffer, to_buffer, n8::f32_exact),
         Precision::U16 => convert_t_to_rgba_f32(channels, from_buffer, to_buffer, n16::f32_exact),
+        Precision::U32 => convert_t_to_rgba_f32(channels, from_buffer, to_buffer, n32::f32_exact),
         Precision::F32 => {
             // since the precision is already f32, we just need to convert
             // channels
@@ -127,6 +127,12 @@ pub(crate) fn srgb_to_oklab(rgb: Vec3A) -> Vec3A {
 pub(crate) fn oklab_to_srgb(lab: Vec3A) -> Vec3A {
     oklab_to_srgb_impl::<Reference>(lab)
 }
+pub(crate) fn srgb_to_linear(c: f32) -> f32 {
+    Reference::srgb_to_linear(c)
+}
+pub(crate) fn linear_to_srgb(c: f32) -> f32 {
+    Reference::linear_to_srgb(c)
+}
 pub(crate) fn fast_srgb_to_oklab(rgb: Vec3A) -> Vec3A {
     srgb_to_oklab_impl::<Fast>(rgb)
 }
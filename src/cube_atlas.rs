@@ -0,0 +1,280 @@
+use std::io::{Read, Seek};
+
+use crate::header::RawHeader;
+use crate::{decode_rect, ColorFormat, DecodeError, DecodeOptions, Format, Rect, Size};
+
+/// A side of a cube map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+impl CubeFace {
+    /// All faces in the order they are stored in cube map DDS files.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+}
+
+/// The arrangement of cube faces inside a single 2D surface.
+///
+/// Some exporters store cube maps as a single 2D texture that contains all 6
+/// faces in a cross or grid arrangement instead of using DDS cube maps. See
+/// [`CubeAtlas`] for how to read the faces of such textures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CubeAtlasLayout {
+    /// A horizontal cross of 4x3 faces:
+    ///
+    /// ```text
+    ///     +Y
+    /// -X  +Z  +X  -Z
+    ///     -Y
+    /// ```
+    HorizontalCross,
+    /// A vertical cross of 3x4 faces:
+    ///
+    /// ```text
+    ///     +Y
+    /// -X  +Z  +X
+    ///     -Y
+    ///     -Z
+    /// ```
+    ///
+    /// The -Z face is stored rotated by 180°.
+    VerticalCross,
+    /// A grid of 3x2 faces:
+    ///
+    /// ```text
+    /// +X  -X  +Y
+    /// -Y  +Z  -Z
+    /// ```
+    Grid3x2,
+}
+impl CubeAtlasLayout {
+    /// The index of the first of the two `reserved1` values holding the
+    /// signature.
+    const SIGNATURE_INDEX: usize = 5;
+    /// The first `reserved1` value of the signature. The second value
+    /// identifies the layout.
+    const SIGNATURE_MAGIC: u32 = u32::from_le_bytes(*b"DDSD");
+
+    /// The signature that marks files storing a cube atlas with this layout.
+    ///
+    /// It is stored in `reserved1[5..7]` of the header.
+    pub const fn signature(self) -> [u32; 2] {
+        let id = match self {
+            Self::HorizontalCross => *b"CUBH",
+            Self::VerticalCross => *b"CUBV",
+            Self::Grid3x2 => *b"CUBG",
+        };
+        [Self::SIGNATURE_MAGIC, u32::from_le_bytes(id)]
+    }
+
+    /// Detects the cube atlas hint from the `reserved1` field of a header.
+    pub fn from_raw_header(raw: &RawHeader) -> Option<Self> {
+        let i = Self::SIGNATURE_INDEX;
+        [Self::HorizontalCross, Self::VerticalCross, Self::Grid3x2]
+            .into_iter()
+            .find(|layout| raw.reserved1[i..i + 2] == layout.signature())
+    }
+    /// Writes the signature of this layout into the `reserved1` field of the
+    /// given header.
+    pub fn apply_to_raw_header(self, raw: &mut RawHeader) {
+        let i = Self::SIGNATURE_INDEX;
+        raw.reserved1[i..i + 2].copy_from_slice(&self.signature());
+    }
+
+    /// Guesses the layout from the size of a surface.
+    ///
+    /// Surfaces with an aspect ratio of 4:3, 3:4, or 3:2 are assumed to be
+    /// horizontal crosses, vertical crosses, and 3x2 grids respectively. This
+    /// is a heuristic, since regular textures may have these aspect ratios
+    /// too.
+    pub fn guess_from_size(size: Size) -> Option<Self> {
+        [Self::HorizontalCross, Self::VerticalCross, Self::Grid3x2]
+            .into_iter()
+            .find(|layout| CubeAtlas::new(*layout, size).is_some())
+    }
+
+    /// The number of faces horizontally and vertically.
+    pub const fn grid_size(self) -> (u32, u32) {
+        match self {
+            Self::HorizontalCross => (4, 3),
+            Self::VerticalCross => (3, 4),
+            Self::Grid3x2 => (3, 2),
+        }
+    }
+
+    /// The position of the given face in units of faces.
+    const fn face_position(self, face: CubeFace) -> (u32, u32) {
+        match self {
+            Self::HorizontalCross => match face {
+                CubeFace::PositiveX => (2, 1),
+                CubeFace::NegativeX => (0, 1),
+                CubeFace::PositiveY => (1, 0),
+                CubeFace::NegativeY => (1, 2),
+                CubeFace::PositiveZ => (1, 1),
+                CubeFace::NegativeZ => (3, 1),
+            },
+            Self::VerticalCross => match face {
+                CubeFace::PositiveX => (2, 1),
+                CubeFace::NegativeX => (0, 1),
+                CubeFace::PositiveY => (1, 0),
+                CubeFace::NegativeY => (1, 2),
+                CubeFace::PositiveZ => (1, 1),
+                CubeFace::NegativeZ => (1, 3),
+            },
+            Self::Grid3x2 => match face {
+                CubeFace::PositiveX => (0, 0),
+                CubeFace::NegativeX => (1, 0),
+                CubeFace::PositiveY => (2, 0),
+                CubeFace::NegativeY => (0, 1),
+                CubeFace::PositiveZ => (1, 1),
+                CubeFace::NegativeZ => (2, 1),
+            },
+        }
+    }
+    /// Whether the given face is stored rotated by 180°.
+    pub const fn is_rotated(self, face: CubeFace) -> bool {
+        matches!((self, face), (Self::VerticalCross, CubeFace::NegativeZ))
+    }
+}
+
+/// A cube map stored as an atlas of faces inside a single 2D surface.
+///
+/// This maps each [`CubeFace`] to its rectangle in the surface, so the faces
+/// can be read individually with [`decode_rect`] (see [`Self::decode_face`]).
+///
+/// ```no_run
+/// # use dds::*;
+/// # fn f(file: &mut std::fs::File) -> Result<(), DecodeError> {
+/// let info = DdsInfo::read(file)?;
+/// if let Some(atlas) = info.cube_atlas(true) {
+///     let face_size = atlas.face_size();
+///     let row_pitch = face_size as usize * 4;
+///     let mut face = vec![0_u8; row_pitch * face_size as usize];
+///     atlas.decode_face(
+///         file,
+///         &mut face,
+///         row_pitch,
+///         ColorFormat::RGBA_U8,
+///         CubeFace::PositiveX,
+///         info.format(),
+///         &DecodeOptions::default(),
+///     )?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CubeAtlas {
+    layout: CubeAtlasLayout,
+    face_size: u32,
+}
+impl CubeAtlas {
+    /// Creates a new cube atlas for a surface of the given size.
+    ///
+    /// Returns `None` if the surface cannot be divided into square faces
+    /// according to the given layout.
+    pub fn new(layout: CubeAtlasLayout, size: Size) -> Option<Self> {
+        let (columns, rows) = layout.grid_size();
+        let face_size = size.width / columns;
+        if face_size == 0 || size.width != face_size * columns || size.height != face_size * rows {
+            return None;
+        }
+        Some(Self { layout, face_size })
+    }
+
+    pub fn layout(&self) -> CubeAtlasLayout {
+        self.layout
+    }
+    /// The width and height of each face.
+    pub fn face_size(&self) -> u32 {
+        self.face_size
+    }
+    /// The size of the entire atlas surface.
+    pub fn size(&self) -> Size {
+        let (columns, rows) = self.layout.grid_size();
+        Size::new(self.face_size * columns, self.face_size * rows)
+    }
+
+    /// The rectangle of the given face within the atlas surface.
+    pub fn face_rect(&self, face: CubeFace) -> Rect {
+        let (x, y) = self.layout.face_position(face);
+        Rect::new(
+            x * self.face_size,
+            y * self.face_size,
+            self.face_size,
+            self.face_size,
+        )
+    }
+
+    /// Decodes a single face of the atlas.
+    ///
+    /// This is [`decode_rect`] with the rectangle of the face. Faces that are
+    /// stored rotated (see [`CubeAtlasLayout::is_rotated`]) are rotated back,
+    /// so the output is always in the standard orientation of cube map faces.
+    ///
+    /// The reader is expected to be positioned at the start of the atlas
+    /// surface. The state of the reader afterwards is the same as for
+    /// [`decode_rect`], so multiple faces can be decoded by seeking back to
+    /// the start of the surface.
+    #[allow(clippy::too_many_arguments)]
+    pub fn decode_face<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        output: &mut [u8],
+        row_pitch: usize,
+        color: ColorFormat,
+        face: CubeFace,
+        format: Format,
+        options: &DecodeOptions,
+    ) -> Result<(), DecodeError> {
+        let rect = self.face_rect(face);
+        decode_rect(
+            reader,
+            output,
+            row_pitch,
+            color,
+            self.size(),
+            rect,
+            format,
+            options,
+        )?;
+
+        if self.layout.is_rotated(face) {
+            rotate_180(output, row_pitch, color, rect.size());
+        }
+
+        Ok(())
+    }
+}
+
+fn rotate_180(output: &mut [u8], row_pitch: usize, color: ColorFormat, size: Size) {
+    let bytes_per_pixel = color.bytes_per_pixel() as usize;
+    let row_bytes = size.width as usize * bytes_per_pixel;
+    let height = size.height as usize;
+
+    // reverse the order of pixels in each row
+    for y in 0..height {
+        let row = &mut output[y * row_pitch..y * row_pitch + row_bytes];
+        row.reverse();
+        for pixel in row.chunks_exact_mut(bytes_per_pixel) {
+            pixel.reverse();
+        }
+    }
+    // reverse the order of rows
+    for y in 0..height / 2 {
+        let (top, bottom) = output.split_at_mut((height - 1 - y) * row_pitch);
+        top[y * row_pitch..y * row_pitch + row_bytes].swap_with_slice(&mut bottom[..row_bytes]);
+    }
+}
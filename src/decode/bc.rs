@@ -180,8 +180,14 @@ pub(crate) const BC7_UNORM: DecoderSet = DecoderSet::new(&[
     rgba!(f32, 16, blocks::bc7_f32_rgba),
 ]);
 
+pub(crate) const BC7_UNORM_FAST: DecoderSet = DecoderSet::new(&[
+    rgba!(u8, 16, blocks::bc7_fast_u8_rgba),
+    rgba!(u16, 16, blocks::bc7_fast_u16_rgba),
+    rgba!(f32, 16, blocks::bc7_fast_f32_rgba),
+]);
+
 /// Internal module for the underlying logic of decoding BC1-7 blocks.
-mod blocks {
+pub(crate) mod blocks {
     // use crate::decode::convert::{bc6h_uf16, fp16, n4, n8, s8, Norm, ToRgba, B5G6R5};
     use crate::{bc6h_uf16, fp16, n4, n8, s8, Norm, ToRgba, B5G6R5};
 
@@ -599,4 +605,16 @@ mod blocks {
     pub(crate) fn bc7_f32_rgba(block_bytes: [u8; 16]) -> [[f32; 4]; 16] {
         super::super::bc7::decode_bc7_block(block_bytes).map(|p| p.map(n8::f32))
     }
+
+    /// Decodes a BC7 UNORM block into 16 RGBA pixels using the table-driven
+    /// decoder.
+    pub(crate) fn bc7_fast_u8_rgba(block_bytes: [u8; 16]) -> [[u8; 4]; 16] {
+        super::super::bc7_fast::decode_bc7_block(block_bytes)
+    }
+    pub(crate) fn bc7_fast_u16_rgba(block_bytes: [u8; 16]) -> [[u16; 4]; 16] {
+        super::super::bc7_fast::decode_bc7_block(block_bytes).map(|p| p.map(n8::n16))
+    }
+    pub(crate) fn bc7_fast_f32_rgba(block_bytes: [u8; 16]) -> [[f32; 4]; 16] {
+        super::super::bc7_fast::decode_bc7_block(block_bytes).map(|p| p.map(n8::f32))
+    }
 }
@@ -616,7 +616,7 @@ fn decompress_endpoints_two(
     endpoints
 }
 
-fn unquantize(mut component: i32, u_bits_per_comp: u8, format: BC6HFormat) -> i32 {
+pub(crate) fn unquantize(mut component: i32, u_bits_per_comp: u8, format: BC6HFormat) -> i32 {
     let mut unq: i32;
     match format {
         BC6HFormat::UnsignedF16 => {
@@ -658,7 +658,7 @@ fn unquantize(mut component: i32, u_bits_per_comp: u8, format: BC6HFormat) -> i3
     unq
 }
 
-fn finish_unquantize(mut component: i32, format: BC6HFormat) -> u16 {
+pub(crate) fn finish_unquantize(mut component: i32, format: BC6HFormat) -> u16 {
     match format {
         BC6HFormat::UnsignedF16 => {
             component = (component * 31) >> 6; // scale the magnitude by 31/64
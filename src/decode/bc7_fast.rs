@@ -0,0 +1,297 @@
+//! A table-driven BC7 decoder.
+//!
+//! Instead of hand-written code for each mode (see `bc7.rs`), the layout of
+//! all modes is described by [`MODES`]. The decoder is instantiated once per
+//! mode with the mode as a constant, so the compiler can fold the table into
+//! straight-line code. Blocks are decoded in two steps:
+//!
+//! 1. The endpoints of all subsets are expanded into palettes of up to 16
+//!    colors. The interpolation works on all 4 channels at once, which the
+//!    compiler turns into SIMD instructions.
+//! 2. Each pixel looks up its color in the palette of its subset.
+//!
+//! This has to produce exactly the same results as the reference decoder.
+
+use crate::util::unlikely_branch;
+
+use super::bcn_util::{Indexes, PARTITION_SET_2, PARTITION_SET_3};
+
+/// The layout of a BC7 mode.
+///
+/// See <https://learn.microsoft.com/en-us/windows/win32/direct3d11/bc7-format-mode-reference>
+#[derive(Clone, Copy)]
+struct ModeInfo {
+    subsets: u8,
+    partition_bits: u8,
+    rotation_bits: u8,
+    index_selection_bits: u8,
+    color_bits: u8,
+    alpha_bits: u8,
+    /// Whether each endpoint has its own P-bit.
+    endpoint_p_bits: bool,
+    /// Whether the endpoints of each subset share a P-bit.
+    shared_p_bits: bool,
+    index_bits: u8,
+    /// The number of bits of the secondary index set. 0 if the mode doesn't
+    /// have a secondary index set.
+    index_bits_2: u8,
+}
+#[allow(clippy::too_many_arguments)]
+const fn mode(
+    subsets: u8,
+    partition_bits: u8,
+    rotation_bits: u8,
+    index_selection_bits: u8,
+    color_bits: u8,
+    alpha_bits: u8,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u8,
+    index_bits_2: u8,
+) -> ModeInfo {
+    ModeInfo {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_p_bits,
+        shared_p_bits,
+        index_bits,
+        index_bits_2,
+    }
+}
+const MODES: [ModeInfo; 8] = [
+    mode(3, 4, 0, 0, 4, 0, true, false, 3, 0),
+    mode(2, 6, 0, 0, 6, 0, false, true, 3, 0),
+    mode(3, 6, 0, 0, 5, 0, false, false, 2, 0),
+    mode(2, 6, 0, 0, 7, 0, true, false, 2, 0),
+    mode(1, 0, 2, 1, 5, 6, false, false, 2, 3),
+    mode(1, 0, 2, 0, 7, 8, false, false, 2, 2),
+    mode(1, 0, 0, 0, 7, 7, true, false, 4, 0),
+    mode(2, 6, 0, 0, 5, 5, true, false, 2, 0),
+];
+
+/// The interpolation weights for 2, 3, and 4 index bits. The weights are
+/// multiplied by 4 compared to the spec, see `bc7.rs`.
+const WEIGHTS: [[u16; 16]; 3] = [
+    [0, 84, 172, 256, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 36, 72, 108, 148, 184, 220, 256, 0, 0, 0, 0, 0, 0, 0, 0],
+    [
+        0, 16, 36, 52, 68, 84, 104, 120, 136, 152, 172, 188, 204, 220, 240, 256,
+    ],
+];
+
+struct Bits(u128);
+impl Bits {
+    #[inline(always)]
+    fn take(&mut self, count: u8) -> u8 {
+        debug_assert!(count <= 8);
+        let value = (self.0 & ((1 << count) - 1)) as u8;
+        self.0 >>= count;
+        value
+    }
+    #[inline(always)]
+    fn take_64(&mut self, count: u8) -> u64 {
+        debug_assert!(count <= 64);
+        let value = (self.0 & ((1 << count) - 1)) as u64;
+        self.0 >>= count;
+        value
+    }
+}
+
+pub(crate) fn decode_bc7_block(block: [u8; 16]) -> [[u8; 4]; 16] {
+    // The table is indexed with constants, so each mode gets its own
+    // specialized copy of the decoder.
+    match block[0].trailing_zeros() {
+        0 => decode_mode::<0>(block),
+        1 => decode_mode::<1>(block),
+        2 => decode_mode::<2>(block),
+        3 => decode_mode::<3>(block),
+        4 => decode_mode::<4>(block),
+        5 => decode_mode::<5>(block),
+        6 => decode_mode::<6>(block),
+        7 => decode_mode::<7>(block),
+        _ => {
+            unlikely_branch();
+            // mode 8 is reserved and decodes to transparent black
+            [[0; 4]; 16]
+        }
+    }
+}
+
+#[inline(always)]
+fn decode_mode<const MODE: usize>(block: [u8; 16]) -> [[u8; 4]; 16] {
+    let mode = MODES[MODE];
+    let mut bits = Bits(u128::from_le_bytes(block) >> (MODE + 1));
+
+    // the subset of each pixel and the anchor pixels of all subsets (in
+    // pixel order)
+    let partition = bits.take(mode.partition_bits) as usize;
+    let (subset_of, fixup_2, fixup_3) = match mode.subsets {
+        2 => {
+            let map = PARTITION_SET_2[partition];
+            (map.subset_indexes as u32, map.fixup_index_2, 0)
+        }
+        3 => {
+            let map = PARTITION_SET_3[partition];
+            (map.subset_indexes(), map.fixup_index_2, map.fixup_index_3)
+        }
+        _ => (0, 0, 0),
+    };
+    let subset_bits = if mode.subsets == 3 { 2 } else { 1 };
+
+    let rotation = bits.take(mode.rotation_bits);
+    let index_selection = bits.take(mode.index_selection_bits) != 0;
+
+    let endpoints = read_endpoints(&mut bits, mode);
+
+    let index_len = 16 * mode.index_bits - mode.subsets;
+    let indexes = match mode.subsets {
+        1 => Indexes::from_compressed_p1(mode.index_bits, bits.take_64(index_len)),
+        2 => Indexes::from_compressed_p2(mode.index_bits, bits.take_64(index_len), fixup_2),
+        _ => {
+            Indexes::from_compressed_p3(mode.index_bits, bits.take_64(index_len), fixup_2, fixup_3)
+        }
+    };
+
+    let mut output = [[0_u8; 4]; 16];
+    if mode.index_bits_2 == 0 {
+        let weights = &WEIGHTS[mode.index_bits as usize - 2];
+        let mut palettes = [[[0_u8; 4]; 16]; 3];
+        for (subset, palette_out) in palettes.iter_mut().enumerate().take(mode.subsets as usize) {
+            *palette_out = palette(
+                endpoints[subset * 2],
+                endpoints[subset * 2 + 1],
+                weights,
+                1 << mode.index_bits,
+            );
+        }
+
+        let subset_mask = (1 << subset_bits) - 1;
+        for (pixel, out) in output.iter_mut().enumerate() {
+            let subset = (subset_of >> (pixel * subset_bits) & subset_mask).min(2) as usize;
+            *out = palettes[subset][indexes.get_index(pixel as u8) as usize & 0xF];
+        }
+    } else {
+        let index_len_2 = 16 * mode.index_bits_2 - 1;
+        let indexes_2 = Indexes::from_compressed_p1(mode.index_bits_2, bits.take_64(index_len_2));
+
+        let (color_indexes, color_bits, alpha_indexes, alpha_bits) = if index_selection {
+            (&indexes_2, mode.index_bits_2, &indexes, mode.index_bits)
+        } else {
+            (&indexes, mode.index_bits, &indexes_2, mode.index_bits_2)
+        };
+        let color_palette = palette(
+            endpoints[0],
+            endpoints[1],
+            &WEIGHTS[color_bits as usize - 2],
+            1 << color_bits,
+        );
+        let alpha_palette = palette(
+            endpoints[0],
+            endpoints[1],
+            &WEIGHTS[alpha_bits as usize - 2],
+            1 << alpha_bits,
+        );
+
+        for (pixel, out) in output.iter_mut().enumerate() {
+            let [r, g, b, _] = color_palette[color_indexes.get_index(pixel as u8) as usize & 0xF];
+            let a = alpha_palette[alpha_indexes.get_index(pixel as u8) as usize & 0xF][3];
+            *out = [r, g, b, a];
+        }
+
+        if rotation != 0 {
+            let channel = rotation as usize - 1;
+            for pixel in output.iter_mut() {
+                pixel.swap(channel, 3);
+            }
+        }
+    }
+
+    output
+}
+
+/// Reads the endpoints of all subsets and expands them to 8 bits.
+#[inline(always)]
+fn read_endpoints(bits: &mut Bits, mode: ModeInfo) -> [[u8; 4]; 6] {
+    let count = mode.subsets as usize * 2;
+    let mut endpoints = [[0_u8; 4]; 6];
+
+    for channel in 0..3 {
+        for endpoint in endpoints.iter_mut().take(count) {
+            endpoint[channel] = bits.take(mode.color_bits);
+        }
+    }
+    if mode.alpha_bits > 0 {
+        for endpoint in endpoints.iter_mut().take(count) {
+            endpoint[3] = bits.take(mode.alpha_bits);
+        }
+    }
+
+    let mut color_bits = mode.color_bits;
+    let mut alpha_bits = mode.alpha_bits;
+    if mode.endpoint_p_bits || mode.shared_p_bits {
+        let mut p_bits = [0_u8; 6];
+        if mode.endpoint_p_bits {
+            for p in p_bits.iter_mut().take(count) {
+                *p = bits.take(1);
+            }
+        } else {
+            for subset in 0..mode.subsets as usize {
+                let p = bits.take(1);
+                p_bits[subset * 2] = p;
+                p_bits[subset * 2 + 1] = p;
+            }
+        }
+        for (endpoint, p) in endpoints.iter_mut().zip(p_bits).take(count) {
+            for value in endpoint.iter_mut() {
+                *value = *value << 1 | p;
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    for endpoint in endpoints.iter_mut().take(count) {
+        for value in &mut endpoint[..3] {
+            *value = promote(*value, color_bits);
+        }
+        endpoint[3] = if alpha_bits > 0 {
+            promote(endpoint[3], alpha_bits)
+        } else {
+            255
+        };
+    }
+
+    endpoints
+}
+
+#[inline(always)]
+fn promote(value: u8, bits: u8) -> u8 {
+    debug_assert!((4..=8).contains(&bits));
+    if bits == 8 {
+        return value;
+    }
+    let value = value << (8 - bits);
+    value | value >> bits
+}
+
+/// Interpolates the first `len` palette entries between the two endpoints.
+#[inline(always)]
+fn palette(e0: [u8; 4], e1: [u8; 4], weights: &[u16; 16], len: usize) -> [[u8; 4]; 16] {
+    let e0 = e0.map(u16::from);
+    let e1 = e1.map(u16::from);
+
+    let mut palette = [[0_u8; 4]; 16];
+    for (entry, &w1) in palette.iter_mut().zip(weights).take(len) {
+        let w0 = 256 - w1;
+        for channel in 0..4 {
+            entry[channel] = ((w0 * e0[channel] + w1 * e1[channel] + 128) >> 8) as u8;
+        }
+    }
+    palette
+}
@@ -30,6 +30,9 @@ pub(crate) struct Subset3Map {
     pub fixup_index_3: u8,
 }
 impl Subset3Map {
+    pub const fn subset_indexes(self) -> u32 {
+        self.subset_indexes
+    }
     pub const fn get_subset_index(self, pixel_index: u8) -> u8 {
         (self.subset_indexes.wrapping_shr(pixel_index as u32 * 2) & 0b11) as u8
     }
@@ -74,6 +74,14 @@ pub(crate) const NV12: DecoderSet = DecoderSet::new(&[
     rgb!(f32, p1 = u8, p2 = [u8; 2], |y, [u, v], _| y
         .map(|y| yuv8::f32([y, u, v]))),
 ]);
+pub(crate) const NV12_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u8, p1 = u8, p2 = [u8; 2], |y, [u, v], _| y
+        .map(|y| yuv8::raw_n8([y, u, v]))),
+    rgb!(u16, p1 = u8, p2 = [u8; 2], |y, [u, v], _| y
+        .map(|y| yuv8::raw_n16([y, u, v]))),
+    rgb!(f32, p1 = u8, p2 = [u8; 2], |y, [u, v], _| y
+        .map(|y| yuv8::raw_f32([y, u, v]))),
+]);
 
 fn to10(yuv: [u16; 3]) -> [u16; 3] {
     yuv.map(|v| v >> 6)
@@ -86,6 +94,14 @@ pub(crate) const P010: DecoderSet = DecoderSet::new(&[
     rgb!(f32, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
         .map(|y| yuv10::f32(to10([y, u, v])))),
 ]);
+pub(crate) const P010_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u16, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
+        .map(|y| yuv10::raw_n16(to10([y, u, v])))),
+    rgb!(u8, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
+        .map(|y| yuv10::raw_n8(to10([y, u, v])))),
+    rgb!(f32, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
+        .map(|y| yuv10::raw_f32(to10([y, u, v])))),
+]);
 
 pub(crate) const P016: DecoderSet = DecoderSet::new(&[
     rgb!(u16, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
@@ -95,3 +111,11 @@ pub(crate) const P016: DecoderSet = DecoderSet::new(&[
     rgb!(f32, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
         .map(|y| yuv16::f32([y, u, v]))),
 ]);
+pub(crate) const P016_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u16, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
+        .map(|y| yuv16::raw_n16([y, u, v]))),
+    rgb!(u8, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
+        .map(|y| yuv16::raw_n8([y, u, v]))),
+    rgb!(f32, p1 = u16, p2 = [u16; 2], |y, [u, v], _| y
+        .map(|y| yuv16::raw_f32([y, u, v]))),
+]);
@@ -0,0 +1,126 @@
+use std::io::Read;
+
+use crate::{Channels, ColorFormat, DecodeError, Format, ImageViewMut, PixelInfo, Size};
+
+use super::strip::{block_height, strip_height};
+use super::{decode, get_decoders_with, DecodeOptions};
+
+/// A single color or alpha channel.
+///
+/// See [`decode_channel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    R,
+    G,
+    B,
+    A,
+}
+impl Channel {
+    const fn index(self) -> usize {
+        match self {
+            Self::R => 0,
+            Self::G => 1,
+            Self::B => 2,
+            Self::A => 3,
+        }
+    }
+}
+
+/// Decodes a single channel of a surface from the given reader into the given
+/// single-channel image.
+///
+/// The values of the selected channel are the same values that [`decode`]
+/// would produce for this channel when decoding into an RGBA image. E.g.
+/// extracting [`Channel::A`] of a format without alpha yields fully opaque
+/// values, and extracting any color channel of a grayscale format yields the
+/// grayscale values.
+///
+/// The image must have either [`Channels::Grayscale`] or [`Channels::Alpha`],
+/// otherwise [`DecodeError::ExpectedSingleChannel`] is returned. Which one
+/// doesn't matter.
+///
+/// Formats that natively store the selected channel alone (e.g. the gray
+/// values of `BC4_UNORM` or the alpha of `A8_UNORM`) are decoded directly into
+/// the image. All other formats are decoded in strips through a small
+/// intermediate buffer, so no full-size RGBA copy of the surface is ever
+/// allocated.
+///
+/// The state of the reader is the same as for [`decode`].
+pub fn decode_channel(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    channel: Channel,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    let size = image.size();
+    let precision = image.color().precision;
+    if image.color().channels.count() != 1 {
+        return Err(DecodeError::ExpectedSingleChannel);
+    }
+
+    // fast path for formats that store the channel alone
+    let native = get_decoders_with(format, options).native_color().channels;
+    let direct = match (native, channel) {
+        (Channels::Grayscale, Channel::R | Channel::G | Channel::B) => Some(Channels::Grayscale),
+        (Channels::Alpha, Channel::A) => Some(Channels::Alpha),
+        _ => None,
+    };
+    if let Some(channels) = direct {
+        let view = ImageViewMut::new(image.data, size, ColorFormat::new(channels, precision))
+            .expect("invalid image view");
+        return decode(reader, view, format, options);
+    }
+
+    if size.is_empty() {
+        return decode(reader, image, format, options);
+    }
+
+    let rgba = ColorFormat::new(Channels::Rgba, precision);
+    let rgba_bytes_per_pixel = rgba.bytes_per_pixel() as usize;
+
+    // Bi-planar formats cannot be decoded in strips, so they are decoded in
+    // one go.
+    let rows_per_strip = match PixelInfo::from(format) {
+        PixelInfo::BiPlanar(_) => size.height,
+        pixel_info => strip_height(size.width, rgba_bytes_per_pixel, block_height(pixel_info)),
+    };
+
+    let buffer_len = size.width as usize * rows_per_strip as usize * rgba_bytes_per_pixel;
+    if buffer_len > options.memory_limit {
+        return Err(DecodeError::MemoryLimitExceeded);
+    }
+    let mut strip_options = options.without_progress();
+    strip_options.memory_limit -= buffer_len;
+    let mut buffer = vec![0_u8; buffer_len];
+
+    let value_size = precision.size() as usize;
+    let value_offset = channel.index() * value_size;
+    let row_pitch = image.row_pitch();
+
+    let mut y = 0;
+    while y < size.height {
+        options.check_cancelled()?;
+
+        let rows = rows_per_strip.min(size.height - y);
+        let strip_size = Size::new(size.width, rows);
+        let strip = &mut buffer[..strip_size.pixels() as usize * rgba_bytes_per_pixel];
+
+        let strip_view = ImageViewMut::new(strip, strip_size, rgba).expect("invalid strip");
+        decode(reader, strip_view, format, &strip_options)?;
+
+        let out_start = y as usize * row_pitch;
+        let out = &mut image.data[out_start..out_start + rows as usize * row_pitch];
+        for (pixel, value) in strip
+            .chunks_exact(rgba_bytes_per_pixel)
+            .zip(out.chunks_exact_mut(value_size))
+        {
+            value.copy_from_slice(&pixel[value_offset..value_offset + value_size]);
+        }
+
+        y += rows;
+        options.report_progress(y, size.height);
+    }
+
+    Ok(())
+}
@@ -2,7 +2,8 @@ use std::io::{Read, Seek};
 use std::mem::size_of;
 
 use crate::{
-    Channels, ColorFormat, ColorFormatSet, DecodeError, ImageViewMut, Precision, Rect, Size,
+    cast, u32_source_precision, util, widen_to_u32, Channels, ColorFormat, ColorFormatSet,
+    DecodeError, ImageViewMut, Precision, Rect, Size,
 };
 
 use super::DecodeOptions;
@@ -10,12 +11,15 @@ use super::DecodeOptions;
 pub(crate) type DecodeFn = fn(args: Args) -> Result<(), DecodeError>;
 pub(crate) type DecodeRectFn = fn(args: RArgs) -> Result<(), DecodeError>;
 
-pub(crate) struct DecodeContext {
+pub(crate) struct DecodeContext<'s> {
     pub color: ColorFormat,
     pub size: Size,
     pub memory_limit: usize,
+    /// Scratch memory provided by the caller. Buffers are taken from the
+    /// front of this slice before falling back to allocation.
+    pub scratch: &'s mut [u8],
 }
-impl DecodeContext {
+impl<'s> DecodeContext<'s> {
     pub fn reserve_bytes(&mut self, bytes: usize) -> Result<(), DecodeError> {
         if self.memory_limit < bytes {
             return Err(DecodeError::MemoryLimitExceeded);
@@ -32,6 +36,50 @@ impl DecodeContext {
         self.reserve_bytes(len * size_of::<T>())?;
         Ok(Vec::with_capacity(len))
     }
+    /// Returns the next `len` bytes of scratch memory, if there are enough
+    /// left. Scratch memory does not count towards the memory limit, since
+    /// it has already been allocated.
+    pub fn take_scratch(&mut self, len: usize) -> Option<&'s mut [u8]> {
+        if self.scratch.len() < len {
+            return None;
+        }
+        let scratch = std::mem::take(&mut self.scratch);
+        let (taken, rest) = scratch.split_at_mut(len);
+        self.scratch = rest;
+        Some(taken)
+    }
+    /// Returns a zero-initialized buffer of the given length. The buffer is
+    /// taken from scratch memory if possible.
+    pub fn alloc_bytes(&mut self, len: usize) -> Result<Buffer<'s>, DecodeError> {
+        if let Some(scratch) = self.take_scratch(len) {
+            scratch.fill(0);
+            return Ok(Buffer::Scratch(scratch));
+        }
+        Ok(Buffer::Owned(self.alloc(len)?))
+    }
+}
+
+/// A byte buffer that is either borrowed from scratch memory or allocated.
+pub(crate) enum Buffer<'s> {
+    Scratch(&'s mut [u8]),
+    Owned(Box<[u8]>),
+}
+impl std::ops::Deref for Buffer<'_> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            Buffer::Scratch(buf) => buf,
+            Buffer::Owned(buf) => buf,
+        }
+    }
+}
+impl std::ops::DerefMut for Buffer<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Buffer::Scratch(buf) => buf,
+            Buffer::Owned(buf) => buf,
+        }
+    }
 }
 
 pub(crate) trait ReadSeek: Read + Seek {}
@@ -48,12 +96,16 @@ impl<T: Read + Seek> ReadSeek for T {}
 /// The "fix" is to wrap all mutable references in a struct so that compiler
 /// can't see them in the type signature of the function pointer anymore. Truly
 /// silly, and thankfully not necessary on never compiler versions.
-pub(crate) struct Args<'a, 'b>(pub &'a mut dyn Read, pub &'b mut [u8], pub DecodeContext);
+pub(crate) struct Args<'a, 'b>(
+    pub &'a mut dyn Read,
+    pub &'b mut [u8],
+    pub DecodeContext<'b>,
+);
 impl<'a, 'b> Args<'a, 'b> {
     pub fn new(
         reader: &'a mut dyn Read,
         output: &'b mut [u8],
-        context: DecodeContext,
+        context: DecodeContext<'b>,
     ) -> Result<Self, DecodeError> {
         let bytes_per_pixel = context.color.bytes_per_pixel() as u64;
         assert_eq!(
@@ -70,7 +122,7 @@ pub(crate) struct RArgs<'a, 'b>(
     pub &'b mut [u8],
     pub usize,
     pub Rect,
-    pub DecodeContext,
+    pub DecodeContext<'b>,
 );
 impl<'a, 'b> RArgs<'a, 'b> {
     pub fn new(
@@ -78,7 +130,7 @@ impl<'a, 'b> RArgs<'a, 'b> {
         output: &'b mut [u8],
         row_pitch: usize,
         rect: Rect,
-        context: DecodeContext,
+        context: DecodeContext<'b>,
     ) -> Result<Self, DecodeError> {
         // Check that the rect is within the bounds of the image.
         if !rect.is_within_bounds(context.size) {
@@ -143,8 +195,26 @@ impl Decoder {
 struct SpecializedDecodeFn {
     decode_fn: DecodeFn,
     color: ColorFormat,
+    /// Whether the encoded pixels are identical to the decoded pixels in
+    /// `color` (in little endian).
+    zero_copy: bool,
 }
 
+const COPY_U8: DecodeFn = |Args(r, out, _)| {
+    r.read_exact(out)?;
+    Ok(())
+};
+const COPY_U16: DecodeFn = |Args(r, out, _)| {
+    r.read_exact(out)?;
+    cast::slice_le_to_ne_16(out);
+    Ok(())
+};
+const COPY_U32: DecodeFn = |Args(r, out, _)| {
+    r.read_exact(out)?;
+    cast::slice_le_to_ne_32(out);
+    Ok(())
+};
+
 pub(crate) struct DecoderSet {
     decoders: &'static [Decoder],
     optimized: Option<SpecializedDecodeFn>,
@@ -174,7 +244,10 @@ impl DecoderSet {
             i += 1;
         }
 
-        debug_assert!(supported_colors.is_all(), "All colors must be supported");
+        debug_assert!(
+            supported_colors.contains_all(ColorFormatSet::REQUIRED),
+            "All colors must be supported"
+        );
         debug_assert!(
             native_colors.len() as usize == decoders.len(),
             "There should only be one decoder per native color."
@@ -192,13 +265,61 @@ impl DecoderSet {
             optimized: Some(SpecializedDecodeFn {
                 decode_fn,
                 color: ColorFormat::new(channels, precision),
+                zero_copy: false,
             }),
         }
     }
+    /// Marks the given color format as zero-copy. This means that the encoded
+    /// pixels are byte-for-byte identical to the decoded pixels (in little
+    /// endian), so decoding is a simple copy.
+    pub const fn add_zero_copy(self, channels: Channels, precision: Precision) -> Self {
+        let decode_fn = match precision {
+            Precision::U8 => COPY_U8,
+            Precision::U16 => COPY_U16,
+            Precision::F32 | Precision::U32 => COPY_U32,
+        };
+        let mut set = self.add_specialized(channels, precision, decode_fn);
+        if let Some(optimized) = &mut set.optimized {
+            optimized.zero_copy = true;
+        }
+        set
+    }
 
     pub const fn native_color(&self) -> ColorFormat {
         self.decoders[0].native_color
     }
+    /// Returns the color format that can be decoded by simply copying the
+    /// encoded data on the current platform, if any.
+    pub const fn zero_copy_color(&self) -> Option<ColorFormat> {
+        match &self.optimized {
+            Some(optimized) if optimized.zero_copy => {
+                // multi-byte values are stored in little endian
+                if cfg!(target_endian = "little")
+                    || matches!(optimized.color.precision, Precision::U8)
+                {
+                    Some(optimized.color)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the color format to decode into before widening to U32, if
+    /// the given color cannot be decoded directly.
+    fn u32_source_color(&self, color: ColorFormat) -> Option<ColorFormat> {
+        if color.precision != Precision::U32
+            || self
+                .decoders
+                .iter()
+                .any(|d| d.supported_colors.contains(color))
+        {
+            return None;
+        }
+        let precision = u32_source_precision(self.native_color().precision);
+        Some(ColorFormat::new(color.channels, precision))
+    }
 
     fn get_decoder(&self, color: ColorFormat) -> &Decoder {
         // try to find an exact match
@@ -218,10 +339,29 @@ impl DecoderSet {
         reader: &mut dyn Read,
         image: ImageViewMut,
         options: &DecodeOptions,
+    ) -> Result<(), DecodeError> {
+        self.decode_with_scratch(reader, image, options, &mut [])
+    }
+    pub fn decode_with_scratch(
+        &self,
+        reader: &mut dyn Read,
+        image: ImageViewMut,
+        options: &DecodeOptions,
+        scratch: &mut [u8],
     ) -> Result<(), DecodeError> {
         let color = image.color();
         let size = image.size();
 
+        if let Some(source) = self.u32_source_color(color) {
+            // decode into the start of the output buffer and widen in place
+            let source_len = (size.pixels() * source.bytes_per_pixel() as u64) as usize;
+            let (data, _) = image.data.split_at_mut(source_len);
+            let view = ImageViewMut::new(data, size, source).expect("invalid source view");
+            self.decode_with_scratch(reader, view, options, scratch)?;
+            widen_to_u32(source.precision, image.data);
+            return Ok(());
+        }
+
         let args = Args::new(
             reader,
             image.data,
@@ -229,6 +369,7 @@ impl DecoderSet {
                 color,
                 size,
                 memory_limit: options.memory_limit,
+                scratch,
             },
         )?;
 
@@ -268,6 +409,7 @@ impl DecoderSet {
                 color,
                 size,
                 memory_limit: options.memory_limit,
+                scratch: &mut [],
             },
         )?;
 
@@ -276,7 +418,55 @@ impl DecoderSet {
             return Ok(());
         }
 
+        if let Some(source) = self.u32_source_color(color) {
+            // decode each row into the start of its output row and widen in
+            // place
+            let RArgs(reader, output, row_pitch, rect, _) = args;
+            self.decode_rect(source, reader, size, rect, output, row_pitch, options)?;
+            let row_bytes = rect.width as usize * color.bytes_per_pixel() as usize;
+            for y in 0..rect.height as usize {
+                let start = y * row_pitch;
+                widen_to_u32(source.precision, &mut output[start..start + row_bytes]);
+            }
+            return Ok(());
+        }
+
+        if self.zero_copy_color() == Some(color) {
+            return decode_rect_zero_copy(args);
+        }
+
         let decoder = self.get_decoder(color);
         (decoder.decode_rect_fn)(args)
     }
 }
+
+/// Reads the rows of the rectangle directly into the output buffer.
+fn decode_rect_zero_copy(args: RArgs) -> Result<(), DecodeError> {
+    let RArgs(r, output, row_pitch, rect, context) = args;
+    let size = context.size;
+    let bytes_per_pixel = context.color.bytes_per_pixel() as u64;
+
+    let bytes_per_row = size.width as u64 * bytes_per_pixel;
+    let bytes_before_rect = rect.x as u64 * bytes_per_pixel;
+    let bytes_after_rect = (size.width - rect.x - rect.width) as u64 * bytes_per_pixel;
+    let rect_row_bytes = rect.width as usize * bytes_per_pixel as usize;
+
+    // jump to the first pixel
+    util::io_skip_exact(r, bytes_per_row * rect.y as u64 + bytes_before_rect)?;
+
+    for y in 0..rect.height as usize {
+        if y > 0 {
+            util::io_skip_exact(r, bytes_before_rect + bytes_after_rect)?;
+        }
+        let row = &mut output[y * row_pitch..y * row_pitch + rect_row_bytes];
+        r.read_exact(row)?;
+    }
+
+    // jump to the end of the surface to put the reader into a known position
+    util::io_skip_exact(
+        r,
+        bytes_after_rect + (size.height - rect.y - rect.height) as u64 * bytes_per_row,
+    )?;
+
+    Ok(())
+}
@@ -2,23 +2,45 @@ mod astc;
 mod bc;
 mod bc6;
 mod bc7;
+mod bc7_fast;
 mod bcn_util;
 mod bi_planar;
+mod channel;
 mod decoder;
+mod padded;
+mod progress;
 mod read_write;
+mod scratch;
+mod skip;
+mod strip;
 mod sub_sampled;
+mod tone_mapping;
+mod truncated;
 mod uncompressed;
 
 use std::io::{Read, Seek};
 
 use astc::*;
+pub(crate) use bc::blocks as bc_blocks;
 use bc::*;
+pub(crate) use bc6::{finish_unquantize, unquantize, BC6HFormat};
+pub(crate) use bcn_util::{PARTITION_SET_2, PARTITION_SET_3};
 use bi_planar::*;
+pub use channel::*;
 pub(crate) use decoder::*;
+pub(crate) use padded::*;
+pub use progress::*;
+pub use scratch::*;
+use skip::*;
 use sub_sampled::*;
+use tone_mapping::*;
+pub(crate) use truncated::fill_rows;
+use truncated::*;
 use uncompressed::*;
 
-use crate::{ColorFormat, DecodeError, Format, ImageViewMut, Rect, Size};
+use crate::{
+    ColorFormat, DecodeError, Format, ImageView, ImageViewMut, PixelInfo, Rect, Size, SizeMultiple,
+};
 
 pub(crate) const fn get_decoders(format: Format) -> DecoderSet {
     match format {
@@ -108,6 +130,72 @@ pub(crate) const fn get_decoders(format: Format) -> DecoderSet {
     }
 }
 
+/// Returns the decoders that return YUV values as is, without converting them
+/// to RGB. Returns `None` for non-YUV formats.
+const fn get_raw_yuv_decoders(format: Format) -> Option<DecoderSet> {
+    Some(match format {
+        Format::AYUV => AYUV_RAW,
+        Format::Y410 => Y410_RAW,
+        Format::Y416 => Y416_RAW,
+        Format::UYVY => UYVY_RAW,
+        Format::YUY2 => YUY2_RAW,
+        Format::Y210 => Y210_RAW,
+        Format::Y216 => Y216_RAW,
+        Format::NV12 => NV12_RAW,
+        Format::P010 => P010_RAW,
+        Format::P016 => P016_RAW,
+        _ => return None,
+    })
+}
+fn get_decoders_with(format: Format, options: &DecodeOptions) -> DecoderSet {
+    if options.raw_yuv {
+        if let Some(decoders) = get_raw_yuv_decoders(format) {
+            return decoders;
+        }
+    }
+    if options.keep_premultiplied_alpha {
+        // the premultiplied formats only differ from their straight-alpha
+        // counterparts in that they divide out alpha
+        match format {
+            Format::BC2_UNORM_PREMULTIPLIED_ALPHA => return BC2_UNORM,
+            Format::BC3_UNORM_PREMULTIPLIED_ALPHA => return BC3_UNORM,
+            _ => {}
+        }
+    }
+    if format == Format::BC7_UNORM && options.bc7_decoder == Bc7Decoder::TableDriven {
+        return BC7_UNORM_FAST;
+    }
+    get_decoders(format)
+}
+
+/// Returns the chroma sub-sampling of the given format, if any.
+const fn get_chroma_sub_sampling(format: Format) -> Option<SizeMultiple> {
+    match format {
+        Format::R8G8_B8G8_UNORM
+        | Format::G8R8_G8B8_UNORM
+        | Format::UYVY
+        | Format::YUY2
+        | Format::Y210
+        | Format::Y216 => Some(SizeMultiple::new(2, 1)),
+        Format::NV12 | Format::P010 | Format::P016 => Some(SizeMultiple::M2_2),
+        _ => None,
+    }
+}
+fn check_sub_sampled_size(
+    format: Format,
+    size: Size,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    if options.strict_sub_sampling {
+        if let Some(multiple) = get_chroma_sub_sampling(format) {
+            if !size.is_multiple_of(multiple) {
+                return Err(DecodeError::InvalidSize(multiple));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Decodes the image data of a surface from the given reader and writes it
 /// to the given output buffer.
 ///
@@ -132,7 +220,108 @@ pub fn decode(
     format: Format,
     options: &DecodeOptions,
 ) -> Result<(), DecodeError> {
-    get_decoders(format).decode(reader, image, options)
+    if options.truncated_fill.is_some() {
+        return decode_partial(reader, image, format, options).map(|_| ());
+    }
+
+    check_sub_sampled_size(format, image.size(), options)?;
+
+    if options.reports_progress() {
+        return decode_with_progress(reader, image, format, options, |reader, image, options| {
+            decode_surface(reader, image, format, options, &mut [])
+        });
+    }
+
+    decode_surface(reader, image, format, options, &mut [])
+}
+
+/// Decodes the image data of a surface like [`decode`], but takes
+/// intermediate buffers from the given scratch memory instead of allocating
+/// them.
+///
+/// The scratch memory is grown as needed, as long as it stays within
+/// [`DecodeOptions::memory_limit`]. See [`DecodeScratch`] for more
+/// information.
+pub fn decode_with_scratch(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    options: &DecodeOptions,
+    scratch: &mut DecodeScratch,
+) -> Result<(), DecodeError> {
+    if options.truncated_fill.is_some() || options.reports_progress() {
+        return decode(reader, image, format, options);
+    }
+
+    check_sub_sampled_size(format, image.size(), options)?;
+
+    let scratch = scratch.prepare(format, image.size(), options.memory_limit);
+    decode_surface(reader, image, format, options, scratch)
+}
+
+/// Decodes the image data of a surface like [`decode`] and returns the
+/// number of rows that were decoded.
+///
+/// If [`DecodeOptions::truncated_fill`] is set and the reader runs out of
+/// data, all complete rows are decoded, the remaining rows are filled with
+/// the fill color, and the number of decoded rows is returned. Otherwise,
+/// this behaves exactly like [`decode`] and always returns the full height
+/// of the image on success.
+///
+/// ```
+/// # use dds::*;
+/// // a 4x8 BC1 image needs 2 rows of blocks, but the data only has 1
+/// let data = [0_u8; 8];
+/// let mut pixels = vec![0_u8; 4 * 8 * 4];
+/// let image = ImageViewMut::new(pixels.as_mut_slice(), Size::new(4, 8), ColorFormat::RGBA_U8).unwrap();
+///
+/// let mut options = DecodeOptions::default();
+/// options.truncated_fill = Some([0.0; 4]);
+/// let progress = decode_partial(&mut &data[..], image, Format::BC1_UNORM, &options).unwrap();
+/// assert_eq!((progress.decoded_rows, progress.total_rows), (4, 8));
+/// ```
+pub fn decode_partial(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<DecodeProgress, DecodeError> {
+    let total_rows = image.height();
+    let fill = match options.truncated_fill {
+        Some(fill) => fill,
+        None => {
+            decode(reader, image, format, options)?;
+            return Ok(DecodeProgress {
+                decoded_rows: total_rows,
+                total_rows,
+            });
+        }
+    };
+
+    check_sub_sampled_size(format, image.size(), options)?;
+
+    decode_truncated(
+        reader,
+        image,
+        format,
+        options,
+        fill,
+        |reader, image, options| decode_surface(reader, image, format, options, &mut []),
+    )
+}
+
+fn decode_surface(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    options: &DecodeOptions,
+    scratch: &mut [u8],
+) -> Result<(), DecodeError> {
+    if uses_f32_pipeline(format, image.color(), options) {
+        return decode_tone_mapped(reader, image, format, options);
+    }
+
+    get_decoders_with(format, options).decode_with_scratch(reader, image, options, scratch)
 }
 
 /// Decodes a rectangle of the image data of a surface from the given reader
@@ -143,6 +332,15 @@ pub fn decode(
 /// The `row_pitch` parameter specifies the number of bytes between the start
 /// of one row and the start of the next row in the output buffer.
 ///
+/// ## Block-compressed and sub-sampled formats
+///
+/// The rectangle does not need to be aligned to the blocks of the format.
+/// This includes block-compressed formats (e.g. BC1) and formats that store
+/// pixels in horizontal groups (e.g. `YUY2`, `R8G8_B8G8_UNORM`, or
+/// `R1_UNORM`). Reads are snapped to block boundaries internally and only
+/// the pixels within the rectangle are written to the output buffer. See
+/// [`Rect::align_to_blocks`] for the area that is actually decoded.
+///
 /// ## State of the reader
 ///
 /// The reader is expected to be positioned at the start of the encoded
@@ -169,12 +367,311 @@ pub fn decode_rect<R: Read + Seek>(
     format: Format,
     options: &DecodeOptions,
 ) -> Result<(), DecodeError> {
+    check_sub_sampled_size(format, size, options)?;
+
     let reader = reader as &mut dyn ReadSeek;
-    let decoders = get_decoders(format);
+
+    if options.reports_progress() {
+        return decode_rect_with_progress(
+            reader,
+            output,
+            row_pitch,
+            color,
+            size,
+            rect,
+            format,
+            options,
+            |reader, output, rect, options| {
+                decode_rect_surface(
+                    reader, output, row_pitch, color, size, rect, format, options,
+                )
+            },
+        );
+    }
+
+    decode_rect_surface(
+        reader, output, row_pitch, color, size, rect, format, options,
+    )
+}
+#[allow(clippy::too_many_arguments)]
+fn decode_rect_surface(
+    reader: &mut dyn ReadSeek,
+    output: &mut [u8],
+    row_pitch: usize,
+    color: ColorFormat,
+    size: Size,
+    rect: Rect,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    if uses_f32_pipeline(format, color, options) {
+        return decode_rect_tone_mapped(
+            reader, output, row_pitch, color, size, rect, format, options,
+        );
+    }
+
+    let decoders = get_decoders_with(format, options);
     decoders.decode_rect(color, reader, size, rect, output, row_pitch, options)
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Decodes a rectangle of the image data of a surface from a reader that
+/// does not support seeking.
+///
+/// This is the same as [`decode_rect`], but only requires [`Read`]. This
+/// makes it usable with network streams and decompressing readers. Instead
+/// of seeking, bytes before and after the rectangle are read and discarded,
+/// so this is typically slower than [`decode_rect`] for seekable readers.
+///
+/// Tone mapping, color adjustment, and progress reporting (see
+/// [`DecodeOptions`]) decode the rectangle in multiple passes. If any of them
+/// are used, the entire surface is read into memory first. This allocation
+/// counts towards [`DecodeOptions::memory_limit`].
+///
+/// ## State of the reader
+///
+/// The reader is expected to be positioned at the start of the encoded
+/// image data of the current surface. If the operation completes
+/// successfully, the reader will be positioned at the end of the encoded
+/// image data. If the operation fails, the reader may be positioned anywhere
+/// within the encoded image data.
+#[allow(clippy::too_many_arguments)]
+pub fn decode_rect_sequential<R: Read>(
+    reader: &mut R,
+    output: &mut [u8],
+    row_pitch: usize,
+    color: ColorFormat,
+    size: Size,
+    rect: Rect,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    if options.reports_progress() || uses_f32_pipeline(format, color, options) {
+        // These read the surface multiple times, so we buffer it.
+        let surface_len = PixelInfo::from(format)
+            .surface_bytes(size)
+            .and_then(|len| usize::try_from(len).ok())
+            .filter(|&len| len <= options.memory_limit)
+            .ok_or(DecodeError::MemoryLimitExceeded)?;
+        let mut surface = vec![0_u8; surface_len];
+        reader.read_exact(&mut surface)?;
+
+        let mut options = options.clone();
+        options.memory_limit -= surface_len;
+        return decode_rect(
+            &mut std::io::Cursor::new(surface.as_slice()),
+            output,
+            row_pitch,
+            color,
+            size,
+            rect,
+            format,
+            &options,
+        );
+    }
+
+    decode_rect(
+        &mut SkipReader::new(reader),
+        output,
+        row_pitch,
+        color,
+        size,
+        rect,
+        format,
+        options,
+    )
+}
+
+/// Decodes the image data of a surface like [`decode`], but writes rows
+/// `row_pitch` bytes apart.
+///
+/// This allows surfaces to be decoded directly into a sub-region of a larger
+/// image (e.g. a texture atlas) or into GPU staging memory with padded rows.
+/// Bytes between the end of one row and the start of the next row in the
+/// output buffer are left unchanged.
+///
+/// This is the same as calling [`decode_rect_sequential`] with a rectangle
+/// that covers the entire surface. If the rows are tightly packed, this is
+/// the same as [`decode`].
+///
+/// ```
+/// # use dds::*;
+/// // decode a 2x2 image into the top-right corner of a 4x2 RGBA8 image
+/// let data = [255_u8; 2 * 2 * 4];
+/// let mut atlas = vec![0_u8; 4 * 2 * 4];
+/// let row_pitch = 4 * 4;
+/// decode_with_pitch(
+///     &mut &data[..],
+///     &mut atlas[2 * 4..],
+///     row_pitch,
+///     ColorFormat::RGBA_U8,
+///     Size::new(2, 2),
+///     Format::R8G8B8A8_UNORM,
+///     &DecodeOptions::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(atlas[..8], [0; 8]);
+/// assert_eq!(atlas[8..16], [255; 8]);
+/// ```
+///
+/// ## State of the reader
+///
+/// The reader is expected to be positioned at the start of the encoded
+/// image data of the current surface. If the operation completes
+/// successfully, the reader will be positioned at the end of the encoded
+/// image data.
+pub fn decode_with_pitch(
+    reader: &mut dyn Read,
+    output: &mut [u8],
+    row_pitch: usize,
+    color: ColorFormat,
+    size: Size,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    let row_bytes = color.bytes_per_pixel() as usize * size.width as usize;
+    if row_pitch == row_bytes {
+        let len = row_bytes.saturating_mul(size.height as usize);
+        if let Some(image) = output
+            .get_mut(..len)
+            .and_then(|output| ImageViewMut::new(output, size, color))
+        {
+            return decode(reader, image, format, options);
+        }
+    }
+
+    decode_rect_sequential(
+        &mut &mut *reader,
+        output,
+        row_pitch,
+        color,
+        size,
+        Rect::new(0, 0, size.width, size.height),
+        format,
+        options,
+    )
+}
+
+/// Decodes only the first rows of a surface.
+///
+/// `image` must have the width of the surface and at most its height. The
+/// first `image.height()` rows of the surface are decoded into `image`. This
+/// is useful for progressive previews of large surfaces, since rows below
+/// the decoded ones are never read. Unlike [`decode_rect`], this does not
+/// require the reader to implement [`Seek`].
+///
+/// Since block-compressed formats store rows in groups (e.g. 4 rows for
+/// BC1), reading stops at the end of the block row containing the last
+/// decoded row. Bi-planar formats (e.g. `NV12`) cannot be decoded in parts,
+/// so the entire surface is read and decoded for them.
+///
+/// [`DecodeOptions::progress`] is invoked once all rows have been decoded.
+///
+/// ```
+/// # use dds::*;
+/// // decode the top 5 rows of an 8x16 BC1 surface
+/// let data = [0_u8; 8 * 8];
+/// let mut pixels = vec![0_u8; 8 * 5 * 4];
+/// let image = ImageViewMut::new(pixels.as_mut_slice(), Size::new(8, 5), ColorFormat::RGBA_U8).unwrap();
+/// let mut reader = &data[..];
+/// decode_top_rows(&mut reader, image, Size::new(8, 16), Format::BC1_UNORM, &DecodeOptions::default()).unwrap();
+/// // only the first 2 of 4 block rows were read
+/// assert_eq!(reader.len(), 8 * 4);
+/// ```
+///
+/// ## State of the reader
+///
+/// The reader is expected to be positioned at the start of the encoded
+/// image data of the surface. If the operation completes successfully, the
+/// reader will be positioned right after the last encoded row that was read.
+pub fn decode_top_rows(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    surface_size: Size,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    if image.width() != surface_size.width || image.height() > surface_size.height {
+        return Err(DecodeError::UnexpectedSurfaceSize);
+    }
+    if image.height() == surface_size.height {
+        return decode(reader, image, format, options);
+    }
+
+    let row_multiple = match PixelInfo::from(format) {
+        PixelInfo::Fixed { .. } => 1,
+        PixelInfo::Block(block) => block.size().1 as u32,
+        PixelInfo::BiPlanar(_) => surface_size.height,
+    };
+    let width = image.width();
+    let rows = image.height();
+    let color = image.color();
+    let row_bytes = image.row_pitch();
+
+    options.check_cancelled()?;
+    let strip_options = options.without_progress();
+
+    // rows that can be decoded directly
+    let full_rows = rows / row_multiple * row_multiple;
+    let (full, rest) = image.data.split_at_mut(full_rows as usize * row_bytes);
+    if full_rows > 0 {
+        let view =
+            ImageViewMut::new(full, Size::new(width, full_rows), color).expect("invalid strip");
+        decode(reader, view, format, &strip_options)?;
+    }
+
+    // the remaining rows have to be decoded together with the rows below them
+    if !rest.is_empty() {
+        let strip_rows = row_multiple.min(surface_size.height - full_rows);
+        let strip_len = strip_rows as usize * row_bytes;
+        if strip_len > options.memory_limit {
+            return Err(DecodeError::MemoryLimitExceeded);
+        }
+        let mut strip = vec![0_u8; strip_len];
+        let view = ImageViewMut::new(strip.as_mut_slice(), Size::new(width, strip_rows), color)
+            .expect("invalid strip");
+        decode(reader, view, format, &strip_options)?;
+        rest.copy_from_slice(&strip[..rest.len()]);
+    }
+
+    options.report_progress(rows, rows);
+    Ok(())
+}
+
+/// Returns a view of the given encoded surface data as a decoded image without
+/// copying, if possible.
+///
+/// This is only possible if [`Format::is_zero_copy`] returns `true` for the
+/// given format and color. If the combination is not zero-copy or `data` is
+/// too short to contain the entire surface, `None` is returned. Any bytes
+/// after the surface are ignored.
+///
+/// ```
+/// # use dds::*;
+/// let data = vec![1_u8, 2, 3, 4, 5, 6, 7, 8];
+/// let size = Size::new(2, 1);
+/// let image = decode_borrowed(&data, size, Format::R8G8B8A8_UNORM, ColorFormat::RGBA_U8);
+/// assert_eq!(image.map(|i| i.data()), Some(data.as_slice()));
+/// ```
+pub fn decode_borrowed(
+    data: &[u8],
+    size: Size,
+    format: Format,
+    color: ColorFormat,
+) -> Option<ImageView<'_>> {
+    if !format.is_zero_copy(color) {
+        return None;
+    }
+
+    let bytes = usize::try_from(size.pixels().checked_mul(color.bytes_per_pixel() as u64)?).ok()?;
+    ImageView::new(data.get(..bytes)?, size, color)
+}
+
+/// Options for decoding images.
+///
+/// Floating-point fields are compared and hashed by their bit patterns, so
+/// e.g. `NaN == NaN` and `0.0 != -0.0`. [`ProgressCallback`]s and
+/// [`CancellationToken`]s are equal if they are clones of each other.
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct DecodeOptions {
     /// The maximum amount of memory that the decoder is allowed to allocate.
@@ -189,17 +686,415 @@ pub struct DecodeOptions {
     /// is a bi-planar format and decoding it generally requires reading the
     /// entire Y plane into memory. This can be a problem for large images.
     ///
+    /// Buffers returned to the caller (e.g. by
+    /// [`Decoder::read_texture`](crate::Decoder::read_texture)) don't count
+    /// towards this limit. See [`Self::max_output_size`] for those.
+    ///
     /// Default: 33 MiB
     ///
     /// (The default was chosen to be large enough to decode 4K `NV12`, `P016`,
     /// and `P010` images. All other formats require at most 256 KiB for 16K
     /// images.)
     pub memory_limit: usize,
+    /// The maximum number of bytes of all surfaces returned together by
+    /// functions of [`Decoder`](crate::Decoder) that allocate their output,
+    /// e.g. [`Decoder::read_texture`](crate::Decoder::read_texture) and
+    /// [`Decoder::decode_thumbnail`](crate::Decoder::decode_thumbnail).
+    ///
+    /// If the output would be larger, these functions return
+    /// [`DecodeError::OutputSizeExceeded`] before allocating anything. This
+    /// protects against files that declare huge surfaces.
+    ///
+    /// Default: 4 GiB
+    pub max_output_size: u64,
+    /// The tone mapping applied when decoding HDR images into U8 or U16 color
+    /// formats.
+    ///
+    /// Formats that natively decode to [`Precision::F32`](crate::Precision::F32)
+    /// (e.g. BC6H and the floating-point formats) can store values outside
+    /// the range [0, 1]. Without tone mapping, these values are simply clamped
+    /// when decoding to integer precisions. Tone mapping is not applied when
+    /// decoding to F32, when decoding the alpha channel only, or for formats
+    /// with integer native precision.
+    ///
+    /// Default: [`ToneMapping::NONE`]
+    pub tone_mapping: ToneMapping,
+    /// Color adjustments (exposure and gamma) applied to all decoded pixels.
+    ///
+    /// This is intended for transcoding, i.e. decoding a file with
+    /// [`Decoder`](crate::Decoder) and writing it in a different format with
+    /// [`Encoder`](crate::Encoder). The adjustment is baked into the decoded
+    /// pixels, so assets authored for a different renderer can e.g. be
+    /// brightened in the same pass. Adjustments are applied in f32 before
+    /// tone mapping and converting to the output precision. Alpha is never
+    /// adjusted.
+    ///
+    /// If set, pixels are decoded via f32, which is slower than decoding
+    /// directly into the output color format.
+    ///
+    /// Default: [`ColorAdjustment::NONE`]
+    pub color_adjustment: ColorAdjustment,
+    /// Whether YUV formats should be decoded without converting to RGB.
+    ///
+    /// If enabled, the Y, U, and V values of YUV formats (e.g. `AYUV`, `YUY2`,
+    /// `NV12`, `P010`) are written to the R, G, and B channels of the output
+    /// respectively. The values are only normalized to the output precision,
+    /// no color conversion happens. For sub-sampled formats, chroma values are
+    /// repeated for all pixels that share them.
+    ///
+    /// This option has no effect on non-YUV formats.
+    ///
+    /// Default: `false`
+    pub raw_yuv: bool,
+    /// Whether to reject chroma sub-sampled surfaces with sizes that are not
+    /// a multiple of their sub-sampling.
+    ///
+    /// Formats like `YUY2` (4:2:2) and `NV12` (4:2:0) share chroma samples
+    /// between 2x1 and 2x2 pixels respectively. Some files declare odd widths
+    /// or heights for these formats anyway. The data of such surfaces is laid
+    /// out as if the size was rounded up to the next multiple, so the last
+    /// column/row of pixels uses the chroma samples of a partial macro-pixel.
+    ///
+    /// By default, such surfaces are decoded permissively: the partial
+    /// macro-pixel is decoded and clipped to the declared size. If this
+    /// option is enabled, [`DecodeError::InvalidSize`] is returned instead.
+    ///
+    /// Default: `false`
+    pub strict_sub_sampling: bool,
+    /// Whether to keep the premultiplied color values of formats with
+    /// premultiplied alpha.
+    ///
+    /// [`Format::BC2_UNORM_PREMULTIPLIED_ALPHA`] (`DXT2`) and
+    /// [`Format::BC3_UNORM_PREMULTIPLIED_ALPHA`] (`DXT4`) store color values
+    /// that are premultiplied with alpha. By default, the decoder divides out
+    /// alpha, so the output has straight alpha like all other formats. Pixels
+    /// with an alpha of 0 keep their stored color values, since there is no
+    /// color information to recover.
+    ///
+    /// If this option is enabled, the stored premultiplied values are output
+    /// as is. This is useful for pipelines that work with premultiplied alpha,
+    /// since dividing out alpha is lossy.
+    ///
+    /// This option has no effect on other formats.
+    ///
+    /// Default: `false`
+    pub keep_premultiplied_alpha: bool,
+    /// A callback that is periodically invoked with the progress of the
+    /// decoding operation.
+    ///
+    /// If set (or if [`Self::cancellation`] is set), surfaces are decoded in
+    /// strips of rows, and progress is reported after each strip. Progress is
+    /// reported at most ~100 times per surface, and always at least once
+    /// after the last row has been decoded. Bi-planar formats (e.g. `NV12`)
+    /// cannot be decoded in strips, so they only report completion.
+    ///
+    /// Progress is reported by [`decode`](crate::decode),
+    /// [`decode_rect`](crate::decode_rect), and everything built on top of
+    /// them (e.g. [`Decoder::read_surface`](crate::Decoder::read_surface)).
+    ///
+    /// Default: `None`
+    pub progress: Option<ProgressCallback>,
+    /// A token to abort decoding operations.
+    ///
+    /// Cancellation is checked before each strip of rows (see
+    /// [`Self::progress`]). Once the token is cancelled,
+    /// [`DecodeError::Cancelled`] is returned. In this case, the output
+    /// buffer will be partially written and the position of the reader is
+    /// unspecified.
+    ///
+    /// Default: `None`
+    pub cancellation: Option<CancellationToken>,
+    /// The RGBA color used to fill the rows of a surface that are missing
+    /// because the data was cut off.
+    ///
+    /// Many DDS files found in the wild (e.g. in archives) are truncated
+    /// somewhere in their data section. By default, decoding such a surface
+    /// fails with an IO error. If this option is set, all complete rows
+    /// (or rows of blocks for block-compressed formats) are decoded and the
+    /// remaining rows are filled with this color instead. Use `[0.0; 4]` for
+    /// transparent black. Bi-planar formats (e.g. `NV12`) are either decoded
+    /// entirely or filled entirely.
+    ///
+    /// Use [`decode_partial`](crate::decode_partial) or
+    /// [`Decoder::read_surface_partial`](crate::Decoder::read_surface_partial)
+    /// to find out how many rows were decoded. Surfaces read after the end of
+    /// the data are filled entirely.
+    ///
+    /// This option only affects [`decode`](crate::decode) and everything
+    /// built on top of it. Rectangle decoding is not affected.
+    ///
+    /// Default: `None`
+    pub truncated_fill: Option<[f32; 4]>,
+    /// The implementation used to decode `BC7_UNORM` blocks.
+    ///
+    /// Both implementations produce exactly the same output. See
+    /// [`Bc7Decoder`] for the differences.
+    ///
+    /// Default: [`Bc7Decoder::TableDriven`]
+    pub bc7_decoder: Bc7Decoder,
+}
+impl DecodeOptions {
+    /// Sets [`Self::memory_limit`].
+    ///
+    /// ```
+    /// # use dds::*;
+    /// let options = DecodeOptions::default()
+    ///     .with_memory_limit(1024 * 1024)
+    ///     .with_raw_yuv(true)
+    ///     .with_truncated_fill(Some([0.0; 4]));
+    /// assert_eq!(options.memory_limit, 1024 * 1024);
+    /// ```
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+    /// Sets [`Self::max_output_size`].
+    pub fn with_max_output_size(mut self, max_output_size: u64) -> Self {
+        self.max_output_size = max_output_size;
+        self
+    }
+    /// Sets [`Self::tone_mapping`].
+    pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+        self.tone_mapping = tone_mapping;
+        self
+    }
+    /// Sets [`Self::color_adjustment`].
+    pub fn with_color_adjustment(mut self, color_adjustment: ColorAdjustment) -> Self {
+        self.color_adjustment = color_adjustment;
+        self
+    }
+    /// Sets [`Self::raw_yuv`].
+    pub fn with_raw_yuv(mut self, raw_yuv: bool) -> Self {
+        self.raw_yuv = raw_yuv;
+        self
+    }
+    /// Sets [`Self::strict_sub_sampling`].
+    pub fn with_strict_sub_sampling(mut self, strict_sub_sampling: bool) -> Self {
+        self.strict_sub_sampling = strict_sub_sampling;
+        self
+    }
+    /// Sets [`Self::keep_premultiplied_alpha`].
+    pub fn with_keep_premultiplied_alpha(mut self, keep_premultiplied_alpha: bool) -> Self {
+        self.keep_premultiplied_alpha = keep_premultiplied_alpha;
+        self
+    }
+    /// Sets [`Self::progress`].
+    pub fn with_progress(mut self, progress: Option<ProgressCallback>) -> Self {
+        self.progress = progress;
+        self
+    }
+    /// Sets [`Self::cancellation`].
+    pub fn with_cancellation(mut self, cancellation: Option<CancellationToken>) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+    /// Sets [`Self::truncated_fill`].
+    pub fn with_truncated_fill(mut self, truncated_fill: Option<[f32; 4]>) -> Self {
+        self.truncated_fill = truncated_fill;
+        self
+    }
+    /// Sets [`Self::bc7_decoder`].
+    pub fn with_bc7_decoder(mut self, bc7_decoder: Bc7Decoder) -> Self {
+        self.bc7_decoder = bc7_decoder;
+        self
+    }
 }
 impl Default for DecodeOptions {
     fn default() -> Self {
         Self {
             memory_limit: 33 * 1024 * 1024,
+            max_output_size: 4 * 1024 * 1024 * 1024,
+            tone_mapping: ToneMapping::NONE,
+            color_adjustment: ColorAdjustment::NONE,
+            raw_yuv: false,
+            strict_sub_sampling: false,
+            keep_premultiplied_alpha: false,
+            progress: None,
+            cancellation: None,
+            truncated_fill: None,
+            bc7_decoder: Bc7Decoder::TableDriven,
+        }
+    }
+}
+impl PartialEq for DecodeOptions {
+    fn eq(&self, other: &Self) -> bool {
+        let fill_bits = |fill: &Option<[f32; 4]>| fill.map(|fill| fill.map(f32::to_bits));
+
+        self.memory_limit == other.memory_limit
+            && self.max_output_size == other.max_output_size
+            && self.tone_mapping == other.tone_mapping
+            && self.color_adjustment == other.color_adjustment
+            && self.raw_yuv == other.raw_yuv
+            && self.strict_sub_sampling == other.strict_sub_sampling
+            && self.keep_premultiplied_alpha == other.keep_premultiplied_alpha
+            && self.progress == other.progress
+            && self.cancellation == other.cancellation
+            && fill_bits(&self.truncated_fill) == fill_bits(&other.truncated_fill)
+            && self.bc7_decoder == other.bc7_decoder
+    }
+}
+impl Eq for DecodeOptions {}
+impl std::hash::Hash for DecodeOptions {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // destructured, so new fields can't be forgotten
+        let Self {
+            memory_limit,
+            max_output_size,
+            tone_mapping,
+            color_adjustment,
+            raw_yuv,
+            strict_sub_sampling,
+            keep_premultiplied_alpha,
+            progress,
+            cancellation,
+            truncated_fill,
+            bc7_decoder,
+        } = self;
+
+        memory_limit.hash(state);
+        max_output_size.hash(state);
+        tone_mapping.hash(state);
+        color_adjustment.hash(state);
+        raw_yuv.hash(state);
+        strict_sub_sampling.hash(state);
+        keep_premultiplied_alpha.hash(state);
+        progress.hash(state);
+        cancellation.hash(state);
+        truncated_fill
+            .map(|fill| fill.map(f32::to_bits))
+            .hash(state);
+        bc7_decoder.hash(state);
+    }
+}
+
+/// An implementation of the BC7 block decoder.
+///
+/// See [`DecodeOptions::bc7_decoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Bc7Decoder {
+    /// A decoder that describes all modes with a table and expands the
+    /// endpoints of each subset into a palette before looking up pixels.
+    /// Interpolating the palettes is vectorized by the compiler.
+    #[default]
+    TableDriven,
+    /// The straightforward decoder with specialized code for each mode.
+    ///
+    /// This is the reference implementation the table-driven decoder is
+    /// verified against.
+    Reference,
+}
+
+/// Controls how HDR values are mapped to the range [0, 1].
+///
+/// The color channels are first multiplied by `exposure` and then mapped
+/// using `curve`. Alpha is never tone mapped.
+///
+/// `exposure` is compared and hashed by its bit pattern.
+#[derive(Debug, Clone, Copy)]
+pub struct ToneMapping {
+    /// A multiplier applied to all color channels before the tone mapping
+    /// curve.
+    pub exposure: f32,
+    pub curve: ToneMapCurve,
+}
+impl ToneMapping {
+    /// No tone mapping. Values outside the range [0, 1] are clamped.
+    pub const NONE: Self = Self::new(ToneMapCurve::Clamp);
+
+    /// Creates a new tone mapping with the given curve and an exposure of 1.
+    pub const fn new(curve: ToneMapCurve) -> Self {
+        Self {
+            exposure: 1.0,
+            curve,
         }
     }
+    pub const fn with_exposure(self, exposure: f32) -> Self {
+        Self { exposure, ..self }
+    }
+
+    pub(crate) fn is_none(&self) -> bool {
+        self.curve == ToneMapCurve::Clamp && self.exposure == 1.0
+    }
+}
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+impl PartialEq for ToneMapping {
+    fn eq(&self, other: &Self) -> bool {
+        self.exposure.to_bits() == other.exposure.to_bits() && self.curve == other.curve
+    }
+}
+impl Eq for ToneMapping {}
+impl std::hash::Hash for ToneMapping {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.exposure.to_bits().hash(state);
+        self.curve.hash(state);
+    }
+}
+
+/// Exposure and gamma adjustments applied to the color channels of decoded
+/// pixels.
+///
+/// Each color value `x` is mapped to `(x * exposure) ^ (1 / gamma)`. So an
+/// exposure greater than 1 brightens the image uniformly, while a gamma
+/// greater than 1 brightens mid-tones (e.g. 0.5 becomes 0.73 for a gamma of
+/// 2.2) and keeps black and white unchanged.
+///
+/// `exposure` and `gamma` are compared and hashed by their bit patterns.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorAdjustment {
+    /// A multiplier applied to all color channels.
+    pub exposure: f32,
+    /// The gamma applied to all color channels after exposure.
+    pub gamma: f32,
+}
+impl ColorAdjustment {
+    /// No adjustment.
+    pub const NONE: Self = Self {
+        exposure: 1.0,
+        gamma: 1.0,
+    };
+
+    pub const fn with_exposure(self, exposure: f32) -> Self {
+        Self { exposure, ..self }
+    }
+    pub const fn with_gamma(self, gamma: f32) -> Self {
+        Self { gamma, ..self }
+    }
+
+    pub(crate) fn is_none(&self) -> bool {
+        self.exposure == 1.0 && self.gamma == 1.0
+    }
+}
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+impl PartialEq for ColorAdjustment {
+    fn eq(&self, other: &Self) -> bool {
+        self.exposure.to_bits() == other.exposure.to_bits()
+            && self.gamma.to_bits() == other.gamma.to_bits()
+    }
+}
+impl Eq for ColorAdjustment {}
+impl std::hash::Hash for ColorAdjustment {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.exposure.to_bits().hash(state);
+        self.gamma.to_bits().hash(state);
+    }
+}
+
+/// The curve used to map HDR values to the range [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ToneMapCurve {
+    /// Values are clamped to [0, 1].
+    #[default]
+    Clamp,
+    /// The simple Reinhard operator `x / (1 + x)`.
+    Reinhard,
+    /// An approximation of the ACES filmic curve.
+    Aces,
 }
@@ -0,0 +1,247 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// A reader that hides the padding at the end of each row of a surface.
+///
+/// Some writers pad the rows of uncompressed surfaces (see
+/// [`crate::DataLayout::row_alignment`]). Decoders expect tightly packed rows,
+/// so this reader presents the surface as if its rows were tightly packed.
+/// Padding bytes are skipped as soon as the last byte of a row has been read,
+/// so the underlying reader is positioned at the end of the padded surface
+/// after the last row has been read.
+///
+/// Positions reported and accepted by [`Seek`] are positions in the tightly
+/// packed surface relative to the start of the surface. Seeking relative to
+/// the end is not supported.
+pub(crate) struct PaddedRowReader<R> {
+    inner: R,
+    row_bytes: u64,
+    padding: u64,
+    /// The current position in the tightly packed surface.
+    pos: u64,
+}
+impl<R> PaddedRowReader<R> {
+    /// Creates a new reader for a surface starting at the current position of
+    /// the given reader.
+    pub fn new(inner: R, row_bytes: u64, row_pitch: u64) -> Self {
+        debug_assert!(row_pitch >= row_bytes);
+        Self {
+            inner,
+            row_bytes,
+            padding: row_pitch - row_bytes,
+            pos: 0,
+        }
+    }
+
+    /// Maps a position in the tightly packed surface to a position in the
+    /// padded surface.
+    fn padded_position(&self, pos: u64) -> u64 {
+        if self.row_bytes == 0 {
+            return pos;
+        }
+        let row = pos / self.row_bytes;
+        row * (self.row_bytes + self.padding) + pos % self.row_bytes
+    }
+}
+impl<R: Read> Read for PaddedRowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.row_bytes == 0 {
+            return Ok(0);
+        }
+
+        // never read across the end of a row
+        let remaining_in_row = self.row_bytes - self.pos % self.row_bytes;
+        let len = buf
+            .len()
+            .min(remaining_in_row.min(usize::MAX as u64) as usize);
+        let read = self.inner.read(&mut buf[..len])?;
+        self.pos += read as u64;
+
+        if read > 0 && self.pos % self.row_bytes == 0 && self.padding > 0 {
+            // Skip the padding of the row. Some writers omit the padding of
+            // the very last row, so a short skip is not an error.
+            let mut padding = (&mut self.inner).take(self.padding);
+            io::copy(&mut padding, &mut io::sink())?;
+        }
+
+        Ok(read)
+    }
+}
+impl<R: Read + Seek> Seek for PaddedRowReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(offset) => {
+                if offset >= 0 {
+                    self.pos.checked_add(offset as u64)
+                } else {
+                    self.pos.checked_sub(offset.unsigned_abs())
+                }
+            }
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek relative to the end of a padded surface",
+                ))
+            }
+        };
+        let target = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+
+        let from = self.padded_position(self.pos) as i128;
+        let to = self.padded_position(target) as i128;
+        let offset = i64::try_from(to - from)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "seek offset too large"))?;
+        self.inner.seek(SeekFrom::Current(offset))?;
+
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+/// A reader that fills in the blocks missing from surfaces written by legacy
+/// tools.
+///
+/// Some legacy tools round the number of blocks of a surface down instead of
+/// up (see [`crate::DataLayout::has_legacy_block_sizes`]), so the last column
+/// and row of blocks are missing. This reader presents the surface as if it
+/// had all blocks, with the bytes of missing blocks being zero. After the
+/// entire surface has been read, the underlying reader is positioned at the
+/// end of the stored surface.
+///
+/// Positions reported and accepted by [`Seek`] are positions in the complete
+/// surface relative to the start of the surface. Seeking relative to the end
+/// is not supported.
+pub(crate) struct LegacyBlockReader<R> {
+    inner: R,
+    /// The number of bytes of a complete row of blocks.
+    row_bytes: u64,
+    /// The number of bytes of a stored row of blocks.
+    stored_row_bytes: u64,
+    /// The number of stored rows of blocks.
+    stored_rows: u64,
+    /// The number of bytes of the complete surface.
+    len: u64,
+    /// The current position in the complete surface.
+    pos: u64,
+}
+impl<R> LegacyBlockReader<R> {
+    /// Creates a new reader for a surface starting at the current position of
+    /// the given reader.
+    pub fn new(
+        inner: R,
+        row_bytes: u64,
+        stored_row_bytes: u64,
+        rows: u64,
+        stored_rows: u64,
+    ) -> Self {
+        debug_assert!(row_bytes >= stored_row_bytes && rows >= stored_rows);
+        Self {
+            inner,
+            row_bytes,
+            stored_row_bytes,
+            stored_rows,
+            len: row_bytes * rows,
+            pos: 0,
+        }
+    }
+
+    /// Maps a position in the complete surface to a position in the stored
+    /// surface.
+    fn stored_position(&self, pos: u64) -> u64 {
+        if self.row_bytes == 0 {
+            return 0;
+        }
+        let row = pos / self.row_bytes;
+        if row < self.stored_rows {
+            row * self.stored_row_bytes + (pos % self.row_bytes).min(self.stored_row_bytes)
+        } else {
+            self.stored_rows * self.stored_row_bytes
+        }
+    }
+}
+impl<R: Read> Read for LegacyBlockReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let row = self.pos / self.row_bytes;
+        let column = self.pos % self.row_bytes;
+        if row < self.stored_rows && column < self.stored_row_bytes {
+            // never read across the end of a stored row
+            let remaining = self.stored_row_bytes - column;
+            let len = buf.len().min(remaining.min(usize::MAX as u64) as usize);
+            let read = self.inner.read(&mut buf[..len])?;
+            self.pos += read as u64;
+            return Ok(read);
+        }
+
+        // missing blocks are zero
+        let remaining = if row < self.stored_rows {
+            self.row_bytes - column
+        } else {
+            self.len - self.pos
+        };
+        let len = buf.len().min(remaining.min(usize::MAX as u64) as usize);
+        buf[..len].fill(0);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+impl<R: Read + Seek> Seek for LegacyBlockReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(offset) => {
+                if offset >= 0 {
+                    self.pos.checked_add(offset as u64)
+                } else {
+                    self.pos.checked_sub(offset.unsigned_abs())
+                }
+            }
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek relative to the end of a legacy surface",
+                ))
+            }
+        };
+        let target = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+
+        let from = self.stored_position(self.pos) as i128;
+        let to = self.stored_position(target) as i128;
+        let offset = i64::try_from(to - from)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "seek offset too large"))?;
+        self.inner.seek(SeekFrom::Current(offset))?;
+
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+/// A reader for the encoded data of a surface, which presents the data the
+/// way decoders expect it, no matter how it is stored.
+pub(crate) enum SurfaceReader<R> {
+    Tight(R),
+    Padded(PaddedRowReader<R>),
+    LegacyBlocks(LegacyBlockReader<R>),
+}
+impl<R: Read> Read for SurfaceReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SurfaceReader::Tight(r) => r.read(buf),
+            SurfaceReader::Padded(r) => r.read(buf),
+            SurfaceReader::LegacyBlocks(r) => r.read(buf),
+        }
+    }
+}
+impl<R: Read + Seek> Seek for SurfaceReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            SurfaceReader::Tight(r) => r.seek(pos),
+            SurfaceReader::Padded(r) => r.seek(pos),
+            SurfaceReader::LegacyBlocks(r) => r.seek(pos),
+        }
+    }
+}
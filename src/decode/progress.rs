@@ -0,0 +1,230 @@
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::{
+    util::div_ceil, ColorFormat, DecodeError, Format, ImageViewMut, PixelInfo, Rect, Size,
+};
+
+use super::strip::block_height;
+use super::{DecodeContext, DecodeOptions, RArgs, ReadSeek};
+
+/// The number of times progress is reported while decoding a surface (at
+/// most).
+const PROGRESS_STEPS: u32 = 100;
+
+/// The progress of a decoding operation.
+///
+/// See [`DecodeOptions::progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct DecodeProgress {
+    /// The number of rows of pixels that have been fully decoded so far.
+    pub decoded_rows: u32,
+    /// The total number of rows of pixels that will be decoded.
+    pub total_rows: u32,
+}
+impl DecodeProgress {
+    /// Returns the progress as a fraction in the range [0, 1].
+    pub fn fraction(&self) -> f32 {
+        if self.total_rows == 0 {
+            1.0
+        } else {
+            self.decoded_rows as f32 / self.total_rows as f32
+        }
+    }
+}
+
+/// A callback that is invoked with the progress of a decoding operation.
+///
+/// See [`DecodeOptions::progress`].
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(DecodeProgress) + Send + Sync>);
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(DecodeProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    fn report(&self, decoded_rows: u32, total_rows: u32) {
+        (self.0)(DecodeProgress {
+            decoded_rows,
+            total_rows,
+        })
+    }
+}
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+impl PartialEq for ProgressCallback {
+    /// Two callbacks are equal if they are clones of each other.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::as_ptr(&self.0) as *const u8 == Arc::as_ptr(&other.0) as *const u8
+    }
+}
+impl Eq for ProgressCallback {}
+impl Hash for ProgressCallback {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const u8).hash(state);
+    }
+}
+
+/// A token that can be used to abort decoding operations, e.g. from another
+/// thread.
+///
+/// Clones of a token share the same state, so cancelling one clone cancels
+/// all of them. See [`DecodeOptions::cancellation`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of all operations using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+impl PartialEq for CancellationToken {
+    /// Two tokens are equal if they are clones of each other.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for CancellationToken {}
+impl Hash for CancellationToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).hash(state);
+    }
+}
+
+impl DecodeOptions {
+    pub(crate) fn reports_progress(&self) -> bool {
+        self.progress.is_some() || self.cancellation.is_some()
+    }
+    pub(crate) fn check_cancelled(&self) -> Result<(), DecodeError> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(DecodeError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+    pub(crate) fn report_progress(&self, decoded_rows: u32, total_rows: u32) {
+        if let Some(progress) = &self.progress {
+            progress.report(decoded_rows, total_rows);
+        }
+    }
+    /// Returns a copy of the options without progress reporting and
+    /// cancellation. This is used for the decoding of individual strips.
+    pub(crate) fn without_progress(&self) -> Self {
+        let mut options = self.clone();
+        options.progress = None;
+        options.cancellation = None;
+        options
+    }
+}
+
+/// Returns the number of rows per strip when decoding a surface with the
+/// given height while reporting progress.
+fn progress_strip_height(format: Format, height: u32) -> u32 {
+    match PixelInfo::from(format) {
+        // bi-planar formats cannot be decoded in strips
+        PixelInfo::BiPlanar(_) => height.max(1),
+        pixel_info => {
+            let block_height = block_height(pixel_info);
+            let rows = div_ceil(height, PROGRESS_STEPS).max(1);
+            div_ceil(rows, block_height) * block_height
+        }
+    }
+}
+
+/// Decodes the given image in strips, checking for cancellation before and
+/// reporting progress after each strip.
+pub(crate) fn decode_with_progress(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    options: &DecodeOptions,
+    decode: impl Fn(&mut dyn Read, ImageViewMut, &DecodeOptions) -> Result<(), DecodeError>,
+) -> Result<(), DecodeError> {
+    let size = image.size();
+    let color = image.color();
+    let row_pitch = image.row_pitch();
+    let strip_options = options.without_progress();
+    let rows_per_strip = progress_strip_height(format, size.height);
+
+    let mut y = 0;
+    loop {
+        options.check_cancelled()?;
+
+        let rows = rows_per_strip.min(size.height - y);
+        let strip = &mut image.data[y as usize * row_pitch..(y + rows) as usize * row_pitch];
+        let strip_size = Size::new(size.width, rows);
+        let strip_view = ImageViewMut::new(strip, strip_size, color).expect("invalid strip");
+        decode(reader, strip_view, &strip_options)?;
+
+        y += rows;
+        options.report_progress(y, size.height);
+
+        if y >= size.height {
+            return Ok(());
+        }
+    }
+}
+
+/// Decodes the given rectangle in strips, checking for cancellation before
+/// and reporting progress after each strip.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode_rect_with_progress(
+    reader: &mut dyn ReadSeek,
+    output: &mut [u8],
+    row_pitch: usize,
+    color: ColorFormat,
+    size: Size,
+    rect: Rect,
+    format: Format,
+    options: &DecodeOptions,
+    decode_rect: impl Fn(&mut dyn ReadSeek, &mut [u8], Rect, &DecodeOptions) -> Result<(), DecodeError>,
+) -> Result<(), DecodeError> {
+    // validate the arguments against the entire rectangle
+    RArgs::new(
+        reader,
+        output,
+        row_pitch,
+        rect,
+        DecodeContext {
+            color,
+            size,
+            memory_limit: options.memory_limit,
+            scratch: &mut [],
+        },
+    )?;
+
+    let strip_options = options.without_progress();
+    let rows_per_strip = progress_strip_height(format, rect.height);
+    let start = reader.stream_position()?;
+
+    let mut y = 0;
+    loop {
+        options.check_cancelled()?;
+
+        let rows = rows_per_strip.min(rect.height - y);
+        let strip_rect = Rect::new(rect.x, rect.y + y, rect.width, rows);
+        reader.seek(SeekFrom::Start(start))?;
+        let out_start = (y as usize * row_pitch).min(output.len());
+        decode_rect(reader, &mut output[out_start..], strip_rect, &strip_options)?;
+
+        y += rows;
+        options.report_progress(y, rect.height);
+
+        if y >= rect.height {
+            return Ok(());
+        }
+    }
+}
@@ -8,7 +8,7 @@ use crate::util::round_down_to_multiple;
 use crate::{cast, util::div_ceil, DecodeError, Rect, Size};
 use crate::{convert_channels_for, util, Channels, ColorFormat};
 
-use super::{DecodeContext, ReadSeek};
+use super::{Buffer, DecodeContext, ReadSeek};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct PixelSize {
@@ -182,7 +182,7 @@ pub(crate) fn for_each_pixel_rect_untyped(
         )?;
 
         let pixels_per_line = rect.width as usize;
-        let mut row: Box<[u8]> = context.alloc(pixels_per_line * size_of_in)?;
+        let mut row = context.alloc_bytes(pixels_per_line * size_of_in)?;
         let mut conversion_buffer = ChannelConversionBuffer::new(native_color, buf_color.channels);
         for y in 0..rect.height {
             if y > 0 {
@@ -1026,8 +1026,8 @@ impl UntypedPixelBuffer {
 }
 
 /// A buffer holding raw encoded lines of pixels straight from the reader.
-struct UntypedLineBuffer {
-    buf: Box<[u8]>,
+struct UntypedLineBuffer<'s> {
+    buf: Buffer<'s>,
     buf_filled: usize,
     bytes_per_line: usize,
     /// How many lines are still left to read from disk
@@ -1037,17 +1037,21 @@ struct UntypedLineBuffer {
     /// If `>= buffer.len()`, the buffer is empty and needs to be refilled.
     current_line_start: usize,
 }
-impl UntypedLineBuffer {
+/// Returns the number of bytes [`UntypedLineBuffer`] uses for the given lines.
+pub(crate) fn line_buffer_len(bytes_per_line: usize, height: usize) -> usize {
+    const TARGET_BUFFER_SIZE: usize = 64 * 1024; // 64 KB
+
+    let lines_in_buffer = (TARGET_BUFFER_SIZE / bytes_per_line.max(1)).clamp(1, height.max(1));
+    lines_in_buffer * bytes_per_line
+}
+impl<'s> UntypedLineBuffer<'s> {
     fn new(
         bytes_per_line: usize,
         height: usize,
-        context: &mut DecodeContext,
+        context: &mut DecodeContext<'s>,
     ) -> Result<Self, DecodeError> {
-        const TARGET_BUFFER_SIZE: usize = 64 * 1024; // 64 KB
-
-        let lines_in_buffer = (TARGET_BUFFER_SIZE / bytes_per_line).clamp(1, height);
-        let buf_len = lines_in_buffer * bytes_per_line;
-        let buf = context.alloc(buf_len)?;
+        let buf_len = line_buffer_len(bytes_per_line, height);
+        let buf = context.alloc_bytes(buf_len)?;
 
         Ok(Self {
             buf,
@@ -1192,8 +1196,7 @@ pub(crate) fn for_each_bi_planar(
     // Step 1: Read the entirety of plane 1
     let plain1_bytes_per_line = size.width as usize * info.plane1_element_size as usize;
     let plane1_size = plain1_bytes_per_line * size.height as usize;
-    let mut plane1 = context.alloc_capacity(plane1_size)?;
-    read_exact_into(r, &mut plane1, plane1_size)?;
+    let plane1 = read_plane(r, plane1_size, &mut context)?;
 
     // Step 2: Go through plane 2
     let uv_width = div_ceil(size.width, info.sub_sampling.0 as u32) as usize;
@@ -1255,8 +1258,7 @@ pub(crate) fn for_each_bi_planar_rect(
     let plain1_bytes_per_line = size.width as usize * info.plane1_element_size as usize;
     util::io_skip_exact(r, plain1_bytes_per_line as u64 * rect.y as u64)?;
     let plane1_bytes = plain1_bytes_per_line * rect.height as usize;
-    let mut plane1 = context.alloc_capacity(plane1_bytes)?;
-    read_exact_into(r, &mut plane1, plane1_bytes)?;
+    let plane1 = read_plane(r, plane1_bytes, &mut context)?;
     util::io_skip_exact(
         r,
         plain1_bytes_per_line as u64 * (size.height - rect.y - rect.height) as u64,
@@ -1331,6 +1333,22 @@ pub(crate) fn for_each_bi_planar_rect(
     Ok(())
 }
 
+/// Reads an entire plane into scratch memory or a newly allocated buffer.
+fn read_plane<'s, R: Read + ?Sized>(
+    r: &mut R,
+    len: usize,
+    context: &mut DecodeContext<'s>,
+) -> Result<Buffer<'s>, DecodeError> {
+    if let Some(scratch) = context.take_scratch(len) {
+        r.read_exact(scratch)?;
+        return Ok(Buffer::Scratch(scratch));
+    }
+
+    let mut plane = context.alloc_capacity(len)?;
+    read_exact_into(r, &mut plane, len)?;
+    Ok(Buffer::Owned(plane.into_boxed_slice()))
+}
+
 fn read_exact_into<R: Read + ?Sized>(
     r: &mut R,
     buf: &mut Vec<u8>,
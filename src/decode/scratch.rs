@@ -0,0 +1,99 @@
+use crate::{util::div_ceil, Format, PixelInfo, Size};
+
+use super::read_write::line_buffer_len;
+
+/// Reusable memory for intermediate buffers used while decoding.
+///
+/// Decoding most formats requires buffers for encoded data read from the
+/// reader. By default, these buffers are allocated for every call. When
+/// decoding many surfaces (e.g. batch-processing thousands of textures), a
+/// single `DecodeScratch` can be created once and passed to
+/// [`decode_with_scratch`](crate::decode_with_scratch) to reuse the same
+/// memory for all calls.
+///
+/// The scratch memory grows as needed and never shrinks. Use
+/// [`DecodeScratch::required_bytes`] to allocate enough memory up front.
+///
+/// ```
+/// # use dds::*;
+/// let size = Size::new(64, 64);
+/// let format = Format::BC1_UNORM;
+/// let mut scratch = DecodeScratch::with_capacity(DecodeScratch::required_bytes(format, size));
+///
+/// let data = vec![0_u8; 64 * 64 / 2];
+/// let mut pixels = vec![0_u8; 64 * 64 * 4];
+/// for _ in 0..3 {
+///     let image = ImageViewMut::new(pixels.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+///     let options = DecodeOptions::default();
+///     decode_with_scratch(&mut data.as_slice(), image, format, &options, &mut scratch).unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DecodeScratch {
+    buffer: Vec<u8>,
+}
+impl DecodeScratch {
+    /// Creates empty scratch memory. This does not allocate.
+    pub const fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+    /// Creates scratch memory with room for at least the given number of
+    /// bytes.
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self {
+            buffer: vec![0; bytes],
+        }
+    }
+
+    /// Returns the number of bytes of scratch memory currently allocated.
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns the number of bytes of scratch memory needed to decode a
+    /// surface of the given format and size without allocating.
+    ///
+    /// This is an upper bound for [`decode_rect`](crate::decode_rect) as well.
+    /// Uncompressed formats only need scratch memory for
+    /// [`decode_rect`](crate::decode_rect). Tone mapping, color adjustment,
+    /// progress reporting, and [`DecodeOptions::truncated_fill`](crate::DecodeOptions::truncated_fill)
+    /// may still allocate additional buffers.
+    pub fn required_bytes(format: Format, size: Size) -> usize {
+        if size.is_empty() {
+            return 0;
+        }
+
+        let width = size.width as usize;
+        let height = size.height as usize;
+        match PixelInfo::from(format) {
+            PixelInfo::Fixed { bytes_per_pixel } => width.saturating_mul(bytes_per_pixel as usize),
+            PixelInfo::Block(block) => {
+                let (block_width, block_height) = block.size();
+                let bytes_per_line = (div_ceil(size.width, block_width as u32) as usize)
+                    .saturating_mul(block.bytes_per_block() as usize);
+                let lines = div_ceil(size.height, block_height as u32) as usize;
+                line_buffer_len(bytes_per_line, lines)
+            }
+            PixelInfo::BiPlanar(bi_planar) => {
+                let plane1 = width
+                    .saturating_mul(height)
+                    .saturating_mul(bi_planar.plane1_bytes_per_pixel() as usize);
+                let (sub_x, sub_y) = bi_planar.plane2_sub_sampling();
+                let bytes_per_line = (div_ceil(size.width, sub_x as u32) as usize)
+                    .saturating_mul(bi_planar.plane2_bytes_per_sample() as usize);
+                let lines = div_ceil(size.height, sub_y as u32) as usize;
+                plane1.saturating_add(line_buffer_len(bytes_per_line, lines))
+            }
+        }
+    }
+
+    /// Grows the scratch memory to the number of bytes needed for the given
+    /// surface and returns it. Memory is not grown beyond `memory_limit`.
+    pub(crate) fn prepare(&mut self, format: Format, size: Size, memory_limit: usize) -> &mut [u8] {
+        let required = Self::required_bytes(format, size);
+        if self.buffer.len() < required && required <= memory_limit {
+            self.buffer.resize(required, 0);
+        }
+        &mut self.buffer
+    }
+}
@@ -0,0 +1,58 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// A reader that implements forward seeking by reading and discarding bytes.
+///
+/// This makes readers without [`Seek`] (e.g. network streams or decompressing
+/// readers) usable for functions that only ever seek forward. Positions are
+/// relative to the position of the inner reader when this reader was created.
+/// Seeking backwards or relative to the end is not supported.
+pub(crate) struct SkipReader<R> {
+    inner: R,
+    pos: u64,
+}
+impl<R> SkipReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, pos: 0 }
+    }
+}
+impl<R: Read> Read for SkipReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+impl<R: Read> Seek for SkipReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(offset) => {
+                if offset >= 0 {
+                    self.pos.checked_add(offset as u64)
+                } else {
+                    self.pos.checked_sub(offset.unsigned_abs())
+                }
+            }
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek relative to the end of a non-seekable reader",
+                ))
+            }
+        };
+        let target = target
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+        if target < self.pos {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek backwards in a non-seekable reader",
+            ));
+        }
+
+        // A short skip means that EOF was reached. Like seeking past the end
+        // of a file, this isn't an error, so we report the actual position.
+        let mut skipped = (&mut self.inner).take(target - self.pos);
+        self.pos += io::copy(&mut skipped, &mut io::sink())?;
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,23 @@
+//! Helpers for decoding images in horizontal strips through a small
+//! intermediate buffer.
+
+use crate::{util::div_ceil, PixelInfo};
+
+/// The approximate number of bytes used for intermediate buffers.
+const TARGET_BUFFER_BYTES: usize = 64 * 1024;
+
+pub(crate) fn block_height(pixel_info: PixelInfo) -> u32 {
+    match pixel_info {
+        PixelInfo::Block(block) => block.size().1 as u32,
+        _ => 1,
+    }
+}
+
+/// Returns the number of rows per strip such that a strip of the given width
+/// takes up roughly [`TARGET_BUFFER_BYTES`] bytes. The result is always a
+/// non-zero multiple of `block_height`.
+pub(crate) fn strip_height(width: u32, bytes_per_pixel: usize, block_height: u32) -> u32 {
+    let row_bytes = (width as usize * bytes_per_pixel).max(1);
+    let rows = (TARGET_BUFFER_BYTES / row_bytes).max(1) as u32;
+    div_ceil(rows, block_height) * block_height
+}
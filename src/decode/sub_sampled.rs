@@ -140,6 +140,11 @@ pub(crate) const YUY2: DecoderSet = DecoderSet::new(&[
     rgb!(u16, |pair| decode_yuv2(pair, yuv8::n16)),
     rgb!(f32, |pair| decode_yuv2(pair, yuv8::f32)),
 ]);
+pub(crate) const YUY2_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u8, |pair| decode_yuv2(pair, yuv8::raw_n8)),
+    rgb!(u16, |pair| decode_yuv2(pair, yuv8::raw_n16)),
+    rgb!(f32, |pair| decode_yuv2(pair, yuv8::raw_f32)),
+]);
 
 #[inline]
 fn decode_uyvy<T>([u0, y0, v0, y1]: [u8; 4], decode: impl Fn([u8; 3]) -> T) -> [T; 2] {
@@ -150,6 +155,11 @@ pub(crate) const UYVY: DecoderSet = DecoderSet::new(&[
     rgb!(u16, |pair| decode_uyvy(pair, yuv8::n16)),
     rgb!(f32, |pair| decode_uyvy(pair, yuv8::f32)),
 ]);
+pub(crate) const UYVY_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u8, |pair| decode_uyvy(pair, yuv8::raw_n8)),
+    rgb!(u16, |pair| decode_uyvy(pair, yuv8::raw_n16)),
+    rgb!(f32, |pair| decode_uyvy(pair, yuv8::raw_f32)),
+]);
 
 #[inline]
 fn decode_y210<T>(block: [u8; 8], decode: impl Fn([u16; 3]) -> T) -> [T; 2] {
@@ -162,6 +172,11 @@ pub(crate) const Y210: DecoderSet = DecoderSet::new(&[
     rgb!(f32, 8, |pair| decode_y210(pair, yuv10::f32)),
     rgb!(u8, 8, |pair| decode_y210(pair, yuv10::n8)),
 ]);
+pub(crate) const Y210_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u16, 8, |pair| decode_y210(pair, yuv10::raw_n16)),
+    rgb!(f32, 8, |pair| decode_y210(pair, yuv10::raw_f32)),
+    rgb!(u8, 8, |pair| decode_y210(pair, yuv10::raw_n8)),
+]);
 
 #[inline]
 fn decode_y216<T>(block: [u8; 8], decode: impl Fn([u16; 3]) -> T) -> [T; 2] {
@@ -173,6 +188,11 @@ pub(crate) const Y216: DecoderSet = DecoderSet::new(&[
     rgb!(f32, 8, |pair| decode_y216(pair, yuv16::f32)),
     rgb!(u8, 8, |pair| decode_y216(pair, yuv16::n8)),
 ]);
+pub(crate) const Y216_RAW: DecoderSet = DecoderSet::new(&[
+    rgb!(u16, 8, |pair| decode_y216(pair, yuv16::raw_n16)),
+    rgb!(f32, 8, |pair| decode_y216(pair, yuv16::raw_f32)),
+    rgb!(u8, 8, |pair| decode_y216(pair, yuv16::raw_n8)),
+]);
 
 #[inline]
 fn r1_bits(bits: u8) -> [u8; 8] {
@@ -0,0 +1,298 @@
+use std::io::{Read, SeekFrom};
+
+use crate::{
+    cast, color::fp, Channels, ColorAdjustment, ColorFormat, DecodeError, Format, ImageViewMut,
+    PixelInfo, Precision, Rect, Size, ToneMapCurve, ToneMapping,
+};
+
+use super::strip::{block_height, strip_height};
+use super::{get_decoders, get_decoders_with, DecodeContext, DecodeOptions, RArgs, ReadSeek};
+
+/// Returns whether tone mapping has to be applied when decoding the given
+/// format into the given color format.
+fn is_tone_mapped(format: Format, color: ColorFormat, options: &DecodeOptions) -> bool {
+    !options.tone_mapping.is_none()
+        && color.precision != Precision::F32
+        && color.channels != Channels::Alpha
+        && get_decoders(format).native_color().precision == Precision::F32
+}
+/// Returns whether the given format has to be decoded via f32 to apply tone
+/// mapping or color adjustments.
+pub(crate) fn uses_f32_pipeline(
+    format: Format,
+    color: ColorFormat,
+    options: &DecodeOptions,
+) -> bool {
+    let adjusted = !options.color_adjustment.is_none() && color.channels != Channels::Alpha;
+    adjusted || is_tone_mapped(format, color, options)
+}
+
+pub(crate) fn decode_tone_mapped(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    let size = image.size();
+    let color = image.color();
+    let decoders = get_decoders_with(format, options);
+
+    if size.is_empty() {
+        return Ok(());
+    }
+
+    // Most formats store their rows (or rows of blocks) sequentially, so the
+    // image can be decoded in strips. Bi-planar formats cannot, so they are
+    // decoded in one go.
+    let rows_per_strip = match PixelInfo::from(format) {
+        PixelInfo::BiPlanar(_) => size.height,
+        pixel_info => strip_height(
+            size.width,
+            f32_bytes_per_pixel(color),
+            block_height(pixel_info),
+        ),
+    };
+
+    let (mut buffer, options) = alloc_strip_buffer(size.width, rows_per_strip, color, options)?;
+    let f32_color = ColorFormat::new(color.channels, Precision::F32);
+    let row_pitch = image.row_pitch();
+
+    let mut y = 0;
+    while y < size.height {
+        let rows = rows_per_strip.min(size.height - y);
+        let strip_size = Size::new(size.width, rows);
+        let strip_len = strip_size.pixels() as usize * color.channels.count() as usize;
+        let strip = &mut buffer[..strip_len];
+
+        let strip_view = ImageViewMut::new(strip, strip_size, f32_color).expect("invalid strip");
+        decoders.decode(reader, strip_view, &options)?;
+
+        let out_start = y as usize * row_pitch;
+        let out = &mut image.data[out_start..out_start + rows as usize * row_pitch];
+        apply(format, color, &options, strip, out);
+
+        y += rows;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn decode_rect_tone_mapped(
+    reader: &mut dyn ReadSeek,
+    output: &mut [u8],
+    row_pitch: usize,
+    color: ColorFormat,
+    size: Size,
+    rect: Rect,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    // validate the arguments against the user's buffer
+    RArgs::new(
+        reader,
+        output,
+        row_pitch,
+        rect,
+        DecodeContext {
+            color,
+            size,
+            memory_limit: options.memory_limit,
+            scratch: &mut [],
+        },
+    )?;
+
+    // never decode empty rectangles
+    if rect.size().is_empty() {
+        return Ok(());
+    }
+
+    let block_height = block_height(PixelInfo::from(format));
+    let rows_per_strip = strip_height(rect.width, f32_bytes_per_pixel(color), block_height);
+    let (mut buffer, options) = alloc_strip_buffer(rect.width, rows_per_strip, color, options)?;
+
+    let start = reader.stream_position()?;
+
+    let mut y = 0;
+    while y < rect.height {
+        let rows = rows_per_strip.min(rect.height - y);
+        let strip_rect = Rect::new(rect.x, rect.y + y, rect.width, rows);
+
+        reader.seek(SeekFrom::Start(start))?;
+        let out_start = y as usize * row_pitch;
+        decode_rect_strip(
+            reader,
+            &mut buffer,
+            &mut output[out_start..],
+            row_pitch,
+            color,
+            size,
+            strip_rect,
+            format,
+            &options,
+        )?;
+
+        y += rows;
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn decode_rect_strip(
+    reader: &mut dyn ReadSeek,
+    buffer: &mut [f32],
+    output: &mut [u8],
+    row_pitch: usize,
+    color: ColorFormat,
+    size: Size,
+    rect: Rect,
+    format: Format,
+    options: &DecodeOptions,
+) -> Result<(), DecodeError> {
+    let f32_color = ColorFormat::new(color.channels, Precision::F32);
+    let row_values = rect.width as usize * color.channels.count() as usize;
+    let buffer = &mut buffer[..row_values * rect.height as usize];
+
+    get_decoders_with(format, options).decode_rect(
+        f32_color,
+        reader,
+        size,
+        rect,
+        cast::as_bytes_mut(buffer),
+        row_values * 4,
+        options,
+    )?;
+
+    let row_bytes = rect.width as usize * color.bytes_per_pixel() as usize;
+    for (src, dest) in buffer
+        .chunks_exact(row_values.max(1))
+        .zip(output.chunks_mut(row_pitch))
+    {
+        apply(format, color, options, src, &mut dest[..row_bytes]);
+    }
+
+    Ok(())
+}
+
+fn f32_bytes_per_pixel(color: ColorFormat) -> usize {
+    color.channels.count() as usize * 4
+}
+
+/// Allocates the intermediate buffer for the given number of rows. The
+/// returned options have their memory limit reduced by the size of the
+/// buffer. Tone mapping and color adjustments are kept, since the caller
+/// applies them.
+fn alloc_strip_buffer(
+    width: u32,
+    rows: u32,
+    color: ColorFormat,
+    options: &DecodeOptions,
+) -> Result<(Vec<f32>, DecodeOptions), DecodeError> {
+    let len = width as usize * rows as usize * color.channels.count() as usize;
+    let bytes = len.saturating_mul(4);
+    if bytes > options.memory_limit {
+        return Err(DecodeError::MemoryLimitExceeded);
+    }
+
+    let mut options = options.clone();
+    options.memory_limit -= bytes;
+    Ok((vec![0.0; len], options))
+}
+
+/// Applies color adjustments and tone mapping to the given f32 values and
+/// writes them into `output` in the precision of `color`.
+fn apply(
+    format: Format,
+    color: ColorFormat,
+    options: &DecodeOptions,
+    input: &[f32],
+    output: &mut [u8],
+) {
+    let channels = color.channels.count() as usize;
+    let has_alpha = color.channels == Channels::Rgba;
+    let adjustment = if color.channels == Channels::Alpha {
+        ColorAdjustment::NONE
+    } else {
+        options.color_adjustment
+    };
+    // formats that aren't tone mapped are simply clamped
+    let tone_mapping = if is_tone_mapped(format, color, options) {
+        options.tone_mapping
+    } else {
+        ToneMapping::NONE
+    };
+
+    let map = |i: usize, value: f32| -> f32 {
+        if has_alpha && i % channels == 3 {
+            value
+        } else {
+            tone_mapping.map(adjustment.apply(value))
+        }
+    };
+
+    match color.precision {
+        Precision::U8 => {
+            debug_assert_eq!(input.len(), output.len());
+            for (i, (&value, out)) in input.iter().zip(output.iter_mut()).enumerate() {
+                *out = fp::n8(map(i, value));
+            }
+        }
+        Precision::U16 => {
+            debug_assert_eq!(input.len() * 2, output.len());
+            for (i, (&value, out)) in input.iter().zip(output.chunks_exact_mut(2)).enumerate() {
+                out.copy_from_slice(&fp::n16(map(i, value)).to_ne_bytes());
+            }
+        }
+        Precision::U32 => {
+            debug_assert_eq!(input.len() * 4, output.len());
+            for (i, (&value, out)) in input.iter().zip(output.chunks_exact_mut(4)).enumerate() {
+                out.copy_from_slice(&fp::n32(map(i, value)).to_ne_bytes());
+            }
+        }
+        Precision::F32 => {
+            // f32 outputs are never tone mapped
+            debug_assert_eq!(input.len() * 4, output.len());
+            for (i, (&value, out)) in input.iter().zip(output.chunks_exact_mut(4)).enumerate() {
+                let value = if has_alpha && i % channels == 3 {
+                    value
+                } else {
+                    adjustment.apply(value)
+                };
+                out.copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+    }
+}
+
+impl ColorAdjustment {
+    fn apply(&self, value: f32) -> f32 {
+        let x = value * self.exposure;
+        if self.gamma == 1.0 {
+            x
+        } else {
+            // keep the sign of negative values (e.g. from SNORM and HDR formats)
+            x.signum() * x.abs().powf(1.0 / self.gamma)
+        }
+    }
+}
+
+impl ToneMapping {
+    fn map(&self, value: f32) -> f32 {
+        let x = (value * self.exposure).max(0.0);
+        match self.curve {
+            ToneMapCurve::Clamp => x,
+            ToneMapCurve::Reinhard => x / (1.0 + x),
+            ToneMapCurve::Aces => {
+                // Krzysztof Narkowicz's fit of the ACES filmic curve
+                // https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+                const A: f32 = 2.51;
+                const B: f32 = 0.03;
+                const C: f32 = 2.43;
+                const D: f32 = 0.59;
+                const E: f32 = 0.14;
+                (x * (A * x + B)) / (x * (C * x + D) + E)
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::{ErrorKind, Read};
+
+use crate::{
+    cast, color::fp, convert_channels_for, ColorFormat, DecodeError, DecodeProgress, Format,
+    ImageViewMut, PixelInfo, Precision, Size,
+};
+
+use super::strip::{block_height, strip_height};
+use super::DecodeOptions;
+
+/// Decodes the given image in strips. If the reader runs out of data, all
+/// complete rows are decoded and the remaining rows are filled with the
+/// given color.
+///
+/// Progress is reported after each strip.
+pub(crate) fn decode_truncated(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    format: Format,
+    options: &DecodeOptions,
+    fill: [f32; 4],
+    decode: impl Fn(&mut dyn Read, ImageViewMut, &DecodeOptions) -> Result<(), DecodeError>,
+) -> Result<DecodeProgress, DecodeError> {
+    let size = image.size();
+    let color = image.color();
+    let row_pitch = image.row_pitch();
+    let pixel_info = PixelInfo::from(format);
+    let strip_options = options.without_progress();
+
+    let block_height = block_height(pixel_info);
+    let rows_per_strip = match pixel_info {
+        // bi-planar formats cannot be decoded in strips
+        PixelInfo::BiPlanar(_) => size.height.max(1),
+        _ => strip_height(size.width, color.bytes_per_pixel() as usize, block_height),
+    };
+    // the number of encoded bytes of one row of blocks
+    let block_row_bytes = match pixel_info {
+        PixelInfo::BiPlanar(_) => None,
+        _ => pixel_info.surface_bytes(Size::new(size.width, block_height)),
+    };
+
+    let mut buffer = Vec::new();
+    let mut y = 0;
+    while y < size.height {
+        options.check_cancelled()?;
+
+        let rows = rows_per_strip.min(size.height - y);
+        let strip_len = pixel_info
+            .surface_bytes(Size::new(size.width, rows))
+            .and_then(|len| usize::try_from(len).ok())
+            .filter(|&len| len <= options.memory_limit)
+            .ok_or(DecodeError::MemoryLimitExceeded)?;
+        buffer.resize(strip_len, 0);
+        let read = read_until_eof(reader, &mut buffer)?;
+
+        let complete_rows = if read == strip_len {
+            rows
+        } else {
+            match block_row_bytes {
+                Some(bytes) if bytes > 0 => {
+                    let blocks = read as u64 / bytes;
+                    (blocks.min(u32::MAX as u64) as u32 * block_height).min(rows)
+                }
+                _ => 0,
+            }
+        };
+
+        if complete_rows > 0 {
+            let strip_bytes = if complete_rows == rows {
+                strip_len
+            } else {
+                (complete_rows / block_height) as usize
+                    * block_row_bytes.unwrap_or_default() as usize
+            };
+            let strip =
+                &mut image.data[y as usize * row_pitch..][..complete_rows as usize * row_pitch];
+            let strip_size = Size::new(size.width, complete_rows);
+            let strip_view = ImageViewMut::new(strip, strip_size, color).expect("invalid strip");
+            decode(&mut &buffer[..strip_bytes], strip_view, &strip_options)?;
+        }
+
+        y += complete_rows;
+        options.report_progress(y, size.height);
+
+        if complete_rows < rows {
+            fill_rows(&mut image.data[y as usize * row_pitch..], color, fill);
+            break;
+        }
+    }
+
+    Ok(DecodeProgress {
+        decoded_rows: y,
+        total_rows: size.height,
+    })
+}
+
+/// Reads into the given buffer until it is full or the reader reaches EOF.
+/// Returns the number of bytes read.
+fn read_until_eof(reader: &mut dyn Read, buffer: &mut [u8]) -> Result<usize, DecodeError> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(read)
+}
+
+/// Fills the given buffer with the given RGBA color converted to the given
+/// color format.
+pub(crate) fn fill_rows(buffer: &mut [u8], color: ColorFormat, fill: [f32; 4]) {
+    let channels = color.channels.count() as usize;
+    let mut values = [0_f32; 4];
+    convert_channels_for(
+        ColorFormat::RGBA_F32,
+        color.channels,
+        cast::as_bytes(&fill),
+        cast::as_bytes_mut(&mut values[..channels]),
+    );
+
+    let mut pixel = [0_u8; 16];
+    let pixel_len = color.bytes_per_pixel() as usize;
+    for (i, &value) in values[..channels].iter().enumerate() {
+        match color.precision {
+            Precision::U8 => pixel[i] = fp::n8(value),
+            Precision::U16 => pixel[i * 2..][..2].copy_from_slice(&fp::n16(value).to_ne_bytes()),
+            Precision::U32 => pixel[i * 4..][..4].copy_from_slice(&fp::n32(value).to_ne_bytes()),
+            Precision::F32 => pixel[i * 4..][..4].copy_from_slice(&value.to_ne_bytes()),
+        }
+    }
+
+    for chunk in buffer.chunks_exact_mut(pixel_len) {
+        chunk.copy_from_slice(&pixel[..pixel_len]);
+    }
+}
@@ -4,8 +4,8 @@ use super::read_write::{
 };
 use super::{Args, DecodeFn, Decoder, DecoderSet, RArgs};
 use crate::{
-    cast, fp, fp10, fp11, fp16, n10, n16, n2, n4, n8, rgb9995f, s16, s8, xr10, yuv10, yuv16, yuv8,
-    Norm, SwapRB, ToRgba, WithPrecision, B5G5R5A1, B5G6R5,
+    fp, fp10, fp11, fp16, n10, n16, n2, n4, n8, rgb9995f, s16, s8, xr10, yuv10, yuv16, yuv8, Norm,
+    SwapRB, ToRgba, WithPrecision, B5G5R5A1, B5G6R5,
 };
 
 use crate::util::closure_types;
@@ -93,24 +93,14 @@ macro_rules! rgba {
 
 // Dedicated (whole-image) decoding functions.
 //
-// Some formats allow us to basically just memcpy the data into the output
-// buffer. This allows for some very efficient decoding.
+// Some formats allow us to basically just memcpy (and maybe slightly modify)
+// the data into the output buffer. This allows for some very efficient
+// decoding.
 // Note that this is only an optimization, and not required for correctness.
 
-const COPY_U8: DecodeFn = |Args(r, out, _)| {
-    r.read_exact(out)?;
-    Ok(())
-};
-const COPY_U16: DecodeFn = |Args(r, out, _)| {
-    r.read_exact(out)?;
-    cast::slice_le_to_ne_16(out);
-    Ok(())
-};
-const COPY_U32: DecodeFn = |Args(r, out, _)| {
-    r.read_exact(out)?;
-    cast::slice_le_to_ne_32(out);
-    Ok(())
-};
+// Formats whose encoded pixels are identical to the decoded pixels are marked
+// with `add_zero_copy`. See `DecoderSet::add_zero_copy`.
+
 const COPY_S8: DecodeFn = |Args(r, out, _)| {
     r.read_exact(out)?;
     out.iter_mut().for_each(|v| *v = s8::n8(*v));
@@ -167,7 +157,7 @@ pub(crate) const R8G8B8_UNORM: DecoderSet = DecoderSet::new(&[
     rgb!(u16, [u8; 3], process_fn = N8_TO_U16),
     rgb!(f32, [u8; 3], process_fn = N8_TO_F32),
 ])
-.add_specialized(Rgb, U8, COPY_U8);
+.add_zero_copy(Rgb, U8);
 
 pub(crate) const B8G8R8_UNORM: DecoderSet = DecoderSet::new(&[
     rgb!(u8, [u8; 3], |bgr| bgr.swap_rb()),
@@ -180,7 +170,7 @@ pub(crate) const R8G8B8A8_UNORM: DecoderSet = DecoderSet::new(&[
     rgba!(u16, [u8; 4], process_fn = N8_TO_U16),
     rgba!(f32, [u8; 4], process_fn = N8_TO_F32),
 ])
-.add_specialized(Rgba, U8, COPY_U8);
+.add_zero_copy(Rgba, U8);
 
 pub(crate) const R8G8B8A8_SNORM: DecoderSet = DecoderSet::new(&[
     rgba!(u8, [u8; 4], process_fn = S8_TO_U8),
@@ -216,15 +206,24 @@ pub(crate) const B8G8R8X8_UNORM: DecoderSet = DecoderSet::new(&[
     rgba!(u8, [u8; 4], |bgrx| bgrx_to_rgb(bgrx).to_rgba()),
 ]);
 
+// 5-, 6-, and 10-bit formats convert whole rows with dedicated functions.
+// Some of them go through lookup tables. See `color/formats.rs` for details.
+
+const B5G6R5_TO_U8: ProcessPixelsFn = create!(|[bgr]: [u16; 1]| B5G6R5::from_u16(bgr).to_n8());
+const B5G6R5_TO_U16: ProcessPixelsFn = create!(|[bgr]: [u16; 1]| B5G6R5::from_u16(bgr).to_n16());
 pub(crate) const B5G6R5_UNORM: DecoderSet = DecoderSet::new(&[
-    rgb!(u8, [u16; 1], |[bgr]| B5G6R5::from_u16(bgr).to_n8()),
-    rgb!(u16, [u16; 1], |[bgr]| B5G6R5::from_u16(bgr).to_n16()),
+    rgb!(u8, [u16; 1], process_fn = B5G6R5_TO_U8),
+    rgb!(u16, [u16; 1], process_fn = B5G6R5_TO_U16),
     rgb!(f32, [u16; 1], |[bgr]| B5G6R5::from_u16(bgr).to_f32()),
 ]);
 
+const B5G5R5A1_TO_U8: ProcessPixelsFn =
+    create!(|[bgra]: [u16; 1]| B5G5R5A1::from_u16(bgra).to_n8());
+const B5G5R5A1_TO_U16: ProcessPixelsFn =
+    create!(|[bgra]: [u16; 1]| B5G5R5A1::from_u16(bgra).to_n16());
 pub(crate) const B5G5R5A1_UNORM: DecoderSet = DecoderSet::new(&[
-    rgba!(u8, [u16; 1], |[bgra]| B5G5R5A1::from_u16(bgra).to_n8()),
-    rgba!(u16, [u16; 1], |[bgra]| B5G5R5A1::from_u16(bgra).to_n16()),
+    rgba!(u8, [u16; 1], process_fn = B5G5R5A1_TO_U8),
+    rgba!(u16, [u16; 1], process_fn = B5G5R5A1_TO_U16),
     rgba!(f32, [u16; 1], |[bgra]| B5G5R5A1::from_u16(bgra).to_f32()),
 ]);
 
@@ -263,7 +262,7 @@ pub(crate) const R8_UNORM: DecoderSet = DecoderSet::new(&[
     gray!(u16, [u8; 1], process_fn = N8_TO_U16),
     gray!(f32, [u8; 1], process_fn = N8_TO_F32),
 ])
-.add_specialized(Grayscale, U8, COPY_U8);
+.add_zero_copy(Grayscale, U8);
 
 pub(crate) const R8_SNORM: DecoderSet = DecoderSet::new(&[
     gray!(u8, [u8; 1], process_fn = S8_TO_U8),
@@ -293,14 +292,14 @@ pub(crate) const A8_UNORM: DecoderSet = DecoderSet::new(&[
     alpha!(u16, [u8; 1], process_fn = N8_TO_U16),
     alpha!(f32, [u8; 1], process_fn = N8_TO_F32),
 ])
-.add_specialized(Alpha, U8, COPY_U8);
+.add_zero_copy(Alpha, U8);
 
 pub(crate) const R16_UNORM: DecoderSet = DecoderSet::new(&[
     gray!(u16, [u16; 1], process_fn = N16_TO_U16),
     gray!(u8, [u16; 1], process_fn = N16_TO_U8),
     gray!(f32, [u16; 1], process_fn = N16_TO_F32),
 ])
-.add_specialized(Grayscale, U16, COPY_U16);
+.add_zero_copy(Grayscale, U16);
 
 pub(crate) const R16_SNORM: DecoderSet = DecoderSet::new(&[
     gray!(u16, [u16; 1], process_fn = S16_TO_U16),
@@ -333,7 +332,7 @@ pub(crate) const R16G16B16A16_UNORM: DecoderSet = DecoderSet::new(&[
     rgba!(u8, [u16; 4], process_fn = N16_TO_U8),
     rgba!(f32, [u16; 4], process_fn = N16_TO_F32),
 ])
-.add_specialized(Rgba, U16, COPY_U16);
+.add_zero_copy(Rgba, U16);
 
 pub(crate) const R16G16B16A16_SNORM: DecoderSet = DecoderSet::new(&[
     rgba!(u16, [u16; 4], process_fn = S16_TO_U16),
@@ -349,15 +348,22 @@ fn unpack_rgba1010102(rgba: u32) -> (u16, u16, u16, u8) {
     let a2 = (rgba >> 30) & 0x3;
     (r10 as u16, g10 as u16, b10 as u16, a2 as u8)
 }
+const R10G10B10A2_TO_U8: ProcessPixelsFn = create!(|[rgba]: [u32; 1]| {
+    let (r, g, b, a) = unpack_rgba1010102(rgba);
+    [n10::n8(r), n10::n8(g), n10::n8(b), n2::n8(a)]
+});
+const R10G10B10A2_TO_U16: ProcessPixelsFn = create!(|[rgba]: [u32; 1]| {
+    let (r, g, b, a) = unpack_rgba1010102(rgba);
+    [
+        n10::N16_LUT[r as usize],
+        n10::N16_LUT[g as usize],
+        n10::N16_LUT[b as usize],
+        n2::n16(a),
+    ]
+});
 pub(crate) const R10G10B10A2_UNORM: DecoderSet = DecoderSet::new(&[
-    rgba!(u16, [u32; 1], |[rgba]| {
-        let (r, g, b, a) = unpack_rgba1010102(rgba);
-        [n10::n16(r), n10::n16(g), n10::n16(b), n2::n16(a)]
-    }),
-    rgba!(u8, [u32; 1], |[rgba]| {
-        let (r, g, b, a) = unpack_rgba1010102(rgba);
-        [n10::n8(r), n10::n8(g), n10::n8(b), n2::n8(a)]
-    }),
+    rgba!(u16, [u32; 1], process_fn = R10G10B10A2_TO_U16),
+    rgba!(u8, [u32; 1], process_fn = R10G10B10A2_TO_U8),
     rgba!(f32, [u32; 1], |[rgba]| {
         let (r, g, b, a) = unpack_rgba1010102(rgba);
         [n10::f32(r), n10::f32(g), n10::f32(b), n2::f32(a)]
@@ -415,7 +421,7 @@ pub(crate) const R32_FLOAT: DecoderSet = DecoderSet::new(&[
     gray!(u8, [f32; 1], process_fn = F32_TO_U8),
     gray!(u16, [f32; 1], process_fn = F32_TO_U16),
 ])
-.add_specialized(Grayscale, F32, COPY_U32);
+.add_zero_copy(Grayscale, F32);
 
 pub(crate) const R32G32_FLOAT: DecoderSet = DecoderSet::new(&[
     rgb!(f32, [f32; 2], |[r, g]| [r, g, 0.0]),
@@ -428,14 +434,14 @@ pub(crate) const R32G32B32_FLOAT: DecoderSet = DecoderSet::new(&[
     rgb!(u8, [f32; 3], process_fn = F32_TO_U8),
     rgb!(u16, [f32; 3], process_fn = F32_TO_U16),
 ])
-.add_specialized(Rgb, F32, COPY_U32);
+.add_zero_copy(Rgb, F32);
 
 pub(crate) const R32G32B32A32_FLOAT: DecoderSet = DecoderSet::new(&[
     rgba!(f32, [f32; 4], process_fn = F32_TO_F32),
     rgba!(u8, [f32; 4], process_fn = F32_TO_U8),
     rgba!(u16, [f32; 4], process_fn = F32_TO_U16),
 ])
-.add_specialized(Rgba, F32, COPY_U32);
+.add_zero_copy(Rgba, F32);
 
 #[inline(always)]
 fn unpack_rgba1010102_xr(rgba: u32) -> ([u16; 3], u8) {
@@ -478,6 +484,19 @@ pub(crate) const AYUV: DecoderSet = DecoderSet::new(&[
     rgba!(u16, [u8; 4], |ayuv| unpack_ayuv(ayuv, yuv8::n16, n8::n16)),
     rgba!(f32, [u8; 4], |ayuv| unpack_ayuv(ayuv, yuv8::f32, n8::f32)),
 ]);
+pub(crate) const AYUV_RAW: DecoderSet = DecoderSet::new(&[
+    rgba!(u8, [u8; 4], |ayuv| unpack_ayuv(ayuv, yuv8::raw_n8, |x| x)),
+    rgba!(u16, [u8; 4], |ayuv| unpack_ayuv(
+        ayuv,
+        yuv8::raw_n16,
+        n8::n16
+    )),
+    rgba!(f32, [u8; 4], |ayuv| unpack_ayuv(
+        ayuv,
+        yuv8::raw_f32,
+        n8::f32
+    )),
+]);
 
 fn unpack_y410<T>(
     y410: u32,
@@ -493,6 +512,11 @@ pub(crate) const Y410: DecoderSet = DecoderSet::new(&[
     rgba!(f32, u32, |y410| unpack_y410(y410, yuv10::f32, n2::f32)),
     rgba!(u8, u32, |y410| unpack_y410(y410, yuv10::n8, n2::n8)),
 ]);
+pub(crate) const Y410_RAW: DecoderSet = DecoderSet::new(&[
+    rgba!(u16, u32, |y410| unpack_y410(y410, yuv10::raw_n16, n2::n16)),
+    rgba!(f32, u32, |y410| unpack_y410(y410, yuv10::raw_f32, n2::f32)),
+    rgba!(u8, u32, |y410| unpack_y410(y410, yuv10::raw_n8, n2::n8)),
+]);
 
 fn unpack_y416<T>(
     y416: [u16; 4],
@@ -512,3 +536,20 @@ pub(crate) const Y416: DecoderSet = DecoderSet::new(&[
     )),
     rgba!(u8, [u16; 4], |y416| unpack_y416(y416, yuv16::n8, n16::n8)),
 ]);
+pub(crate) const Y416_RAW: DecoderSet = DecoderSet::new(&[
+    rgba!(u16, [u16; 4], |y416| unpack_y416(
+        y416,
+        yuv16::raw_n16,
+        |x| x
+    )),
+    rgba!(f32, [u16; 4], |y416| unpack_y416(
+        y416,
+        yuv16::raw_f32,
+        n16::f32
+    )),
+    rgba!(u8, [u16; 4], |y416| unpack_y416(
+        y416,
+        yuv16::raw_n8,
+        n16::n8
+    )),
+]);
@@ -1,10 +1,22 @@
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, SeekFrom};
+use std::mem::size_of;
+use std::num::NonZeroU32;
+
+#[cfg(feature = "async")]
+mod async_io;
 
 use crate::{
-    decode, decode_rect,
-    header::{Header, ParseOptions},
+    decode,
+    decode::{fill_rows, LegacyBlockReader, PaddedRowReader, SurfaceReader},
+    decode_partial, decode_rect, decode_rect_sequential, decode_with_scratch,
+    header::{
+        AlphaMode, DdsFlags, DdsWriter, Header, HeaderFix, ParseOptions, RawHeader, Reserved1Tag,
+    },
     iter::{SurfaceInfo, SurfaceIterator},
-    util, ColorFormat, DataLayout, DecodeError, DecodeOptions, Format, ImageViewMut, Rect, Size,
+    layout::legacy_block_count,
+    util, AsBytes, ColorFormat, CubeAtlas, CubeAtlasLayout, CubeFace, DataLayout, DataRegion,
+    DecodeError, DecodeOptions, DecodeProgress, DecodeScratch, Format, ImageView, ImageViewMut,
+    MipOrder, PixelInfo, Rect, Size, SurfaceLocation, Texture, WithPrecision,
 };
 
 /// Information about the header, pixel format, and data layout of a DDS file.
@@ -17,6 +29,11 @@ pub struct DdsInfo {
     header: Header,
     format: Format,
     layout: DataLayout,
+    mip_order: MipOrder,
+    cube_atlas_hint: Option<CubeAtlasLayout>,
+    reserved1_tag: Option<Reserved1Tag>,
+    writer: DdsWriter,
+    fixes: Vec<HeaderFix>,
 }
 
 impl DdsInfo {
@@ -35,8 +52,83 @@ impl DdsInfo {
         r: &mut R,
         options: &ParseOptions,
     ) -> Result<Self, DecodeError> {
-        let header = Header::read(r, options)?;
-        Self::new(header)
+        if !options.skip_magic_bytes {
+            Header::read_magic(r)?;
+        }
+        let raw = RawHeader::read(r)?;
+        let (header, fixes) = Header::from_raw_with_fixes(&raw, options)?;
+
+        let mut info = Self::new(header)?
+            .with_mip_order(MipOrder::from_raw_header(&raw))
+            .with_cube_atlas_hint(CubeAtlasLayout::from_raw_header(&raw))
+            .with_reserved1_tag(Reserved1Tag::from_raw_header(&raw))
+            .with_writer(raw.detect_writer());
+        info.fixes = fixes;
+
+        if options.permissive {
+            if let Some(row_alignment) = info.detect_row_alignment(&raw, options.file_len) {
+                info = info.with_row_alignment(row_alignment)?;
+                // the declared pitch is correct for padded rows
+                info.fixes
+                    .retain(|fix| !matches!(fix, HeaderFix::PitchOrLinearSize { .. }));
+            }
+            if info.detect_legacy_block_sizes(options.file_len) {
+                info = info.with_legacy_block_sizes()?;
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Detects whether the rows of the uncompressed surfaces of the file are
+    /// padded.
+    ///
+    /// Padding is detected if the pitch declared in the header is the row
+    /// length rounded up to a multiple of a power of 2 and the data section
+    /// is long enough to hold the padded surfaces. Many writers declare a DWORD-aligned
+    /// pitch while storing rows tightly, so the length of the file is
+    /// required to tell the two apart.
+    fn detect_row_alignment(&self, raw: &RawHeader, file_len: Option<u64>) -> Option<NonZeroU32> {
+        let bytes_per_pixel = match PixelInfo::from(self.format) {
+            PixelInfo::Fixed { bytes_per_pixel } => bytes_per_pixel as u64,
+            _ => return None,
+        };
+        if !raw.flags.contains(DdsFlags::PITCH) {
+            return None;
+        }
+
+        let row_bytes = self.header.width() as u64 * bytes_per_pixel;
+        let pitch = raw.pitch_or_linear_size as u64;
+        if pitch <= row_bytes {
+            return None;
+        }
+
+        let header_len = (Header::MAGIC.len() + self.header.byte_len()) as u64;
+        let data_len = file_len?.checked_sub(header_len)?;
+
+        // Different alignments may result in the same pitch for the level 0
+        // surface, but not for smaller mipmaps. Prefer alignments that
+        // exactly match the length of the data section.
+        let mut fitting = None;
+        for shift in 1..=8 {
+            let alignment = 1_u64 << shift;
+            if util::div_ceil(row_bytes, alignment) * alignment != pitch {
+                continue;
+            }
+
+            let row_alignment = NonZeroU32::new(alignment as u32)?;
+            let layout = DataLayout::from_header_with_row_alignment(
+                &self.header,
+                self.format.into(),
+                row_alignment,
+            );
+            match layout.map(|layout| layout.data_len()) {
+                Ok(len) if len == data_len => return Some(row_alignment),
+                Ok(len) if len < data_len && fitting.is_none() => fitting = Some(row_alignment),
+                _ => {}
+            }
+        }
+        fitting
     }
 
     pub fn new(header: Header) -> Result<Self, DecodeError> {
@@ -53,8 +145,96 @@ impl DdsInfo {
             header,
             format,
             layout,
+            mip_order: MipOrder::LargestFirst,
+            cube_atlas_hint: None,
+            reserved1_tag: None,
+            writer: DdsWriter::Unknown,
+            fixes: Vec::new(),
         })
     }
+    /// Detects whether the file was written by a legacy tool that rounds the
+    /// number of blocks of each surface down instead of up.
+    ///
+    /// This is only detected if the data section is too short for the
+    /// regular data layout and has exactly the length of the legacy data
+    /// layout.
+    fn detect_legacy_block_sizes(&self, file_len: Option<u64>) -> bool {
+        if !matches!(PixelInfo::from(self.format), PixelInfo::Block(_)) {
+            return false;
+        }
+        let header_len = (Header::MAGIC.len() + self.header.byte_len()) as u64;
+        let data_len = match file_len.and_then(|len| len.checked_sub(header_len)) {
+            Some(data_len) => data_len,
+            None => return false,
+        };
+        if self.layout.data_len() <= data_len {
+            return false;
+        }
+
+        let legacy =
+            DataLayout::from_header_with_legacy_block_sizes(&self.header, self.format.into());
+        matches!(legacy, Ok(legacy) if legacy.data_len() == data_len)
+    }
+
+    /// Sets the order in which mipmaps are stored in the data section.
+    ///
+    /// [`Self::read`] and [`Self::read_with_options`] detect the mip order
+    /// automatically. See [`MipOrder`] for more information.
+    pub fn with_mip_order(mut self, mip_order: MipOrder) -> Self {
+        self.mip_order = mip_order;
+        self
+    }
+    /// Sets the number of bytes each row of pixels is padded to.
+    ///
+    /// If the header is read with [`ParseOptions::permissive`] and
+    /// [`ParseOptions::file_len`], [`Self::read_with_options`] detects padded
+    /// rows automatically. See [`DataLayout::from_header_with_row_alignment`]
+    /// for more information.
+    pub fn with_row_alignment(mut self, row_alignment: NonZeroU32) -> Result<Self, DecodeError> {
+        self.layout = DataLayout::from_header_with_row_alignment(
+            &self.header,
+            self.format.into(),
+            row_alignment,
+        )?;
+        Ok(self)
+    }
+    /// Sets the data layout to that of files written by legacy tools which
+    /// round the number of blocks of each surface down.
+    ///
+    /// If the header is read with [`ParseOptions::permissive`] and
+    /// [`ParseOptions::file_len`], [`Self::read_with_options`] detects such
+    /// files automatically. See
+    /// [`DataLayout::from_header_with_legacy_block_sizes`] for more
+    /// information.
+    pub fn with_legacy_block_sizes(mut self) -> Result<Self, DecodeError> {
+        self.layout =
+            DataLayout::from_header_with_legacy_block_sizes(&self.header, self.format.into())?;
+        Ok(self)
+    }
+    /// Sets the cube atlas layout declared by the file.
+    ///
+    /// [`Self::read`] and [`Self::read_with_options`] detect the hint
+    /// automatically. See [`CubeAtlasLayout::from_raw_header`].
+    pub fn with_cube_atlas_hint(mut self, hint: Option<CubeAtlasLayout>) -> Self {
+        self.cube_atlas_hint = hint;
+        self
+    }
+    /// Sets the tag stored in the `reserved1` field of the header.
+    ///
+    /// [`Self::read`] and [`Self::read_with_options`] detect the tag
+    /// automatically. See [`Reserved1Tag::from_raw_header`].
+    pub fn with_reserved1_tag(mut self, tag: Option<Reserved1Tag>) -> Self {
+        self.reserved1_tag = tag;
+        self
+    }
+    /// Sets the software that wrote the file.
+    ///
+    /// [`Self::read`] and [`Self::read_with_options`] detect the writer
+    /// automatically. See [`RawHeader::detect_writer`].
+    pub fn with_writer(mut self, writer: DdsWriter) -> Self {
+        self.writer = writer;
+        self
+    }
 
     pub fn header(&self) -> &Header {
         &self.header
@@ -65,6 +245,117 @@ impl DdsInfo {
     pub fn layout(&self) -> DataLayout {
         self.layout
     }
+    /// The alpha mode declared by the header.
+    ///
+    /// See [`Header::alpha_mode`].
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.header.alpha_mode()
+    }
+    /// The order in which mipmaps are stored in the data section.
+    ///
+    /// If this is [`MipOrder::SmallestFirst`], [`Decoder`] will read surfaces
+    /// in that order. Use the `mipmap_level` of [`Decoder::surface_info`] to
+    /// determine the level of each surface.
+    pub fn mip_order(&self) -> MipOrder {
+        self.mip_order
+    }
+    /// The cube atlas layout declared in the header of the file, if any.
+    pub fn cube_atlas_hint(&self) -> Option<CubeAtlasLayout> {
+        self.cube_atlas_hint
+    }
+    /// The known tag in the `reserved1` field of the header, if any.
+    ///
+    /// This typically identifies the software that wrote the file.
+    pub fn reserved1_tag(&self) -> Option<&Reserved1Tag> {
+        self.reserved1_tag.as_ref()
+    }
+    /// The software that likely wrote the file.
+    ///
+    /// See [`RawHeader::detect_writer`].
+    pub fn writer(&self) -> DdsWriter {
+        self.writer
+    }
+    /// The fixes that were applied to the header while reading it.
+    ///
+    /// This is always empty unless the header was read with
+    /// [`ParseOptions::permissive`]. Tools can use this to warn users about
+    /// invalid files or to write a corrected header.
+    pub fn fixes(&self) -> &[HeaderFix] {
+        &self.fixes
+    }
+    /// Detects whether the file is a 2D texture that stores the faces of a
+    /// cube map as an atlas.
+    ///
+    /// If the header declares a cube atlas layout (see
+    /// [`Self::cube_atlas_hint`]), that layout is used. Otherwise, if
+    /// `guess_from_size` is `true`, the layout is guessed from the size of
+    /// the texture (see [`CubeAtlasLayout::guess_from_size`]).
+    ///
+    /// Returns `None` for cube maps, volumes, texture arrays, and textures
+    /// whose size doesn't fit the layout.
+    pub fn cube_atlas(&self, guess_from_size: bool) -> Option<CubeAtlas> {
+        let size = match self.layout {
+            DataLayout::Texture(texture) => texture.main().size(),
+            _ => return None,
+        };
+
+        match self.cube_atlas_hint {
+            Some(layout) => CubeAtlas::new(layout, size),
+            None if guess_from_size => {
+                CubeAtlasLayout::guess_from_size(size).and_then(|l| CubeAtlas::new(l, size))
+            }
+            None => None,
+        }
+    }
+}
+
+/// A decoded surface.
+///
+/// See [`Decoder::read_texture`] and [`Decoder::read_all`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Surface<T> {
+    pub size: Size,
+    /// The color format of [`Self::data`].
+    pub color: ColorFormat,
+    /// The mipmap level of the surface.
+    ///
+    /// For volumes, this is the mipmap level of the volume the depth slice
+    /// belongs to.
+    pub mipmap_level: u8,
+    /// The decoded pixels of the surface, row by row without padding.
+    pub data: Vec<T>,
+}
+impl<T> Surface<T>
+where
+    [T]: AsBytes,
+{
+    /// Returns a view of the decoded pixels.
+    pub fn view(&self) -> ImageView<'_> {
+        // PANIC SAFETY: The data of surfaces always matches size and color.
+        ImageView::new(self.data.as_slice(), self.size, self.color).unwrap()
+    }
+}
+
+/// The surfaces decoded by [`Decoder::read_all_skip_errors`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RecoveredSurfaces<T> {
+    /// All surfaces in the order they are stored in the file, including
+    /// damaged surfaces filled with the placeholder color.
+    pub surfaces: Vec<Surface<T>>,
+    /// The index in [`Self::surfaces`] and the error of each damaged
+    /// surface.
+    ///
+    /// All errors are [`DecodeError::InSurface`], so their location in the
+    /// file is known.
+    pub errors: Vec<(usize, DecodeError)>,
+}
+impl<T> RecoveredSurfaces<T> {
+    /// Whether all surfaces were decoded without errors.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
 }
 
 /// A decoder for reading the pixel data of a DDS file.
@@ -73,8 +364,170 @@ pub struct Decoder<R> {
 
     info: DdsInfo,
     iter: SurfaceIterator,
+    scratch: DecodeScratch,
     pub options: DecodeOptions,
 }
+impl<'a> Decoder<&'a [u8]> {
+    /// Creates a decoder for a DDS file that is entirely in memory (e.g. a
+    /// memory-mapped file or a file inside an archive).
+    ///
+    /// The slice itself is used as the reader, so no [`std::io::Cursor`] is
+    /// needed. Since slices do not implement [`Seek`], use a cursor if you
+    /// need [`Decoder::read_surface_rect`].
+    pub fn from_slice(data: &'a [u8]) -> Result<Self, DecodeError> {
+        Self::from_slice_with_options(data, &ParseOptions::default())
+    }
+    /// Same as [`Self::from_slice`], but with the given parse options.
+    ///
+    /// If [`ParseOptions::file_len`] is `None`, it is set to the length of
+    /// the data.
+    pub fn from_slice_with_options(
+        data: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<Self, DecodeError> {
+        let mut options = options.clone();
+        if options.file_len.is_none() {
+            let magic_len = if options.skip_magic_bytes {
+                Header::MAGIC.len() as u64
+            } else {
+                0
+            };
+            options.file_len = Some(data.len() as u64 + magic_len);
+        }
+
+        Self::new_with_options(data, &options)
+    }
+
+    /// The encoded data that has not been read yet.
+    pub fn remaining_data(&self) -> &'a [u8] {
+        self.reader
+    }
+
+    /// Decodes all remaining surfaces of the file in parallel.
+    ///
+    /// This returns the same surfaces as [`Decoder::read_all`]. Since the
+    /// entire file is in memory, every surface (mipmap, texture of an array,
+    /// cube map face, or depth slice) can be decoded independently of all
+    /// others, so surfaces are distributed across worker threads. This is
+    /// most effective for files with many surfaces, e.g. cube map arrays.
+    ///
+    /// If the `rayon` feature is not enabled, surfaces are decoded one after
+    /// another.
+    ///
+    /// If this operation succeeds, all surfaces have been read. If it fails,
+    /// the decoder is not advanced. Errors of individual surfaces are
+    /// returned as [`DecodeError::InSurface`]. Since all surfaces are
+    /// allocated at the same time, their total size may not exceed
+    /// [`DecodeOptions::max_output_size`]. This is checked before decoding
+    /// starts.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let data = std::fs::read("cube_map.dds").unwrap();
+    /// let mut decoder = Decoder::from_slice(&data).unwrap();
+    /// let surfaces = decoder.decode_all_parallel::<u8>().unwrap();
+    /// ```
+    pub fn decode_all_parallel<T>(&mut self) -> Result<Vec<Surface<T>>, DecodeError>
+    where
+        T: WithPrecision + Send,
+        [T]: AsBytes,
+    {
+        // determine where each surface is stored
+        let mut iter = self.iter.clone();
+        let mut jobs = Vec::new();
+        let mut offset: u64 = 0;
+        while let Some(current) = iter.current() {
+            jobs.push(SurfaceJob {
+                // PANIC SAFETY: there is a current surface
+                location: iter.current_location().unwrap(),
+                offset,
+                len: current.data_len(),
+                size: current.size(),
+                mipmap_level: current.mipmap_level(),
+                stored_rows: self.stored_rows(current.size()),
+            });
+            offset += current.data_len();
+            iter.advance();
+        }
+
+        let data = self.reader;
+        let color = ColorFormat::new(self.native_color().channels, T::PRECISION);
+        let format = self.info.format;
+        let options = &self.options;
+
+        // all surfaces are allocated at the same time
+        let mut max_output_size = options.max_output_size;
+        for job in &jobs {
+            reserve_surface_data::<T>(job.size, color, &mut max_output_size)
+                .map_err(|error| error.in_surface(job.location))?;
+        }
+
+        let decode_surface = |scratch: &mut DecodeScratch, job: &SurfaceJob| {
+            let start = usize::try_from(job.offset).unwrap_or(usize::MAX);
+            let len = usize::try_from(job.len).unwrap_or(usize::MAX);
+            let mut reader = data.get(start..).unwrap_or(&[]);
+            reader = &reader[..len.min(reader.len())];
+
+            let mut max_output_size = options.max_output_size;
+            let mut surface_data = alloc_surface_data::<T>(job.size, color, &mut max_output_size)?;
+            // PANIC SAFETY: The buffer has the correct length.
+            let image = ImageViewMut::new(surface_data.as_mut_slice(), job.size, color).unwrap();
+            decode_surface_data(
+                &mut reader,
+                image,
+                job.stored_rows,
+                format,
+                options,
+                scratch,
+            )?;
+
+            Ok(Surface {
+                size: job.size,
+                color,
+                mipmap_level: job.mipmap_level,
+                data: surface_data,
+            })
+        };
+        let decode_job = |scratch: &mut DecodeScratch, job: &SurfaceJob| {
+            decode_surface(scratch, job)
+                .map_err(|error: DecodeError| error.in_surface(job.location))
+        };
+
+        #[cfg(feature = "rayon")]
+        let surfaces = {
+            use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+            jobs.par_iter()
+                .map_init(DecodeScratch::new, decode_job)
+                .collect::<Result<Vec<_>, DecodeError>>()?
+        };
+        #[cfg(not(feature = "rayon"))]
+        let surfaces = {
+            let mut scratch = DecodeScratch::new();
+            jobs.iter()
+                .map(|job| decode_job(&mut scratch, job))
+                .collect::<Result<Vec<_>, DecodeError>>()?
+        };
+
+        let end = usize::try_from(offset).unwrap_or(usize::MAX);
+        self.reader = data.get(end..).unwrap_or(&[]);
+        self.iter = iter;
+        Ok(surfaces)
+    }
+}
+
+/// The location of a surface in the data section of a file.
+///
+/// See [`Decoder::decode_all_parallel`].
+struct SurfaceJob {
+    location: SurfaceLocation,
+    /// The byte offset of the surface relative to the current position of
+    /// the decoder.
+    offset: u64,
+    len: u64,
+    size: Size,
+    mipmap_level: u8,
+    stored_rows: StoredRows,
+}
 impl<R> Decoder<R> {
     pub fn new(reader: R) -> Result<Self, DecodeError>
     where
@@ -94,8 +547,9 @@ impl<R> Decoder<R> {
     pub fn from_info(reader: R, info: DdsInfo) -> Result<Self, DecodeError> {
         Ok(Self {
             reader,
-            iter: SurfaceIterator::new(info.layout()),
+            iter: SurfaceIterator::new_with_order(info.layout(), info.mip_order()),
             info,
+            scratch: DecodeScratch::new(),
             options: DecodeOptions::default(),
         })
     }
@@ -109,6 +563,19 @@ impl<R> Decoder<R> {
     pub fn layout(&self) -> DataLayout {
         self.info.layout()
     }
+    /// The alpha mode declared by the header.
+    ///
+    /// Use this to decide how to treat the alpha channel of decoded images,
+    /// e.g. whether they need to be unpremultiplied.
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.info.alpha_mode()
+    }
+    /// The fixes that were applied to the header while reading it.
+    ///
+    /// See [`DdsInfo::fixes`].
+    pub fn fixes(&self) -> &[HeaderFix] {
+        self.info.fixes()
+    }
 
     /// The size of the level 0 object.
     ///
@@ -145,6 +612,38 @@ impl<R> Decoder<R> {
     /// The next surface is determined by the data layout of the DDS file. For
     /// volume textures, this function will read the next depth slice.
     pub fn read_surface(&mut self, image: ImageViewMut) -> Result<(), DecodeError>
+    where
+        R: Read,
+    {
+        self.read_surface_partial(image).map(|_| ())
+    }
+
+    /// Reads the next surface into the given buffer and returns the number of
+    /// rows that were decoded.
+    ///
+    /// This is the same as [`Self::read_surface`], but intended for use with
+    /// [`DecodeOptions::truncated_fill`]. If the file is cut off within (or
+    /// before) this surface, the missing rows are filled and the returned
+    /// progress reports fewer decoded rows than the height of the surface.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let file = std::fs::File::open("truncated.dds").unwrap();
+    /// let mut decoder = Decoder::new(file).unwrap();
+    /// decoder.options.truncated_fill = Some([1.0, 0.0, 1.0, 1.0]);
+    ///
+    /// let size = decoder.main_size();
+    /// let mut pixels = vec![0_u8; size.pixels() as usize * 4];
+    /// let image = ImageViewMut::new(pixels.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+    /// let progress = decoder.read_surface_partial(image).unwrap();
+    /// if progress.decoded_rows < progress.total_rows {
+    ///     println!("only {} rows could be decoded", progress.decoded_rows);
+    /// }
+    /// ```
+    pub fn read_surface_partial(
+        &mut self,
+        image: ImageViewMut,
+    ) -> Result<DecodeProgress, DecodeError>
     where
         R: Read,
     {
@@ -153,10 +652,198 @@ impl<R> Decoder<R> {
             return Err(DecodeError::UnexpectedSurfaceSize);
         }
 
-        decode(&mut self.reader, image, self.info.format, &self.options)?;
+        let stored_rows = self.stored_rows(current.size());
+        let progress = decode_surface_data(
+            &mut self.reader,
+            image,
+            stored_rows,
+            self.info.format,
+            &self.options,
+            &mut self.scratch,
+        )?;
 
         self.iter.advance();
-        Ok(())
+        Ok(progress)
+    }
+
+    /// Reads all surfaces of the current texture (or volume), including all
+    /// mipmaps.
+    ///
+    /// Surfaces are decoded with the channels of [`Self::native_color`] and
+    /// the precision of `T`, and returned in the order they are stored in the
+    /// file. For simple textures, this means that all mipmaps of the texture
+    /// are returned, largest first (unless the file stores them
+    /// [smallest first](MipOrder::SmallestFirst)). For volumes, each depth
+    /// slice is one surface.
+    ///
+    /// For texture arrays and cube maps, only the current element (e.g. the
+    /// first texture or cube map face) is read. Call this function again to
+    /// read the next element, or use [`Self::read_all`] to read everything.
+    ///
+    /// Errors of individual surfaces are returned as
+    /// [`DecodeError::InSurface`] with the location of the failed surface.
+    /// The total size of all returned surfaces may not exceed
+    /// [`DecodeOptions::max_output_size`].
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let file = std::fs::File::open("example.dds").unwrap();
+    /// let mut decoder = Decoder::new(file).unwrap();
+    /// let mipmaps = decoder.read_texture::<u8>().unwrap();
+    /// let main = &mipmaps[0];
+    /// println!("{}x{} {:?}", main.size.width, main.size.height, main.color);
+    /// ```
+    pub fn read_texture<T: WithPrecision>(&mut self) -> Result<Vec<Surface<T>>, DecodeError>
+    where
+        R: Read,
+        [T]: AsBytes,
+    {
+        self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+
+        let mut max_output_size = self.options.max_output_size;
+        let mut surfaces = Vec::new();
+        loop {
+            surfaces.push(self.read_surface_owned(&mut max_output_size)?);
+            if self.iter.is_at_element_start() {
+                return Ok(surfaces);
+            }
+        }
+    }
+
+    /// Reads all remaining surfaces of the file.
+    ///
+    /// This is the same as calling [`Self::read_texture`] until there are no
+    /// more surfaces. All surfaces of all elements are returned in the order
+    /// they are stored in the file.
+    ///
+    /// Errors of individual surfaces are returned as
+    /// [`DecodeError::InSurface`] with the location of the failed surface.
+    /// The total size of all returned surfaces may not exceed
+    /// [`DecodeOptions::max_output_size`].
+    pub fn read_all<T: WithPrecision>(&mut self) -> Result<Vec<Surface<T>>, DecodeError>
+    where
+        R: Read,
+        [T]: AsBytes,
+    {
+        let mut max_output_size = self.options.max_output_size;
+        let mut surfaces = Vec::new();
+        while self.iter.current().is_some() {
+            surfaces.push(self.read_surface_owned(&mut max_output_size)?);
+        }
+        Ok(surfaces)
+    }
+
+    /// Reads all remaining surfaces of the file, skipping surfaces that fail
+    /// to decode.
+    ///
+    /// This is the same as [`Self::read_all`], but a damaged surface (e.g.
+    /// corrupt data or a file that is cut off) doesn't abort decoding.
+    /// Instead, the error is recorded, the surface is filled with the RGBA
+    /// color `placeholder`, and decoding continues by seeking to the offset of
+    /// the next surface in the data section. This is intended for tools that
+    /// recover as much as possible from damaged files.
+    ///
+    /// Errors that can't be recovered from are still returned. This includes
+    /// [`DecodeError::OutputSizeExceeded`] if the total size of all returned
+    /// surfaces (including placeholders) exceeds
+    /// [`DecodeOptions::max_output_size`], [`DecodeError::Cancelled`], and I/O
+    /// errors while seeking. The output size is checked before a surface is
+    /// allocated, so headers declaring huge surfaces fail without allocating.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let file = std::fs::File::open("damaged.dds").unwrap();
+    /// let mut decoder = Decoder::new(file).unwrap();
+    /// let recovered = decoder.read_all_skip_errors::<u8>([1.0, 0.0, 1.0, 1.0]).unwrap();
+    /// for (index, error) in &recovered.errors {
+    ///     println!("surface {} is damaged: {}", index, error);
+    /// }
+    /// ```
+    pub fn read_all_skip_errors<T: WithPrecision>(
+        &mut self,
+        placeholder: [f32; 4],
+    ) -> Result<RecoveredSurfaces<T>, DecodeError>
+    where
+        R: Read + Seek,
+        [T]: AsBytes,
+    {
+        let data_start = self
+            .reader
+            .stream_position()?
+            .checked_sub(self.iter.current_offset())
+            .ok_or(DecodeError::SurfaceNotFound)?;
+
+        let mut max_output_size = self.options.max_output_size;
+        let mut recovered = RecoveredSurfaces {
+            surfaces: Vec::new(),
+            errors: Vec::new(),
+        };
+        while let Some(current) = self.iter.current() {
+            let size = current.size();
+            let mipmap_level = current.mipmap_level();
+            let color = ColorFormat::new(self.native_color().channels, T::PRECISION);
+            // PANIC SAFETY: there is a current surface
+            let location = self.iter.current_location().unwrap();
+
+            let mut data = alloc_surface_data::<T>(size, color, &mut max_output_size)
+                .map_err(|error| error.in_surface(location))?;
+            // PANIC SAFETY: The buffer has the correct length.
+            let image = ImageViewMut::new(data.as_mut_slice(), size, color).unwrap();
+            match self.read_surface(image) {
+                Ok(()) => {}
+                Err(DecodeError::Cancelled) => return Err(DecodeError::Cancelled),
+                Err(error) => {
+                    fill_rows(data.as_bytes_mut(), color, placeholder);
+                    let index = recovered.surfaces.len();
+                    recovered.errors.push((index, error.in_surface(location)));
+
+                    // continue with the next surface, wherever the reader is
+                    self.iter.advance();
+                    let next = data_start + self.iter.current_offset();
+                    self.reader.seek(SeekFrom::Start(next))?;
+                }
+            }
+
+            recovered.surfaces.push(Surface {
+                size,
+                color,
+                mipmap_level,
+                data,
+            });
+        }
+        Ok(recovered)
+    }
+
+    /// Reads the current surface into a new buffer, whose size is subtracted
+    /// from `max_output_size`.
+    fn read_surface_owned<T: WithPrecision>(
+        &mut self,
+        max_output_size: &mut u64,
+    ) -> Result<Surface<T>, DecodeError>
+    where
+        R: Read,
+        [T]: AsBytes,
+    {
+        let current = self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+        let size = current.size();
+        let mipmap_level = current.mipmap_level();
+        let color = ColorFormat::new(self.native_color().channels, T::PRECISION);
+        // PANIC SAFETY: there is a current surface
+        let location = self.iter.current_location().unwrap();
+
+        let mut data = alloc_surface_data::<T>(size, color, max_output_size)
+            .map_err(|error| error.in_surface(location))?;
+        // PANIC SAFETY: The buffer has the correct length.
+        let image = ImageViewMut::new(data.as_mut_slice(), size, color).unwrap();
+        self.read_surface(image)
+            .map_err(|error| error.in_surface(location))?;
+
+        Ok(Surface {
+            size,
+            color,
+            mipmap_level,
+            data,
+        })
     }
 
     /// Reads a rectangle of the next surface into the given buffer.
@@ -177,8 +864,9 @@ impl<R> Decoder<R> {
     {
         let current = self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
 
+        let mut reader = self.stored_rows(current.size()).reader(&mut self.reader);
         decode_rect(
-            &mut self.reader,
+            &mut reader,
             buffer,
             row_pitch,
             color,
@@ -192,6 +880,374 @@ impl<R> Decoder<R> {
         Ok(())
     }
 
+    /// Reads the next surface into the given buffer with each row padded to a
+    /// multiple of `row_alignment` bytes and returns the row pitch.
+    ///
+    /// The padding bytes at the end of each row are set to zero. This allows
+    /// decoded surfaces to be copied directly into GPU upload buffers, which
+    /// typically require aligned rows (e.g. 256 bytes for D3D12). Use
+    /// [`ColorFormat::aligned_buffer_size`] to get the required length of the
+    /// buffer.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// # use std::num::NonZeroU32;
+    /// let file = std::fs::File::open("example.dds").unwrap();
+    /// let mut decoder = Decoder::new(file).unwrap();
+    ///
+    /// let alignment = NonZeroU32::new(256).unwrap();
+    /// let color = ColorFormat::RGBA_U8;
+    /// let size = decoder.main_size();
+    /// let mut buffer = vec![0_u8; color.aligned_buffer_size(size, alignment).unwrap()];
+    /// let row_pitch = decoder.read_surface_aligned(&mut buffer, color, alignment).unwrap();
+    /// assert_eq!(row_pitch % 256, 0);
+    /// ```
+    pub fn read_surface_aligned(
+        &mut self,
+        buffer: &mut [u8],
+        color: ColorFormat,
+        row_alignment: NonZeroU32,
+    ) -> Result<usize, DecodeError>
+    where
+        R: Read,
+    {
+        let current = self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+        let size = current.size();
+        let row_pitch = color
+            .aligned_row_pitch(size.width, row_alignment)
+            .ok_or(DecodeError::MemoryLimitExceeded)?;
+
+        let mut reader = self.stored_rows(size).reader(&mut self.reader);
+        decode_rect_sequential(
+            &mut reader,
+            buffer,
+            row_pitch,
+            color,
+            size,
+            Rect::new(0, 0, size.width, size.height),
+            self.info.format,
+            &self.options,
+        )?;
+
+        let row_bytes = size.width as usize * color.bytes_per_pixel() as usize;
+        for row in buffer.chunks_mut(row_pitch).take(size.height as usize) {
+            if row.len() > row_bytes {
+                row[row_bytes..].fill(0);
+            }
+        }
+
+        self.iter.advance();
+        Ok(row_pitch)
+    }
+
+    /// Decodes a single surface of a texture array (or texture) into the given
+    /// image.
+    ///
+    /// `index` is the index of the texture in the array. For cube maps and
+    /// cube map arrays, each face is a texture (see [`Self::decode_face`]).
+    /// Files that are not arrays only have the texture with index 0. For
+    /// volumes, use [`Self::decode_depth_slices`] instead.
+    ///
+    /// Unlike [`Self::read_surface`], this can decode any surface in any
+    /// order. The decoder seeks to the surface and back afterward, so the
+    /// state of the decoder (see [`Self::surface_info`]) is not changed.
+    ///
+    /// If the texture or mipmap level does not exist,
+    /// [`DecodeError::SurfaceNotFound`] is returned.
+    pub fn decode_layer(
+        &mut self,
+        index: usize,
+        mipmap_level: u8,
+        image: ImageViewMut,
+    ) -> Result<(), DecodeError>
+    where
+        R: Read + Seek,
+    {
+        let texture = match self.info.layout() {
+            DataLayout::Texture(texture) if index == 0 => texture,
+            DataLayout::TextureArray(array) => {
+                array.get(index).ok_or(DecodeError::SurfaceNotFound)?
+            }
+            _ => return Err(DecodeError::SurfaceNotFound),
+        };
+        self.decode_texture_surface(&texture, mipmap_level, image)
+    }
+
+    /// Decodes a single face of a cube map (or cube map array) into the given
+    /// image.
+    ///
+    /// `cube_index` is the index of the cube map in the array. Files with a
+    /// single cube map only have the cube map with index 0. For partial cube
+    /// maps (DX9 cube maps with fewer than 6 faces), only the faces stored in
+    /// the file can be decoded.
+    ///
+    /// See [`Self::decode_layer`] for more details.
+    pub fn decode_face(
+        &mut self,
+        cube_index: usize,
+        face: CubeFace,
+        mipmap_level: u8,
+        image: ImageViewMut,
+    ) -> Result<(), DecodeError>
+    where
+        R: Read + Seek,
+    {
+        let array = match self.info.layout() {
+            DataLayout::TextureArray(array) => array,
+            _ => return Err(DecodeError::SurfaceNotFound),
+        };
+        let index = array
+            .face_index(cube_index, face)
+            .ok_or(DecodeError::SurfaceNotFound)?;
+        self.decode_layer(index, mipmap_level, image)
+    }
+
+    /// Decodes a range of depth slices of a volume into the given image.
+    ///
+    /// The slices are stacked vertically in the image, so the image must have
+    /// the width of the volume mipmap and `depths.len()` times its height.
+    /// E.g. to decode slices 2 to 4 of a 64x64x16 volume, the image must have
+    /// a size of 64x192. The range must not be empty.
+    ///
+    /// This reads only the requested slices, so it is much faster and uses
+    /// much less memory than decoding the entire volume. Like
+    /// [`Self::decode_layer`], this does not change the state of the decoder.
+    ///
+    /// If the file is not a volume or the mipmap level or depth slices do not
+    /// exist, [`DecodeError::SurfaceNotFound`] is returned.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let file = std::fs::File::open("volume.dds").unwrap();
+    /// let mut decoder = Decoder::new(file).unwrap();
+    /// let volume = *decoder.layout().volume().unwrap();
+    /// let size = volume.main().size();
+    ///
+    /// // decode the slice in the middle of the volume
+    /// let middle = volume.main().depth() / 2;
+    /// let mut data = vec![0_u8; size.pixels() as usize * 4];
+    /// let image = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+    /// decoder.decode_depth_slices(0, middle..middle + 1, image).unwrap();
+    /// ```
+    pub fn decode_depth_slices(
+        &mut self,
+        mipmap_level: u8,
+        depths: std::ops::Range<u32>,
+        image: ImageViewMut,
+    ) -> Result<(), DecodeError>
+    where
+        R: Read + Seek,
+    {
+        let volume = match self.info.layout() {
+            DataLayout::Volume(volume) => volume,
+            _ => return Err(DecodeError::SurfaceNotFound),
+        };
+        let level = volume
+            .get(mipmap_level)
+            .ok_or(DecodeError::SurfaceNotFound)?;
+        if depths.is_empty() || depths.end > level.depth() {
+            return Err(DecodeError::SurfaceNotFound);
+        }
+        let slice_size = level.size();
+        let expected_size = Size::new(
+            slice_size.width,
+            slice_size
+                .height
+                .checked_mul(depths.len() as u32)
+                .ok_or(DecodeError::UnexpectedSurfaceSize)?,
+        );
+        if image.size() != expected_size {
+            return Err(DecodeError::UnexpectedSurfaceSize);
+        }
+
+        // PANIC SAFETY: the range was checked above
+        let first = level.get_depth_slice(depths.start).unwrap();
+        let offset = self.info.mip_order().file_offset(&volume, &level) + first.data_offset()
+            - level.data_offset();
+
+        let color = image.color();
+        let slice_bytes = image.data.len() / depths.len();
+        self.decode_at(offset, |decoder| {
+            for slice in image.data.chunks_exact_mut(slice_bytes) {
+                // PANIC SAFETY: the chunk has the size of exactly one slice
+                let slice = ImageViewMut::new(slice, slice_size, color).unwrap();
+                decoder.decode_next_at(slice)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the mipmap level best suited for creating a thumbnail of the
+    /// given size.
+    ///
+    /// This is the smallest mipmap level whose width and height are both at
+    /// least the width and height of `max_size`. If no mipmap is that large,
+    /// level 0 is returned.
+    pub fn thumbnail_level(&self, max_size: Size) -> u8 {
+        let main = self.main_size();
+        (0..self.info.layout().mipmaps())
+            .rev()
+            .find(|&level| {
+                let size = main.get_mipmap(level);
+                size.width >= max_size.width && size.height >= max_size.height
+            })
+            .unwrap_or(0)
+    }
+    /// Decodes the mipmap best suited for a thumbnail of the given size.
+    ///
+    /// The mipmap level is chosen with [`Self::thumbnail_level`], so the
+    /// returned surface is the smallest mipmap that can be downscaled to
+    /// `max_size` without losing detail. Only that one surface is read. For
+    /// texture arrays, this is the first texture, for cube maps the positive
+    /// X face, and for volumes the first depth slice.
+    ///
+    /// Surfaces are decoded with the channels of [`Self::native_color`] and
+    /// the precision of `T`. Like [`Self::decode_layer`], this does not
+    /// change the state of the decoder.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let file = std::fs::File::open("example.dds").unwrap();
+    /// let mut decoder = Decoder::new(file).unwrap();
+    /// let surface = decoder.decode_thumbnail::<u8>(Size::new(128, 128)).unwrap();
+    /// println!("using mipmap level {}", surface.mipmap_level);
+    /// ```
+    pub fn decode_thumbnail<T: WithPrecision>(
+        &mut self,
+        max_size: Size,
+    ) -> Result<Surface<T>, DecodeError>
+    where
+        R: Read + Seek,
+        [T]: AsBytes,
+    {
+        let mipmap_level = self.thumbnail_level(max_size);
+        let size = self.main_size().get_mipmap(mipmap_level);
+        let color = ColorFormat::new(self.native_color().channels, T::PRECISION);
+
+        let mut max_output_size = self.options.max_output_size;
+        let mut data = alloc_surface_data::<T>(size, color, &mut max_output_size)?;
+        // PANIC SAFETY: The buffer has the correct length.
+        let image = ImageViewMut::new(data.as_mut_slice(), size, color).unwrap();
+        self.decode_first_surface(mipmap_level, image)?;
+
+        Ok(Surface {
+            size,
+            color,
+            mipmap_level,
+            data,
+        })
+    }
+    /// Decodes the first surface of the given mipmap level, i.e. the surface
+    /// of the first texture, the positive X face, or the first depth slice.
+    pub(crate) fn decode_first_surface(
+        &mut self,
+        mipmap_level: u8,
+        image: ImageViewMut,
+    ) -> Result<(), DecodeError>
+    where
+        R: Read + Seek,
+    {
+        match self.info.layout() {
+            DataLayout::Volume(_) => self.decode_depth_slices(mipmap_level, 0..1, image),
+            _ => self.decode_layer(0, mipmap_level, image),
+        }
+    }
+
+    fn decode_texture_surface(
+        &mut self,
+        texture: &Texture,
+        mipmap_level: u8,
+        image: ImageViewMut,
+    ) -> Result<(), DecodeError>
+    where
+        R: Read + Seek,
+    {
+        let surface = texture
+            .get(mipmap_level)
+            .ok_or(DecodeError::SurfaceNotFound)?;
+        if image.size() != surface.size() {
+            return Err(DecodeError::UnexpectedSurfaceSize);
+        }
+
+        let offset = self.info.mip_order().file_offset(texture, &surface);
+        self.decode_at(offset, |decoder| decoder.decode_next_at(image))
+    }
+
+    /// Seeks to the given offset in the data section, calls the given
+    /// function, and seeks back to the current position.
+    fn decode_at(
+        &mut self,
+        offset: u64,
+        decode: impl FnOnce(&mut Self) -> Result<(), DecodeError>,
+    ) -> Result<(), DecodeError>
+    where
+        R: Read + Seek,
+    {
+        let position = self.reader.stream_position()?;
+        let data_start = position
+            .checked_sub(self.iter.current_offset())
+            .ok_or(DecodeError::SurfaceNotFound)?;
+        self.reader.seek(SeekFrom::Start(data_start + offset))?;
+
+        let result = decode(self);
+
+        // go back, so the decoder can continue reading surfaces in order
+        self.reader.seek(SeekFrom::Start(position))?;
+        result
+    }
+    /// Decodes the surface at the current position of the reader without
+    /// advancing the surface iterator.
+    fn decode_next_at(&mut self, image: ImageViewMut) -> Result<(), DecodeError>
+    where
+        R: Read,
+    {
+        let stored_rows = self.stored_rows(image.size());
+        decode_surface_data(
+            &mut self.reader,
+            image,
+            stored_rows,
+            self.info.format,
+            &self.options,
+            &mut self.scratch,
+        )
+        .map(|_| ())
+    }
+
+    /// Returns how the rows of surfaces with the given size are stored.
+    fn stored_rows(&self, size: Size) -> StoredRows {
+        let layout = self.info.layout();
+        match layout.pixel_info() {
+            PixelInfo::Fixed { bytes_per_pixel } if layout.row_alignment().get() != 1 => {
+                let row_bytes = size.width as u64 * bytes_per_pixel as u64;
+                match layout.row_pitch(size.width) {
+                    Some(row_pitch) if row_pitch > row_bytes => StoredRows::Padded {
+                        row_bytes,
+                        row_pitch,
+                    },
+                    _ => StoredRows::Tight,
+                }
+            }
+            PixelInfo::Block(block) if layout.has_legacy_block_sizes() => {
+                let (block_width, block_height) = block.size();
+                let bytes_per_block = block.bytes_per_block() as u64;
+                let blocks_x = util::div_ceil(size.width, block_width as u32) as u64;
+                let blocks_y = util::div_ceil(size.height, block_height as u32) as u64;
+                let (stored_x, stored_y) = legacy_block_count(block, size);
+                if (stored_x, stored_y) == (blocks_x, blocks_y) {
+                    StoredRows::Tight
+                } else {
+                    StoredRows::LegacyBlocks {
+                        row_bytes: blocks_x * bytes_per_block,
+                        stored_row_bytes: stored_x * bytes_per_block,
+                        rows: blocks_y,
+                        stored_rows: stored_y,
+                    }
+                }
+            }
+            _ => StoredRows::Tight,
+        }
+    }
+
     /// Skips over the next surface.
     ///
     /// This behaves the same as [`Decoder::read_surface_rect`] when decoding
@@ -222,6 +1278,9 @@ impl<R> Decoder<R> {
     ///
     /// - If the DDS file does not contain any mipmaps, this is a no-op.
     /// - Calling this at the start or end of a DDS file is a no-op.
+    /// - If mipmaps are stored [smallest first](MipOrder::SmallestFirst), this
+    ///   skips the remaining mipmaps of the current texture/volume, so the
+    ///   next surface is its level 0 surface.
     pub fn skip_mipmaps(&mut self) -> Result<(), DecodeError>
     where
         R: Seek,
@@ -235,4 +1294,288 @@ impl<R> Decoder<R> {
             Err(DecodeError::CannotSkipMipmapsInVolume)
         }
     }
+
+    /// Whether the encoded data of the current surface is stored exactly as
+    /// the encoder would write it, meaning that its rows are not padded and
+    /// no blocks are missing.
+    pub(crate) fn is_surface_tightly_packed(&self) -> bool {
+        match self.iter.current() {
+            Some(current) => matches!(self.stored_rows(current.size()), StoredRows::Tight),
+            None => false,
+        }
+    }
+    /// Passes the reader positioned at the encoded data of the current
+    /// surface to the given function and advances to the next surface.
+    ///
+    /// The function must consume exactly the data of the current surface.
+    pub(crate) fn with_surface_reader<T, E: From<DecodeError>>(
+        &mut self,
+        f: impl FnOnce(&mut R) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+        let result = f(&mut self.reader)?;
+        self.iter.advance();
+        Ok(result)
+    }
+    /// Reads and discards the data of the current surface.
+    ///
+    /// Unlike [`Self::skip_surface`], this doesn't require the reader to
+    /// implement [`Seek`].
+    pub(crate) fn discard_surface(&mut self) -> Result<(), DecodeError>
+    where
+        R: Read,
+    {
+        let current = self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+        let len = current.data_len();
+        let discarded = std::io::copy(&mut (&mut self.reader).take(len), &mut std::io::sink())?;
+        if discarded != len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        self.iter.advance();
+        Ok(())
+    }
+}
+
+/// How the encoded data of a surface is stored in the data section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StoredRows {
+    /// The data is stored exactly as decoders expect it.
+    Tight,
+    /// Each row of pixels is padded. See [`DataLayout::row_alignment`].
+    Padded { row_bytes: u64, row_pitch: u64 },
+    /// The last column and row of blocks are missing. See
+    /// [`DataLayout::has_legacy_block_sizes`].
+    LegacyBlocks {
+        row_bytes: u64,
+        stored_row_bytes: u64,
+        rows: u64,
+        stored_rows: u64,
+    },
+}
+impl StoredRows {
+    /// Wraps the given reader, so that it presents the data of the surface
+    /// the way decoders expect it.
+    fn reader<R>(self, reader: R) -> SurfaceReader<R> {
+        match self {
+            StoredRows::Tight => SurfaceReader::Tight(reader),
+            StoredRows::Padded {
+                row_bytes,
+                row_pitch,
+            } => SurfaceReader::Padded(PaddedRowReader::new(reader, row_bytes, row_pitch)),
+            StoredRows::LegacyBlocks {
+                row_bytes,
+                stored_row_bytes,
+                rows,
+                stored_rows,
+            } => SurfaceReader::LegacyBlocks(LegacyBlockReader::new(
+                reader,
+                row_bytes,
+                stored_row_bytes,
+                rows,
+                stored_rows,
+            )),
+        }
+    }
+}
+
+/// Allocates the buffer for a decoded surface of the given size and color.
+///
+/// The size of the buffer is subtracted from `max_output_size`. If the buffer
+/// is larger than the remaining size, nothing is allocated.
+fn alloc_surface_data<T: WithPrecision>(
+    size: Size,
+    color: ColorFormat,
+    max_output_size: &mut u64,
+) -> Result<Vec<T>, DecodeError> {
+    reserve_surface_data::<T>(size, color, max_output_size)?;
+    let len = usize::try_from(size.pixels() * color.channels.count() as u64)
+        .map_err(|_| DecodeError::OutputSizeExceeded)?;
+    Ok(vec![T::default(); len])
+}
+/// Subtracts the size of the buffer of a decoded surface from
+/// `max_output_size`.
+fn reserve_surface_data<T: WithPrecision>(
+    size: Size,
+    color: ColorFormat,
+    max_output_size: &mut u64,
+) -> Result<(), DecodeError> {
+    let bytes = size
+        .pixels()
+        .checked_mul(color.channels.count() as u64)
+        .and_then(|len| len.checked_mul(size_of::<T>() as u64))
+        .ok_or(DecodeError::OutputSizeExceeded)?;
+    *max_output_size = max_output_size
+        .checked_sub(bytes)
+        .ok_or(DecodeError::OutputSizeExceeded)?;
+    Ok(())
+}
+
+/// Decodes a surface, skipping the padding of its rows and filling in
+/// missing blocks if necessary.
+///
+/// Intermediate buffers are taken from the given scratch memory, so they are
+/// reused across surfaces.
+fn decode_surface_data(
+    reader: &mut dyn Read,
+    image: ImageViewMut,
+    stored_rows: StoredRows,
+    format: Format,
+    options: &DecodeOptions,
+    scratch: &mut DecodeScratch,
+) -> Result<DecodeProgress, DecodeError> {
+    if stored_rows != StoredRows::Tight {
+        let mut reader = stored_rows.reader(reader);
+        return decode_surface_data(
+            &mut reader,
+            image,
+            StoredRows::Tight,
+            format,
+            options,
+            scratch,
+        );
+    }
+
+    if options.truncated_fill.is_some() {
+        return decode_partial(reader, image, format, options);
+    }
+
+    let total_rows = image.height();
+    decode_with_scratch(reader, image, format, options, scratch)?;
+    Ok(DecodeProgress {
+        decoded_rows: total_rows,
+        total_rows,
+    })
+}
+
+/// A pull-based decoder that decodes a single surface a few rows at a time.
+///
+/// This allows huge surfaces to be processed without allocating a buffer for
+/// the entire decoded image. Rows are decoded from top to bottom.
+///
+/// ```no_run
+/// # use dds::*;
+/// # fn process(rows: &[u8]) {}
+/// # let mut reader = std::io::empty();
+/// let size = Size::new(16384, 16384);
+/// let mut decoder =
+///     SurfaceDecoder::new(&mut reader, Format::BC7_UNORM, size, DecodeOptions::default());
+/// let mut buffer = vec![0_u8; size.width as usize * 4 * 64];
+/// loop {
+///     let rows = decoder.next_rows(&mut buffer, ColorFormat::RGBA_U8).unwrap();
+///     if rows == 0 {
+///         break;
+///     }
+///     process(&buffer[..rows as usize * size.width as usize * 4]);
+/// }
+/// ```
+pub struct SurfaceDecoder<R> {
+    reader: R,
+    format: Format,
+    size: Size,
+    decoded_rows: u32,
+    pub options: DecodeOptions,
+}
+impl<R> SurfaceDecoder<R> {
+    /// Creates a new surface decoder.
+    ///
+    /// The reader is expected to be positioned at the start of the encoded
+    /// surface, just like for [`decode`].
+    pub fn new(reader: R, format: Format, size: Size, options: DecodeOptions) -> Self {
+        Self {
+            reader,
+            format,
+            size,
+            decoded_rows: 0,
+            options,
+        }
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+    /// The size of the entire surface.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+    /// The number of rows decoded so far.
+    pub fn decoded_rows(&self) -> u32 {
+        self.decoded_rows
+    }
+    /// The number of rows that have yet to be decoded.
+    pub fn remaining_rows(&self) -> u32 {
+        self.size.height - self.decoded_rows
+    }
+
+    /// The number of rows that have to be decoded together.
+    ///
+    /// [`Self::next_rows`] always decodes a multiple of this number of rows,
+    /// except for the last rows of the surface. For block-compressed formats,
+    /// this is the height of a block. Bi-planar formats (e.g. `NV12`) cannot
+    /// be decoded in parts, so this is the height of the entire surface.
+    pub fn row_multiple(&self) -> u32 {
+        match PixelInfo::from(self.format) {
+            PixelInfo::Fixed { .. } => 1,
+            PixelInfo::Block(block) => block.size().1 as u32,
+            PixelInfo::BiPlanar(_) => self.size.height.max(1),
+        }
+    }
+
+    pub fn into_reader(self) -> R {
+        self.reader
+    }
+
+    /// Decodes the next rows into the given buffer and returns the number of
+    /// rows decoded.
+    ///
+    /// The decoded rows are tightly packed in the given color format. As many
+    /// rows as fit into the buffer are decoded, rounded down to a multiple of
+    /// [`Self::row_multiple`]. If the buffer cannot hold
+    /// [`Self::row_multiple`] rows (or all remaining rows, if fewer remain),
+    /// [`DecodeError::RectBufferTooSmall`] is returned.
+    ///
+    /// Once all rows have been decoded, this returns `Ok(0)` and the reader
+    /// is positioned at the end of the encoded surface.
+    ///
+    /// [`DecodeOptions::progress`] is invoked after each call with the
+    /// progress of the entire surface.
+    pub fn next_rows(&mut self, buffer: &mut [u8], color: ColorFormat) -> Result<u32, DecodeError>
+    where
+        R: Read,
+    {
+        let remaining = self.remaining_rows();
+        if remaining == 0 {
+            return Ok(0);
+        }
+
+        let row_bytes = self.size.width as usize * color.bytes_per_pixel() as usize;
+        let multiple = self.row_multiple();
+        let min_rows = multiple.min(remaining);
+        let required_minimum = min_rows as usize * row_bytes;
+        if buffer.len() < required_minimum {
+            return Err(DecodeError::RectBufferTooSmall { required_minimum });
+        }
+
+        let fitting = (buffer.len() / row_bytes.max(1)).min(u32::MAX as usize) as u32;
+        let rows = if fitting >= remaining {
+            remaining
+        } else {
+            fitting / multiple * multiple
+        };
+
+        let strip = &mut buffer[..rows as usize * row_bytes];
+        let strip_size = Size::new(self.size.width, rows);
+        let view = ImageViewMut::new(strip, strip_size, color).expect("invalid strip");
+        self.options.check_cancelled()?;
+        decode(
+            &mut self.reader,
+            view,
+            self.format,
+            &self.options.without_progress(),
+        )?;
+
+        self.decoded_rows += rows;
+        self.options
+            .report_progress(self.decoded_rows, self.size.height);
+        Ok(rows)
+    }
 }
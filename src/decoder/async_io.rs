@@ -0,0 +1,116 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::header::{FourCC, Header, ParseOptions, PixelFormatFlags, RawDx10Header, RawHeader};
+use crate::{DecodeError, ImageViewMut};
+
+use super::{decode_surface_data, DdsInfo, Decoder};
+
+/// The offset of the pixel format flags in the raw header.
+const PIXEL_FORMAT_FLAGS_OFFSET: usize = 76;
+/// The offset of the FourCC in the raw header.
+const FOUR_CC_OFFSET: usize = 80;
+
+impl DdsInfo {
+    /// Same as [`DdsInfo::read`], but reads from an async reader.
+    pub async fn read_async<R: AsyncRead + Unpin>(r: &mut R) -> Result<Self, DecodeError> {
+        Self::read_with_options_async(r, &ParseOptions::default()).await
+    }
+    /// Same as [`DdsInfo::read_with_options`], but reads from an async
+    /// reader.
+    pub async fn read_with_options_async<R: AsyncRead + Unpin>(
+        r: &mut R,
+        options: &ParseOptions,
+    ) -> Result<Self, DecodeError> {
+        // Read all bytes of the header first and then parse them. Whether the
+        // DX10 header extension follows is determined by the pixel format.
+        let prefix = if options.skip_magic_bytes {
+            0
+        } else {
+            Header::MAGIC.len()
+        };
+        let mut buffer = vec![0_u8; prefix + RawHeader::SIZE as usize];
+        r.read_exact(&mut buffer).await?;
+
+        let read_u32 = |offset: usize| {
+            let bytes = &buffer[prefix + offset..prefix + offset + 4];
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        };
+        let pixel_format_flags =
+            PixelFormatFlags::from_bits_retain(read_u32(PIXEL_FORMAT_FLAGS_OFFSET));
+        let four_cc = read_u32(FOUR_CC_OFFSET);
+        if pixel_format_flags.contains(PixelFormatFlags::FOURCC) && FourCC(four_cc) == FourCC::DX10
+        {
+            let header_len = buffer.len();
+            buffer.resize(header_len + RawDx10Header::SIZE as usize, 0);
+            r.read_exact(&mut buffer[header_len..]).await?;
+        }
+
+        Self::read_with_options(&mut buffer.as_slice(), options)
+    }
+}
+
+impl<R: AsyncRead + Unpin> Decoder<R> {
+    /// Same as [`Decoder::new`], but reads from an async reader.
+    pub async fn new_async(reader: R) -> Result<Self, DecodeError> {
+        Self::new_with_options_async(reader, &ParseOptions::default()).await
+    }
+    /// Same as [`Decoder::new_with_options`], but reads from an async reader.
+    pub async fn new_with_options_async(
+        mut reader: R,
+        options: &ParseOptions,
+    ) -> Result<Self, DecodeError> {
+        let info = DdsInfo::read_with_options_async(&mut reader, options).await?;
+
+        Self::from_info(reader, info)
+    }
+
+    /// Same as [`Decoder::read_surface`], but reads from an async reader.
+    ///
+    /// The encoded data of the surface is read into memory first and then
+    /// decoded. So only one encoded surface is buffered at a time, not the
+    /// entire file. Decoding itself does not await and is CPU-bound.
+    pub async fn read_surface_async(&mut self, image: ImageViewMut<'_>) -> Result<(), DecodeError> {
+        let current = self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+        if image.size() != current.size() {
+            return Err(DecodeError::UnexpectedSurfaceSize);
+        }
+        let data_len =
+            usize::try_from(current.data_len()).map_err(|_| DecodeError::MemoryLimitExceeded)?;
+        let stored_rows = self.stored_rows(current.size());
+
+        let mut data = vec![0_u8; data_len];
+        self.reader.read_exact(&mut data).await?;
+        decode_surface_data(
+            &mut data.as_slice(),
+            image,
+            stored_rows,
+            self.info.format,
+            &self.options,
+            &mut self.scratch,
+        )?;
+
+        self.iter.advance();
+        Ok(())
+    }
+
+    /// Same as [`Decoder::skip_surface`], but reads from an async reader.
+    ///
+    /// Since async readers cannot seek, the data of the surface is read and
+    /// discarded.
+    pub async fn skip_surface_async(&mut self) -> Result<(), DecodeError> {
+        let current = self.iter.current().ok_or(DecodeError::NoMoreSurfaces)?;
+        let data_len = current.data_len();
+
+        let skipped = tokio::io::copy(
+            &mut (&mut self.reader).take(data_len),
+            &mut tokio::io::sink(),
+        )
+        .await?;
+        if skipped < data_len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        self.iter.advance();
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@
 use crate::header::{
     AlphaMode, Dx10Header, DxgiFormat, FourCC, MaskPixelFormat, PixelFormatFlags, RgbBitCount,
 };
-use crate::Format;
+use crate::{Format, FormatDifference};
 
 pub(crate) const fn special_cases(dx10: &Dx10Header) -> Option<Format> {
     if matches!(dx10.alpha_mode, AlphaMode::Premultiplied) {
@@ -150,6 +150,40 @@ pub(crate) const fn dxgi_format_to_supported(dxgi_format: DxgiFormat) -> Option<
     }
 }
 
+/// The DXGI formats that map to a format via [`dxgi_format_to_supported`].
+#[derive(Clone, Copy)]
+struct DxgiAliases {
+    formats: [DxgiFormat; 3],
+    len: u8,
+}
+const FORMAT_COUNT: usize = Format::all().len();
+static DXGI_ALIASES: [DxgiAliases; FORMAT_COUNT] = {
+    let mut aliases = [DxgiAliases {
+        formats: [DxgiFormat::UNKNOWN; 3],
+        len: 0,
+    }; FORMAT_COUNT];
+
+    let mut i = 0;
+    while i <= u8::MAX as u32 {
+        if let Some(dxgi) = DxgiFormat::from_u32(i) {
+            if let Some(format) = dxgi_format_to_supported(dxgi) {
+                let index = format as usize;
+                // this fails to compile if a format has too many aliases
+                aliases[index].formats[aliases[index].len as usize] = dxgi;
+                aliases[index].len += 1;
+            }
+        }
+        i += 1;
+    }
+
+    aliases
+};
+
+pub(crate) fn dxgi_aliases(format: Format) -> &'static [DxgiFormat] {
+    let aliases = &DXGI_ALIASES[format as usize];
+    &aliases.formats[..aliases.len as usize]
+}
+
 pub(crate) const fn four_cc_to_dxgi(four_cc: FourCC) -> Option<DxgiFormat> {
     match four_cc {
         FourCC::DXT1 => Some(DxgiFormat::BC1_UNORM),
@@ -272,6 +306,74 @@ pub(crate) fn supported_to_masked(format: Format) -> Option<MaskPixelFormat> {
     })
 }
 
+/// Returns the supported DXGI format with the same channels and bit depths,
+/// but a different channel type (e.g. `R8G8B8A8_UNORM` for
+/// `R8G8B8A8_UINT`).
+pub(crate) fn closest_to_dxgi(dxgi_format: DxgiFormat) -> Option<Format> {
+    // the channel layout is the first part of the name, e.g. `R8G8B8A8`
+    let layout = |format: DxgiFormat| format.name()?.split('_').next();
+    let target = layout(dxgi_format)?;
+    DxgiFormat::all()
+        .filter(|&format| layout(format) == Some(target))
+        .find_map(dxgi_format_to_supported)
+}
+
+/// Returns the supported format of a FourCC code that was written with the
+/// wrong case or byte order (e.g. `dxt1` or `1TXD`).
+pub(crate) fn closest_to_four_cc(four_cc: FourCC) -> Option<Format> {
+    let upper = u32::from_le_bytes(four_cc.0.to_le_bytes().map(|b| b.to_ascii_uppercase()));
+    [upper, upper.swap_bytes()]
+        .into_iter()
+        .filter(|&code| code != four_cc.0)
+        .find_map(|code| four_cc_to_supported(FourCC(code)))
+}
+
+/// Returns all pixel formats that are detected as supported formats, in the
+/// order in which they are checked.
+pub(crate) fn known_pixel_formats() -> impl Iterator<Item = (MaskPixelFormat, Format)> {
+    KNOWN_PIXEL_FORMATS
+        .iter()
+        .map(|(p, _, format)| (p.to_masked(), *format))
+}
+
+/// Returns the supported pixel format that is most similar to the given
+/// pixel format and how they differ.
+pub(crate) fn closest_to_masked(pf: &MaskPixelFormat) -> Option<(Format, FormatDifference)> {
+    // most similar first
+    let rank = |difference: FormatDifference| match difference {
+        FormatDifference::Flags => 0,
+        FormatDifference::BitCount => 1,
+        _ => 2,
+    };
+    known_pixel_formats()
+        .filter_map(|(known, format)| Some((format, mask_difference(pf, &known)?)))
+        .min_by_key(|(_, difference)| rank(*difference))
+}
+
+/// Returns how two pixel formats differ, if they differ in exactly one way.
+fn mask_difference(a: &MaskPixelFormat, b: &MaskPixelFormat) -> Option<FormatDifference> {
+    let masks = |pf: &MaskPixelFormat| [pf.r_bit_mask, pf.g_bit_mask, pf.b_bit_mask, pf.a_bit_mask];
+    let sorted_masks = |pf: &MaskPixelFormat| {
+        let mut masks = masks(pf);
+        masks.sort_unstable();
+        masks
+    };
+
+    let same_flags = a.flags == b.flags;
+    let same_bit_count = a.rgb_bit_count == b.rgb_bit_count;
+    if masks(a) == masks(b) {
+        match (same_flags, same_bit_count) {
+            (false, true) => Some(FormatDifference::Flags),
+            (true, false) => Some(FormatDifference::BitCount),
+            _ => None,
+        }
+    } else if sorted_masks(a) == sorted_masks(b) && same_bit_count {
+        Some(FormatDifference::SwappedMasks)
+    } else {
+        None
+    }
+}
+
 struct PFPattern {
     flags: PixelFormatFlags,
     rgb_bit_count: RgbBitCount,
@@ -0,0 +1,359 @@
+use std::fmt::Debug;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::header::{Header, RawHeader};
+use crate::metrics;
+use crate::redundancy::{get_elements, to_usize, Element};
+use crate::{
+    decode, Channels, ColorFormat, DataRegion, DdsInfo, DecodeError, DecodeOptions, Format,
+    ImageViewMut, Size,
+};
+
+/// Options for [`diff_with_options`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct DiffOptions {
+    /// Whether to compare the surfaces of files with different formats by
+    /// decoding them and computing their PSNR.
+    ///
+    /// Surfaces of different formats can't be compared byte by byte. If this
+    /// option is disabled, the surfaces of such files are not compared at
+    /// all.
+    ///
+    /// Default: `true`
+    pub psnr: bool,
+    /// The options used to decode surfaces when computing the PSNR.
+    ///
+    /// Default: `DecodeOptions::default()`
+    pub decode_options: DecodeOptions,
+}
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            psnr: true,
+            decode_options: DecodeOptions::default(),
+        }
+    }
+}
+
+/// The differences between 2 DDS files.
+///
+/// See [`diff`].
+///
+/// With the `serde` feature enabled, this implements `serde::Serialize`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct DdsDiff {
+    /// All fields of the raw headers that differ.
+    pub header: Vec<HeaderDifference>,
+    /// Whether the files have different surfaces, i.e. a different number of
+    /// textures, mipmaps, or depth slices, or surfaces of different sizes.
+    ///
+    /// Surfaces that only exist in one file or that have different sizes are
+    /// not compared.
+    pub layout_differs: bool,
+    /// All compared surfaces that differ.
+    pub surfaces: Vec<SurfaceDifference>,
+}
+
+impl DdsDiff {
+    /// Whether no differences were found.
+    ///
+    /// Note that surfaces are not compared if the formats of the files
+    /// differ and [`DiffOptions::psnr`] is disabled.
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty() && !self.layout_differs && self.surfaces.is_empty()
+    }
+}
+
+/// A field of the raw header that differs between 2 DDS files.
+///
+/// See [`DdsDiff::header`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct HeaderDifference {
+    /// The name of the field in [`RawHeader`], e.g. `"mipmap_count"` or
+    /// `"pixel_format.four_cc"`.
+    pub field: &'static str,
+    /// The `Debug` representation of the value in the first file.
+    pub a: String,
+    /// The `Debug` representation of the value in the second file.
+    pub b: String,
+}
+
+/// A surface that differs between 2 DDS files.
+///
+/// See [`DdsDiff::surfaces`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct SurfaceDifference {
+    /// The index of the texture (or volume) the surface belongs to.
+    ///
+    /// For texture arrays, this is the index in the array. For cube maps,
+    /// this is the index of the face.
+    pub element: usize,
+    pub level: u8,
+    /// The index of the depth slice. This is 0 for everything but volumes.
+    pub depth_slice: u32,
+    pub width: u32,
+    pub height: u32,
+    /// The number of bytes that differ.
+    ///
+    /// This is `None` if the files have different formats.
+    pub differing_bytes: Option<u64>,
+    /// The PSNR (see [`metrics::psnr`]) of the RGBA channels of the decoded
+    /// surfaces.
+    ///
+    /// This is only computed if the files have different formats.
+    pub psnr: Option<Vec<f64>>,
+}
+
+/// Compares 2 DDS files.
+///
+/// This is the same as [`diff_with_options`] with the default options.
+///
+/// ```
+/// # use dds::{header::*, *};
+/// let header = Header::new_image(4, 4, Format::R8G8B8A8_UNORM);
+/// let mut a = Vec::new();
+/// header.write(&mut a).unwrap();
+/// a.extend_from_slice(&[0; 64]);
+/// let mut b = a.clone();
+/// *b.last_mut().unwrap() = 255;
+///
+/// let diff = diff(&mut std::io::Cursor::new(&a), &mut std::io::Cursor::new(&b)).unwrap();
+/// assert!(diff.header.is_empty());
+/// assert_eq!(diff.surfaces.len(), 1);
+/// assert_eq!(diff.surfaces[0].differing_bytes, Some(1));
+/// ```
+pub fn diff<A: Read + Seek, B: Read + Seek>(a: &mut A, b: &mut B) -> Result<DdsDiff, DecodeError> {
+    diff_with_options(a, b, &DiffOptions::default())
+}
+
+/// Compares 2 DDS files.
+///
+/// Both readers are expected to be positioned at the start of a DDS file.
+/// The result contains the differing fields of the raw headers and all
+/// differing surfaces. Surfaces are matched by their element, mipmap level,
+/// and depth slice. If both files have the same format, surfaces are
+/// compared byte by byte. Otherwise, they are decoded and compared by their
+/// PSNR (see [`DiffOptions::psnr`]).
+///
+/// If this operation succeeds, both readers will be positioned at the end of
+/// their data section.
+pub fn diff_with_options<A: Read + Seek, B: Read + Seek>(
+    a: &mut A,
+    b: &mut B,
+    options: &DiffOptions,
+) -> Result<DdsDiff, DecodeError> {
+    let (raw_a, info_a, start_a) = read_file(a)?;
+    let (raw_b, info_b, start_b) = read_file(b)?;
+
+    let elements_a: Vec<Element> = get_elements(&info_a.layout(), info_a.mip_order()).collect();
+    let elements_b: Vec<Element> = get_elements(&info_b.layout(), info_b.mip_order()).collect();
+
+    let mut layout_differs = elements_a.len() != elements_b.len();
+    let same_format = info_a.format() == info_b.format();
+    let mut surfaces = Vec::new();
+    let mut data_a = Vec::new();
+    let mut data_b = Vec::new();
+    for (element, (element_a, element_b)) in elements_a.iter().zip(&elements_b).enumerate() {
+        layout_differs |= element_a.levels.len() != element_b.levels.len();
+
+        for (level, (level_a, level_b)) in
+            element_a.levels.iter().zip(&element_b.levels).enumerate()
+        {
+            if level_a.size != level_b.size || level_a.depth != level_b.depth {
+                layout_differs = true;
+                continue;
+            }
+            if !same_format && !options.psnr {
+                continue;
+            }
+
+            a.seek(SeekFrom::Start(start_a + element_a.offset + level_a.offset))?;
+            data_a.resize(to_usize(level_a.len)?, 0);
+            a.read_exact(&mut data_a)?;
+            b.seek(SeekFrom::Start(start_b + element_b.offset + level_b.offset))?;
+            data_b.resize(to_usize(level_b.len)?, 0);
+            b.read_exact(&mut data_b)?;
+
+            let depth = level_a.depth as usize;
+            let slices_a = data_a.chunks_exact(data_a.len() / depth);
+            let slices_b = data_b.chunks_exact(data_b.len() / depth);
+            for (depth_slice, (slice_a, slice_b)) in slices_a.zip(slices_b).enumerate() {
+                let mut difference = SurfaceDifference {
+                    element,
+                    level: level as u8,
+                    depth_slice: depth_slice as u32,
+                    width: level_a.size.width,
+                    height: level_a.size.height,
+                    differing_bytes: None,
+                    psnr: None,
+                };
+                if same_format {
+                    let differing = slice_a.iter().zip(slice_b).filter(|(a, b)| a != b).count();
+                    if differing == 0 {
+                        continue;
+                    }
+                    difference.differing_bytes = Some(differing as u64);
+                } else {
+                    let size = level_a.size;
+                    let pixels_a = decode_rgba(slice_a, size, info_a.format(), options)?;
+                    let pixels_b = decode_rgba(slice_b, size, info_b.format(), options)?;
+                    if pixels_a == pixels_b {
+                        continue;
+                    }
+                    difference.psnr =
+                        Some(metrics::psnr(&pixels_a, &pixels_b, size, Channels::Rgba));
+                }
+                surfaces.push(difference);
+            }
+        }
+    }
+
+    a.seek(SeekFrom::Start(start_a + info_a.layout().data_len()))?;
+    b.seek(SeekFrom::Start(start_b + info_b.layout().data_len()))?;
+
+    Ok(DdsDiff {
+        header: header_differences(&raw_a, &raw_b),
+        layout_differs,
+        surfaces,
+    })
+}
+
+/// Reads the raw header and info of a DDS file and returns them together
+/// with the position of the data section.
+fn read_file<R: Read + Seek>(file: &mut R) -> Result<(RawHeader, DdsInfo, u64), DecodeError> {
+    let start = file.stream_position()?;
+    Header::read_magic(file)?;
+    let raw = RawHeader::read(file)?;
+
+    file.seek(SeekFrom::Start(start))?;
+    let info = DdsInfo::read(file)?;
+    let data_start = file.stream_position()?;
+    Ok((raw, info, data_start))
+}
+
+fn decode_rgba(
+    data: &[u8],
+    size: Size,
+    format: Format,
+    options: &DiffOptions,
+) -> Result<Vec<f32>, DecodeError> {
+    let len = ColorFormat::RGBA_F32
+        .buffer_size(size)
+        .ok_or(DecodeError::MemoryLimitExceeded)?;
+    let mut pixels = vec![0_f32; len / 4];
+    let image = ImageViewMut::new(pixels.as_mut_slice(), size, ColorFormat::RGBA_F32)
+        .expect("invalid buffer size");
+    decode(&mut &data[..], image, format, &options.decode_options)?;
+    Ok(pixels)
+}
+
+fn header_differences(a: &RawHeader, b: &RawHeader) -> Vec<HeaderDifference> {
+    fn field<T: PartialEq + Debug>(
+        differences: &mut Vec<HeaderDifference>,
+        field: &'static str,
+        a: &T,
+        b: &T,
+    ) {
+        if a != b {
+            differences.push(HeaderDifference {
+                field,
+                a: format!("{:?}", a),
+                b: format!("{:?}", b),
+            });
+        }
+    }
+
+    let mut d = Vec::new();
+    field(&mut d, "size", &a.size, &b.size);
+    field(&mut d, "flags", &a.flags, &b.flags);
+    field(&mut d, "height", &a.height, &b.height);
+    field(&mut d, "width", &a.width, &b.width);
+    let pitch = (&a.pitch_or_linear_size, &b.pitch_or_linear_size);
+    field(&mut d, "pitch_or_linear_size", pitch.0, pitch.1);
+    field(&mut d, "depth", &a.depth, &b.depth);
+    field(&mut d, "mipmap_count", &a.mipmap_count, &b.mipmap_count);
+    field(&mut d, "reserved1", &a.reserved1, &b.reserved1);
+
+    let (pf_a, pf_b) = (&a.pixel_format, &b.pixel_format);
+    field(&mut d, "pixel_format.size", &pf_a.size, &pf_b.size);
+    field(&mut d, "pixel_format.flags", &pf_a.flags, &pf_b.flags);
+    field(&mut d, "pixel_format.four_cc", &pf_a.four_cc, &pf_b.four_cc);
+    let bit_count = (&pf_a.rgb_bit_count, &pf_b.rgb_bit_count);
+    field(
+        &mut d,
+        "pixel_format.rgb_bit_count",
+        bit_count.0,
+        bit_count.1,
+    );
+    field(
+        &mut d,
+        "pixel_format.r_bit_mask",
+        &pf_a.r_bit_mask,
+        &pf_b.r_bit_mask,
+    );
+    field(
+        &mut d,
+        "pixel_format.g_bit_mask",
+        &pf_a.g_bit_mask,
+        &pf_b.g_bit_mask,
+    );
+    field(
+        &mut d,
+        "pixel_format.b_bit_mask",
+        &pf_a.b_bit_mask,
+        &pf_b.b_bit_mask,
+    );
+    field(
+        &mut d,
+        "pixel_format.a_bit_mask",
+        &pf_a.a_bit_mask,
+        &pf_b.a_bit_mask,
+    );
+
+    field(&mut d, "caps", &a.caps, &b.caps);
+    field(&mut d, "caps2", &a.caps2, &b.caps2);
+    field(&mut d, "caps3", &a.caps3, &b.caps3);
+    field(&mut d, "caps4", &a.caps4, &b.caps4);
+    field(&mut d, "reserved2", &a.reserved2, &b.reserved2);
+
+    match (&a.dx10, &b.dx10) {
+        (Some(dx10_a), Some(dx10_b)) => {
+            field(
+                &mut d,
+                "dx10.dxgi_format",
+                &dx10_a.dxgi_format,
+                &dx10_b.dxgi_format,
+            );
+            let dimension = (&dx10_a.resource_dimension, &dx10_b.resource_dimension);
+            field(&mut d, "dx10.resource_dimension", dimension.0, dimension.1);
+            field(
+                &mut d,
+                "dx10.misc_flag",
+                &dx10_a.misc_flag,
+                &dx10_b.misc_flag,
+            );
+            field(
+                &mut d,
+                "dx10.array_size",
+                &dx10_a.array_size,
+                &dx10_b.array_size,
+            );
+            field(
+                &mut d,
+                "dx10.misc_flags2",
+                &dx10_a.misc_flags2,
+                &dx10_b.misc_flags2,
+            );
+        }
+        (dx10_a, dx10_b) => field(&mut d, "dx10", dx10_a, dx10_b),
+    }
+
+    d
+}
@@ -1,15 +1,17 @@
 // helpers
 
 use crate::{
-    cast, ch, convert_to_rgba_f32, n4,
+    cast, ch, convert_to_rgba_f32,
+    decode::BC6HFormat,
+    n4,
     util::{self, clamp_0_1},
     EncodeError,
 };
 
 use super::{
-    bc1, bc4, bcn_util,
+    bc1, bc4, bc6, bc7, bcn_util,
     encoder::{Args, Encoder, EncoderSet, Flags},
-    CompressionQuality, EncodeOptions, ErrorMetric, PreferredGroupSize,
+    Bc7Options, CompressionQuality, EncodeOptions, ErrorMetric, PreferredGroupSize,
 };
 
 fn block_universal<
@@ -25,11 +27,13 @@ fn block_universal<
         color,
         writer,
         width,
-        options,
+        mut options,
         ..
     } = args;
     let bytes_per_pixel = color.bytes_per_pixel() as usize;
 
+    let blocks_x = util::div_ceil(width, BLOCK_WIDTH);
+    options.reserve_memory((width * BLOCK_HEIGHT * 16 + blocks_x * BLOCK_BYTES) as u64)?;
     let mut intermediate_buffer = vec![[0_f32; 4]; width * BLOCK_HEIGHT];
     let mut encoded_buffer = vec![[0_u8; BLOCK_BYTES]; util::div_ceil(width, BLOCK_WIDTH)];
 
@@ -143,30 +147,43 @@ fn get_bc1_options(options: &EncodeOptions) -> bc1::Bc1Options {
     bc1::Bc1Options {
         dither: options.dithering.color(),
         perceptual: options.error_metric == ErrorMetric::Perceptual,
+        weights: {
+            let [r, g, b, _] = options.error_metric.channel_weights();
+            [r, g, b]
+        },
         opaque_always_p4: options.quality <= CompressionQuality::Normal,
         refine_max_iter: match options.quality {
             CompressionQuality::Fast => 0,
             CompressionQuality::Normal => 3,
             CompressionQuality::High | CompressionQuality::Unreasonable => 10,
         },
+        exhaustive: options.quality == CompressionQuality::Unreasonable,
+        // alpha values are rounded to 8 bits before comparing
+        alpha_threshold: (options.bc1_alpha_threshold as f32 - 0.5) / 255.0,
         ..bc1::Bc1Options::default()
     }
 }
+pub(crate) fn bc1_block(mut block: [[f32; 4]; 16], options: &EncodeOptions) -> [u8; 8] {
+    let bc1_options = get_bc1_options(options);
+
+    if options.dithering.alpha() {
+        let alpha = get_alpha(&block);
+        bcn_util::block_dither(&alpha, |i, pixel| {
+            let alpha = if pixel >= bc1_options.alpha_threshold {
+                1.0
+            } else {
+                0.0
+            };
+            block[i][3] = alpha;
+            alpha
+        });
+    }
+
+    bc1::compress_bc1_block(block, bc1_options)
+}
 pub(crate) const BC1_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
     block_universal::<4, 4, 8>(args, |data, row_pitch, options, out| {
-        let bc1_options = get_bc1_options(options);
-        let mut block = get_4x4_rgba(data, row_pitch);
-
-        if options.dithering.alpha() {
-            let alpha = get_alpha(&block);
-            bcn_util::block_dither(&alpha, |i, pixel| {
-                let alpha = if pixel >= 0.5 { 1.0 } else { 0.0 };
-                block[i][3] = alpha;
-                alpha
-            });
-        }
-
-        *out = bc1::compress_bc1_block(block, bc1_options);
+        *out = bc1_block(get_4x4_rgba(data, row_pitch), options);
     })
 })
 .add_flags(Flags::DITHER_ALL)
@@ -195,16 +212,17 @@ fn bc2_alpha(alpha: [f32; 16], options: &EncodeOptions) -> [u8; 8] {
     indexes.to_le_bytes()
 }
 
-pub(crate) const BC2_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
-    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-        let (bc1_options, _) = get_bc3_options(options);
+pub(crate) fn bc2_block(block: [[f32; 4]; 16], options: &EncodeOptions) -> [u8; 16] {
+    let (bc1_options, _) = get_bc3_options(options);
 
-        let block = get_4x4_rgba(data, row_pitch);
+    let alpha_block = bc2_alpha(get_alpha(&block), options);
+    let bc1_block = bc1::compress_bc1_block(block, bc1_options);
 
-        let alpha_block = bc2_alpha(get_alpha(&block), options);
-        let bc1_block = bc1::compress_bc1_block(block, bc1_options);
-
-        *out = concat_blocks(alpha_block, bc1_block);
+    concat_blocks(alpha_block, bc1_block)
+}
+pub(crate) const BC2_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
+    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
+        *out = bc2_block(get_4x4_rgba(data, row_pitch), options);
     })
 })
 .add_flags(Flags::DITHER_ALL)
@@ -213,15 +231,9 @@ pub(crate) const BC2_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_unive
 pub(crate) const BC2_UNORM_PREMULTIPLIED_ALPHA: EncoderSet =
     EncoderSet::new_bc(&[Encoder::new_universal(|args| {
         block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-            let (bc1_options, _) = get_bc3_options(options);
-
             let mut block = get_4x4_rgba(data, row_pitch);
             pre_multiply_alpha(&mut block);
-
-            let alpha_block = bc2_alpha(get_alpha(&block), options);
-            let bc1_block = bc1::compress_bc1_block(block, bc1_options);
-
-            *out = concat_blocks(alpha_block, bc1_block);
+            *out = bc2_block(block, options);
         })
     })
     .add_flags(Flags::DITHER_ALL)
@@ -231,21 +243,24 @@ fn get_bc3_options(options: &EncodeOptions) -> (bc1::Bc1Options, bc4::Bc4Options
     let mut bc1_options = get_bc1_options(options);
     bc1_options.no_default = true;
 
+    // the BC4 block stores alpha
     let mut bc4_options = get_bc4_options(options);
     bc4_options.snorm = false;
+    bc4_options.dither = options.dithering.alpha();
 
     (bc1_options, bc4_options)
 }
-pub(crate) const BC3_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
-    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-        let (bc1_options, bc4_options) = get_bc3_options(options);
-
-        let block = get_4x4_rgba(data, row_pitch);
+pub(crate) fn bc3_block(block: [[f32; 4]; 16], options: &EncodeOptions) -> [u8; 16] {
+    let (bc1_options, bc4_options) = get_bc3_options(options);
 
-        let bc4_block = bc4::compress_bc4_block(get_alpha(&block), bc4_options);
-        let bc1_block = bc1::compress_bc1_block(block, bc1_options);
+    let bc4_block = bc4::compress_bc4_block(get_alpha(&block), bc4_options);
+    let bc1_block = bc1::compress_bc1_block(block, bc1_options);
 
-        *out = concat_blocks(bc4_block, bc1_block);
+    concat_blocks(bc4_block, bc1_block)
+}
+pub(crate) const BC3_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
+    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
+        *out = bc3_block(get_4x4_rgba(data, row_pitch), options);
     })
 })
 .add_flags(Flags::DITHER_ALL)
@@ -254,15 +269,9 @@ pub(crate) const BC3_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_unive
 pub(crate) const BC3_UNORM_PREMULTIPLIED_ALPHA: EncoderSet =
     EncoderSet::new_bc(&[Encoder::new_universal(|args| {
         block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-            let (bc1_options, bc4_options) = get_bc3_options(options);
-
             let mut block = get_4x4_rgba(data, row_pitch);
             pre_multiply_alpha(&mut block);
-
-            let bc4_block = bc4::compress_bc4_block(get_alpha(&block), bc4_options);
-            let bc1_block = bc1::compress_bc1_block(block, bc1_options);
-
-            *out = concat_blocks(bc4_block, bc1_block);
+            *out = bc3_block(block, options);
         })
     })
     .add_flags(Flags::DITHER_ALL)
@@ -271,7 +280,9 @@ pub(crate) const BC3_UNORM_PREMULTIPLIED_ALPHA: EncoderSet =
 pub(crate) const BC3_UNORM_RXGB: EncoderSet =
     EncoderSet::new_bc(&[Encoder::new_universal(|args| {
         block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-            let (bc1_options, bc4_options) = get_bc3_options(options);
+            let (bc1_options, mut bc4_options) = get_bc3_options(options);
+            // the BC4 block stores red, not alpha
+            bc4_options.dither = options.dithering.color();
 
             let block_r = get_4x4_select_channel::<0>(data, row_pitch);
             let mut block_gb = get_4x4_rgba(data, row_pitch);
@@ -295,8 +306,9 @@ pub(crate) const BC3_UNORM_RXGB: EncoderSet =
     .add_flags(Flags::DITHER_COLOR)
     .with_group_size(BC3_GROUP_SIZE)]);
 
-fn handle_bc4(data: &[[f32; 4]], row_pitch: usize, options: bc4::Bc4Options) -> [u8; 8] {
-    let block = get_4x4_grayscale(data, row_pitch);
+pub(crate) fn bc4_block(block: [f32; 16], options: &EncodeOptions, snorm: bool) -> [u8; 8] {
+    let mut options = get_bc4_options(options);
+    options.snorm = snorm;
     bc4::compress_bc4_block(block, options)
 }
 fn get_bc4_options(options: &EncodeOptions) -> bc4::Bc4Options {
@@ -313,9 +325,7 @@ fn get_bc4_options(options: &EncodeOptions) -> bc4::Bc4Options {
 
 pub(crate) const BC4_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
     block_universal::<4, 4, 8>(args, |data, row_pitch, options, out| {
-        let mut options = get_bc4_options(options);
-        options.snorm = false;
-        *out = handle_bc4(data, row_pitch, options);
+        *out = bc4_block(get_4x4_grayscale(data, row_pitch), options, false);
     })
 })
 .add_flags(Flags::DITHER_COLOR)
@@ -323,29 +333,37 @@ pub(crate) const BC4_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_unive
 
 pub(crate) const BC4_SNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
     block_universal::<4, 4, 8>(args, |data, row_pitch, options, out| {
-        let mut options = get_bc4_options(options);
-        options.snorm = true;
-        *out = handle_bc4(data, row_pitch, options);
+        *out = bc4_block(get_4x4_grayscale(data, row_pitch), options, true);
     })
 })
 .add_flags(Flags::DITHER_COLOR)
 .with_group_size(BC4_GROUP_SIZE)]);
 
-fn handle_bc5(data: &[[f32; 4]], row_pitch: usize, options: bc4::Bc4Options) -> [u8; 16] {
-    let red_block = get_4x4_select_channel::<0>(data, row_pitch);
-    let green_block = get_4x4_select_channel::<1>(data, row_pitch);
-
-    let red = bc4::compress_bc4_block(red_block, options);
-    let green = bc4::compress_bc4_block(green_block, options);
-
-    concat_blocks(red, green)
+pub(crate) fn bc5_block(
+    red: [f32; 16],
+    green: [f32; 16],
+    options: &EncodeOptions,
+    snorm: bool,
+) -> [u8; 16] {
+    concat_blocks(
+        bc4_block(red, options, snorm),
+        bc4_block(green, options, snorm),
+    )
+}
+fn handle_bc5(
+    data: &[[f32; 4]],
+    row_pitch: usize,
+    options: &EncodeOptions,
+    snorm: bool,
+) -> [u8; 16] {
+    let red = get_4x4_select_channel::<0>(data, row_pitch);
+    let green = get_4x4_select_channel::<1>(data, row_pitch);
+    bc5_block(red, green, options, snorm)
 }
 
 pub(crate) const BC5_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
     block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-        let mut options = get_bc4_options(options);
-        options.snorm = false;
-        *out = handle_bc5(data, row_pitch, options);
+        *out = handle_bc5(data, row_pitch, options, false);
     })
 })
 .add_flags(Flags::DITHER_COLOR)
@@ -353,10 +371,47 @@ pub(crate) const BC5_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_unive
 
 pub(crate) const BC5_SNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
     block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
-        let mut options = get_bc4_options(options);
-        options.snorm = true;
-        *out = handle_bc5(data, row_pitch, options);
+        *out = handle_bc5(data, row_pitch, options, true);
     })
 })
 .add_flags(Flags::DITHER_COLOR)
 .with_group_size(BC4_GROUP_SIZE)]);
+
+const BC6H_GROUP_SIZE: PreferredGroupSize = PreferredGroupSize::group(64 * 64, 32 * 32, 8 * 8);
+
+fn bc6h_block(block: [[f32; 4]; 16], format: BC6HFormat, options: &EncodeOptions) -> [u8; 16] {
+    bc6::compress_bc6h_block(block, bc6::Bc6hOptions::new(format, options.quality))
+}
+
+pub(crate) const BC6H_UF16: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
+    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
+        let block = get_4x4_rgba(data, row_pitch);
+        *out = bc6h_block(block, BC6HFormat::UnsignedF16, options);
+    })
+})
+.with_group_size(BC6H_GROUP_SIZE)]);
+
+pub(crate) const BC6H_SF16: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
+    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
+        let block = get_4x4_rgba(data, row_pitch);
+        *out = bc6h_block(block, BC6HFormat::SignedF16, options);
+    })
+})
+.with_group_size(BC6H_GROUP_SIZE)]);
+
+pub(crate) fn bc7_block(block: [[f32; 4]; 16], options: &EncodeOptions) -> [u8; 16] {
+    let bc7_options = options
+        .bc7
+        .unwrap_or_else(|| Bc7Options::from_quality(options.quality));
+    let weights = options.error_metric.channel_weights();
+    bc7::compress_bc7_block(block, bc7_options, weights)
+}
+
+const BC7_GROUP_SIZE: PreferredGroupSize = PreferredGroupSize::group(64 * 64, 32 * 32, 8 * 8);
+
+pub(crate) const BC7_UNORM: EncoderSet = EncoderSet::new_bc(&[Encoder::new_universal(|args| {
+    block_universal::<4, 4, 16>(args, |data, row_pitch, options, out| {
+        *out = bc7_block(get_4x4_rgba(data, row_pitch), options);
+    })
+})
+.with_group_size(BC7_GROUP_SIZE)]);
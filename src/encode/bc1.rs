@@ -14,8 +14,14 @@ pub struct Bc1Options {
     pub no_default: bool,
     pub alpha_threshold: f32,
     pub perceptual: bool,
+    /// The relative weights of the R, G, and B channels. Ignored if
+    /// `perceptual` is set.
+    pub weights: [f32; 3],
     pub opaque_always_p4: bool,
     pub refine_max_iter: u8,
+    /// Whether to refine endpoints with finer steps and search a larger
+    /// neighborhood of quantized endpoints. This is very slow.
+    pub exhaustive: bool,
 }
 impl Default for Bc1Options {
     fn default() -> Self {
@@ -24,8 +30,10 @@ impl Default for Bc1Options {
             no_default: false,
             alpha_threshold: 0.5,
             perceptual: false,
+            weights: [1.0; 3],
             opaque_always_p4: false,
             refine_max_iter: 10,
+            exhaustive: false,
         }
     }
 }
@@ -51,6 +59,8 @@ pub(crate) fn compress_bc1_block(mut block: [[f32; 4]; 16], options: Bc1Options)
 
     if options.perceptual {
         compress(block, Perceptual, options)
+    } else if options.weights != [1.0; 3] {
+        compress(block, Weighted::new(options.weights), options)
     } else {
         compress(block, Uniform, options)
     }
@@ -106,7 +116,7 @@ fn compress_p4(
     (min, max) = bcn_util::refine_endpoints(
         min,
         max,
-        bcn_util::RefinementOptions::new_bc1(min.0.distance(max.0), options.refine_max_iter as u32),
+        refinement_options(min.0.distance(max.0), options),
         |(min, max)| {
             let min = error_metric.color_space_to_srgb(min);
             let max = error_metric.color_space_to_srgb(max);
@@ -119,7 +129,7 @@ fn compress_p4(
         },
     );
 
-    let endpoints = pick_best_quantization_p4(min, max, &block, error_metric);
+    let endpoints = pick_best_quantization_p4(min, max, &block, error_metric, options.exhaustive);
     let palette = P4Palette::from(&endpoints, error_metric);
 
     let (indexes, error) = if options.dither {
@@ -156,7 +166,7 @@ fn compress_p3_default(
     (min, max) = bcn_util::refine_endpoints(
         min,
         max,
-        bcn_util::RefinementOptions::new_bc1(min.0.distance(max.0), options.refine_max_iter as u32),
+        refinement_options(min.0.distance(max.0), options),
         |(min, max)| {
             let min = error_metric.color_space_to_srgb(min);
             let max = error_metric.color_space_to_srgb(max);
@@ -168,7 +178,14 @@ fn compress_p3_default(
             palette.block_closest_error(&block, alpha_map)
         },
     );
-    let endpoints = pick_best_quantization_p3(min, max, &block, alpha_map, error_metric);
+    let endpoints = pick_best_quantization_p3(
+        min,
+        max,
+        &block,
+        alpha_map,
+        error_metric,
+        options.exhaustive,
+    );
     let palette = P3Palette::from(&endpoints, error_metric);
 
     let (indexes, error) = if options.dither {
@@ -180,6 +197,16 @@ fn compress_p3_default(
     (endpoints.with_indexes(indexes), error)
 }
 
+fn refinement_options(dist: f32, options: Bc1Options) -> bcn_util::RefinementOptions {
+    let mut refinement = bcn_util::RefinementOptions::new_bc1(dist, options.refine_max_iter as u32);
+    if options.exhaustive {
+        // continue until the steps are smaller than a 6-bit quantization step
+        refinement.step_min = 1. / 256.;
+        refinement.max_iter = refinement.max_iter.max(32);
+    }
+    refinement
+}
+
 fn get_single_color(block: &[Vec3A; 16], alpha_map: AlphaMap) -> Option<Vec3A> {
     if block.is_empty() {
         return None;
@@ -349,10 +376,12 @@ fn pick_best_quantization_p4(
     c1: ColorSpace,
     block: impl Block4x4<ColorSpace> + Copy,
     error_metric: impl ErrorMetric,
+    exhaustive: bool,
 ) -> EndPoints {
     let (c0, c1) = pick_best_quantization(
         error_metric.color_space_to_srgb(c0),
         error_metric.color_space_to_srgb(c1),
+        exhaustive,
         move |c0, c1| {
             let endpoints = EndPoints::new_p4(c0, c1);
             let palette = P4Palette::from(&endpoints, error_metric);
@@ -367,10 +396,12 @@ fn pick_best_quantization_p3(
     block: impl Block4x4<ColorSpace> + Copy,
     alpha_map: AlphaMap,
     error_metric: impl ErrorMetric,
+    exhaustive: bool,
 ) -> EndPoints {
     let (c0, c1) = pick_best_quantization(
         error_metric.color_space_to_srgb(c0),
         error_metric.color_space_to_srgb(c1),
+        exhaustive,
         move |c0, c1| {
             let endpoints = EndPoints::new_p3_default(c0, c1);
             let palette = P3Palette::from(&endpoints, error_metric);
@@ -379,15 +410,40 @@ fn pick_best_quantization_p3(
     );
     EndPoints::new_p3_default(c0, c1)
 }
+/// Picks the best quantized endpoints near the given endpoints.
+///
+/// Normally, only the floor and ceil of each channel are considered. If
+/// `exhaustive` is set, the search is extended by one quantization step in
+/// each direction and repeated until no better endpoints are found.
 fn pick_best_quantization(
     c0: Vec3A,
     c1: Vec3A,
+    exhaustive: bool,
     mut f: impl FnMut(R5G6B5Color, R5G6B5Color) -> f32,
 ) -> (R5G6B5Color, R5G6B5Color) {
-    let c0_min = R5G6B5Color::from_color_floor(c0);
-    let c0_max = R5G6B5Color::from_color_ceil(c0);
-    let c1_min = R5G6B5Color::from_color_floor(c1);
-    let c1_max = R5G6B5Color::from_color_ceil(c1);
+    let search_range = |c: Vec3A| {
+        let (min, max) = (
+            R5G6B5Color::from_color_floor(c),
+            R5G6B5Color::from_color_ceil(c),
+        );
+        if exhaustive {
+            let min = R5G6B5Color::new(
+                min.r.saturating_sub(1),
+                min.g.saturating_sub(1),
+                min.b.saturating_sub(1),
+            );
+            let max = R5G6B5Color::new(
+                (max.r + 1).min(31),
+                (max.g + 1).min(63),
+                (max.b + 1).min(31),
+            );
+            (min, max)
+        } else {
+            (min, max)
+        }
+    };
+    let (c0_min, c0_max) = search_range(c0);
+    let (c1_min, c1_max) = search_range(c1);
 
     let mut best: (R5G6B5Color, R5G6B5Color) = (
         R5G6B5Color::from_color_round(c0),
@@ -395,37 +451,45 @@ fn pick_best_quantization(
     );
     let mut best_error = f(best.0, best.1);
 
-    let already_checked = best.0;
-    for r0 in c0_min.r..=c0_max.r {
-        for g0 in c0_min.g..=c0_max.g {
-            for b0 in c0_min.b..=c0_max.b {
-                let c0 = R5G6B5Color::new(r0, g0, b0);
-                if c0 == already_checked {
-                    continue;
-                }
-                let error = f(c0, best.1);
-                if error < best_error {
-                    best.0 = c0;
-                    best_error = error;
+    loop {
+        let previous = best;
+
+        let already_checked = best.0;
+        for r0 in c0_min.r..=c0_max.r {
+            for g0 in c0_min.g..=c0_max.g {
+                for b0 in c0_min.b..=c0_max.b {
+                    let c0 = R5G6B5Color::new(r0, g0, b0);
+                    if c0 == already_checked {
+                        continue;
+                    }
+                    let error = f(c0, best.1);
+                    if error < best_error {
+                        best.0 = c0;
+                        best_error = error;
+                    }
                 }
             }
         }
-    }
-    let already_checked = best.1;
-    for r1 in c1_min.r..=c1_max.r {
-        for g1 in c1_min.g..=c1_max.g {
-            for b1 in c1_min.b..=c1_max.b {
-                let c1 = R5G6B5Color::new(r1, g1, b1);
-                if c1 == already_checked {
-                    continue;
-                }
-                let error = f(best.0, c1);
-                if error < best_error {
-                    best.1 = c1;
-                    best_error = error;
+        let already_checked = best.1;
+        for r1 in c1_min.r..=c1_max.r {
+            for g1 in c1_min.g..=c1_max.g {
+                for b1 in c1_min.b..=c1_max.b {
+                    let c1 = R5G6B5Color::new(r1, g1, b1);
+                    if c1 == already_checked {
+                        continue;
+                    }
+                    let error = f(best.0, c1);
+                    if error < best_error {
+                        best.1 = c1;
+                        best_error = error;
+                    }
                 }
             }
         }
+
+        if !exhaustive || best == previous {
+            break;
+        }
     }
 
     best
@@ -993,3 +1057,31 @@ impl ErrorMetric for Perceptual {
         fast_oklab_to_srgb(color.0)
     }
 }
+/// Scales each channel by the square root of its weight, so the squared
+/// distance in the scaled space is the weighted squared error.
+#[derive(Debug, Clone, Copy)]
+struct Weighted {
+    scale: Vec3A,
+    inv_scale: Vec3A,
+}
+impl Weighted {
+    fn new(weights: [f32; 3]) -> Self {
+        // channels with a weight of 0 still need a tiny weight to be able to
+        // map back to sRGB
+        let scale = Vec3A::from(weights.map(|w| w.max(1e-4).sqrt()));
+        Self {
+            scale,
+            inv_scale: scale.recip(),
+        }
+    }
+}
+impl ErrorMetric for Weighted {
+    #[inline]
+    fn srgb_to_color_space(&self, color: Vec3A) -> ColorSpace {
+        ColorSpace(color * self.scale)
+    }
+    #[inline]
+    fn color_space_to_srgb(&self, color: ColorSpace) -> Vec3A {
+        color.0 * self.inv_scale
+    }
+}
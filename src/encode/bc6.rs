@@ -0,0 +1,886 @@
+//! A BC6H encoder.
+//!
+//! BC6H stores half floats. The encoder works with the bit patterns of the
+//! half floats interpreted as integers (sign-magnitude for signed values),
+//! because this is the space in which BC6H interpolates. The bit patterns are
+//! roughly logarithmic, so the squared error in this space is a good proxy for
+//! the perceived error of HDR data.
+//!
+//! For each examined partition, the endpoints of each subset are initialized
+//! to the extremes of the principal axis of its pixels. These endpoints are
+//! then quantized for every endpoint mode, and the mode with the smallest
+//! error is picked. Endpoints are optionally refined with least squares.
+
+#![allow(clippy::needless_range_loop)]
+
+use glam::Vec3A;
+
+use crate::{
+    decode::{finish_unquantize, unquantize, BC6HFormat, PARTITION_SET_2},
+    fp16,
+};
+
+use super::CompressionQuality;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Bc6hOptions {
+    pub format: BC6HFormat,
+    /// The number of partitions examined for 2-subset modes.
+    pub partitions: u8,
+    /// The number of least-squares refinement iterations for endpoints.
+    pub refine_iterations: u8,
+}
+impl Bc6hOptions {
+    pub fn new(format: BC6HFormat, quality: CompressionQuality) -> Self {
+        let (partitions, refine_iterations) = match quality {
+            CompressionQuality::Fast => (1, 0),
+            CompressionQuality::Normal => (4, 1),
+            CompressionQuality::High => (12, 2),
+            CompressionQuality::Unreasonable => (32, 4),
+        };
+        Self {
+            format,
+            partitions,
+            refine_iterations,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Mode {
+    header: u8,
+    header_bits: u8,
+    subsets: u8,
+    /// The precision of the first endpoint.
+    endpoint_bits: u8,
+    /// The number of bits of the other endpoints per channel. These are
+    /// deltas to the first endpoint for transformed modes.
+    delta_bits: [u8; 3],
+    transformed: bool,
+}
+const fn mode(header: u8, header_bits: u8, subsets: u8, bits: u8, delta: [u8; 3]) -> Mode {
+    Mode {
+        header,
+        header_bits,
+        subsets,
+        endpoint_bits: bits,
+        delta_bits: delta,
+        transformed: !(bits == delta[0] && bits == delta[1] && bits == delta[2]),
+    }
+}
+const MODES_1: [Mode; 4] = [
+    mode(0b00011, 5, 1, 10, [10, 10, 10]),
+    mode(0b00111, 5, 1, 11, [9, 9, 9]),
+    mode(0b01011, 5, 1, 12, [8, 8, 8]),
+    mode(0b01111, 5, 1, 16, [4, 4, 4]),
+];
+const MODES_2: [Mode; 10] = [
+    mode(0b00, 2, 2, 10, [5, 5, 5]),
+    mode(0b01, 2, 2, 7, [6, 6, 6]),
+    mode(0b00010, 5, 2, 11, [5, 4, 4]),
+    mode(0b00110, 5, 2, 11, [4, 5, 4]),
+    mode(0b01010, 5, 2, 11, [4, 4, 5]),
+    mode(0b01110, 5, 2, 9, [5, 5, 5]),
+    mode(0b10010, 5, 2, 8, [6, 5, 5]),
+    mode(0b10110, 5, 2, 8, [5, 6, 5]),
+    mode(0b11010, 5, 2, 8, [5, 5, 6]),
+    mode(0b11110, 5, 2, 6, [6, 6, 6]),
+];
+
+const WEIGHTS_3: [u8; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u8; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+pub(crate) fn compress_bc6h_block(block: [[f32; 4]; 16], options: Bc6hOptions) -> [u8; 16] {
+    let block = Block::new(block, options.format);
+
+    let mut best = Candidate {
+        error: u64::MAX,
+        mode: MODES_1[0],
+        partition: 0,
+        endpoints: [[[0; 3]; 2]; 2],
+        indexes: [0; 16],
+    };
+
+    let all = [0; 16];
+    let line = block.fit_line(&all, 0);
+    for mode in &MODES_1 {
+        let candidate = block.refine(mode, 0, [line, line], options.refine_iterations);
+        if candidate.error < best.error {
+            best = candidate;
+        }
+    }
+
+    for partition in block.rank_partitions(options.partitions as usize) {
+        let subsets = subset_map(partition);
+        let lines = [block.fit_line(&subsets, 0), block.fit_line(&subsets, 1)];
+        for mode in &MODES_2 {
+            let candidate = block.refine(mode, partition, lines, options.refine_iterations);
+            if candidate.error < best.error {
+                best = candidate;
+            }
+        }
+    }
+
+    best.write()
+}
+
+fn subset_map(partition: u8) -> [u8; 16] {
+    let map = PARTITION_SET_2[partition as usize];
+    std::array::from_fn(|i| map.get_subset_index(i as u8))
+}
+/// Returns the anchor pixel of each subset. The index of an anchor pixel has
+/// its most significant bit implied to be 0.
+fn anchors(mode: &Mode, partition: u8) -> [usize; 2] {
+    if mode.subsets == 1 {
+        [0, 0]
+    } else {
+        [
+            0,
+            PARTITION_SET_2[partition as usize].fixup_index_2 as usize,
+        ]
+    }
+}
+fn weights(mode: &Mode) -> &'static [u8] {
+    if mode.subsets == 1 {
+        &WEIGHTS_4
+    } else {
+        &WEIGHTS_3
+    }
+}
+
+struct Block {
+    format: BC6HFormat,
+    /// The half float bit patterns of the pixels. Signed values use
+    /// sign-magnitude.
+    target: [[i32; 3]; 16],
+    /// The pixels in unquantized space, which is the space endpoints are
+    /// interpolated in.
+    pixels: [Vec3A; 16],
+}
+impl Block {
+    fn new(block: [[f32; 4]; 16], format: BC6HFormat) -> Self {
+        let target = block.map(|[r, g, b, _]| [r, g, b].map(|v| to_half(v, format)));
+        let scale = match format {
+            BC6HFormat::UnsignedF16 => 64.0 / 31.0,
+            BC6HFormat::SignedF16 => 32.0 / 31.0,
+        };
+        let pixels = target.map(|[r, g, b]| Vec3A::new(r as f32, g as f32, b as f32) * scale);
+        Self {
+            format,
+            target,
+            pixels,
+        }
+    }
+
+    /// Returns the best `keep` partitions by how well the pixels of each
+    /// subset fit onto a line.
+    fn rank_partitions(&self, keep: usize) -> Vec<u8> {
+        if keep == 0 {
+            return Vec::new();
+        }
+        let mut scores: Vec<(f32, u8)> = (0..32)
+            .map(|partition| {
+                let subsets = subset_map(partition);
+                let score = self.line_residual(&subsets, 0) + self.line_residual(&subsets, 1);
+                (score, partition)
+            })
+            .collect();
+        scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scores.truncate(keep);
+        scores.into_iter().map(|(_, partition)| partition).collect()
+    }
+
+    fn mean_and_axis(&self, subsets: &[u8; 16], subset: u8) -> (Vec3A, Vec3A) {
+        let mut mean = Vec3A::ZERO;
+        let mut count = 0;
+        for (pixel, &s) in self.pixels.iter().zip(subsets) {
+            if s == subset {
+                mean += *pixel;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return (Vec3A::ZERO, Vec3A::ZERO);
+        }
+        mean /= count as f32;
+
+        let mut cov = [Vec3A::ZERO; 3];
+        for (pixel, &s) in self.pixels.iter().zip(subsets) {
+            if s == subset {
+                let d = *pixel - mean;
+                cov[0] += d * d.x;
+                cov[1] += d * d.y;
+                cov[2] += d * d.z;
+            }
+        }
+        let mut axis = Vec3A::ONE;
+        for _ in 0..8 {
+            axis = Vec3A::new(cov[0].dot(axis), cov[1].dot(axis), cov[2].dot(axis))
+                .normalize_or_zero();
+        }
+        (mean, axis)
+    }
+    fn line_residual(&self, subsets: &[u8; 16], subset: u8) -> f32 {
+        let (mean, axis) = self.mean_and_axis(subsets, subset);
+        let mut residual = 0.0;
+        for (pixel, &s) in self.pixels.iter().zip(subsets) {
+            if s == subset {
+                let d = *pixel - mean;
+                residual += d.length_squared() - d.dot(axis).powi(2);
+            }
+        }
+        residual
+    }
+    /// Returns the endpoints of the principal axis that cover all pixels of
+    /// the subset.
+    fn fit_line(&self, subsets: &[u8; 16], subset: u8) -> [Vec3A; 2] {
+        let (mean, axis) = self.mean_and_axis(subsets, subset);
+        let mut min = 0.0_f32;
+        let mut max = 0.0_f32;
+        for (pixel, &s) in self.pixels.iter().zip(subsets) {
+            if s == subset {
+                let t = (*pixel - mean).dot(axis);
+                min = min.min(t);
+                max = max.max(t);
+            }
+        }
+        [mean + axis * min, mean + axis * max]
+    }
+
+    /// Encodes the block with the given endpoints and then refines the
+    /// endpoints with least squares.
+    fn refine(
+        &self,
+        mode: &Mode,
+        partition: u8,
+        lines: [[Vec3A; 2]; 2],
+        iterations: u8,
+    ) -> Candidate {
+        let subsets = if mode.subsets == 1 {
+            [0; 16]
+        } else {
+            subset_map(partition)
+        };
+
+        let mut best = self.encode(mode, partition, &subsets, lines);
+        let mut current = best.indexes;
+        for _ in 0..iterations {
+            let lines = match self.least_squares(mode, &subsets, &current) {
+                Some(lines) => lines,
+                None => break,
+            };
+            let candidate = self.encode(mode, partition, &subsets, lines);
+            if candidate.indexes == current {
+                if candidate.error < best.error {
+                    best = candidate;
+                }
+                break;
+            }
+            current = candidate.indexes;
+            if candidate.error < best.error {
+                best = candidate;
+            }
+        }
+        best
+    }
+    fn least_squares(
+        &self,
+        mode: &Mode,
+        subsets: &[u8; 16],
+        indexes: &[u8; 16],
+    ) -> Option<[[Vec3A; 2]; 2]> {
+        let weights = weights(mode);
+        let mut lines = [[Vec3A::ZERO; 2]; 2];
+        for (subset, line) in lines.iter_mut().enumerate().take(mode.subsets as usize) {
+            let (mut aa, mut ab, mut bb) = (0.0, 0.0, 0.0);
+            let (mut ax, mut bx) = (Vec3A::ZERO, Vec3A::ZERO);
+            for i in 0..16 {
+                if subsets[i] as usize != subset {
+                    continue;
+                }
+                let t = weights[indexes[i] as usize] as f32 / 64.0;
+                let s = 1.0 - t;
+                aa += s * s;
+                ab += s * t;
+                bb += t * t;
+                ax += self.pixels[i] * s;
+                bx += self.pixels[i] * t;
+            }
+            let det = aa * bb - ab * ab;
+            if det.abs() < 1e-6 {
+                return None;
+            }
+            *line = [(ax * bb - bx * ab) / det, (bx * aa - ax * ab) / det];
+        }
+        Some(lines)
+    }
+
+    fn encode(
+        &self,
+        mode: &Mode,
+        partition: u8,
+        subsets: &[u8; 16],
+        lines: [[Vec3A; 2]; 2],
+    ) -> Candidate {
+        let format = self.format;
+        let mut endpoints = [[[0_i32; 3]; 2]; 2];
+        for s in 0..mode.subsets as usize {
+            for e in 0..2 {
+                endpoints[s][e] = lines[s][e]
+                    .to_array()
+                    .map(|v| quantize(v, mode.endpoint_bits, format));
+            }
+        }
+
+        let anchors = anchors(mode, partition);
+        let mut indexes = [0_u8; 16];
+        let half = if mode.subsets == 1 { 8 } else { 4 };
+        let max_index = half * 2 - 1;
+        let mut error = 0;
+        for s in 0..mode.subsets as usize {
+            // the palette of swapped endpoints is reversed, so the error stays
+            // the same
+            error += self.assign_indexes(
+                mode,
+                subsets,
+                s,
+                endpoints[s],
+                false,
+                anchors[s],
+                &mut indexes,
+            );
+            if indexes[anchors[s]] >= half {
+                endpoints[s].swap(0, 1);
+                for i in 0..16 {
+                    if subsets[i] as usize == s {
+                        indexes[i] = max_index - indexes[i];
+                    }
+                }
+            }
+        }
+
+        let mut changed = false;
+        if mode.transformed {
+            // all other endpoints are stored as deltas to the first
+            let base = endpoints[0][0];
+            for s in 0..mode.subsets as usize {
+                for e in 0..2 {
+                    if s == 0 && e == 0 {
+                        continue;
+                    }
+                    for c in 0..3 {
+                        let bits = mode.delta_bits[c];
+                        let min = -(1 << (bits - 1));
+                        let max = (1 << (bits - 1)) - 1;
+                        let value = base[c] + (endpoints[s][e][c] - base[c]).clamp(min, max);
+                        changed |= value != endpoints[s][e][c];
+                        endpoints[s][e][c] = value;
+                    }
+                }
+            }
+        }
+
+        if changed {
+            // the clamped endpoints may require different indexes, but the
+            // anchor index must still be < half
+            error = 0;
+            for s in 0..mode.subsets as usize {
+                error += self.assign_indexes(
+                    mode,
+                    subsets,
+                    s,
+                    endpoints[s],
+                    true,
+                    anchors[s],
+                    &mut indexes,
+                );
+            }
+        }
+
+        Candidate {
+            error,
+            mode: *mode,
+            partition,
+            endpoints,
+            indexes,
+        }
+    }
+
+    fn palette(&self, mode: &Mode, endpoints: [[i32; 3]; 2]) -> ([[i32; 3]; 16], usize) {
+        let format = self.format;
+        let bits = mode.endpoint_bits;
+        let a = endpoints[0].map(|v| unquantize(v, bits, format));
+        let b = endpoints[1].map(|v| unquantize(v, bits, format));
+        let weights = weights(mode);
+        let mut palette = [[0; 3]; 16];
+        for (entry, &w) in palette.iter_mut().zip(weights) {
+            let w = w as i32;
+            *entry = std::array::from_fn(|c| {
+                let value = (a[c] * (64 - w) + b[c] * w + 32) >> 6;
+                from_half(finish_unquantize(value, format), format)
+            });
+        }
+        (palette, weights.len())
+    }
+    /// Assigns each pixel of the subset the closest palette entry. Returns the
+    /// total error.
+    #[allow(clippy::too_many_arguments)]
+    fn assign_indexes(
+        &self,
+        mode: &Mode,
+        subsets: &[u8; 16],
+        subset: usize,
+        endpoints: [[i32; 3]; 2],
+        restrict_anchor: bool,
+        anchor: usize,
+        indexes: &mut [u8; 16],
+    ) -> u64 {
+        let (palette, len) = self.palette(mode, endpoints);
+        let mut error = 0;
+        for i in 0..16 {
+            if subsets[i] as usize != subset {
+                continue;
+            }
+            let len = if restrict_anchor && i == anchor {
+                len / 2
+            } else {
+                len
+            };
+            let mut best = (u64::MAX, 0);
+            for (index, entry) in palette[..len].iter().enumerate() {
+                let error = distance(self.target[i], *entry);
+                if error < best.0 {
+                    best = (error, index as u8);
+                }
+            }
+            indexes[i] = best.1;
+            error += best.0;
+        }
+        error
+    }
+}
+
+fn distance(a: [i32; 3], b: [i32; 3]) -> u64 {
+    (0..3).map(|c| ((a[c] - b[c]) as i64).pow(2) as u64).sum()
+}
+
+/// Converts the given value to the bit pattern of a half float that BC6H can
+/// represent. Signed values use sign-magnitude.
+fn to_half(value: f32, format: BC6HFormat) -> i32 {
+    const MAX: f32 = 65504.0;
+    match format {
+        BC6HFormat::UnsignedF16 => {
+            // this also maps NaN to 0
+            if value > 0.0 {
+                fp16::from_f32(value.min(MAX)) as i32
+            } else {
+                0
+            }
+        }
+        BC6HFormat::SignedF16 => {
+            if value.is_nan() {
+                return 0;
+            }
+            let half = fp16::from_f32(value.clamp(-MAX, MAX));
+            let magnitude = (half & 0x7FFF) as i32;
+            if half & 0x8000 != 0 {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+    }
+}
+fn from_half(half: u16, format: BC6HFormat) -> i32 {
+    match format {
+        BC6HFormat::UnsignedF16 => half as i32,
+        BC6HFormat::SignedF16 => {
+            let magnitude = (half & 0x7FFF) as i32;
+            if half & 0x8000 != 0 {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+    }
+}
+
+/// Returns the quantized endpoint value with `bits` bits that unquantizes to
+/// the value closest to the given one.
+fn quantize(value: f32, bits: u8, format: BC6HFormat) -> i32 {
+    match format {
+        BC6HFormat::UnsignedF16 => {
+            if bits >= 15 {
+                return (value.round() as i32).clamp(0, (1 << bits) - 1);
+            }
+            let max = (1 << bits) - 1;
+            let estimate = value * (1 << bits) as f32 / 65536.0 - 0.5;
+            closest(value, bits, format, estimate.floor() as i32, 0, max)
+        }
+        BC6HFormat::SignedF16 => {
+            if bits >= 16 {
+                // -32768 would decode to -infinity
+                return (value.round() as i32).clamp(-32767, 32767);
+            }
+            let max = (1 << (bits - 1)) - 1;
+            let estimate = value.abs() * (1 << (bits - 1)) as f32 / 32768.0 - 0.5;
+            let magnitude = closest(value.abs(), bits, format, estimate.floor() as i32, 0, max);
+            if value < 0.0 {
+                -magnitude
+            } else {
+                magnitude
+            }
+        }
+    }
+}
+fn closest(value: f32, bits: u8, format: BC6HFormat, low: i32, min: i32, max: i32) -> i32 {
+    let a = low.clamp(min, max);
+    let b = (low + 1).clamp(min, max);
+    let error = |q: i32| (unquantize(q, bits, format) as f32 - value).abs();
+    if error(b) < error(a) {
+        b
+    } else {
+        a
+    }
+}
+
+struct Candidate {
+    error: u64,
+    mode: Mode,
+    partition: u8,
+    endpoints: [[[i32; 3]; 2]; 2],
+    indexes: [u8; 16],
+}
+impl Candidate {
+    fn write(&self) -> [u8; 16] {
+        let mode = &self.mode;
+        let base = self.endpoints[0][0];
+        let mask = |value: i32, bits: u8| (value as u32) & ((1 << bits) - 1);
+        let stored = |s: usize, e: usize| {
+            let value = self.endpoints[s][e];
+            if s == 0 && e == 0 {
+                let bits = mode.endpoint_bits;
+                Rgb::new(value.map(|v| mask(v, bits)))
+            } else if mode.transformed {
+                Rgb::new(std::array::from_fn(|c| {
+                    mask(value[c] - base[c], mode.delta_bits[c])
+                }))
+            } else {
+                Rgb::new(std::array::from_fn(|c| mask(value[c], mode.delta_bits[c])))
+            }
+        };
+
+        let mut writer = BitWriter::new();
+        writer.write(mode.header as u32, mode.header_bits);
+        if mode.subsets == 1 {
+            let a = stored(0, 0);
+            let b = stored(0, 1);
+            let extension = mode.endpoint_bits - 10;
+            let b_bits = mode.delta_bits[0];
+            writer.write(a.r & 0x3FF, 10);
+            writer.write(a.g & 0x3FF, 10);
+            writer.write(a.b & 0x3FF, 10);
+            writer.write(b.r, b_bits);
+            writer.write(reverse(a.r >> 10, extension), extension);
+            writer.write(b.g, b_bits);
+            writer.write(reverse(a.g >> 10, extension), extension);
+            writer.write(b.b, b_bits);
+            writer.write(reverse(a.b >> 10, extension), extension);
+
+            for (i, &index) in self.indexes.iter().enumerate() {
+                writer.write(index as u32, if i == 0 { 3 } else { 4 });
+            }
+        } else {
+            write_endpoints_two(
+                &mut writer,
+                mode,
+                [stored(0, 0), stored(0, 1), stored(1, 0), stored(1, 1)],
+            );
+            writer.write(self.partition as u32, 5);
+            let anchors = anchors(mode, self.partition);
+            for (i, &index) in self.indexes.iter().enumerate() {
+                writer.write(index as u32, if anchors.contains(&i) { 2 } else { 3 });
+            }
+        }
+        writer.finish()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Rgb {
+    r: u32,
+    g: u32,
+    b: u32,
+}
+impl Rgb {
+    fn new([r, g, b]: [u32; 3]) -> Self {
+        Self { r, g, b }
+    }
+}
+
+fn reverse(value: u32, bits: u8) -> u32 {
+    if bits == 0 {
+        0
+    } else {
+        value.reverse_bits() >> (32 - bits)
+    }
+}
+
+fn write_endpoints_two(writer: &mut BitWriter, mode: &Mode, [w, x, y, z]: [Rgb; 4]) {
+    /// The inverse of the `consume!` macro of the decoder. See there for
+    /// the syntax.
+    macro_rules! put {
+        ($i1:ident, $i2:ident, $index:literal) => {
+            writer.write(($i2.$i1 >> $index) & 1, 1);
+        };
+        ($i1:ident, $i2:ident, $high:literal .. 0) => {
+            writer.write($i2.$i1 & ((1 << ($high + 1)) - 1), $high + 1);
+        };
+    }
+
+    match mode.header {
+        0b00 => {
+            put!(g, y, 4);
+            put!(b, y, 4);
+            put!(b, z, 4);
+            put!(r, w, 9..0);
+            put!(g, w, 9..0);
+            put!(b, w, 9..0);
+            put!(r, x, 4..0);
+            put!(g, z, 4);
+            put!(g, y, 3..0);
+            put!(g, x, 4..0);
+            put!(b, z, 0);
+            put!(g, z, 3..0);
+            put!(b, x, 4..0);
+            put!(b, z, 1);
+            put!(b, y, 3..0);
+            put!(r, y, 4..0);
+            put!(b, z, 2);
+            put!(r, z, 4..0);
+            put!(b, z, 3);
+        }
+        0b01 => {
+            put!(g, y, 5);
+            put!(g, z, 4);
+            put!(g, z, 5);
+            put!(r, w, 6..0);
+            put!(b, z, 0);
+            put!(b, z, 1);
+            put!(b, y, 4);
+            put!(g, w, 6..0);
+            put!(b, y, 5);
+            put!(b, z, 2);
+            put!(g, y, 4);
+            put!(b, w, 6..0);
+            put!(b, z, 3);
+            put!(b, z, 5);
+            put!(b, z, 4);
+            put!(r, x, 5..0);
+            put!(g, y, 3..0);
+            put!(g, x, 5..0);
+            put!(g, z, 3..0);
+            put!(b, x, 5..0);
+            put!(b, y, 3..0);
+            put!(r, y, 5..0);
+            put!(r, z, 5..0);
+        }
+        0b00010 => {
+            put!(r, w, 9..0);
+            put!(g, w, 9..0);
+            put!(b, w, 9..0);
+            put!(r, x, 4..0);
+            put!(r, w, 10);
+            put!(g, y, 3..0);
+            put!(g, x, 3..0);
+            put!(g, w, 10);
+            put!(b, z, 0);
+            put!(g, z, 3..0);
+            put!(b, x, 3..0);
+            put!(b, w, 10);
+            put!(b, z, 1);
+            put!(b, y, 3..0);
+            put!(r, y, 4..0);
+            put!(b, z, 2);
+            put!(r, z, 4..0);
+            put!(b, z, 3);
+        }
+        0b00110 => {
+            put!(r, w, 9..0);
+            put!(g, w, 9..0);
+            put!(b, w, 9..0);
+            put!(r, x, 3..0);
+            put!(r, w, 10);
+            put!(g, z, 4);
+            put!(g, y, 3..0);
+            put!(g, x, 4..0);
+            put!(g, w, 10);
+            put!(g, z, 3..0);
+            put!(b, x, 3..0);
+            put!(b, w, 10);
+            put!(b, z, 1);
+            put!(b, y, 3..0);
+            put!(r, y, 3..0);
+            put!(b, z, 0);
+            put!(b, z, 2);
+            put!(r, z, 3..0);
+            put!(g, y, 4);
+            put!(b, z, 3);
+        }
+        0b01010 => {
+            put!(r, w, 9..0);
+            put!(g, w, 9..0);
+            put!(b, w, 9..0);
+            put!(r, x, 3..0);
+            put!(r, w, 10);
+            put!(b, y, 4);
+            put!(g, y, 3..0);
+            put!(g, x, 3..0);
+            put!(g, w, 10);
+            put!(b, z, 0);
+            put!(g, z, 3..0);
+            put!(b, x, 4..0);
+            put!(b, w, 10);
+            put!(b, y, 3..0);
+            put!(r, y, 3..0);
+            put!(b, z, 1);
+            put!(b, z, 2);
+            put!(r, z, 3..0);
+            put!(b, z, 4);
+            put!(b, z, 3);
+        }
+        0b01110 => {
+            put!(r, w, 8..0);
+            put!(b, y, 4);
+            put!(g, w, 8..0);
+            put!(g, y, 4);
+            put!(b, w, 8..0);
+            put!(b, z, 4);
+            put!(r, x, 4..0);
+            put!(g, z, 4);
+            put!(g, y, 3..0);
+            put!(g, x, 4..0);
+            put!(b, z, 0);
+            put!(g, z, 3..0);
+            put!(b, x, 4..0);
+            put!(b, z, 1);
+            put!(b, y, 3..0);
+            put!(r, y, 4..0);
+            put!(b, z, 2);
+            put!(r, z, 4..0);
+            put!(b, z, 3);
+        }
+        0b10010 => {
+            put!(r, w, 7..0);
+            put!(g, z, 4);
+            put!(b, y, 4);
+            put!(g, w, 7..0);
+            put!(b, z, 2);
+            put!(g, y, 4);
+            put!(b, w, 7..0);
+            put!(b, z, 3);
+            put!(b, z, 4);
+            put!(r, x, 5..0);
+            put!(g, y, 3..0);
+            put!(g, x, 4..0);
+            put!(b, z, 0);
+            put!(g, z, 3..0);
+            put!(b, x, 4..0);
+            put!(b, z, 1);
+            put!(b, y, 3..0);
+            put!(r, y, 5..0);
+            put!(r, z, 5..0);
+        }
+        0b10110 => {
+            put!(r, w, 7..0);
+            put!(b, z, 0);
+            put!(b, y, 4);
+            put!(g, w, 7..0);
+            put!(g, y, 5);
+            put!(g, y, 4);
+            put!(b, w, 7..0);
+            put!(g, z, 5);
+            put!(b, z, 4);
+            put!(r, x, 4..0);
+            put!(g, z, 4);
+            put!(g, y, 3..0);
+            put!(g, x, 5..0);
+            put!(g, z, 3..0);
+            put!(b, x, 4..0);
+            put!(b, z, 1);
+            put!(b, y, 3..0);
+            put!(r, y, 4..0);
+            put!(b, z, 2);
+            put!(r, z, 4..0);
+            put!(b, z, 3);
+        }
+        0b11010 => {
+            put!(r, w, 7..0);
+            put!(b, z, 1);
+            put!(b, y, 4);
+            put!(g, w, 7..0);
+            put!(b, y, 5);
+            put!(g, y, 4);
+            put!(b, w, 7..0);
+            put!(b, z, 5);
+            put!(b, z, 4);
+            put!(r, x, 4..0);
+            put!(g, z, 4);
+            put!(g, y, 3..0);
+            put!(g, x, 4..0);
+            put!(b, z, 0);
+            put!(g, z, 3..0);
+            put!(b, x, 5..0);
+            put!(b, y, 3..0);
+            put!(r, y, 4..0);
+            put!(b, z, 2);
+            put!(r, z, 4..0);
+            put!(b, z, 3);
+        }
+        0b11110 => {
+            put!(r, w, 5..0);
+            put!(g, z, 4);
+            put!(b, z, 0);
+            put!(b, z, 1);
+            put!(b, y, 4);
+            put!(g, w, 5..0);
+            put!(g, y, 5);
+            put!(b, y, 5);
+            put!(b, z, 2);
+            put!(g, y, 4);
+            put!(b, w, 5..0);
+            put!(g, z, 5);
+            put!(b, z, 3);
+            put!(b, z, 5);
+            put!(b, z, 4);
+            put!(r, x, 5..0);
+            put!(g, y, 3..0);
+            put!(g, x, 5..0);
+            put!(g, z, 3..0);
+            put!(b, x, 5..0);
+            put!(b, y, 3..0);
+            put!(r, y, 5..0);
+            put!(r, z, 5..0);
+        }
+        _ => unreachable!("not a 2-subset mode"),
+    }
+}
+
+struct BitWriter {
+    value: u128,
+    len: u8,
+}
+impl BitWriter {
+    fn new() -> Self {
+        Self { value: 0, len: 0 }
+    }
+    fn write(&mut self, value: u32, bits: u8) {
+        debug_assert!(bits == 0 || (value as u64) < 1 << bits);
+        self.value |= (value as u128) << self.len;
+        self.len += bits;
+    }
+    fn finish(self) -> [u8; 16] {
+        debug_assert_eq!(self.len, 128);
+        self.value.to_le_bytes()
+    }
+}
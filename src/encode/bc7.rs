@@ -0,0 +1,808 @@
+//! A BC7 encoder.
+//!
+//! Each enabled mode is tried and the encoding with the smallest error is
+//! picked. For all modes, the encoder works in 3 steps:
+//!
+//! 1. Modes with 2 or 3 subsets rank all partitions by how well the pixels of
+//!    each subset fit onto a line. Only the best partitions are examined.
+//! 2. The endpoints of each subset are initialized to the extremes of the
+//!    principal axis of its pixels, quantized, and pixels are assigned the
+//!    closest palette entry.
+//! 3. The endpoints are refined with least squares for a number of
+//!    iterations.
+//!
+//! The encoder works with values in the range 0 to 255 and uses the squared
+//! error across all 4 channels.
+
+#![allow(clippy::needless_range_loop)]
+
+use crate::decode::{PARTITION_SET_2, PARTITION_SET_3};
+
+use super::{Bc7Modes, Bc7Options};
+
+type Pixel = [f32; 4];
+
+#[derive(Clone, Copy)]
+struct ModeInfo {
+    subsets: u8,
+    partition_bits: u8,
+    rotation_bits: u8,
+    index_selection_bits: u8,
+    color_bits: u8,
+    alpha_bits: u8,
+    /// Whether each endpoint has its own P-bit.
+    endpoint_p_bits: bool,
+    /// Whether the endpoints of each subset share a P-bit.
+    shared_p_bits: bool,
+    index_bits: u8,
+    /// The number of bits of the secondary index set. 0 if the mode doesn't
+    /// have a secondary index set.
+    index_bits_2: u8,
+}
+#[allow(clippy::too_many_arguments)]
+const fn mode(
+    subsets: u8,
+    partition_bits: u8,
+    rotation_bits: u8,
+    index_selection_bits: u8,
+    color_bits: u8,
+    alpha_bits: u8,
+    endpoint_p_bits: bool,
+    shared_p_bits: bool,
+    index_bits: u8,
+    index_bits_2: u8,
+) -> ModeInfo {
+    ModeInfo {
+        subsets,
+        partition_bits,
+        rotation_bits,
+        index_selection_bits,
+        color_bits,
+        alpha_bits,
+        endpoint_p_bits,
+        shared_p_bits,
+        index_bits,
+        index_bits_2,
+    }
+}
+const MODES: [ModeInfo; 8] = [
+    mode(3, 4, 0, 0, 4, 0, true, false, 3, 0),
+    mode(2, 6, 0, 0, 6, 0, false, true, 3, 0),
+    mode(3, 6, 0, 0, 5, 0, false, false, 2, 0),
+    mode(2, 6, 0, 0, 7, 0, true, false, 2, 0),
+    mode(1, 0, 2, 1, 5, 6, false, false, 2, 3),
+    mode(1, 0, 2, 0, 7, 8, false, false, 2, 2),
+    mode(1, 0, 0, 0, 7, 7, true, false, 4, 0),
+    mode(2, 6, 0, 0, 5, 5, true, false, 2, 0),
+];
+
+const WEIGHTS_2: [u8; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u8; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u8; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+fn weights(index_bits: u8) -> &'static [u8] {
+    match index_bits {
+        2 => &WEIGHTS_2,
+        3 => &WEIGHTS_3,
+        _ => &WEIGHTS_4,
+    }
+}
+fn interpolate(e0: u8, e1: u8, weight: u8) -> u8 {
+    let (e0, e1, weight) = (e0 as u16, e1 as u16, weight as u16);
+    (((64 - weight) * e0 + weight * e1 + 32) >> 6) as u8
+}
+
+pub(crate) fn compress_bc7_block(
+    block: [[f32; 4]; 16],
+    options: Bc7Options,
+    channel_weights: [f32; 4],
+) -> [u8; 16] {
+    let block = block.map(|pixel| pixel.map(|v| (v * 255.0).clamp(0.0, 255.0)));
+
+    let mut modes = options.modes;
+    if modes.is_empty() {
+        modes = Bc7Modes::MODE_6;
+    }
+    let partitions = options.partitions.clamp(1, 64) as usize;
+    let iterations = options.refine_iterations;
+
+    let mut best = Candidate {
+        error: f32::INFINITY,
+        block: [0; 16],
+    };
+    let mut consider = |candidate: Candidate| {
+        if candidate.error < best.error {
+            best = candidate;
+        }
+    };
+
+    if modes.intersects(Bc7Modes::MODE_1 | Bc7Modes::MODE_3 | Bc7Modes::MODE_7) {
+        let ranked = rank_partitions(&block, 2, 64, partitions);
+        for mode in [1, 3, 7] {
+            if modes.contains(Bc7Modes::from_mode(mode)) {
+                for &partition in &ranked {
+                    consider(encode_subsets(
+                        &block,
+                        channel_weights,
+                        mode,
+                        partition,
+                        iterations,
+                    ));
+                }
+            }
+        }
+    }
+    if modes.contains(Bc7Modes::MODE_0) {
+        for partition in rank_partitions(&block, 3, 16, partitions) {
+            consider(encode_subsets(
+                &block,
+                channel_weights,
+                0,
+                partition,
+                iterations,
+            ));
+        }
+    }
+    if modes.contains(Bc7Modes::MODE_2) {
+        for partition in rank_partitions(&block, 3, 64, partitions) {
+            consider(encode_subsets(
+                &block,
+                channel_weights,
+                2,
+                partition,
+                iterations,
+            ));
+        }
+    }
+    if modes.contains(Bc7Modes::MODE_6) {
+        consider(encode_subsets(&block, channel_weights, 6, 0, iterations));
+    }
+    for mode in [4, 5] {
+        if modes.contains(Bc7Modes::from_mode(mode)) {
+            consider(encode_separate_alpha(
+                &block,
+                channel_weights,
+                mode,
+                iterations,
+            ));
+        }
+    }
+
+    best.block
+}
+
+struct Candidate {
+    error: f32,
+    block: [u8; 16],
+}
+
+fn subset_of(subsets: u8, partition: u8, pixel: usize) -> usize {
+    match subsets {
+        2 => PARTITION_SET_2[partition as usize].get_subset_index(pixel as u8) as usize,
+        3 => PARTITION_SET_3[partition as usize].get_subset_index(pixel as u8) as usize,
+        _ => 0,
+    }
+}
+/// Returns the anchor pixel of each subset. The index of an anchor pixel has
+/// its most significant bit implied to be 0.
+fn anchors(subsets: u8, partition: u8) -> [usize; 3] {
+    let mut anchors = [0; 3];
+    let fixups = match subsets {
+        2 => [PARTITION_SET_2[partition as usize].fixup_index_2, 0],
+        3 => {
+            let map = PARTITION_SET_3[partition as usize];
+            [map.fixup_index_2, map.fixup_index_3]
+        }
+        _ => return anchors,
+    };
+    // the fixups are sorted by pixel, so we have to find their subsets
+    for &fixup in &fixups[..subsets as usize - 1] {
+        anchors[subset_of(subsets, partition, fixup as usize)] = fixup as usize;
+    }
+    anchors
+}
+
+/// Returns the best `keep` partitions out of the first `count` partitions.
+fn rank_partitions(block: &[Pixel; 16], subsets: u8, count: u8, keep: usize) -> Vec<u8> {
+    if keep >= count as usize {
+        return (0..count).collect();
+    }
+
+    let stats = block.map(Stats::from_pixel);
+    let mut scores: Vec<(f32, u8)> = (0..count)
+        .map(|partition| {
+            let mut subset_stats = [Stats::default(); 3];
+            for (i, pixel_stats) in stats.iter().enumerate() {
+                subset_stats[subset_of(subsets, partition, i)].add(pixel_stats);
+            }
+            let score = subset_stats[..subsets as usize]
+                .iter()
+                .map(Stats::line_residual)
+                .sum();
+            (score, partition)
+        })
+        .collect();
+    scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+    scores.truncate(keep);
+    scores.into_iter().map(|(_, partition)| partition).collect()
+}
+
+/// The sums needed to compute the covariance matrix of a set of pixels.
+#[derive(Clone, Copy, Default)]
+struct Stats {
+    count: f32,
+    sum: Pixel,
+    /// The upper triangle of the sum of outer products.
+    products: [f32; 10],
+}
+impl Stats {
+    const PAIRS: [(usize, usize); 10] = [
+        (0, 0),
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (1, 1),
+        (1, 2),
+        (1, 3),
+        (2, 2),
+        (2, 3),
+        (3, 3),
+    ];
+
+    fn from_pixel(pixel: Pixel) -> Self {
+        Self {
+            count: 1.0,
+            sum: pixel,
+            products: Self::PAIRS.map(|(i, j)| pixel[i] * pixel[j]),
+        }
+    }
+    fn add(&mut self, other: &Self) {
+        self.count += other.count;
+        for c in 0..4 {
+            self.sum[c] += other.sum[c];
+        }
+        for i in 0..10 {
+            self.products[i] += other.products[i];
+        }
+    }
+    /// An estimate of the sum of squared distances of the pixels to their
+    /// best fitting line.
+    fn line_residual(&self) -> f32 {
+        if self.count <= 1.0 {
+            return 0.0;
+        }
+        let mean = self.sum.map(|v| v / self.count);
+        let mut cov = [[0.0_f32; 4]; 4];
+        for (&(i, j), &product) in Self::PAIRS.iter().zip(&self.products) {
+            let value = product - mean[i] * self.sum[j];
+            cov[i][j] = value;
+            cov[j][i] = value;
+        }
+        let trace = cov[0][0] + cov[1][1] + cov[2][2] + cov[3][3];
+        trace - largest_eigenvalue(&cov)
+    }
+}
+
+/// Estimates the largest eigenvalue of a covariance matrix with a few steps
+/// of power iteration.
+fn largest_eigenvalue(cov: &[[f32; 4]; 4]) -> f32 {
+    let axis = power_iteration(cov, 3);
+    let next: Pixel = std::array::from_fn(|i| (0..4).map(|j| cov[i][j] * axis[j]).sum());
+    next.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+/// Returns an approximation of the principal axis of a covariance matrix.
+/// The result is normalized or zero.
+fn power_iteration(cov: &[[f32; 4]; 4], steps: u8) -> Pixel {
+    // start with the row of the channel with the largest variance
+    let mut largest = 0;
+    for c in 1..4 {
+        if cov[c][c] > cov[largest][largest] {
+            largest = c;
+        }
+    }
+    let mut axis = cov[largest];
+    for _ in 0..steps {
+        let len = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if len < 1e-6 {
+            return [0.0; 4];
+        }
+        axis = axis.map(|v| v / len);
+        axis = std::array::from_fn(|i| (0..4).map(|j| cov[i][j] * axis[j]).sum());
+    }
+    let len = axis.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if len < 1e-6 {
+        return [0.0; 4];
+    }
+    axis.map(|v| v / len)
+}
+
+fn mean(pixels: &[Pixel]) -> Pixel {
+    let mut mean = [0.0; 4];
+    for pixel in pixels {
+        for c in 0..4 {
+            mean[c] += pixel[c];
+        }
+    }
+    mean.map(|v| v / pixels.len().max(1) as f32)
+}
+/// Returns the principal axis of the given pixels.
+fn principal_axis(pixels: &[Pixel], mean: Pixel) -> Pixel {
+    let mut cov = [[0.0_f32; 4]; 4];
+    for pixel in pixels {
+        let d: Pixel = std::array::from_fn(|c| pixel[c] - mean[c]);
+        for i in 0..4 {
+            for j in 0..4 {
+                cov[i][j] += d[i] * d[j];
+            }
+        }
+    }
+    power_iteration(&cov, 4)
+}
+/// Returns the endpoints of the principal axis that cover all pixels.
+fn initial_endpoints(pixels: &[Pixel]) -> [Pixel; 2] {
+    let mean = mean(pixels);
+    let axis = principal_axis(pixels, mean);
+    let (mut min, mut max) = (0.0_f32, 0.0_f32);
+    for pixel in pixels {
+        let t: f32 = (0..4).map(|c| (pixel[c] - mean[c]) * axis[c]).sum();
+        min = min.min(t);
+        max = max.max(t);
+    }
+    let at =
+        |t: f32| -> Pixel { std::array::from_fn(|c| (mean[c] + t * axis[c]).clamp(0.0, 255.0)) };
+    [at(min), at(max)]
+}
+
+fn promote(value: u8, bits: u8) -> u8 {
+    if bits >= 8 {
+        return value;
+    }
+    let value = value << (8 - bits);
+    value | value >> bits
+}
+/// Quantizes a single channel. Returns the stored value (without P-bit) and
+/// the value after expansion to 8 bits.
+fn quantize_channel(value: f32, bits: u8, p_bit: Option<u8>) -> (u8, u8) {
+    let (total_bits, p) = match p_bit {
+        Some(p) => (bits + 1, p),
+        None => (bits, 0),
+    };
+    let max = (1_u16 << bits) - 1;
+    let scaled = value / 255.0 * ((1_u16 << total_bits) - 1) as f32;
+    let guess = if p_bit.is_some() {
+        ((scaled - p as f32) / 2.0).round()
+    } else {
+        scaled.round()
+    };
+    let guess = guess.clamp(0.0, max as f32) as u16;
+
+    let mut best = (0, 0, f32::INFINITY);
+    for q in guess.saturating_sub(1)..=(guess + 1).min(max) {
+        let raw = if p_bit.is_some() {
+            q << 1 | p as u16
+        } else {
+            q
+        };
+        let expanded = promote(raw as u8, total_bits);
+        let error = (expanded as f32 - value).abs();
+        if error < best.2 {
+            best = (q as u8, expanded, error);
+        }
+    }
+    (best.0, best.1)
+}
+
+/// An endpoint quantized to the precision of a mode.
+#[derive(Clone, Copy, Default)]
+struct Endpoint {
+    stored: [u8; 4],
+    p: u8,
+    expanded: [u8; 4],
+}
+fn quantize_endpoint(value: Pixel, mode: ModeInfo, p_bit: Option<u8>) -> (Endpoint, f32) {
+    let mut endpoint = Endpoint {
+        p: p_bit.unwrap_or(0),
+        ..Endpoint::default()
+    };
+    let mut error = 0.0;
+    for c in 0..4 {
+        let bits = if c < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+        if bits == 0 {
+            endpoint.expanded[c] = 255;
+            continue;
+        }
+        let (stored, expanded) = quantize_channel(value[c], bits, p_bit);
+        endpoint.stored[c] = stored;
+        endpoint.expanded[c] = expanded;
+        error += (expanded as f32 - value[c]).powi(2);
+    }
+    (endpoint, error)
+}
+/// Quantizes the 2 endpoints of a subset, choosing the best P-bits.
+fn quantize_endpoints(values: [Pixel; 2], mode: ModeInfo) -> [Endpoint; 2] {
+    if mode.endpoint_p_bits {
+        values.map(|value| {
+            let (e0, error0) = quantize_endpoint(value, mode, Some(0));
+            let (e1, error1) = quantize_endpoint(value, mode, Some(1));
+            if error0 <= error1 {
+                e0
+            } else {
+                e1
+            }
+        })
+    } else if mode.shared_p_bits {
+        let mut best = ([Endpoint::default(); 2], f32::INFINITY);
+        for p in 0..2 {
+            let (e0, error0) = quantize_endpoint(values[0], mode, Some(p));
+            let (e1, error1) = quantize_endpoint(values[1], mode, Some(p));
+            if error0 + error1 < best.1 {
+                best = ([e0, e1], error0 + error1);
+            }
+        }
+        best.0
+    } else {
+        values.map(|value| quantize_endpoint(value, mode, None).0)
+    }
+}
+
+fn pixel_error(a: Pixel, b: [u8; 4], channel_weights: Pixel) -> f32 {
+    (0..4)
+        .map(|c| channel_weights[c] * (a[c] - b[c] as f32).powi(2))
+        .sum()
+}
+
+/// Assigns each pixel the closest palette entry. Returns the total error.
+fn assign_indexes(
+    pixels: &[Pixel],
+    endpoints: [[u8; 4]; 2],
+    index_bits: u8,
+    channel_weights: Pixel,
+    indexes: &mut [u8],
+) -> f32 {
+    let weights = weights(index_bits);
+    let mut palette = [[0_u8; 4]; 16];
+    for (entry, &w) in palette.iter_mut().zip(weights) {
+        *entry = std::array::from_fn(|c| interpolate(endpoints[0][c], endpoints[1][c], w));
+    }
+    let palette = &palette[..weights.len()];
+
+    let mut total = 0.0;
+    for (pixel, index) in pixels.iter().zip(indexes.iter_mut()) {
+        let mut best = (0, f32::INFINITY);
+        for (i, &entry) in palette.iter().enumerate() {
+            let error = pixel_error(*pixel, entry, channel_weights);
+            if error < best.1 {
+                best = (i as u8, error);
+            }
+        }
+        *index = best.0;
+        total += best.1;
+    }
+    total
+}
+/// Finds the endpoints that minimize the squared error for the given indexes.
+fn least_squares(pixels: &[Pixel], indexes: &[u8], index_bits: u8) -> Option<[Pixel; 2]> {
+    let weights = weights(index_bits);
+    let (mut aa, mut ab, mut bb) = (0.0_f32, 0.0_f32, 0.0_f32);
+    let mut ax = [0.0_f32; 4];
+    let mut bx = [0.0_f32; 4];
+    for (pixel, &index) in pixels.iter().zip(indexes) {
+        let t = weights[index as usize] as f32 / 64.0;
+        let s = 1.0 - t;
+        aa += s * s;
+        ab += s * t;
+        bb += t * t;
+        for c in 0..4 {
+            ax[c] += s * pixel[c];
+            bx[c] += t * pixel[c];
+        }
+    }
+    let det = aa * bb - ab * ab;
+    if det.abs() < 1e-6 {
+        return None;
+    }
+    let e0 = std::array::from_fn(|c| ((bb * ax[c] - ab * bx[c]) / det).clamp(0.0, 255.0));
+    let e1 = std::array::from_fn(|c| ((aa * bx[c] - ab * ax[c]) / det).clamp(0.0, 255.0));
+    Some([e0, e1])
+}
+
+/// The result of fitting one set of endpoints to some pixels.
+struct Fit {
+    endpoints: [Endpoint; 2],
+    indexes: [u8; 16],
+    error: f32,
+}
+/// Channels with a weight of 0 are ignored.
+fn fit(
+    pixels: &[Pixel],
+    mode: ModeInfo,
+    index_bits: u8,
+    channel_weights: Pixel,
+    iterations: u8,
+) -> Fit {
+    let mut values = initial_endpoints(pixels);
+    if channel_weights[3] == 0.0 {
+        // alpha is either not stored, encoded separately, or irrelevant
+        values[0][3] = 255.0;
+        values[1][3] = 255.0;
+    }
+
+    let mut best = Fit {
+        endpoints: [Endpoint::default(); 2],
+        indexes: [0; 16],
+        error: f32::INFINITY,
+    };
+    let mut indexes = [0_u8; 16];
+    for iteration in 0..=iterations {
+        if iteration > 0 {
+            match least_squares(pixels, &best.indexes[..pixels.len()], index_bits) {
+                Some(refined) => values = refined,
+                None => break,
+            }
+        }
+
+        let endpoints = quantize_endpoints(values, mode);
+        let expanded = endpoints.map(|e| e.expanded);
+        let error = assign_indexes(
+            pixels,
+            expanded,
+            index_bits,
+            channel_weights,
+            &mut indexes[..pixels.len()],
+        );
+        if error < best.error {
+            best = Fit {
+                endpoints,
+                indexes,
+                error,
+            };
+        } else if iteration > 0 {
+            break;
+        }
+    }
+    best
+}
+
+/// Encodes the block with modes 0, 1, 2, 3, 6, or 7.
+fn encode_subsets(
+    block: &[Pixel; 16],
+    channel_weights: Pixel,
+    mode_index: u8,
+    partition: u8,
+    iterations: u8,
+) -> Candidate {
+    let mode = MODES[mode_index as usize];
+    let subsets = mode.subsets;
+    let has_alpha = mode.alpha_bits > 0;
+    let fit_weights = if has_alpha {
+        channel_weights
+    } else {
+        [
+            channel_weights[0],
+            channel_weights[1],
+            channel_weights[2],
+            0.0,
+        ]
+    };
+
+    let mut endpoints = [[Endpoint::default(); 2]; 3];
+    let mut indexes = [0_u8; 16];
+    let mut error = 0.0;
+    for subset in 0..subsets as usize {
+        let mut pixels = [[0.0; 4]; 16];
+        let mut pixel_indexes = [0; 16];
+        let mut len = 0;
+        for (i, pixel) in block.iter().enumerate() {
+            if subset_of(subsets, partition, i) == subset {
+                pixels[len] = *pixel;
+                pixel_indexes[len] = i;
+                len += 1;
+            }
+        }
+        if len == 0 {
+            continue;
+        }
+
+        let fit = fit(
+            &pixels[..len],
+            mode,
+            mode.index_bits,
+            fit_weights,
+            iterations,
+        );
+        endpoints[subset] = fit.endpoints;
+        for (&i, &index) in pixel_indexes[..len].iter().zip(&fit.indexes) {
+            indexes[i] = index;
+        }
+        error += fit.error;
+        if !has_alpha {
+            // alpha is always decoded as 255
+            error += pixels[..len]
+                .iter()
+                .map(|p| channel_weights[3] * (p[3] - 255.0).powi(2))
+                .sum::<f32>();
+        }
+    }
+
+    // the anchor pixel of each subset must have an index < half
+    let anchors = anchors(subsets, partition);
+    let half = 1 << (mode.index_bits - 1);
+    let max_index = (1 << mode.index_bits) - 1;
+    for subset in 0..subsets as usize {
+        if indexes[anchors[subset]] >= half {
+            endpoints[subset].swap(0, 1);
+            for i in 0..16 {
+                if subset_of(subsets, partition, i) == subset {
+                    indexes[i] = max_index - indexes[i];
+                }
+            }
+        }
+    }
+
+    let mut writer = BitWriter::new(mode_index);
+    writer.write(partition, mode.partition_bits);
+    write_endpoints(&mut writer, mode, &endpoints[..subsets as usize]);
+    write_indexes(
+        &mut writer,
+        &indexes,
+        mode.index_bits,
+        &anchors[..subsets as usize],
+    );
+
+    Candidate {
+        error,
+        block: writer.finish(),
+    }
+}
+
+/// Encodes the block with mode 4 or 5, which store color and alpha
+/// separately.
+fn encode_separate_alpha(
+    block: &[Pixel; 16],
+    channel_weights: Pixel,
+    mode_index: u8,
+    iterations: u8,
+) -> Candidate {
+    let mode = MODES[mode_index as usize];
+    let mut best = Candidate {
+        error: f32::INFINITY,
+        block: [0; 16],
+    };
+
+    for rotation in 0..4_u8 {
+        let mut rotated = *block;
+        let mut rotated_weights = channel_weights;
+        if rotation > 0 {
+            for pixel in rotated.iter_mut() {
+                pixel.swap(rotation as usize - 1, 3);
+            }
+            rotated_weights.swap(rotation as usize - 1, 3);
+        }
+        let [w0, w1, w2, w3] = rotated_weights;
+        let alpha_pixels = rotated.map(|[_, _, _, a]| [a, a, a, a]);
+        let alpha_mode = ModeInfo {
+            color_bits: mode.alpha_bits,
+            ..mode
+        };
+
+        for index_selection in 0..(1 << mode.index_selection_bits) {
+            let (color_index_bits, alpha_index_bits) = if index_selection == 0 {
+                (mode.index_bits, mode.index_bits_2)
+            } else {
+                (mode.index_bits_2, mode.index_bits)
+            };
+
+            let mut color = fit(
+                &rotated,
+                mode,
+                color_index_bits,
+                [w0, w1, w2, 0.0],
+                iterations,
+            );
+            // alpha is fitted as a gray color with 1 channel
+            let alpha_weights = [w3, 0.0, 0.0, 0.0];
+            let mut alpha = fit(
+                &alpha_pixels,
+                alpha_mode,
+                alpha_index_bits,
+                alpha_weights,
+                iterations,
+            );
+            let error = color.error + alpha.error;
+            if error >= best.error {
+                continue;
+            }
+
+            // the anchor pixel is always pixel 0
+            for (fit, bits) in [
+                (&mut color, color_index_bits),
+                (&mut alpha, alpha_index_bits),
+            ] {
+                if fit.indexes[0] >= 1 << (bits - 1) {
+                    fit.endpoints.swap(0, 1);
+                    let max_index = (1 << bits) - 1;
+                    fit.indexes.iter_mut().for_each(|i| *i = max_index - *i);
+                }
+            }
+
+            let mut endpoints = color.endpoints;
+            endpoints[0].stored[3] = alpha.endpoints[0].stored[0];
+            endpoints[1].stored[3] = alpha.endpoints[1].stored[0];
+
+            let (indexes_1, indexes_2) = if index_selection == 0 {
+                (&color.indexes, &alpha.indexes)
+            } else {
+                (&alpha.indexes, &color.indexes)
+            };
+
+            let mut writer = BitWriter::new(mode_index);
+            writer.write(rotation, mode.rotation_bits);
+            writer.write(index_selection, mode.index_selection_bits);
+            write_endpoints(&mut writer, mode, &[endpoints]);
+            write_indexes(&mut writer, indexes_1, mode.index_bits, &[0]);
+            write_indexes(&mut writer, indexes_2, mode.index_bits_2, &[0]);
+
+            best = Candidate {
+                error,
+                block: writer.finish(),
+            };
+        }
+    }
+
+    best
+}
+
+fn write_endpoints(writer: &mut BitWriter, mode: ModeInfo, endpoints: &[[Endpoint; 2]]) {
+    for c in 0..3 {
+        for endpoint in endpoints.iter().flatten() {
+            writer.write(endpoint.stored[c], mode.color_bits);
+        }
+    }
+    for endpoint in endpoints.iter().flatten() {
+        writer.write(endpoint.stored[3], mode.alpha_bits);
+    }
+    if mode.endpoint_p_bits {
+        for endpoint in endpoints.iter().flatten() {
+            writer.write(endpoint.p, 1);
+        }
+    } else if mode.shared_p_bits {
+        for [endpoint, _] in endpoints {
+            writer.write(endpoint.p, 1);
+        }
+    }
+}
+fn write_indexes(writer: &mut BitWriter, indexes: &[u8; 16], bits: u8, anchors: &[usize]) {
+    for (pixel, &index) in indexes.iter().enumerate() {
+        let bits = if anchors.contains(&pixel) {
+            bits - 1
+        } else {
+            bits
+        };
+        writer.write(index, bits);
+    }
+}
+
+struct BitWriter {
+    value: u128,
+    len: u8,
+}
+impl BitWriter {
+    fn new(mode: u8) -> Self {
+        Self {
+            value: 1 << mode,
+            len: mode + 1,
+        }
+    }
+    fn write(&mut self, value: u8, bits: u8) {
+        debug_assert!(bits <= 8 && (value as u16) < 1 << bits);
+        self.value |= (value as u128) << self.len;
+        self.len += bits;
+    }
+    fn finish(self) -> [u8; 16] {
+        debug_assert_eq!(self.len, 128);
+        self.value.to_le_bytes()
+    }
+}
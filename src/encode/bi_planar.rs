@@ -1,8 +1,10 @@
 // helpers
 
+use std::mem::size_of;
+
 use crate::{
     cast::{self, ToLe},
-    convert_to_rgba_f32, yuv10, yuv16, yuv8, EncodeError, SizeMultiple,
+    convert_to_rgba_f32, util, yuv10, yuv16, yuv8, EncodeError, SizeMultiple,
 };
 
 use super::{
@@ -23,21 +25,26 @@ fn bi_planar_universal<P1: ToLe + cast::Castable + Default + Copy, P2: ToLe + ca
         writer,
         width,
         height,
-        options,
+        mut options,
         ..
     } = args;
     let bytes_per_pixel = color.bytes_per_pixel() as usize;
 
-    if width % BLOCK_WIDTH != 0 || height % BLOCK_HEIGHT != 0 {
+    if !options.extend_edges && (width % BLOCK_WIDTH != 0 || height % BLOCK_HEIGHT != 0) {
         return Err(EncodeError::InvalidSize(SizeMultiple::new(
             BLOCK_WIDTH as u8,
             BLOCK_HEIGHT as u8,
         )));
     }
 
+    // the second plane is written after the first, so it's kept in memory
+    let plane2_len = util::div_ceil(width, BLOCK_WIDTH) * util::div_ceil(height, BLOCK_HEIGHT);
+    let row_buffers = width * BLOCK_HEIGHT * (16 + size_of::<P1>());
+    options.reserve_memory((row_buffers + plane2_len * size_of::<P2>()) as u64)?;
+
     let mut intermediate_buffer = vec![[0_f32; 4]; width * BLOCK_HEIGHT];
     let mut plane1_buffer = vec![P1::default(); width * BLOCK_HEIGHT];
-    let mut plane2: Vec<P2> = Vec::new();
+    let mut plane2: Vec<P2> = Vec::with_capacity(plane2_len);
 
     let row_pitch = width * bytes_per_pixel;
     for line_group in data.chunks(row_pitch * BLOCK_HEIGHT) {
@@ -51,21 +58,27 @@ fn bi_planar_universal<P1: ToLe + cast::Castable + Default + Copy, P2: ToLe + ca
             &mut intermediate_buffer[..rows_in_group * width],
         );
 
-        // handle full blocks
-        for macro_x in 0..width / BLOCK_WIDTH {
+        // If the size isn't a multiple of the block size, the last row and
+        // column are extended to fill the partial blocks. Only the pixels
+        // inside the image are written to plane 1.
+        for macro_x in 0..util::div_ceil(width, BLOCK_WIDTH) {
             let mut block = [[0_f32; 4]; 4];
             for y in 0..BLOCK_HEIGHT {
                 for x in 0..BLOCK_WIDTH {
-                    block[y * BLOCK_WIDTH + x] =
-                        intermediate_buffer[y * width + macro_x * BLOCK_WIDTH + x];
+                    let src_y = y.min(rows_in_group - 1);
+                    let src_x = (macro_x * BLOCK_WIDTH + x).min(width - 1);
+                    block[y * BLOCK_WIDTH + x] = intermediate_buffer[src_y * width + src_x];
                 }
             }
 
             let (p1, p2) = encode_macro_pixel(block, &options);
 
-            for y in 0..BLOCK_HEIGHT {
+            for y in 0..rows_in_group {
                 for x in 0..BLOCK_WIDTH {
-                    plane1_buffer[y * width + macro_x * BLOCK_WIDTH + x] = p1[y * BLOCK_WIDTH + x];
+                    let pixel_x = macro_x * BLOCK_WIDTH + x;
+                    if pixel_x < width {
+                        plane1_buffer[y * width + pixel_x] = p1[y * BLOCK_WIDTH + x];
+                    }
                 }
             }
             plane2.push(p2);
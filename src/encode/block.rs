@@ -0,0 +1,82 @@
+//! Encoders for single 4x4 blocks of BCn formats.
+
+use crate::n8;
+
+use super::{bc1_block, bc2_block, bc3_block, bc4_block, bc5_block, bc7_block, EncodeOptions};
+
+fn to_f32<const N: usize>(pixels: &[[u8; N]; 16]) -> [[f32; N]; 16] {
+    pixels.map(|pixel| pixel.map(n8::f32_exact))
+}
+fn channel_to_f32<const N: usize>(pixels: &[[u8; N]; 16], channel: usize) -> [f32; 16] {
+    pixels.map(|pixel| n8::f32_exact(pixel[channel]))
+}
+
+/// Encodes a 4x4 block of RGBA pixels as a BC1 block.
+///
+/// Pixels are given in row-major order. This produces the same result as
+/// encoding a 4x4 image with [`Format::BC1_UNORM`](crate::Format::BC1_UNORM)
+/// and the given options.
+///
+/// Single-block encoders are intended for tools that implement their own
+/// tiling or streaming schemes. Since BCn formats dither within a block (see
+/// [`EncodingSupport::local_dithering`](crate::EncodingSupport::local_dithering)),
+/// all [`EncodeOptions`] apply to individual blocks as well.
+///
+/// ```
+/// # use dds::*;
+/// let pixels = [[255, 0, 0, 255]; 16];
+/// let block = encode_bc1_block(&pixels, &EncodeOptions::default());
+/// assert_eq!(block.len(), 8);
+/// ```
+pub fn encode_bc1_block(pixels: &[[u8; 4]; 16], options: &EncodeOptions) -> [u8; 8] {
+    bc1_block(to_f32(pixels), options)
+}
+
+/// Encodes a 4x4 block of RGBA pixels as a BC2 block.
+///
+/// See [`encode_bc1_block`] for details.
+pub fn encode_bc2_block(pixels: &[[u8; 4]; 16], options: &EncodeOptions) -> [u8; 16] {
+    bc2_block(to_f32(pixels), options)
+}
+
+/// Encodes a 4x4 block of RGBA pixels as a BC3 block.
+///
+/// See [`encode_bc1_block`] for details.
+pub fn encode_bc3_block(pixels: &[[u8; 4]; 16], options: &EncodeOptions) -> [u8; 16] {
+    bc3_block(to_f32(pixels), options)
+}
+
+/// Encodes a 4x4 block of single-channel pixels as a BC4 block.
+///
+/// If `snorm` is `true`, the block is encoded as
+/// [`Format::BC4_SNORM`](crate::Format::BC4_SNORM). The pixel values are
+/// always UNORM, so 0 is mapped to -1 and 255 is mapped to 1.
+///
+/// See [`encode_bc1_block`] for details.
+pub fn encode_bc4_block(pixels: &[u8; 16], options: &EncodeOptions, snorm: bool) -> [u8; 8] {
+    bc4_block(pixels.map(n8::f32_exact), options, snorm)
+}
+
+/// Encodes a 4x4 block of two-channel (RG) pixels as a BC5 block.
+///
+/// If `snorm` is `true`, the block is encoded as
+/// [`Format::BC5_SNORM`](crate::Format::BC5_SNORM). The pixel values are
+/// always UNORM, so 0 is mapped to -1 and 255 is mapped to 1.
+///
+/// See [`encode_bc1_block`] for details.
+pub fn encode_bc5_block(pixels: &[[u8; 2]; 16], options: &EncodeOptions, snorm: bool) -> [u8; 16] {
+    bc5_block(
+        channel_to_f32(pixels, 0),
+        channel_to_f32(pixels, 1),
+        options,
+        snorm,
+    )
+}
+
+/// Encodes a 4x4 block of RGBA pixels as a BC7 block.
+///
+/// The encoder is configured with [`EncodeOptions::bc7`] and
+/// [`EncodeOptions::quality`]. See [`encode_bc1_block`] for details.
+pub fn encode_bc7_block(pixels: &[[u8; 4]; 16], options: &EncodeOptions) -> [u8; 16] {
+    bc7_block(to_f32(pixels), options)
+}
@@ -1,11 +1,26 @@
 use std::{io::Write, num::NonZeroU8};
 
 use bitflags::bitflags;
+use glam::Vec4;
 
-use crate::{cast, ColorFormat, ColorFormatSet, EncodeError, ImageView, Precision, SizeMultiple};
+use crate::{
+    cast, color::convert_to_rgba_f32, linear_to_srgb, n32, Channels, ColorFormat, ColorFormatSet,
+    EncodeError, ImageView, Precision, SizeMultiple,
+};
 
 use super::{Dithering, EncodeOptions, EncodingSupport, PreferredGroupSize};
 
+/// The error that global error-diffusion dithering carries from the last
+/// encoded row into the next row.
+///
+/// Encoding an image in chunks of rows and passing the same carry to all
+/// chunks (in order) produces the same output as encoding the whole image at
+/// once. Encoders without global dithering ignore the carry.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DitherCarry {
+    pub error: Vec<Vec4>,
+}
+
 pub(crate) struct Args<'a, 'b> {
     pub data: &'a [u8],
     pub width: usize,
@@ -13,12 +28,14 @@ pub(crate) struct Args<'a, 'b> {
     pub color: ColorFormat,
     pub writer: &'b mut dyn Write,
     pub options: EncodeOptions,
+    pub carry: &'b mut DitherCarry,
 }
 impl<'a, 'b> Args<'a, 'b> {
     fn from(
         image: ImageView<'a>,
         writer: &'b mut dyn Write,
         options: EncodeOptions,
+        carry: &'b mut DitherCarry,
     ) -> Result<Self, EncodeError> {
         Ok(Self {
             data: image.data(),
@@ -27,6 +44,7 @@ impl<'a, 'b> Args<'a, 'b> {
             color: image.color(),
             writer,
             options,
+            carry,
         })
     }
 }
@@ -54,7 +72,7 @@ bitflags! {
         /// Whether color dithering is supported.
         const DITHER_COLOR = 0x8;
         /// Whether alpha dithering is supported.
-        const DITHER_ALPHA = 0x16;
+        const DITHER_ALPHA = 0x10;
         /// Whether both alpha and color dithering is supported.
         const DITHER_ALL = Self::DITHER_COLOR.bits() | Self::DITHER_ALPHA.bits();
     }
@@ -65,7 +83,9 @@ impl Flags {
         match precision {
             Precision::U8 => Flags::EXACT_U8,
             Precision::U16 => Flags::EXACT_U16,
-            Precision::F32 => Flags::EXACT_F32,
+            // no format stores more than 16-bit integers, so F32 is as exact
+            // as it gets
+            Precision::F32 | Precision::U32 => Flags::EXACT_F32,
         }
     }
     pub const fn get_dithering(self) -> Dithering {
@@ -155,7 +175,7 @@ impl EncoderSet {
         }
 
         assert!(
-            combined_colors.is_all(),
+            combined_colors.contains_all(ColorFormatSet::REQUIRED),
             "All color formats must be supported"
         );
 
@@ -245,8 +265,51 @@ impl EncoderSet {
         image: ImageView,
         options: &EncodeOptions,
     ) -> Result<(), EncodeError> {
+        self.encode_with_carry(writer, image, options, &mut DitherCarry::default())
+    }
+    pub fn encode_with_carry(
+        &self,
+        writer: &mut dyn Write,
+        image: ImageView,
+        options: &EncodeOptions,
+        carry: &mut DitherCarry,
+    ) -> Result<(), EncodeError> {
+        if !self.encoders_for_color(image.color()).any(|_| true) {
+            // U32 images are converted to F32 first
+            debug_assert_eq!(image.color().precision, Precision::U32);
+            let color = ColorFormat::new(image.color().channels, Precision::F32);
+            let mut options = options.clone();
+            options.reserve_memory(image.data().len() as u64)?;
+            let values: Vec<f32> = image
+                .data()
+                .chunks_exact(4)
+                .map(|v| n32::f32_exact(u32::from_ne_bytes([v[0], v[1], v[2], v[3]])))
+                .collect();
+            let image = ImageView::new(values.as_slice(), image.size(), color)
+                .expect("invalid converted image");
+            return self.encode_with_carry(writer, image, &options, carry);
+        }
+
+        if options.convert_to_srgb && image.color().channels != Channels::Alpha {
+            let mut options = EncodeOptions {
+                convert_to_srgb: false,
+                ..options.clone()
+            };
+            options.reserve_memory(image.size().pixels() * 16)?;
+            let mut rgba = vec![[0_f32; 4]; image.size().pixels() as usize];
+            convert_to_rgba_f32(image.color(), image.data(), &mut rgba);
+            for pixel in &mut rgba {
+                for value in &mut pixel[..3] {
+                    *value = linear_to_srgb(*value);
+                }
+            }
+            let image = ImageView::new(rgba.as_slice(), image.size(), ColorFormat::RGBA_F32)
+                .expect("invalid converted image");
+            return self.encode_with_carry(writer, image, &options, carry);
+        }
+
         let encoder = self.pick_encoder(image.color(), options);
-        let args = Args::from(image, writer, options.clone())?;
+        let args = Args::from(image, writer, options.clone(), carry)?;
         encoder.encode(args)
     }
 }
@@ -1,19 +1,32 @@
 use std::{io::Write, num::NonZeroU8};
 
+use bitflags::bitflags;
+
 use crate::{EncodeError, Format, ImageView, SizeMultiple};
 
 mod bc;
 mod bc1;
 mod bc4;
+mod bc6;
+mod bc7;
 mod bcn_util;
 mod bi_planar;
+mod block;
 mod encoder;
+mod range;
+mod rect;
+mod report;
 mod sub_sampled;
 mod uncompressed;
 
 use bc::*;
 use bi_planar::*;
-pub(crate) use encoder::EncoderSet;
+pub use block::*;
+pub(crate) use encoder::{DitherCarry, EncoderSet};
+pub(crate) use range::check_range;
+pub use range::OutOfRangeStats;
+pub use rect::*;
+pub use report::*;
 use sub_sampled::*;
 use uncompressed::*;
 
@@ -100,8 +113,9 @@ pub(crate) const fn get_encoders(format: Format) -> Option<EncoderSet> {
         // non-standard formats
         Format::BC3_UNORM_RXGB => BC3_UNORM_RXGB,
 
-        // unsupported formats
-        Format::BC6H_UF16 | Format::BC6H_SF16 | Format::BC7_UNORM => return None,
+        Format::BC6H_UF16 => BC6H_UF16,
+        Format::BC6H_SF16 => BC6H_SF16,
+        Format::BC7_UNORM => BC7_UNORM,
     })
 }
 
@@ -112,12 +126,98 @@ pub fn encode(
     options: &EncodeOptions,
 ) -> Result<(), EncodeError> {
     if let Some(encoders) = get_encoders(format) {
+        check_range(image, format, options)?;
         encoders.encode(writer, image, options)
     } else {
         Err(EncodeError::UnsupportedFormat(format))
     }
 }
 
+/// Encodes the given image like [`encode()`], but continues the error
+/// diffusion of global dithering from the given carry and updates the carry
+/// afterward.
+pub(crate) fn encode_with_carry(
+    writer: &mut dyn Write,
+    image: ImageView,
+    format: Format,
+    options: &EncodeOptions,
+    carry: &mut DitherCarry,
+) -> Result<(), EncodeError> {
+    if let Some(encoders) = get_encoders(format) {
+        check_range(image, format, options)?;
+        encoders.encode_with_carry(writer, image, options, carry)
+    } else {
+        Err(EncodeError::UnsupportedFormat(format))
+    }
+}
+
+/// Encodes the given image with the first format in `formats` that accepts it
+/// and returns the format that was used.
+///
+/// A format is skipped if:
+///
+/// - it has no encoder (e.g. `ASTC_4X4_UNORM`),
+/// - the size of the image is not a multiple of the format's
+///   [`EncodingSupport::size_multiple`] and [`EncodeOptions::extend_edges`] is
+///   disabled, or
+/// - the image contains values the format would clamp and
+///   [`EncodeOptions::reject_out_of_range`] is enabled.
+///
+/// Nothing is written to the writer for skipped formats.
+///
+/// If no format accepts the image, the error of the last format is returned.
+/// If `formats` is empty, [`EncodeError::NoFormats`] is returned. All other
+/// errors (e.g. IO errors) are returned immediately without trying the
+/// remaining formats, since the writer may already contain partial output.
+///
+/// ```
+/// # use dds::*;
+/// let data = vec![0_u8; 3 * 3 * 4];
+/// let image = ImageView::new(data.as_slice(), Size::new(3, 3), ColorFormat::RGBA_U8).unwrap();
+/// let mut output = Vec::new();
+/// let format = encode_with_fallback(
+///     &mut output,
+///     image,
+///     &[Format::ASTC_4X4_UNORM, Format::NV12, Format::BC1_UNORM],
+///     &EncodeOptions::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(format, Format::BC1_UNORM);
+/// ```
+pub fn encode_with_fallback(
+    writer: &mut dyn Write,
+    image: ImageView,
+    formats: &[Format],
+    options: &EncodeOptions,
+) -> Result<Format, EncodeError> {
+    let mut last_error = EncodeError::NoFormats;
+    for &format in formats {
+        let encoders = match get_encoders(format) {
+            Some(encoders) => encoders,
+            None => {
+                last_error = EncodeError::UnsupportedFormat(format);
+                continue;
+            }
+        };
+
+        let size_multiple = encoders.encoding_support().size_multiple();
+        if !options.extend_edges && !image.size().is_multiple_of(size_multiple) {
+            last_error = EncodeError::InvalidSize(size_multiple);
+            continue;
+        }
+
+        if let Err(error) = check_range(image, format, options) {
+            last_error = error;
+            continue;
+        }
+
+        encoders.encode(writer, image, options)?;
+        return Ok(format);
+    }
+
+    Err(last_error)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[non_exhaustive]
 pub struct EncodeOptions {
@@ -134,6 +234,36 @@ pub struct EncodeOptions {
     ///
     /// Default: [`Dithering::None`]
     pub dithering: Dithering,
+    /// Whether color dithering should measure quantization error in linear
+    /// light.
+    ///
+    /// If enabled, the color channels of the image are assumed to be
+    /// sRGB-encoded. The encoded values stay sRGB-encoded, but the error
+    /// diffused to neighboring pixels is computed after converting to linear
+    /// light. This produces smoother gradients in dark regions, because the
+    /// sRGB curve is very steep there.
+    ///
+    /// This option only affects uncompressed formats that support dithering
+    /// (e.g. [`Format::B5G6R5_UNORM`], [`Format::B4G4R4A4_UNORM`], and
+    /// [`Format::R10G10B10A2_UNORM`]) and is ignored if color dithering is
+    /// disabled. Block-compressed formats (BC1-BC7) ignore this option and
+    /// always measure their dithering error in the encoded values.
+    ///
+    /// Default: `false`
+    pub linear_light_dithering: bool,
+    /// Whether to convert the color channels of the image from linear light
+    /// to sRGB before encoding.
+    ///
+    /// Enable this when the input image is linear, but the output is tagged
+    /// as sRGB (see [`Header::to_srgb`](crate::header::Header::to_srgb)).
+    /// Alpha is not affected. The conversion is done in `f32`, so no
+    /// precision is lost for low-precision input images.
+    ///
+    /// Since [`Encoder`](crate::Encoder) generates mipmaps before encoding,
+    /// mipmaps are resized in linear light when this option is enabled.
+    ///
+    /// Default: `false`
+    pub convert_to_srgb: bool,
     /// The error metric for block compression formats.
     ///
     /// Default: [`ErrorMetric::Uniform`]
@@ -144,20 +274,181 @@ pub struct EncodeOptions {
     ///
     /// Default: [`CompressionQuality::Normal`]
     pub quality: CompressionQuality,
+    /// Fine-grained control over the BC7 encoder.
+    ///
+    /// If `None`, the BC7 encoder uses
+    /// [`Bc7Options::from_quality`] with [`Self::quality`]. This option is
+    /// ignored for all other formats.
+    ///
+    /// Default: `None`
+    pub bc7: Option<Bc7Options>,
+    /// The alpha threshold for BC1's 1-bit (punch-through) alpha.
+    ///
+    /// Pixels with an alpha value below this threshold (after rounding alpha
+    /// to 8 bits) are encoded as transparent black, all other pixels are
+    /// encoded as opaque. A threshold of 0 encodes all pixels as opaque.
+    ///
+    /// This option is ignored for all other formats, including BC2 and BC3.
+    ///
+    /// Default: `128`
+    pub bc1_alpha_threshold: u8,
+    /// Whether to accept images with sizes that are not a multiple of the
+    /// format's [`SizeMultiple`].
+    ///
+    /// Some formats (e.g. `NV12`) store chroma for groups of pixels and
+    /// require image dimensions to be multiples of the group size (see
+    /// [`EncodingSupport::size_multiple`]). If this option is enabled, the
+    /// last row and column of the image are virtually extended (edge clamp)
+    /// to fill partial groups. The encoded data still describes an image
+    /// with the original size.
+    ///
+    /// If disabled, the encoder returns [`EncodeError::InvalidSize`] for such
+    /// images instead.
+    ///
+    /// Default: `false`
+    pub extend_edges: bool,
+    /// Whether to reject images with values outside the range [0, 1] instead
+    /// of clamping them.
+    ///
+    /// This only affects [`Precision::F32`](crate::Precision::F32) images
+    /// encoded with formats that cannot store values outside this range,
+    /// i.e. formats without [`Precision::F32`](crate::Precision::F32)
+    /// precision. If enabled, [`EncodeError::OutOfRange`] is returned for such
+    /// images and nothing is written. Use [`OutOfRangeStats::collect`] to
+    /// inspect images without encoding them.
+    ///
+    /// Mipmaps generated by [`Encoder`](crate::Encoder) are not checked,
+    /// since some resize filters slightly overshoot the range of the
+    /// original image.
+    ///
+    /// Default: `false`
+    pub reject_out_of_range: bool,
+    /// The maximum amount of memory that the encoder is allowed to allocate
+    /// for intermediate buffers.
+    ///
+    /// If the encoder needs to allocate more memory than this limit, it will
+    /// return [`EncodeError::MemoryLimitExceeded`].
+    ///
+    /// This includes buffers for converting images to `f32`, the rows of
+    /// blocks being encoded, the second plane of bi-planar formats, the
+    /// encoded fragments of surfaces encoded in parallel, and the buffers
+    /// used by [`Encoder`](crate::Encoder) to generate and reorder mipmaps.
+    /// The input image and the writer are not included. This is intended for
+    /// servers that encode untrusted images and need to bound their memory
+    /// usage, similar to [`DecodeOptions::memory_limit`](crate::DecodeOptions::memory_limit).
+    ///
+    /// Default: `usize::MAX` (no limit)
+    pub memory_limit: usize,
+    /// Whether large surfaces may be split into fragments that are encoded
+    /// in parallel.
+    ///
+    /// This option only affects [`Encoder`](crate::Encoder) and is ignored if
+    /// the `rayon` feature is disabled. Disable it to encode on the calling
+    /// thread only, e.g. when many images are already encoded in parallel.
+    ///
+    /// Default: `true`
+    pub parallel: bool,
+}
+impl EncodeOptions {
+    /// Sets [`Self::dithering`].
+    ///
+    /// ```
+    /// # use dds::*;
+    /// let options = EncodeOptions::default()
+    ///     .with_quality(CompressionQuality::High)
+    ///     .with_dithering(Dithering::Color)
+    ///     .with_error_metric(ErrorMetric::Perceptual);
+    /// assert_eq!(options.quality, CompressionQuality::High);
+    /// ```
+    pub fn with_dithering(mut self, dithering: Dithering) -> Self {
+        self.dithering = dithering;
+        self
+    }
+    /// Sets [`Self::linear_light_dithering`].
+    pub fn with_linear_light_dithering(mut self, linear_light_dithering: bool) -> Self {
+        self.linear_light_dithering = linear_light_dithering;
+        self
+    }
+    /// Sets [`Self::convert_to_srgb`].
+    pub fn with_convert_to_srgb(mut self, convert_to_srgb: bool) -> Self {
+        self.convert_to_srgb = convert_to_srgb;
+        self
+    }
+    /// Sets [`Self::error_metric`].
+    pub fn with_error_metric(mut self, error_metric: ErrorMetric) -> Self {
+        self.error_metric = error_metric;
+        self
+    }
+    /// Sets [`Self::quality`].
+    pub fn with_quality(mut self, quality: CompressionQuality) -> Self {
+        self.quality = quality;
+        self
+    }
+    /// Sets [`Self::bc7`].
+    pub fn with_bc7(mut self, bc7: Option<Bc7Options>) -> Self {
+        self.bc7 = bc7;
+        self
+    }
+    /// Sets [`Self::bc1_alpha_threshold`].
+    pub fn with_bc1_alpha_threshold(mut self, bc1_alpha_threshold: u8) -> Self {
+        self.bc1_alpha_threshold = bc1_alpha_threshold;
+        self
+    }
+    /// Sets [`Self::extend_edges`].
+    pub fn with_extend_edges(mut self, extend_edges: bool) -> Self {
+        self.extend_edges = extend_edges;
+        self
+    }
+    /// Sets [`Self::reject_out_of_range`].
+    pub fn with_reject_out_of_range(mut self, reject_out_of_range: bool) -> Self {
+        self.reject_out_of_range = reject_out_of_range;
+        self
+    }
+    /// Sets [`Self::memory_limit`].
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+    /// Sets [`Self::parallel`].
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Reserves the given number of bytes for an intermediate buffer by
+    /// reducing [`Self::memory_limit`].
+    pub(crate) fn reserve_memory(&mut self, bytes: u64) -> Result<(), EncodeError> {
+        match usize::try_from(bytes) {
+            Ok(bytes) if bytes <= self.memory_limit => {
+                self.memory_limit -= bytes;
+                Ok(())
+            }
+            _ => Err(EncodeError::MemoryLimitExceeded),
+        }
+    }
 }
 impl Default for EncodeOptions {
     fn default() -> Self {
         Self {
             dithering: Dithering::None,
+            linear_light_dithering: false,
+            convert_to_srgb: false,
             error_metric: ErrorMetric::Uniform,
             quality: CompressionQuality::Normal,
+            bc7: None,
+            bc1_alpha_threshold: 128,
+            extend_edges: false,
+            reject_out_of_range: false,
+            memory_limit: usize::MAX,
+            parallel: true,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Dithering {
     /// Dithering is disabled for all channels.
+    #[default]
     None = 0b00,
     /// Dithering is enabled for all channels (RGBA).
     ColorAndAlpha = 0b11,
@@ -195,20 +486,51 @@ impl Dithering {
         }
     }
 }
-impl Default for Dithering {
-    fn default() -> Self {
-        Self::None
-    }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+const LUMA_WEIGHTS: [u8; 4] = [54, 183, 19, 255];
+
+/// The error metric block compression formats minimize when picking
+/// endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum ErrorMetric {
+    /// The squared error of all channels is weighted equally. This is
+    /// typically best for data textures (e.g. normal maps).
+    #[default]
     Uniform,
+    /// Colors are compared perceptually.
+    ///
+    /// BC1, BC2, and BC3 compare colors in the OKLab color space. BC7 uses the
+    /// weights of [`ErrorMetric::LUMA`].
     Perceptual,
+    /// The squared error of the R, G, B, and A channels is weighted with the
+    /// given relative weights.
+    ///
+    /// Only the ratios between weights matter, so `Weighted([1, 1, 1, 1])` is
+    /// the same as [`ErrorMetric::Uniform`]. A weight of 0 means that the
+    /// channel is ignored. If all weights are 0, all channels are weighted
+    /// equally.
+    ///
+    /// This affects BC1, BC2, BC3 (color only), and BC7.
+    Weighted([u8; 4]),
 }
-impl Default for ErrorMetric {
-    fn default() -> Self {
-        Self::Uniform
+impl ErrorMetric {
+    /// Weights based on the Rec. 709 luma coefficients. Green is the most
+    /// important color channel and blue the least important one. Alpha has
+    /// the same weight as all color channels combined.
+    pub const LUMA: Self = Self::Weighted(LUMA_WEIGHTS);
+
+    /// Returns the relative weight of each channel. The largest weight is 1.
+    pub(crate) fn channel_weights(self) -> [f32; 4] {
+        let weights = match self {
+            ErrorMetric::Uniform => return [1.0; 4],
+            ErrorMetric::Perceptual => LUMA_WEIGHTS,
+            ErrorMetric::Weighted(weights) => weights,
+        };
+        let max = weights.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return [1.0; 4];
+        }
+        weights.map(|w| w as f32 / max as f32)
     }
 }
 
@@ -236,16 +558,126 @@ impl Default for ErrorMetric {
 ///
 /// Encoding DDS images is embarrassingly parallel, so using multiple cores
 /// should make encoding roughly 4-10x faster on normal consumer hardware.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+///
+/// For BC1-BC5, the quality controls how much effort is spent searching for
+/// block endpoints:
+///
+/// - `Fast`: Endpoints are derived directly from the colors of the block.
+///   BC4 and BC5 (and alpha in BC3) only use the 8-value interpolation mode.
+/// - `Normal`: Endpoints are refined for a few iterations.
+/// - `High`: Endpoints are refined until convergence. BC1 additionally tries
+///   the 3-color mode for opaque blocks.
+/// - `Unreasonable`: BC4 and BC5 brute-force all endpoints. BC1 refines
+///   endpoints with finer steps and searches a larger neighborhood of
+///   quantized endpoints.
+///
+/// For BC6H, all 14 endpoint modes are always tried. The quality controls
+/// how many partitions are examined for the 2-subset modes and how often
+/// endpoints are refined. `Fast` only examines the most promising partition
+/// and doesn't refine endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub enum CompressionQuality {
     Fast,
+    #[default]
     Normal,
     High,
     Unreasonable,
 }
-impl Default for CompressionQuality {
-    fn default() -> Self {
-        Self::Normal
+
+bitflags! {
+    /// A set of BC7 modes.
+    ///
+    /// Modes 0 to 3 cannot store alpha, while modes 4 to 7 can.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct Bc7Modes: u8 {
+        /// 3 subsets, 16 partitions, RGB 4.4.4 + P-bit per endpoint.
+        const MODE_0 = 1 << 0;
+        /// 2 subsets, 64 partitions, RGB 6.6.6 + shared P-bit.
+        const MODE_1 = 1 << 1;
+        /// 3 subsets, 64 partitions, RGB 5.5.5.
+        const MODE_2 = 1 << 2;
+        /// 2 subsets, 64 partitions, RGB 7.7.7 + P-bit per endpoint.
+        const MODE_3 = 1 << 3;
+        /// 1 subset, RGB 5.5.5 with separate 6-bit alpha.
+        const MODE_4 = 1 << 4;
+        /// 1 subset, RGB 7.7.7 with separate 8-bit alpha.
+        const MODE_5 = 1 << 5;
+        /// 1 subset, RGBA 7.7.7.7 + P-bit per endpoint.
+        const MODE_6 = 1 << 6;
+        /// 2 subsets, 64 partitions, RGBA 5.5.5.5 + P-bit per endpoint.
+        const MODE_7 = 1 << 7;
+    }
+}
+impl Bc7Modes {
+    /// Returns the set containing only the given mode.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `mode` is greater than 7.
+    pub const fn from_mode(mode: u8) -> Self {
+        assert!(mode < 8, "BC7 only has 8 modes");
+        Self::from_bits_retain(1 << mode)
+    }
+}
+
+/// Options for the BC7 encoder.
+///
+/// BC7 has 8 modes with different trade-offs. The encoder tries all enabled
+/// modes and picks the one with the smallest error. Modes with 2 or 3 subsets
+/// can additionally choose between up to 64 partitions, which is the most
+/// expensive part of encoding.
+///
+/// Use [`EncodeOptions::bc7`] to set these options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Bc7Options {
+    /// The modes the encoder may use.
+    ///
+    /// If empty, only mode 6 is used.
+    pub modes: Bc7Modes,
+    /// The number of partitions examined for modes with 2 or 3 subsets.
+    ///
+    /// Partitions are ranked by a quick estimate and only the best ones are
+    /// fully encoded. Values are clamped to 1 to 64. Mode 0 only has 16
+    /// partitions.
+    pub partitions: u8,
+    /// The number of times endpoints are refined after the initial fit.
+    pub refine_iterations: u8,
+}
+impl Bc7Options {
+    /// A fast profile for opaque images.
+    ///
+    /// This only uses modes 1 and 6, examines 8 partitions, and refines
+    /// endpoints once. Alpha is still encoded by mode 6, but blocks with
+    /// alpha will be of low quality.
+    pub const FAST_RGB: Self = Self {
+        modes: Bc7Modes::MODE_1.union(Bc7Modes::MODE_6),
+        partitions: 8,
+        refine_iterations: 1,
+    };
+    /// The highest quality profile, suitable for images with alpha.
+    ///
+    /// This uses all modes, examines all partitions, and refines endpoints
+    /// until they converge. This is very slow.
+    pub const MAX_QUALITY: Self = Self {
+        modes: Bc7Modes::all(),
+        partitions: 64,
+        refine_iterations: 8,
+    };
+
+    /// Returns the options used for the given compression quality.
+    pub const fn from_quality(quality: CompressionQuality) -> Self {
+        let (partitions, refine_iterations) = match quality {
+            CompressionQuality::Fast => (1, 0),
+            CompressionQuality::Normal => (4, 1),
+            CompressionQuality::High => (16, 2),
+            CompressionQuality::Unreasonable => return Self::MAX_QUALITY,
+        };
+        Self {
+            modes: Bc7Modes::all(),
+            partitions,
+            refine_iterations,
+        }
     }
 }
 
@@ -363,10 +795,11 @@ impl EncodingSupport {
     /// Whether the format supports local dithering.
     ///
     /// Most formats implement global error diffusing dithering for best quality.
-    /// However, this prevents parallel encoding of the image, as the dithering
-    /// error of one chunk depends on the dithering error of the previous chunk.
-    /// It's still possible to encode the image in parallel, but the dither
-    /// pattern may reveal the chunk seams.
+    /// However, this complicates parallel encoding of the image, as the
+    /// dithering error of one chunk depends on the dithering error of the
+    /// previous chunk. Encoding chunks separately may reveal the chunk seams.
+    /// [`crate::SplitSurface::encode`] avoids this by computing the error
+    /// carried between chunks in a sequential first pass.
     ///
     /// Local dithering on the other hand will attempt to diffuse the error
     /// within a small region of the image. E.g. `BC1_UNORM` will dither within
@@ -0,0 +1,111 @@
+use crate::{cast, EncodeError, EncodeOptions, Format, ImageView, Precision};
+
+/// Statistics about values outside the range [0, 1] in an image.
+///
+/// Formats that don't store floating-point values (see [`Format::precision`])
+/// can only represent values in the range [0, 1]. When encoding
+/// [`Precision::F32`] images into such formats, all values outside this range
+/// are clamped. These statistics can be used to detect such data loss, e.g.
+/// when HDR data is accidentally encoded into an LDR format.
+///
+/// See also [`EncodeOptions::reject_out_of_range`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct OutOfRangeStats {
+    /// The number of values (not pixels) outside the range [0, 1]. This
+    /// includes NaN values.
+    pub count: u64,
+    /// The smallest value in the image. NaN values are ignored.
+    ///
+    /// This is 0 for empty images.
+    pub min: f32,
+    /// The largest value in the image. NaN values are ignored.
+    ///
+    /// This is 0 for empty images.
+    pub max: f32,
+}
+impl OutOfRangeStats {
+    /// Collects the statistics for all channels (including alpha) of the
+    /// given image.
+    ///
+    /// Images with integer precisions can never contain
+    /// values outside the range [0, 1], so only their minimum and maximum are
+    /// computed.
+    pub fn collect(image: ImageView) -> Self {
+        let mut stats = Self {
+            count: 0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+        };
+
+        match image.color().precision {
+            Precision::U8 => {
+                for &value in image.data() {
+                    stats.add(value as f32 / 255.0);
+                }
+            }
+            Precision::U16 => {
+                for value in image.data().chunks_exact(2) {
+                    stats.add(u16::from_ne_bytes([value[0], value[1]]) as f32 / 65535.0);
+                }
+            }
+            Precision::U32 => {
+                for value in image.data().chunks_exact(4) {
+                    let value = u32::from_ne_bytes([value[0], value[1], value[2], value[3]]);
+                    stats.add(value as f32 / u32::MAX as f32);
+                }
+            }
+            Precision::F32 => match cast::from_bytes::<f32>(image.data()) {
+                Some(values) => values.iter().for_each(|&value| stats.add(value)),
+                None => {
+                    for value in image.data().chunks_exact(4) {
+                        stats.add(f32::from_ne_bytes([value[0], value[1], value[2], value[3]]));
+                    }
+                }
+            },
+        }
+
+        if stats.min > stats.max {
+            // empty image or only NaN values
+            stats.min = 0.0;
+            stats.max = 0.0;
+        }
+        stats
+    }
+
+    fn add(&mut self, value: f32) {
+        if !(0.0..=1.0).contains(&value) {
+            self.count += 1;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Whether all values are in the range [0, 1].
+    pub fn is_in_range(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Returns an error if the given image contains values that would be clamped
+/// when encoded with the given format and
+/// [`EncodeOptions::reject_out_of_range`] is enabled.
+pub(crate) fn check_range(
+    image: ImageView,
+    format: Format,
+    options: &EncodeOptions,
+) -> Result<(), EncodeError> {
+    if !options.reject_out_of_range
+        || image.color().precision != Precision::F32
+        || format.precision() == Precision::F32
+    {
+        return Ok(());
+    }
+
+    let stats = OutOfRangeStats::collect(image);
+    if stats.is_in_range() {
+        Ok(())
+    } else {
+        Err(EncodeError::OutOfRange(stats))
+    }
+}
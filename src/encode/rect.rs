@@ -0,0 +1,118 @@
+use std::io::{Seek, SeekFrom, Write};
+
+use crate::{util::div_ceil, EncodeError, EncodeOptions, Format, ImageView, PixelInfo, Rect, Size};
+
+use super::{check_range, get_encoders};
+
+/// Encodes the given image into a rectangle of an already encoded surface.
+///
+/// Only the bytes of the blocks within the rectangle are overwritten. All
+/// other bytes of the surface are left untouched. This is useful for editing
+/// tools that change small parts of large surfaces, since only the changed
+/// area needs to be re-encoded. To patch a surface in memory, wrap the bytes
+/// of the surface in a [`std::io::Cursor`].
+///
+/// `image` contains the new pixels of the rectangle, so its size must be the
+/// size of `rect`. `surface_size` is the size of the whole surface.
+///
+/// ## Alignment
+///
+/// The rectangle must be aligned to the blocks of the format, meaning that
+/// it must be equal to `rect.align_to_blocks(format).clamp_to(surface_size)`
+/// (see [`Rect::align_to_blocks`]). Otherwise,
+/// [`EncodeError::UnalignedRect`] is returned. Bi-planar formats (e.g. `NV12`)
+/// are not supported.
+///
+/// Global error-diffusion dithering (see
+/// [`crate::EncodingSupport::local_dithering`]) only diffuses the error
+/// within the rectangle, so the result may differ from re-encoding the whole
+/// surface.
+///
+/// ## State of the writer
+///
+/// The writer is expected to be positioned at the start of the encoded
+/// image data of the surface. If the operation completes successfully, the
+/// writer will be positioned at the end of the encoded image data.
+///
+/// If the operation fails and returns an error **other** than an IO error,
+/// nothing is written and the position of the writer remains unchanged.
+///
+/// ```
+/// # use dds::*;
+/// let size = Size::new(16, 16);
+/// let data = vec![0_u8; 16 * 16 * 4];
+/// let image = ImageView::new(data.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+/// let options = EncodeOptions::default();
+/// let mut surface = Vec::new();
+/// encode(&mut surface, image, Format::BC1_UNORM, &options).unwrap();
+///
+/// // paint a white 4x8 stroke
+/// let stroke = vec![255_u8; 4 * 8 * 4];
+/// let stroke = ImageView::new(stroke.as_slice(), Size::new(4, 8), ColorFormat::RGBA_U8).unwrap();
+/// let rect = Rect::new(8, 4, 4, 8);
+/// let mut cursor = std::io::Cursor::new(surface.as_mut_slice());
+/// encode_rect(&mut cursor, stroke, size, rect, Format::BC1_UNORM, &options).unwrap();
+/// ```
+pub fn encode_rect<W: Write + Seek>(
+    writer: &mut W,
+    image: ImageView,
+    surface_size: Size,
+    rect: Rect,
+    format: Format,
+    options: &EncodeOptions,
+) -> Result<(), EncodeError> {
+    let encoders = get_encoders(format).ok_or(EncodeError::UnsupportedFormat(format))?;
+    let (block_width, block_height, bytes_per_block) = match PixelInfo::from(format) {
+        PixelInfo::Fixed { bytes_per_pixel } => (1, 1, bytes_per_pixel),
+        PixelInfo::Block(block) => (block.size().0, block.size().1, block.bytes_per_block()),
+        PixelInfo::BiPlanar(_) => return Err(EncodeError::UnsupportedFormat(format)),
+    };
+    let (block_width, block_height) = (block_width as u64, block_height as u64);
+    let bytes_per_block = bytes_per_block as u64;
+
+    let size_multiple = encoders.encoding_support().size_multiple();
+    if !options.extend_edges && !surface_size.is_multiple_of(size_multiple) {
+        return Err(EncodeError::InvalidSize(size_multiple));
+    }
+    if image.size() != rect.size() {
+        return Err(EncodeError::UnexpectedSurfaceSize);
+    }
+    if !rect.is_within_bounds(surface_size) {
+        return Err(EncodeError::RectOutOfBounds);
+    }
+    if rect.align_to_blocks(format).clamp_to(surface_size) != rect {
+        return Err(EncodeError::UnalignedRect);
+    }
+
+    let surface_row_bytes = div_ceil(surface_size.width as u64, block_width) * bytes_per_block;
+    let surface_bytes = surface_row_bytes * div_ceil(surface_size.height as u64, block_height);
+
+    // the encoded rectangle is written row by row after encoding it
+    let mut options = options.clone();
+    let rect_bytes = div_ceil(rect.width as u64, block_width)
+        * div_ceil(rect.height as u64, block_height)
+        * bytes_per_block;
+    options.reserve_memory(rect_bytes)?;
+
+    let mut encoded = Vec::new();
+    if !rect.is_empty() {
+        check_range(image, format, &options)?;
+        encoders.encode(&mut encoded, image, &options)?;
+    }
+
+    let start = writer.stream_position()?;
+    if !encoded.is_empty() {
+        let rect_row_bytes = div_ceil(rect.width as u64, block_width) * bytes_per_block;
+        let first_block_row = rect.y as u64 / block_height;
+        let first_block_column = rect.x as u64 / block_width;
+        for (i, row) in encoded.chunks(rect_row_bytes as usize).enumerate() {
+            let offset = (first_block_row + i as u64) * surface_row_bytes
+                + first_block_column * bytes_per_block;
+            writer.seek(SeekFrom::Start(start + offset))?;
+            writer.write_all(row)?;
+        }
+    }
+    writer.seek(SeekFrom::Start(start + surface_bytes))?;
+
+    Ok(())
+}
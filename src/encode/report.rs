@@ -0,0 +1,179 @@
+use std::io::Write;
+
+use crate::{
+    cast, color, decode, Channels, ColorFormat, DecodeOptions, EncodeError, EncodeOptions, Format,
+    ImageView, ImageViewMut, PixelInfo, Precision,
+};
+
+use super::{check_range, get_encoders};
+
+/// Statistics about the quality loss of an encoded image.
+///
+/// Returned by [`encode_with_report`]. All values are computed by decoding
+/// the encoded data again and comparing it to the original image. Channels
+/// are always in RGBA order.
+///
+/// Before comparing, the original image is converted to the channels of the
+/// format (see [`Format::channels`]). This way, channels that the format
+/// doesn't store are compared against what the decoder outputs for them, e.g.
+/// alpha is always 1 for `B8G8R8_UNORM`, and green and blue are copies of red
+/// for `R8_UNORM`.
+///
+/// Asset pipelines can use this to flag textures that need a different
+/// format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct QualityReport {
+    /// The peak signal-to-noise ratio (in dB) of each channel. The peak value
+    /// is 1.0.
+    ///
+    /// This is `f32::INFINITY` for channels without any error.
+    pub psnr: [f32; 4],
+    /// The largest absolute difference between original and decoded values of
+    /// each channel.
+    ///
+    /// If only one of the two values is NaN, the error is 1.0.
+    pub max_error: [f32; 4],
+    /// The number of pixels with at least one value that was clamped to the
+    /// range [0, 1] because the format doesn't store floating-point values.
+    /// NaN values count as clamped.
+    ///
+    /// See [`crate::OutOfRangeStats`] for more details.
+    pub clamped_pixels: u64,
+}
+impl QualityReport {
+    /// The smallest PSNR of all channels.
+    pub fn min_psnr(&self) -> f32 {
+        self.psnr.iter().copied().fold(f32::INFINITY, f32::min)
+    }
+}
+
+/// Encodes the given image like [`crate::encode()`] and returns a
+/// [`QualityReport`] for the encoded data.
+///
+/// The encoded data is buffered in memory, decoded, and compared against the
+/// original image before it is written to the writer. This makes this function
+/// noticeably slower than [`crate::encode()`] and it requires memory for the
+/// encoded data and 2 RGBA F32 copies of the image.
+///
+/// ```
+/// # use dds::*;
+/// let data = vec![0.5_f32; 8 * 8 * 3];
+/// let image = ImageView::new(data.as_slice(), Size::new(8, 8), ColorFormat::RGB_F32).unwrap();
+/// let mut output = Vec::new();
+/// let report = encode_with_report(
+///     &mut output,
+///     image,
+///     Format::BC1_UNORM,
+///     &EncodeOptions::default(),
+/// )
+/// .unwrap();
+/// assert!(report.min_psnr() > 30.0);
+/// assert_eq!(report.clamped_pixels, 0);
+/// ```
+pub fn encode_with_report(
+    writer: &mut dyn Write,
+    image: ImageView,
+    format: Format,
+    options: &EncodeOptions,
+) -> Result<QualityReport, EncodeError> {
+    let encoders = get_encoders(format).ok_or(EncodeError::UnsupportedFormat(format))?;
+    check_range(image, format, options)?;
+
+    // the encoded surface, the decoded surface, and the reference are kept in
+    // memory at the same time
+    let mut options = options.clone();
+    let pixels = image.size().pixels();
+    let encoded_bytes = PixelInfo::from(format)
+        .surface_bytes(image.size())
+        .unwrap_or(u64::MAX);
+    options.reserve_memory(encoded_bytes.saturating_add(pixels * 2 * 16))?;
+
+    let mut encoded = Vec::new();
+    encoders.encode(&mut encoded, image, &options)?;
+    writer.write_all(&encoded)?;
+
+    let pixels = image.size().pixels() as usize;
+    let mut decoded = vec![[0_f32; 4]; pixels];
+    let decoded_view =
+        ImageViewMut::new(decoded.as_mut_slice(), image.size(), ColorFormat::RGBA_F32)
+            .expect("buffer has the correct size");
+    let decode_options = DecodeOptions {
+        memory_limit: usize::MAX,
+        ..Default::default()
+    };
+    decode(
+        &mut encoded.as_slice(),
+        decoded_view,
+        format,
+        &decode_options,
+    )
+    .expect("just-encoded data must be decodable");
+
+    let original = reference_rgba(image, format.channels());
+    let clamps = format.precision() != Precision::F32;
+
+    let mut squared_error = [0_f64; 4];
+    let mut max_error = [0_f32; 4];
+    let mut clamped_pixels = 0;
+    for (&original, decoded) in original.iter().zip(&decoded) {
+        if clamps && original.iter().any(|v| !(0.0..=1.0).contains(v)) {
+            clamped_pixels += 1;
+        }
+
+        for c in 0..4 {
+            let error = match (original[c].is_nan(), decoded[c].is_nan()) {
+                (false, false) => (original[c] - decoded[c]).abs(),
+                (true, true) => 0.0,
+                // a NaN on only one side is counted as an error of the peak value
+                _ => 1.0,
+            };
+            squared_error[c] += error as f64 * error as f64;
+            max_error[c] = max_error[c].max(error);
+        }
+    }
+
+    let psnr = squared_error.map(|squared_error| {
+        let mse = squared_error / pixels.max(1) as f64;
+        if mse == 0.0 {
+            f32::INFINITY
+        } else {
+            (-10.0 * mse.log10()) as f32
+        }
+    });
+
+    Ok(QualityReport {
+        psnr,
+        max_error,
+        clamped_pixels,
+    })
+}
+
+/// Returns the RGBA F32 values of the original image after converting it to
+/// the channels the format stores.
+///
+/// This makes the reference match what the decoder outputs for channels that
+/// aren't stored, e.g. alpha is always 1 for opaque formats.
+fn reference_rgba(image: ImageView, channels: Channels) -> Vec<[f32; 4]> {
+    let pixels = image.size().pixels() as usize;
+
+    let mut rgba = vec![[0_f32; 4]; pixels];
+    color::convert_to_rgba_f32(image.color(), image.data(), &mut rgba);
+    if channels == Channels::Rgba {
+        return rgba;
+    }
+
+    let mut stored = vec![0_f32; pixels * channels.count() as usize];
+    color::convert_channels::<f32>(
+        Channels::Rgba,
+        channels,
+        cast::as_bytes(rgba.as_slice()),
+        cast::as_bytes_mut(stored.as_mut_slice()),
+    );
+    color::convert_to_rgba_f32(
+        ColorFormat::new(channels, Precision::F32),
+        cast::as_bytes(stored.as_slice()),
+        &mut rgba,
+    );
+    rgba
+}
@@ -1,9 +1,11 @@
+use std::mem::size_of;
+
 use glam::Vec4;
 
 use crate::{
-    as_rgba_f32, cast, ch, convert_channels, convert_channels_for, fp10, fp11, fp16, n1, n10, n16,
-    n2, n4, n5, n6, n8, rgb9995f, s16, s8, util, xr10, yuv10, yuv16, yuv8, Channels, ColorFormat,
-    ColorFormatSet, EncodeError, Precision,
+    as_rgba_f32, cast, ch, convert_channels, convert_channels_for, fp10, fp11, fp16,
+    linear_to_srgb, n1, n10, n16, n2, n4, n5, n6, n8, rgb9995f, s16, s8, srgb_to_linear, util,
+    xr10, yuv10, yuv16, yuv8, Channels, ColorFormat, ColorFormatSet, EncodeError, Precision,
 };
 
 use super::{
@@ -60,15 +62,21 @@ where
         color,
         writer,
         width,
-        options,
+        mut options,
+        carry,
         ..
     } = args;
     let bytes_per_pixel = color.bytes_per_pixel() as usize;
 
     let error_padding = 2;
-    let mut error_buffer = vec![Vec4::ZERO; 2 * (width + error_padding * 2)];
-    let (mut current_line_error, mut next_line_error) =
-        error_buffer.split_at_mut(width + error_padding * 2);
+    let error_len = width + error_padding * 2;
+    options.reserve_memory((2 * error_len * size_of::<Vec4>()) as u64)?;
+    let mut error_buffer = vec![Vec4::ZERO; 2 * error_len];
+    let (mut current_line_error, mut next_line_error) = error_buffer.split_at_mut(error_len);
+    // continue diffusing the error of the previously encoded rows
+    if carry.error.len() == error_len {
+        next_line_error.copy_from_slice(&carry.error);
+    }
 
     let error_mask = match options.dithering {
         Dithering::None => Vec4::ZERO,
@@ -76,6 +84,7 @@ where
         Dithering::Color => Vec4::new(1.0, 1.0, 1.0, 0.0),
         Dithering::Alpha => Vec4::new(0.0, 0.0, 0.0, 1.0),
     };
+    let linear_light = options.linear_light_dithering && options.dithering.color();
 
     const BUFFER_PIXELS: usize = 512;
     let mut intermediate_buffer = [[0_f32; 4]; BUFFER_PIXELS];
@@ -101,7 +110,11 @@ where
 
             for (i, out) in intermediate.iter().zip(encoded.iter_mut()) {
                 let error = current_line_error[error_offset] + next_error_add;
-                let (encoded_pixel, mut error) = f(Vec4::from(*i) + error);
+                let (encoded_pixel, mut error) = if linear_light {
+                    quantize_linear_light(&f, Vec4::from(*i), error)
+                } else {
+                    f(Vec4::from(*i) + error)
+                };
 
                 // diffuse error with Floyd-Steinberg weights
                 error *= error_mask;
@@ -119,9 +132,44 @@ where
         }
     }
 
+    carry.error.clear();
+    carry.error.extend_from_slice(next_line_error);
+
     Ok(())
 }
 
+/// Quantizes an sRGB-encoded pixel such that the error of the color channels
+/// is measured in linear light.
+///
+/// The given and returned errors are linear for the color channels. Alpha is
+/// not affected.
+fn quantize_linear_light<EncodedPixel>(
+    f: &impl Fn(Vec4) -> (EncodedPixel, Vec4),
+    pixel: Vec4,
+    error: Vec4,
+) -> (EncodedPixel, Vec4) {
+    let linear = pixel.truncate().to_array().map(srgb_to_linear);
+    let linear = Vec4::new(linear[0], linear[1], linear[2], pixel.w) + error;
+    let target = Vec4::new(
+        linear_to_srgb(linear.x),
+        linear_to_srgb(linear.y),
+        linear_to_srgb(linear.z),
+        linear.w,
+    );
+
+    let (encoded, srgb_error) = f(target);
+
+    let quantized = (target - srgb_error)
+        .truncate()
+        .to_array()
+        .map(srgb_to_linear);
+    let linear_error = linear - Vec4::new(quantized[0], quantized[1], quantized[2], linear.w);
+    (
+        encoded,
+        Vec4::new(linear_error.x, linear_error.y, linear_error.z, srgb_error.w),
+    )
+}
+
 fn uncompressed_untyped(
     args: Args,
     bytes_per_encoded_pixel: usize,
@@ -174,7 +222,7 @@ fn simple_color_convert(
                     *o = s16::from_n16(u16::from_ne_bytes(*o)).to_ne_bytes();
                 });
             }
-            Precision::F32 => unreachable!(),
+            Precision::F32 | Precision::U32 => unreachable!(),
         }
     }
 
@@ -1,10 +1,12 @@
-use std::io::Write;
+use std::io::{Read, Write};
 
 use crate::{
-    header::Header,
+    color::{convert_to_rgba_f32, srgb_to_linear},
+    header::{Header, Reserved1Tag, WriteHeaderOptions},
     iter::{SurfaceInfo, SurfaceIterator},
     resize::{Aligner, ResizeState},
-    split_encode, ColorFormat, DataLayout, EncodeError, EncodeOptions, Format, ImageView, Size,
+    split_encode, Channels, ColorFormat, DataLayout, EncodeError, EncodeOptions, Format, ImageView,
+    MipOrder, Size,
 };
 
 pub struct Encoder<W> {
@@ -14,9 +16,93 @@ pub struct Encoder<W> {
     iter: SurfaceIterator,
     pub options: EncodeOptions,
     resize: Option<Box<(Aligner, ResizeState)>>,
+    mip_order: MipOrder,
+    /// Whether the header declares sRGB color data.
+    srgb: bool,
+    /// The encoded mipmaps of the current texture/volume by level. Only used
+    /// for [`MipOrder::SmallestFirst`].
+    pending_mipmaps: Vec<Vec<u8>>,
 }
 impl<W> Encoder<W> {
-    pub fn new(mut writer: W, format: Format, header: &Header) -> Result<Self, EncodeError>
+    pub fn new(writer: W, format: Format, header: &Header) -> Result<Self, EncodeError>
+    where
+        W: Write,
+    {
+        Self::new_with_mip_order(writer, format, header, MipOrder::LargestFirst)
+    }
+    /// Creates a new encoder that stores mipmaps in the given order.
+    ///
+    /// Surfaces are always written to the encoder in the standard order
+    /// (largest mipmap first). For [`MipOrder::SmallestFirst`], the encoder
+    /// buffers the encoded mipmaps of each texture (or volume) and writes them
+    /// in reverse order once the texture is complete. The signature of the mip
+    /// order is written into the header.
+    ///
+    /// See [`MipOrder`] for more information.
+    pub fn new_with_mip_order(
+        writer: W,
+        format: Format,
+        header: &Header,
+        mip_order: MipOrder,
+    ) -> Result<Self, EncodeError>
+    where
+        W: Write,
+    {
+        let options = WriteHeaderOptions::default();
+        Self::new_impl(writer, format, header, &options, mip_order, None)
+    }
+    /// Creates a new encoder that writes the given tag into the `reserved1`
+    /// field of the header.
+    ///
+    /// See [`Reserved1Tag`] for more information.
+    pub fn new_with_reserved1_tag(
+        writer: W,
+        format: Format,
+        header: &Header,
+        tag: &Reserved1Tag,
+    ) -> Result<Self, EncodeError>
+    where
+        W: Write,
+    {
+        let options = WriteHeaderOptions::default();
+        Self::new_impl(
+            writer,
+            format,
+            header,
+            &options,
+            MipOrder::LargestFirst,
+            Some(tag),
+        )
+    }
+    /// Creates a new encoder that writes the header with the given options.
+    ///
+    /// See [`WriteHeaderOptions`] for more information.
+    pub fn new_with_header_options(
+        writer: W,
+        format: Format,
+        header: &Header,
+        options: &WriteHeaderOptions,
+    ) -> Result<Self, EncodeError>
+    where
+        W: Write,
+    {
+        Self::new_impl(
+            writer,
+            format,
+            header,
+            options,
+            MipOrder::LargestFirst,
+            None,
+        )
+    }
+    fn new_impl(
+        mut writer: W,
+        format: Format,
+        header: &Header,
+        header_options: &WriteHeaderOptions,
+        mip_order: MipOrder,
+        tag: Option<&Reserved1Tag>,
+    ) -> Result<Self, EncodeError>
     where
         W: Write,
     {
@@ -26,7 +112,13 @@ impl<W> Encoder<W> {
 
         let layout = DataLayout::from_header_with(header, format.into())?;
 
-        header.write(&mut writer)?;
+        let mut raw = header.to_raw_with(header_options);
+        if let Some(tag) = tag {
+            tag.apply_to_raw_header(&mut raw);
+        }
+        mip_order.apply_to_raw_header(&mut raw);
+        writer.write_all(&Header::MAGIC)?;
+        raw.write(&mut writer)?;
 
         Ok(Self {
             writer,
@@ -35,9 +127,28 @@ impl<W> Encoder<W> {
             iter: SurfaceIterator::new(layout),
             options: EncodeOptions::default(),
             resize: None,
+            mip_order,
+            srgb: header.is_srgb(),
+            pending_mipmaps: Vec::new(),
         })
     }
 
+    /// Sets the options used to encode all following surfaces.
+    ///
+    /// ```
+    /// # use dds::{header::*, *};
+    /// let header = Header::new_image(8, 8, Format::BC1_UNORM);
+    /// let options = EncodeOptions::default().with_quality(CompressionQuality::Fast);
+    /// let encoder = Encoder::new(Vec::new(), Format::BC1_UNORM, &header)
+    ///     .unwrap()
+    ///     .with_options(options);
+    /// assert_eq!(encoder.options.quality, CompressionQuality::Fast);
+    /// ```
+    pub fn with_options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     pub fn format(&self) -> Format {
         self.format
     }
@@ -136,15 +247,53 @@ impl<W> Encoder<W> {
         if current.size() != image.size() {
             return Err(EncodeError::UnexpectedSurfaceSize);
         }
-        split_encode(&mut self.writer, image, self.format, &self.options)?;
-        self.iter.advance();
+        if options.generate_mipmaps && !options.resize_filter.is_valid() {
+            return Err(EncodeError::InvalidResizeFilter);
+        }
+        let encode_options = self.options.clone();
+        self.encode_next(image, &encode_options)?;
 
         if options.generate_mipmaps
             && self.layout.volume().is_none()
             && self.iter.current().map_or(false, |c| c.is_mipmap())
         {
-            let (align, resize) = Self::get_or_init(&mut self.resize);
+            // the resize state is taken out of `self`, so that mipmaps can be
+            // encoded while it's borrowed
+            let mut state = self
+                .resize
+                .take()
+                .unwrap_or_else(|| Box::new((Aligner::new(), ResizeState::new())));
+            let (align, resize) = &mut *state;
+
+            // only the user's image is checked for out-of-range values
+            let mut mip_options = encode_options;
+            mip_options.reject_out_of_range = false;
+
+            let linear = self.resizes_in_linear_light(image.color(), options);
+            let normal_map = options.normal_map.is_some();
+            let first_mip = self.iter.current().map_or(Size::new(0, 0), |c| c.size());
+            let mut bytes = mipmap_memory(image, first_mip, linear || normal_map);
+            if normal_map {
+                bytes += first_mip.pixels() * 16;
+            }
+            mip_options.reserve_memory(bytes)?;
+
+            let mut converted = Vec::new();
+            let image = if let Some(normal_map) = &options.normal_map {
+                to_normal_rgba(image, normal_map, &mut converted);
+                ImageView::new(converted.as_slice(), image.size(), ColorFormat::RGBA_F32)
+                    .expect("invalid normal map")
+            } else if linear {
+                to_linear_rgba(image, &mut converted);
+                mip_options.convert_to_srgb = true;
+                ImageView::new(converted.as_slice(), image.size(), ColorFormat::RGBA_F32)
+                    .expect("invalid linear image")
+            } else {
+                image
+            };
             let src = align.align(image);
+            let straight_alpha = options.resize_straight_alpha && options.normal_map.is_none();
+            let mut normals = Vec::new();
 
             let mut count = 0;
             while let Some(current) = self.iter.current() {
@@ -157,18 +306,22 @@ impl<W> Encoder<W> {
                 progress.report(1.0 - 0.3_f32.powi(count));
 
                 let mipmap_size = current.size();
-                let mip_data = resize.resize(
-                    &src,
-                    mipmap_size,
-                    options.resize_straight_alpha,
-                    options.resize_filter,
-                );
-                let mip =
-                    ImageView::new(mip_data, mipmap_size, image.color).expect("invalid mipmap");
-
-                split_encode(&mut self.writer, mip, self.format, &self.options)?;
-                self.iter.advance();
+                let mip_data =
+                    resize.resize(&src, mipmap_size, straight_alpha, options.resize_filter);
+                let mip = if let Some(normal_map) = &options.normal_map {
+                    normals.resize(mipmap_size.pixels() as usize, [0.0; 4]);
+                    convert_to_rgba_f32(src.color(), mip_data, &mut normals);
+                    renormalize(&mut normals, normal_map);
+                    ImageView::new(normals.as_slice(), mipmap_size, ColorFormat::RGBA_F32)
+                } else {
+                    ImageView::new(mip_data, mipmap_size, src.color())
+                }
+                .expect("invalid mipmap");
+
+                self.encode_next(mip, &mip_options)?;
             }
+
+            self.resize = Some(state);
         }
 
         progress.report(1.0);
@@ -176,30 +329,498 @@ impl<W> Encoder<W> {
         Ok(())
     }
 
-    fn get_or_init(
-        resize: &mut Option<Box<(Aligner, ResizeState)>>,
-    ) -> &mut (Aligner, ResizeState) {
-        if resize.is_none() {
-            *resize = Some(Box::new((Aligner::new(), ResizeState::new())));
+    /// Writes all depth slices of the current mipmap level of a volume
+    /// texture.
+    ///
+    /// The encoder must be at the first depth slice of a mipmap level, and
+    /// `slices` must contain one image for each depth slice of that level.
+    /// Otherwise, [`EncodeError::UnexpectedVolumeDepth`] is returned.
+    ///
+    /// ```
+    /// # use dds::{*, header::*};
+    /// let slice = vec![0_u8; 8 * 8 * 4];
+    /// let slice = ImageView::new(slice.as_slice(), Size::new(8, 8), ColorFormat::RGBA_U8).unwrap();
+    ///
+    /// let header = Header::new_volume(8, 8, 4, Format::BC1_UNORM);
+    /// let mut output = Vec::new();
+    /// let mut encoder = Encoder::new(&mut output, Format::BC1_UNORM, &header).unwrap();
+    /// encoder.write_volume(&[slice; 4]).unwrap();
+    /// encoder.finish().unwrap();
+    /// ```
+    pub fn write_volume(&mut self, slices: &[ImageView]) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        self.write_volume_impl(slices, ProgressToken::none(), &WriteOptions::default())
+    }
+
+    /// Writes all depth slices of the current mipmap level of a volume
+    /// texture.
+    ///
+    /// See [`Self::write_volume`] for more information.
+    ///
+    /// Unlike [`Self::write_surface_with`], this supports generating mipmaps.
+    /// If [`WriteOptions::generate_mipmaps`] is enabled and `slices` are the
+    /// depth slices of the level 0 volume, all mipmap levels of the volume
+    /// will be generated. Each depth slice of a mipmap is the average of the
+    /// resized level 0 depth slices it covers.
+    pub fn write_volume_with(
+        &mut self,
+        slices: &[ImageView],
+        mut progress: impl FnMut(f32),
+        options: &WriteOptions,
+    ) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        self.write_volume_impl(slices, ProgressToken::new(&mut progress), options)
+    }
+
+    fn write_volume_impl(
+        &mut self,
+        slices: &[ImageView],
+        mut progress: ProgressToken,
+        options: &WriteOptions,
+    ) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        progress.report(0.0);
+
+        let current = self.iter.current().ok_or(EncodeError::TooManySurfaces)?;
+        let level = current.mipmap_level();
+        let level_depth = match self.layout.volume().and_then(|v| v.get(level)) {
+            Some(volume) if self.iter.is_at_volume_level_start() => volume.depth(),
+            _ => return Err(EncodeError::UnexpectedVolumeDepth),
+        };
+        if slices.len() as u64 != level_depth as u64 {
+            return Err(EncodeError::UnexpectedVolumeDepth);
         }
-        resize.as_mut().unwrap()
+        if slices.iter().any(|slice| slice.size() != current.size()) {
+            return Err(EncodeError::UnexpectedSurfaceSize);
+        }
+        if options.generate_mipmaps && !options.resize_filter.is_valid() {
+            return Err(EncodeError::InvalidResizeFilter);
+        }
+
+        let encode_options = self.options.clone();
+        for slice in slices {
+            self.encode_next(*slice, &encode_options)?;
+        }
+
+        if options.generate_mipmaps
+            && level == 0
+            && self.iter.current().map_or(false, |c| c.is_mipmap())
+        {
+            self.generate_volume_mipmaps(slices, &mut progress, options)?;
+        }
+
+        progress.report(1.0);
+
+        Ok(())
+    }
+
+    /// Generates and writes all remaining mipmap levels of the current volume
+    /// from its level 0 depth slices.
+    fn generate_volume_mipmaps(
+        &mut self,
+        slices: &[ImageView],
+        progress: &mut ProgressToken,
+        options: &WriteOptions,
+    ) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        let mut state = self
+            .resize
+            .take()
+            .unwrap_or_else(|| Box::new((Aligner::new(), ResizeState::new())));
+        let (align, resize) = &mut *state;
+
+        // only the user's image is checked for out-of-range values
+        let mut mip_options = self.options.clone();
+        mip_options.reject_out_of_range = false;
+
+        let linear = slices.first().map_or(false, |slice| {
+            self.resizes_in_linear_light(slice.color(), options)
+        });
+        if linear {
+            mip_options.convert_to_srgb = true;
+        }
+
+        // slices are converted and resized one at a time
+        let first_mip = self.iter.current().map_or(Size::new(0, 0), |c| c.size());
+        let bytes = slices
+            .first()
+            .map_or(0, |slice| {
+                mipmap_memory(*slice, first_mip, linear || options.normal_map.is_some())
+            })
+            .saturating_add(first_mip.pixels() * 2 * 16);
+        mip_options.reserve_memory(bytes)?;
+
+        let straight_alpha = options.resize_straight_alpha && options.normal_map.is_none();
+        let mut converted_slice: Vec<[f32; 4]> = Vec::new();
+
+        let source_depth = slices.len();
+        let mut resized: Vec<[f32; 4]> = Vec::new();
+        let mut sum: Vec<[f32; 4]> = Vec::new();
+
+        let mut count = 0;
+        while let Some(current) = self.iter.current() {
+            if !current.is_mipmap() {
+                break;
+            }
+
+            count += 1;
+            progress.report(1.0 - 0.3_f32.powi(count));
+
+            let level = current.mipmap_level();
+            let mipmap_size = current.size();
+            let depth = self
+                .layout
+                .volume()
+                .and_then(|v| v.get(level))
+                .map_or(1, |v| v.depth()) as usize;
+
+            let pixels = mipmap_size.pixels() as usize;
+            resized.resize(pixels, [0.0; 4]);
+            for slice_index in 0..depth {
+                // the range of level 0 depth slices covered by this slice
+                let start = slice_index * source_depth / depth;
+                let end = ((slice_index + 1) * source_depth / depth).max(start + 1);
+
+                sum.clear();
+                sum.resize(pixels, [0.0; 4]);
+                for source in &slices[start..end] {
+                    let source = if let Some(normal_map) = &options.normal_map {
+                        to_normal_rgba(*source, normal_map, &mut converted_slice);
+                        ImageView::new(
+                            converted_slice.as_slice(),
+                            source.size(),
+                            ColorFormat::RGBA_F32,
+                        )
+                        .expect("invalid normal map")
+                    } else if linear {
+                        to_linear_rgba(*source, &mut converted_slice);
+                        ImageView::new(
+                            converted_slice.as_slice(),
+                            source.size(),
+                            ColorFormat::RGBA_F32,
+                        )
+                        .expect("invalid linear image")
+                    } else {
+                        *source
+                    };
+                    let src = align.align(source);
+                    let data =
+                        resize.resize(&src, mipmap_size, straight_alpha, options.resize_filter);
+                    convert_to_rgba_f32(src.color(), data, &mut resized);
+                    for (sum, pixel) in sum.iter_mut().zip(&resized) {
+                        // straight alpha is weighted by alpha, like in 2D
+                        let weight = if straight_alpha { pixel[3] } else { 1.0 };
+                        for c in 0..3 {
+                            sum[c] += pixel[c] * weight;
+                        }
+                        sum[3] += pixel[3];
+                    }
+                }
+
+                let n = (end - start) as f32;
+                for pixel in sum.iter_mut() {
+                    let alpha = pixel[3];
+                    let color_divisor = if straight_alpha { alpha } else { n };
+                    for channel in &mut pixel[..3] {
+                        *channel = if color_divisor > 0.0 {
+                            *channel / color_divisor
+                        } else {
+                            0.0
+                        };
+                    }
+                    pixel[3] = alpha / n;
+                }
+                if let Some(normal_map) = &options.normal_map {
+                    renormalize(&mut sum, normal_map);
+                }
+
+                let mip = ImageView::new(sum.as_slice(), mipmap_size, ColorFormat::RGBA_F32)
+                    .expect("invalid mipmap");
+                self.encode_next(mip, &mip_options)?;
+            }
+        }
+
+        self.resize = Some(state);
+        Ok(())
+    }
+
+    /// Whether mipmaps of images with the given color are resized in linear
+    /// light. See [`WriteOptions::resize_in_linear_light`].
+    fn resizes_in_linear_light(&self, color: ColorFormat, options: &WriteOptions) -> bool {
+        // images are already linear if they are converted to sRGB anyway
+        options.resize_in_linear_light
+            && options.normal_map.is_none()
+            && self.srgb
+            && !self.options.convert_to_srgb
+            && color.channels != Channels::Alpha
+    }
+
+    /// Copies the already encoded data of the current surface from the given
+    /// reader and advances to the next surface.
+    ///
+    /// The data must be in the format of this encoder.
+    pub(crate) fn copy_encoded_surface(&mut self, reader: &mut dyn Read) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        let current = self.iter.current().ok_or(EncodeError::TooManySurfaces)?;
+        let len = current.data_len();
+        self.write_next(|writer| {
+            let copied = std::io::copy(&mut reader.take(len), writer)?;
+            if copied != len {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Encodes the given image as the current surface and advances to the next.
+    fn encode_next(&mut self, image: ImageView, options: &EncodeOptions) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        let format = self.format;
+        self.write_next(|writer| split_encode(writer, image, format, options))
+    }
+
+    /// Writes the current surface with the given function and advances to the
+    /// next.
+    fn write_next(
+        &mut self,
+        write: impl FnOnce(&mut dyn Write) -> Result<(), EncodeError>,
+    ) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        if self.mip_order == MipOrder::LargestFirst {
+            write(&mut self.writer)?;
+            self.iter.advance();
+            return Ok(());
+        }
+
+        let current = self.iter.current();
+        let level = current.map_or(0, |c| c.mipmap_level()) as usize;
+        // all mipmaps of the current texture/volume are kept in memory
+        let pending: u64 = self.pending_mipmaps.iter().map(|m| m.len() as u64).sum();
+        let data_len = current.map_or(0, |c| c.data_len());
+        if pending.saturating_add(data_len) > self.options.memory_limit as u64 {
+            return Err(EncodeError::MemoryLimitExceeded);
+        }
+        if self.pending_mipmaps.len() <= level {
+            self.pending_mipmaps.resize_with(level + 1, Vec::new);
+        }
+        write(&mut self.pending_mipmaps[level])?;
+        self.iter.advance();
+
+        // write the mipmaps smallest first once the texture/volume is complete
+        if self.iter.is_at_element_start() {
+            for mipmap in self.pending_mipmaps.iter_mut().rev() {
+                self.writer.write_all(mipmap)?;
+                mipmap.clear();
+            }
+        }
+
+        Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum ResizeFilter {
     Nearest,
+    #[default]
     Box,
     Triangle,
     Mitchell,
     Lanczos3,
+    /// A Kaiser-windowed sinc filter.
+    ///
+    /// `radius` is the support of the filter in source pixels and `alpha`
+    /// controls the shape of the window. Larger `alpha` values reduce ringing
+    /// at the cost of sharpness. `radius: 3.0, alpha: 4.0` is a good default.
+    ///
+    /// `radius` must be finite and non-negative, and `alpha` must be finite.
+    /// Otherwise, generating mipmaps fails with
+    /// [`EncodeError::InvalidResizeFilter`].
+    Kaiser {
+        radius: f32,
+        alpha: f32,
+    },
+    /// A user-provided separable filter kernel.
+    Custom(FilterKernel),
 }
-impl Default for ResizeFilter {
-    fn default() -> Self {
-        Self::Box
+impl ResizeFilter {
+    /// Returns whether the parameters of this filter are valid.
+    ///
+    /// The built-in filters are always valid. See [`ResizeFilter::Kaiser`]
+    /// and [`FilterKernel`] for the requirements of the others. Generating
+    /// mipmaps with an invalid filter fails with
+    /// [`EncodeError::InvalidResizeFilter`].
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Self::Kaiser { radius, alpha } => {
+                radius.is_finite() && radius >= 0.0 && alpha.is_finite()
+            }
+            Self::Custom(kernel) => kernel.support.is_finite() && kernel.support >= 0.0,
+            _ => true,
+        }
     }
 }
 
+/// A user-defined separable filter kernel for resizing.
+///
+/// A kernel is a function `f(x)` that returns the weight of a source pixel at
+/// distance `x` from the sample position, together with its support. The
+/// weight of all pixels with `|x| > support` is assumed to be zero.
+///
+/// Weights do not need to be normalized. The resizer will normalize them
+/// so that all weights for a destination pixel sum to 1.
+///
+/// The support must be finite and non-negative. Otherwise, generating
+/// mipmaps fails with [`EncodeError::InvalidResizeFilter`].
+///
+/// ```
+/// # use dds::FilterKernel;
+/// fn tent(x: f32) -> f32 {
+///     (1.0 - x.abs()).max(0.0)
+/// }
+/// let kernel = FilterKernel::new(tent, 1.0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FilterKernel {
+    kernel: fn(f32) -> f32,
+    support: f32,
+}
+impl FilterKernel {
+    /// Creates a new filter kernel from the given function and support.
+    pub fn new(kernel: fn(f32) -> f32, support: f32) -> Self {
+        Self { kernel, support }
+    }
+
+    pub fn kernel(&self) -> fn(f32) -> f32 {
+        self.kernel
+    }
+    pub fn support(&self) -> f32 {
+        self.support
+    }
+}
+
+/// Converts the given sRGB image to linear RGBA and stores the result in
+/// the given buffer.
+fn to_linear_rgba(image: ImageView, rgba: &mut Vec<[f32; 4]>) {
+    rgba.clear();
+    rgba.resize(image.size().pixels() as usize, [0.0; 4]);
+    convert_to_rgba_f32(image.color(), image.data(), rgba);
+    for pixel in rgba.iter_mut() {
+        for value in &mut pixel[..3] {
+            *value = srgb_to_linear(*value);
+        }
+    }
+}
+
+/// Converts the given normal map to RGBA and stores the result in the given
+/// buffer. If enabled, the Z component of the normals is reconstructed.
+fn to_normal_rgba(image: ImageView, options: &NormalMapOptions, rgba: &mut Vec<[f32; 4]>) {
+    rgba.clear();
+    rgba.resize(image.size().pixels() as usize, [0.0; 4]);
+    convert_to_rgba_f32(image.color(), image.data(), rgba);
+    if options.reconstruct_z {
+        for pixel in rgba.iter_mut() {
+            let x = pixel[0] * 2.0 - 1.0;
+            let y = pixel[1] * 2.0 - 1.0;
+            let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+            pixel[2] = z * 0.5 + 0.5;
+        }
+    }
+}
+
+/// The number of bytes of the intermediate buffers used to resize the given
+/// image to the given mipmap size.
+///
+/// If `converted`, the image is converted to RGBA f32 before resizing.
+fn mipmap_memory(image: ImageView, mipmap_size: Size, converted: bool) -> u64 {
+    let pixels = image.size().pixels();
+    if converted {
+        // the converted image is already aligned
+        pixels * 16 + mipmap_size.pixels() * 16
+    } else {
+        // the image may have to be copied to be aligned
+        let bytes_per_pixel = image.color().bytes_per_pixel() as u64;
+        (pixels + mipmap_size.pixels()) * bytes_per_pixel
+    }
+}
+
+/// Normalizes the normals of a resized normal map and adjusts its roughness.
+fn renormalize(pixels: &mut [[f32; 4]], options: &NormalMapOptions) {
+    for pixel in pixels {
+        let x = pixel[0] * 2.0 - 1.0;
+        let y = pixel[1] * 2.0 - 1.0;
+        let z = pixel[2] * 2.0 - 1.0;
+        let len = (x * x + y * y + z * z).sqrt();
+
+        let [x, y, z] = if len > 1e-6 {
+            [x / len, y / len, z / len]
+        } else {
+            // opposing normals cancel out, so there is no meaningful direction
+            [0.0, 0.0, 1.0]
+        };
+        pixel[0] = x * 0.5 + 0.5;
+        pixel[1] = y * 0.5 + 0.5;
+        pixel[2] = z * 0.5 + 0.5;
+
+        if options.alpha_is_roughness {
+            // Toksvig: the shorter the average normal, the higher the variance
+            let variance = (1.0 - len.min(1.0)) / len.max(1e-6);
+            let roughness = pixel[3];
+            pixel[3] = (roughness * roughness + variance).sqrt().min(1.0);
+        }
+    }
+}
+
+/// Options for generating the mipmaps of normal maps.
+///
+/// Normals are stored in the red, green, and blue channels, where the range
+/// `[0, 1]` of each channel maps to the range `[-1, 1]` of the X, Y, and Z
+/// components of the normal. Averaging normals shortens them, so the normals
+/// of mipmaps are renormalized after resizing. This is essential for formats
+/// like [`Format::BC5_UNORM`], which only store X and Y and rely on unit
+/// normals to reconstruct Z.
+///
+/// See [`WriteOptions::normal_map`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[non_exhaustive]
+pub struct NormalMapOptions {
+    /// Whether to reconstruct the Z component of the normals from X and Y
+    /// instead of reading it from the blue channel.
+    ///
+    /// This is necessary for images that only store X and Y, e.g. the
+    /// decoded images of BC5 normal maps. The reconstructed Z is always
+    /// positive.
+    ///
+    /// Default: `false`
+    pub reconstruct_z: bool,
+    /// Whether the alpha channel stores the roughness of the surface.
+    ///
+    /// If enabled, the variance of the normals lost by averaging them is
+    /// added to the roughness of mipmaps, so that they don't appear shinier
+    /// than the full resolution texture (Toksvig). The variance is derived
+    /// from the length `l` of the average normal as `(1 - l) / l`, and the
+    /// roughness `r` of the mipmap becomes `sqrt(r^2 + variance)`, clamped to
+    /// 1.
+    ///
+    /// If disabled, the alpha channel is resized like any other channel.
+    ///
+    /// Default: `false`
+    pub alpha_is_roughness: bool,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct WriteOptions {
     /// Whether to generate mipmaps for the texture.
@@ -207,9 +828,10 @@ pub struct WriteOptions {
     /// Since the encoder knows exactly how many mipmaps are needed, it will
     /// generate all mipmaps until the next level 0 object or EOF.
     ///
-    /// Note: Generating mipmaps for volume depth slices is not supported. This
-    /// will **NOT** result in an error and instead the encoder will silently
-    /// ignore the option.
+    /// Note: Generating mipmaps for individual volume depth slices is not
+    /// supported. This will **NOT** result in an error and instead the encoder
+    /// will silently ignore the option. Use [`Encoder::write_volume_with`] to
+    /// generate the mipmaps of volumes.
     ///
     /// Default: `false`
     pub generate_mipmaps: bool,
@@ -232,6 +854,31 @@ pub struct WriteOptions {
     ///
     /// Default: [`ResizeFilter::Box`]
     pub resize_filter: ResizeFilter,
+    /// Whether mipmaps of sRGB textures are resized in linear light.
+    ///
+    /// Resizing sRGB-encoded values directly makes mipmaps too dark, since
+    /// the average of sRGB values is darker than the sRGB value of the
+    /// average light. If this option is enabled and the header of the file
+    /// is sRGB (see [`Header::is_srgb`]), the color channels are converted
+    /// to linear light before resizing and back to sRGB afterward. Resizing
+    /// is done in `f32`, so no precision is lost. Alpha is not affected.
+    ///
+    /// This has no effect for files that are not sRGB, and if
+    /// [`EncodeOptions::convert_to_srgb`] is enabled, because the image is
+    /// already linear in that case.
+    ///
+    /// Default: `true`
+    pub resize_in_linear_light: bool,
+    /// If set, the texture is treated as a normal map when generating
+    /// mipmaps.
+    ///
+    /// The normals of each mipmap are renormalized after resizing (see
+    /// [`NormalMapOptions`]). All channels are resized independently of each
+    /// other, so [`Self::resize_straight_alpha`] and
+    /// [`Self::resize_in_linear_light`] are ignored.
+    ///
+    /// Default: `None`
+    pub normal_map: Option<NormalMapOptions>,
 }
 impl Default for WriteOptions {
     fn default() -> Self {
@@ -239,6 +886,8 @@ impl Default for WriteOptions {
             generate_mipmaps: false,
             resize_straight_alpha: true,
             resize_filter: ResizeFilter::Box,
+            resize_in_linear_light: true,
+            normal_map: None,
         }
     }
 }
@@ -0,0 +1,220 @@
+use std::f32::consts::PI;
+use std::io::Write;
+
+use crate::color::as_rgba_f32;
+use crate::encoder::WriteOptions;
+use crate::header::Header;
+use crate::{
+    ColorFormat, CubeFace, EncodeError, EncodeOptions, Encoder, Format, ImageView, Size, Surface,
+};
+
+/// Options for converting equirectangular images to cube maps.
+///
+/// See [`equirect_to_cube_faces`] and [`encode_equirect_cube_map`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct EquirectOptions {
+    /// The width and height of each cube map face.
+    ///
+    /// If `None`, the face size is a quarter of the width of the
+    /// equirectangular image, which roughly preserves the resolution at the
+    /// equator.
+    ///
+    /// Default: `None`
+    pub face_size: Option<u32>,
+    /// Whether to make the edges of adjacent faces identical.
+    ///
+    /// Without seamless cube map filtering (e.g. in D3D9 or older OpenGL),
+    /// bilinear filtering does not blend across faces, so visible seams
+    /// appear at the edges of faces. If enabled, the texels at the edges of
+    /// each face are sampled exactly on the edge of the cube, so adjacent
+    /// faces have identical edge texels and the seams disappear. This
+    /// slightly stretches the faces.
+    ///
+    /// Default: `false`
+    pub fix_seams: bool,
+    /// Whether to generate mipmaps for the faces when writing a cube map.
+    ///
+    /// Default: `true`
+    pub mipmaps: bool,
+}
+impl Default for EquirectOptions {
+    fn default() -> Self {
+        Self {
+            face_size: None,
+            fix_seams: false,
+            mipmaps: true,
+        }
+    }
+}
+
+/// Resamples an equirectangular (latitude-longitude) image into the 6 faces
+/// of a cube map.
+///
+/// The horizontal axis of the image is the longitude and the vertical axis
+/// the latitude. The center of the image faces the +Z direction, the top
+/// edge is +Y, and +X is at three quarters of the width. Faces use the
+/// orientation of D3D cube maps and are returned in the order of
+/// [`CubeFace::ALL`]. The image is sampled with bilinear filtering and wraps
+/// around horizontally.
+///
+/// The returned faces have the color format [`ColorFormat::RGBA_F32`].
+///
+/// ```
+/// # use dds::*;
+/// let pixels = vec![0.5_f32; 64 * 32 * 3];
+/// let image = ImageView::new(pixels.as_slice(), Size::new(64, 32), ColorFormat::RGB_F32).unwrap();
+/// let faces = equirect_to_cube_faces(image, &EquirectOptions::default()).unwrap();
+/// assert_eq!(faces.len(), 6);
+/// assert_eq!(faces[0].size, Size::new(16, 16));
+/// ```
+pub fn equirect_to_cube_faces(
+    image: ImageView,
+    options: &EquirectOptions,
+) -> Result<Vec<Surface<f32>>, EncodeError> {
+    let face_size = options
+        .face_size
+        .unwrap_or_else(|| (image.width() / 4).max(1));
+    if image.size().is_empty() || face_size == 0 {
+        return Err(EncodeError::EmptySurface);
+    }
+
+    let mut buffer = vec![[0_f32; 4]; image.size().pixels() as usize];
+    let pixels = as_rgba_f32(image.color(), image.data(), &mut buffer);
+    let source = Equirect {
+        pixels,
+        width: image.width() as usize,
+        height: image.height() as usize,
+    };
+
+    // maps a texel index to a coordinate in [-1, 1]
+    let texel_coord = |i: u32| -> f32 {
+        if options.fix_seams && face_size > 1 {
+            2.0 * i as f32 / (face_size - 1) as f32 - 1.0
+        } else {
+            2.0 * (i as f32 + 0.5) / face_size as f32 - 1.0
+        }
+    };
+
+    let faces = CubeFace::ALL
+        .iter()
+        .map(|&face| {
+            let mut data = Vec::with_capacity(face_size as usize * face_size as usize * 4);
+            for y in 0..face_size {
+                let v = texel_coord(y);
+                for x in 0..face_size {
+                    let u = texel_coord(x);
+                    data.extend_from_slice(&source.sample(face_direction(face, u, v)));
+                }
+            }
+            Surface {
+                size: Size::new(face_size, face_size),
+                color: ColorFormat::RGBA_F32,
+                mipmap_level: 0,
+                data,
+            }
+        })
+        .collect();
+    Ok(faces)
+}
+
+/// Converts an equirectangular image to a cube map and writes it as a cube
+/// map DDS file in the given format.
+///
+/// The faces are created with [`equirect_to_cube_faces`]. If
+/// [`EquirectOptions::mipmaps`] is enabled, all mipmaps of the faces are
+/// generated.
+///
+/// ```
+/// # use dds::*;
+/// let pixels = vec![128_u8; 64 * 32 * 4];
+/// let image = ImageView::new(pixels.as_slice(), Size::new(64, 32), ColorFormat::RGBA_U8).unwrap();
+///
+/// let mut file = Vec::new();
+/// let options = EquirectOptions::default();
+/// encode_equirect_cube_map(&mut file, image, Format::BC1_UNORM, &options, &EncodeOptions::default()).unwrap();
+///
+/// let decoder = Decoder::new(file.as_slice()).unwrap();
+/// assert!(decoder.info().header().is_cube_map());
+/// assert_eq!(decoder.main_size(), Size::new(16, 16));
+/// ```
+pub fn encode_equirect_cube_map<W: Write>(
+    writer: W,
+    image: ImageView,
+    format: Format,
+    options: &EquirectOptions,
+    encode_options: &EncodeOptions,
+) -> Result<(), EncodeError> {
+    let faces = equirect_to_cube_faces(image, options)?;
+    let face_size = faces[0].size.width;
+
+    let mut header = Header::new_cube_map(face_size, face_size, format);
+    if options.mipmaps {
+        header = header.with_mipmaps();
+    }
+    let mut encoder = Encoder::new(writer, format, &header)?;
+    encoder.options = encode_options.clone();
+
+    let write_options = WriteOptions {
+        generate_mipmaps: options.mipmaps,
+        ..WriteOptions::default()
+    };
+    for face in &faces {
+        encoder.write_surface_with(face.view(), |_| {}, &write_options)?;
+    }
+    encoder.finish()
+}
+
+struct Equirect<'a> {
+    pixels: &'a [[f32; 4]],
+    width: usize,
+    height: usize,
+}
+impl Equirect<'_> {
+    /// Samples the image in the given direction with bilinear filtering.
+    fn sample(&self, [x, y, z]: [f32; 3]) -> [f32; 4] {
+        let len = (x * x + y * y + z * z).sqrt();
+        let longitude = x.atan2(z);
+        let latitude = (y / len).clamp(-1.0, 1.0).asin();
+
+        let px = (0.5 + longitude / (2.0 * PI)) * self.width as f32 - 0.5;
+        let py = (0.5 - latitude / PI) * self.height as f32 - 0.5;
+
+        let x0 = px.floor();
+        let y0 = py.floor();
+        let fx = px - x0;
+        let fy = py - y0;
+
+        // wrap around horizontally, clamp vertically
+        let wrap_x = |x: f32| (x as i64).rem_euclid(self.width as i64) as usize;
+        let clamp_y = |y: f32| (y.max(0.0) as usize).min(self.height - 1);
+        let (x0, x1) = (wrap_x(x0), wrap_x(x0 + 1.0));
+        let (y0, y1) = (clamp_y(y0), clamp_y(y0 + 1.0));
+
+        let get = |x: usize, y: usize| self.pixels[y * self.width + x];
+        let (p00, p10, p01, p11) = (get(x0, y0), get(x1, y0), get(x0, y1), get(x1, y1));
+        let mut result = [0.0; 4];
+        for c in 0..4 {
+            let top = p00[c] + (p10[c] - p00[c]) * fx;
+            let bottom = p01[c] + (p11[c] - p01[c]) * fx;
+            result[c] = top + (bottom - top) * fy;
+        }
+        result
+    }
+}
+
+/// Returns the (unnormalized) direction of the point with the given
+/// coordinates on the given face.
+///
+/// `u` goes from left to right and `v` from top to bottom, both in the range
+/// [-1, 1].
+fn face_direction(face: CubeFace, u: f32, v: f32) -> [f32; 3] {
+    match face {
+        CubeFace::PositiveX => [1.0, -v, -u],
+        CubeFace::NegativeX => [-1.0, -v, u],
+        CubeFace::PositiveY => [u, 1.0, v],
+        CubeFace::NegativeY => [u, -1.0, -v],
+        CubeFace::PositiveZ => [u, -v, 1.0],
+        CubeFace::NegativeZ => [-u, -v, -1.0],
+    }
+}
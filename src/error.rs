@@ -1,6 +1,8 @@
 use crate::{
-    header::{DxgiFormat, FourCC, Header},
-    Format, SizeMultiple,
+    detect,
+    header::{DxgiFormat, FourCC, Header, HeaderCompat, MaskPixelFormat},
+    ktx2::VkFormat,
+    Format, OutOfRangeStats, SizeMultiple,
 };
 
 #[derive(Debug)]
@@ -8,25 +10,157 @@ use crate::{
 pub enum FormatError {
     UnsupportedDxgiFormat(DxgiFormat),
     UnsupportedFourCC(FourCC),
+    /// The DX9 pixel format is not supported.
+    ///
+    /// This variant only exists for API compatibility and is never returned
+    /// by this crate. [`Format::from_header`] reports unsupported bit masks as
+    /// [`FormatError::UnsupportedMaskPixelFormat`], which also contains the
+    /// pixel format.
     UnsupportedPixelFormat,
+    /// The bit masks of the DX9 pixel format don't describe a supported
+    /// format.
+    ///
+    /// This is returned by [`Format::from_header`] for unsupported DX9 pixel
+    /// formats with bit masks. The pixel format is included so callers can
+    /// report it or look for similar formats with
+    /// [`FormatError::closest_match`].
+    UnsupportedMaskPixelFormat(MaskPixelFormat),
+}
+impl FormatError {
+    /// Returns the supported format that is most similar to the unsupported
+    /// format and how the two differ.
+    ///
+    /// This is a heuristic intended to help diagnose files written by buggy
+    /// tools. The returned format is not necessarily the format the file was
+    /// meant to have. The error message includes this match as a hint.
+    ///
+    /// ```
+    /// # use dds::{header::*, *};
+    /// // R8G8B8A8 with the masks of red and blue swapped and alpha in the lowest byte
+    /// let pixel_format = MaskPixelFormat {
+    ///     flags: PixelFormatFlags::RGBA,
+    ///     rgb_bit_count: RgbBitCount::Count32,
+    ///     r_bit_mask: 0xFF00_0000,
+    ///     g_bit_mask: 0xFF_0000,
+    ///     b_bit_mask: 0xFF00,
+    ///     a_bit_mask: 0xFF,
+    /// };
+    /// let error = FormatError::UnsupportedMaskPixelFormat(pixel_format);
+    /// let (_, difference) = error.closest_match().unwrap();
+    /// assert_eq!(difference, FormatDifference::SwappedMasks);
+    ///
+    /// let error = FormatError::UnsupportedDxgiFormat(DxgiFormat::R8G8B8A8_UINT);
+    /// assert_eq!(
+    ///     error.closest_match(),
+    ///     Some((Format::R8G8B8A8_UNORM, FormatDifference::ChannelType))
+    /// );
+    /// ```
+    pub fn closest_match(&self) -> Option<(Format, FormatDifference)> {
+        match self {
+            FormatError::UnsupportedDxgiFormat(dxgi_format) => {
+                detect::closest_to_dxgi(*dxgi_format)
+                    .map(|format| (format, FormatDifference::ChannelType))
+            }
+            FormatError::UnsupportedFourCC(four_cc) => detect::closest_to_four_cc(*four_cc)
+                .map(|format| (format, FormatDifference::FourCCSpelling)),
+            FormatError::UnsupportedPixelFormat => None,
+            FormatError::UnsupportedMaskPixelFormat(pixel_format) => {
+                detect::closest_to_masked(pixel_format)
+            }
+        }
+    }
 }
 impl std::fmt::Display for FormatError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             FormatError::UnsupportedDxgiFormat(format) => {
-                write!(f, "DXGI format {:?} is not supported for decoding", format)
+                write!(f, "DXGI format {:?} is not supported for decoding", format)?;
             }
             FormatError::UnsupportedFourCC(four_cc) => {
-                write!(f, "Unsupported {:?} in DX10 header extension", four_cc)
+                write!(f, "Unsupported {:?} in DX10 header extension", four_cc)?;
             }
             FormatError::UnsupportedPixelFormat => {
-                write!(f, "Unsupported pixel format in the DDS header")
+                write!(f, "Unsupported pixel format in the DDS header")?;
+            }
+            FormatError::UnsupportedMaskPixelFormat(pf) => {
+                write!(
+                    f,
+                    "Unsupported pixel format in the DDS header (flags: {:?}, bit count: {}, masks: R {:#010X} G {:#010X} B {:#010X} A {:#010X})",
+                    pf.flags,
+                    pf.rgb_bit_count as u32,
+                    pf.r_bit_mask,
+                    pf.g_bit_mask,
+                    pf.b_bit_mask,
+                    pf.a_bit_mask
+                )?;
             }
         }
+
+        if let Some((format, difference)) = self.closest_match() {
+            write!(f, "; looks like {:?} with {}", format, difference)?;
+        }
+        Ok(())
+    }
+}
+/// How an unsupported format differs from the most similar supported format.
+///
+/// See [`FormatError::closest_match`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FormatDifference {
+    /// The DXGI format has the same channels and bit depths, but a different
+    /// channel type (e.g. `UINT` instead of `UNORM`).
+    ChannelType,
+    /// The FourCC code is written with the wrong case or byte order.
+    FourCCSpelling,
+    /// The pixel format has the same bit count and masks, but different
+    /// flags.
+    Flags,
+    /// The pixel format has the same flags and masks, but a different bit
+    /// count.
+    BitCount,
+    /// The pixel format has the same bit count and masks, but the masks are
+    /// assigned to different channels.
+    SwappedMasks,
+}
+impl std::fmt::Display for FormatDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatDifference::ChannelType => write!(f, "a different channel type"),
+            FormatDifference::FourCCSpelling => write!(f, "a misspelled FourCC"),
+            FormatDifference::Flags => write!(f, "different flags"),
+            FormatDifference::BitCount => write!(f, "a different bit count"),
+            FormatDifference::SwappedMasks => write!(f, "swapped masks"),
+        }
     }
 }
 impl std::error::Error for FormatError {}
 
+/// The error returned when a [`Format`], [`DxgiFormat`], or [`FourCC`] cannot
+/// be parsed from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFormatError {
+    input: String,
+}
+impl ParseFormatError {
+    pub(crate) fn new(input: &str) -> Self {
+        Self {
+            input: input.to_string(),
+        }
+    }
+
+    /// The string that could not be parsed.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+impl std::fmt::Display for ParseFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown format {:?}", self.input)
+    }
+}
+impl std::error::Error for ParseFormatError {}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum LayoutError {
@@ -111,14 +245,69 @@ pub enum DecodeError {
     CannotSkipMipmapsInVolume,
     /// There are no further surfaces to decode.
     NoMoreSurfaces,
+    /// The requested surface (e.g. an array layer, cube map face, or mipmap
+    /// level) does not exist in the file.
+    ///
+    /// See [`crate::Decoder::decode_layer`], [`crate::Decoder::decode_face`],
+    /// and [`crate::Decoder::decode_depth_slices`].
+    SurfaceNotFound,
 
     /// The decoder has exceeded its memory limit.
     MemoryLimitExceeded,
+    /// The surfaces returned by a function of [`crate::Decoder`] would exceed
+    /// [`crate::DecodeOptions::max_output_size`].
+    OutputSizeExceeded,
+    /// Returned by [`crate::decode_channel`] when the output image does not
+    /// have exactly one channel.
+    ExpectedSingleChannel,
+    /// The size of a chroma sub-sampled surface is not a multiple of its
+    /// sub-sampling.
+    ///
+    /// This is only returned if [`crate::DecodeOptions::strict_sub_sampling`]
+    /// is enabled.
+    InvalidSize(SizeMultiple),
+    /// The operation was aborted via
+    /// [`crate::DecodeOptions::cancellation`].
+    Cancelled,
 
     Layout(LayoutError),
     Format(FormatError),
     Header(HeaderError),
+    Ktx2(Ktx2Error),
     Io(std::io::Error),
+
+    /// An error that occurred while decoding a specific surface.
+    ///
+    /// Functions that decode multiple surfaces (e.g.
+    /// [`crate::Decoder::read_all`]) wrap the errors of individual surfaces
+    /// in this variant, so it's possible to tell which surface of the file
+    /// failed. Use [`Self::without_location`] to get the underlying error.
+    InSurface(Box<SurfaceError>),
+}
+impl DecodeError {
+    /// Returns the underlying error if this is a [`DecodeError::InSurface`]
+    /// and the error itself otherwise.
+    pub fn without_location(&self) -> &DecodeError {
+        match self {
+            DecodeError::InSurface(error) => error.error.without_location(),
+            error => error,
+        }
+    }
+    /// The location of the surface the error occurred in, if known.
+    pub fn location(&self) -> Option<SurfaceLocation> {
+        match self {
+            DecodeError::InSurface(error) => Some(error.location),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn in_surface(self, location: SurfaceLocation) -> Self {
+        match self {
+            // keep the innermost location
+            DecodeError::InSurface(_) => self,
+            error => DecodeError::InSurface(Box::new(SurfaceError { location, error })),
+        }
+    }
 }
 
 impl std::fmt::Display for DecodeError {
@@ -150,14 +339,30 @@ impl std::fmt::Display for DecodeError {
             DecodeError::NoMoreSurfaces => {
                 write!(f, "No more surfaces to decode")
             }
+            DecodeError::SurfaceNotFound => {
+                write!(f, "The requested surface does not exist")
+            }
             DecodeError::MemoryLimitExceeded => {
                 write!(f, "Memory limit exceeded")
             }
+            DecodeError::OutputSizeExceeded => {
+                write!(f, "Maximum output size exceeded")
+            }
+            DecodeError::ExpectedSingleChannel => {
+                write!(f, "Expected an output image with a single channel")
+            }
+            DecodeError::InvalidSize(size) => {
+                write!(f, "Size is not a multiple of {:?}", size)
+            }
+            DecodeError::Cancelled => write!(f, "The operation was cancelled"),
 
             DecodeError::Layout(error) => write!(f, "{}", error),
             DecodeError::Format(error) => write!(f, "{}", error),
             DecodeError::Header(error) => write!(f, "Header error: {}", error),
+            DecodeError::Ktx2(error) => write!(f, "KTX2 error: {}", error),
             DecodeError::Io(error) => write!(f, "I/O error: {}", error),
+
+            DecodeError::InSurface(error) => write!(f, "{}", error),
         }
     }
 }
@@ -177,6 +382,11 @@ impl From<HeaderError> for DecodeError {
         DecodeError::Header(error)
     }
 }
+impl From<Ktx2Error> for DecodeError {
+    fn from(error: Ktx2Error) -> Self {
+        DecodeError::Ktx2(error)
+    }
+}
 impl From<std::io::Error> for DecodeError {
     fn from(error: std::io::Error) -> Self {
         DecodeError::Io(error)
@@ -189,12 +399,72 @@ impl std::error::Error for DecodeError {
             DecodeError::Layout(error) => Some(error),
             DecodeError::Format(error) => Some(error),
             DecodeError::Header(error) => Some(error),
+            DecodeError::Ktx2(error) => Some(error),
             DecodeError::Io(error) => Some(error),
+            DecodeError::InSurface(error) => Some(&error.error),
             _ => None,
         }
     }
 }
 
+/// The location of a surface in the data section of a DDS file.
+///
+/// See [`DecodeError::InSurface`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SurfaceLocation {
+    /// The index of the texture in texture arrays, or the index of the face
+    /// in cube maps (times the array index for cube map arrays).
+    ///
+    /// This is always 0 for volumes.
+    pub element: u32,
+    /// The mipmap level of the surface.
+    pub mipmap_level: u8,
+    /// The depth slice of volumes.
+    ///
+    /// This is always 0 for textures.
+    pub depth_slice: u32,
+    /// The offset in bytes of the surface relative to the start of the data
+    /// section.
+    ///
+    /// This is the start of the surface, not the exact position at which
+    /// the error occurred.
+    pub offset: u64,
+}
+impl std::fmt::Display for SurfaceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "element {}, mipmap level {}, depth slice {} (data offset {})",
+            self.element, self.mipmap_level, self.depth_slice, self.offset
+        )
+    }
+}
+
+/// An error with the location of the surface it occurred in.
+///
+/// See [`DecodeError::InSurface`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SurfaceError {
+    pub location: SurfaceLocation,
+    pub error: DecodeError,
+}
+impl std::fmt::Display for SurfaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to decode surface at {}: {}",
+            self.location, self.error
+        )
+    }
+}
+impl std::error::Error for SurfaceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum HeaderError {
@@ -206,6 +476,53 @@ pub enum HeaderError {
     InvalidResourceDimension(u32),
     InvalidAlphaMode(u32),
     InvalidArraySizeForTexture3D(u32),
+    /// The DX9 pixel format sets the FourCC flag, but also has bit masks that
+    /// describe a different format.
+    ///
+    /// See [`RawPixelFormat::contradicting_masks`](crate::header::RawPixelFormat::contradicting_masks).
+    AmbiguousPixelFormat(FourCC),
+    /// The width exceeds [`ParseOptions::max_width`](crate::header::ParseOptions::max_width).
+    WidthTooLarge {
+        width: u32,
+        max_width: u32,
+    },
+    /// The height exceeds [`ParseOptions::max_height`](crate::header::ParseOptions::max_height).
+    HeightTooLarge {
+        height: u32,
+        max_height: u32,
+    },
+    /// The depth exceeds [`ParseOptions::max_depth`](crate::header::ParseOptions::max_depth).
+    DepthTooLarge {
+        depth: u32,
+        max_depth: u32,
+    },
+    /// The mipmap count exceeds [`ParseOptions::max_mipmap_count`](crate::header::ParseOptions::max_mipmap_count).
+    MipmapCountTooLarge {
+        mipmap_count: u32,
+        max_mipmap_count: u32,
+    },
+    /// The array size exceeds [`ParseOptions::max_array_size`](crate::header::ParseOptions::max_array_size).
+    ArraySizeTooLarge {
+        array_size: u32,
+        max_array_size: u32,
+    },
+    /// The total number of pixels exceeds [`ParseOptions::max_pixels`](crate::header::ParseOptions::max_pixels).
+    TooManyPixels {
+        pixels: u64,
+        max_pixels: u64,
+    },
+    /// The pitch or linear size declared by the header doesn't match the
+    /// format and size of the header.
+    ///
+    /// This is only returned if [`ParseOptions::verify_pitch`](crate::header::ParseOptions::verify_pitch)
+    /// is enabled.
+    IncorrectPitchOrLinearSize {
+        declared: u32,
+        expected: u32,
+    },
+    /// Returned by [`Header::to_compat`] when the header can't be represented
+    /// in the header style required by the policy.
+    UnsupportedHeaderCompat(HeaderCompat),
 
     Io(std::io::Error),
 }
@@ -266,6 +583,71 @@ impl std::fmt::Display for HeaderError {
                     array_size
                 )
             }
+            HeaderError::AmbiguousPixelFormat(four_cc) => {
+                write!(
+                    f,
+                    "Ambiguous DDS header pixel format: {:?} contradicts the RGB bit masks",
+                    four_cc
+                )
+            }
+            HeaderError::WidthTooLarge { width, max_width } => {
+                write!(
+                    f,
+                    "Width {} exceeds the maximum width of {}",
+                    width, max_width
+                )
+            }
+            HeaderError::HeightTooLarge { height, max_height } => {
+                write!(
+                    f,
+                    "Height {} exceeds the maximum height of {}",
+                    height, max_height
+                )
+            }
+            HeaderError::DepthTooLarge { depth, max_depth } => {
+                write!(
+                    f,
+                    "Depth {} exceeds the maximum depth of {}",
+                    depth, max_depth
+                )
+            }
+            HeaderError::MipmapCountTooLarge {
+                mipmap_count,
+                max_mipmap_count,
+            } => {
+                write!(
+                    f,
+                    "Mipmap count {} exceeds the maximum mipmap count of {}",
+                    mipmap_count, max_mipmap_count
+                )
+            }
+            HeaderError::ArraySizeTooLarge {
+                array_size,
+                max_array_size,
+            } => {
+                write!(
+                    f,
+                    "Array size {} exceeds the maximum array size of {}",
+                    array_size, max_array_size
+                )
+            }
+            HeaderError::TooManyPixels { pixels, max_pixels } => {
+                write!(
+                    f,
+                    "Texture has {} pixels, which exceeds the maximum of {}",
+                    pixels, max_pixels
+                )
+            }
+            HeaderError::IncorrectPitchOrLinearSize { declared, expected } => {
+                write!(
+                    f,
+                    "Incorrect pitch or linear size {}, expected {}",
+                    declared, expected
+                )
+            }
+            HeaderError::UnsupportedHeaderCompat(compat) => {
+                write!(f, "The header cannot be represented as {:?}", compat)
+            }
 
             HeaderError::Io(error) => write!(f, "I/O error: {}", error),
         }
@@ -286,6 +668,86 @@ impl std::error::Error for HeaderError {
     }
 }
 
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Ktx2Error {
+    /// The file doesn't start with the KTX2 identifier.
+    InvalidIdentifier([u8; 12]),
+    /// The data of the file is supercompressed (e.g. with Zstandard or
+    /// BasisLZ), which isn't supported.
+    UnsupportedSupercompression(u32),
+    /// The format of the file cannot be decoded.
+    ///
+    /// See [`Ktx2Format::from_vk_format`](crate::ktx2::Ktx2Format::from_vk_format).
+    UnsupportedVkFormat(VkFormat),
+    /// The face count is neither 1 nor 6.
+    InvalidFaceCount(u32),
+    /// The length of a mipmap level in the level index does not match the
+    /// length computed from the dimensions and format of the texture.
+    InvalidLevelLength {
+        level: u32,
+        length: u64,
+    },
+    /// The dimensions of the texture describe data that is too large.
+    DataLayoutTooBig,
+    /// The format of a DDS file cannot be stored in a KTX2 file.
+    ///
+    /// See [`Ktx2Format::new`](crate::ktx2::Ktx2Format::new).
+    UnsupportedFormat(Format),
+    /// KTX2 files cannot store cube maps with missing faces.
+    PartialCubeMap,
+
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for Ktx2Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ktx2Error::InvalidIdentifier(identifier) => {
+                write!(f, "Invalid KTX2 identifier {:?}", identifier)
+            }
+            Ktx2Error::UnsupportedSupercompression(scheme) => {
+                write!(f, "Unsupported supercompression scheme {}", scheme)
+            }
+            Ktx2Error::UnsupportedVkFormat(format) => {
+                write!(f, "Unsupported VkFormat {}", format.0)
+            }
+            Ktx2Error::InvalidFaceCount(count) => {
+                write!(f, "Invalid face count {}, expected 1 or 6", count)
+            }
+            Ktx2Error::InvalidLevelLength { level, length } => {
+                write!(f, "Invalid length {} of mipmap level {}", length, level)
+            }
+            Ktx2Error::DataLayoutTooBig => {
+                write!(f, "Data layout described by the header is too large")
+            }
+            Ktx2Error::UnsupportedFormat(format) => {
+                write!(f, "Format {:?} cannot be stored in a KTX2 file", format)
+            }
+            Ktx2Error::PartialCubeMap => {
+                write!(f, "Partial cube maps cannot be stored in a KTX2 file")
+            }
+
+            Ktx2Error::Io(error) => write!(f, "I/O error: {}", error),
+        }
+    }
+}
+
+impl From<std::io::Error> for Ktx2Error {
+    fn from(error: std::io::Error) -> Self {
+        Ktx2Error::Io(error)
+    }
+}
+
+impl std::error::Error for Ktx2Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Ktx2Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum EncodeError {
@@ -302,9 +764,40 @@ pub enum EncodeError {
     /// surfaces declared in the header, but the user attempts to write
     /// additional surfaces.
     TooManySurfaces,
+    /// Returned by [`crate::Encoder::write_volume()`] when the encoder is not
+    /// at the start of a mipmap level of a volume texture or when the number
+    /// of depth slices doesn't match the depth of the level.
+    UnexpectedVolumeDepth,
     /// Returned by [`crate::Encoder::finish()`] when the encoder has not
     /// written all surfaces declared in the header.
     MissingSurfaces,
+    /// Returned by [`crate::SurfaceEncoder::finish()`] when not all rows of
+    /// the surface have been pushed.
+    MissingRows,
+    /// Returned by [`crate::encode_rect()`] when the rectangle is not
+    /// completely within the bounds of the surface.
+    RectOutOfBounds,
+    /// Returned by [`crate::encode_rect()`] when the rectangle is not aligned
+    /// to the blocks of the format.
+    UnalignedRect,
+    /// The image contains values outside the range [0, 1] that would be
+    /// clamped by the format.
+    ///
+    /// This is only returned if
+    /// [`crate::EncodeOptions::reject_out_of_range`] is enabled.
+    OutOfRange(OutOfRangeStats),
+    /// The encoder has exceeded its memory limit.
+    ///
+    /// See [`crate::EncodeOptions::memory_limit`].
+    MemoryLimitExceeded,
+    /// Returned by [`crate::Encoder`] when mipmaps should be generated with a
+    /// [`crate::ResizeFilter`] whose parameters are invalid.
+    ///
+    /// See [`crate::ResizeFilter::is_valid`].
+    InvalidResizeFilter,
+    /// Returned by [`crate::encode_with_fallback()`] when the list of formats
+    /// is empty.
+    NoFormats,
 
     Layout(LayoutError),
     Io(std::io::Error),
@@ -325,7 +818,25 @@ impl std::fmt::Display for EncodeError {
                 write!(f, "Unexpected size of the surface")
             }
             EncodeError::TooManySurfaces => write!(f, "Too many surfaces are attempted to written"),
+            EncodeError::UnexpectedVolumeDepth => {
+                write!(f, "Depth slices don't match the current volume level")
+            }
             EncodeError::MissingSurfaces => write!(f, "Not enough surfaces have been written"),
+            EncodeError::MissingRows => write!(f, "Not enough rows have been written"),
+            EncodeError::RectOutOfBounds => {
+                write!(f, "Rectangle is out of bounds of the surface size")
+            }
+            EncodeError::UnalignedRect => {
+                write!(f, "Rectangle is not aligned to the blocks of the format")
+            }
+            EncodeError::OutOfRange(stats) => write!(
+                f,
+                "Image contains {} values outside the range [0, 1] (min: {}, max: {})",
+                stats.count, stats.min, stats.max
+            ),
+            EncodeError::MemoryLimitExceeded => write!(f, "Memory limit exceeded"),
+            EncodeError::InvalidResizeFilter => write!(f, "Invalid resize filter parameters"),
+            EncodeError::NoFormats => write!(f, "No formats to try"),
 
             EncodeError::Layout(err) => write!(f, "Layout error: {}", err),
             EncodeError::Io(err) => write!(f, "IO error: {}", err),
@@ -352,3 +863,60 @@ impl From<std::io::Error> for EncodeError {
         EncodeError::Io(err)
     }
 }
+
+/// An error returned by [`crate::transcode()`] and
+/// [`crate::merge_bc4_into_bc5()`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TranscodeError {
+    /// The data layouts of the decoder and encoder are incompatible.
+    ///
+    /// Both layouts must have the same main size and the same kind (texture,
+    /// volume, or texture array with the same length and kind). The number
+    /// of mipmaps may differ, but the decoder must store its mipmaps largest
+    /// first.
+    IncompatibleLayout,
+    /// The formats of the source(s) and target are not supported by the
+    /// operation.
+    IncompatibleFormats,
+    Decode(DecodeError),
+    Encode(EncodeError),
+}
+
+impl std::fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TranscodeError::IncompatibleLayout => {
+                write!(
+                    f,
+                    "The data layouts of the source and target are incompatible"
+                )
+            }
+            TranscodeError::IncompatibleFormats => {
+                write!(f, "The formats of the source and target are incompatible")
+            }
+            TranscodeError::Decode(err) => write!(f, "Decode error: {}", err),
+            TranscodeError::Encode(err) => write!(f, "Encode error: {}", err),
+        }
+    }
+}
+impl std::error::Error for TranscodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TranscodeError::IncompatibleLayout | TranscodeError::IncompatibleFormats => None,
+            TranscodeError::Decode(err) => Some(err),
+            TranscodeError::Encode(err) => Some(err),
+        }
+    }
+}
+
+impl From<DecodeError> for TranscodeError {
+    fn from(err: DecodeError) -> Self {
+        TranscodeError::Decode(err)
+    }
+}
+impl From<EncodeError> for TranscodeError {
+    fn from(err: EncodeError) -> Self {
+        TranscodeError::Encode(err)
+    }
+}
@@ -0,0 +1,186 @@
+use std::num::NonZeroU32;
+
+use crate::{util::div_ceil, DataLayout, DataRegion, PixelInfo, Size};
+
+/// The alignment rules for placing subresources in a GPU upload buffer.
+///
+/// See [`DataLayout::footprints`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FootprintAlignment {
+    /// The row pitch of each subresource is a multiple of this many bytes.
+    pub row_pitch: NonZeroU32,
+    /// The offset of each subresource is a multiple of this many bytes.
+    pub placement: NonZeroU32,
+}
+impl FootprintAlignment {
+    /// The alignment rules of D3D12.
+    ///
+    /// Rows are aligned to `D3D12_TEXTURE_DATA_PITCH_ALIGNMENT` (256 bytes)
+    /// and subresources to `D3D12_TEXTURE_DATA_PLACEMENT_ALIGNMENT` (512
+    /// bytes).
+    pub const D3D12: Self = Self {
+        row_pitch: if let Some(n) = NonZeroU32::new(256) {
+            n
+        } else {
+            panic!()
+        },
+        placement: if let Some(n) = NonZeroU32::new(512) {
+            n
+        } else {
+            panic!()
+        },
+    };
+}
+
+/// The placement of a subresource in a GPU upload buffer.
+///
+/// This is equivalent to `D3D12_PLACED_SUBRESOURCE_FOOTPRINT` together with
+/// the number of rows and the row size returned by `GetCopyableFootprints`.
+/// It additionally describes where the subresource is stored in the data
+/// section of the DDS file.
+///
+/// A row is a row of blocks for block-compressed formats and a row of pixels
+/// otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SubresourceFootprint {
+    /// The D3D subresource index.
+    ///
+    /// This is `mipmap_level + array_slice * mipmap_count`, where each face
+    /// of a cube map is an array slice.
+    pub subresource: u32,
+    /// The byte offset of the subresource in the upload buffer.
+    pub offset: u64,
+    /// The width of the subresource in pixels, rounded up to a multiple of
+    /// the block width.
+    pub width: u32,
+    /// The height of the subresource in pixels, rounded up to a multiple of
+    /// the block height.
+    pub height: u32,
+    /// The depth of the subresource. This is 1 for everything but volumes.
+    pub depth: u32,
+    /// The number of bytes between the start of two rows in the upload
+    /// buffer.
+    pub row_pitch: u32,
+    /// The number of rows of each depth slice.
+    pub num_rows: u32,
+    /// The number of bytes of each row without padding.
+    pub row_size: u64,
+    /// The byte offset of the subresource in the data section of the DDS
+    /// file.
+    pub data_offset: u64,
+    /// The number of bytes between the start of two rows in the data section
+    /// of the DDS file.
+    pub data_row_pitch: u64,
+}
+impl SubresourceFootprint {
+    /// The number of bytes the subresource occupies in the upload buffer.
+    ///
+    /// The last row is not padded, so this is
+    /// `row_pitch * (num_rows * depth - 1) + row_size`.
+    pub fn buffer_len(&self) -> u64 {
+        let rows = self.num_rows as u64 * self.depth as u64;
+        self.row_pitch as u64 * (rows - 1) + self.row_size
+    }
+}
+
+/// The placement of all subresources of a DDS file in a GPU upload buffer.
+///
+/// See [`DataLayout::footprints`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Footprints {
+    /// The footprints of all subresources ordered by subresource index.
+    pub subresources: Vec<SubresourceFootprint>,
+    /// The number of bytes of the upload buffer required to hold all
+    /// subresources.
+    pub total_bytes: u64,
+}
+
+impl DataLayout {
+    /// Computes the placement of all subresources in a GPU upload buffer.
+    ///
+    /// This is equivalent to `ID3D12Device::GetCopyableFootprints` with
+    /// [`FootprintAlignment::D3D12`]. Each row of each subresource is copied
+    /// from `data_offset + i * data_row_pitch` in the data section of the
+    /// file to `offset + i * row_pitch` in the upload buffer.
+    ///
+    /// Returns `None` for bi-planar formats, which consist of multiple
+    /// planes in D3D12, and if any value overflows.
+    ///
+    /// ```
+    /// # use dds::{header::Header, *};
+    /// let header = Header::new_image(64, 64, Format::BC1_UNORM).with_mipmaps();
+    /// let layout = DataLayout::from_header(&header).unwrap();
+    /// let footprints = layout.footprints(FootprintAlignment::D3D12).unwrap();
+    ///
+    /// let level1 = &footprints.subresources[1];
+    /// assert_eq!((level1.width, level1.height), (32, 32));
+    /// assert_eq!((level1.row_pitch, level1.num_rows, level1.row_size), (256, 8, 64));
+    /// assert_eq!(level1.offset % 512, 0);
+    /// ```
+    pub fn footprints(&self, alignment: FootprintAlignment) -> Option<Footprints> {
+        let (block_size, bytes_per_block) = match self.pixel_info() {
+            PixelInfo::Fixed { bytes_per_pixel } => ((1, 1), bytes_per_pixel),
+            PixelInfo::Block(block) => (block.size(), block.bytes_per_block()),
+            PixelInfo::BiPlanar(_) => return None,
+        };
+
+        // (size, depth, data offset) of all subresources
+        let mut surfaces: Vec<(Size, u32, u64)> = Vec::new();
+        match self {
+            DataLayout::Texture(texture) => {
+                surfaces.extend(texture.iter_mips().map(|s| (s.size(), 1, s.data_offset())));
+            }
+            DataLayout::Volume(volume) => {
+                surfaces.extend(
+                    volume
+                        .iter_mips()
+                        .map(|v| (v.size(), v.depth(), v.data_offset())),
+                );
+            }
+            DataLayout::TextureArray(array) => {
+                for texture in array.iter() {
+                    surfaces.extend(texture.iter_mips().map(|s| (s.size(), 1, s.data_offset())));
+                }
+            }
+        }
+
+        let row_pitch_alignment = alignment.row_pitch.get() as u64;
+        let placement_alignment = alignment.placement.get() as u64;
+
+        let mut subresources = Vec::with_capacity(surfaces.len());
+        let mut total_bytes = 0_u64;
+        for (index, (size, depth, data_offset)) in surfaces.into_iter().enumerate() {
+            let blocks_x = div_ceil(size.width, block_size.0 as u32);
+            let num_rows = div_ceil(size.height, block_size.1 as u32);
+            let row_size = blocks_x as u64 * bytes_per_block as u64;
+            let row_pitch = div_ceil(row_size, row_pitch_alignment) * row_pitch_alignment;
+            let data_row_pitch = self.row_pitch(size.width).unwrap_or(row_size);
+
+            let offset =
+                div_ceil(total_bytes, placement_alignment).checked_mul(placement_alignment)?;
+            let footprint = SubresourceFootprint {
+                subresource: index.try_into().ok()?,
+                offset,
+                width: blocks_x.checked_mul(block_size.0 as u32)?,
+                height: num_rows.checked_mul(block_size.1 as u32)?,
+                depth,
+                row_pitch: row_pitch.try_into().ok()?,
+                num_rows,
+                row_size,
+                data_offset,
+                data_row_pitch,
+            };
+            let rows = num_rows as u64 * depth as u64;
+            let len = row_pitch.checked_mul(rows - 1)?.checked_add(row_size)?;
+            total_bytes = offset.checked_add(len)?;
+            subresources.push(footprint);
+        }
+
+        Some(Footprints {
+            subresources,
+            total_bytes,
+        })
+    }
+}
@@ -1,7 +1,8 @@
 use crate::header::{Dx9PixelFormat, DxgiFormat, FourCC, Header, MaskPixelFormat};
 use crate::{
-    decode::get_decoders, detect, encode::get_encoders, Channels, ColorFormat, EncodingSupport,
-    FormatError, Precision,
+    decode, decode::get_decoders, detect, encode::get_encoders, Bc1AlphaUsage, Channels,
+    ColorFormat, DecodeError, DecodeOptions, EncodingSupport, FormatError, ImageViewMut,
+    ParseFormatError, PixelInfo, Precision, Size, SizeMultiple,
 };
 
 /// The format of the pixel data of a surface.
@@ -9,6 +10,7 @@ use crate::{
 /// This enumeration is modelled after the [DXGI_FORMAT enumeration](https://learn.microsoft.com/en-us/windows/win32/api/dxgiformat/ne-dxgiformat-dxgi_format)
 /// and has the same semantics and naming conventions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[non_exhaustive]
 #[allow(non_camel_case_types)]
 pub enum Format {
@@ -106,6 +108,36 @@ pub enum Format {
     BC3_UNORM_RXGB,
 }
 impl Format {
+    /// Returns all formats.
+    ///
+    /// The order of formats is the same as in the declaration of this enum,
+    /// but may change between versions as new formats are added.
+    ///
+    /// ```
+    /// # use dds::Format;
+    /// assert!(Format::all().contains(&Format::BC1_UNORM));
+    /// ```
+    pub const fn all() -> &'static [Format] {
+        ALL_FORMATS
+    }
+
+    /// Returns the DX9 pixel formats (bit masks) that are detected as
+    /// supported formats by [`Format::from_header`].
+    ///
+    /// Some formats can be described by multiple pixel formats. The pixel
+    /// formats are returned in the order in which they are checked.
+    ///
+    /// ```
+    /// # use dds::{header::*, Format};
+    /// let (pixel_format, _) = Format::known_pixel_formats()
+    ///     .find(|(_, format)| *format == Format::B5G6R5_UNORM)
+    ///     .unwrap();
+    /// assert_eq!(pixel_format.rgb_bit_count, RgbBitCount::Count16);
+    /// ```
+    pub fn known_pixel_formats() -> impl Iterator<Item = (MaskPixelFormat, Format)> {
+        detect::known_pixel_formats()
+    }
+
     /// Returns the format of the surfaces from a DDS header.
     pub fn from_header(header: &Header) -> Result<Format, FormatError> {
         match header {
@@ -113,7 +145,7 @@ impl Format {
                 Dx9PixelFormat::FourCC(four_cc) => detect::four_cc_to_supported(*four_cc)
                     .ok_or(FormatError::UnsupportedFourCC(*four_cc)),
                 Dx9PixelFormat::Mask(pixel_format) => detect::masked_to_supported(pixel_format)
-                    .ok_or(FormatError::UnsupportedPixelFormat),
+                    .ok_or_else(|| FormatError::UnsupportedMaskPixelFormat(pixel_format.clone())),
             },
             Header::Dx10(dx10) => {
                 if let Some(format) = detect::special_cases(dx10) {
@@ -131,6 +163,28 @@ impl Format {
     pub const fn from_dxgi(dxgi: DxgiFormat) -> Option<Format> {
         detect::dxgi_format_to_supported(dxgi)
     }
+    /// Returns all DXGI formats that map to this format.
+    ///
+    /// This is the reverse of [`Format::from_dxgi`]. The returned DXGI formats
+    /// are exactly the ones for which [`Format::from_dxgi`] returns this
+    /// format, in ascending order. Typically, these are the `TYPELESS`,
+    /// `UNORM`, and `UNORM_SRGB` variants of a DXGI format.
+    ///
+    /// Formats that can only be detected through other means (e.g. FourCC
+    /// codes, pixel format masks, or the alpha mode of the DX10 header)
+    /// return an empty slice.
+    ///
+    /// ```
+    /// # use dds::{Format, header::DxgiFormat};
+    /// assert_eq!(
+    ///     Format::BC1_UNORM.dxgi_aliases(),
+    ///     &[DxgiFormat::BC1_TYPELESS, DxgiFormat::BC1_UNORM, DxgiFormat::BC1_UNORM_SRGB]
+    /// );
+    /// assert_eq!(Format::R8G8B8_UNORM.dxgi_aliases(), &[]);
+    /// ```
+    pub fn dxgi_aliases(self) -> &'static [DxgiFormat] {
+        detect::dxgi_aliases(self)
+    }
     /// Returns the format of a surface from a FourCC code.
     ///
     /// `None` if the FourCC code is not supported for decoding.
@@ -146,6 +200,19 @@ impl Format {
     pub const fn channels(&self) -> Channels {
         self.color().channels
     }
+    /// Same as [`Self::channels`], but reports [`Channels::Rgb`] for
+    /// [`Format::BC1_UNORM`] if the given analysis found no transparent
+    /// pixels.
+    ///
+    /// [`Self::channels`] always reports [`Channels::Rgba`] for BC1, since
+    /// BC1 blocks may contain transparent pixels. See [`Bc1AlphaUsage`] for
+    /// how to analyze the data of a BC1 surface.
+    pub fn channels_with_bc1_alpha(&self, usage: &Bc1AlphaUsage) -> Channels {
+        match self {
+            Format::BC1_UNORM => usage.channels(),
+            _ => self.channels(),
+        }
+    }
     /// The precision/bit depth closest to the values in the surface.
     ///
     /// DDS supports formats with various precisions and ranges, and not all of
@@ -167,6 +234,207 @@ impl Format {
     pub const fn color(&self) -> ColorFormat {
         get_decoders(*self).native_color()
     }
+    /// Whether decoding this format to the given color format is a simple
+    /// copy on the current platform.
+    ///
+    /// This is the case if the encoded pixels are byte-for-byte identical to
+    /// the decoded pixels, e.g. [`Format::R8G8B8A8_UNORM`] decoded as
+    /// [`ColorFormat::RGBA_U8`]. Formats with 16- and 32-bit values are
+    /// stored in little endian, so they are only zero-copy on little-endian
+    /// platforms.
+    ///
+    /// Decoding zero-copy combinations reads pixels directly into the output
+    /// buffer. For in-memory data, [`decode_borrowed`](crate::decode_borrowed)
+    /// can be used to avoid the copy entirely.
+    pub fn is_zero_copy(self, color: ColorFormat) -> bool {
+        get_decoders(self).zero_copy_color() == Some(color)
+    }
+
+    /// Decodes a surface of this format from the given in-memory data.
+    ///
+    /// This is the same as [`decode`](crate::decode), but reads from a slice
+    /// instead of a reader. Since the length of the data is known upfront,
+    /// truncated data is detected before any decoding happens. Any bytes
+    /// after the surface are ignored.
+    ///
+    /// Returns the number of bytes of encoded surface data, i.e. the offset
+    /// of the next surface in `data`.
+    ///
+    /// If `data` is too short to contain the entire surface, an IO error of
+    /// kind [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) is returned
+    /// and `image` is left unchanged.
+    pub fn decode_from_slice(
+        self,
+        data: &[u8],
+        image: ImageViewMut,
+        options: &DecodeOptions,
+    ) -> Result<usize, DecodeError> {
+        let surface_bytes = PixelInfo::from(self)
+            .surface_bytes(image.size())
+            .and_then(|bytes| usize::try_from(bytes).ok())
+            .filter(|&bytes| bytes <= data.len())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "data is too short for the surface",
+                )
+            })?;
+
+        let mut surface = &data[..surface_bytes];
+        decode(&mut surface, image, self, options)?;
+        Ok(surface_bytes)
+    }
+
+    /// The pixel info of this format.
+    ///
+    /// This is the same as `PixelInfo::from(self)`.
+    pub fn pixel_info(self) -> PixelInfo {
+        PixelInfo::from(self)
+    }
+    /// Returns the `(width, height)` of the smallest unit of pixels that is
+    /// stored together.
+    ///
+    /// This is the block size for block-compressed and channel-packed
+    /// sub-sampled formats (see [`PixelInfo::Block`]), the chroma
+    /// sub-sampling for bi-planar formats, and `(1, 1)` for all other
+    /// formats.
+    ///
+    /// ```
+    /// # use dds::Format;
+    /// assert_eq!(Format::R8G8B8A8_UNORM.block_dimensions(), (1, 1));
+    /// assert_eq!(Format::BC1_UNORM.block_dimensions(), (4, 4));
+    /// assert_eq!(Format::YUY2.block_dimensions(), (2, 1));
+    /// assert_eq!(Format::NV12.block_dimensions(), (2, 2));
+    /// ```
+    pub fn block_dimensions(self) -> (u8, u8) {
+        match self.pixel_info() {
+            PixelInfo::Fixed { .. } => (1, 1),
+            PixelInfo::Block(block) => block.size(),
+            PixelInfo::BiPlanar(bi_planar) => bi_planar.plane2_sub_sampling(),
+        }
+    }
+    /// Returns the number of bytes of one block of [`Self::block_dimensions`]
+    /// pixels.
+    ///
+    /// For uncompressed formats, this is the number of bytes per pixel. For
+    /// bi-planar formats, this includes the bytes of both planes.
+    ///
+    /// ```
+    /// # use dds::Format;
+    /// assert_eq!(Format::R8G8B8A8_UNORM.bytes_per_block(), 4);
+    /// assert_eq!(Format::BC1_UNORM.bytes_per_block(), 8);
+    /// assert_eq!(Format::NV12.bytes_per_block(), 6);
+    /// ```
+    pub fn bytes_per_block(self) -> u8 {
+        match self.pixel_info() {
+            PixelInfo::Fixed { bytes_per_pixel } => bytes_per_pixel,
+            PixelInfo::Block(block) => block.bytes_per_block(),
+            PixelInfo::BiPlanar(bi_planar) => {
+                let (x, y) = bi_planar.plane2_sub_sampling();
+                bi_planar.plane1_bytes_per_pixel() * x * y + bi_planar.plane2_bytes_per_sample()
+            }
+        }
+    }
+    /// Returns the number of bits per pixel.
+    ///
+    /// If the number of bits per pixel is not an integer, the result is
+    /// rounded up. See [`PixelInfo::bits_per_pixel`].
+    pub fn bits_per_pixel(self) -> u32 {
+        self.pixel_info().bits_per_pixel()
+    }
+    /// Returns the number of bits each channel is stored with.
+    ///
+    /// For block-compressed formats, this is the precision of the endpoints.
+    /// For formats where the precision changes from block to block (BC6H,
+    /// BC7, and ASTC), `None` is returned.
+    ///
+    /// Channels are always given in RGBA order, regardless of the order in
+    /// memory. YUV formats report the bit depth of their Y, U, and V samples
+    /// as the R, G, and B bit depths.
+    ///
+    /// ```
+    /// # use dds::{ChannelBits, Format};
+    /// assert_eq!(
+    ///     Format::B5G5R5A1_UNORM.channel_bits(),
+    ///     Some(ChannelBits { r: 5, g: 5, b: 5, a: 1 })
+    /// );
+    /// assert_eq!(
+    ///     Format::R8_UNORM.channel_bits(),
+    ///     Some(ChannelBits { r: 8, g: 0, b: 0, a: 0 })
+    /// );
+    /// assert_eq!(Format::BC7_UNORM.channel_bits(), None);
+    /// ```
+    pub const fn channel_bits(self) -> Option<ChannelBits> {
+        use Format as F;
+
+        let [r, g, b, a] = match self {
+            // uncompressed formats
+            F::R8G8B8_UNORM | F::B8G8R8_UNORM | F::B8G8R8X8_UNORM => [8, 8, 8, 0],
+            F::R8G8B8A8_UNORM | F::R8G8B8A8_SNORM | F::B8G8R8A8_UNORM => [8, 8, 8, 8],
+            F::B5G6R5_UNORM => [5, 6, 5, 0],
+            F::B5G5R5A1_UNORM => [5, 5, 5, 1],
+            F::B4G4R4A4_UNORM | F::A4B4G4R4_UNORM => [4, 4, 4, 4],
+            F::R8_SNORM | F::R8_UNORM => [8, 0, 0, 0],
+            F::R8G8_UNORM | F::R8G8_SNORM => [8, 8, 0, 0],
+            F::A8_UNORM => [0, 0, 0, 8],
+            F::R16_UNORM | F::R16_SNORM | F::R16_FLOAT => [16, 0, 0, 0],
+            F::R16G16_UNORM | F::R16G16_SNORM | F::R16G16_FLOAT => [16, 16, 0, 0],
+            F::R16G16B16A16_UNORM | F::R16G16B16A16_SNORM | F::R16G16B16A16_FLOAT => {
+                [16, 16, 16, 16]
+            }
+            F::R10G10B10A2_UNORM | F::R10G10B10_XR_BIAS_A2_UNORM => [10, 10, 10, 2],
+            F::R11G11B10_FLOAT => [11, 11, 10, 0],
+            // the 5-bit exponent is shared by all channels
+            F::R9G9B9E5_SHAREDEXP => [9, 9, 9, 0],
+            F::R32_FLOAT => [32, 0, 0, 0],
+            F::R32G32_FLOAT => [32, 32, 0, 0],
+            F::R32G32B32_FLOAT => [32, 32, 32, 0],
+            F::R32G32B32A32_FLOAT => [32, 32, 32, 32],
+            F::AYUV => [8, 8, 8, 8],
+            F::Y410 => [10, 10, 10, 2],
+            F::Y416 => [16, 16, 16, 16],
+
+            // sub-sampled formats
+            F::R1_UNORM => [1, 0, 0, 0],
+            F::R8G8_B8G8_UNORM | F::G8R8_G8B8_UNORM | F::UYVY | F::YUY2 => [8, 8, 8, 0],
+            F::Y210 => [10, 10, 10, 0],
+            F::Y216 => [16, 16, 16, 0],
+
+            // bi-planar formats
+            F::NV12 => [8, 8, 8, 0],
+            F::P010 => [10, 10, 10, 0],
+            F::P016 => [16, 16, 16, 0],
+
+            // block compression formats
+            F::BC1_UNORM => [5, 6, 5, 1],
+            F::BC2_UNORM | F::BC2_UNORM_PREMULTIPLIED_ALPHA => [5, 6, 5, 4],
+            F::BC3_UNORM | F::BC3_UNORM_PREMULTIPLIED_ALPHA => [5, 6, 5, 8],
+            F::BC4_UNORM | F::BC4_SNORM => [8, 0, 0, 0],
+            F::BC5_UNORM | F::BC5_SNORM => [8, 8, 0, 0],
+            F::BC6H_UF16 | F::BC6H_SF16 | F::BC7_UNORM => return None,
+
+            // ASTC
+            F::ASTC_4X4_UNORM
+            | F::ASTC_5X4_UNORM
+            | F::ASTC_5X5_UNORM
+            | F::ASTC_6X5_UNORM
+            | F::ASTC_6X6_UNORM
+            | F::ASTC_8X5_UNORM
+            | F::ASTC_8X6_UNORM
+            | F::ASTC_8X8_UNORM
+            | F::ASTC_10X5_UNORM
+            | F::ASTC_10X6_UNORM
+            | F::ASTC_10X8_UNORM
+            | F::ASTC_10X10_UNORM
+            | F::ASTC_12X10_UNORM
+            | F::ASTC_12X12_UNORM => return None,
+
+            // non-standard formats
+            // R is stored in the 8-bit alpha channel of BC3
+            F::BC3_UNORM_RXGB => [8, 6, 5, 0],
+        };
+        Some(ChannelBits { r, g, b, a })
+    }
 
     /// Returns information about the encoding support of this format.
     ///
@@ -178,6 +446,239 @@ impl Format {
             None
         }
     }
+    /// Returns whether this format can be decoded and encoded.
+    ///
+    /// ```
+    /// # use dds::Format;
+    /// let encodable: Vec<Format> = Format::all()
+    ///     .iter()
+    ///     .copied()
+    ///     .filter(|format| format.support().encode.is_some())
+    ///     .collect();
+    /// assert!(encodable.contains(&Format::BC7_UNORM));
+    /// ```
+    pub const fn support(self) -> SupportInfo {
+        SupportInfo {
+            decode: true,
+            encode: self.encoding_support(),
+        }
+    }
+
+    /// The size multiple that images of this format must satisfy to be
+    /// encoded.
+    ///
+    /// Formats without any constraints (or that do not support encoding)
+    /// return [`SizeMultiple::ONE`].
+    ///
+    /// ```
+    /// # use dds::{Format, SizeMultiple};
+    /// assert_eq!(Format::BC1_UNORM.size_multiple(), SizeMultiple::ONE);
+    /// assert_eq!(Format::NV12.size_multiple(), SizeMultiple::M2_2);
+    /// ```
+    pub const fn size_multiple(self) -> SizeMultiple {
+        if let Some(support) = self.encoding_support() {
+            support.size_multiple()
+        } else {
+            SizeMultiple::ONE
+        }
+    }
+    /// Rounds the given size up to the nearest size that can be encoded with
+    /// this format.
+    ///
+    /// See [`Size::round_up_to_multiple`].
+    ///
+    /// ```
+    /// # use dds::{Format, Size};
+    /// assert_eq!(Format::NV12.round_size_up(Size::new(5, 3)), Size::new(6, 4));
+    /// ```
+    pub const fn round_size_up(self, size: Size) -> Size {
+        size.round_up_to_multiple(self.size_multiple())
+    }
+    /// Rounds the given size down to the nearest size that can be encoded with
+    /// this format.
+    ///
+    /// Note that the returned size may be empty. See
+    /// [`Size::round_down_to_multiple`].
+    ///
+    /// ```
+    /// # use dds::{Format, Size};
+    /// assert_eq!(Format::NV12.round_size_down(Size::new(5, 3)), Size::new(4, 2));
+    /// ```
+    pub const fn round_size_down(self, size: Size) -> Size {
+        size.round_down_to_multiple(self.size_multiple())
+    }
+}
+
+/// Describes the operations a format supports.
+///
+/// See [`Format::support`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct SupportInfo {
+    /// Whether the format can be decoded.
+    ///
+    /// This is currently `true` for all formats.
+    pub decode: bool,
+    /// Whether and how the format can be encoded. `None` if the format does
+    /// not support encoding.
+    ///
+    /// This is the same as [`Format::encoding_support`].
+    pub encode: Option<EncodingSupport>,
+}
+
+/// The number of bits each channel of a format is stored with.
+///
+/// Channels that are not stored have 0 bits. See [`Format::channel_bits`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ChannelBits {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// All variants of [`Format`] in declaration order. See [`Format::all`].
+const ALL_FORMATS: &[Format] = &[
+    Format::R8G8B8_UNORM,
+    Format::B8G8R8_UNORM,
+    Format::R8G8B8A8_UNORM,
+    Format::R8G8B8A8_SNORM,
+    Format::B8G8R8A8_UNORM,
+    Format::B8G8R8X8_UNORM,
+    Format::B5G6R5_UNORM,
+    Format::B5G5R5A1_UNORM,
+    Format::B4G4R4A4_UNORM,
+    Format::A4B4G4R4_UNORM,
+    Format::R8_SNORM,
+    Format::R8_UNORM,
+    Format::R8G8_UNORM,
+    Format::R8G8_SNORM,
+    Format::A8_UNORM,
+    Format::R16_UNORM,
+    Format::R16_SNORM,
+    Format::R16G16_UNORM,
+    Format::R16G16_SNORM,
+    Format::R16G16B16A16_UNORM,
+    Format::R16G16B16A16_SNORM,
+    Format::R10G10B10A2_UNORM,
+    Format::R11G11B10_FLOAT,
+    Format::R9G9B9E5_SHAREDEXP,
+    Format::R16_FLOAT,
+    Format::R16G16_FLOAT,
+    Format::R16G16B16A16_FLOAT,
+    Format::R32_FLOAT,
+    Format::R32G32_FLOAT,
+    Format::R32G32B32_FLOAT,
+    Format::R32G32B32A32_FLOAT,
+    Format::R10G10B10_XR_BIAS_A2_UNORM,
+    Format::AYUV,
+    Format::Y410,
+    Format::Y416,
+    Format::R1_UNORM,
+    Format::R8G8_B8G8_UNORM,
+    Format::G8R8_G8B8_UNORM,
+    Format::UYVY,
+    Format::YUY2,
+    Format::Y210,
+    Format::Y216,
+    Format::NV12,
+    Format::P010,
+    Format::P016,
+    Format::BC1_UNORM,
+    Format::BC2_UNORM,
+    Format::BC2_UNORM_PREMULTIPLIED_ALPHA,
+    Format::BC3_UNORM,
+    Format::BC3_UNORM_PREMULTIPLIED_ALPHA,
+    Format::BC4_UNORM,
+    Format::BC4_SNORM,
+    Format::BC5_UNORM,
+    Format::BC5_SNORM,
+    Format::BC6H_UF16,
+    Format::BC6H_SF16,
+    Format::BC7_UNORM,
+    Format::ASTC_4X4_UNORM,
+    Format::ASTC_5X4_UNORM,
+    Format::ASTC_5X5_UNORM,
+    Format::ASTC_6X5_UNORM,
+    Format::ASTC_6X6_UNORM,
+    Format::ASTC_8X5_UNORM,
+    Format::ASTC_8X6_UNORM,
+    Format::ASTC_8X8_UNORM,
+    Format::ASTC_10X5_UNORM,
+    Format::ASTC_10X6_UNORM,
+    Format::ASTC_10X8_UNORM,
+    Format::ASTC_10X10_UNORM,
+    Format::ASTC_12X10_UNORM,
+    Format::ASTC_12X12_UNORM,
+    Format::BC3_UNORM_RXGB,
+];
+// Tables indexed by `format as usize` rely on this list containing every
+// format at the index of its discriminant.
+const _: () = {
+    let mut i = 0;
+    while i < ALL_FORMATS.len() {
+        assert!(
+            ALL_FORMATS[i] as usize == i,
+            "ALL_FORMATS must be in declaration order"
+        );
+        i += 1;
+    }
+};
+
+/// Short names of formats that are commonly used instead of the full name.
+const FORMAT_ALIASES: &[(&str, Format)] = &[
+    ("BC1", Format::BC1_UNORM),
+    ("BC2", Format::BC2_UNORM),
+    ("BC3", Format::BC3_UNORM),
+    ("BC4", Format::BC4_UNORM),
+    ("BC5", Format::BC5_UNORM),
+    ("BC6H", Format::BC6H_UF16),
+    ("BC7", Format::BC7_UNORM),
+];
+
+/// Formats the name of the format, e.g. `BC1_UNORM`.
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+/// Parses the name of a format (e.g. `BC7_UNORM`), a common alias (e.g.
+/// `BC7`), a FourCC code (e.g. `DXT5`), or the name of a DXGI format (e.g.
+/// `BC7_UNORM_SRGB`). Names are case-insensitive.
+///
+/// ```
+/// # use dds::Format;
+/// assert_eq!("BC7_UNORM".parse(), Ok(Format::BC7_UNORM));
+/// assert_eq!("dxt5".parse(), Ok(Format::BC3_UNORM));
+/// assert_eq!("DXGI_FORMAT_BC1_UNORM_SRGB".parse(), Ok(Format::BC1_UNORM));
+/// assert!("BC8".parse::<Format>().is_err());
+/// ```
+impl std::str::FromStr for Format {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let by_name = ALL_FORMATS
+            .iter()
+            .copied()
+            .find(|format| format!("{:?}", format).eq_ignore_ascii_case(s));
+        let by_alias = || {
+            FORMAT_ALIASES
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(s))
+                .map(|(_, format)| *format)
+        };
+        let by_four_cc = || {
+            let four_cc: FourCC = s.to_ascii_uppercase().parse().ok()?;
+            Format::from_four_cc(four_cc)
+        };
+        let by_dxgi = || Format::from_dxgi(s.parse().ok()?);
+
+        by_name
+            .or_else(by_alias)
+            .or_else(by_four_cc)
+            .or_else(by_dxgi)
+            .ok_or_else(|| ParseFormatError::new(s))
+    }
 }
 
 impl TryFrom<Format> for DxgiFormat {
@@ -66,9 +66,11 @@
 
 use crate::{
     cast,
-    detect::{dxgi_to_four_cc, dxgi_to_masked, four_cc_to_dxgi, masked_to_dxgi},
-    util::{get_maximum_mipmap_count, read_u32_le_array, NON_ZERO_U32_ONE},
-    CubeMapFaces, DataLayout, DataRegion, Format, HeaderError, PixelInfo, Size,
+    detect::{
+        dxgi_to_four_cc, dxgi_to_masked, four_cc_to_dxgi, masked_to_dxgi, masked_to_supported,
+    },
+    util::{div_ceil, get_maximum_mipmap_count, read_u32_le_array, NON_ZERO_U32_ONE},
+    CubeMapFaces, DataLayout, DataRegion, Format, HeaderError, ParseFormatError, PixelInfo, Size,
 };
 use bitflags::bitflags;
 use std::{
@@ -261,6 +263,46 @@ impl RawHeader {
 impl RawPixelFormat {
     const SIZE: u32 = 32;
 
+    /// Returns the bit mask pixel format described by this pixel format if it
+    /// contradicts the FourCC code.
+    ///
+    /// Some writers set the [`PixelFormatFlags::FOURCC`] flag together with
+    /// [`PixelFormatFlags::RGB`] (or similar flags) and populated bit masks.
+    /// This returns `Some` if the bit masks describe a supported format that
+    /// is different from the format of the FourCC code. Otherwise (including
+    /// DX10 headers), `None` is returned.
+    ///
+    /// When parsing such pixel formats, the FourCC code takes precedence.
+    /// Strict parsing rejects them with [`HeaderError::AmbiguousPixelFormat`].
+    /// With [`ParseOptions::permissive`], the bit masks are used instead iff
+    /// only they match the length of the file (see [`ParseOptions::file_len`]).
+    pub fn contradicting_masks(&self) -> Option<MaskPixelFormat> {
+        if !self.flags.contains(PixelFormatFlags::FOURCC) || self.four_cc == FourCC::DX10 {
+            return None;
+        }
+        let mask_flags = PixelFormatFlags::RGB
+            | PixelFormatFlags::YUV
+            | PixelFormatFlags::LUMINANCE
+            | PixelFormatFlags::ALPHA;
+        if !self.flags.intersects(mask_flags) {
+            return None;
+        }
+
+        let masks = MaskPixelFormat {
+            flags: self.flags - PixelFormatFlags::FOURCC,
+            rgb_bit_count: RgbBitCount::try_from(self.rgb_bit_count).ok()?,
+            r_bit_mask: self.r_bit_mask,
+            g_bit_mask: self.g_bit_mask,
+            b_bit_mask: self.b_bit_mask,
+            a_bit_mask: self.a_bit_mask,
+        };
+        let masked_format = masked_to_supported(&masks)?;
+        if Format::from_four_cc(self.four_cc) == Some(masked_format) {
+            return None;
+        }
+        Some(masks)
+    }
+
     fn new_four_cc(four_cc: FourCC) -> RawPixelFormat {
         Self {
             size: Self::SIZE,
@@ -291,6 +333,167 @@ impl RawDx10Header {
     pub(crate) const SIZE: u32 = 20;
 }
 
+/// Metadata stored in the `reserved1` field of a DDS header.
+///
+/// DirectX ignores `reserved1`, so some writers use it to identify
+/// themselves. This enum represents the known conventions and allows custom
+/// tags to be written into new files.
+///
+/// Note that this crate uses `reserved1[5..9]` for the signatures of
+/// [`crate::CubeAtlasLayout`] and [`crate::MipOrder`]. These take precedence
+/// over tags written with [`Reserved1Tag::Bytes`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Reserved1Tag {
+    /// NVIDIA Texture Tools.
+    ///
+    /// Stored as `"NVTT"` in `reserved1[9]` followed by the version in
+    /// `reserved1[10]`. The version is encoded as `major << 16 | minor << 8 | revision`.
+    Nvtt { version: u32 },
+    /// The DDS plugin of GIMP.
+    ///
+    /// Stored as `"GIMP-DDS"` in `reserved1[0..2]` followed by the version in
+    /// `reserved1[2]`.
+    Gimp { version: u32 },
+    /// A custom 4-character identifier and a version.
+    ///
+    /// Stored as the identifier in `reserved1[0]` followed by the version in
+    /// `reserved1[1]`. E.g. `Custom { id: FourCC(u32::from_le_bytes(*b"DDSD")), version: 1 }`.
+    ///
+    /// When reading, only identifiers consisting of ASCII letters, digits,
+    /// `-`, and `_` are detected.
+    Custom { id: FourCC, version: u32 },
+    /// Arbitrary data filling all 44 bytes of `reserved1`.
+    ///
+    /// This is never detected when reading. Use [`RawHeader::reserved1`]
+    /// to read arbitrary data.
+    Bytes([u8; 44]),
+}
+impl Reserved1Tag {
+    const NVTT: u32 = u32::from_le_bytes(*b"NVTT");
+    const GIMP: [u32; 2] = [u32::from_le_bytes(*b"GIMP"), u32::from_le_bytes(*b"-DDS")];
+
+    /// Detects a known tag in the `reserved1` field of a header.
+    ///
+    /// Returns `None` if `reserved1` doesn't contain any known tag.
+    pub fn from_raw_header(raw: &RawHeader) -> Option<Self> {
+        let r = &raw.reserved1;
+        if r[9] == Self::NVTT {
+            return Some(Self::Nvtt { version: r[10] });
+        }
+        if r[0..2] == Self::GIMP {
+            return Some(Self::Gimp { version: r[2] });
+        }
+
+        let is_id_char = |b: u8| b.is_ascii_alphanumeric() || b == b'-' || b == b'_';
+        if r[0].to_le_bytes().into_iter().all(is_id_char) {
+            return Some(Self::Custom {
+                id: FourCC(r[0]),
+                version: r[1],
+            });
+        }
+
+        None
+    }
+    /// Writes this tag into the `reserved1` field of the given header.
+    ///
+    /// Only the values used by the tag are changed, except for
+    /// [`Reserved1Tag::Bytes`] which overwrites all of `reserved1`.
+    pub fn apply_to_raw_header(&self, raw: &mut RawHeader) {
+        let r = &mut raw.reserved1;
+        match *self {
+            Self::Nvtt { version } => {
+                r[9] = Self::NVTT;
+                r[10] = version;
+            }
+            Self::Gimp { version } => {
+                r[0..2].copy_from_slice(&Self::GIMP);
+                r[2] = version;
+            }
+            Self::Custom { id, version } => {
+                r[0] = id.0;
+                r[1] = version;
+            }
+            Self::Bytes(bytes) => {
+                for (value, chunk) in r.iter_mut().zip(bytes.chunks_exact(4)) {
+                    *value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                }
+            }
+        }
+    }
+}
+
+/// The software that likely wrote a DDS file.
+///
+/// See [`RawHeader::detect_writer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DdsWriter {
+    /// NVIDIA Texture Tools, including the NVIDIA Texture Tools Exporter.
+    Nvtt,
+    /// DirectXTex and tools based on it, like texconv.
+    DirectXTex,
+    /// The DDS plugin of GIMP.
+    Gimp,
+    /// The legacy DDS plugin of Paint.NET.
+    PaintNet,
+    /// The writer could not be determined.
+    Unknown,
+}
+
+impl RawHeader {
+    /// The signature of the NVIDIA Texture Tools Exporter in `reserved1[9]`.
+    const NVTT_EXPORTER: u32 = u32::from_le_bytes(*b"NVT3");
+
+    /// Guesses the software that wrote the file with this header.
+    ///
+    /// NVTT and GIMP are detected by the signatures they store in `reserved1`
+    /// (see [`Reserved1Tag`]). Since other writers leave `reserved1` empty,
+    /// they are detected using the FourCC codes and header flags they
+    /// typically write:
+    ///
+    /// - DirectXTex is the only writer that uses the `BC4U`, `BC4S`, `BC5U`,
+    ///   and `BC5S` FourCC codes. It also always sets
+    ///   [`DdsFlags::MIPMAP_COUNT`] and declares the pitch or linear size.
+    /// - The legacy Paint.NET plugin declares the pitch or linear size, but
+    ///   omits [`DdsFlags::MIPMAP_COUNT`] for files without mipmaps.
+    ///
+    /// This is a heuristic. Files written by other software (including this
+    /// crate) may be detected as one of the above writers.
+    pub fn detect_writer(&self) -> DdsWriter {
+        match Reserved1Tag::from_raw_header(self) {
+            Some(Reserved1Tag::Nvtt { .. }) => return DdsWriter::Nvtt,
+            Some(Reserved1Tag::Gimp { .. }) => return DdsWriter::Gimp,
+            _ => {}
+        }
+        if self.reserved1[9] == Self::NVTT_EXPORTER {
+            return DdsWriter::Nvtt;
+        }
+        if self.reserved1 != [0; 11] {
+            return DdsWriter::Unknown;
+        }
+
+        let pf = &self.pixel_format;
+        if pf.flags.contains(PixelFormatFlags::FOURCC)
+            && [FourCC::BC4U, FourCC::BC4S, FourCC::BC5U, FourCC::BC5S].contains(&pf.four_cc)
+        {
+            return DdsWriter::DirectXTex;
+        }
+
+        let declares_size = self
+            .flags
+            .intersects(DdsFlags::PITCH | DdsFlags::LINEAR_SIZE);
+        if !declares_size {
+            return DdsWriter::Unknown;
+        }
+        if self.flags.contains(DdsFlags::MIPMAP_COUNT) {
+            DdsWriter::DirectXTex
+        } else if self.mipmap_count <= 1 && self.caps == Caps::TEXTURE {
+            DdsWriter::PaintNet
+        } else {
+            DdsWriter::Unknown
+        }
+    }
+}
+
 /// A parsed header, split by version.
 ///
 /// <https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dds-header>
@@ -320,6 +523,12 @@ pub struct Dx9Header {
 /// structure or with a `DXGI_FORMAT` from the Direct3D 10 and later APIs. This
 /// enum represents all cases in a single type.
 ///
+/// If the raw pixel format sets the [`PixelFormatFlags::FOURCC`] flag, the
+/// FourCC code takes precedence over the bit masks. Pixel formats whose bit
+/// masks contradict the FourCC code are ambiguous and rejected unless
+/// [`ParseOptions::permissive`] is enabled. See
+/// [`RawPixelFormat::contradicting_masks`] for details.
+///
 /// <https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dds-pixelformat>
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Dx9PixelFormat {
@@ -412,7 +621,38 @@ pub struct Dx10Header {
     pub alpha_mode: AlphaMode,
 }
 
+/// A policy for choosing between DX9 and DX10 headers when writing files.
+///
+/// DX9 headers are supported by more (older) programs, while DX10 headers
+/// can represent more formats and texture arrays. Not every header can be
+/// represented in both styles.
+///
+/// See [`Header::to_compat`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum HeaderCompat {
+    /// Use a DX9 header if possible, and a DX10 header otherwise.
+    PreferDx9,
+    /// Use a DX10 header if possible, and a DX9 header otherwise.
+    ///
+    /// This is the policy used by constructors like [`Header::new_image`].
+    #[default]
+    PreferDx10,
+    /// Always use a DX9 header.
+    Dx9Only,
+    /// Always use a DX10 header.
+    Dx10Only,
+}
+
 /// Options specifying how to read and interpret a DDS header.
+///
+/// ## Limits
+///
+/// The `max_*` options limit the dimensions a header may declare. Their
+/// defaults follow the limits of Direct3D 11 and 12 (16384 for width and
+/// height, 2048 for depth, 2048 array elements) and additionally limit the
+/// total number of pixels to 2^32. They reject headers that were previously
+/// accepted. Set the limits to `u32::MAX` (and [`Self::max_pixels`] to
+/// `u64::MAX`) to restore the old behavior.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct ParseOptions {
@@ -470,6 +710,78 @@ pub struct ParseOptions {
     /// let options = ParseOptions::new_permissive(file_len);
     /// ```
     pub file_len: Option<u64>,
+
+    /// The maximum width of the texture in pixels.
+    ///
+    /// Headers with a larger width are rejected with
+    /// [`HeaderError::WidthTooLarge`], even if [`Self::permissive`] is set.
+    /// Together with the other limits, this protects against malicious
+    /// headers that claim huge dimensions to cause excessive allocations.
+    ///
+    /// Defaults to `16384`, the maximum width of 2D textures in Direct3D 11
+    /// and 12.
+    pub max_width: u32,
+    /// The maximum height of the texture in pixels.
+    ///
+    /// Headers with a larger height are rejected with
+    /// [`HeaderError::HeightTooLarge`].
+    ///
+    /// Defaults to `16384`, the maximum height of 2D textures in Direct3D 11
+    /// and 12.
+    pub max_height: u32,
+    /// The maximum depth of volume textures in pixels.
+    ///
+    /// Headers with a larger depth are rejected with
+    /// [`HeaderError::DepthTooLarge`].
+    ///
+    /// Defaults to `2048`, the maximum depth of volume textures in Direct3D 11
+    /// and 12.
+    pub max_depth: u32,
+    /// The maximum number of mipmaps.
+    ///
+    /// Headers with more mipmaps are rejected with
+    /// [`HeaderError::MipmapCountTooLarge`]. A texture with dimensions that
+    /// fit into `u32` never has more than 32 mipmaps.
+    ///
+    /// Mipmap counts above 255 are not checked against this limit. They can't
+    /// be represented by [`DataLayout`] and fail with
+    /// [`LayoutError::TooManyMipMaps`](crate::LayoutError::TooManyMipMaps) as
+    /// before.
+    ///
+    /// Defaults to `32`.
+    pub max_mipmap_count: u32,
+    /// The maximum number of elements in a texture array.
+    ///
+    /// For cube maps, this is the number of cubes, not faces. Headers with a
+    /// larger array size are rejected with [`HeaderError::ArraySizeTooLarge`].
+    ///
+    /// Defaults to `2048`, the maximum array size in Direct3D 11 and 12.
+    pub max_array_size: u32,
+    /// The maximum number of pixels of the main surfaces of all textures.
+    ///
+    /// This is the product of width, height, depth, array size, and the
+    /// number of cube map faces. Mipmaps are not counted. Headers that
+    /// declare more pixels are rejected with [`HeaderError::TooManyPixels`].
+    /// Unlike the per-dimension limits, this also bounds the size of large
+    /// texture arrays and volumes.
+    ///
+    /// Defaults to `2^32` (e.g. a 16384x16384 texture array with 16
+    /// elements).
+    pub max_pixels: u64,
+
+    /// Whether to verify the pitch or linear size declared by the header.
+    ///
+    /// Many writers put incorrect values into the `pitch_or_linear_size`
+    /// field, so it is ignored by default. If this is set to `true`, a
+    /// declared pitch (or linear size) that doesn't match the format and
+    /// width of the header is rejected with
+    /// [`HeaderError::IncorrectPitchOrLinearSize`].
+    ///
+    /// If [`Self::permissive`] is set, incorrect values are never rejected
+    /// and reported as [`HeaderFix::PitchOrLinearSize`] instead.
+    ///
+    /// Defaults to `false`.
+    pub verify_pitch: bool,
 }
 impl ParseOptions {
     pub fn new_permissive(file_len: Option<u64>) -> Self {
@@ -479,6 +791,63 @@ impl ParseOptions {
             ..Default::default()
         }
     }
+
+    /// Sets [`Self::skip_magic_bytes`].
+    ///
+    /// ```
+    /// # use dds::header::*;
+    /// let options = ParseOptions::default().with_skip_magic_bytes(true);
+    /// assert!(options.skip_magic_bytes);
+    /// ```
+    pub fn with_skip_magic_bytes(mut self, skip_magic_bytes: bool) -> Self {
+        self.skip_magic_bytes = skip_magic_bytes;
+        self
+    }
+    /// Sets [`Self::permissive`].
+    pub fn with_permissive(mut self, permissive: bool) -> Self {
+        self.permissive = permissive;
+        self
+    }
+    /// Sets [`Self::file_len`].
+    pub fn with_file_len(mut self, file_len: Option<u64>) -> Self {
+        self.file_len = file_len;
+        self
+    }
+    /// Sets [`Self::max_width`].
+    pub fn with_max_width(mut self, max_width: u32) -> Self {
+        self.max_width = max_width;
+        self
+    }
+    /// Sets [`Self::max_height`].
+    pub fn with_max_height(mut self, max_height: u32) -> Self {
+        self.max_height = max_height;
+        self
+    }
+    /// Sets [`Self::max_depth`].
+    pub fn with_max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// Sets [`Self::max_mipmap_count`].
+    pub fn with_max_mipmap_count(mut self, max_mipmap_count: u32) -> Self {
+        self.max_mipmap_count = max_mipmap_count;
+        self
+    }
+    /// Sets [`Self::max_array_size`].
+    pub fn with_max_array_size(mut self, max_array_size: u32) -> Self {
+        self.max_array_size = max_array_size;
+        self
+    }
+    /// Sets [`Self::max_pixels`].
+    pub fn with_max_pixels(mut self, max_pixels: u64) -> Self {
+        self.max_pixels = max_pixels;
+        self
+    }
+    /// Sets [`Self::verify_pitch`].
+    pub fn with_verify_pitch(mut self, verify_pitch: bool) -> Self {
+        self.verify_pitch = verify_pitch;
+        self
+    }
 }
 #[allow(clippy::derivable_impls)]
 impl Default for ParseOptions {
@@ -487,6 +856,114 @@ impl Default for ParseOptions {
             skip_magic_bytes: false,
             permissive: false,
             file_len: None,
+            max_width: 16384,
+            max_height: 16384,
+            max_depth: 2048,
+            max_mipmap_count: 32,
+            max_array_size: 2048,
+            max_pixels: 1 << 32,
+            verify_pitch: false,
+        }
+    }
+}
+
+/// A fix applied to an invalid header when reading it with
+/// [`ParseOptions::permissive`].
+///
+/// See [`Header::from_raw_with_fixes`] and [`DdsInfo::fixes`](crate::DdsInfo::fixes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum HeaderFix {
+    /// The header size was not 124 and was ignored.
+    HeaderSize(u32),
+    /// The pixel format size was not 32 and was ignored.
+    PixelFormatSize(u32),
+    /// The pixel format had a FourCC code but not the
+    /// [`PixelFormatFlags::FOURCC`] flag, so the flag was added.
+    FourCCFlag,
+    /// The FourCC code contradicted the bit masks of the pixel format, and
+    /// the bit masks were used since they match the length of the file.
+    ///
+    /// See [`RawPixelFormat::contradicting_masks`].
+    BitMasks,
+    /// The alpha mode of the DX10 header was invalid and was replaced with
+    /// [`AlphaMode::Unknown`].
+    AlphaMode(u32),
+    /// The array size was changed.
+    ///
+    /// This fixes 3D textures with an array size other than 1, an array size
+    /// of 0, and single cube maps with an array size of 6.
+    ArraySize { declared: u32, fixed: u32 },
+    /// The mipmap count was changed to match the length of the file.
+    ///
+    /// `declared` is the mipmap count as interpreted from the header. This is
+    /// 1 if the mipmap count field is 0 or was ignored because neither
+    /// [`DdsFlags::MIPMAP_COUNT`] nor mipmap caps were set.
+    MipmapCount { declared: u32, fixed: u32 },
+    /// The DX9 header describes a single texture (or a cube map without
+    /// faces), but the file contains the six faces of a cube map, so
+    /// [`Caps2::CUBE_MAP`] and [`Caps2::CUBE_MAP_ALL_FACES`] were set.
+    CubeMap { declared: Caps2 },
+    /// [`DdsFlags::DEPTH`] was set with the given depth of 0 or 1 on a 2D
+    /// texture, so the depth was removed.
+    Depth(u32),
+    /// The declared pitch or linear size doesn't match the format and width
+    /// of the header and was ignored.
+    ///
+    /// Padded rows detected by [`DdsInfo::read_with_options`](crate::DdsInfo::read_with_options)
+    /// are not reported as incorrect.
+    PitchOrLinearSize { declared: u32, expected: u32 },
+}
+impl std::fmt::Display for HeaderFix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderFix::HeaderSize(size) => write!(f, "Ignored invalid header size {}", size),
+            HeaderFix::PixelFormatSize(size) => {
+                write!(f, "Ignored invalid pixel format size {}", size)
+            }
+            HeaderFix::FourCCFlag => write!(f, "Added missing FourCC flag to the pixel format"),
+            HeaderFix::BitMasks => write!(f, "Used the bit masks instead of the FourCC code"),
+            HeaderFix::AlphaMode(mode) => {
+                write!(f, "Replaced invalid alpha mode {} with unknown", mode)
+            }
+            HeaderFix::ArraySize { declared, fixed } => {
+                write!(f, "Changed array size from {} to {}", declared, fixed)
+            }
+            HeaderFix::MipmapCount { declared, fixed } => {
+                write!(f, "Changed mipmap count from {} to {}", declared, fixed)
+            }
+            HeaderFix::CubeMap { .. } => write!(f, "Added missing cube map flags"),
+            HeaderFix::Depth(depth) => write!(f, "Removed depth {} of a 2D texture", depth),
+            HeaderFix::PitchOrLinearSize { declared, expected } => write!(
+                f,
+                "Ignored incorrect pitch or linear size {}, expected {}",
+                declared, expected
+            ),
+        }
+    }
+}
+
+/// Options specifying how to write a DDS header.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WriteHeaderOptions {
+    /// Whether the `pitch_or_linear_size` field and the corresponding
+    /// [`DdsFlags::PITCH`] or [`DdsFlags::LINEAR_SIZE`] flag are set.
+    ///
+    /// Uncompressed formats declare the pitch of a row and block-compressed
+    /// formats declare the byte length of the level 0 surface. Some engines
+    /// validate these values strictly, while some very old readers expect
+    /// them to be zero. Set this to `false` to leave the field empty and both
+    /// flags unset.
+    ///
+    /// Defaults to `true`.
+    pub pitch_or_linear_size: bool,
+}
+impl Default for WriteHeaderOptions {
+    fn default() -> Self {
+        Self {
+            pitch_or_linear_size: true,
         }
     }
 }
@@ -577,6 +1054,22 @@ impl Header {
             Self::Dx10(dx10) => dx10.is_cube_map(),
         }
     }
+    /// Returns the cube map faces iff this header describes a cube map.
+    ///
+    /// DX10 headers always describe full cube maps, so this will return
+    /// [`CubeMapFaces::ALL`] for DX10 cube maps.
+    pub fn cube_map_faces(&self) -> Option<CubeMapFaces> {
+        match self {
+            Self::Dx9(dx9) => dx9.cube_map_faces(),
+            Self::Dx10(dx10) => {
+                if dx10.is_cube_map() {
+                    Some(CubeMapFaces::ALL)
+                } else {
+                    None
+                }
+            }
+        }
+    }
     /// Whether this header describes a volume texture.
     pub const fn is_volume(&self) -> bool {
         match self {
@@ -667,6 +1160,57 @@ impl Header {
         }
     }
 
+    /// Creates a new header for an array of 2D textures with the given
+    /// dimensions and format.
+    ///
+    /// Texture arrays can only be represented by DX10 headers. If the format
+    /// isn't supported by DX10, `None` is returned for arrays with more than
+    /// 1 texture. An array with 1 texture is the same as [`Header::new_image`].
+    ///
+    /// The mipmap count is set to 1.
+    pub fn new_texture_array(
+        width: u32,
+        height: u32,
+        array_size: u32,
+        format: Format,
+    ) -> Option<Self> {
+        if let Ok(dxgi) = DxgiFormat::try_from(format) {
+            Some(Self::Dx10(Dx10Header::new_texture_array(
+                width, height, array_size, dxgi,
+            )))
+        } else if array_size == 1 {
+            Some(Self::new_image(width, height, format))
+        } else {
+            None
+        }
+    }
+    /// Creates a new header for an array of cube maps with the given
+    /// dimensions and format. `cube_count` is the number of cube maps, so the
+    /// array contains `6 * cube_count` faces.
+    ///
+    /// Cube map arrays can only be represented by DX10 headers. If the format
+    /// isn't supported by DX10, `None` is returned for arrays with more than
+    /// 1 cube map. An array with 1 cube map is the same as
+    /// [`Header::new_cube_map`].
+    ///
+    /// The mipmap count is set to 1.
+    pub fn new_cube_map_array(
+        width: u32,
+        height: u32,
+        cube_count: u32,
+        format: Format,
+    ) -> Option<Self> {
+        if let Ok(dxgi) = DxgiFormat::try_from(format) {
+            Some(Self::Dx10(Dx10Header::new_cube_map_array(
+                width, height, cube_count, dxgi,
+            )))
+        } else if cube_count == 1 {
+            Some(Self::new_cube_map(width, height, format))
+        } else {
+            None
+        }
+    }
+
     /// A builder-pattern-style method to set the width and height of the
     /// header.
     ///
@@ -747,15 +1291,151 @@ impl Header {
         }
     }
 
-    fn fix_based_on_file_len(&mut self, options: &ParseOptions) -> Option<()> {
-        fn get_expected_data_len(header: &Header, options: &ParseOptions) -> Option<u64> {
-            let non_data = Header::MAGIC.len() + header.byte_len();
-            options.file_len?.checked_sub(non_data as u64)
+    /// Converts this header into a DX10 header with the sRGB variant of its
+    /// DXGI format, e.g. `BC1_UNORM_SRGB` instead of `BC1_UNORM`.
+    ///
+    /// Returns `None` if the format has no sRGB variant or cannot be
+    /// represented by a DX10 header. Headers that are sRGB already are
+    /// returned as is.
+    ///
+    /// Note that this only tags the data as sRGB. Use
+    /// [`EncodeOptions::convert_to_srgb`](crate::EncodeOptions::convert_to_srgb)
+    /// to convert linear images to sRGB when encoding.
+    ///
+    /// ```
+    /// # use dds::{*, header::*};
+    /// let header = Header::new_image(64, 64, Format::BC1_UNORM).to_srgb().unwrap();
+    /// assert!(header.is_srgb());
+    /// assert_eq!(header.dx10().unwrap().dxgi_format, DxgiFormat::BC1_UNORM_SRGB);
+    /// ```
+    pub fn to_srgb(&self) -> Option<Header> {
+        let dx10 = self.to_dx10()?;
+        let srgb = dx10.dxgi_format.to_srgb();
+        if !srgb.is_srgb() {
+            return None;
+        }
+        // the alpha mode is kept as is
+        Some(Header::Dx10(Dx10Header {
+            dxgi_format: srgb,
+            ..dx10
+        }))
+    }
+
+    /// Returns a copy of this header that declares the given alpha mode.
+    ///
+    /// The alpha mode is stored in the `misc_flags2` field of DX10 headers.
+    /// DX9 headers can only declare premultiplied alpha via the `DXT2` and
+    /// `DXT4` FourCCs. DX9 headers are kept if they can represent the alpha
+    /// mode and converted to DX10 headers otherwise.
+    ///
+    /// Returns `None` if the header can't be converted to a DX10 header
+    /// where necessary.
+    ///
+    /// ```
+    /// # use dds::{*, header::*};
+    /// let header = Header::new_image(64, 64, Format::BC3_UNORM)
+    ///     .to_alpha_mode(AlphaMode::Premultiplied)
+    ///     .unwrap();
+    /// assert_eq!(header.alpha_mode(), AlphaMode::Premultiplied);
+    /// ```
+    pub fn to_alpha_mode(&self, alpha_mode: AlphaMode) -> Option<Header> {
+        if self.alpha_mode() == alpha_mode {
+            return Some(self.clone());
         }
 
+        let dx10 = self.to_dx10()?.with_alpha_mode(alpha_mode);
+        if let Header::Dx9(_) = self {
+            // keep the DX9 header if it can represent the alpha mode
+            if let Some(dx9) = dx10.to_dx9() {
+                if dx9.alpha_mode() == alpha_mode {
+                    return Some(Header::Dx9(dx9));
+                }
+            }
+        }
+        Some(Header::Dx10(dx10))
+    }
+
+    /// Converts this header into the representation chosen by the given
+    /// policy.
+    ///
+    /// Returns [`HeaderError::UnsupportedHeaderCompat`] if the header can't be
+    /// represented in the style required by [`HeaderCompat::Dx9Only`] or
+    /// [`HeaderCompat::Dx10Only`]. E.g. texture arrays and formats like
+    /// `BC7_UNORM` require DX10 headers.
+    ///
+    /// ```
+    /// # use dds::{*, header::*};
+    /// let header = Header::new_image(64, 64, Format::BC1_UNORM);
+    /// assert!(header.dx10().is_some());
+    ///
+    /// let header = header.to_compat(HeaderCompat::PreferDx9).unwrap();
+    /// assert!(header.dx9().is_some());
+    ///
+    /// let bc7 = Header::new_image(64, 64, Format::BC7_UNORM);
+    /// assert!(bc7.to_compat(HeaderCompat::Dx9Only).is_err());
+    /// ```
+    pub fn to_compat(&self, compat: HeaderCompat) -> Result<Header, HeaderError> {
+        let dx9 = || self.to_dx9().map(Header::Dx9);
+        let dx10 = || self.to_dx10().map(Header::Dx10);
+        let header = match compat {
+            HeaderCompat::PreferDx9 => dx9().or_else(dx10),
+            HeaderCompat::PreferDx10 => dx10().or_else(dx9),
+            HeaderCompat::Dx9Only => dx9(),
+            HeaderCompat::Dx10Only => dx10(),
+        };
+        // one of the two conversions always returns the header as is
+        header.ok_or(HeaderError::UnsupportedHeaderCompat(compat))
+    }
+
+    /// Whether the data section described by this header exactly fills the
+    /// rest of the file. `false` if [`ParseOptions::file_len`] is `None`.
+    fn matches_file_len(&self, options: &ParseOptions) -> bool {
+        let non_data = (Header::MAGIC.len() + self.byte_len()) as u64;
+        let expected_data_len = options.file_len.and_then(|len| len.checked_sub(non_data));
+        match (expected_data_len, DataLayout::from_header(self)) {
+            (Some(expected), Ok(layout)) => layout.data_len() == expected,
+            _ => false,
+        }
+    }
+
+    /// Returns a copy of this header with invalid values fixed based on the
+    /// length of the file.
+    ///
+    /// This applies the same fixes as [`ParseOptions::permissive`]: array
+    /// sizes of 0, single cube maps with an array size of 6, DX9 cube maps
+    /// with missing cube map flags, 2D textures with a depth, and incorrect
+    /// mipmap counts are fixed such that the data section described by the
+    /// header exactly fills the rest of the file. If no fix results in a
+    /// matching data section, only the array size of 0 is fixed.
+    ///
+    /// `file_len` must include the magic bytes and the header (see
+    /// [`ParseOptions::file_len`]). `pixel_info` is typically
+    /// `PixelInfo::from_header(header)`.
+    ///
+    /// Since [`Self::to_raw`] always computes the pitch or linear size, writing
+    /// the returned header also fixes incorrect pitch fields. See
+    /// [`repair_header`](crate::repair_header) to fix the header of a file in
+    /// place.
+    pub fn sanitized(&self, file_len: u64, pixel_info: PixelInfo) -> Header {
+        let mut header = self.clone();
+        let mipmap_count = self.mipmap_count().get();
+        _ = header.fix_based_on_file_len(file_len, pixel_info, mipmap_count, &mut Vec::new());
+        header
+    }
+
+    /// `declared_mipmap_count` is the value of the mipmap count field of the
+    /// raw header. It may differ from the mipmap count of this header, since
+    /// the field is ignored if neither flags nor caps declare mipmaps.
+    fn fix_based_on_file_len(
+        &mut self,
+        file_len: u64,
+        pixel_info: PixelInfo,
+        declared_mipmap_count: u32,
+        fixes: &mut Vec<HeaderFix>,
+    ) -> Option<()> {
         // Prepare the necessary information
-        let expected_data_len = get_expected_data_len(self, options)?;
-        let pixel_info = PixelInfo::from_header(self).ok()?;
+        let non_data = Header::MAGIC.len() + self.byte_len();
+        let expected_data_len = file_len.checked_sub(non_data as u64)?;
         let test = move |header: &Header| {
             if let Ok(layout) = DataLayout::from_header_with(header, pixel_info) {
                 layout.data_len() == expected_data_len
@@ -764,6 +1444,34 @@ impl Header {
             }
         };
 
+        // Some exporters set DDSD_DEPTH with a depth of 0 or 1 on plain 2D
+        // textures. The depth is meaningless for 2D textures and breaks the
+        // layout of volumes with a depth of 0, so it's removed if the data
+        // matches a 2D texture.
+        if let Some(depth @ (0 | 1)) = self.depth() {
+            if !self.is_volume() || depth == 0 {
+                let mut new_header = self.clone();
+                match &mut new_header {
+                    Header::Dx9(dx9) => {
+                        dx9.depth = None;
+                        dx9.caps2.remove(Caps2::VOLUME);
+                    }
+                    Header::Dx10(dx10) => {
+                        dx10.depth = None;
+                        if dx10.resource_dimension == ResourceDimension::Texture3D {
+                            dx10.resource_dimension = ResourceDimension::Texture2D;
+                        }
+                    }
+                }
+
+                if test(&new_header) {
+                    *self = new_header;
+                    fixes.push(HeaderFix::Depth(depth));
+                    return Some(());
+                }
+            }
+        }
+
         // The common is that the header is already correct
         if test(self) {
             return Some(());
@@ -779,6 +1487,10 @@ impl Header {
         if let Header::Dx10(dx10) = self {
             if expected_data_len > 0 && dx10.array_size == 0 {
                 dx10.array_size = 1;
+                fixes.push(HeaderFix::ArraySize {
+                    declared: 0,
+                    fixed: 1,
+                });
 
                 // update the current layout since we directly changed the header
                 if test(self) {
@@ -802,6 +1514,29 @@ impl Header {
 
                 if test(&new_header) {
                     *self = new_header;
+                    fixes.push(HeaderFix::ArraySize {
+                        declared: 6,
+                        fixed: 1,
+                    });
+                    return Some(());
+                }
+            }
+        }
+
+        // Some exporters write all six faces of a cube map, but forget to set
+        // DDSCAPS2_CUBEMAP or the flags of the individual faces.
+        if let Header::Dx9(dx9) = self {
+            let faces = dx9.caps2 & Caps2::CUBE_MAP_ALL_FACES;
+            if faces.is_empty() && dx9.depth.is_none() && dx9.width == dx9.height {
+                let caps2 = dx9.caps2;
+                let mut new_header = self.clone();
+                if let Header::Dx9(dx9) = &mut new_header {
+                    dx9.caps2 |= Caps2::CUBE_MAP | Caps2::CUBE_MAP_ALL_FACES;
+                }
+
+                if test(&new_header) {
+                    *self = new_header;
+                    fixes.push(HeaderFix::CubeMap { declared: caps2 });
                     return Some(());
                 }
             }
@@ -816,6 +1551,9 @@ impl Header {
                 .max(self.depth().unwrap_or(1)),
         );
         let guesses = [
+            // the mipmap count field is ignored without the MIPMAP_COUNT flag
+            // or mipmap caps, but some writers forget to set them
+            declared_mipmap_count,
             1,                // it's very common for DDS images to have no mipmaps
             max_levels.get(), // or a full mipmap chain
             mipmap - 1,       // otherwise, it could be an off-by-one error
@@ -826,6 +1564,10 @@ impl Header {
 
             if test(&new_header) {
                 *self = new_header;
+                fixes.push(HeaderFix::MipmapCount {
+                    declared: mipmap,
+                    fixed: guess.get(),
+                });
                 return Some(());
             }
         }
@@ -868,6 +1610,19 @@ impl Header {
     }
 
     pub fn from_raw(raw: &RawHeader, options: &ParseOptions) -> Result<Self, HeaderError> {
+        Self::from_raw_with_fixes(raw, options).map(|(header, _)| header)
+    }
+    /// Same as [`Self::from_raw`], but also returns the fixes that were
+    /// applied to the header.
+    ///
+    /// Fixes are only applied if [`ParseOptions::permissive`] is set, so the
+    /// list of fixes is always empty otherwise.
+    pub fn from_raw_with_fixes(
+        raw: &RawHeader,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<HeaderFix>), HeaderError> {
+        let mut fixes = Vec::new();
+
         // verify header size
         if raw.size != RawHeader::SIZE {
             if options.permissive && raw.size == 24 {
@@ -875,6 +1630,7 @@ impl Header {
                 // set to 24 instead of 124. This is likely a typo in the source
                 // code from the DDS encoder they used.
                 // https://github.com/microsoft/DirectXTex/issues/399
+                fixes.push(HeaderFix::HeaderSize(raw.size));
             } else {
                 return Err(HeaderError::InvalidHeaderSize(raw.size));
             }
@@ -899,8 +1655,30 @@ impl Header {
         };
         let mipmap_count = NonZeroU32::new(mipmap_count).unwrap_or(NON_ZERO_U32_ONE);
 
+        // check limits
+        if width > options.max_width {
+            return Err(HeaderError::WidthTooLarge {
+                width,
+                max_width: options.max_width,
+            });
+        }
+        if height > options.max_height {
+            return Err(HeaderError::HeightTooLarge {
+                height,
+                max_height: options.max_height,
+            });
+        }
+        if let Some(depth) = depth {
+            if depth > options.max_depth {
+                return Err(HeaderError::DepthTooLarge {
+                    depth,
+                    max_depth: options.max_depth,
+                });
+            }
+        }
+
         // this always has to be parsed to throw an error if it's invalid
-        let pixel_format = Dx9PixelFormat::from_raw(&raw.pixel_format, options)?;
+        let pixel_format = Dx9PixelFormat::from_raw(&raw.pixel_format, options, &mut fixes)?;
 
         let mut header = if let Some(dx10) = &raw.dx10 {
             let dxgi_format =
@@ -914,6 +1692,7 @@ impl Header {
             let alpha_mode = if let Ok(alpha_mode) = AlphaMode::try_from(raw_alpha_mode) {
                 alpha_mode
             } else if options.permissive {
+                fixes.push(HeaderFix::AlphaMode(raw_alpha_mode));
                 AlphaMode::Unknown
             } else {
                 return Err(HeaderError::InvalidAlphaMode(raw_alpha_mode));
@@ -922,11 +1701,21 @@ impl Header {
             let mut array_size = dx10.array_size;
             if resource_dimension == ResourceDimension::Texture3D && array_size != 1 {
                 if options.permissive {
+                    fixes.push(HeaderFix::ArraySize {
+                        declared: array_size,
+                        fixed: 1,
+                    });
                     array_size = 1;
                 } else {
                     return Err(HeaderError::InvalidArraySizeForTexture3D(array_size));
                 }
             }
+            if array_size > options.max_array_size {
+                return Err(HeaderError::ArraySizeTooLarge {
+                    array_size,
+                    max_array_size: options.max_array_size,
+                });
+            }
 
             // DX10 header
             Header::Dx10(Dx10Header {
@@ -942,32 +1731,152 @@ impl Header {
             })
         } else {
             // DX9 header
-            Header::Dx9(Dx9Header {
+            let contradicting_masks = raw.pixel_format.contradicting_masks();
+            if contradicting_masks.is_some() && !options.permissive {
+                return Err(HeaderError::AmbiguousPixelFormat(raw.pixel_format.four_cc));
+            }
+
+            let header = Header::Dx9(Dx9Header {
                 height,
                 width,
                 depth,
                 mipmap_count,
                 caps2: raw.caps2,
                 pixel_format,
-            })
+            });
+
+            // The FourCC code takes precedence, unless only the masks match
+            // the length of the file.
+            match contradicting_masks {
+                Some(masks) if !header.matches_file_len(options) => {
+                    let mut mask_header = header.clone();
+                    if let Header::Dx9(dx9) = &mut mask_header {
+                        dx9.pixel_format = Dx9PixelFormat::Mask(masks);
+                    }
+                    if mask_header.matches_file_len(options) {
+                        fixes.push(HeaderFix::BitMasks);
+                        mask_header
+                    } else {
+                        header
+                    }
+                }
+                _ => header,
+            }
         };
 
         if options.permissive {
-            _ = header.fix_based_on_file_len(options);
+            if let (Some(file_len), Ok(pixel_info)) =
+                (options.file_len, PixelInfo::from_header(&header))
+            {
+                _ = header.fix_based_on_file_len(
+                    file_len,
+                    pixel_info,
+                    raw.mipmap_count,
+                    &mut fixes,
+                );
+            }
         }
 
-        Ok(header)
+        if options.permissive || options.verify_pitch {
+            if let Some((flag, expected)) = header.pitch_or_linear_size() {
+                let declared = raw.pitch_or_linear_size;
+                if raw.flags.contains(flag) && declared != expected {
+                    if options.permissive {
+                        fixes.push(HeaderFix::PitchOrLinearSize { declared, expected });
+                    } else {
+                        return Err(HeaderError::IncorrectPitchOrLinearSize { declared, expected });
+                    }
+                }
+            }
+        }
+
+        // checked after fixing, since permissive parsing can fix invalid
+        // mipmap counts. Counts the layout can't represent at all are left to
+        // `LayoutError::TooManyMipMaps`.
+        let mipmap_count = header.mipmap_count().get();
+        if mipmap_count <= u8::MAX as u32 && mipmap_count > options.max_mipmap_count {
+            return Err(HeaderError::MipmapCountTooLarge {
+                mipmap_count,
+                max_mipmap_count: options.max_mipmap_count,
+            });
+        }
+
+        // checked after fixing, since permissive parsing can fix the array
+        // size and cube map faces
+        let faces = header
+            .cube_map_faces()
+            .map_or(1, |faces| faces.bits().count_ones());
+        let pixels = (header.width() as u64 * header.height() as u64)
+            .saturating_mul(header.depth().unwrap_or(1) as u64)
+            .saturating_mul(header.array_size().max(1) as u64)
+            .saturating_mul(faces as u64);
+        if pixels > options.max_pixels {
+            return Err(HeaderError::TooManyPixels {
+                pixels,
+                max_pixels: options.max_pixels,
+            });
+        }
+
+        Ok((header, fixes))
     }
 
     /// Writes the header including magic bytes.
     pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.write_with(writer, &WriteHeaderOptions::default())
+    }
+
+    /// Writes the header including magic bytes with the given options.
+    pub fn write_with<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteHeaderOptions,
+    ) -> std::io::Result<()> {
         writer.write_all(&Self::MAGIC)?;
 
-        let raw = self.to_raw();
+        let raw = self.to_raw_with(options);
         raw.write(writer)
     }
 
+    /// Returns the pitch or linear size of this header along with the flag
+    /// declaring it.
+    ///
+    /// Uncompressed formats declare the pitch of a row of the level 0
+    /// surface with [`DdsFlags::PITCH`]. For bi-planar formats, this is the
+    /// pitch of the luma plane. Block-compressed formats declare the number
+    /// of bytes of the level 0 surface with [`DdsFlags::LINEAR_SIZE`].
+    ///
+    /// Returns `None` if the pixel format is unknown or the value doesn't fit
+    /// into a `u32`.
+    pub(crate) fn pitch_or_linear_size(&self) -> Option<(DdsFlags, u32)> {
+        match PixelInfo::from_header(self).ok()? {
+            PixelInfo::Fixed { bytes_per_pixel } => {
+                let pitch = self.width().checked_mul(bytes_per_pixel as u32)?;
+                Some((DdsFlags::PITCH, pitch))
+            }
+            // channel-packed sub-sampled formats like YUY2 are uncompressed
+            PixelInfo::Block(block) if block.size().1 == 1 => {
+                let blocks_x = div_ceil(self.width(), block.size().0 as u32);
+                let pitch = blocks_x.checked_mul(block.bytes_per_block() as u32)?;
+                Some((DdsFlags::PITCH, pitch))
+            }
+            PixelInfo::Block(block) => {
+                let linear_size = PixelInfo::Block(block).surface_bytes(self.size())?;
+                Some((DdsFlags::LINEAR_SIZE, linear_size.try_into().ok()?))
+            }
+            PixelInfo::BiPlanar(bi_planar) => {
+                let pitch = self
+                    .width()
+                    .checked_mul(bi_planar.plane1_bytes_per_pixel() as u32)?;
+                Some((DdsFlags::PITCH, pitch))
+            }
+        }
+    }
+
     pub fn to_raw(&self) -> RawHeader {
+        self.to_raw_with(&WriteHeaderOptions::default())
+    }
+    /// Converts this header into a raw header with the given options.
+    pub fn to_raw_with(&self, options: &WriteHeaderOptions) -> RawHeader {
         let mut flags = DdsFlags::REQUIRED | DdsFlags::MIPMAP_COUNT;
         let mut caps = Caps::REQUIRED;
 
@@ -978,24 +1887,11 @@ impl Header {
             flags |= DdsFlags::DEPTH;
         }
 
-        // We can only calculate the pitch or linear size if we know the byte
-        // size and layout of the pixel data.
         let mut pitch_or_linear_size = 0;
-        if let Ok(pixel_info) = PixelInfo::from_header(self) {
-            if let PixelInfo::Fixed { bytes_per_pixel } = pixel_info {
-                let pitch = self.width().checked_mul(bytes_per_pixel as u32);
-                if let Some(pitch) = pitch {
-                    pitch_or_linear_size = pitch;
-                    flags |= DdsFlags::PITCH;
-                }
-            } else {
-                let linear_size: Option<u32> = pixel_info
-                    .surface_bytes(self.size())
-                    .and_then(|size| size.try_into().ok());
-                if let Some(linear_size) = linear_size {
-                    pitch_or_linear_size = linear_size;
-                    flags |= DdsFlags::LINEAR_SIZE;
-                }
+        if options.pitch_or_linear_size {
+            if let Some((flag, value)) = self.pitch_or_linear_size() {
+                pitch_or_linear_size = value;
+                flags |= flag;
             }
         }
 
@@ -1052,17 +1948,23 @@ impl Header {
 }
 
 impl Dx9PixelFormat {
-    fn from_raw(raw: &RawPixelFormat, options: &ParseOptions) -> Result<Self, HeaderError> {
+    fn from_raw(
+        raw: &RawPixelFormat,
+        options: &ParseOptions,
+        fixes: &mut Vec<HeaderFix>,
+    ) -> Result<Self, HeaderError> {
         let size = raw.size;
         if size != RawPixelFormat::SIZE {
             if options.permissive && size == 0 {
                 // Some DDS files have their pixel format size set to 0.
                 // https://github.com/microsoft/DirectXTex/issues/392
+                fixes.push(HeaderFix::PixelFormatSize(size));
             } else if options.permissive && size == 24 {
                 // Some DDS files from the game Flat Out 2 have their pixel
                 // format size set to 24 instead of 32. This is likely a bug in
                 // the program that created the DDS files.
                 // https://github.com/microsoft/DirectXTex/issues/392
+                fixes.push(HeaderFix::PixelFormatSize(size));
             } else {
                 return Err(HeaderError::InvalidPixelFormatSize(size));
             }
@@ -1084,6 +1986,7 @@ impl Dx9PixelFormat {
             // missing flag.
             // https://github.com/microsoft/DirectXTex/pull/371
             flags |= PixelFormatFlags::FOURCC;
+            fixes.push(HeaderFix::FourCCFlag);
         }
 
         let format = if flags.contains(PixelFormatFlags::FOURCC) {
@@ -1240,10 +2143,8 @@ impl Dx9Header {
                     DxgiFormat::BC2_UNORM
                 } else if *four_cc == FourCC::DXT4 {
                     DxgiFormat::BC3_UNORM
-                } else if let Some(dxgi) = four_cc_to_dxgi(*four_cc) {
-                    dxgi
                 } else {
-                    return None;
+                    four_cc_to_dxgi(*four_cc)?
                 }
             }
             Dx9PixelFormat::Mask(mask_pixel_format) => masked_to_dxgi(mask_pixel_format)?,
@@ -1352,6 +2253,35 @@ impl Dx10Header {
         }
     }
 
+    /// Creates a new header for a DX10 array of 2D textures with the given
+    /// dimensions and format.
+    ///
+    /// The mipmap count is set to 1 and the alpha mode is set to unknown.
+    pub const fn new_texture_array(
+        width: u32,
+        height: u32,
+        array_size: u32,
+        format: DxgiFormat,
+    ) -> Self {
+        let mut header = Self::new_image(width, height, format);
+        header.array_size = array_size;
+        header
+    }
+    /// Creates a new header for a DX10 array of cube maps with the given
+    /// dimensions and format. `cube_count` is the number of cube maps.
+    ///
+    /// The mipmap count is set to 1 and the alpha mode is set to unknown.
+    pub const fn new_cube_map_array(
+        width: u32,
+        height: u32,
+        cube_count: u32,
+        format: DxgiFormat,
+    ) -> Self {
+        let mut header = Self::new_cube_map(width, height, format);
+        header.array_size = cube_count;
+        header
+    }
+
     /// A builder-pattern-style method to set the width and height of the
     /// header.
     ///
@@ -1575,6 +2505,7 @@ bitflags! {
 ///
 /// <https://learn.microsoft.com/en-us/windows/win32/direct3ddds/dds-header-dxt10>
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AlphaMode {
     /// Alpha channel content is unknown. This is the value for legacy files, which typically is assumed to be 'straight' alpha.
     Unknown = 0,
@@ -1666,6 +2597,19 @@ impl FourCC {
     pub const YUY2: Self = FourCC(u32::from_le_bytes(*b"YUY2"));
     pub const UYVY: Self = FourCC(u32::from_le_bytes(*b"UYVY"));
 }
+#[cfg(feature = "serde")]
+impl serde::Serialize for Caps2 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FourCC {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.0)
+    }
+}
 
 impl From<u32> for FourCC {
     fn from(value: u32) -> Self {
@@ -1678,20 +2622,40 @@ impl From<FourCC> for u32 {
     }
 }
 
-impl std::fmt::Debug for FourCC {
+/// Formats the 4 characters of the code (e.g. `DXT1`) or its hexadecimal
+/// value if it contains characters that are not ASCII letters or digits.
+impl std::fmt::Display for FourCC {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let bytes = self.0.to_le_bytes();
         if bytes.iter().all(|&b| b.is_ascii_alphanumeric()) {
-            write!(
-                f,
-                "FourCC({}{}{}{})",
-                bytes[0] as char, bytes[1] as char, bytes[2] as char, bytes[3] as char
-            )
+            bytes.iter().try_for_each(|&b| write!(f, "{}", b as char))
         } else {
-            write!(f, "FourCC(0x{:x})", self.0)
+            write!(f, "0x{:x}", self.0)
         }
     }
 }
+/// Parses a code of exactly 4 ASCII characters (e.g. `DXT1`).
+///
+/// Unlike format names, FourCC codes are case-sensitive.
+impl std::str::FromStr for FourCC {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|bytes: &[u8; 4]| bytes.is_ascii())
+            .ok_or_else(|| ParseFormatError::new(s))?;
+        Ok(FourCC(u32::from_le_bytes(bytes)))
+    }
+}
+
+impl std::fmt::Debug for FourCC {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FourCC({})", self)
+    }
+}
 
 /// Resource data formats, including fully-typed and typeless formats. A list
 /// of modifiers at the bottom of the page more fully describes each format
@@ -1854,15 +2818,7 @@ impl DxgiFormat {
         )
     }
 
-    #[allow(unused)]
-    pub(crate) fn all() -> impl Iterator<Item = DxgiFormat> {
-        (0..192).filter_map(|i| DxgiFormat::try_from(i).ok())
-    }
-}
-impl TryFrom<u32> for DxgiFormat {
-    type Error = u32;
-
-    fn try_from(value: u32) -> Result<Self, Self::Error> {
+    pub(crate) const fn from_u32(value: u32) -> Option<Self> {
         // NOTE: This implementation is NOT generated by the marco for
         // performance and code size reasons. On virtually any optimization
         // level, the below code translates to around 6 instructions, while a
@@ -1886,16 +2842,72 @@ impl TryFrom<u32> for DxgiFormat {
             | 177..=179
             | 181..=183
             | 185..=187
-            | 191 => Ok(DxgiFormat(value as u8)),
-            _ => Err(value),
+            | 191 => Some(DxgiFormat(value as u8)),
+            _ => None,
         }
     }
+
+    pub(crate) fn all() -> impl Iterator<Item = DxgiFormat> {
+        (0..192).filter_map(|i| DxgiFormat::try_from(i).ok())
+    }
+}
+impl TryFrom<u32> for DxgiFormat {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        DxgiFormat::from_u32(value).ok_or(value)
+    }
 }
 impl From<DxgiFormat> for u32 {
     fn from(value: DxgiFormat) -> Self {
         value.0 as u32
     }
 }
+/// Formats the name of the format (e.g. `BC1_UNORM`) or its numeric value
+/// if the format has no name.
+impl std::fmt::Display for DxgiFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+/// Parses the name of a format (e.g. `BC1_UNORM`) or its numeric value.
+///
+/// Names are case-insensitive and may start with `DXGI_FORMAT_`.
+impl std::str::FromStr for DxgiFormat {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(value) = s.parse::<u32>() {
+            return DxgiFormat::try_from(value).map_err(|_| ParseFormatError::new(s));
+        }
+
+        let name = strip_prefix_ignore_case(s, "DXGI_FORMAT_").unwrap_or(s);
+        DxgiFormat::all()
+            .find(|format| {
+                format
+                    .name()
+                    .map_or(false, |n| n.eq_ignore_ascii_case(name))
+            })
+            .ok_or_else(|| ParseFormatError::new(s))
+    }
+}
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    if head.eq_ignore_ascii_case(prefix) {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+#[cfg(feature = "serde")]
+impl serde::Serialize for DxgiFormat {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(u32::from(*self))
+    }
+}
 
 macro_rules! define_dxgi_formats {
     ($($name:ident = $n:literal),+) => {
@@ -1903,15 +2915,25 @@ macro_rules! define_dxgi_formats {
             $(pub const $name: DxgiFormat = DxgiFormat($n);)+
         }
 
+        impl DxgiFormat {
+            /// The name of the format without the `DXGI_FORMAT_` prefix, e.g.
+            /// `BC1_UNORM`.
+            ///
+            /// `None` if the format has no name.
+            pub const fn name(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$name => Some(stringify!($name)),)+
+                    _ => None,
+                }
+            }
+        }
+
         impl std::fmt::Debug for DxgiFormat {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                let name = match *self {
-                    $(Self::$name => stringify!($name),)+
-                    _ => {
-                        return write!(f, "DxgiFormat({})", self.0);
-                    }
-                };
-                write!(f, "{} ({})", name, self.0)
+                match self.name() {
+                    Some(name) => write!(f, "{} ({})", name, self.0),
+                    None => write!(f, "DxgiFormat({})", self.0),
+                }
             }
         }
     };
@@ -0,0 +1,263 @@
+//! Integration with the [`image`] crate.
+//!
+//! [`DdsDecoder`] implements [`image::ImageDecoder`], so DDS files can be
+//! loaded with e.g. [`image::DynamicImage::from_decoder`]. [`encode_image`]
+//! encodes a [`image::DynamicImage`] directly.
+
+use std::io::{Read, Seek, Write};
+
+use image::error::{DecodingError, ImageFormatHint, LimitError, LimitErrorKind};
+use image::{
+    ColorType, DynamicImage, ExtendedColorType, ImageBuffer, ImageDecoder, ImageError, ImageFormat,
+    ImageResult,
+};
+
+use crate::{
+    encode, Channels, ColorFormat, DecodeError, Decoder, EncodeError, EncodeOptions, Format,
+    ImageView, ImageViewMut, Precision, Size,
+};
+
+/// An [`image::ImageDecoder`] for DDS files.
+///
+/// This decodes the first surface of the DDS file, i.e. mipmap level 0 of
+/// the first texture in texture arrays, the positive X face of cube maps,
+/// and the first depth slice of volumes.
+///
+/// The output color type is chosen to match the native color of the format
+/// (see [`Decoder::native_color`]) as closely as possible while being
+/// supported by PNG. Formats with float precision are decoded as 16-bit
+/// integers, which clamps values to `[0, 1]`, and alpha-only formats are
+/// decoded as RGBA.
+///
+/// ```no_run
+/// # use dds::*;
+/// let file = std::fs::File::open("example.dds").unwrap();
+/// let decoder = DdsDecoder::new(file).unwrap();
+/// let image = image::DynamicImage::from_decoder(decoder).unwrap();
+/// image.save("example.png").unwrap();
+/// ```
+pub struct DdsDecoder<R> {
+    decoder: Decoder<R>,
+    color: ColorFormat,
+}
+impl<R: Read> DdsDecoder<R> {
+    /// Creates a new decoder by reading the header of the given DDS file.
+    pub fn new(reader: R) -> Result<Self, DecodeError> {
+        Ok(Self::from_decoder(Decoder::new(reader)?))
+    }
+}
+impl<R> DdsDecoder<R> {
+    /// Creates a new decoder from an existing decoder.
+    ///
+    /// The options of the decoder (see [`Decoder::options`]) are used for
+    /// decoding. The decoder must be positioned at its first surface.
+    pub fn from_decoder(decoder: Decoder<R>) -> Self {
+        let native = decoder.native_color();
+        let channels = match native.channels {
+            Channels::Alpha => Channels::Rgba,
+            channels => channels,
+        };
+        let precision = match native.precision {
+            Precision::F32 | Precision::U32 => Precision::U16,
+            precision => precision,
+        };
+
+        Self {
+            decoder,
+            color: ColorFormat::new(channels, precision),
+        }
+    }
+
+    /// The color format the image is decoded as.
+    pub fn color(&self) -> ColorFormat {
+        self.color
+    }
+
+    pub fn into_inner(self) -> Decoder<R> {
+        self.decoder
+    }
+}
+
+impl<R: Read + Seek> DdsDecoder<R> {
+    /// Decodes the mipmap best suited for a thumbnail of the given size.
+    ///
+    /// This is the smallest mipmap whose width and height are both at least
+    /// the width and height of `max_size` (see [`Decoder::decode_thumbnail`]).
+    /// The returned image has the size of that mipmap and [`Self::color`], so
+    /// it typically has to be resized to the final thumbnail size.
+    ///
+    /// ```no_run
+    /// # use dds::*;
+    /// let file = std::fs::File::open("example.dds").unwrap();
+    /// let mut decoder = DdsDecoder::new(file).unwrap();
+    /// let thumbnail = decoder.decode_thumbnail(Size::new(256, 256)).unwrap();
+    /// let thumbnail = thumbnail.thumbnail(256, 256);
+    /// ```
+    pub fn decode_thumbnail(&mut self, max_size: Size) -> ImageResult<DynamicImage> {
+        let level = self.decoder.thumbnail_level(max_size);
+        let size = self.decoder.main_size().get_mipmap(level);
+        let len = self
+            .color
+            .buffer_size(size)
+            .ok_or(ImageError::Limits(LimitError::from_kind(
+                LimitErrorKind::InsufficientMemory,
+            )))?;
+
+        let mut buf = vec![0_u8; len];
+        let image = ImageViewMut::new(buf.as_mut_slice(), size, self.color)
+            .expect("buffer must have the length of the surface");
+        self.decoder
+            .decode_first_surface(level, image)
+            .map_err(to_image_error)?;
+
+        let (width, height) = (size.width, size.height);
+        let image = match to_color_type(self.color) {
+            ColorType::L8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8)
+            }
+            ColorType::Rgb8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8)
+            }
+            ColorType::Rgba8 => {
+                ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8)
+            }
+            color_type => {
+                let buf: Vec<u16> = buf
+                    .chunks_exact(2)
+                    .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+                    .collect();
+                match color_type {
+                    ColorType::L16 => {
+                        ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma16)
+                    }
+                    ColorType::Rgb16 => {
+                        ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb16)
+                    }
+                    _ => ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba16),
+                }
+            }
+        };
+        Ok(image.expect("decoded surfaces always have the correct length"))
+    }
+}
+
+impl<R: Read> ImageDecoder for DdsDecoder<R> {
+    fn dimensions(&self) -> (u32, u32) {
+        let size = self.decoder.main_size();
+        (size.width, size.height)
+    }
+
+    fn color_type(&self) -> ColorType {
+        to_color_type(self.color)
+    }
+
+    fn original_color_type(&self) -> ExtendedColorType {
+        let native = self.decoder.native_color();
+        if native == ColorFormat::ALPHA_U8 {
+            ExtendedColorType::A8
+        } else {
+            to_color_type(native).into()
+        }
+    }
+
+    fn set_limits(&mut self, limits: image::Limits) -> ImageResult<()> {
+        limits.check_support(&image::LimitSupport::default())?;
+        let (width, height) = self.dimensions();
+        limits.check_dimensions(width, height)?;
+
+        if let Some(max_alloc) = limits.max_alloc {
+            let max_alloc = usize::try_from(max_alloc).unwrap_or(usize::MAX);
+            let options = &mut self.decoder.options;
+            options.memory_limit = options.memory_limit.min(max_alloc);
+        }
+        Ok(())
+    }
+
+    fn read_image(mut self, buf: &mut [u8]) -> ImageResult<()> {
+        let size = self.decoder.main_size();
+        let image = ImageViewMut::new(buf, size, self.color)
+            .expect("buffer must have the length of `total_bytes`");
+        self.decoder.read_surface(image).map_err(to_image_error)
+    }
+
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+fn to_color_type(color: ColorFormat) -> ColorType {
+    match (color.channels, color.precision) {
+        (Channels::Grayscale, Precision::U8) => ColorType::L8,
+        (Channels::Grayscale, Precision::U16) => ColorType::L16,
+        (Channels::Grayscale, Precision::F32 | Precision::U32) => ColorType::Rgb32F,
+        (Channels::Rgb, Precision::U8) => ColorType::Rgb8,
+        (Channels::Rgb, Precision::U16) => ColorType::Rgb16,
+        (Channels::Rgb, Precision::F32 | Precision::U32) => ColorType::Rgb32F,
+        (Channels::Alpha | Channels::Rgba, Precision::U8) => ColorType::Rgba8,
+        (Channels::Alpha | Channels::Rgba, Precision::U16) => ColorType::Rgba16,
+        (Channels::Alpha | Channels::Rgba, Precision::F32 | Precision::U32) => ColorType::Rgba32F,
+    }
+}
+
+fn to_image_error(error: DecodeError) -> ImageError {
+    match error {
+        DecodeError::Io(error) => ImageError::IoError(error),
+        DecodeError::MemoryLimitExceeded => {
+            ImageError::Limits(LimitError::from_kind(LimitErrorKind::InsufficientMemory))
+        }
+        error => ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Dds),
+            error,
+        )),
+    }
+}
+
+/// Encodes the given image in the given format and writes the encoded
+/// surface to the writer.
+///
+/// This is the same as [`encode`], but the channels and precision of the
+/// image are mapped automatically. 8-bit, 16-bit, and f32 images are
+/// encoded without conversion. Images with luma and alpha channels are
+/// converted to RGBA first, since there is no equivalent color format.
+///
+/// ```
+/// # use dds::*;
+/// let image = image::DynamicImage::new_rgb16(8, 8);
+/// let mut encoded = Vec::new();
+/// encode_image(&image, Format::BC1_UNORM, &EncodeOptions::default(), &mut encoded).unwrap();
+/// assert_eq!(encoded.len(), 4 * 8);
+/// ```
+pub fn encode_image(
+    image: &DynamicImage,
+    format: Format,
+    options: &EncodeOptions,
+    writer: &mut dyn Write,
+) -> Result<(), EncodeError> {
+    let color = match image {
+        DynamicImage::ImageLuma8(_) => ColorFormat::GRAYSCALE_U8,
+        DynamicImage::ImageRgb8(_) => ColorFormat::RGB_U8,
+        DynamicImage::ImageRgba8(_) => ColorFormat::RGBA_U8,
+        DynamicImage::ImageLuma16(_) => ColorFormat::GRAYSCALE_U16,
+        DynamicImage::ImageRgb16(_) => ColorFormat::RGB_U16,
+        DynamicImage::ImageRgba16(_) => ColorFormat::RGBA_U16,
+        DynamicImage::ImageRgb32F(_) => ColorFormat::RGB_F32,
+        DynamicImage::ImageRgba32F(_) => ColorFormat::RGBA_F32,
+        DynamicImage::ImageLumaA8(_) => {
+            let converted = DynamicImage::ImageRgba8(image.to_rgba8());
+            return encode_image(&converted, format, options, writer);
+        }
+        DynamicImage::ImageLumaA16(_) => {
+            let converted = DynamicImage::ImageRgba16(image.to_rgba16());
+            return encode_image(&converted, format, options, writer);
+        }
+        _ => {
+            let converted = DynamicImage::ImageRgba32F(image.to_rgba32f());
+            return encode_image(&converted, format, options, writer);
+        }
+    };
+
+    let size = Size::new(image.width(), image.height());
+    let view = ImageView::new(image.as_bytes(), size, color)
+        .expect("image buffers always have the correct length");
+    encode(writer, view, format, options)
+}
@@ -1,4 +1,6 @@
-use crate::{DataLayout, DataRegion, Size, SurfaceDescriptor, Texture, Volume};
+use crate::{
+    DataLayout, DataRegion, MipOrder, Size, SurfaceDescriptor, SurfaceLocation, Texture, Volume,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct SurfaceInfo<'a> {
@@ -27,6 +29,14 @@ impl SurfaceInfo<'_> {
         self.len
     }
 
+    /// The mipmap level of the surface.
+    ///
+    /// For volumes, this is the mipmap level of the volume the depth slice
+    /// belongs to.
+    pub fn mipmap_level(&self) -> u8 {
+        self.mipmap_level
+    }
+
     /// Whether this surface is has a mipmapping level greater than 0.
     ///
     /// For textures and texture arrays, this means that the texture is not
@@ -37,26 +47,35 @@ impl SurfaceInfo<'_> {
     }
 }
 
+#[derive(Clone)]
 pub(crate) enum SurfaceIterator {
     Texture(TextureSurfaceIterator),
     Volume(VolumeSurfaceIterator),
 }
 impl SurfaceIterator {
     pub fn new(layout: DataLayout) -> Self {
+        Self::new_with_order(layout, MipOrder::LargestFirst)
+    }
+    pub fn new_with_order(layout: DataLayout, order: MipOrder) -> Self {
+        let reversed = order == MipOrder::SmallestFirst;
         match layout {
             DataLayout::Texture(texture) => {
-                SurfaceIterator::Texture(TextureSurfaceIterator::new(texture, 1))
+                SurfaceIterator::Texture(TextureSurfaceIterator::new(texture, 1, reversed))
             }
             DataLayout::Volume(volume) => {
-                SurfaceIterator::Volume(VolumeSurfaceIterator::new(volume))
+                SurfaceIterator::Volume(VolumeSurfaceIterator::new(volume, reversed))
+            }
+            DataLayout::TextureArray(texture_array) => {
+                SurfaceIterator::Texture(TextureSurfaceIterator::new(
+                    texture_array.first(),
+                    texture_array.len() as u32,
+                    reversed,
+                ))
             }
-            DataLayout::TextureArray(texture_array) => SurfaceIterator::Texture(
-                TextureSurfaceIterator::new(texture_array.first(), texture_array.len() as u32),
-            ),
         }
     }
 
-    pub fn current(&self) -> Option<SurfaceInfo> {
+    pub fn current(&self) -> Option<SurfaceInfo<'_>> {
         match self {
             Self::Texture(iter) => iter.current(),
             Self::Volume(iter) => iter.current(),
@@ -70,31 +89,95 @@ impl SurfaceIterator {
         }
     }
 
+    /// The offset of the current surface in the data section of the file.
+    ///
+    /// If there are no more surfaces, this is the length of the data section.
+    pub fn current_offset(&self) -> u64 {
+        match self {
+            Self::Texture(iter) => iter.current_offset(),
+            Self::Volume(iter) => iter.current_offset(),
+        }
+    }
+
+    /// The location of the current surface.
+    pub(crate) fn current_location(&self) -> Option<SurfaceLocation> {
+        let current = self.current()?;
+        let (element, depth_slice) = match self {
+            Self::Texture(iter) => (iter.current_index, 0),
+            Self::Volume(iter) => (0, iter.current_depth),
+        };
+        Some(SurfaceLocation {
+            element,
+            mipmap_level: current.mipmap_level(),
+            depth_slice,
+            offset: self.current_offset(),
+        })
+    }
+
     pub fn skip_mipmaps(&mut self) -> Result<u64, ()> {
         match self {
             Self::Texture(iter) => Ok(iter.skip_mipmaps()),
             Self::Volume(iter) => iter.skip_mipmaps(),
         }
     }
+
+    /// Whether the current surface is the first depth slice of a mipmap
+    /// level of a volume.
+    pub fn is_at_volume_level_start(&self) -> bool {
+        match self {
+            Self::Texture(_) => false,
+            Self::Volume(iter) => {
+                iter.current_level < iter.volume.mipmaps() && iter.current_depth == 0
+            }
+        }
+    }
+
+    /// Whether the iterator is at the start of a texture/volume or at the end.
+    pub fn is_at_element_start(&self) -> bool {
+        match self {
+            Self::Texture(iter) => {
+                iter.current_index >= iter.len || iter.current_level == iter.first_level()
+            }
+            Self::Volume(iter) => {
+                iter.current_level >= iter.volume.mipmaps()
+                    || (iter.current_level == iter.first_level() && iter.current_depth == 0)
+            }
+        }
+    }
 }
 
+#[derive(Clone)]
 pub(crate) struct TextureSurfaceIterator {
     first: Texture,
     len: u32,
+    /// Whether mipmaps are iterated from smallest to largest.
+    reversed: bool,
     current_index: u32,
     current_level: u8,
 }
 impl TextureSurfaceIterator {
-    fn new(first: Texture, len: u32) -> Self {
-        Self {
+    fn new(first: Texture, len: u32, reversed: bool) -> Self {
+        let mut iter = Self {
             first,
             len,
+            reversed,
             current_index: 0,
             current_level: 0,
+        };
+        iter.current_level = iter.first_level();
+        iter
+    }
+
+    /// The mipmap level each texture starts with.
+    fn first_level(&self) -> u8 {
+        if self.reversed {
+            self.first.mipmaps() - 1
+        } else {
+            0
         }
     }
 
-    fn current(&self) -> Option<SurfaceInfo> {
+    fn current(&self) -> Option<SurfaceInfo<'_>> {
         if self.current_index < self.len {
             let desc = self.first.get(self.current_level);
             debug_assert!(desc.is_some());
@@ -106,6 +189,16 @@ impl TextureSurfaceIterator {
 
     fn advance(&mut self) {
         if self.current_index < self.len {
+            if self.reversed {
+                if self.current_level > 0 {
+                    self.current_level -= 1;
+                } else {
+                    self.current_index += 1;
+                    self.current_level = self.first_level();
+                }
+                return;
+            }
+
             // this can never overflow, because we ensure that
             // `current_level < first.mipmaps()`
             let next_level = self.current_level + 1;
@@ -118,9 +211,41 @@ impl TextureSurfaceIterator {
         }
     }
 
+    fn current_offset(&self) -> u64 {
+        let texture_len = self.first.data_len();
+        if self.current_index >= self.len {
+            return texture_len * self.len as u64;
+        }
+
+        let texture_start = texture_len * self.current_index as u64;
+        // PANIC SAFETY: the current level is always a valid level
+        let surface = self.first.get(self.current_level).unwrap();
+        texture_start + self.order().file_offset(&self.first, &surface)
+    }
+    fn order(&self) -> MipOrder {
+        if self.reversed {
+            MipOrder::SmallestFirst
+        } else {
+            MipOrder::LargestFirst
+        }
+    }
+
     fn skip_mipmaps(&mut self) -> u64 {
         if self.current_index < self.len && self.current_level != 0 {
             let mut skipped_bytes = 0;
+
+            if self.reversed {
+                // skip all remaining mipmaps up to the level 0 surface of the
+                // current texture
+                for surface in self.first.iter_mips().take(self.current_level as usize + 1) {
+                    skipped_bytes += surface.data_len();
+                }
+                skipped_bytes -= self.first.main().data_len();
+
+                self.current_level = 0;
+                return skipped_bytes;
+            }
+
             for surface in self.first.iter_mips().skip(self.current_level as usize) {
                 skipped_bytes += surface.data_len();
             }
@@ -135,21 +260,36 @@ impl TextureSurfaceIterator {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct VolumeSurfaceIterator {
     volume: Volume,
+    /// Whether mipmaps are iterated from smallest to largest.
+    reversed: bool,
     current_level: u8,
     current_depth: u32,
 }
 impl VolumeSurfaceIterator {
-    fn new(volume: Volume) -> Self {
-        Self {
+    fn new(volume: Volume, reversed: bool) -> Self {
+        let mut iter = Self {
             volume,
+            reversed,
             current_level: 0,
             current_depth: 0,
+        };
+        iter.current_level = iter.first_level();
+        iter
+    }
+
+    /// The mipmap level the volume starts with.
+    fn first_level(&self) -> u8 {
+        if self.reversed {
+            self.volume.mipmaps() - 1
+        } else {
+            0
         }
     }
 
-    fn current(&self) -> Option<SurfaceInfo> {
+    fn current(&self) -> Option<SurfaceInfo<'_>> {
         let v = self.volume.get(self.current_level)?;
         debug_assert!(self.current_depth < v.depth());
         let desc = v.get_depth_slice(self.current_depth);
@@ -162,6 +302,13 @@ impl VolumeSurfaceIterator {
             let next_depth = self.current_depth + 1;
             if next_depth < v.depth() {
                 self.current_depth = next_depth;
+            } else if self.reversed {
+                // after level 0, the volume is done
+                self.current_level = match self.current_level {
+                    0 => self.volume.mipmaps(),
+                    level => level - 1,
+                };
+                self.current_depth = 0;
             } else {
                 self.current_level += 1;
                 self.current_depth = 0;
@@ -169,6 +316,22 @@ impl VolumeSurfaceIterator {
         }
     }
 
+    fn current_offset(&self) -> u64 {
+        if let Some(level) = self.volume.get(self.current_level) {
+            let slice_len = level.data_len() / level.depth() as u64;
+            self.order().file_offset(&self.volume, &level) + self.current_depth as u64 * slice_len
+        } else {
+            self.volume.data_end()
+        }
+    }
+    fn order(&self) -> MipOrder {
+        if self.reversed {
+            MipOrder::SmallestFirst
+        } else {
+            MipOrder::LargestFirst
+        }
+    }
+
     fn skip_mipmaps(&mut self) -> Result<u64, ()> {
         // we cannot skip anything within a volume
         if self.current_depth != 0 {
@@ -181,6 +344,22 @@ impl VolumeSurfaceIterator {
         }
 
         let mut skipped_bytes = 0;
+
+        if self.reversed {
+            // skip all remaining mipmaps up to the level 0 volume
+            for surface in self
+                .volume
+                .iter_mips()
+                .take(self.current_level as usize + 1)
+            {
+                skipped_bytes += surface.data_len();
+            }
+            skipped_bytes -= self.volume.main().data_len();
+
+            self.current_level = 0;
+            return Ok(skipped_bytes);
+        }
+
         for surface in self.volume.iter_mips().skip(self.current_level as usize) {
             skipped_bytes += surface.data_len();
         }
@@ -0,0 +1,698 @@
+use crate::Format;
+
+/// A Vulkan format as stored in the `vkFormat` field of a KTX2 header.
+///
+/// Only the formats supported by [`Ktx2Format`] have associated constants.
+/// All other values can still be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VkFormat(pub u32);
+
+impl VkFormat {
+    pub const UNDEFINED: Self = VkFormat(0);
+
+    pub const R4G4B4A4_UNORM_PACK16: Self = VkFormat(2);
+    pub const R5G6B5_UNORM_PACK16: Self = VkFormat(4);
+    pub const A1R5G5B5_UNORM_PACK16: Self = VkFormat(8);
+    pub const R8_UNORM: Self = VkFormat(9);
+    pub const R8_SNORM: Self = VkFormat(10);
+    pub const R8_SRGB: Self = VkFormat(15);
+    pub const R8G8_UNORM: Self = VkFormat(16);
+    pub const R8G8_SNORM: Self = VkFormat(17);
+    pub const R8G8_SRGB: Self = VkFormat(22);
+    pub const R8G8B8_UNORM: Self = VkFormat(23);
+    pub const R8G8B8_SRGB: Self = VkFormat(29);
+    pub const B8G8R8_UNORM: Self = VkFormat(30);
+    pub const B8G8R8_SRGB: Self = VkFormat(36);
+    pub const R8G8B8A8_UNORM: Self = VkFormat(37);
+    pub const R8G8B8A8_SNORM: Self = VkFormat(38);
+    pub const R8G8B8A8_SRGB: Self = VkFormat(43);
+    pub const B8G8R8A8_UNORM: Self = VkFormat(44);
+    pub const B8G8R8A8_SRGB: Self = VkFormat(50);
+    pub const A2B10G10R10_UNORM_PACK32: Self = VkFormat(64);
+    pub const R16_UNORM: Self = VkFormat(70);
+    pub const R16_SNORM: Self = VkFormat(71);
+    pub const R16_SFLOAT: Self = VkFormat(76);
+    pub const R16G16_UNORM: Self = VkFormat(77);
+    pub const R16G16_SNORM: Self = VkFormat(78);
+    pub const R16G16_SFLOAT: Self = VkFormat(83);
+    pub const R16G16B16A16_UNORM: Self = VkFormat(91);
+    pub const R16G16B16A16_SNORM: Self = VkFormat(92);
+    pub const R16G16B16A16_SFLOAT: Self = VkFormat(97);
+    pub const R32_SFLOAT: Self = VkFormat(100);
+    pub const R32G32_SFLOAT: Self = VkFormat(103);
+    pub const R32G32B32_SFLOAT: Self = VkFormat(106);
+    pub const R32G32B32A32_SFLOAT: Self = VkFormat(109);
+    pub const B10G11R11_UFLOAT_PACK32: Self = VkFormat(122);
+
+    pub const BC1_RGBA_UNORM_BLOCK: Self = VkFormat(133);
+    pub const BC1_RGBA_SRGB_BLOCK: Self = VkFormat(134);
+    pub const BC2_UNORM_BLOCK: Self = VkFormat(135);
+    pub const BC2_SRGB_BLOCK: Self = VkFormat(136);
+    pub const BC3_UNORM_BLOCK: Self = VkFormat(137);
+    pub const BC3_SRGB_BLOCK: Self = VkFormat(138);
+    pub const BC4_UNORM_BLOCK: Self = VkFormat(139);
+    pub const BC4_SNORM_BLOCK: Self = VkFormat(140);
+    pub const BC5_UNORM_BLOCK: Self = VkFormat(141);
+    pub const BC5_SNORM_BLOCK: Self = VkFormat(142);
+    pub const BC6H_UFLOAT_BLOCK: Self = VkFormat(143);
+    pub const BC6H_SFLOAT_BLOCK: Self = VkFormat(144);
+    pub const BC7_UNORM_BLOCK: Self = VkFormat(145);
+    pub const BC7_SRGB_BLOCK: Self = VkFormat(146);
+
+    pub const ASTC_4X4_UNORM_BLOCK: Self = VkFormat(157);
+    pub const ASTC_4X4_SRGB_BLOCK: Self = VkFormat(158);
+    pub const ASTC_5X4_UNORM_BLOCK: Self = VkFormat(159);
+    pub const ASTC_5X4_SRGB_BLOCK: Self = VkFormat(160);
+    pub const ASTC_5X5_UNORM_BLOCK: Self = VkFormat(161);
+    pub const ASTC_5X5_SRGB_BLOCK: Self = VkFormat(162);
+    pub const ASTC_6X5_UNORM_BLOCK: Self = VkFormat(163);
+    pub const ASTC_6X5_SRGB_BLOCK: Self = VkFormat(164);
+    pub const ASTC_6X6_UNORM_BLOCK: Self = VkFormat(165);
+    pub const ASTC_6X6_SRGB_BLOCK: Self = VkFormat(166);
+    pub const ASTC_8X5_UNORM_BLOCK: Self = VkFormat(167);
+    pub const ASTC_8X5_SRGB_BLOCK: Self = VkFormat(168);
+    pub const ASTC_8X6_UNORM_BLOCK: Self = VkFormat(169);
+    pub const ASTC_8X6_SRGB_BLOCK: Self = VkFormat(170);
+    pub const ASTC_8X8_UNORM_BLOCK: Self = VkFormat(171);
+    pub const ASTC_8X8_SRGB_BLOCK: Self = VkFormat(172);
+    pub const ASTC_10X5_UNORM_BLOCK: Self = VkFormat(173);
+    pub const ASTC_10X5_SRGB_BLOCK: Self = VkFormat(174);
+    pub const ASTC_10X6_UNORM_BLOCK: Self = VkFormat(175);
+    pub const ASTC_10X6_SRGB_BLOCK: Self = VkFormat(176);
+    pub const ASTC_10X8_UNORM_BLOCK: Self = VkFormat(177);
+    pub const ASTC_10X8_SRGB_BLOCK: Self = VkFormat(178);
+    pub const ASTC_10X10_UNORM_BLOCK: Self = VkFormat(179);
+    pub const ASTC_10X10_SRGB_BLOCK: Self = VkFormat(180);
+    pub const ASTC_12X10_UNORM_BLOCK: Self = VkFormat(181);
+    pub const ASTC_12X10_SRGB_BLOCK: Self = VkFormat(182);
+    pub const ASTC_12X12_UNORM_BLOCK: Self = VkFormat(183);
+    pub const ASTC_12X12_SRGB_BLOCK: Self = VkFormat(184);
+
+    pub const A4R4G4B4_UNORM_PACK16: Self = VkFormat(1000340000);
+}
+impl From<u32> for VkFormat {
+    fn from(value: u32) -> Self {
+        VkFormat(value)
+    }
+}
+impl From<VkFormat> for u32 {
+    fn from(value: VkFormat) -> Self {
+        value.0
+    }
+}
+
+/// A format that can be stored in a KTX2 file.
+///
+/// This combines a [`Format`] with the color space (linear or sRGB) of its
+/// data, since KTX2 stores the color space as part of the format.
+///
+/// Formats with premultiplied alpha (e.g.
+/// [`Format::BC3_UNORM_PREMULTIPLIED_ALPHA`]) are supported by setting the
+/// premultiplied alpha flag in the data format descriptor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ktx2Format {
+    format: Format,
+    srgb: bool,
+}
+impl Ktx2Format {
+    /// Returns the KTX2 format for the given format and color space.
+    ///
+    /// Returns `None` if the format cannot be stored in KTX2 files or if
+    /// `srgb` is `true` and the format has no sRGB variant.
+    pub fn new(format: Format, srgb: bool) -> Option<Self> {
+        let info = FormatInfo::of(format)?;
+        if srgb && info.vk_srgb.is_none() {
+            return None;
+        }
+        Some(Self { format, srgb })
+    }
+    /// Returns the KTX2 format of the given `VkFormat`.
+    ///
+    /// Returns `None` if the `VkFormat` isn't supported. Formats with
+    /// premultiplied alpha can't be detected this way, since they share
+    /// their `VkFormat` with the straight alpha variant.
+    pub fn from_vk_format(vk_format: VkFormat) -> Option<Self> {
+        ALL_SUPPORTED.iter().find_map(|&format| {
+            let info = FormatInfo::of(format)?;
+            if info.vk == vk_format.0 {
+                Some(Self {
+                    format,
+                    srgb: false,
+                })
+            } else if info.vk_srgb == Some(vk_format.0) {
+                Some(Self { format, srgb: true })
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the premultiplied alpha variant of this format, if any.
+    pub(crate) fn to_premultiplied_alpha(self) -> Self {
+        let format = match self.format {
+            Format::BC2_UNORM => Format::BC2_UNORM_PREMULTIPLIED_ALPHA,
+            Format::BC3_UNORM => Format::BC3_UNORM_PREMULTIPLIED_ALPHA,
+            format => format,
+        };
+        Self { format, ..self }
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+    pub fn is_srgb(&self) -> bool {
+        self.srgb
+    }
+    /// Whether the data has premultiplied alpha.
+    pub fn is_premultiplied_alpha(&self) -> bool {
+        matches!(
+            self.format,
+            Format::BC2_UNORM_PREMULTIPLIED_ALPHA | Format::BC3_UNORM_PREMULTIPLIED_ALPHA
+        )
+    }
+
+    pub fn vk_format(&self) -> VkFormat {
+        let info = self.info();
+        match info.vk_srgb {
+            Some(srgb) if self.srgb => VkFormat(srgb),
+            _ => VkFormat(info.vk),
+        }
+    }
+    /// The value of the `typeSize` field of the KTX2 header.
+    ///
+    /// This is 1 for block-compressed formats and the size of the data type
+    /// (e.g. 2 for 16-bit channels or `PACK16` formats) otherwise.
+    pub fn type_size(&self) -> u32 {
+        match self.info().layout {
+            Layout::Uncompressed { type_size, .. } => type_size as u32,
+            Layout::Compressed { .. } => 1,
+        }
+    }
+
+    /// Creates the data format descriptor of the format.
+    ///
+    /// The returned bytes contain a single basic descriptor block and start
+    /// with the total size of the descriptor (`dfdTotalSize`), exactly as
+    /// stored in KTX2 files.
+    pub fn data_format_descriptor(&self) -> Vec<u8> {
+        let info = self.info();
+
+        let mut samples: Vec<[u32; 4]> = Vec::new();
+        let (model, block_size, bytes_per_block) = match info.layout {
+            Layout::Uncompressed {
+                bytes,
+                numeric,
+                channels,
+                ..
+            } => {
+                for &(channel, offset, bits) in channels {
+                    let mut qualifiers = match numeric {
+                        Numeric::Unorm => 0,
+                        Numeric::Snorm => SAMPLE_SIGNED,
+                        Numeric::UFloat => SAMPLE_FLOAT,
+                        Numeric::SFloat => SAMPLE_FLOAT | SAMPLE_SIGNED,
+                    };
+                    if self.srgb && channel == CHANNEL_ALPHA {
+                        qualifiers |= SAMPLE_LINEAR;
+                    }
+                    let (lower, upper) = match numeric {
+                        Numeric::Unorm => (0, ((1_u64 << bits) - 1) as u32),
+                        Numeric::Snorm => {
+                            let max = (1_i32 << (bits - 1)) - 1;
+                            (-max as u32, max as u32)
+                        }
+                        Numeric::UFloat => (0, 1.0_f32.to_bits()),
+                        Numeric::SFloat => ((-1.0_f32).to_bits(), 1.0_f32.to_bits()),
+                    };
+                    samples.push(sample(channel | qualifiers, offset, bits, lower, upper));
+                }
+                (MODEL_RGBSDA, (1, 1), bytes)
+            }
+            Layout::Compressed {
+                model,
+                block_size,
+                bytes,
+                numeric,
+                channels,
+            } => {
+                // all samples have the same size
+                let bits = bytes as u16 * 8 / channels.len() as u16;
+                for (i, &channel) in channels.iter().enumerate() {
+                    let (qualifiers, lower, upper) = match numeric {
+                        Numeric::Unorm => (0, 0, u32::MAX),
+                        Numeric::Snorm => (SAMPLE_SIGNED, i32::MIN as u32, i32::MAX as u32),
+                        Numeric::UFloat => (SAMPLE_FLOAT, 0, 1.0_f32.to_bits()),
+                        Numeric::SFloat => (
+                            SAMPLE_FLOAT | SAMPLE_SIGNED,
+                            (-1.0_f32).to_bits(),
+                            1.0_f32.to_bits(),
+                        ),
+                    };
+                    let offset = i as u16 * bits;
+                    samples.push(sample(channel | qualifiers, offset, bits, lower, upper));
+                }
+                (model, block_size, bytes)
+            }
+        };
+
+        let block_len = 24 + 16 * samples.len() as u32;
+        let transfer = if self.srgb {
+            TRANSFER_SRGB
+        } else {
+            TRANSFER_LINEAR
+        };
+        let flags = if self.is_premultiplied_alpha() {
+            FLAG_ALPHA_PREMULTIPLIED
+        } else {
+            0
+        };
+
+        let mut words: Vec<u32> = vec![
+            4 + block_len,
+            // vendor ID 0 (Khronos) and descriptor type 0 (basic)
+            0,
+            // version 1.3 and descriptor block size
+            2 | block_len << 16,
+            model as u32 | PRIMARIES_BT709 << 8 | transfer << 16 | flags << 24,
+            (block_size.0 as u32 - 1) | (block_size.1 as u32 - 1) << 8,
+            bytes_per_block as u32,
+            0,
+        ];
+        for sample in samples {
+            words.extend_from_slice(&sample);
+        }
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    fn info(&self) -> FormatInfo {
+        // PANIC SAFETY: the constructors ensure that the format is supported
+        FormatInfo::of(self.format).unwrap()
+    }
+}
+
+const MODEL_RGBSDA: u8 = 1;
+const MODEL_BC1A: u8 = 128;
+const MODEL_BC2: u8 = 129;
+const MODEL_BC3: u8 = 130;
+const MODEL_BC4: u8 = 131;
+const MODEL_BC5: u8 = 132;
+const MODEL_BC6H: u8 = 133;
+const MODEL_BC7: u8 = 134;
+const MODEL_ASTC: u8 = 162;
+
+const PRIMARIES_BT709: u32 = 1;
+const TRANSFER_LINEAR: u32 = 1;
+const TRANSFER_SRGB: u32 = 2;
+const FLAG_ALPHA_PREMULTIPLIED: u32 = 1;
+
+const CHANNEL_R: u8 = 0;
+const CHANNEL_G: u8 = 1;
+const CHANNEL_B: u8 = 2;
+const CHANNEL_ALPHA: u8 = 15;
+/// The color channel of BC1A, BC2, BC3, BC6H, and BC7.
+const CHANNEL_COLOR: u8 = 0;
+/// The channel of BC1A that signals that the block may be transparent.
+const CHANNEL_BC1A_ALPHA_PRESENT: u8 = 1;
+
+const SAMPLE_LINEAR: u8 = 0x10;
+const SAMPLE_SIGNED: u8 = 0x40;
+const SAMPLE_FLOAT: u8 = 0x80;
+
+fn sample(channel_type: u8, bit_offset: u16, bits: u16, lower: u32, upper: u32) -> [u32; 4] {
+    let first = bit_offset as u32 | ((bits - 1) as u32) << 16 | (channel_type as u32) << 24;
+    [first, 0, lower, upper]
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Numeric {
+    Unorm,
+    Snorm,
+    UFloat,
+    SFloat,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Layout {
+    Uncompressed {
+        bytes: u8,
+        type_size: u8,
+        numeric: Numeric,
+        /// `(channel, bit offset, bits)` in ascending bit offset.
+        channels: &'static [(u8, u16, u16)],
+    },
+    Compressed {
+        model: u8,
+        block_size: (u8, u8),
+        bytes: u8,
+        numeric: Numeric,
+        /// The channels of all samples in the order they are stored.
+        channels: &'static [u8],
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FormatInfo {
+    vk: u32,
+    vk_srgb: Option<u32>,
+    layout: Layout,
+}
+impl FormatInfo {
+    fn of(format: Format) -> Option<Self> {
+        use Format as F;
+        use Numeric::*;
+
+        const R: u8 = CHANNEL_R;
+        const G: u8 = CHANNEL_G;
+        const B: u8 = CHANNEL_B;
+        const A: u8 = CHANNEL_ALPHA;
+
+        let info = |vk: VkFormat, vk_srgb: Option<VkFormat>, layout| FormatInfo {
+            vk: vk.0,
+            vk_srgb: vk_srgb.map(|f| f.0),
+            layout,
+        };
+        let uncompressed =
+            |vk, vk_srgb, type_size, numeric, channels: &'static [(u8, u16, u16)]| {
+                let bits: u16 = channels.iter().map(|c| c.2).sum();
+                info(
+                    vk,
+                    vk_srgb,
+                    Layout::Uncompressed {
+                        bytes: (bits / 8) as u8,
+                        type_size,
+                        numeric,
+                        channels,
+                    },
+                )
+            };
+        let compressed = |vk, vk_srgb, model, bytes, numeric, channels| {
+            info(
+                vk,
+                vk_srgb,
+                Layout::Compressed {
+                    model,
+                    block_size: (4, 4),
+                    bytes,
+                    numeric,
+                    channels,
+                },
+            )
+        };
+        let astc = |vk: VkFormat, block_size| {
+            info(
+                vk,
+                Some(VkFormat(vk.0 + 1)),
+                Layout::Compressed {
+                    model: MODEL_ASTC,
+                    block_size,
+                    bytes: 16,
+                    numeric: Unorm,
+                    channels: &[0],
+                },
+            )
+        };
+        type V = VkFormat;
+
+        Some(match format {
+            // uncompressed formats
+            F::R8G8B8_UNORM => uncompressed(
+                V::R8G8B8_UNORM,
+                Some(V::R8G8B8_SRGB),
+                1,
+                Unorm,
+                &[(R, 0, 8), (G, 8, 8), (B, 16, 8)],
+            ),
+            F::B8G8R8_UNORM => uncompressed(
+                V::B8G8R8_UNORM,
+                Some(V::B8G8R8_SRGB),
+                1,
+                Unorm,
+                &[(B, 0, 8), (G, 8, 8), (R, 16, 8)],
+            ),
+            F::R8G8B8A8_UNORM => uncompressed(
+                V::R8G8B8A8_UNORM,
+                Some(V::R8G8B8A8_SRGB),
+                1,
+                Unorm,
+                &[(R, 0, 8), (G, 8, 8), (B, 16, 8), (A, 24, 8)],
+            ),
+            F::R8G8B8A8_SNORM => uncompressed(
+                V::R8G8B8A8_SNORM,
+                None,
+                1,
+                Snorm,
+                &[(R, 0, 8), (G, 8, 8), (B, 16, 8), (A, 24, 8)],
+            ),
+            F::B8G8R8A8_UNORM => uncompressed(
+                V::B8G8R8A8_UNORM,
+                Some(V::B8G8R8A8_SRGB),
+                1,
+                Unorm,
+                &[(B, 0, 8), (G, 8, 8), (R, 16, 8), (A, 24, 8)],
+            ),
+            F::B5G6R5_UNORM => uncompressed(
+                V::R5G6B5_UNORM_PACK16,
+                None,
+                2,
+                Unorm,
+                &[(B, 0, 5), (G, 5, 6), (R, 11, 5)],
+            ),
+            F::B5G5R5A1_UNORM => uncompressed(
+                V::A1R5G5B5_UNORM_PACK16,
+                None,
+                2,
+                Unorm,
+                &[(B, 0, 5), (G, 5, 5), (R, 10, 5), (A, 15, 1)],
+            ),
+            F::B4G4R4A4_UNORM => uncompressed(
+                V::A4R4G4B4_UNORM_PACK16,
+                None,
+                2,
+                Unorm,
+                &[(B, 0, 4), (G, 4, 4), (R, 8, 4), (A, 12, 4)],
+            ),
+            F::A4B4G4R4_UNORM => uncompressed(
+                V::R4G4B4A4_UNORM_PACK16,
+                None,
+                2,
+                Unorm,
+                &[(A, 0, 4), (B, 4, 4), (G, 8, 4), (R, 12, 4)],
+            ),
+            F::R8_UNORM => uncompressed(V::R8_UNORM, Some(V::R8_SRGB), 1, Unorm, &[(R, 0, 8)]),
+            F::R8_SNORM => uncompressed(V::R8_SNORM, None, 1, Snorm, &[(R, 0, 8)]),
+            F::R8G8_UNORM => uncompressed(
+                V::R8G8_UNORM,
+                Some(V::R8G8_SRGB),
+                1,
+                Unorm,
+                &[(R, 0, 8), (G, 8, 8)],
+            ),
+            F::R8G8_SNORM => uncompressed(V::R8G8_SNORM, None, 1, Snorm, &[(R, 0, 8), (G, 8, 8)]),
+            F::R16_UNORM => uncompressed(V::R16_UNORM, None, 2, Unorm, &[(R, 0, 16)]),
+            F::R16_SNORM => uncompressed(V::R16_SNORM, None, 2, Snorm, &[(R, 0, 16)]),
+            F::R16_FLOAT => uncompressed(V::R16_SFLOAT, None, 2, SFloat, &[(R, 0, 16)]),
+            F::R16G16_UNORM => {
+                uncompressed(V::R16G16_UNORM, None, 2, Unorm, &[(R, 0, 16), (G, 16, 16)])
+            }
+            F::R16G16_SNORM => {
+                uncompressed(V::R16G16_SNORM, None, 2, Snorm, &[(R, 0, 16), (G, 16, 16)])
+            }
+            F::R16G16_FLOAT => uncompressed(
+                V::R16G16_SFLOAT,
+                None,
+                2,
+                SFloat,
+                &[(R, 0, 16), (G, 16, 16)],
+            ),
+            F::R16G16B16A16_UNORM => uncompressed(
+                V::R16G16B16A16_UNORM,
+                None,
+                2,
+                Unorm,
+                &[(R, 0, 16), (G, 16, 16), (B, 32, 16), (A, 48, 16)],
+            ),
+            F::R16G16B16A16_SNORM => uncompressed(
+                V::R16G16B16A16_SNORM,
+                None,
+                2,
+                Snorm,
+                &[(R, 0, 16), (G, 16, 16), (B, 32, 16), (A, 48, 16)],
+            ),
+            F::R16G16B16A16_FLOAT => uncompressed(
+                V::R16G16B16A16_SFLOAT,
+                None,
+                2,
+                SFloat,
+                &[(R, 0, 16), (G, 16, 16), (B, 32, 16), (A, 48, 16)],
+            ),
+            F::R10G10B10A2_UNORM => uncompressed(
+                V::A2B10G10R10_UNORM_PACK32,
+                None,
+                4,
+                Unorm,
+                &[(R, 0, 10), (G, 10, 10), (B, 20, 10), (A, 30, 2)],
+            ),
+            F::R11G11B10_FLOAT => uncompressed(
+                V::B10G11R11_UFLOAT_PACK32,
+                None,
+                4,
+                UFloat,
+                &[(R, 0, 11), (G, 11, 11), (B, 22, 10)],
+            ),
+            F::R32_FLOAT => uncompressed(V::R32_SFLOAT, None, 4, SFloat, &[(R, 0, 32)]),
+            F::R32G32_FLOAT => uncompressed(
+                V::R32G32_SFLOAT,
+                None,
+                4,
+                SFloat,
+                &[(R, 0, 32), (G, 32, 32)],
+            ),
+            F::R32G32B32_FLOAT => uncompressed(
+                V::R32G32B32_SFLOAT,
+                None,
+                4,
+                SFloat,
+                &[(R, 0, 32), (G, 32, 32), (B, 64, 32)],
+            ),
+            F::R32G32B32A32_FLOAT => uncompressed(
+                V::R32G32B32A32_SFLOAT,
+                None,
+                4,
+                SFloat,
+                &[(R, 0, 32), (G, 32, 32), (B, 64, 32), (A, 96, 32)],
+            ),
+
+            // block compression formats
+            F::BC1_UNORM => compressed(
+                V::BC1_RGBA_UNORM_BLOCK,
+                Some(V::BC1_RGBA_SRGB_BLOCK),
+                MODEL_BC1A,
+                8,
+                Unorm,
+                &[CHANNEL_BC1A_ALPHA_PRESENT],
+            ),
+            F::BC2_UNORM | F::BC2_UNORM_PREMULTIPLIED_ALPHA => compressed(
+                V::BC2_UNORM_BLOCK,
+                Some(V::BC2_SRGB_BLOCK),
+                MODEL_BC2,
+                16,
+                Unorm,
+                &[A, CHANNEL_COLOR],
+            ),
+            F::BC3_UNORM | F::BC3_UNORM_PREMULTIPLIED_ALPHA => compressed(
+                V::BC3_UNORM_BLOCK,
+                Some(V::BC3_SRGB_BLOCK),
+                MODEL_BC3,
+                16,
+                Unorm,
+                &[A, CHANNEL_COLOR],
+            ),
+            F::BC4_UNORM => compressed(V::BC4_UNORM_BLOCK, None, MODEL_BC4, 8, Unorm, &[R]),
+            F::BC4_SNORM => compressed(V::BC4_SNORM_BLOCK, None, MODEL_BC4, 8, Snorm, &[R]),
+            F::BC5_UNORM => compressed(V::BC5_UNORM_BLOCK, None, MODEL_BC5, 16, Unorm, &[R, G]),
+            F::BC5_SNORM => compressed(V::BC5_SNORM_BLOCK, None, MODEL_BC5, 16, Snorm, &[R, G]),
+            F::BC6H_UF16 => compressed(
+                V::BC6H_UFLOAT_BLOCK,
+                None,
+                MODEL_BC6H,
+                16,
+                UFloat,
+                &[CHANNEL_COLOR],
+            ),
+            F::BC6H_SF16 => compressed(
+                V::BC6H_SFLOAT_BLOCK,
+                None,
+                MODEL_BC6H,
+                16,
+                SFloat,
+                &[CHANNEL_COLOR],
+            ),
+            F::BC7_UNORM => compressed(
+                V::BC7_UNORM_BLOCK,
+                Some(V::BC7_SRGB_BLOCK),
+                MODEL_BC7,
+                16,
+                Unorm,
+                &[CHANNEL_COLOR],
+            ),
+
+            // ASTC
+            F::ASTC_4X4_UNORM => astc(V::ASTC_4X4_UNORM_BLOCK, (4, 4)),
+            F::ASTC_5X4_UNORM => astc(V::ASTC_5X4_UNORM_BLOCK, (5, 4)),
+            F::ASTC_5X5_UNORM => astc(V::ASTC_5X5_UNORM_BLOCK, (5, 5)),
+            F::ASTC_6X5_UNORM => astc(V::ASTC_6X5_UNORM_BLOCK, (6, 5)),
+            F::ASTC_6X6_UNORM => astc(V::ASTC_6X6_UNORM_BLOCK, (6, 6)),
+            F::ASTC_8X5_UNORM => astc(V::ASTC_8X5_UNORM_BLOCK, (8, 5)),
+            F::ASTC_8X6_UNORM => astc(V::ASTC_8X6_UNORM_BLOCK, (8, 6)),
+            F::ASTC_8X8_UNORM => astc(V::ASTC_8X8_UNORM_BLOCK, (8, 8)),
+            F::ASTC_10X5_UNORM => astc(V::ASTC_10X5_UNORM_BLOCK, (10, 5)),
+            F::ASTC_10X6_UNORM => astc(V::ASTC_10X6_UNORM_BLOCK, (10, 6)),
+            F::ASTC_10X8_UNORM => astc(V::ASTC_10X8_UNORM_BLOCK, (10, 8)),
+            F::ASTC_10X10_UNORM => astc(V::ASTC_10X10_UNORM_BLOCK, (10, 10)),
+            F::ASTC_12X10_UNORM => astc(V::ASTC_12X10_UNORM_BLOCK, (12, 10)),
+            F::ASTC_12X12_UNORM => astc(V::ASTC_12X12_UNORM_BLOCK, (12, 12)),
+
+            _ => return None,
+        })
+    }
+}
+
+/// All formats that [`FormatInfo::of`] may support. Formats with premultiplied
+/// alpha are listed after their straight alpha variant, so they are never
+/// returned by [`Ktx2Format::from_vk_format`].
+const ALL_SUPPORTED: &[Format] = &[
+    Format::R8G8B8_UNORM,
+    Format::B8G8R8_UNORM,
+    Format::R8G8B8A8_UNORM,
+    Format::R8G8B8A8_SNORM,
+    Format::B8G8R8A8_UNORM,
+    Format::B5G6R5_UNORM,
+    Format::B5G5R5A1_UNORM,
+    Format::B4G4R4A4_UNORM,
+    Format::A4B4G4R4_UNORM,
+    Format::R8_UNORM,
+    Format::R8_SNORM,
+    Format::R8G8_UNORM,
+    Format::R8G8_SNORM,
+    Format::R16_UNORM,
+    Format::R16_SNORM,
+    Format::R16_FLOAT,
+    Format::R16G16_UNORM,
+    Format::R16G16_SNORM,
+    Format::R16G16_FLOAT,
+    Format::R16G16B16A16_UNORM,
+    Format::R16G16B16A16_SNORM,
+    Format::R16G16B16A16_FLOAT,
+    Format::R10G10B10A2_UNORM,
+    Format::R11G11B10_FLOAT,
+    Format::R32_FLOAT,
+    Format::R32G32_FLOAT,
+    Format::R32G32B32_FLOAT,
+    Format::R32G32B32A32_FLOAT,
+    Format::BC1_UNORM,
+    Format::BC2_UNORM,
+    Format::BC2_UNORM_PREMULTIPLIED_ALPHA,
+    Format::BC3_UNORM,
+    Format::BC3_UNORM_PREMULTIPLIED_ALPHA,
+    Format::BC4_UNORM,
+    Format::BC4_SNORM,
+    Format::BC5_UNORM,
+    Format::BC5_SNORM,
+    Format::BC6H_UF16,
+    Format::BC6H_SF16,
+    Format::BC7_UNORM,
+    Format::ASTC_4X4_UNORM,
+    Format::ASTC_5X4_UNORM,
+    Format::ASTC_5X5_UNORM,
+    Format::ASTC_6X5_UNORM,
+    Format::ASTC_6X6_UNORM,
+    Format::ASTC_8X5_UNORM,
+    Format::ASTC_8X6_UNORM,
+    Format::ASTC_8X8_UNORM,
+    Format::ASTC_10X5_UNORM,
+    Format::ASTC_10X6_UNORM,
+    Format::ASTC_10X8_UNORM,
+    Format::ASTC_10X10_UNORM,
+    Format::ASTC_12X10_UNORM,
+    Format::ASTC_12X12_UNORM,
+];
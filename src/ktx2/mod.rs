@@ -0,0 +1,31 @@
+//! Support for [KTX2](https://registry.khronos.org/KTX/specs/2.0/ktxspec.v2.html)
+//! files.
+//!
+//! KTX2 files can store the same uncompressed and block-compressed formats
+//! as DDS files. This module provides the format descriptors needed to store
+//! DDS data in KTX2 files, a [`Ktx2Decoder`] that decodes KTX2 files
+//! using the decoders of this crate, and [`dds_to_ktx2`] to convert DDS
+//! files to KTX2 without decoding them.
+//!
+//! KTX2 describes the format of its data with a `VkFormat` and a
+//! [Khronos Data Format Descriptor](https://registry.khronos.org/DataFormat/specs/1.3/dataformat.1.3.html)
+//! (DFD). [`Ktx2Format`] provides both for all formats that DDS and KTX2
+//! share, i.e. uncompressed formats, BCn, and ASTC.
+//!
+//! ```
+//! # use dds::{ktx2::*, Format};
+//! let format = Ktx2Format::new(Format::BC7_UNORM, true).unwrap();
+//! assert_eq!(format.vk_format(), VkFormat::BC7_SRGB_BLOCK);
+//! assert_eq!(format.type_size(), 1);
+//!
+//! let dfd = format.data_format_descriptor();
+//! assert_eq!(dfd.len(), 44);
+//! ```
+
+mod format;
+mod reader;
+mod writer;
+
+pub use format::*;
+pub use reader::*;
+pub use writer::*;
@@ -0,0 +1,278 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{decode, DecodeError, DecodeOptions, ImageViewMut, Ktx2Error, PixelInfo, Size};
+
+use super::{Ktx2Format, VkFormat};
+
+/// The location of a mipmap level in a KTX2 file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ktx2Level {
+    /// The byte offset of the level from the start of the file.
+    pub byte_offset: u64,
+    /// The number of bytes of the level in the file.
+    pub byte_length: u64,
+    /// The number of bytes of the level after inflating supercompression.
+    pub uncompressed_byte_length: u64,
+}
+
+/// The header, index, and level index of a KTX2 file.
+///
+/// This is a direct representation of the data in the file. Fields are not
+/// validated or normalized, so e.g. `pixel_depth` is 0 for 2D textures.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Ktx2Header {
+    pub vk_format: VkFormat,
+    pub type_size: u32,
+    pub pixel_width: u32,
+    pub pixel_height: u32,
+    pub pixel_depth: u32,
+    pub layer_count: u32,
+    pub face_count: u32,
+    pub level_count: u32,
+    pub supercompression_scheme: u32,
+
+    pub dfd_byte_offset: u32,
+    pub dfd_byte_length: u32,
+    pub kvd_byte_offset: u32,
+    pub kvd_byte_length: u32,
+    pub sgd_byte_offset: u64,
+    pub sgd_byte_length: u64,
+
+    /// The location of each mipmap level, starting with the largest (level 0).
+    ///
+    /// This has `max(1, level_count)` entries.
+    pub levels: Vec<Ktx2Level>,
+}
+impl Ktx2Header {
+    /// The 12 bytes every KTX2 file starts with.
+    pub const IDENTIFIER: [u8; 12] = [
+        0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+    ];
+
+    /// Reads the identifier, header, index, and level index of a KTX2 file.
+    ///
+    /// The reader must be at the start of the file. After this, the reader
+    /// will be at the start of the data format descriptor.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, Ktx2Error> {
+        let mut identifier = [0; 12];
+        reader.read_exact(&mut identifier)?;
+        if identifier != Self::IDENTIFIER {
+            return Err(Ktx2Error::InvalidIdentifier(identifier));
+        }
+
+        let mut header = [0_u32; 13];
+        for value in header.iter_mut() {
+            *value = read_u32(reader)?;
+        }
+        let [vk_format, type_size, pixel_width, pixel_height, pixel_depth, layer_count, face_count, level_count, supercompression_scheme, dfd_byte_offset, dfd_byte_length, kvd_byte_offset, kvd_byte_length] =
+            header;
+        let sgd_byte_offset = read_u64(reader)?;
+        let sgd_byte_length = read_u64(reader)?;
+
+        let mut levels = Vec::new();
+        for _ in 0..level_count.max(1) {
+            levels.push(Ktx2Level {
+                byte_offset: read_u64(reader)?,
+                byte_length: read_u64(reader)?,
+                uncompressed_byte_length: read_u64(reader)?,
+            });
+        }
+
+        Ok(Self {
+            vk_format: VkFormat(vk_format),
+            type_size,
+            pixel_width,
+            pixel_height,
+            pixel_depth,
+            layer_count,
+            face_count,
+            level_count,
+            supercompression_scheme,
+            dfd_byte_offset,
+            dfd_byte_length,
+            kvd_byte_offset,
+            kvd_byte_length,
+            sgd_byte_offset,
+            sgd_byte_length,
+            levels,
+        })
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> std::io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+fn read_u64<R: Read>(reader: &mut R) -> std::io::Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// A decoder for KTX2 files.
+///
+/// This supports KTX2 files without supercompression whose format is
+/// supported by [`Ktx2Format`]. Surfaces are decoded with the same decoders
+/// as DDS files and can be decoded in any order.
+///
+/// ```no_run
+/// # use dds::{ktx2::*, *};
+/// let file = std::fs::File::open("texture.ktx2").unwrap();
+/// let mut decoder = Ktx2Decoder::new(file).unwrap();
+/// let size = decoder.level_size(0).unwrap();
+///
+/// let mut data = vec![0_u8; size.pixels() as usize * 4];
+/// let image = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+/// decoder.decode_surface(0, 0, 0, 0, image).unwrap();
+/// ```
+pub struct Ktx2Decoder<R> {
+    reader: R,
+    header: Ktx2Header,
+    format: Ktx2Format,
+    pub options: DecodeOptions,
+}
+impl<R: Read + Seek> Ktx2Decoder<R> {
+    /// Creates a new decoder by reading the header of the given KTX2 file.
+    ///
+    /// The reader must be at the start of the file.
+    pub fn new(mut reader: R) -> Result<Self, DecodeError> {
+        let header = Ktx2Header::read(&mut reader)?;
+
+        if header.supercompression_scheme != 0 {
+            return Err(
+                Ktx2Error::UnsupportedSupercompression(header.supercompression_scheme).into(),
+            );
+        }
+        let mut format = Ktx2Format::from_vk_format(header.vk_format)
+            .ok_or(Ktx2Error::UnsupportedVkFormat(header.vk_format))?;
+        if header.face_count != 1 && header.face_count != 6 {
+            return Err(Ktx2Error::InvalidFaceCount(header.face_count).into());
+        }
+        if header.pixel_width == 0 {
+            return Err(Ktx2Error::DataLayoutTooBig.into());
+        }
+
+        // premultiplied alpha is only stored in the data format descriptor
+        if header.dfd_byte_length >= 16 {
+            reader.seek(SeekFrom::Start(header.dfd_byte_offset as u64 + 12))?;
+            let flags = read_u32(&mut reader)? >> 24;
+            if flags & 1 != 0 {
+                format = format.to_premultiplied_alpha();
+            }
+        }
+
+        let decoder = Self {
+            reader,
+            header,
+            format,
+            options: DecodeOptions::default(),
+        };
+
+        for (level, entry) in decoder.header.levels.iter().enumerate() {
+            let expected = decoder
+                .level_len(level as u8)
+                .ok_or(Ktx2Error::DataLayoutTooBig)?;
+            if entry.byte_length != expected || entry.byte_offset.checked_add(expected).is_none() {
+                return Err(Ktx2Error::InvalidLevelLength {
+                    level: level as u32,
+                    length: entry.byte_length,
+                }
+                .into());
+            }
+        }
+
+        Ok(decoder)
+    }
+}
+impl<R> Ktx2Decoder<R> {
+    pub fn header(&self) -> &Ktx2Header {
+        &self.header
+    }
+    pub fn format(&self) -> Ktx2Format {
+        self.format
+    }
+    pub fn into_reader(self) -> R {
+        self.reader
+    }
+
+    /// The number of mipmap levels stored in the file.
+    pub fn level_count(&self) -> u8 {
+        self.header.levels.len().min(u8::MAX as usize) as u8
+    }
+    /// The number of array layers. This is 1 for files that are not arrays.
+    pub fn layer_count(&self) -> u32 {
+        self.header.layer_count.max(1)
+    }
+    /// The number of faces. This is 6 for cube maps and 1 otherwise.
+    pub fn face_count(&self) -> u32 {
+        self.header.face_count
+    }
+    /// The size of the surfaces of the given mipmap level.
+    pub fn level_size(&self, level: u8) -> Option<Size> {
+        if level < self.level_count() {
+            let size = Size::new(self.header.pixel_width, self.header.pixel_height.max(1));
+            Some(size.get_mipmap(level))
+        } else {
+            None
+        }
+    }
+    /// The number of depth slices of the given mipmap level. This is 1 for
+    /// everything but 3D textures.
+    pub fn level_depth(&self, level: u8) -> Option<u32> {
+        if level < self.level_count() {
+            let depth = Size::new(self.header.pixel_depth.max(1), 1);
+            Some(depth.get_mipmap(level).width)
+        } else {
+            None
+        }
+    }
+
+    fn surface_len(&self, level: u8) -> Option<u64> {
+        PixelInfo::from(self.format.format()).surface_bytes(self.level_size(level)?)
+    }
+    fn level_len(&self, level: u8) -> Option<u64> {
+        let surfaces =
+            self.layer_count() as u64 * self.face_count() as u64 * self.level_depth(level)? as u64;
+        self.surface_len(level)?.checked_mul(surfaces)
+    }
+}
+impl<R: Read + Seek> Ktx2Decoder<R> {
+    /// Decodes a single surface into the given image.
+    ///
+    /// The image must have the size of the mipmap level (see
+    /// [`Self::level_size`]). `face` is the index of the face in the order
+    /// +X, -X, +Y, -Y, +Z, -Z for cube maps and must be 0 otherwise.
+    ///
+    /// If the surface does not exist, [`DecodeError::SurfaceNotFound`] is
+    /// returned.
+    pub fn decode_surface(
+        &mut self,
+        level: u8,
+        layer: u32,
+        face: u32,
+        depth_slice: u32,
+        image: ImageViewMut,
+    ) -> Result<(), DecodeError> {
+        let depth = self
+            .level_depth(level)
+            .ok_or(DecodeError::SurfaceNotFound)?;
+        if layer >= self.layer_count() || face >= self.face_count() || depth_slice >= depth {
+            return Err(DecodeError::SurfaceNotFound);
+        }
+        if Some(image.size()) != self.level_size(level) {
+            return Err(DecodeError::UnexpectedSurfaceSize);
+        }
+
+        // The constructor checked that the entire level can be addressed,
+        // so none of this can overflow.
+        let surface_len = self.surface_len(level).unwrap_or(0);
+        let index = (layer as u64 * self.face_count() as u64 + face as u64) * depth as u64
+            + depth_slice as u64;
+        let offset = self.header.levels[level as usize].byte_offset + index * surface_len;
+
+        self.reader.seek(SeekFrom::Start(offset))?;
+        decode(&mut self.reader, image, self.format.format(), &self.options)
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::{
+    header::{Header, ResourceDimension},
+    util::div_ceil,
+    DataLayout, DataRegion, DecodeError, Format, Ktx2Error, TextureArrayKind,
+};
+
+use super::{Ktx2Format, Ktx2Header, Ktx2Level};
+
+impl Ktx2Header {
+    /// Writes the identifier, header, index, and level index.
+    ///
+    /// This is the inverse of [`Ktx2Header::read`].
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&Self::IDENTIFIER)?;
+        for value in [
+            self.vk_format.0,
+            self.type_size,
+            self.pixel_width,
+            self.pixel_height,
+            self.pixel_depth,
+            self.layer_count,
+            self.face_count,
+            self.level_count,
+            self.supercompression_scheme,
+            self.dfd_byte_offset,
+            self.dfd_byte_length,
+            self.kvd_byte_offset,
+            self.kvd_byte_length,
+        ] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&self.sgd_byte_offset.to_le_bytes())?;
+        writer.write_all(&self.sgd_byte_length.to_le_bytes())?;
+
+        for level in &self.levels {
+            writer.write_all(&level.byte_offset.to_le_bytes())?;
+            writer.write_all(&level.byte_length.to_le_bytes())?;
+            writer.write_all(&level.uncompressed_byte_length.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the data of a DDS file as a KTX2 file without decoding it.
+///
+/// `reader` must be positioned at the start of the data section of the DDS
+/// file, i.e. right after the header. The encoded data of every surface is
+/// copied as is. Only the order of surfaces changes, since KTX2 groups
+/// surfaces by mipmap level while DDS groups them by array layer.
+///
+/// The KTX2 file has no supercompression and no key/value data. Partial cube
+/// maps and formats not supported by [`Ktx2Format`] result in an error.
+///
+/// ```
+/// # use dds::{header::Header, ktx2::*, *};
+/// let header = Header::new_image(4, 4, Format::BC1_UNORM).with_mipmaps();
+/// let data = vec![0_u8; 8 * 3];
+///
+/// let mut ktx2 = Vec::new();
+/// dds_to_ktx2(&header, &mut std::io::Cursor::new(&data), &mut ktx2).unwrap();
+///
+/// let decoder = Ktx2Decoder::new(std::io::Cursor::new(ktx2)).unwrap();
+/// assert_eq!(decoder.level_count(), 3);
+/// ```
+pub fn dds_to_ktx2<R: Read + Seek, W: Write>(
+    header: &Header,
+    reader: &mut R,
+    writer: &mut W,
+) -> Result<(), DecodeError> {
+    let format = Format::from_header(header)?;
+    let ktx2_format =
+        Ktx2Format::new(format, header.is_srgb()).ok_or(Ktx2Error::UnsupportedFormat(format))?;
+    let layout = DataLayout::from_header(header)?;
+
+    // KTX2 uses 0 for dimensions that the texture doesn't have
+    let (pixel_depth, layer_count, face_count) = match &layout {
+        DataLayout::Texture(_) => (0, 0, 1),
+        DataLayout::Volume(volume) => (volume.main().depth(), 0, 1),
+        DataLayout::TextureArray(array) => match array.kind() {
+            TextureArrayKind::Textures => (0, array.len() as u32, 1),
+            TextureArrayKind::CubeMaps => {
+                let cube_maps = array.len() as u32 / 6;
+                (0, if cube_maps > 1 { cube_maps } else { 0 }, 6)
+            }
+            TextureArrayKind::PartialCubeMap(_) => return Err(Ktx2Error::PartialCubeMap.into()),
+        },
+    };
+    let is_1d = header.dx10().map_or(false, |dx10| {
+        dx10.resource_dimension == ResourceDimension::Texture1D
+    });
+    let size = layout.main_size();
+
+    // The data of all surfaces of each mipmap level in KTX2 order
+    let level_regions: Vec<Vec<(u64, u64)>> = (0..layout.mipmaps())
+        .map(|level| level_regions(&layout, level))
+        .collect();
+
+    // Levels are stored from the smallest to the largest, and each level
+    // must be aligned to lcm(texel block size, 4).
+    let dfd = ktx2_format.data_format_descriptor();
+    let dfd_byte_offset = 80 + 24 * level_regions.len() as u32;
+    let data_start = dfd_byte_offset as u64 + dfd.len() as u64;
+    let alignment = lcm_4(ktx2_format.format().bytes_per_block() as u64);
+    let mut levels = vec![
+        Ktx2Level {
+            byte_offset: 0,
+            byte_length: 0,
+            uncompressed_byte_length: 0,
+        };
+        level_regions.len()
+    ];
+    let mut offset = data_start;
+    for (level, regions) in levels.iter_mut().zip(&level_regions).rev() {
+        let len: u64 = regions.iter().map(|(_, len)| len).sum();
+        offset = div_ceil(offset, alignment) * alignment;
+        *level = Ktx2Level {
+            byte_offset: offset,
+            byte_length: len,
+            uncompressed_byte_length: len,
+        };
+        offset += len;
+    }
+
+    let ktx2_header = Ktx2Header {
+        vk_format: ktx2_format.vk_format(),
+        type_size: ktx2_format.type_size(),
+        pixel_width: size.width,
+        pixel_height: if is_1d { 0 } else { size.height },
+        pixel_depth,
+        layer_count,
+        face_count,
+        level_count: levels.len() as u32,
+        supercompression_scheme: 0,
+        dfd_byte_offset,
+        dfd_byte_length: dfd.len() as u32,
+        kvd_byte_offset: 0,
+        kvd_byte_length: 0,
+        sgd_byte_offset: 0,
+        sgd_byte_length: 0,
+        levels,
+    };
+    ktx2_header.write(writer)?;
+    writer.write_all(&dfd)?;
+
+    let data_section = reader.stream_position()?;
+    let mut position = data_start;
+    for (level, regions) in ktx2_header.levels.iter().zip(&level_regions).rev() {
+        let padding = level.byte_offset - position;
+        writer.write_all(&[0; 16][..padding as usize])?;
+        for &(offset, len) in regions {
+            reader.seek(SeekFrom::Start(data_section + offset))?;
+            let copied = std::io::copy(&mut Read::take(&mut *reader, len), writer)?;
+            if copied != len {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "DDS data section is too short",
+                )
+                .into());
+            }
+        }
+        position = level.byte_offset + level.byte_length;
+    }
+
+    Ok(())
+}
+
+/// Returns the (offset, length) of the data of all surfaces of the given
+/// mipmap level, ordered by layer, face, and depth slice.
+fn level_regions(layout: &DataLayout, level: u8) -> Vec<(u64, u64)> {
+    let region = |r: &dyn DataRegion| (r.data_offset(), r.data_len());
+    match layout {
+        DataLayout::Texture(texture) => texture.get(level).iter().map(|s| region(s)).collect(),
+        DataLayout::Volume(volume) => volume.get(level).iter().map(|v| region(v)).collect(),
+        DataLayout::TextureArray(array) => array
+            .iter()
+            .filter_map(|texture| texture.get(level))
+            .map(|s| region(&s))
+            .collect(),
+    }
+}
+
+/// Returns the least common multiple of `n` and 4.
+fn lcm_4(n: u64) -> u64 {
+    if n % 4 == 0 {
+        n
+    } else if n % 2 == 0 {
+        n * 2
+    } else {
+        n * 4
+    }
+}
@@ -2,11 +2,11 @@ use std::num::{NonZeroU32, NonZeroU8};
 
 use bitflags::bitflags;
 
-use crate::header::{Caps2, Header, ResourceDimension};
+use crate::header::{Caps2, Header, RawHeader, ResourceDimension};
 use crate::DecodeError;
 use crate::{
-    util::{get_mipmap_size, NON_ZERO_U32_ONE},
-    LayoutError, PixelInfo, Size,
+    util::{div_ceil, get_mipmap_size, NON_ZERO_U32_ONE},
+    BlockPixelInfo, CubeFace, LayoutError, PixelInfo, Size,
 };
 
 pub trait DataRegion {
@@ -57,6 +57,51 @@ impl SurfaceDescriptor {
     pub fn size(&self) -> Size {
         Size::new(self.width(), self.height())
     }
+    /// The byte offset of this surface in the data section of a DDS file.
+    ///
+    /// This is the same as [`DataRegion::data_offset`].
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the luma (Y) and chroma (UV) planes of this surface.
+    ///
+    /// This is only supported for bi-planar formats (e.g. `NV12`). Returns
+    /// `None` if the given pixel info is not [`PixelInfo::BiPlanar`].
+    ///
+    /// ```
+    /// # use dds::{header::Header, *};
+    /// let header = Header::new_image(6, 4, Format::NV12);
+    /// let layout = DataLayout::from_header(&header).unwrap();
+    /// let surface = layout.texture().unwrap().main();
+    /// let [luma, chroma] = surface.planes(layout.pixel_info()).unwrap();
+    /// assert_eq!((luma.offset(), luma.row_pitch(), luma.size()), (0, 6, Size::new(6, 4)));
+    /// assert_eq!((chroma.offset(), chroma.row_pitch(), chroma.size()), (24, 6, Size::new(3, 2)));
+    /// ```
+    pub fn planes(&self, pixel_info: PixelInfo) -> Option<[PlaneDescriptor; 2]> {
+        let bi_planar = match pixel_info {
+            PixelInfo::BiPlanar(bi_planar) => bi_planar,
+            _ => return None,
+        };
+
+        let luma = PlaneDescriptor {
+            width: self.width(),
+            height: self.height(),
+            offset: self.offset,
+            row_pitch: self.width() as u64 * bi_planar.plane1_bytes_per_pixel() as u64,
+        };
+        let (sub_x, sub_y) = bi_planar.plane2_sub_sampling();
+        let chroma_width = div_ceil(self.width(), sub_x as u32);
+        let chroma = PlaneDescriptor {
+            width: chroma_width,
+            height: div_ceil(self.height(), sub_y as u32),
+            offset: luma.data_end(),
+            row_pitch: chroma_width as u64 * bi_planar.plane2_bytes_per_sample() as u64,
+        };
+        debug_assert_eq!(chroma.data_end(), self.data_end());
+
+        Some([luma, chroma])
+    }
 }
 impl DataRegion for SurfaceDescriptor {
     fn data_len(&self) -> u64 {
@@ -67,6 +112,49 @@ impl DataRegion for SurfaceDescriptor {
     }
 }
 
+/// A plane of a surface with a bi-planar format.
+///
+/// The size of a plane is given in samples. E.g. the chroma plane of a 6x4
+/// `NV12` surface has 3x2 samples, each storing U and V.
+///
+/// See [`SurfaceDescriptor::planes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaneDescriptor {
+    width: u32,
+    height: u32,
+    offset: u64,
+    row_pitch: u64,
+}
+impl PlaneDescriptor {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+    /// The byte offset of this plane in the data section of a DDS file.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+    /// The number of bytes between the start of two consecutive rows of
+    /// samples.
+    pub fn row_pitch(&self) -> u64 {
+        self.row_pitch
+    }
+}
+impl DataRegion for PlaneDescriptor {
+    fn data_len(&self) -> u64 {
+        // Cannot overflow, since the plane is part of a surface.
+        self.row_pitch * self.height as u64
+    }
+    fn data_offset(&self) -> u64 {
+        self.offset
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct VolumeDescriptor {
     width: NonZeroU32,
@@ -111,6 +199,13 @@ impl VolumeDescriptor {
     pub fn size(&self) -> Size {
         Size::new(self.width(), self.height())
     }
+    /// The byte offset of the first depth slice of this volume in the data
+    /// section of a DDS file.
+    ///
+    /// This is the same as [`DataRegion::data_offset`].
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
 
     pub fn get_depth_slice(&self, depth: u32) -> Option<SurfaceDescriptor> {
         if depth < self.depth() {
@@ -164,6 +259,65 @@ impl DataRegion for VolumeDescriptor {
     }
 }
 
+/// The pixel info of a layout along with the alignment of its rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LayoutPixels {
+    info: PixelInfo,
+    /// The number of bytes each row is padded to. This is always 1 for
+    /// non-uncompressed formats.
+    row_alignment: NonZeroU32,
+    /// Whether the number of blocks of each surface is rounded down instead
+    /// of up. This is always `false` for non-block formats.
+    legacy_blocks: bool,
+}
+impl LayoutPixels {
+    fn new(info: PixelInfo, row_alignment: NonZeroU32) -> Self {
+        let row_alignment = match info {
+            PixelInfo::Fixed { .. } => row_alignment,
+            _ => NON_ZERO_U32_ONE,
+        };
+        Self {
+            info,
+            row_alignment,
+            legacy_blocks: false,
+        }
+    }
+    fn new_legacy_blocks(info: PixelInfo) -> Self {
+        Self {
+            info,
+            row_alignment: NON_ZERO_U32_ONE,
+            legacy_blocks: matches!(info, PixelInfo::Block(_)),
+        }
+    }
+
+    fn surface_bytes(&self, size: Size) -> Option<u64> {
+        match self.info {
+            PixelInfo::Fixed { bytes_per_pixel } if self.row_alignment.get() > 1 => {
+                let row_bytes = size.width as u64 * bytes_per_pixel as u64;
+                let pitch = padded_row_pitch(row_bytes, self.row_alignment);
+                pitch.checked_mul(size.height as u64)
+            }
+            PixelInfo::Block(block) if self.legacy_blocks => {
+                let (blocks_x, blocks_y) = legacy_block_count(block, size);
+                (blocks_x * blocks_y).checked_mul(block.bytes_per_block() as u64)
+            }
+            _ => self.info.surface_bytes(size),
+        }
+    }
+}
+/// The number of blocks in x and y direction of a surface as computed by
+/// legacy writers, which round down instead of up.
+pub(crate) fn legacy_block_count(block: BlockPixelInfo, size: Size) -> (u64, u64) {
+    let (block_width, block_height) = block.size();
+    let blocks_x = (size.width / block_width as u32).max(1);
+    let blocks_y = (size.height / block_height as u32).max(1);
+    (blocks_x as u64, blocks_y as u64)
+}
+fn padded_row_pitch(row_bytes: u64, row_alignment: NonZeroU32) -> u64 {
+    let alignment = row_alignment.get() as u64;
+    div_ceil(row_bytes, alignment) * alignment
+}
+
 fn to_short_len(len: u64) -> Option<NonZeroU32> {
     len.try_into().ok().and_then(NonZeroU32::new)
 }
@@ -171,7 +325,7 @@ fn get_texture_len(
     width: NonZeroU32,
     height: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
 ) -> Option<u64> {
     let size = Size::new(width.get(), height.get());
 
@@ -188,7 +342,7 @@ pub struct Texture {
     width: NonZeroU32,
     height: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
     offset_index: u32,
     // A cache for data length. This is used to avoid recomputing the length
     // when the length is isn't too large.
@@ -200,7 +354,7 @@ impl Texture {
         width: NonZeroU32,
         height: NonZeroU32,
         mipmaps: NonZeroU8,
-        pixels: PixelInfo,
+        pixels: LayoutPixels,
     ) -> Result<Self, LayoutError> {
         // Check that length and all other calculations do not overflow
         let len =
@@ -217,7 +371,7 @@ impl Texture {
     }
 
     pub fn pixel_info(&self) -> PixelInfo {
-        self.pixels
+        self.pixels.info
     }
 
     /// The level 0 size of this texture.
@@ -280,7 +434,7 @@ fn get_volume_len(
     height: NonZeroU32,
     depth: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
 ) -> Option<u64> {
     let mut len: u64 = 0;
     for level in 0..mipmaps.get() {
@@ -304,7 +458,7 @@ pub struct Volume {
     height: NonZeroU32,
     depth: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
 }
 impl Volume {
     /// Creates a new volume at offset 0.
@@ -313,7 +467,7 @@ impl Volume {
         height: NonZeroU32,
         depth: NonZeroU32,
         mipmaps: NonZeroU8,
-        pixels: PixelInfo,
+        pixels: LayoutPixels,
     ) -> Result<Self, LayoutError> {
         // compute the length of the entire volume (including mips) to check
         // for overflows, so we can assume no overflows in the rest of the code
@@ -330,7 +484,7 @@ impl Volume {
     }
 
     pub fn pixel_info(&self) -> PixelInfo {
-        self.pixels
+        self.pixels.info
     }
 
     /// The level 0 mipmap of this volume.
@@ -425,14 +579,44 @@ impl From<CubeMapFaces> for Caps2 {
         Caps2::from_bits_truncate((faces as u32) << 10)
     }
 }
+impl From<CubeFace> for CubeMapFaces {
+    fn from(value: CubeFace) -> Self {
+        match value {
+            CubeFace::PositiveX => CubeMapFaces::POSITIVE_X,
+            CubeFace::NegativeX => CubeMapFaces::NEGATIVE_X,
+            CubeFace::PositiveY => CubeMapFaces::POSITIVE_Y,
+            CubeFace::NegativeY => CubeMapFaces::NEGATIVE_Y,
+            CubeFace::PositiveZ => CubeMapFaces::POSITIVE_Z,
+            CubeFace::NegativeZ => CubeMapFaces::NEGATIVE_Z,
+        }
+    }
+}
 impl CubeMapFaces {
     /// Returns the number of cube map sides set in this bit mask.
     pub fn count(&self) -> u32 {
         self.bits().count_ones()
     }
+    /// Whether the given face is set in this bit mask.
+    pub fn contains_face(&self, face: CubeFace) -> bool {
+        self.contains(face.into())
+    }
+    /// Iterates over all faces set in this bit mask in the order they are
+    /// stored in DDS files.
+    pub fn faces(&self) -> impl Iterator<Item = CubeFace> {
+        let faces = *self;
+        CubeFace::ALL
+            .into_iter()
+            .filter(move |&face| faces.contains_face(face))
+    }
 }
 
 /// An array of textures or (partial) cube maps.
+///
+/// The array only stores the layout of its first texture and the number of
+/// textures. The descriptors of all other textures (and their surfaces) are
+/// computed on demand in O(1). Creating, copying, and querying a texture
+/// array therefore takes constant time and memory, no matter how many
+/// textures it contains.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TextureArray {
     kind: TextureArrayKind,
@@ -441,7 +625,7 @@ pub struct TextureArray {
     width: NonZeroU32,
     height: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
     texture_short_len: Option<NonZeroU32>,
 }
 impl TextureArray {
@@ -468,12 +652,23 @@ impl TextureArray {
     }
 
     pub fn pixel_info(&self) -> PixelInfo {
-        self.pixels
+        self.pixels.info
     }
 
     pub fn kind(&self) -> TextureArrayKind {
         self.kind
     }
+    /// Returns the faces stored for each cube map, or `None` if the array
+    /// doesn't contain cube maps.
+    ///
+    /// This is [`CubeMapFaces::ALL`] for (arrays of) full cube maps.
+    pub fn cube_map_faces(&self) -> Option<CubeMapFaces> {
+        match self.kind {
+            TextureArrayKind::Textures => None,
+            TextureArrayKind::CubeMaps => Some(CubeMapFaces::ALL),
+            TextureArrayKind::PartialCubeMap(faces) => Some(faces),
+        }
+    }
 
     pub fn is_empty(&self) -> bool {
         self.array_len == 0
@@ -505,6 +700,51 @@ impl TextureArray {
             None
         }
     }
+    /// Returns the index of the texture of the given face of the given cube
+    /// map.
+    ///
+    /// For partial cube maps, only the faces stored in the file have an
+    /// index and `cube_index` must be 0. Returns `None` if the array doesn't
+    /// contain cube maps or the face does not exist.
+    pub fn face_index(&self, cube_index: usize, face: CubeFace) -> Option<usize> {
+        // PANIC SAFETY: all faces are in `CubeFace::ALL`
+        let face_index = CubeFace::ALL.iter().position(|f| f == &face).unwrap();
+
+        let index = match self.kind {
+            TextureArrayKind::Textures => return None,
+            TextureArrayKind::CubeMaps => cube_index.checked_mul(6)?.checked_add(face_index)?,
+            TextureArrayKind::PartialCubeMap(faces) => {
+                let face_bit = CubeMapFaces::from(face);
+                if cube_index != 0 || !faces.contains(face_bit) {
+                    return None;
+                }
+                // faces are stored in order, skipping the missing ones
+                (faces.bits() & (face_bit.bits() - 1)).count_ones() as usize
+            }
+        };
+        if index < self.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+    /// Returns the index of the cube map and the face of the texture with the
+    /// given index.
+    ///
+    /// This is the inverse of [`Self::face_index`]. Returns `None` if the
+    /// array doesn't contain cube maps or the index is out of bounds.
+    pub fn face_of(&self, index: usize) -> Option<(usize, CubeFace)> {
+        if index >= self.len() {
+            return None;
+        }
+        match self.kind {
+            TextureArrayKind::Textures => None,
+            TextureArrayKind::CubeMaps => Some((index / 6, CubeFace::ALL[index % 6])),
+            TextureArrayKind::PartialCubeMap(faces) => {
+                faces.faces().nth(index).map(|face| (0, face))
+            }
+        }
+    }
     pub fn iter(&self) -> impl Iterator<Item = Texture> {
         let mut texture = self.first();
         (0..self.array_len).map(move |index| {
@@ -523,7 +763,81 @@ impl DataRegion for TextureArray {
     }
 }
 
+/// The order in which the mipmaps of a texture or volume are stored.
+///
+/// DDS files store the mipmaps of each texture from largest to smallest. As a
+/// non-standard extension, this crate can also write and read files that store
+/// them from smallest to largest. This allows a texture to be streamed
+/// progressively (e.g. via HTTP range requests), since a low-resolution version
+/// is available after reading only a small part of the file.
+///
+/// Only the order of mip levels within a texture or volume is reversed. The
+/// textures of an array, the faces of a cube map, and the depth slices of a
+/// volume mipmap are stored in their usual order. Note that the offsets of
+/// [`DataLayout`] always describe the standard order.
+///
+/// Files that store mipmaps smallest first are marked with
+/// [`MipOrder::SMALLEST_FIRST_SIGNATURE`] in the `reserved1` field of the
+/// header. Other DDS readers will not recognize this signature and
+/// misinterpret such files, so only use this for files read by this crate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MipOrder {
+    /// Largest to smallest. This is the standard order of DDS files.
+    #[default]
+    LargestFirst,
+    /// Smallest to largest. This is a non-standard extension.
+    SmallestFirst,
+}
+impl MipOrder {
+    /// The index of the first of the two `reserved1` values holding the
+    /// signature.
+    const SIGNATURE_INDEX: usize = 7;
+    /// The signature that marks files storing mipmaps smallest first.
+    ///
+    /// It is stored in `reserved1[7..9]` of the header.
+    pub const SMALLEST_FIRST_SIGNATURE: [u32; 2] =
+        [u32::from_le_bytes(*b"DDSD"), u32::from_le_bytes(*b"MIPR")];
+
+    /// Detects the mip order of a file from the `reserved1` field of its
+    /// header.
+    pub fn from_raw_header(raw: &RawHeader) -> Self {
+        let i = Self::SIGNATURE_INDEX;
+        if raw.reserved1[i..i + 2] == Self::SMALLEST_FIRST_SIGNATURE {
+            Self::SmallestFirst
+        } else {
+            Self::LargestFirst
+        }
+    }
+    /// Returns the offset of a mipmap level in the data section of a file
+    /// using this mip order.
+    ///
+    /// `element` is the texture or volume the level belongs to and `level` is
+    /// the mipmap level, both with offsets in the standard order as given by
+    /// [`DataLayout`].
+    pub(crate) fn file_offset(self, element: &impl DataRegion, level: &impl DataRegion) -> u64 {
+        match self {
+            Self::LargestFirst => level.data_offset(),
+            Self::SmallestFirst => element.data_offset() + (element.data_end() - level.data_end()),
+        }
+    }
+
+    /// Writes the signature of this mip order into the `reserved1` field of
+    /// the given header.
+    ///
+    /// For [`MipOrder::LargestFirst`], the header is left unchanged.
+    pub fn apply_to_raw_header(self, raw: &mut RawHeader) {
+        if self == Self::SmallestFirst {
+            let i = Self::SIGNATURE_INDEX;
+            raw.reserved1[i..i + 2].copy_from_slice(&Self::SMALLEST_FIRST_SIGNATURE);
+        }
+    }
+}
+
 /// The type and layout of the surfaces/volumes in the data section of a DDS file.
+///
+/// Data layouts never allocate. Descriptors for individual textures, surfaces,
+/// and depth slices are computed on demand, so even layouts with millions of
+/// surfaces are cheap to create and query.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DataLayout {
     Texture(Texture),
@@ -536,6 +850,126 @@ impl DataLayout {
         Ok(layout)
     }
     pub fn from_header_with(header: &Header, pixel_info: PixelInfo) -> Result<Self, LayoutError> {
+        Self::from_header_with_pixels(header, LayoutPixels::new(pixel_info, NON_ZERO_U32_ONE))
+    }
+    /// Creates the data layout of a file whose rows of pixels are padded to a
+    /// multiple of the given number of bytes.
+    ///
+    /// Some writers pad each row of uncompressed surfaces (e.g. to 16 bytes
+    /// for 12-byte `R32G32B32_FLOAT` pixels), and declare the padded row
+    /// pitch in the `pitch_or_linear_size` field of the header. Rows are
+    /// padded in every surface, including mipmaps.
+    ///
+    /// The row alignment is only applied to uncompressed formats (see
+    /// [`PixelInfo::Fixed`]). It is ignored for all other formats.
+    pub fn from_header_with_row_alignment(
+        header: &Header,
+        pixel_info: PixelInfo,
+        row_alignment: NonZeroU32,
+    ) -> Result<Self, LayoutError> {
+        Self::from_header_with_pixels(header, LayoutPixels::new(pixel_info, row_alignment))
+    }
+    /// Creates the data layout of a file written by legacy tools that compute
+    /// the number of blocks of each surface by rounding down instead of up.
+    ///
+    /// Such tools compute the data length of block-compressed surfaces as
+    /// `max(1, width / 4) * max(1, height / 4) * bytes_per_block`. For
+    /// surfaces whose dimensions are not multiples of the block size, this
+    /// results in slightly shorter surfaces, missing the last column and row
+    /// of blocks. [`Decoder`](crate::Decoder) decodes missing blocks as if
+    /// all of their bytes were zero.
+    ///
+    /// This only affects block formats (see [`PixelInfo::Block`]). For all
+    /// other formats, this is the same as [`Self::from_header_with`].
+    pub fn from_header_with_legacy_block_sizes(
+        header: &Header,
+        pixel_info: PixelInfo,
+    ) -> Result<Self, LayoutError> {
+        Self::from_header_with_pixels(header, LayoutPixels::new_legacy_blocks(pixel_info))
+    }
+    /// Creates a data layout from its individual parts instead of a header.
+    ///
+    /// This is useful for containers other than DDS that store their data
+    /// in the same layout.
+    ///
+    /// - `depth`: `Some` for volumes, `None` for (arrays of) 2D textures and
+    ///   cube maps.
+    /// - `mipmaps`: The number of mipmaps of each texture/volume. A value of
+    ///   0 is treated as 1.
+    /// - `array_size`: The number of textures in a texture array, or the
+    ///   number of cube maps if `cube_map` is true. Must be 1 for volumes.
+    /// - `cube_map`: Whether each array element is a cube map with all 6
+    ///   faces.
+    ///
+    /// A texture with an `array_size` of 1 that is not a cube map will be a
+    /// [`DataLayout::Texture`].
+    ///
+    /// ```
+    /// # use dds::{header::Header, *};
+    /// let layout = DataLayout::from_parts(Format::BC1_UNORM, 64, 64, None, 7, 2, true).unwrap();
+    ///
+    /// let header = Header::new_cube_map_array(64, 64, 2, Format::BC1_UNORM)
+    ///     .unwrap()
+    ///     .with_mipmaps();
+    /// assert_eq!(layout, DataLayout::from_header(&header).unwrap());
+    /// ```
+    pub fn from_parts(
+        pixel_info: impl Into<PixelInfo>,
+        width: u32,
+        height: u32,
+        depth: Option<u32>,
+        mipmaps: u32,
+        array_size: u32,
+        cube_map: bool,
+    ) -> Result<Self, LayoutError> {
+        let pixels = LayoutPixels::new(pixel_info.into(), NON_ZERO_U32_ONE);
+        let width = parse_dimension(width)?;
+        let height = parse_dimension(height)?;
+        let mipmaps = parse_mipmap_count(NonZeroU32::new(mipmaps).unwrap_or(NON_ZERO_U32_ONE))?;
+
+        if let Some(depth) = depth {
+            if cube_map {
+                return Err(LayoutError::InvalidCubeMapDimensions);
+            }
+            if array_size != 1 {
+                return Err(LayoutError::ArraySizeTooBig(array_size));
+            }
+            let info = VolumeLayoutInfo {
+                width,
+                height,
+                depth: parse_dimension(depth)?,
+                mipmaps,
+                pixels,
+            };
+            return Ok(Self::Volume(info.create()?));
+        }
+
+        let info = SurfaceLayoutInfo {
+            width,
+            height,
+            mipmaps,
+            pixels,
+        };
+        if cube_map {
+            let cube_map_faces = array_size
+                .checked_mul(6)
+                .ok_or(LayoutError::ArraySizeTooBig(array_size))?;
+            Ok(Self::TextureArray(info.create_array(
+                TextureArrayKind::CubeMaps,
+                cube_map_faces,
+            )?))
+        } else if array_size == 1 {
+            Ok(Self::Texture(info.create()?))
+        } else {
+            Ok(Self::TextureArray(
+                info.create_array(TextureArrayKind::Textures, array_size)?,
+            ))
+        }
+    }
+    fn from_header_with_pixels(
+        header: &Header,
+        pixel_info: LayoutPixels,
+    ) -> Result<Self, LayoutError> {
         match header {
             Header::Dx10(dx10) => {
                 if dx10.is_cube_map() {
@@ -643,6 +1077,183 @@ impl DataLayout {
             DataLayout::TextureArray(array) => array.pixel_info(),
         }
     }
+    /// The number of bytes each row of pixels is padded to.
+    ///
+    /// This is 1 for all layouts without padded rows. See
+    /// [`Self::from_header_with_row_alignment`].
+    pub fn row_alignment(&self) -> NonZeroU32 {
+        match self {
+            DataLayout::Texture(texture) => texture.pixels.row_alignment,
+            DataLayout::Volume(volume) => volume.pixels.row_alignment,
+            DataLayout::TextureArray(array) => array.pixels.row_alignment,
+        }
+    }
+    /// Whether the number of blocks of each surface is rounded down.
+    ///
+    /// See [`Self::from_header_with_legacy_block_sizes`].
+    pub fn has_legacy_block_sizes(&self) -> bool {
+        match self {
+            DataLayout::Texture(texture) => texture.pixels.legacy_blocks,
+            DataLayout::Volume(volume) => volume.pixels.legacy_blocks,
+            DataLayout::TextureArray(array) => array.pixels.legacy_blocks,
+        }
+    }
+    /// The number of bytes between the start of two consecutive rows of
+    /// pixels in a surface of the given width.
+    ///
+    /// Returns `None` for formats that do not store pixels in rows (e.g.
+    /// block-compressed formats).
+    pub fn row_pitch(&self, width: u32) -> Option<u64> {
+        match self.pixel_info() {
+            PixelInfo::Fixed { bytes_per_pixel } => Some(padded_row_pitch(
+                width as u64 * bytes_per_pixel as u64,
+                self.row_alignment(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// The number of mipmap levels of each texture or volume.
+    pub fn mipmaps(&self) -> u8 {
+        match self {
+            DataLayout::Texture(texture) => texture.mipmaps(),
+            DataLayout::Volume(volume) => volume.mipmaps(),
+            DataLayout::TextureArray(array) => array.mipmaps.get(),
+        }
+    }
+    /// The number of array slices in D3D terms.
+    ///
+    /// This is the number of textures for texture arrays, where each face of
+    /// a cube map is a texture. Single textures and volumes have 1 array
+    /// slice.
+    pub fn array_slices(&self) -> u32 {
+        match self {
+            DataLayout::TextureArray(array) => array.array_len,
+            _ => 1,
+        }
+    }
+
+    /// Returns the D3D subresource index of the given mipmap level of the
+    /// given array slice.
+    ///
+    /// The index is `mipmap_level + array_slice * mipmaps`. For volumes, each
+    /// mipmap level (including all of its depth slices) is one subresource.
+    ///
+    /// Returns `None` if the mipmap level or array slice is out of range.
+    ///
+    /// ```
+    /// # use dds::{header::Header, *};
+    /// let header = Header::new_cube_map(64, 64, Format::BC1_UNORM).with_mipmaps();
+    /// let layout = DataLayout::from_header(&header).unwrap();
+    /// assert_eq!(layout.subresource_index(2, 3), Some(2 + 3 * 7));
+    /// assert_eq!(layout.subresource_location(23), Some((2, 3)));
+    /// assert_eq!(layout.subresource_index(7, 0), None);
+    /// ```
+    pub fn subresource_index(&self, mipmap_level: u8, array_slice: u32) -> Option<u32> {
+        let mipmaps = self.mipmaps();
+        if mipmap_level >= mipmaps || array_slice >= self.array_slices() {
+            return None;
+        }
+        array_slice
+            .checked_mul(mipmaps as u32)?
+            .checked_add(mipmap_level as u32)
+    }
+    /// Returns the `(mipmap_level, array_slice)` of the given D3D subresource
+    /// index.
+    ///
+    /// This is the inverse of [`Self::subresource_index`]. Returns `None` if
+    /// the index is out of range.
+    pub fn subresource_location(&self, index: u32) -> Option<(u8, u32)> {
+        let mipmaps = self.mipmaps() as u32;
+        let array_slice = index / mipmaps;
+        if array_slice >= self.array_slices() {
+            return None;
+        }
+        Some(((index % mipmaps) as u8, array_slice))
+    }
+    /// Returns the byte offset of a surface (or volume mipmap) in the data
+    /// section of a DDS file without iterating over the data section.
+    ///
+    /// - For texture arrays, `layer` is the index of the texture and `face`
+    ///   must be `None`.
+    /// - For cube maps (and cube map arrays), `layer` is the index of the cube
+    ///   map and `face` must be `Some`. See [`TextureArray::face_index`].
+    /// - For single textures and volumes, `layer` must be 0 and `face` must
+    ///   be `None`. For volumes, the offset of the first depth slice is
+    ///   returned.
+    ///
+    /// Returns `None` if the surface does not exist.
+    ///
+    /// ```
+    /// # use dds::{header::Header, *};
+    /// let header = Header::new_cube_map(4, 4, Format::R8G8B8A8_UNORM);
+    /// let layout = DataLayout::from_header(&header).unwrap();
+    /// // each face is 4 * 4 * 4 = 64 bytes
+    /// assert_eq!(layout.offset_of(0, Some(CubeFace::NegativeX), 0), Some(64));
+    /// assert_eq!(layout.offset_of(0, None, 0), None);
+    /// ```
+    pub fn offset_of(&self, layer: usize, face: Option<CubeFace>, mipmap_level: u8) -> Option<u64> {
+        match (self, face) {
+            (DataLayout::Texture(texture), None) if layer == 0 => {
+                texture.get(mipmap_level).map(|s| s.offset())
+            }
+            (DataLayout::Volume(volume), None) if layer == 0 => {
+                volume.get(mipmap_level).map(|v| v.offset())
+            }
+            (DataLayout::TextureArray(array), face) => {
+                let index = match (array.kind(), face) {
+                    (TextureArrayKind::Textures, None) => layer,
+                    (_, Some(face)) => array.face_index(layer, face)?,
+                    _ => return None,
+                };
+                array.get(index)?.get(mipmap_level).map(|s| s.offset())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the surface of the given D3D subresource index.
+    ///
+    /// Returns `None` if the index is out of range or the layout is a volume.
+    /// Subresources of volumes contain multiple depth slices, so use
+    /// [`Volume::get`] with the mipmap level returned by
+    /// [`Self::subresource_location`] instead.
+    pub fn get_subresource(&self, index: u32) -> Option<SurfaceDescriptor> {
+        let (level, array_slice) = self.subresource_location(index)?;
+        match self {
+            DataLayout::Texture(texture) => texture.get(level),
+            DataLayout::Volume(_) => None,
+            DataLayout::TextureArray(array) => array.get(array_slice as usize)?.get(level),
+        }
+    }
+
+    /// Iterates over all 2D surfaces of the data section in the order they
+    /// are stored.
+    ///
+    /// This includes all mipmaps of all textures, cube map faces, and the
+    /// depth slices of all volume mipmaps. Each surface comes with its
+    /// position in the layout. See [`SurfaceKind`].
+    ///
+    /// ```
+    /// # use dds::{header::Header, *};
+    /// let header = Header::new_cube_map(4, 4, Format::BC1_UNORM).with_mipmaps();
+    /// let layout = DataLayout::from_header(&header).unwrap();
+    /// for (surface, kind) in layout.iter_surfaces() {
+    ///     println!(
+    ///         "{:?} mip {} at offset {}",
+    ///         kind.face, kind.mipmap_level, surface.offset()
+    ///     );
+    /// }
+    /// assert_eq!(layout.iter_surfaces().count(), 6 * 3);
+    /// ```
+    pub fn iter_surfaces(&self) -> impl Iterator<Item = (SurfaceDescriptor, SurfaceKind)> {
+        SurfaceIter {
+            layout: *self,
+            element: 0,
+            level: 0,
+            depth: 0,
+        }
+    }
 }
 impl DataRegion for DataLayout {
     fn data_len(&self) -> u64 {
@@ -658,6 +1269,78 @@ impl DataRegion for DataLayout {
     }
 }
 
+/// The position of a 2D surface within a [`DataLayout`].
+///
+/// See [`DataLayout::iter_surfaces`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SurfaceKind {
+    /// The index of the texture in a texture array or the index of the cube
+    /// map in a cube map array. This is 0 for single textures, single cube
+    /// maps, and volumes.
+    pub layer: u32,
+    /// The face of a cube map. This is `None` if the layout doesn't contain
+    /// cube maps.
+    pub face: Option<CubeFace>,
+    /// The mipmap level of the surface.
+    pub mipmap_level: u8,
+    /// The depth slice of a volume mipmap. This is 0 for everything but
+    /// volumes.
+    pub depth_slice: u32,
+}
+
+struct SurfaceIter {
+    layout: DataLayout,
+    element: u32,
+    level: u8,
+    depth: u32,
+}
+impl Iterator for SurfaceIter {
+    type Item = (SurfaceDescriptor, SurfaceKind);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.element >= self.layout.array_slices() {
+            return None;
+        }
+
+        let mut kind = SurfaceKind {
+            layer: 0,
+            face: None,
+            mipmap_level: self.level,
+            depth_slice: self.depth,
+        };
+        let (surface, depth) = match &self.layout {
+            DataLayout::Texture(texture) => (texture.get(self.level)?, 1),
+            DataLayout::Volume(volume) => {
+                let volume = volume.get(self.level)?;
+                (volume.get_depth_slice(self.depth)?, volume.depth())
+            }
+            DataLayout::TextureArray(array) => {
+                let index = self.element as usize;
+                if let Some((cube_index, face)) = array.face_of(index) {
+                    kind.layer = cube_index as u32;
+                    kind.face = Some(face);
+                } else {
+                    kind.layer = self.element;
+                }
+                (array.get(index)?.get(self.level)?, 1)
+            }
+        };
+
+        self.depth += 1;
+        if self.depth >= depth {
+            self.depth = 0;
+            self.level += 1;
+            if self.level >= self.layout.mipmaps() {
+                self.level = 0;
+                self.element += 1;
+            }
+        }
+
+        Some((surface, kind))
+    }
+}
+
 fn parse_dimension(dim: u32) -> Result<NonZeroU32, LayoutError> {
     NonZeroU32::new(dim).ok_or(LayoutError::ZeroDimension)
 }
@@ -670,10 +1353,10 @@ struct SurfaceLayoutInfo {
     width: NonZeroU32,
     height: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
 }
 impl SurfaceLayoutInfo {
-    fn from_header(header: &Header, pixels: PixelInfo) -> Result<Self, LayoutError> {
+    fn from_header(header: &Header, pixels: LayoutPixels) -> Result<Self, LayoutError> {
         Ok(Self {
             width: parse_dimension(header.width())?,
             height: parse_dimension(header.height())?,
@@ -700,10 +1383,10 @@ struct VolumeLayoutInfo {
     height: NonZeroU32,
     depth: NonZeroU32,
     mipmaps: NonZeroU8,
-    pixels: PixelInfo,
+    pixels: LayoutPixels,
 }
 impl VolumeLayoutInfo {
-    fn from_header(header: &Header, pixels: PixelInfo) -> Result<Self, LayoutError> {
+    fn from_header(header: &Header, pixels: LayoutPixels) -> Result<Self, LayoutError> {
         Ok(Self {
             width: parse_dimension(header.width())?,
             height: parse_dimension(header.height())?,
@@ -1,36 +1,74 @@
 #![forbid(unsafe_code)]
 
+mod bc1_alpha;
 mod cast;
 mod color;
+mod cube_atlas;
 mod decode;
 mod decoder;
 mod detect;
+mod diff;
 mod encode;
 mod encoder;
+mod equirect;
 mod error;
+mod footprint;
 mod format;
 pub mod header;
+#[cfg(feature = "image")]
+mod image_interop;
 mod iter;
+pub mod ktx2;
 mod layout;
+pub mod metrics;
 mod pixel;
+mod redundancy;
+mod repair;
+mod report;
 mod resize;
 mod split;
+mod surface_encoder;
+mod transcode;
 mod util;
+mod validate;
+#[cfg(feature = "wgpu")]
+mod wgpu_interop;
 
 use std::num::NonZeroU8;
 
+pub use bc1_alpha::*;
 pub use color::*;
-pub use decode::{decode, decode_rect, DecodeOptions};
+pub use cube_atlas::*;
+pub use decode::{
+    decode, decode_borrowed, decode_channel, decode_partial, decode_rect, decode_rect_sequential,
+    decode_top_rows, decode_with_pitch, decode_with_scratch, Bc7Decoder, CancellationToken,
+    Channel, ColorAdjustment, DecodeOptions, DecodeProgress, DecodeScratch, ProgressCallback,
+    ToneMapCurve, ToneMapping,
+};
 pub use decoder::*;
+pub use diff::*;
 pub use encode::{
-    encode, CompressionQuality, Dithering, EncodeOptions, EncodingSupport, ErrorMetric,
+    encode, encode_bc1_block, encode_bc2_block, encode_bc3_block, encode_bc4_block,
+    encode_bc5_block, encode_bc7_block, encode_rect, encode_with_fallback, encode_with_report,
+    Bc7Modes, Bc7Options, CompressionQuality, Dithering, EncodeOptions, EncodingSupport,
+    ErrorMetric, OutOfRangeStats, QualityReport,
 };
 pub use encoder::*;
+pub use equirect::*;
 pub use error::*;
+pub use footprint::*;
 pub use format::*;
+#[cfg(feature = "image")]
+pub use image_interop::*;
 pub use layout::*;
 pub use pixel::*;
+pub use redundancy::*;
+pub use repair::*;
+pub use report::*;
 pub use split::*;
+pub use surface_encoder::*;
+pub use transcode::*;
+pub use validate::*;
 
 pub trait AsBytes {
     fn as_bytes(&self) -> &[u8];
@@ -50,7 +88,7 @@ macro_rules! for_slices {
         )*
     };
 }
-for_slices!(u8, u16, f32);
+for_slices!(u8, u16, u32, f32);
 macro_rules! for_array_slices {
     ($($t:ty),*) => {
         $(
@@ -65,7 +103,7 @@ macro_rules! for_array_slices {
         )*
     };
 }
-for_array_slices!(u8, u16, f32);
+for_array_slices!(u8, u16, u32, f32);
 
 /// A borrowed slice of image data.
 #[derive(Clone, Copy)]
@@ -202,16 +240,62 @@ impl Size {
         }
     }
 
+    /// Whether the width and height of this size are multiples of the given
+    /// size multiple.
+    ///
+    /// ```
+    /// # use dds::{Size, SizeMultiple};
+    /// assert!(Size::new(4, 6).is_multiple_of(SizeMultiple::M2_2));
+    /// assert!(!Size::new(4, 5).is_multiple_of(SizeMultiple::M2_2));
+    /// ```
     pub const fn is_multiple_of(&self, multiple: SizeMultiple) -> bool {
         self.width % multiple.width_multiple.get() as u32 == 0
             && self.height % multiple.height_multiple.get() as u32 == 0
     }
+    /// Rounds the width and height of this size down to the nearest multiple
+    /// of the given size multiple.
+    ///
+    /// Note that the returned size may be empty.
+    ///
+    /// ```
+    /// # use dds::{Size, SizeMultiple};
+    /// assert_eq!(Size::new(5, 6).round_down_to_multiple(SizeMultiple::M2_2), Size::new(4, 6));
+    /// assert_eq!(Size::new(1, 1).round_down_to_multiple(SizeMultiple::M2_2), Size::new(0, 0));
+    /// ```
     pub const fn round_down_to_multiple(&self, multiple: SizeMultiple) -> Self {
         Self {
             width: self.width - self.width % multiple.width_multiple.get() as u32,
             height: self.height - self.height % multiple.height_multiple.get() as u32,
         }
     }
+    /// Rounds the width and height of this size up to the nearest multiple
+    /// of the given size multiple.
+    ///
+    /// If rounding up would overflow `u32`, the dimension is rounded down
+    /// instead.
+    ///
+    /// ```
+    /// # use dds::{Size, SizeMultiple};
+    /// assert_eq!(Size::new(5, 6).round_up_to_multiple(SizeMultiple::M2_2), Size::new(6, 6));
+    /// assert_eq!(Size::new(0, 1).round_up_to_multiple(SizeMultiple::M2_2), Size::new(0, 2));
+    /// ```
+    pub const fn round_up_to_multiple(&self, multiple: SizeMultiple) -> Self {
+        const fn round_up(value: u32, multiple: u32) -> u32 {
+            let rem = value % multiple;
+            if rem == 0 {
+                value
+            } else if let Some(rounded) = value.checked_add(multiple - rem) {
+                rounded
+            } else {
+                value - rem
+            }
+        }
+
+        Self {
+            width: round_up(self.width, multiple.width_multiple.get() as u32),
+            height: round_up(self.height, multiple.height_multiple.get() as u32),
+        }
+    }
 }
 impl From<(u32, u32)> for Size {
     fn from((width, height): (u32, u32)) -> Self {
@@ -219,18 +303,39 @@ impl From<(u32, u32)> for Size {
     }
 }
 
-// TODO: Rethink this API
+/// A constraint on the width and height of an image.
+///
+/// Some formats can only be encoded if the width and height of the image are
+/// multiples of certain values. E.g. the bi-planar format `NV12` stores
+/// chroma for 2x2 pixel groups, so both the width and height of an `NV12`
+/// image must be even. This is described by [`SizeMultiple::M2_2`].
+///
+/// The width and height rules are independent of each other. A size
+/// satisfies a size multiple if `width % width_multiple == 0` and
+/// `height % height_multiple == 0`. See [`Size::is_multiple_of`].
+///
+/// Use [`Format::size_multiple`] to get the size multiple of a format and
+/// [`Format::round_size_up`] or [`Format::round_size_down`] to adjust a size
+/// before encoding.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SizeMultiple {
+    /// The width of the image must be a multiple of this value.
     pub width_multiple: NonZeroU8,
+    /// The height of the image must be a multiple of this value.
     pub height_multiple: NonZeroU8,
 }
 impl SizeMultiple {
+    /// No constraint. All sizes are multiples of 1x1.
     pub const ONE: Self = Self::new(1, 1);
-    // TODO: rename
+    /// The width and height must both be multiples of 2.
     pub const M2_2: Self = Self::new(2, 2);
 
-    const fn new(width_multiple: u8, height_multiple: u8) -> Self {
+    /// Creates a new size multiple.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if either argument is zero.
+    pub const fn new(width_multiple: u8, height_multiple: u8) -> Self {
         if let Some(width_multiple) = NonZeroU8::new(width_multiple) {
             if let Some(height_multiple) = NonZeroU8::new(height_multiple) {
                 return Self {
@@ -260,19 +365,180 @@ impl Rect {
         }
     }
 
+    /// Creates a rectangle at the origin covering an entire image of the
+    /// given size.
+    pub const fn from_size(size: Size) -> Self {
+        Self::new(0, 0, size.width, size.height)
+    }
+
     pub const fn size(&self) -> Size {
         Size::new(self.width, self.height)
     }
 
+    /// Returns `true` if the rectangle has a width or height of 0.
+    pub const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
     /// Returns `true` if this rectangle is completely within the bounds of the
     /// given size.
     ///
     /// This means that `self.x + self.width <= size.width` and
-    /// `self.y + self.height <= size.height`.
-    pub(crate) fn is_within_bounds(&self, size: Size) -> bool {
+    /// `self.y + self.height <= size.height`. Functions like [`decode_rect`]
+    /// return [`DecodeError::RectOutOfBounds`] for rectangles that are not.
+    pub fn is_within_bounds(&self, size: Size) -> bool {
         // use u64 to prevent overflow
         let end_x = self.x as u64 + self.width as u64;
         let end_y = self.y as u64 + self.height as u64;
         end_x <= size.width as u64 && end_y <= size.height as u64
     }
+
+    /// Returns the overlapping area of this rectangle and the given one.
+    ///
+    /// If the rectangles don't overlap, `None` is returned.
+    ///
+    /// ```
+    /// # use dds::Rect;
+    /// let a = Rect::new(0, 0, 10, 10);
+    /// assert_eq!(a.intersection(Rect::new(5, 8, 10, 10)), Some(Rect::new(5, 8, 5, 2)));
+    /// assert_eq!(a.intersection(Rect::new(10, 0, 10, 10)), None);
+    /// ```
+    pub fn intersection(&self, other: Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let end_x = self.end_x().min(other.end_x());
+        let end_y = self.end_y().min(other.end_y());
+
+        if x as u64 >= end_x || y as u64 >= end_y {
+            return None;
+        }
+        Some(Rect::new(
+            x,
+            y,
+            (end_x - x as u64) as u32,
+            (end_y - y as u64) as u32,
+        ))
+    }
+
+    /// Returns the part of this rectangle that is within the bounds of the
+    /// given size.
+    ///
+    /// The returned rectangle is always within bounds. If this rectangle is
+    /// completely out of bounds, an empty rectangle is returned.
+    ///
+    /// ```
+    /// # use dds::{Rect, Size};
+    /// let size = Size::new(10, 10);
+    /// assert_eq!(Rect::new(5, 8, 10, 10).clamp_to(size), Rect::new(5, 8, 5, 2));
+    /// assert!(Rect::new(20, 0, 10, 10).clamp_to(size).is_empty());
+    /// ```
+    pub fn clamp_to(&self, size: Size) -> Rect {
+        let x = self.x.min(size.width);
+        let y = self.y.min(size.height);
+        let end_x = self.end_x().min(size.width as u64) as u32;
+        let end_y = self.end_y().min(size.height as u64) as u32;
+        Rect::new(x, y, end_x - x, end_y - y)
+    }
+
+    /// Expands this rectangle such that its edges lie on the block boundaries
+    /// of the given format.
+    ///
+    /// Block-compressed formats (e.g. BC1) and sub-sampled formats (e.g.
+    /// `YUY2` or `NV12`) store pixels in groups. Rectangles aligned to these
+    /// groups can be decoded without decoding any pixels outside of them. For
+    /// all other formats, the rectangle is returned unchanged.
+    ///
+    /// The returned rectangle may extend beyond the size of a surface whose
+    /// size is not a multiple of the block size. Use [`Self::clamp_to`] to
+    /// clamp it to the surface.
+    ///
+    /// ```
+    /// # use dds::{Format, Rect, Size};
+    /// let rect = Rect::new(5, 3, 2, 2).align_to_blocks(Format::BC1_UNORM);
+    /// assert_eq!(rect, Rect::new(4, 0, 4, 8));
+    /// assert_eq!(rect.clamp_to(Size::new(6, 6)), Rect::new(4, 0, 2, 6));
+    /// ```
+    pub fn align_to_blocks(&self, format: Format) -> Rect {
+        let (block_width, block_height) = match PixelInfo::from(format) {
+            PixelInfo::Fixed { .. } => (1, 1),
+            PixelInfo::Block(block) => block.size(),
+            PixelInfo::BiPlanar(bi_planar) => bi_planar.plane2_sub_sampling(),
+        };
+        let (block_width, block_height) = (block_width as u64, block_height as u64);
+
+        let x = self.x as u64 / block_width * block_width;
+        let y = self.y as u64 / block_height * block_height;
+        let end_x = util::div_ceil(self.end_x(), block_width) * block_width;
+        let end_y = util::div_ceil(self.end_y(), block_height) * block_height;
+
+        let saturate = |value: u64| value.min(u32::MAX as u64) as u32;
+        Rect::new(x as u32, y as u32, saturate(end_x - x), saturate(end_y - y))
+    }
+
+    /// Divides this rectangle into tiles of the given size.
+    ///
+    /// Tiles are returned row by row, from top to bottom and left to right.
+    /// Tiles at the right and bottom edges are smaller if the size of this
+    /// rectangle is not a multiple of the tile size. Empty rectangles have
+    /// no tiles.
+    ///
+    /// Tile sizes that are multiples of the block size of a format (see
+    /// [`Self::align_to_blocks`]) ensure that all tiles of an aligned
+    /// rectangle are aligned as well.
+    ///
+    /// Pixels of this rectangle with a coordinate larger than `u32::MAX`
+    /// can't be part of a tile, so they are not covered.
+    ///
+    /// ```
+    /// # use dds::{Rect, Size};
+    /// let tiles: Vec<Rect> = Rect::new(0, 0, 5, 3).tiles(Size::new(4, 2)).collect();
+    /// assert_eq!(tiles, [
+    ///     Rect::new(0, 0, 4, 2),
+    ///     Rect::new(4, 0, 1, 2),
+    ///     Rect::new(0, 2, 4, 1),
+    ///     Rect::new(4, 2, 1, 1),
+    /// ]);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the tile size is empty.
+    pub fn tiles(&self, tile_size: Size) -> impl Iterator<Item = Rect> {
+        assert!(!tile_size.is_empty(), "Tile size must not be empty");
+
+        // clamp the size so that `rect.x + x` and `rect.y + y` can't overflow
+        let max_len = |start: u32, len: u32| len.min((u32::MAX - start).saturating_add(1));
+        let rect = Rect::new(
+            self.x,
+            self.y,
+            max_len(self.x, self.width),
+            max_len(self.y, self.height),
+        );
+        let columns = util::div_ceil(rect.width, tile_size.width);
+        let rows = if rect.is_empty() {
+            0
+        } else {
+            util::div_ceil(rect.height, tile_size.height)
+        };
+
+        (0..rows).flat_map(move |row| {
+            (0..columns).map(move |column| {
+                let x = column * tile_size.width;
+                let y = row * tile_size.height;
+                Rect::new(
+                    rect.x + x,
+                    rect.y + y,
+                    tile_size.width.min(rect.width - x),
+                    tile_size.height.min(rect.height - y),
+                )
+            })
+        })
+    }
+
+    fn end_x(&self) -> u64 {
+        self.x as u64 + self.width as u64
+    }
+    fn end_y(&self) -> u64 {
+        self.y as u64 + self.height as u64
+    }
 }
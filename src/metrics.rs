@@ -0,0 +1,256 @@
+//! Image quality metrics for comparing an image against a reference.
+//!
+//! All functions take 2 images with interleaved `f32` values (e.g. RGBA
+//! pixels as `[r, g, b, a, r, g, b, a, ...]`), their size, and the channels of
+//! their pixels. Metrics are computed for each channel separately and returned
+//! in the order of the channels. Values are expected to be in the range
+//! [0, 1], which is also the peak value used for PSNR.
+//!
+//! ```
+//! # use dds::{metrics, Channels, Size};
+//! let size = Size::new(8, 8);
+//! let original = vec![0.5_f32; 8 * 8 * 3];
+//! let mut other = original.clone();
+//! other[0] = 0.6;
+//!
+//! let psnr = metrics::psnr(&original, &other, size, Channels::Rgb);
+//! assert!(psnr[0] > 30.0);
+//! assert_eq!(psnr[1], f64::INFINITY);
+//!
+//! let ssim = metrics::ssim(&original, &original, size, Channels::Rgb);
+//! assert_eq!(ssim, vec![1.0; 3]);
+//! ```
+//!
+//! The functions in this module are not optimized for speed. They are meant
+//! for tests, tools, and asset pipelines that need to judge the quality of
+//! encoded images.
+
+use crate::{Channels, Size};
+
+/// The peak signal-to-noise ratio (in dB) of each channel. The peak value is
+/// 1.0.
+///
+/// This is `f64::INFINITY` for channels that are identical in both images.
+///
+/// # Panics
+///
+/// If the length of either image is not `size.pixels() * channels.count()`.
+pub fn psnr(original: &[f32], other: &[f32], size: Size, channels: Channels) -> Vec<f64> {
+    per_channel(original, other, size, channels, |a, b, _, _| {
+        psnr_of(&a, &b)
+    })
+}
+
+/// The PSNR (see [`psnr`]) of each channel after both images have been
+/// blurred with a small 5x5 Gaussian kernel.
+///
+/// Compared to the plain PSNR, this metric is less sensitive to high-frequency
+/// noise (e.g. from dithering) and more sensitive to errors spanning larger
+/// areas (e.g. color shifts or banding).
+///
+/// # Panics
+///
+/// If the length of either image is not `size.pixels() * channels.count()`.
+pub fn psnr_blurred(original: &[f32], other: &[f32], size: Size, channels: Channels) -> Vec<f64> {
+    per_channel(original, other, size, channels, |a, b, width, height| {
+        let a = blur(a, width, height, &BLUR_WEIGHTS);
+        let b = blur(b, width, height, &BLUR_WEIGHTS);
+        psnr_of(&a, &b)
+    })
+}
+
+/// The mean structural similarity index (SSIM) of each channel.
+///
+/// This uses the standard parameters of an 11x11 Gaussian window with a
+/// standard deviation of 1.5, `k1 = 0.01`, and `k2 = 0.03`. Pixels outside
+/// the image are treated as copies of the nearest edge pixel.
+///
+/// The result is 1.0 for identical channels and decreases with the
+/// perceived difference.
+///
+/// # Panics
+///
+/// If the length of either image is not `size.pixels() * channels.count()`.
+pub fn ssim(original: &[f32], other: &[f32], size: Size, channels: Channels) -> Vec<f64> {
+    per_channel(original, other, size, channels, |a, b, width, height| {
+        ssim_of(a, b, width, height)
+    })
+}
+
+/// The mean absolute value of the average error of each 4x4 region.
+///
+/// Errors within a region can cancel out, so this metric measures errors
+/// that shift the average value of a region (e.g. biased rounding) rather
+/// than noise. Pixels in incomplete regions at the right and bottom edge of
+/// the image are ignored.
+///
+/// # Panics
+///
+/// If the length of either image is not `size.pixels() * channels.count()`.
+pub fn region_error(original: &[f32], other: &[f32], size: Size, channels: Channels) -> Vec<f64> {
+    per_channel(original, other, size, channels, |a, b, width, height| {
+        const REGION_SIZE: usize = 4;
+
+        let regions_x = width / REGION_SIZE;
+        let regions_y = height / REGION_SIZE;
+        if regions_x == 0 || regions_y == 0 {
+            return 0.0;
+        }
+
+        let mut region_error = 0.0;
+        for region_y in 0..regions_y {
+            for region_x in 0..regions_x {
+                let mut region = 0.0;
+                for y in 0..REGION_SIZE {
+                    for x in 0..REGION_SIZE {
+                        let i = (region_y * REGION_SIZE + y) * width + region_x * REGION_SIZE + x;
+                        region += a[i] - b[i];
+                    }
+                }
+                region_error += region.abs() / (REGION_SIZE * REGION_SIZE) as f64;
+            }
+        }
+        region_error / (regions_x * regions_y) as f64
+    })
+}
+
+/// Splits both images into their channels and computes the given metric for
+/// each pair of channels.
+fn per_channel(
+    original: &[f32],
+    other: &[f32],
+    size: Size,
+    channels: Channels,
+    metric: impl Fn(Vec<f64>, Vec<f64>, usize, usize) -> f64,
+) -> Vec<f64> {
+    let count = channels.count() as usize;
+    let expected_len = size.pixels() as usize * count;
+    assert_eq!(original.len(), expected_len, "invalid original image");
+    assert_eq!(other.len(), expected_len, "invalid other image");
+
+    let plane = |image: &[f32], c: usize| -> Vec<f64> {
+        image
+            .iter()
+            .skip(c)
+            .step_by(count)
+            .map(|&v| v as f64)
+            .collect()
+    };
+
+    let width = size.width as usize;
+    let height = size.height as usize;
+    (0..count)
+        .map(|c| metric(plane(original, c), plane(other, c), width, height))
+        .collect()
+}
+
+fn psnr_of(a: &[f64], b: &[f64]) -> f64 {
+    let mut mse = 0.0;
+    for (&a, &b) in a.iter().zip(b) {
+        let diff = a - b;
+        mse += diff * diff;
+    }
+    mse /= a.len().max(1) as f64;
+    -10.0 * mse.log10()
+}
+
+const BLUR_WEIGHTS: [f64; 5] = {
+    let raw = [1.0, 4.0, 6.0, 4.0, 1.0];
+    let sum = raw[0] + raw[1] + raw[2] + raw[3] + raw[4];
+    [
+        raw[0] / sum,
+        raw[1] / sum,
+        raw[2] / sum,
+        raw[3] / sum,
+        raw[4] / sum,
+    ]
+};
+
+/// Applies the given symmetric kernel horizontally and vertically. Pixels
+/// outside the image are treated as copies of the nearest edge pixel.
+fn blur(mut image: Vec<f64>, width: usize, height: usize, kernel: &[f64]) -> Vec<f64> {
+    debug_assert!(kernel.len() % 2 == 1);
+    let radius = kernel.len() / 2;
+    if width == 0 || height == 0 {
+        return image;
+    }
+
+    let convolve = |line: &[f64], out: &mut [f64]| {
+        let last = line.len() - 1;
+        for (i, out) in out.iter_mut().enumerate() {
+            let mut sum = 0.0;
+            for (k, &weight) in kernel.iter().enumerate() {
+                let j = (i + k).saturating_sub(radius).min(last);
+                sum += line[j] * weight;
+            }
+            *out = sum;
+        }
+    };
+
+    let mut line = vec![0.0; width.max(height)];
+
+    // Pass 1: horizontal
+    for row in image.chunks_exact_mut(width) {
+        line[..width].copy_from_slice(row);
+        convolve(&line[..width], row);
+    }
+
+    // Pass 2: vertical
+    let mut column = vec![0.0; height];
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = image[y * width + x];
+        }
+        convolve(&line[..height], &mut column);
+        for y in 0..height {
+            image[y * width + x] = column[y];
+        }
+    }
+
+    image
+}
+
+fn ssim_of(a: Vec<f64>, b: Vec<f64>, width: usize, height: usize) -> f64 {
+    const C1: f64 = 0.01 * 0.01;
+    const C2: f64 = 0.03 * 0.03;
+
+    if a.is_empty() {
+        return 1.0;
+    }
+
+    let kernel = gaussian_kernel(1.5, 5);
+
+    let aa = a.iter().map(|&a| a * a).collect();
+    let bb = b.iter().map(|&b| b * b).collect();
+    let ab = a.iter().zip(&b).map(|(&a, &b)| a * b).collect();
+
+    let mean_a = blur(a, width, height, &kernel);
+    let mean_b = blur(b, width, height, &kernel);
+    let mean_aa = blur(aa, width, height, &kernel);
+    let mean_bb = blur(bb, width, height, &kernel);
+    let mean_ab = blur(ab, width, height, &kernel);
+
+    let mut sum = 0.0;
+    for i in 0..mean_a.len() {
+        let (mu_a, mu_b) = (mean_a[i], mean_b[i]);
+        let var_a = mean_aa[i] - mu_a * mu_a;
+        let var_b = mean_bb[i] - mu_b * mu_b;
+        let cov = mean_ab[i] - mu_a * mu_b;
+
+        let numerator = (2.0 * mu_a * mu_b + C1) * (2.0 * cov + C2);
+        let denominator = (mu_a * mu_a + mu_b * mu_b + C1) * (var_a + var_b + C2);
+        sum += numerator / denominator;
+    }
+    sum / mean_a.len() as f64
+}
+
+fn gaussian_kernel(sigma: f64, radius: usize) -> Vec<f64> {
+    let weights: Vec<f64> = (0..=2 * radius)
+        .map(|i| {
+            let x = i as f64 - radius as f64;
+            (-(x * x) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f64 = weights.iter().sum();
+    weights.into_iter().map(|w| w / sum).collect()
+}
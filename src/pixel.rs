@@ -183,6 +183,11 @@ impl PixelInfo {
     /// If an overflow occurs, `None` is returned. This typically happens when
     /// the surface is unrealistically large, hinting at a modified, corrupted,
     /// or otherwise invalid DDS file.
+    ///
+    /// Blocks and chroma samples are never split. If the size of the surface
+    /// is not a multiple of the block size or chroma sub-sampling (e.g. a
+    /// `YUY2` or `NV12` surface with an odd width), it is rounded up. E.g. a
+    /// 5x3 `NV12` surface stores 5x3 luma samples and 3x2 chroma samples.
     pub fn surface_bytes(&self, size: Size) -> Option<u64> {
         match *self {
             Self::Fixed { bytes_per_pixel } => size.pixels().checked_mul(bytes_per_pixel as u64),
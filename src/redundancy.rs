@@ -0,0 +1,339 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::num::NonZeroU32;
+
+use crate::header::Header;
+use crate::{
+    decode, ColorFormat, DataLayout, DataRegion, DdsInfo, DecodeError, DecodeOptions, ImageViewMut,
+    MipOrder, Size,
+};
+
+/// An analysis of redundant data in the data section of a DDS file.
+///
+/// Two kinds of redundancy are detected:
+///
+/// 1. **Solid mipmaps.** If the smallest mipmaps of a texture all have the
+///    same solid color, they can be removed without changing how the texture
+///    looks when sampled. See [`Self::reduced_mipmap_count`] and
+///    [`truncate_mipmaps`].
+/// 2. **Duplicate elements.** Textures of a texture array (or faces of a cube
+///    map) whose data is byte-for-byte identical to an earlier one. This is
+///    common in padded texture atlases. Since removing them changes the
+///    indexes of the array, they are only reported.
+///
+/// With the `serde` feature enabled, this implements `serde::Serialize`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RedundancyReport {
+    /// The number of mipmap levels of the file.
+    pub mipmap_count: u32,
+    /// The smallest number of mipmap levels such that all removed levels of
+    /// all elements are solid and have the same color as the smallest
+    /// remaining level.
+    ///
+    /// If this is equal to [`Self::mipmap_count`], no mipmaps can be removed.
+    pub reduced_mipmap_count: u32,
+    /// The number of bytes saved by reducing the mipmap count to
+    /// [`Self::reduced_mipmap_count`].
+    pub mipmap_savings: u64,
+    /// The number of bytes of all elements that are duplicates of earlier
+    /// elements.
+    pub duplicate_savings: u64,
+    /// All textures (or volumes) of the data section in the order they are
+    /// stored.
+    ///
+    /// For texture arrays, each element is one texture of the array. For cube
+    /// maps, each element is one face.
+    pub elements: Vec<ElementRedundancy>,
+}
+
+/// The redundancy of a texture or volume in the data section of a DDS file.
+///
+/// See [`RedundancyReport::elements`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ElementRedundancy {
+    /// The byte offset of this element in the data section.
+    pub offset: u64,
+    /// The number of bytes of this element, including all mipmaps.
+    pub len: u64,
+    /// The first mipmap level such that this level and all smaller levels are
+    /// solid and have the same color.
+    ///
+    /// This is `None` if the smallest mipmap is not solid.
+    pub solid_from_level: Option<u8>,
+    /// The RGBA color of the solid mipmaps, if any.
+    pub solid_color: Option<[f32; 4]>,
+    /// The index of the first earlier element with identical data, if any.
+    pub duplicate_of: Option<usize>,
+}
+
+impl RedundancyReport {
+    /// Analyzes the data section of a DDS file.
+    ///
+    /// The reader is expected to be positioned at the start of the data
+    /// section. If this operation succeeds, the reader will be positioned at
+    /// the end of the data section.
+    ///
+    /// To determine whether mipmaps are solid, they are decoded starting with
+    /// the smallest mipmap. Larger mipmaps are only decoded as long as all
+    /// smaller mipmaps are solid, so this is typically fast even for large
+    /// textures.
+    pub fn from_reader<R: Read + Seek>(
+        reader: &mut R,
+        info: &DdsInfo,
+    ) -> Result<Self, DecodeError> {
+        let start = reader.stream_position()?;
+        let layout = info.layout();
+        let mipmap_count = info.header().mipmap_count().get();
+
+        let mut elements: Vec<ElementRedundancy> = Vec::new();
+        let mut element_data: Vec<u8> = Vec::new();
+        let mut other_data: Vec<u8> = Vec::new();
+        for element in get_elements(&layout, info.mip_order()) {
+            reader.seek(SeekFrom::Start(start + element.offset))?;
+            element_data.resize(to_usize(element.len)?, 0);
+            reader.read_exact(&mut element_data)?;
+
+            let (solid_from_level, solid_color) =
+                find_solid_mipmaps(&element, &element_data, info)?;
+
+            // find duplicates
+            let mut duplicate_of = None;
+            for (index, other) in elements.iter().enumerate() {
+                if other.duplicate_of.is_some() {
+                    // duplicates of duplicates are found via the original
+                    continue;
+                }
+                if other.solid_color != solid_color || other.len != element.len {
+                    // can't be identical
+                    continue;
+                }
+                reader.seek(SeekFrom::Start(start + other.offset))?;
+                other_data.resize(element_data.len(), 0);
+                reader.read_exact(&mut other_data)?;
+                if other_data == element_data {
+                    duplicate_of = Some(index);
+                    break;
+                }
+            }
+
+            elements.push(ElementRedundancy {
+                offset: element.offset,
+                len: element.len,
+                solid_from_level,
+                solid_color,
+                duplicate_of,
+            });
+        }
+
+        reader.seek(SeekFrom::Start(start + layout.data_len()))?;
+
+        let reduced_mipmap_count = elements
+            .iter()
+            .map(|e| e.solid_from_level.map_or(mipmap_count, |l| l as u32 + 1))
+            .max()
+            .unwrap_or(mipmap_count);
+        let mipmap_savings = get_elements(&layout, info.mip_order())
+            .map(|e| {
+                e.levels
+                    .iter()
+                    .skip(reduced_mipmap_count as usize)
+                    .map(|l| l.len)
+                    .sum::<u64>()
+            })
+            .sum();
+        let duplicate_savings = elements
+            .iter()
+            .filter(|e| e.duplicate_of.is_some())
+            .map(|e| e.len)
+            .sum();
+
+        Ok(Self {
+            mipmap_count,
+            reduced_mipmap_count,
+            mipmap_savings,
+            duplicate_savings,
+            elements,
+        })
+    }
+}
+
+/// Copies a DDS file while removing all mipmaps beyond the given count.
+///
+/// The reader is expected to be positioned at the start of the data section
+/// of a file with the given info. The header with the reduced mipmap count
+/// (including magic bytes) and the reduced data section are written to the
+/// writer. If this operation succeeds, the reader will be positioned at the
+/// end of the data section.
+///
+/// The written header is created from [`DdsInfo::header`] (and
+/// [`DdsInfo::mip_order`]), so unknown values in reserved fields of the
+/// original header are not preserved. If the given mipmap count is greater
+/// than the mipmap count of the file, all mipmaps are kept.
+///
+/// This is typically used together with
+/// [`RedundancyReport::reduced_mipmap_count`].
+pub fn truncate_mipmaps<R: Read + Seek, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    info: &DdsInfo,
+    mipmap_count: NonZeroU32,
+) -> Result<(), DecodeError> {
+    let start = reader.stream_position()?;
+    let layout = info.layout();
+    let mip_order = info.mip_order();
+    let mipmap_count = mipmap_count.min(info.header().mipmap_count());
+
+    let header = info.header().clone().with_mipmap_count(mipmap_count);
+    let mut raw = header.to_raw();
+    mip_order.apply_to_raw_header(&mut raw);
+    writer.write_all(&Header::MAGIC)?;
+    raw.write(writer)?;
+
+    let mut buffer = Vec::new();
+    for element in get_elements(&layout, mip_order) {
+        let mut levels: Vec<&Level> = element
+            .levels
+            .iter()
+            .take(mipmap_count.get() as usize)
+            .collect();
+        if mip_order == MipOrder::SmallestFirst {
+            levels.reverse();
+        }
+        for level in levels {
+            reader.seek(SeekFrom::Start(start + element.offset + level.offset))?;
+            buffer.resize(to_usize(level.len)?, 0);
+            reader.read_exact(&mut buffer)?;
+            writer.write_all(&buffer)?;
+        }
+    }
+
+    reader.seek(SeekFrom::Start(start + layout.data_len()))?;
+    Ok(())
+}
+
+pub(crate) fn to_usize(len: u64) -> Result<usize, DecodeError> {
+    usize::try_from(len).map_err(|_| DecodeError::MemoryLimitExceeded)
+}
+
+pub(crate) struct Element {
+    pub offset: u64,
+    pub len: u64,
+    /// The mipmap levels of the element, largest first.
+    pub levels: Vec<Level>,
+}
+pub(crate) struct Level {
+    /// The byte offset of this level relative to the element.
+    pub offset: u64,
+    pub len: u64,
+    pub size: Size,
+    /// The number of depth slices.
+    pub depth: u32,
+}
+
+pub(crate) fn get_elements(
+    layout: &DataLayout,
+    mip_order: MipOrder,
+) -> impl Iterator<Item = Element> {
+    fn element(offset: u64, len: u64, levels: Vec<Level>, mip_order: MipOrder) -> Element {
+        let mut levels = levels;
+        if mip_order == MipOrder::SmallestFirst {
+            // the layout describes the standard order
+            for level in &mut levels {
+                level.offset = len - level.offset - level.len;
+            }
+        }
+        Element {
+            offset,
+            len,
+            levels,
+        }
+    }
+
+    let elements: Vec<Element> = match layout {
+        DataLayout::Volume(volume) => {
+            let levels = volume
+                .iter_mips()
+                .map(|mip| Level {
+                    offset: mip.data_offset() - volume.data_offset(),
+                    len: mip.data_len(),
+                    size: mip.size(),
+                    depth: mip.depth(),
+                })
+                .collect();
+            vec![element(
+                volume.data_offset(),
+                volume.data_len(),
+                levels,
+                mip_order,
+            )]
+        }
+        DataLayout::Texture(texture) => vec![texture_element(*texture, mip_order)],
+        DataLayout::TextureArray(array) => array
+            .iter()
+            .map(|texture| texture_element(texture, mip_order))
+            .collect(),
+    };
+
+    fn texture_element(texture: crate::Texture, mip_order: MipOrder) -> Element {
+        let levels = texture
+            .iter_mips()
+            .map(|mip| Level {
+                offset: mip.data_offset() - texture.data_offset(),
+                len: mip.data_len(),
+                size: mip.size(),
+                depth: 1,
+            })
+            .collect();
+        element(texture.data_offset(), texture.data_len(), levels, mip_order)
+    }
+
+    elements.into_iter()
+}
+
+fn find_solid_mipmaps(
+    element: &Element,
+    data: &[u8],
+    info: &DdsInfo,
+) -> Result<(Option<u8>, Option<[f32; 4]>), DecodeError> {
+    let mut solid_from_level = None;
+    let mut solid_color = None;
+    let mut pixels: Vec<[f32; 4]> = Vec::new();
+
+    for (level_index, level) in element.levels.iter().enumerate().rev() {
+        let level_data = &data[level.offset as usize..][..level.len as usize];
+        let slice_len = level_data.len() / level.depth as usize;
+
+        let mut is_solid = true;
+        for slice in level_data.chunks_exact(slice_len) {
+            pixels.clear();
+            pixels.resize(level.size.pixels() as usize, [0.0; 4]);
+            let image = ImageViewMut::new(pixels.as_mut_slice(), level.size, ColorFormat::RGBA_F32)
+                .expect("invalid buffer size");
+            decode(
+                &mut &slice[..],
+                image,
+                info.format(),
+                &DecodeOptions::default(),
+            )?;
+
+            let color = *solid_color.get_or_insert(pixels[0]);
+            if pixels.iter().any(|p| p != &color) {
+                is_solid = false;
+                break;
+            }
+        }
+
+        if !is_solid {
+            break;
+        }
+        solid_from_level = Some(level_index as u8);
+    }
+
+    if solid_from_level.is_none() {
+        solid_color = None;
+    }
+    Ok((solid_from_level, solid_color))
+}
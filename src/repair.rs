@@ -0,0 +1,88 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::header::{DdsFlags, Header, HeaderFix, ParseOptions, RawHeader};
+use crate::{DdsInfo, DecodeError};
+
+/// Reads the header of a DDS file, fixes it, and writes the corrected header
+/// back in place.
+///
+/// The header is read with [`DdsInfo::read_with_options`] with
+/// [`ParseOptions::permissive`] enabled and the length of the file
+/// determined by seeking to the end of the file, so the same fixes as in
+/// [`Header::sanitized`] are applied. The written header is created with
+/// [`Header::to_raw`], which also corrects the flags, caps, and incorrect
+/// pitch or linear sizes. The `reserved1`, `caps3`, `caps4`, and `reserved2`
+/// fields are kept as is.
+///
+/// Only the header is written. The data section is not touched, and nothing
+/// is written if the header is already correct. The file must be positioned
+/// at the start of the DDS file. If this operation succeeds, the file will be
+/// positioned at the start of the data section.
+///
+/// Returns the fixes that were applied. Note that corrected flags and caps
+/// are not reported as fixes.
+///
+/// ```
+/// # use dds::{header::*, *};
+/// // a BC1 image without mipmaps, but the header claims 4 mipmaps
+/// let mut file = Vec::new();
+/// let mipmaps = std::num::NonZeroU32::new(4).unwrap();
+/// let header = Header::new_image(8, 8, Format::BC1_UNORM).with_mipmap_count(mipmaps);
+/// header.write(&mut file).unwrap();
+/// file.extend_from_slice(&[0; 32]);
+///
+/// let mut cursor = std::io::Cursor::new(&mut file);
+/// let fixes = repair_header(&mut cursor, &ParseOptions::default()).unwrap();
+/// assert_eq!(fixes, [HeaderFix::MipmapCount { declared: 4, fixed: 1 }]);
+///
+/// let info = DdsInfo::read(&mut file.as_slice()).unwrap();
+/// assert_eq!(info.header().mipmap_count().get(), 1);
+/// ```
+pub fn repair_header<F: Read + Write + Seek>(
+    file: &mut F,
+    options: &ParseOptions,
+) -> Result<Vec<HeaderFix>, DecodeError> {
+    let start = file.stream_position()?;
+    let end = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut options = options.clone();
+    options.permissive = true;
+    let mut file_len = end.saturating_sub(start);
+    if options.skip_magic_bytes {
+        file_len += Header::MAGIC.len() as u64;
+    }
+    options.file_len = Some(file_len);
+
+    let info = DdsInfo::read_with_options(file, &options)?;
+    let fixes = info.fixes().to_vec();
+
+    file.seek(SeekFrom::Start(start))?;
+    if !options.skip_magic_bytes {
+        Header::read_magic(file)?;
+    }
+    let raw_start = file.stream_position()?;
+    let raw = RawHeader::read(file)?;
+
+    let mut fixed = info.header().to_raw();
+    fixed.reserved1 = raw.reserved1;
+    fixed.caps3 = raw.caps3;
+    fixed.caps4 = raw.caps4;
+    fixed.reserved2 = raw.reserved2;
+    let pitch_fixed = fixes
+        .iter()
+        .any(|fix| matches!(fix, HeaderFix::PitchOrLinearSize { .. }));
+    let pitch_flags = DdsFlags::PITCH | DdsFlags::LINEAR_SIZE;
+    if !pitch_fixed && raw.flags.intersects(pitch_flags) {
+        // keep correct pitches, e.g. of files with padded rows
+        fixed.flags = (fixed.flags - pitch_flags) | (raw.flags & pitch_flags);
+        fixed.pitch_or_linear_size = raw.pitch_or_linear_size;
+    }
+
+    if fixed != raw {
+        file.seek(SeekFrom::Start(raw_start))?;
+        fixed.write(file)?;
+    }
+
+    Ok(fixes)
+}
@@ -0,0 +1,345 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::header::{
+    AlphaMode, Dx9PixelFormat, DxgiFormat, FourCC, Header, HeaderFix, ParseOptions, RawHeader,
+};
+use crate::{ColorFormat, DataLayout, DataRegion, DdsInfo, DecodeError, Format, Texture};
+
+/// A summary of everything there is to know about a DDS file without decoding
+/// its pixel data.
+///
+/// This combines the header, the detected format, the data layout, and any
+/// problems found while reading the file into a single structure. It is meant
+/// for tools that inspect DDS files (e.g. GUI inspectors or CLI tools).
+///
+/// With the `serde` feature enabled, this implements `serde::Serialize`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct DdsInfoReport {
+    /// The width of the level 0 object.
+    pub width: u32,
+    /// The height of the level 0 object.
+    pub height: u32,
+    /// The depth of the level 0 object. Only volume textures have a depth.
+    pub depth: Option<u32>,
+    /// The number of mipmap levels, including the level 0 object.
+    pub mipmap_count: u32,
+    /// The number of elements in a texture array. This is 1 for textures that
+    /// are not arrays. For cube maps, this is the number of cubes.
+    pub array_size: u32,
+    pub is_cube_map: bool,
+    pub is_volume: bool,
+
+    /// The detected format of the pixel data.
+    pub format: Format,
+    /// The native color format of [`Self::format`].
+    pub color: ColorFormat,
+    /// The DXGI format declared by the DX10 header extension, if any.
+    pub dxgi_format: Option<DxgiFormat>,
+    /// The FourCC code declared by the DX9 pixel format, if any.
+    pub four_cc: Option<FourCC>,
+    pub is_srgb: bool,
+    pub alpha_mode: AlphaMode,
+
+    /// The number of bytes before the data section, including the magic bytes.
+    pub header_len: u64,
+    /// The number of bytes of the data section as described by the header.
+    pub data_len: u64,
+    /// The number of textures (or volumes) in the data section.
+    ///
+    /// For texture arrays, each element is one texture of the array. For cube
+    /// maps, each element is one face. All elements have the same size and
+    /// mipmaps. Use [`Self::elements`] to iterate over them.
+    pub element_count: usize,
+    /// The number of bytes of each element, including all mipmaps.
+    pub element_len: u64,
+    /// The mipmaps of the first element.
+    ///
+    /// The mipmaps of all other elements have the same sizes and are offset
+    /// by a multiple of [`Self::element_len`]. See [`Self::elements`].
+    pub mipmaps: Vec<MipmapReport>,
+
+    /// Whether the header was invalid and had to be fixed to read the file.
+    ///
+    /// This is only possible if [`ParseOptions::permissive`] is enabled.
+    pub header_fixed: bool,
+    /// The fixes that were applied to the header.
+    ///
+    /// This may contain fixes (e.g. [`HeaderFix::PitchOrLinearSize`]) that
+    /// strict parsing would also tolerate, so it can be non-empty even if
+    /// [`Self::header_fixed`] is `false`. See [`DdsInfo::fixes`].
+    pub fixes: Vec<HeaderFix>,
+    /// Problems found while reading the file.
+    pub warnings: Vec<ReportWarning>,
+}
+
+/// A texture or volume in the data section of a DDS file.
+///
+/// See [`DdsInfoReport::elements`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ElementReport {
+    /// The byte offset of this element relative to the start of the data
+    /// section.
+    ///
+    /// Add [`DdsInfoReport::header_len`] to get the offset in the file.
+    pub offset: u64,
+    /// The number of bytes of this element, including all mipmaps.
+    pub len: u64,
+    pub mipmaps: Vec<MipmapReport>,
+}
+
+/// A single mipmap level of an [`ElementReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MipmapReport {
+    pub level: u8,
+    pub width: u32,
+    pub height: u32,
+    /// The number of depth slices. This is 1 for everything but volumes.
+    pub depth: u32,
+    /// The byte offset of this mipmap relative to the start of the data
+    /// section.
+    ///
+    /// Add [`DdsInfoReport::header_len`] to get the offset in the file.
+    pub offset: u64,
+    /// The number of bytes of this mipmap.
+    pub len: u64,
+}
+
+/// A problem found while reading a DDS file.
+///
+/// See [`DdsInfoReport::warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ReportWarning {
+    /// The FourCC code of the pixel format contradicts its bit masks.
+    ///
+    /// `used_masks` is whether the bit masks were used to detect the format.
+    AmbiguousPixelFormat { four_cc: FourCC, used_masks: bool },
+    /// The file is shorter than the data section described by the header.
+    TruncatedData { expected: u64, available: u64 },
+    /// The file contains the given number of bytes after the data section.
+    TrailingData(u64),
+}
+impl std::fmt::Display for ReportWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportWarning::AmbiguousPixelFormat {
+                four_cc,
+                used_masks,
+            } => {
+                let used = if *used_masks {
+                    "The bit masks were used"
+                } else {
+                    "The FourCC code was used"
+                };
+                write!(
+                    f,
+                    "The pixel format is ambiguous: {:?} contradicts the RGB bit masks. {}",
+                    four_cc, used
+                )
+            }
+            ReportWarning::TruncatedData {
+                expected,
+                available,
+            } => write!(
+                f,
+                "The data section is truncated: expected {} bytes, but only {} are present",
+                expected, available
+            ),
+            ReportWarning::TrailingData(len) => write!(
+                f,
+                "The file contains {} bytes of trailing data after the data section",
+                len
+            ),
+        }
+    }
+}
+
+impl DdsInfoReport {
+    /// Creates a report for the given info.
+    ///
+    /// Since the info doesn't know about the file it was read from, the
+    /// report will not contain any warnings and [`Self::header_fixed`] will
+    /// be `false`. Use
+    /// [`Self::from_reader`] to get a full report.
+    pub fn new(info: &DdsInfo) -> Self {
+        let header = info.header();
+        let format = info.format();
+        let layout = info.layout();
+
+        let (element_count, element_len, mipmaps) = describe_elements(&layout);
+
+        let (dxgi_format, four_cc) = match header {
+            Header::Dx9(dx9) => match dx9.pixel_format {
+                Dx9PixelFormat::FourCC(four_cc) => (None, Some(four_cc)),
+                Dx9PixelFormat::Mask(_) => (None, None),
+            },
+            Header::Dx10(dx10) => (Some(dx10.dxgi_format), None),
+        };
+
+        Self {
+            width: header.width(),
+            height: header.height(),
+            depth: header.depth(),
+            mipmap_count: header.mipmap_count().get(),
+            array_size: header.array_size(),
+            is_cube_map: header.is_cube_map(),
+            is_volume: header.is_volume(),
+            format,
+            color: format.color(),
+            dxgi_format,
+            four_cc,
+            is_srgb: header.is_srgb(),
+            alpha_mode: header.alpha_mode(),
+            header_len: (Header::MAGIC.len() + header.byte_len()) as u64,
+            data_len: layout.data_len(),
+            element_count,
+            element_len,
+            mipmaps,
+            header_fixed: false,
+            fixes: info.fixes().to_vec(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Reads the header of a DDS file and creates a report for it.
+    ///
+    /// In addition to [`Self::new`], this will detect whether the header had
+    /// to be fixed and whether the length of the file matches the length
+    /// described by the header. If [`ParseOptions::file_len`] is `None`, the
+    /// length of the file is determined by seeking to the end of the reader.
+    ///
+    /// If this operations succeeds, the given reader will be positioned at
+    /// the start of the data section.
+    pub fn from_reader<R: Read + Seek>(
+        reader: &mut R,
+        options: &ParseOptions,
+    ) -> Result<Self, DecodeError> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let mut options = options.clone();
+        if options.file_len.is_none() {
+            let mut file_len = end.saturating_sub(start);
+            if options.skip_magic_bytes {
+                file_len += Header::MAGIC.len() as u64;
+            }
+            options.file_len = Some(file_len);
+        }
+
+        if !options.skip_magic_bytes {
+            Header::read_magic(reader)?;
+        }
+        let raw = RawHeader::read(reader)?;
+        let (header, fixes) = Header::from_raw_with_fixes(&raw, &options)?;
+        let info = DdsInfo::new(header)?;
+
+        let mut report = Self::new(&info);
+        report.fixes = fixes;
+
+        if options.permissive {
+            let mut strict = options.clone();
+            strict.permissive = false;
+            report.header_fixed = Header::from_raw(&raw, &strict)
+                .map_or(true, |strict_header| &strict_header != info.header());
+        }
+
+        if let Some(masks) = raw.pixel_format.contradicting_masks() {
+            let used_masks = matches!(
+                info.header(),
+                Header::Dx9(dx9) if dx9.pixel_format == Dx9PixelFormat::Mask(masks)
+            );
+            report.warnings.push(ReportWarning::AmbiguousPixelFormat {
+                four_cc: raw.pixel_format.four_cc,
+                used_masks,
+            });
+        }
+
+        let file_len = options.file_len.unwrap_or(0);
+        let available = file_len.saturating_sub(report.header_len);
+        if available < report.data_len {
+            report.warnings.push(ReportWarning::TruncatedData {
+                expected: report.data_len,
+                available,
+            });
+        } else if available > report.data_len {
+            report
+                .warnings
+                .push(ReportWarning::TrailingData(available - report.data_len));
+        }
+
+        Ok(report)
+    }
+
+    /// Returns all textures (or volumes) of the data section in the order
+    /// they are stored.
+    ///
+    /// Elements are created on demand, since texture arrays can contain many
+    /// thousands of them.
+    pub fn elements(&self) -> impl Iterator<Item = ElementReport> + '_ {
+        (0..self.element_count).map(move |index| {
+            let offset = index as u64 * self.element_len;
+            ElementReport {
+                offset,
+                len: self.element_len,
+                mipmaps: self
+                    .mipmaps
+                    .iter()
+                    .map(|mipmap| MipmapReport {
+                        offset: offset + mipmap.offset,
+                        ..mipmap.clone()
+                    })
+                    .collect(),
+            }
+        })
+    }
+}
+
+/// Returns the number of elements, the length of each element, and the
+/// mipmaps of the first element.
+fn describe_elements(layout: &DataLayout) -> (usize, u64, Vec<MipmapReport>) {
+    fn texture_mipmaps(texture: Texture) -> Vec<MipmapReport> {
+        texture
+            .iter_mips()
+            .enumerate()
+            .map(|(level, surface)| MipmapReport {
+                level: level as u8,
+                width: surface.width(),
+                height: surface.height(),
+                depth: 1,
+                offset: surface.data_offset() - texture.data_offset(),
+                len: surface.data_len(),
+            })
+            .collect()
+    }
+
+    match layout {
+        DataLayout::Texture(texture) => (1, texture.data_len(), texture_mipmaps(*texture)),
+        DataLayout::Volume(volume) => {
+            let mipmaps = volume
+                .iter_mips()
+                .enumerate()
+                .map(|(level, mip)| MipmapReport {
+                    level: level as u8,
+                    width: mip.width(),
+                    height: mip.height(),
+                    depth: mip.depth(),
+                    offset: mip.data_offset() - volume.data_offset(),
+                    len: mip.data_len(),
+                })
+                .collect();
+            (1, volume.data_len(), mipmaps)
+        }
+        DataLayout::TextureArray(array) => match array.get(0) {
+            Some(first) => (array.len(), first.data_len(), texture_mipmaps(first)),
+            None => (0, 0, Vec::new()),
+        },
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{cast, ColorFormat, ImageView, Precision, ResizeFilter, Size};
+use crate::{cast, n32, ColorFormat, ImageView, Precision, ResizeFilter, Size};
 
 use resize::{Filter, Resizer};
 
@@ -18,6 +18,21 @@ impl Aligner {
         let bytes_per_pixel = color.bytes_per_pixel() as usize;
         debug_assert_eq!(size.pixels() as usize * bytes_per_pixel, data.len());
 
+        if color.precision == Precision::U32 {
+            // resize doesn't support u32, so U32 images are resized as F32
+            let src_slice = get_aligned_slice(&mut self.buffer, size, bytes_per_pixel);
+            for (from, to) in data.chunks_exact(4).zip(src_slice.chunks_exact_mut(4)) {
+                let value = u32::from_ne_bytes([from[0], from[1], from[2], from[3]]);
+                to.copy_from_slice(&n32::f32_exact(value).to_ne_bytes());
+            }
+            let color = ColorFormat::new(color.channels, Precision::F32);
+            return AlignedView {
+                view: src_slice,
+                size,
+                color,
+            };
+        }
+
         let view = if is_aligned(data, color.precision.size() as usize) {
             data
         } else {
@@ -36,6 +51,13 @@ pub(crate) struct AlignedView<'a> {
     size: Size,
     color: ColorFormat,
 }
+impl AlignedView<'_> {
+    /// The color format of the aligned data. This may differ from the color
+    /// format of the original image.
+    pub fn color(&self) -> ColorFormat {
+        self.color
+    }
+}
 
 pub(crate) struct ResizeState {
     dest_buffer: Vec<u8>,
@@ -176,7 +198,58 @@ fn to_resize_filter_type(filter: ResizeFilter) -> resize::Type {
         ResizeFilter::Triangle => resize::Type::Triangle,
         ResizeFilter::Mitchell => resize::Type::Mitchell,
         ResizeFilter::Lanczos3 => resize::Type::Lanczos3,
+        ResizeFilter::Kaiser { radius, alpha } => {
+            debug_assert!(filter.is_valid());
+            let kernel = move |x: f32| kaiser_kernel(x, radius, alpha);
+            resize::Type::Custom(Filter::new(Box::new(kernel), radius))
+        }
+        ResizeFilter::Custom(kernel) => {
+            debug_assert!(filter.is_valid());
+            resize::Type::Custom(Filter::new(Box::new(kernel.kernel()), kernel.support()))
+        }
+    }
+}
+
+/// A Kaiser-windowed sinc.
+fn kaiser_kernel(x: f32, radius: f32, alpha: f32) -> f32 {
+    fn sinc(x: f64) -> f64 {
+        if x == 0.0 {
+            1.0
+        } else {
+            let x = x * std::f64::consts::PI;
+            x.sin() / x
+        }
+    }
+    /// Zeroth-order modified Bessel function of the first kind.
+    fn bessel_i0(x: f64) -> f64 {
+        let half_x = x / 2.0;
+        let mut sum = 1.0;
+        let mut term = 1.0;
+        for k in 1..50 {
+            let t = half_x / k as f64;
+            term *= t * t;
+            sum += term;
+            if term < sum * 1e-12 {
+                break;
+            }
+        }
+        sum
+    }
+
+    if radius <= 0.0 {
+        return if x == 0.0 { 1.0 } else { 0.0 };
     }
+
+    let x = x as f64;
+    let radius = radius as f64;
+    let alpha = alpha as f64;
+
+    let t = x / radius;
+    if t.abs() > 1.0 {
+        return 0.0;
+    }
+    let window = bessel_i0(alpha * (1.0 - t * t).sqrt()) / bessel_i0(alpha);
+    (sinc(x) * window) as f32
 }
 
 use pixel::{Pixel, StraightAlpha};
@@ -438,7 +511,7 @@ mod pixel {
             if a == 0.0 {
                 return [0.0, 0.0, 0.0, 0.0];
             }
-            let a_r = 1.0 / a.recip();
+            let a_r = a.recip();
             [acc.x * a_r, acc.y * a_r, acc.z * a_r, a]
         }
     }
@@ -1,5 +1,6 @@
 use std::{io::Write, ops::Range};
 
+use crate::encode::{encode_with_carry, DitherCarry};
 use crate::{encode, Dithering, EncodeError, EncodeOptions, Format, ImageView, Size};
 
 /// This implements the main logic for splitting a surface into lines.
@@ -15,14 +16,6 @@ fn split_surface_into_lines(
     let support = format.encoding_support()?;
     let split_height = support.split_height()?;
 
-    // dithering destroys our ability to split the surface into lines,
-    // because it would create visible seams
-    if !support.local_dithering()
-        && options.dithering.intersect(support.dithering()) != Dithering::None
-    {
-        return None;
-    }
-
     let group_pixels = support
         .group_size()
         .get_group_pixels(options.quality)
@@ -123,6 +116,12 @@ impl<'a> SplitSurface<'a> {
     }
 
     /// Encodes a single fragment to the writer.
+    ///
+    /// Note that global error-diffusion dithering (see
+    /// [`crate::EncodingSupport::local_dithering`]) starts anew for each
+    /// fragment encoded with this method, which may reveal the seams between
+    /// fragments. Use [`SplitSurface::encode`] to get the same output as
+    /// encoding the whole surface at once.
     pub fn encode_fragment(
         &self,
         writer: &mut dyn Write,
@@ -134,12 +133,47 @@ impl<'a> SplitSurface<'a> {
     /// Encodes all fragments to the writer.
     ///
     /// This will encode the fragments in parallel (if the `rayon` feature is enabled).
+    ///
+    /// The output is the same as encoding the whole surface at once, even for
+    /// formats with global error-diffusion dithering. For those formats, the
+    /// dithering error carried from one fragment into the next is computed in
+    /// a sequential first pass before the fragments are encoded in parallel.
     pub fn encode(&self, writer: &mut dyn Write) -> Result<(), EncodeError> {
         self.encode_impl(writer)
     }
+
+    /// Whether the dithering error of one fragment is carried into the next.
+    fn carries_dither_error(&self) -> bool {
+        match self.format.encoding_support() {
+            Some(support) => {
+                !support.local_dithering()
+                    && self.options.dithering.intersect(support.dithering()) != Dithering::None
+            }
+            None => false,
+        }
+    }
+    /// Returns the dithering error carried into each fragment.
+    #[cfg(feature = "rayon")]
+    fn dither_carries(&self) -> Result<Vec<DitherCarry>, EncodeError> {
+        let mut carries = vec![DitherCarry::default(); self.fragments.len()];
+        if self.carries_dither_error() {
+            for i in 1..self.fragments.len() {
+                let mut carry = carries[i - 1].clone();
+                encode_with_carry(
+                    &mut std::io::sink(),
+                    self.fragments[i - 1],
+                    self.format,
+                    &self.options,
+                    &mut carry,
+                )?;
+                carries[i] = carry;
+            }
+        }
+        Ok(carries)
+    }
     #[cfg(feature = "rayon")]
     fn encode_impl(&self, writer: &mut dyn Write) -> Result<(), EncodeError> {
-        use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+        use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
         // optimization for single fragment
         if let Some(single) = self.single() {
@@ -148,10 +182,27 @@ impl<'a> SplitSurface<'a> {
 
         let pixel_info = crate::PixelInfo::from(self.format);
 
+        // all encoded fragments are kept in memory until they are written
+        let total_bytes = self
+            .fragments
+            .iter()
+            .map(|fragment| pixel_info.surface_bytes(fragment.size).unwrap_or(u64::MAX))
+            .fold(0_u64, u64::saturating_add);
+        let mut options = self.options.clone();
+        if options.reserve_memory(total_bytes).is_err() {
+            // encoding one fragment after another doesn't need the buffers
+            return self.encode_sequential(writer);
+        }
+        // fragments are encoded concurrently, so they share the remaining memory
+        options.memory_limit /= self.fragments.len();
+
+        let carries = self.dither_carries()?;
+
         let result: Result<Vec<Vec<u8>>, EncodeError> = self
             .fragments
             .par_iter()
-            .map(|fragment| -> Result<Vec<u8>, EncodeError> {
+            .zip(carries)
+            .map(|(fragment, mut carry)| -> Result<Vec<u8>, EncodeError> {
                 let bytes: usize = pixel_info
                     .surface_bytes(fragment.size)
                     .unwrap_or(u64::MAX)
@@ -159,7 +210,7 @@ impl<'a> SplitSurface<'a> {
                     .expect("too many bytes");
                 let mut buffer: Vec<u8> = Vec::with_capacity(bytes);
 
-                self.encode_fragment(&mut buffer, fragment)?;
+                encode_with_carry(&mut buffer, *fragment, self.format, &options, &mut carry)?;
 
                 debug_assert_eq!(buffer.len(), bytes);
                 Ok(buffer)
@@ -175,8 +226,16 @@ impl<'a> SplitSurface<'a> {
     }
     #[cfg(not(feature = "rayon"))]
     fn encode_impl(&self, writer: &mut dyn Write) -> Result<(), EncodeError> {
-        for fragment in &self.fragments {
-            self.encode_fragment(writer, fragment)?;
+        self.encode_sequential(writer)
+    }
+    fn encode_sequential(&self, writer: &mut dyn Write) -> Result<(), EncodeError> {
+        let mut carry = DitherCarry::default();
+        let carries = self.carries_dither_error();
+        for fragment in self.fragments.iter() {
+            if !carries {
+                carry = DitherCarry::default();
+            }
+            encode_with_carry(writer, *fragment, self.format, &self.options, &mut carry)?;
         }
         Ok(())
     }
@@ -196,12 +255,65 @@ pub fn split_encode(
     // Only actually split the surface when rayon is available.
     // If we don't get to encode in parallel, splitting is pure overhead.
     #[cfg(feature = "rayon")]
-    {
+    if options.parallel {
+        // check the entire image before any fragment is written
+        crate::encode::check_range(image, format, options)?;
+
         let split = SplitSurface::new(image, format, options);
-        split.encode(writer)
+        return split.encode(writer);
     }
-    #[cfg(not(feature = "rayon"))]
-    {
-        encode(writer, image, format, options)
+
+    encode(writer, image, format, options)
+}
+
+#[cfg(test)]
+mod test {
+    use super::SplitSurface;
+    use crate::*;
+
+    #[test]
+    fn split_dithering_is_seamless() {
+        let size = Size::new(13, 11);
+        let data: Vec<f32> = (0..size.pixels() * 4)
+            .map(|i| (i * 7919 % 1000) as f32 / 1000.0)
+            .collect();
+        let image = ImageView::new(data.as_slice(), size, ColorFormat::RGBA_F32).unwrap();
+
+        for format in [Format::B5G6R5_UNORM, Format::B4G4R4A4_UNORM] {
+            let options = EncodeOptions {
+                dithering: Dithering::ColorAndAlpha,
+                ..Default::default()
+            };
+
+            let mut expected = Vec::new();
+            encode(&mut expected, image, format, &options).unwrap();
+
+            // split into fragments with heights 3-5-1-2
+            let row_pitch = image.row_pitch();
+            let mut fragments = Vec::new();
+            let mut y = 0;
+            for height in [3, 5, 1, 2] {
+                let bytes = &data.as_bytes()[y * row_pitch..(y + height) * row_pitch];
+                let fragment_size = Size::new(size.width, height as u32);
+                fragments.push(ImageView::new(bytes, fragment_size, image.color()).unwrap());
+                y += height;
+            }
+            let split = SplitSurface {
+                fragments: fragments.into_boxed_slice(),
+                format,
+                options: options.clone(),
+            };
+
+            let mut actual = Vec::new();
+            split.encode(&mut actual).unwrap();
+            assert!(actual == expected, "Failed for {:?}", format);
+
+            // encoding fragments separately restarts the dithering
+            let mut separate = Vec::new();
+            for fragment in split.fragments() {
+                split.encode_fragment(&mut separate, fragment).unwrap();
+            }
+            assert!(separate != expected, "Failed for {:?}", format);
+        }
     }
 }
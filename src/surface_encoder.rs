@@ -0,0 +1,157 @@
+use std::io::Write;
+
+use crate::encode::{encode_with_carry, DitherCarry};
+use crate::{EncodeError, EncodeOptions, Format, ImageView, Size, SizeMultiple};
+
+/// An encoder for a single surface that accepts the image data in groups of
+/// rows and writes the encoded data incrementally.
+///
+/// This allows encoding images that don't fit into memory, e.g. by piping
+/// rows straight from another decoder. Only the rows of the current push
+/// need to be in memory.
+///
+/// Rows must be pushed from top to bottom. All pushes except the last must
+/// contain a multiple of [`EncodingSupport::split_height`] rows. Formats
+/// without a split height (e.g. `NV12`) require the whole surface in a single
+/// push. The encoded output is the same as encoding the whole surface at once
+/// with [`crate::encode()`], including global error-diffusion dithering.
+///
+/// Note that this only writes the encoded pixel data of a surface. Use
+/// [`crate::Encoder`] to write complete DDS files.
+///
+/// ```
+/// # use dds::*;
+/// let size = Size::new(16, 12);
+/// let mut encoder =
+///     SurfaceEncoder::new(Vec::new(), Format::BC1_UNORM, size, &EncodeOptions::default())
+///         .unwrap();
+/// let rows = vec![0_u8; 16 * 4 * 4];
+/// for _ in 0..3 {
+///     let rows = ImageView::new(rows.as_slice(), Size::new(16, 4), ColorFormat::RGBA_U8).unwrap();
+///     encoder.push_rows(rows).unwrap();
+/// }
+/// let encoded = encoder.finish().unwrap();
+/// assert_eq!(encoded.len(), 4 * 3 * 8);
+/// ```
+///
+/// [`EncodingSupport::split_height`]: crate::EncodingSupport::split_height
+pub struct SurfaceEncoder<W> {
+    writer: W,
+    format: Format,
+    size: Size,
+    options: EncodeOptions,
+    split_height: Option<u32>,
+    rows_written: u32,
+    carry: DitherCarry,
+}
+impl<W> SurfaceEncoder<W> {
+    /// Creates a new encoder for a surface of the given size.
+    ///
+    /// Returns an error if the format doesn't support encoding or if the size
+    /// isn't a multiple of the format's [`Format::size_multiple`] (unless
+    /// [`EncodeOptions::extend_edges`] is enabled).
+    pub fn new(
+        writer: W,
+        format: Format,
+        size: Size,
+        options: &EncodeOptions,
+    ) -> Result<Self, EncodeError>
+    where
+        W: Write,
+    {
+        let support = format
+            .encoding_support()
+            .ok_or(EncodeError::UnsupportedFormat(format))?;
+
+        let size_multiple = support.size_multiple();
+        if !options.extend_edges && !size.is_multiple_of(size_multiple) {
+            return Err(EncodeError::InvalidSize(size_multiple));
+        }
+
+        Ok(Self {
+            writer,
+            format,
+            size,
+            options: options.clone(),
+            split_height: support.split_height().map(|h| h.get() as u32),
+            rows_written: 0,
+            carry: DitherCarry::default(),
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+    /// The size of the whole surface.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+    /// The number of rows that have been encoded so far.
+    pub fn rows_written(&self) -> u32 {
+        self.rows_written
+    }
+    /// The number of rows that still need to be pushed.
+    pub fn rows_remaining(&self) -> u32 {
+        self.size.height - self.rows_written
+    }
+
+    /// Encodes the given rows and writes them to the writer.
+    ///
+    /// The width of `rows` must be the width of the surface, and its height
+    /// must not exceed [`SurfaceEncoder::rows_remaining`]. Unless this push
+    /// completes the surface, its height must be a multiple of the split
+    /// height of the format.
+    ///
+    /// If [`EncodeOptions::reject_out_of_range`] is enabled, only the pushed
+    /// rows are checked. Previously pushed rows will have already been
+    /// written in case of an error.
+    pub fn push_rows(&mut self, rows: ImageView) -> Result<(), EncodeError>
+    where
+        W: Write,
+    {
+        if rows.width() != self.size.width || rows.height() > self.rows_remaining() {
+            return Err(EncodeError::UnexpectedSurfaceSize);
+        }
+        if rows.height() == 0 {
+            return Ok(());
+        }
+
+        let completes_surface = rows.height() == self.rows_remaining();
+        if !completes_surface {
+            match self.split_height {
+                Some(split_height) => {
+                    if rows.height() % split_height != 0 {
+                        return Err(EncodeError::InvalidSize(SizeMultiple::new(
+                            1,
+                            split_height as u8,
+                        )));
+                    }
+                }
+                None => return Err(EncodeError::UnexpectedSurfaceSize),
+            }
+        }
+
+        encode_with_carry(
+            &mut self.writer,
+            rows,
+            self.format,
+            &self.options,
+            &mut self.carry,
+        )?;
+        self.rows_written += rows.height();
+        Ok(())
+    }
+
+    /// Verifies that all rows have been pushed, flushes the writer, and
+    /// returns it.
+    pub fn finish(mut self) -> Result<W, EncodeError>
+    where
+        W: Write,
+    {
+        if self.rows_remaining() > 0 {
+            return Err(EncodeError::MissingRows);
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
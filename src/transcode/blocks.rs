@@ -0,0 +1,244 @@
+//! Direct conversions between related block-compressed formats.
+//!
+//! These reuse the endpoints and indices of the source blocks instead of
+//! re-optimizing them. Blocks that can't be represented in the target format
+//! this way are decoded and encoded again individually.
+
+use crate::decode::bc_blocks::{bc1_u8_rgba, bc3_u8_rgba, bc4u_gray};
+use crate::{encode_bc3_block, encode_bc7_block, EncodeOptions, Format, B5G6R5};
+
+/// Converts the encoded data of a whole surface from one format to another.
+pub(crate) type SurfaceConversion = fn(&[u8], &EncodeOptions) -> Vec<u8>;
+
+/// Returns the block conversion from `source` to `target`, if any.
+pub(crate) fn get_block_conversion(source: Format, target: Format) -> Option<SurfaceConversion> {
+    match (source, target) {
+        (Format::BC1_UNORM, Format::BC3_UNORM) => {
+            Some(|data, options| convert_blocks::<8, 16>(data, |block| bc1_to_bc3(block, options)))
+        }
+        (Format::BC3_UNORM, Format::BC7_UNORM) => {
+            Some(|data, options| convert_blocks::<16, 16>(data, |block| bc3_to_bc7(block, options)))
+        }
+        _ => None,
+    }
+}
+
+fn convert_blocks<const I: usize, const O: usize>(
+    data: &[u8],
+    convert: impl Fn([u8; I]) -> [u8; O],
+) -> Vec<u8> {
+    debug_assert!(data.len() % I == 0);
+    let mut output = Vec::with_capacity(data.len() / I * O);
+    for block in data.chunks_exact(I) {
+        output.extend_from_slice(&convert(block.try_into().unwrap()));
+    }
+    output
+}
+
+/// Combines 2 BC4 blocks into a BC5 block.
+pub(crate) fn merge_bc4_blocks(red: &[u8], green: &[u8]) -> Vec<u8> {
+    debug_assert!(red.len() == green.len() && red.len() % 8 == 0);
+    let mut output = Vec::with_capacity(red.len() * 2);
+    for (red, green) in red.chunks_exact(8).zip(green.chunks_exact(8)) {
+        output.extend_from_slice(red);
+        output.extend_from_slice(green);
+    }
+    output
+}
+
+/// The color endpoints and 2-bit indices of a BC1 color block.
+#[derive(Clone, Copy)]
+struct ColorBlock {
+    color0: u16,
+    color1: u16,
+    indexes: [u8; 16],
+}
+impl ColorBlock {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let color0 = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let color1 = u16::from_le_bytes([bytes[2], bytes[3]]);
+        let bits = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let indexes = std::array::from_fn(|i| ((bits >> (i * 2)) & 0b11) as u8);
+        Self {
+            color0,
+            color1,
+            indexes,
+        }
+    }
+    fn to_bytes(self) -> [u8; 8] {
+        let mut bits = 0_u32;
+        for (i, &index) in self.indexes.iter().enumerate() {
+            bits |= (index as u32) << (i * 2);
+        }
+        let [c0, c1] = [self.color0.to_le_bytes(), self.color1.to_le_bytes()];
+        let [i0, i1, i2, i3] = bits.to_le_bytes();
+        [c0[0], c0[1], c1[0], c1[1], i0, i1, i2, i3]
+    }
+
+    fn is_four_color(&self) -> bool {
+        self.color0 > self.color1
+    }
+    fn uses(&self, index: u8) -> bool {
+        self.indexes.contains(&index)
+    }
+
+    /// Rewrites the block to use the 4-color mode without changing its
+    /// pixels. This is impossible if the block uses the midpoint or
+    /// transparent color of the 3-color mode.
+    fn to_four_color(self) -> Option<Self> {
+        if self.is_four_color() {
+            return Some(self);
+        }
+        if self.uses(3) {
+            return None;
+        }
+
+        if self.color0 == self.color1 {
+            // all pixels have the same color
+            return Some(match self.color0.checked_add(1) {
+                Some(color0) => Self {
+                    color0,
+                    color1: self.color0,
+                    indexes: [1; 16],
+                },
+                None => Self {
+                    color0: self.color0,
+                    color1: self.color0 - 1,
+                    indexes: [0; 16],
+                },
+            });
+        }
+        if self.uses(2) {
+            return None;
+        }
+        // only the endpoints are used, so they can be swapped
+        Some(Self {
+            color0: self.color1,
+            color1: self.color0,
+            indexes: self.indexes.map(|i| i ^ 1),
+        })
+    }
+}
+
+/// An alpha block of BC3 that sets all pixels to 255.
+const OPAQUE_ALPHA_BLOCK: [u8; 8] = [255, 255, 0, 0, 0, 0, 0, 0];
+
+fn bc1_to_bc3(block: [u8; 8], options: &EncodeOptions) -> [u8; 16] {
+    // BC3 color blocks don't support the 3-color mode of BC1
+    match ColorBlock::from_bytes(&block).to_four_color() {
+        Some(color) => concat(OPAQUE_ALPHA_BLOCK, color.to_bytes()),
+        None => encode_bc3_block(&bc1_u8_rgba(block), options),
+    }
+}
+
+fn bc3_to_bc7(block: [u8; 16], options: &EncodeOptions) -> [u8; 16] {
+    let color = ColorBlock::from_bytes(&block[8..]);
+    let color = match color.to_four_color() {
+        Some(color) => color,
+        None => return encode_bc7_block(&bc3_u8_rgba(block), options),
+    };
+
+    // BC7 mode 5 stores 7-bit RGB endpoints with 2-bit indices, which closely
+    // matches the 4-color mode of BC1. Its 2-bit alpha indices are fitted to
+    // the decoded alpha values.
+    let to_rgb7 = |color: u16| {
+        B5G6R5::from_u16(color)
+            .to_n8()
+            .map(|v| ((v as u16 * 127 + 127) / 255) as u8)
+    };
+    let mut color_endpoints = [to_rgb7(color.color0), to_rgb7(color.color1)];
+    // BC1 orders its palette as [c0, c1, 2/3 c0 + 1/3 c1, 1/3 c0 + 2/3 c1]
+    let mut color_indexes = color.indexes.map(|i| [0, 3, 1, 2][i as usize]);
+
+    let alpha: [u8; 16] = bc4u_gray::<u8>(block[..8].try_into().unwrap()).map(|[a]| a);
+    let min = alpha.iter().copied().min().unwrap_or(0);
+    let max = alpha.iter().copied().max().unwrap_or(0);
+    let mut alpha_endpoints = [min, max];
+    let alpha_palette = [0, 1, 2, 3].map(|i| interpolate_bc7(min, max, i));
+    let mut alpha_indexes = alpha.map(|a| {
+        let distance = |i: usize| (alpha_palette[i] as i16 - a as i16).abs();
+        (0..4).min_by_key(|&i| distance(i)).unwrap_or(0) as u8
+    });
+
+    // the index of the first pixel must have its most significant bit unset
+    if color_indexes[0] >= 2 {
+        color_endpoints.swap(0, 1);
+        color_indexes = color_indexes.map(|i| 3 - i);
+    }
+    if alpha_indexes[0] >= 2 {
+        alpha_endpoints.swap(0, 1);
+        alpha_indexes = alpha_indexes.map(|i| 3 - i);
+    }
+
+    let mut writer = BitWriter::default();
+    writer.write(0b10_0000, 6); // mode 5
+    writer.write(0, 2); // no rotation
+    for channel in 0..3 {
+        for endpoint in color_endpoints {
+            writer.write(endpoint[channel] as u128, 7);
+        }
+    }
+    for endpoint in alpha_endpoints {
+        writer.write(endpoint as u128, 8);
+    }
+    for indexes in [color_indexes, alpha_indexes] {
+        writer.write(indexes[0] as u128, 1);
+        for &index in &indexes[1..] {
+            writer.write(index as u128, 2);
+        }
+    }
+    debug_assert_eq!(writer.len, 128);
+    writer.bits.to_le_bytes()
+}
+
+/// Interpolates between 2 8-bit values using the 2-bit weights of BC7.
+fn interpolate_bc7(e0: u8, e1: u8, index: usize) -> u8 {
+    const WEIGHTS: [u16; 4] = [0, 21, 43, 64];
+    let w = WEIGHTS[index];
+    (((64 - w) * e0 as u16 + w * e1 as u16 + 32) >> 6) as u8
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bits: u128,
+    len: u32,
+}
+impl BitWriter {
+    fn write(&mut self, value: u128, bits: u32) {
+        debug_assert!(value < 1 << bits);
+        self.bits |= value << self.len;
+        self.len += bits;
+    }
+}
+
+fn concat(a: [u8; 8], b: [u8; 8]) -> [u8; 16] {
+    let mut result = [0; 16];
+    result[..8].copy_from_slice(&a);
+    result[8..].copy_from_slice(&b);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bc1_to_bc3_is_lossless_for_opaque_blocks() {
+        let options = EncodeOptions::default();
+        let blocks: [[u8; 8]; 4] = [
+            // 4-color mode
+            [0x00, 0xF8, 0x1F, 0x00, 0xE4, 0x1B, 0x4E, 0xB1],
+            // single color in 3-color mode
+            [0x1F, 0x00, 0x1F, 0x00, 0x00, 0x55, 0xAA, 0x00],
+            // only endpoints in 3-color mode
+            [0x1F, 0x00, 0x00, 0xF8, 0x11, 0x44, 0x10, 0x01],
+            // black in 3-color mode
+            [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        ];
+        for block in blocks {
+            let bc3 = bc1_to_bc3(block, &options);
+            assert_eq!(bc3_u8_rgba(bc3), bc1_u8_rgba(block), "{:?}", block);
+            assert!(ColorBlock::from_bytes(&bc3[8..]).is_four_color());
+        }
+    }
+}
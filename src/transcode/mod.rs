@@ -0,0 +1,245 @@
+use std::io::{Read, Write};
+
+use crate::{
+    DataLayout, DecodeError, Decoder, Encoder, Format, ImageView, ImageViewMut, MipOrder,
+    TranscodeError, WriteOptions,
+};
+
+mod blocks;
+
+use blocks::{get_block_conversion, merge_bc4_blocks};
+
+#[derive(Debug, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct TranscodeOptions {
+    /// The options used to write re-encoded surfaces.
+    ///
+    /// These determine how mipmaps are generated if the encoder has more
+    /// mipmaps than the decoder. [`WriteOptions::generate_mipmaps`] is
+    /// ignored.
+    ///
+    /// Default: `WriteOptions::default()`
+    pub write_options: WriteOptions,
+    /// Whether to convert block-compressed surfaces directly between related
+    /// formats by reusing the endpoints and indices of their blocks.
+    ///
+    /// This is drastically faster than decoding and re-encoding, but the
+    /// resulting quality depends on the quality of the source blocks instead
+    /// of [`crate::EncodeOptions::quality`]. Supported conversions are:
+    ///
+    /// - `BC1_UNORM` → `BC3_UNORM`: Lossless, unless a block uses the 3-color
+    ///   mode of BC1 in a way that BC3 doesn't support. Those blocks are
+    ///   re-encoded.
+    /// - `BC3_UNORM` → `BC7_UNORM`: Colors are stored with BC7 mode 5, which
+    ///   slightly increases the precision of color endpoints. Alpha values
+    ///   are reduced to 4 levels per block. Blocks using the 3-color mode
+    ///   are re-encoded.
+    ///
+    /// Other combinations of formats are always decoded and re-encoded.
+    /// Surfaces with generated mipmaps are also always re-encoded.
+    ///
+    /// Default: `false`
+    pub reuse_blocks: bool,
+}
+
+/// Copies all surfaces from the decoder to the encoder, converting them to
+/// the format of the encoder.
+///
+/// The decoder and encoder must be positioned at their first surface. Both
+/// data layouts must be compatible (see [`TranscodeError::IncompatibleLayout`]).
+/// The options of the decoder ([`Decoder::options`]) and encoder
+/// ([`Encoder::options`]) are used for decoding and encoding respectively.
+///
+/// ## Pass-through
+///
+/// If the decoder and encoder use the same format, the encoded data of each
+/// surface is copied verbatim instead of being decoded and re-encoded. This
+/// is lossless and much faster, which makes it cheap to e.g. change header
+/// fields or drop mipmaps. Surfaces whose rows are padded (see
+/// [`DdsInfo::with_row_alignment`](crate::DdsInfo::with_row_alignment)) are
+/// always decoded and re-encoded.
+///
+/// ## Mipmaps
+///
+/// The number of mipmaps of the decoder and encoder may differ. Mipmaps the
+/// encoder doesn't have are discarded. If the encoder has more mipmaps than
+/// the decoder, all mipmaps of the encoder are generated from the level 0
+/// surface using [`TranscodeOptions::write_options`]. This is not supported
+/// for volume textures.
+///
+/// ```no_run
+/// # use dds::{*, header::*};
+/// // drop all mipmaps of a BC7 file without re-encoding it
+/// let file = std::fs::File::open("example.dds").unwrap();
+/// let mut decoder = Decoder::new(file).unwrap();
+/// let size = decoder.main_size();
+/// let header = Header::new_image(size.width, size.height, decoder.format());
+///
+/// let output = std::fs::File::create("no-mipmaps.dds").unwrap();
+/// let mut encoder = Encoder::new(output, decoder.format(), &header).unwrap();
+/// transcode(&mut decoder, &mut encoder, &TranscodeOptions::default()).unwrap();
+/// encoder.finish().unwrap();
+/// ```
+pub fn transcode<R: Read, W: Write>(
+    decoder: &mut Decoder<R>,
+    encoder: &mut Encoder<W>,
+    options: &TranscodeOptions,
+) -> Result<(), TranscodeError> {
+    let source = decoder.layout();
+    let target = encoder.layout();
+    if !is_compatible(&source, &target) || decoder.info().mip_order() != MipOrder::LargestFirst {
+        return Err(TranscodeError::IncompatibleLayout);
+    }
+    let source_mipmaps = mipmaps(&source);
+    let target_mipmaps = mipmaps(&target);
+    let generate_mipmaps = target_mipmaps > source_mipmaps;
+    if generate_mipmaps && target.volume().is_some() {
+        return Err(TranscodeError::IncompatibleLayout);
+    }
+
+    let pass_through = decoder.format() == encoder.format();
+    let block_conversion =
+        get_block_conversion(decoder.format(), encoder.format()).filter(|_| options.reuse_blocks);
+    let color = decoder.native_color();
+    let mut buffer: Vec<u8> = Vec::new();
+
+    while let Some(current) = encoder.surface_info() {
+        let size = current.size();
+        discard_mipmaps_from(decoder, target_mipmaps)?;
+
+        if pass_through && !generate_mipmaps && decoder.is_surface_tightly_packed() {
+            decoder.with_surface_reader(|reader| {
+                encoder
+                    .copy_encoded_surface(reader)
+                    .map_err(TranscodeError::from)
+            })?;
+            continue;
+        }
+        if let Some(convert) = block_conversion.filter(|_| !generate_mipmaps) {
+            if decoder.is_surface_tightly_packed() {
+                let data = read_encoded_surface(decoder)?;
+                let converted = convert(&data, &encoder.options);
+                encoder.copy_encoded_surface(&mut converted.as_slice())?;
+                continue;
+            }
+        }
+
+        buffer.resize(color.buffer_size(size).expect("surface too large"), 0);
+        let image = ImageViewMut::new(buffer.as_mut_slice(), size, color).expect("invalid buffer");
+        decoder.read_surface(image)?;
+
+        let image = ImageView::new(buffer.as_slice(), size, color).expect("invalid buffer");
+        if generate_mipmaps {
+            let options = WriteOptions {
+                generate_mipmaps: true,
+                ..options.write_options
+            };
+            encoder.write_surface_with(image, |_| {}, &options)?;
+            // the encoder generated all of its mipmaps, so the decoder's
+            // mipmaps of this texture are not needed
+            discard_mipmaps_from(decoder, 1)?;
+        } else {
+            encoder.write_surface(image)?;
+        }
+    }
+
+    discard_mipmaps_from(decoder, target_mipmaps)?;
+
+    Ok(())
+}
+
+/// Combines 2 `BC4` textures into a single `BC5` texture without decoding
+/// them.
+///
+/// The blocks of `red` and `green` are copied verbatim into the red and green
+/// channels of the `BC5` blocks, so this conversion is lossless. This is
+/// commonly used to pack separately authored channels (e.g. the X and Y of a
+/// normal map) into one texture.
+///
+/// The formats must be either `BC4_UNORM` and `BC5_UNORM` or `BC4_SNORM` and
+/// `BC5_SNORM`. Otherwise, [`TranscodeError::IncompatibleFormats`] is
+/// returned. Both decoders must have the same data layout as the encoder
+/// (including the number of mipmaps), must store mipmaps largest first, and
+/// must not pad rows.
+pub fn merge_bc4_into_bc5<R1: Read, R2: Read, W: Write>(
+    red: &mut Decoder<R1>,
+    green: &mut Decoder<R2>,
+    encoder: &mut Encoder<W>,
+) -> Result<(), TranscodeError> {
+    let (bc4, bc5) = match encoder.format() {
+        Format::BC5_UNORM => (Format::BC4_UNORM, Format::BC5_UNORM),
+        Format::BC5_SNORM => (Format::BC4_SNORM, Format::BC5_SNORM),
+        _ => return Err(TranscodeError::IncompatibleFormats),
+    };
+    if red.format() != bc4 || green.format() != bc4 || encoder.format() != bc5 {
+        return Err(TranscodeError::IncompatibleFormats);
+    }
+
+    let layout = encoder.layout();
+    let same_layout =
+        |other: DataLayout| is_compatible(&other, &layout) && mipmaps(&other) == mipmaps(&layout);
+    if !same_layout(red.layout())
+        || !same_layout(green.layout())
+        || red.info().mip_order() != MipOrder::LargestFirst
+        || green.info().mip_order() != MipOrder::LargestFirst
+    {
+        return Err(TranscodeError::IncompatibleLayout);
+    }
+
+    while encoder.surface_info().is_some() {
+        if !red.is_surface_tightly_packed() || !green.is_surface_tightly_packed() {
+            return Err(TranscodeError::IncompatibleLayout);
+        }
+        let red_data = read_encoded_surface(red)?;
+        let green_data = read_encoded_surface(green)?;
+        let merged = merge_bc4_blocks(&red_data, &green_data);
+        encoder.copy_encoded_surface(&mut merged.as_slice())?;
+    }
+
+    Ok(())
+}
+
+/// Reads the encoded data of the current surface as is.
+fn read_encoded_surface<R: Read>(decoder: &mut Decoder<R>) -> Result<Vec<u8>, DecodeError> {
+    let len = decoder.surface_info().map_or(0, |s| s.data_len());
+    decoder.with_surface_reader(|reader| {
+        let mut data = vec![0; len as usize];
+        reader.read_exact(&mut data)?;
+        Ok(data)
+    })
+}
+
+/// Discards all surfaces of the decoder with a mipmap level of at least
+/// `level` until the next surface with a lower level (or the end).
+fn discard_mipmaps_from<R: Read>(decoder: &mut Decoder<R>, level: u8) -> Result<(), DecodeError> {
+    while decoder
+        .surface_info()
+        .map_or(false, |s| s.mipmap_level() >= level)
+    {
+        decoder.discard_surface()?;
+    }
+    Ok(())
+}
+
+fn is_compatible(a: &DataLayout, b: &DataLayout) -> bool {
+    if a.main_size() != b.main_size() {
+        return false;
+    }
+
+    match (a, b) {
+        (DataLayout::Texture(_), DataLayout::Texture(_)) => true,
+        (DataLayout::Volume(a), DataLayout::Volume(b)) => a.main().depth() == b.main().depth(),
+        (DataLayout::TextureArray(a), DataLayout::TextureArray(b)) => {
+            a.kind() == b.kind() && a.len() == b.len()
+        }
+        _ => false,
+    }
+}
+
+fn mipmaps(layout: &DataLayout) -> u8 {
+    match layout {
+        DataLayout::Texture(texture) => texture.mipmaps(),
+        DataLayout::Volume(volume) => volume.mipmaps(),
+        DataLayout::TextureArray(array) => array.first().mipmaps(),
+    }
+}
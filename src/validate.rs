@@ -0,0 +1,451 @@
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+use crate::header::{
+    Caps, Caps2, DdsFlags, Header, MiscFlags, ParseOptions, RawHeader, ResourceDimension,
+};
+use crate::{DataLayout, DataRegion, Format, HeaderError};
+
+/// How severe a [`ValidationIssue`] is.
+///
+/// Severities are ordered, so `Severity::Info < Severity::Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file is valid, but does something unusual.
+    Info,
+    /// The file violates the DDS specification, but can still be read
+    /// correctly by most readers (including this crate).
+    Warning,
+    /// The file is invalid and cannot be read (correctly).
+    Error,
+}
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The kind of problem a [`ValidationIssue`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IssueKind {
+    /// The file doesn't start with the magic bytes `'DDS '`.
+    InvalidMagic,
+    /// The file ended before the header was fully read.
+    UnexpectedEndOfFile,
+    /// Reading the file failed for a reason other than reaching its end.
+    Io,
+    /// The `size` field of the header is not 124.
+    InvalidHeaderSize,
+    /// The `size` field of the pixel format is not 32.
+    InvalidPixelFormatSize,
+    /// One of the flags of [`DdsFlags::REQUIRED`] is missing.
+    MissingRequiredFlag,
+    /// [`DdsFlags::MIPMAP_COUNT`] doesn't agree with the mipmap count.
+    MipmapCountFlag,
+    /// [`DdsFlags::DEPTH`] doesn't agree with [`Caps2::VOLUME`].
+    DepthFlag,
+    /// The caps don't describe the surfaces of the file.
+    MissingCaps,
+    /// The cube map face flags are incomplete or set without
+    /// [`Caps2::CUBE_MAP`].
+    CubeMapFaces,
+    /// The pitch or linear size is missing, incorrect, or declared with the
+    /// wrong flag.
+    IncorrectPitch,
+    /// The array size of the DX10 header extension is invalid.
+    ArraySize,
+    /// The FourCC code of the pixel format contradicts its bit masks.
+    AmbiguousPixelFormat,
+    /// The header cannot be parsed in strict mode.
+    InvalidHeader,
+    /// The pixel format of the header is not supported.
+    UnsupportedFormat,
+    /// The data section is shorter than described by the header.
+    DataTooShort,
+    /// There is data after the end of the data section.
+    TrailingData,
+}
+
+/// A single problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+impl ValidationIssue {
+    fn new(severity: Severity, kind: IssueKind, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            kind,
+            message: message.into(),
+        }
+    }
+}
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Checks the given DDS file for problems.
+///
+/// Unlike [`Header::read`], this doesn't stop at the first problem and also
+/// reports problems that readers commonly tolerate: flags that contradict
+/// header fields, missing or inconsistent caps, incorrect pitch or linear
+/// sizes, incomplete cube map face flags, and data sections that don't match
+/// the length described by the header.
+///
+/// Only the options for magic bytes and limits of `options` are used. Both
+/// [`ParseOptions::permissive`] and [`ParseOptions::file_len`] are ignored;
+/// the length of the file is determined by seeking to the end of the reader.
+///
+/// Returns an empty list if no problems were found. Issues are ordered
+/// roughly in the order of the fields they concern. After this returns, the
+/// position of the reader is unspecified.
+///
+/// ```
+/// # use dds::{header::*, *};
+/// let header = Header::new_image(4, 4, Format::BC1_UNORM);
+/// let mut file = Vec::new();
+/// header.write(&mut file).unwrap();
+/// file.extend_from_slice(&[0; 4]); // BC1 needs 8 bytes for 4x4 pixels
+///
+/// let issues = validate(&mut std::io::Cursor::new(&file), &ParseOptions::default());
+/// assert_eq!(issues.len(), 1);
+/// assert_eq!(issues[0].kind, IssueKind::DataTooShort);
+/// assert_eq!(issues[0].severity, Severity::Error);
+/// ```
+pub fn validate<R: Read + Seek>(reader: &mut R, options: &ParseOptions) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    if let Err(error) = validate_impl(reader, options, &mut issues) {
+        issues.push(if error.kind() == ErrorKind::UnexpectedEof {
+            ValidationIssue::new(
+                Severity::Error,
+                IssueKind::UnexpectedEndOfFile,
+                "The file ended before the header was fully read",
+            )
+        } else {
+            ValidationIssue::new(
+                Severity::Error,
+                IssueKind::Io,
+                format!("Failed to read the file: {}", error),
+            )
+        });
+    }
+    issues
+}
+
+fn validate_impl<R: Read + Seek>(
+    reader: &mut R,
+    options: &ParseOptions,
+    issues: &mut Vec<ValidationIssue>,
+) -> std::io::Result<()> {
+    let mut push = |severity, kind, message: String| {
+        issues.push(ValidationIssue::new(severity, kind, message));
+    };
+
+    let start = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
+    let mut file_len = end.saturating_sub(start);
+    if options.skip_magic_bytes {
+        file_len += Header::MAGIC.len() as u64;
+    }
+
+    if !options.skip_magic_bytes {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != Header::MAGIC {
+            push(
+                Severity::Error,
+                IssueKind::InvalidMagic,
+                format!("Invalid magic bytes {:?}, expected 'DDS '", magic),
+            );
+            return Ok(());
+        }
+    }
+    let raw = RawHeader::read(reader)?;
+
+    // structure sizes
+    if raw.size != RawHeader::SIZE {
+        push(
+            Severity::Error,
+            IssueKind::InvalidHeaderSize,
+            format!("The header size is {}, expected 124", raw.size),
+        );
+    }
+    if raw.pixel_format.size != 32 {
+        push(
+            Severity::Error,
+            IssueKind::InvalidPixelFormatSize,
+            format!(
+                "The pixel format size is {}, expected 32",
+                raw.pixel_format.size
+            ),
+        );
+    }
+
+    // flags vs fields
+    let missing = DdsFlags::REQUIRED.difference(raw.flags);
+    if !missing.is_empty() {
+        push(
+            Severity::Warning,
+            IssueKind::MissingRequiredFlag,
+            format!("The required flags {:?} are not set", missing),
+        );
+    }
+    let has_mipmap_flag = raw.flags.contains(DdsFlags::MIPMAP_COUNT);
+    if !has_mipmap_flag && raw.mipmap_count > 1 {
+        push(
+            Severity::Warning,
+            IssueKind::MipmapCountFlag,
+            format!(
+                "The mipmap count is {}, but DDSD_MIPMAPCOUNT is not set",
+                raw.mipmap_count
+            ),
+        );
+    }
+    if has_mipmap_flag && raw.mipmap_count == 0 {
+        push(
+            Severity::Warning,
+            IssueKind::MipmapCountFlag,
+            "DDSD_MIPMAPCOUNT is set, but the mipmap count is 0".to_string(),
+        );
+    }
+    let has_depth_flag = raw.flags.contains(DdsFlags::DEPTH);
+    let has_volume_caps = raw.caps2.contains(Caps2::VOLUME);
+    if has_depth_flag != has_volume_caps {
+        let message = if has_depth_flag {
+            "DDSD_DEPTH is set, but DDSCAPS2_VOLUME is not"
+        } else {
+            "DDSCAPS2_VOLUME is set, but DDSD_DEPTH is not. The file will be read as a 2D texture"
+        };
+        push(Severity::Warning, IssueKind::DepthFlag, message.to_string());
+    }
+
+    // caps vs layout
+    if !raw.caps.contains(Caps::TEXTURE) {
+        push(
+            Severity::Warning,
+            IssueKind::MissingCaps,
+            "DDSCAPS_TEXTURE is not set".to_string(),
+        );
+    }
+    if raw.mipmap_count > 1 {
+        let missing = (Caps::COMPLEX | Caps::MIPMAP).difference(raw.caps);
+        if !missing.is_empty() {
+            push(
+                Severity::Warning,
+                IssueKind::MissingCaps,
+                format!("The file contains mipmaps, but {:?} is not set", missing),
+            );
+        }
+    }
+
+    // cube map faces
+    let is_cube_map = raw.caps2.contains(Caps2::CUBE_MAP);
+    let faces = raw.caps2 & Caps2::CUBE_MAP_ALL_FACES;
+    if is_cube_map && faces != Caps2::CUBE_MAP_ALL_FACES {
+        if faces.is_empty() {
+            push(
+                Severity::Warning,
+                IssueKind::CubeMapFaces,
+                "DDSCAPS2_CUBEMAP is set, but no faces are declared".to_string(),
+            );
+        } else {
+            push(
+                Severity::Info,
+                IssueKind::CubeMapFaces,
+                format!(
+                    "The cube map is partial and only contains the faces {:?}",
+                    faces
+                ),
+            );
+        }
+    }
+    if !is_cube_map && !faces.is_empty() {
+        push(
+            Severity::Warning,
+            IssueKind::CubeMapFaces,
+            "Cube map faces are declared, but DDSCAPS2_CUBEMAP is not set".to_string(),
+        );
+    }
+
+    // DX10 array size
+    if let Some(dx10) = &raw.dx10 {
+        let is_3d = ResourceDimension::try_from(dx10.resource_dimension)
+            == Ok(ResourceDimension::Texture3D);
+        if dx10.array_size == 0 {
+            push(
+                Severity::Warning,
+                IssueKind::ArraySize,
+                "The array size is 0, expected at least 1".to_string(),
+            );
+        } else if is_3d && dx10.array_size != 1 {
+            push(
+                Severity::Warning,
+                IssueKind::ArraySize,
+                format!(
+                    "The array size of a 3D texture is {}, expected 1",
+                    dx10.array_size
+                ),
+            );
+        }
+        if is_cube_map != dx10.misc_flag.contains(MiscFlags::TEXTURE_CUBE) && !is_3d {
+            push(
+                Severity::Info,
+                IssueKind::CubeMapFaces,
+                "DDSCAPS2_CUBEMAP and D3D10_RESOURCE_MISC_TEXTURECUBE disagree".to_string(),
+            );
+        }
+    }
+
+    if raw.pixel_format.contradicting_masks().is_some() {
+        push(
+            Severity::Warning,
+            IssueKind::AmbiguousPixelFormat,
+            format!(
+                "The FourCC code {:?} contradicts the RGB bit masks",
+                raw.pixel_format.four_cc
+            ),
+        );
+    }
+
+    // parse the header
+    // the pitch is checked separately below
+    let strict = ParseOptions {
+        permissive: false,
+        file_len: Some(file_len),
+        verify_pitch: false,
+        ..options.clone()
+    };
+    let permissive = ParseOptions {
+        permissive: true,
+        ..strict.clone()
+    };
+    let header = match Header::from_raw(&raw, &strict) {
+        Ok(header) => header,
+        Err(error) => {
+            let fixed = Header::from_raw(&raw, &permissive).ok();
+            // these were already reported above
+            let reported = matches!(
+                error,
+                HeaderError::InvalidHeaderSize(_)
+                    | HeaderError::InvalidArraySizeForTexture3D(_)
+                    | HeaderError::AmbiguousPixelFormat(_)
+            ) || (matches!(error, HeaderError::InvalidPixelFormatSize(_))
+                && fixed.is_none());
+            if !reported {
+                let (severity, suffix) = if fixed.is_some() {
+                    (Severity::Warning, ", but can be read in permissive mode")
+                } else {
+                    (Severity::Error, "")
+                };
+                push(
+                    severity,
+                    IssueKind::InvalidHeader,
+                    format!("The header is invalid{}: {}", suffix, error),
+                );
+            }
+            match fixed {
+                Some(header) => header,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    if let Err(error) = Format::from_header(&header) {
+        push(
+            Severity::Error,
+            IssueKind::UnsupportedFormat,
+            format!("The pixel format is not supported: {}", error),
+        );
+        return Ok(());
+    }
+
+    // pitch and linear size
+    let declared = raw.flags & (DdsFlags::PITCH | DdsFlags::LINEAR_SIZE);
+    if let Some((flag, expected)) = header.pitch_or_linear_size() {
+        let name = if flag == DdsFlags::PITCH {
+            "pitch"
+        } else {
+            "linear size"
+        };
+        if declared == (DdsFlags::PITCH | DdsFlags::LINEAR_SIZE) {
+            push(
+                Severity::Warning,
+                IssueKind::IncorrectPitch,
+                "Both DDSD_PITCH and DDSD_LINEARSIZE are set".to_string(),
+            );
+        } else if !declared.is_empty() && declared != flag {
+            push(
+                Severity::Warning,
+                IssueKind::IncorrectPitch,
+                format!(
+                    "The {} is declared with {:?}, expected {:?}",
+                    name, declared, flag
+                ),
+            );
+        }
+        if declared.is_empty() {
+            push(
+                Severity::Info,
+                IssueKind::IncorrectPitch,
+                format!("The {} is not declared", name),
+            );
+        } else if raw.pitch_or_linear_size != expected {
+            push(
+                Severity::Warning,
+                IssueKind::IncorrectPitch,
+                format!(
+                    "The {} is {}, expected {}",
+                    name, raw.pitch_or_linear_size, expected
+                ),
+            );
+        }
+    }
+
+    // data length vs layout
+    let layout = match DataLayout::from_header(&header) {
+        Ok(layout) => layout,
+        Err(error) => {
+            push(
+                Severity::Error,
+                IssueKind::InvalidHeader,
+                format!("The header describes an invalid data layout: {}", error),
+            );
+            return Ok(());
+        }
+    };
+    let header_len = (Header::MAGIC.len() + header.byte_len()) as u64;
+    let available = file_len.saturating_sub(header_len);
+    let data_len = layout.data_len();
+    if available < data_len {
+        push(
+            Severity::Error,
+            IssueKind::DataTooShort,
+            format!(
+                "The data section is truncated: expected {} bytes, but only {} are present",
+                data_len, available
+            ),
+        );
+    } else if available > data_len {
+        push(
+            Severity::Warning,
+            IssueKind::TrailingData,
+            format!(
+                "The file contains {} bytes of trailing data after the data section",
+                available - data_len
+            ),
+        );
+    }
+
+    Ok(())
+}
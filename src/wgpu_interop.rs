@@ -0,0 +1,277 @@
+//! Conversions between the formats of this crate and
+//! [`wgpu::TextureFormat`](wgpu_types::TextureFormat).
+//!
+//! The mapping is done via [`DxgiFormat`], which is the only format type of
+//! this crate that distinguishes between linear and sRGB formats. [`Format`]
+//! converts to the linear variant of a texture format. Use
+//! [`Header::is_srgb`](crate::header::Header::is_srgb) together with
+//! `TextureFormat::add_srgb_suffix` or convert the [`DxgiFormat`] of the
+//! header directly to get the sRGB variant.
+
+use wgpu_types::{AstcBlock, AstcChannel, TextureFormat};
+
+use crate::header::DxgiFormat;
+use crate::Format;
+
+impl TryFrom<DxgiFormat> for TextureFormat {
+    type Error = ();
+
+    fn try_from(value: DxgiFormat) -> Result<Self, Self::Error> {
+        use DxgiFormat as D;
+        use TextureFormat as T;
+
+        let astc = |block, channel| T::Astc { block, channel };
+
+        Ok(match value {
+            // 8 bit
+            D::R8_UNORM => T::R8Unorm,
+            D::R8_SNORM => T::R8Snorm,
+            D::R8_UINT => T::R8Uint,
+            D::R8_SINT => T::R8Sint,
+
+            // 16 bit
+            D::R16_UNORM => T::R16Unorm,
+            D::R16_SNORM => T::R16Snorm,
+            D::R16_UINT => T::R16Uint,
+            D::R16_SINT => T::R16Sint,
+            D::R16_FLOAT => T::R16Float,
+            D::R8G8_UNORM => T::Rg8Unorm,
+            D::R8G8_SNORM => T::Rg8Snorm,
+            D::R8G8_UINT => T::Rg8Uint,
+            D::R8G8_SINT => T::Rg8Sint,
+
+            // 32 bit
+            D::R32_UINT => T::R32Uint,
+            D::R32_SINT => T::R32Sint,
+            D::R32_FLOAT => T::R32Float,
+            D::R16G16_UNORM => T::Rg16Unorm,
+            D::R16G16_SNORM => T::Rg16Snorm,
+            D::R16G16_UINT => T::Rg16Uint,
+            D::R16G16_SINT => T::Rg16Sint,
+            D::R16G16_FLOAT => T::Rg16Float,
+            D::R8G8B8A8_UNORM => T::Rgba8Unorm,
+            D::R8G8B8A8_UNORM_SRGB => T::Rgba8UnormSrgb,
+            D::R8G8B8A8_SNORM => T::Rgba8Snorm,
+            D::R8G8B8A8_UINT => T::Rgba8Uint,
+            D::R8G8B8A8_SINT => T::Rgba8Sint,
+            D::B8G8R8A8_UNORM => T::Bgra8Unorm,
+            D::B8G8R8A8_UNORM_SRGB => T::Bgra8UnormSrgb,
+            D::R9G9B9E5_SHAREDEXP => T::Rgb9e5Ufloat,
+            D::R10G10B10A2_UNORM => T::Rgb10a2Unorm,
+            D::R10G10B10A2_UINT => T::Rgb10a2Uint,
+            D::R11G11B10_FLOAT => T::Rg11b10Ufloat,
+
+            // 64 bit
+            D::R32G32_UINT => T::Rg32Uint,
+            D::R32G32_SINT => T::Rg32Sint,
+            D::R32G32_FLOAT => T::Rg32Float,
+            D::R16G16B16A16_UNORM => T::Rgba16Unorm,
+            D::R16G16B16A16_SNORM => T::Rgba16Snorm,
+            D::R16G16B16A16_UINT => T::Rgba16Uint,
+            D::R16G16B16A16_SINT => T::Rgba16Sint,
+            D::R16G16B16A16_FLOAT => T::Rgba16Float,
+
+            // 128 bit
+            D::R32G32B32A32_UINT => T::Rgba32Uint,
+            D::R32G32B32A32_SINT => T::Rgba32Sint,
+            D::R32G32B32A32_FLOAT => T::Rgba32Float,
+
+            // depth
+            D::D16_UNORM => T::Depth16Unorm,
+            D::D32_FLOAT => T::Depth32Float,
+            D::D24_UNORM_S8_UINT => T::Depth24PlusStencil8,
+            D::D32_FLOAT_S8X24_UINT => T::Depth32FloatStencil8,
+
+            // bi-planar
+            D::NV12 => T::NV12,
+
+            // BCn
+            D::BC1_UNORM => T::Bc1RgbaUnorm,
+            D::BC1_UNORM_SRGB => T::Bc1RgbaUnormSrgb,
+            D::BC2_UNORM => T::Bc2RgbaUnorm,
+            D::BC2_UNORM_SRGB => T::Bc2RgbaUnormSrgb,
+            D::BC3_UNORM => T::Bc3RgbaUnorm,
+            D::BC3_UNORM_SRGB => T::Bc3RgbaUnormSrgb,
+            D::BC4_UNORM => T::Bc4RUnorm,
+            D::BC4_SNORM => T::Bc4RSnorm,
+            D::BC5_UNORM => T::Bc5RgUnorm,
+            D::BC5_SNORM => T::Bc5RgSnorm,
+            D::BC6H_UF16 => T::Bc6hRgbUfloat,
+            D::BC6H_SF16 => T::Bc6hRgbFloat,
+            D::BC7_UNORM => T::Bc7RgbaUnorm,
+            D::BC7_UNORM_SRGB => T::Bc7RgbaUnormSrgb,
+
+            // ASTC
+            D::ASTC_4X4_UNORM => astc(AstcBlock::B4x4, AstcChannel::Unorm),
+            D::ASTC_4X4_UNORM_SRGB => astc(AstcBlock::B4x4, AstcChannel::UnormSrgb),
+            D::ASTC_5X4_UNORM => astc(AstcBlock::B5x4, AstcChannel::Unorm),
+            D::ASTC_5X4_UNORM_SRGB => astc(AstcBlock::B5x4, AstcChannel::UnormSrgb),
+            D::ASTC_5X5_UNORM => astc(AstcBlock::B5x5, AstcChannel::Unorm),
+            D::ASTC_5X5_UNORM_SRGB => astc(AstcBlock::B5x5, AstcChannel::UnormSrgb),
+            D::ASTC_6X5_UNORM => astc(AstcBlock::B6x5, AstcChannel::Unorm),
+            D::ASTC_6X5_UNORM_SRGB => astc(AstcBlock::B6x5, AstcChannel::UnormSrgb),
+            D::ASTC_6X6_UNORM => astc(AstcBlock::B6x6, AstcChannel::Unorm),
+            D::ASTC_6X6_UNORM_SRGB => astc(AstcBlock::B6x6, AstcChannel::UnormSrgb),
+            D::ASTC_8X5_UNORM => astc(AstcBlock::B8x5, AstcChannel::Unorm),
+            D::ASTC_8X5_UNORM_SRGB => astc(AstcBlock::B8x5, AstcChannel::UnormSrgb),
+            D::ASTC_8X6_UNORM => astc(AstcBlock::B8x6, AstcChannel::Unorm),
+            D::ASTC_8X6_UNORM_SRGB => astc(AstcBlock::B8x6, AstcChannel::UnormSrgb),
+            D::ASTC_8X8_UNORM => astc(AstcBlock::B8x8, AstcChannel::Unorm),
+            D::ASTC_8X8_UNORM_SRGB => astc(AstcBlock::B8x8, AstcChannel::UnormSrgb),
+            D::ASTC_10X5_UNORM => astc(AstcBlock::B10x5, AstcChannel::Unorm),
+            D::ASTC_10X5_UNORM_SRGB => astc(AstcBlock::B10x5, AstcChannel::UnormSrgb),
+            D::ASTC_10X6_UNORM => astc(AstcBlock::B10x6, AstcChannel::Unorm),
+            D::ASTC_10X6_UNORM_SRGB => astc(AstcBlock::B10x6, AstcChannel::UnormSrgb),
+            D::ASTC_10X8_UNORM => astc(AstcBlock::B10x8, AstcChannel::Unorm),
+            D::ASTC_10X8_UNORM_SRGB => astc(AstcBlock::B10x8, AstcChannel::UnormSrgb),
+            D::ASTC_10X10_UNORM => astc(AstcBlock::B10x10, AstcChannel::Unorm),
+            D::ASTC_10X10_UNORM_SRGB => astc(AstcBlock::B10x10, AstcChannel::UnormSrgb),
+            D::ASTC_12X10_UNORM => astc(AstcBlock::B12x10, AstcChannel::Unorm),
+            D::ASTC_12X10_UNORM_SRGB => astc(AstcBlock::B12x10, AstcChannel::UnormSrgb),
+            D::ASTC_12X12_UNORM => astc(AstcBlock::B12x12, AstcChannel::Unorm),
+            D::ASTC_12X12_UNORM_SRGB => astc(AstcBlock::B12x12, AstcChannel::UnormSrgb),
+
+            _ => return Err(()),
+        })
+    }
+}
+
+impl TryFrom<TextureFormat> for DxgiFormat {
+    type Error = ();
+
+    fn try_from(value: TextureFormat) -> Result<Self, Self::Error> {
+        use DxgiFormat as D;
+        use TextureFormat as T;
+
+        Ok(match value {
+            // 8 bit
+            T::R8Unorm => D::R8_UNORM,
+            T::R8Snorm => D::R8_SNORM,
+            T::R8Uint => D::R8_UINT,
+            T::R8Sint => D::R8_SINT,
+
+            // 16 bit
+            T::R16Unorm => D::R16_UNORM,
+            T::R16Snorm => D::R16_SNORM,
+            T::R16Uint => D::R16_UINT,
+            T::R16Sint => D::R16_SINT,
+            T::R16Float => D::R16_FLOAT,
+            T::Rg8Unorm => D::R8G8_UNORM,
+            T::Rg8Snorm => D::R8G8_SNORM,
+            T::Rg8Uint => D::R8G8_UINT,
+            T::Rg8Sint => D::R8G8_SINT,
+
+            // 32 bit
+            T::R32Uint => D::R32_UINT,
+            T::R32Sint => D::R32_SINT,
+            T::R32Float => D::R32_FLOAT,
+            T::Rg16Unorm => D::R16G16_UNORM,
+            T::Rg16Snorm => D::R16G16_SNORM,
+            T::Rg16Uint => D::R16G16_UINT,
+            T::Rg16Sint => D::R16G16_SINT,
+            T::Rg16Float => D::R16G16_FLOAT,
+            T::Rgba8Unorm => D::R8G8B8A8_UNORM,
+            T::Rgba8UnormSrgb => D::R8G8B8A8_UNORM_SRGB,
+            T::Rgba8Snorm => D::R8G8B8A8_SNORM,
+            T::Rgba8Uint => D::R8G8B8A8_UINT,
+            T::Rgba8Sint => D::R8G8B8A8_SINT,
+            T::Bgra8Unorm => D::B8G8R8A8_UNORM,
+            T::Bgra8UnormSrgb => D::B8G8R8A8_UNORM_SRGB,
+            T::Rgb9e5Ufloat => D::R9G9B9E5_SHAREDEXP,
+            T::Rgb10a2Unorm => D::R10G10B10A2_UNORM,
+            T::Rgb10a2Uint => D::R10G10B10A2_UINT,
+            T::Rg11b10Ufloat => D::R11G11B10_FLOAT,
+
+            // 64 bit
+            T::Rg32Uint => D::R32G32_UINT,
+            T::Rg32Sint => D::R32G32_SINT,
+            T::Rg32Float => D::R32G32_FLOAT,
+            T::Rgba16Unorm => D::R16G16B16A16_UNORM,
+            T::Rgba16Snorm => D::R16G16B16A16_SNORM,
+            T::Rgba16Uint => D::R16G16B16A16_UINT,
+            T::Rgba16Sint => D::R16G16B16A16_SINT,
+            T::Rgba16Float => D::R16G16B16A16_FLOAT,
+
+            // 128 bit
+            T::Rgba32Uint => D::R32G32B32A32_UINT,
+            T::Rgba32Sint => D::R32G32B32A32_SINT,
+            T::Rgba32Float => D::R32G32B32A32_FLOAT,
+
+            // depth
+            T::Depth16Unorm => D::D16_UNORM,
+            T::Depth32Float => D::D32_FLOAT,
+            T::Depth24PlusStencil8 => D::D24_UNORM_S8_UINT,
+            T::Depth32FloatStencil8 => D::D32_FLOAT_S8X24_UINT,
+
+            // bi-planar
+            T::NV12 => D::NV12,
+
+            // BCn
+            T::Bc1RgbaUnorm => D::BC1_UNORM,
+            T::Bc1RgbaUnormSrgb => D::BC1_UNORM_SRGB,
+            T::Bc2RgbaUnorm => D::BC2_UNORM,
+            T::Bc2RgbaUnormSrgb => D::BC2_UNORM_SRGB,
+            T::Bc3RgbaUnorm => D::BC3_UNORM,
+            T::Bc3RgbaUnormSrgb => D::BC3_UNORM_SRGB,
+            T::Bc4RUnorm => D::BC4_UNORM,
+            T::Bc4RSnorm => D::BC4_SNORM,
+            T::Bc5RgUnorm => D::BC5_UNORM,
+            T::Bc5RgSnorm => D::BC5_SNORM,
+            T::Bc6hRgbUfloat => D::BC6H_UF16,
+            T::Bc6hRgbFloat => D::BC6H_SF16,
+            T::Bc7RgbaUnorm => D::BC7_UNORM,
+            T::Bc7RgbaUnormSrgb => D::BC7_UNORM_SRGB,
+
+            // ASTC
+            T::Astc { block, channel } => {
+                let linear = match block {
+                    AstcBlock::B4x4 => D::ASTC_4X4_UNORM,
+                    AstcBlock::B5x4 => D::ASTC_5X4_UNORM,
+                    AstcBlock::B5x5 => D::ASTC_5X5_UNORM,
+                    AstcBlock::B6x5 => D::ASTC_6X5_UNORM,
+                    AstcBlock::B6x6 => D::ASTC_6X6_UNORM,
+                    AstcBlock::B8x5 => D::ASTC_8X5_UNORM,
+                    AstcBlock::B8x6 => D::ASTC_8X6_UNORM,
+                    AstcBlock::B8x8 => D::ASTC_8X8_UNORM,
+                    AstcBlock::B10x5 => D::ASTC_10X5_UNORM,
+                    AstcBlock::B10x6 => D::ASTC_10X6_UNORM,
+                    AstcBlock::B10x8 => D::ASTC_10X8_UNORM,
+                    AstcBlock::B10x10 => D::ASTC_10X10_UNORM,
+                    AstcBlock::B12x10 => D::ASTC_12X10_UNORM,
+                    AstcBlock::B12x12 => D::ASTC_12X12_UNORM,
+                };
+                match channel {
+                    AstcChannel::Unorm => linear,
+                    AstcChannel::UnormSrgb => linear.to_srgb(),
+                    AstcChannel::Hdr => return Err(()),
+                }
+            }
+
+            _ => return Err(()),
+        })
+    }
+}
+
+/// Converts the format to its linear (non-sRGB) texture format.
+///
+/// Formats without a DXGI equivalent (e.g.
+/// [`Format::BC3_UNORM_PREMULTIPLIED_ALPHA`]) and formats that wgpu doesn't
+/// support (e.g. [`Format::B5G6R5_UNORM`]) cannot be converted.
+impl TryFrom<Format> for TextureFormat {
+    type Error = ();
+
+    fn try_from(value: Format) -> Result<Self, Self::Error> {
+        DxgiFormat::try_from(value)?.try_into()
+    }
+}
+
+/// Converts the texture format to the format of its data.
+///
+/// Linear and sRGB texture formats are converted to the same format.
+impl TryFrom<TextureFormat> for Format {
+    type Error = ();
+
+    fn try_from(value: TextureFormat) -> Result<Self, Self::Error> {
+        Format::from_dxgi(DxgiFormat::try_from(value)?).ok_or(())
+    }
+}
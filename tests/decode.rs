@@ -374,6 +374,682 @@ fn test_rect_out_of_bounds() {
     assert!(matches!(result, Ok(())));
 }
 
+#[test]
+fn decode_tone_mapping() {
+    fn reinhard(x: f32, exposure: f32) -> u8 {
+        let x = (x * exposure).max(0.0);
+        (x / (1.0 + x) * 255.0 + 0.5) as u8
+    }
+
+    let size = Size::new(100, 301);
+    let mut rng = util::create_rng();
+
+    for format in [Format::R32G32B32A32_FLOAT, Format::BC6H_UF16] {
+        // random data makes for valid BC6H blocks, but floats need to be sane
+        let mut data = vec![0_u8; PixelInfo::from(format).surface_bytes(size).unwrap() as usize];
+        if format == Format::R32G32B32A32_FLOAT {
+            for chunk in data.chunks_exact_mut(4) {
+                let value = (rng.next_u32() % 8000) as f32 / 1000.0;
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+        } else {
+            rng.fill_bytes(&mut data);
+        }
+
+        let mut options = DecodeOptions::default();
+        options.tone_mapping = ToneMapping::new(ToneMapCurve::Reinhard).with_exposure(2.0);
+
+        // reference
+        let mut reference = vec![0_f32; size.pixels() as usize * 4];
+        let view =
+            ImageViewMut::new(reference.as_mut_slice(), size, ColorFormat::RGBA_F32).unwrap();
+        decode(
+            &mut data.as_slice(),
+            view,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+        let expected: Vec<u8> = reference
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let a = (p[3].clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+                [
+                    reinhard(p[0], 2.0),
+                    reinhard(p[1], 2.0),
+                    reinhard(p[2], 2.0),
+                    a,
+                ]
+            })
+            .collect();
+
+        // full decode
+        let mut actual = vec![0_u8; size.pixels() as usize * 4];
+        let view = ImageViewMut::new(actual.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+        decode(&mut data.as_slice(), view, format, &options).unwrap();
+        assert!(actual == expected, "Failed for {:?}", format);
+
+        // rect decode
+        let rect = Rect::new(13, 7, 50, 250);
+        let row_pitch = rect.width as usize * 4 + 3;
+        let mut actual = vec![0_u8; row_pitch * rect.height as usize];
+        let mut reader = Cursor::new(data.as_slice());
+        dds::decode_rect(
+            &mut reader,
+            &mut actual,
+            row_pitch,
+            ColorFormat::RGBA_U8,
+            size,
+            rect,
+            format,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(reader.position(), data.len() as u64);
+        for y in 0..rect.height as usize {
+            let actual_row = &actual[y * row_pitch..][..rect.width as usize * 4];
+            let start = ((rect.y as usize + y) * size.width as usize + rect.x as usize) * 4;
+            let expected_row = &expected[start..][..rect.width as usize * 4];
+            assert!(actual_row == expected_row, "Failed for {:?}", format);
+        }
+    }
+}
+
+#[test]
+fn decode_color_adjustment() {
+    fn adjust(x: u8) -> f32 {
+        (x as f32 / 255.0 * 1.5).powf(1.0 / 2.2)
+    }
+
+    let size = Size::new(31, 17);
+    let format = Format::R8G8B8A8_UNORM;
+    let mut rng = util::create_rng();
+    let mut data = vec![0_u8; size.pixels() as usize * 4];
+    rng.fill_bytes(&mut data);
+
+    let mut options = DecodeOptions::default();
+    options.color_adjustment = ColorAdjustment::NONE.with_exposure(1.5).with_gamma(2.2);
+
+    // f32 output is not clamped
+    let mut actual = vec![0_f32; size.pixels() as usize * 4];
+    let view = ImageViewMut::new(actual.as_mut_slice(), size, ColorFormat::RGBA_F32).unwrap();
+    decode(&mut data.as_slice(), view, format, &options).unwrap();
+    for (i, (&a, &x)) in actual.iter().zip(data.iter()).enumerate() {
+        let expected = if i % 4 == 3 {
+            x as f32 / 255.0
+        } else {
+            adjust(x)
+        };
+        assert!((a - expected).abs() < 1e-5, "{a} != {expected}");
+    }
+
+    // U8 output is clamped
+    let mut actual = vec![0_u8; size.pixels() as usize * 4];
+    let view = ImageViewMut::new(actual.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+    decode(&mut data.as_slice(), view, format, &options).unwrap();
+    for (i, (&a, &x)) in actual.iter().zip(data.iter()).enumerate() {
+        if i % 4 == 3 {
+            assert_eq!(a, x);
+        } else {
+            let expected = (adjust(x).min(1.0) * 255.0 + 0.5) as u8;
+            assert!(a.abs_diff(expected) <= 1, "{a} != {expected}");
+        }
+    }
+
+    // rect decode
+    let rect = Rect::new(3, 2, 20, 11);
+    let row_pitch = rect.width as usize * 4;
+    let mut rect_output = vec![0_u8; row_pitch * rect.height as usize];
+    let mut reader = Cursor::new(data.as_slice());
+    dds::decode_rect(
+        &mut reader,
+        &mut rect_output,
+        row_pitch,
+        ColorFormat::RGBA_U8,
+        size,
+        rect,
+        format,
+        &options,
+    )
+    .unwrap();
+    assert_eq!(reader.position(), data.len() as u64);
+    for y in 0..rect.height as usize {
+        let start = ((rect.y as usize + y) * size.width as usize + rect.x as usize) * 4;
+        assert_eq!(
+            &rect_output[y * row_pitch..][..row_pitch],
+            &actual[start..][..row_pitch]
+        );
+    }
+
+    // alpha-only output is never adjusted
+    let mut alpha = vec![0_u8; size.pixels() as usize];
+    let view = ImageViewMut::new(alpha.as_mut_slice(), size, ColorFormat::ALPHA_U8).unwrap();
+    decode(&mut data.as_slice(), view, format, &options).unwrap();
+    assert!(alpha
+        .iter()
+        .zip(data.chunks_exact(4))
+        .all(|(&a, p)| a == p[3]));
+}
+
+#[test]
+fn decode_raw_yuv() {
+    let mut options = DecodeOptions::default();
+    options.raw_yuv = true;
+
+    let decode_rgb = |data: &[u8], size: Size, format: Format| {
+        let mut output = vec![0_u8; size.pixels() as usize * 3];
+        let view = ImageViewMut::new(output.as_mut_slice(), size, ColorFormat::RGB_U8).unwrap();
+        decode(&mut &data[..], view, format, &options).unwrap();
+        output
+    };
+
+    // AYUV stores pixels as V, U, Y, A
+    let data = [10, 20, 30, 255, 40, 50, 60, 255];
+    assert_eq!(
+        decode_rgb(&data, Size::new(2, 1), Format::AYUV),
+        [30, 20, 10, 60, 50, 40]
+    );
+
+    // YUY2 stores 2 pixels as Y0, U, Y1, V
+    let data = [10, 20, 30, 40];
+    assert_eq!(
+        decode_rgb(&data, Size::new(2, 1), Format::YUY2),
+        [10, 20, 40, 30, 20, 40]
+    );
+
+    // NV12 stores the Y plane followed by interleaved U and V
+    let data = [1, 2, 3, 4, 100, 200];
+    assert_eq!(
+        decode_rgb(&data, Size::new(2, 2), Format::NV12),
+        [1, 100, 200, 2, 100, 200, 3, 100, 200, 4, 100, 200]
+    );
+
+    // P010 stores 10-bit values in the upper bits of 16-bit values
+    let data: Vec<u8> = [1023_u16 << 6, 0, 512 << 6, 256 << 6]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let mut output = [0_u16; 6];
+    let view = ImageViewMut::new(&mut output[..], Size::new(2, 1), ColorFormat::RGB_U16).unwrap();
+    decode(&mut data.as_slice(), view, Format::P010, &options).unwrap();
+    let n10 = |v: u16| ((v as u32 * 65535 + 511) / 1023) as u16;
+    assert_eq!(output, [65535, n10(512), n10(256), 0, n10(512), n10(256)]);
+
+    // non-YUV formats are unaffected
+    let data = [1, 2, 3, 4];
+    let mut output = [0_u8; 4];
+    let view = ImageViewMut::new(&mut output[..], Size::new(1, 1), ColorFormat::RGBA_U8).unwrap();
+    decode(&mut &data[..], view, Format::R8G8B8A8_UNORM, &options).unwrap();
+    assert_eq!(output, data);
+}
+
+#[test]
+fn decode_odd_sub_sampled_size() {
+    // the data of odd-sized surfaces is laid out as if rounded up
+    let size = Size::new(3, 3);
+    assert_eq!(PixelInfo::from(Format::YUY2).surface_bytes(size), Some(24));
+    assert_eq!(PixelInfo::from(Format::NV12).surface_bytes(size), Some(17));
+
+    let mut options = DecodeOptions::default();
+    options.raw_yuv = true;
+
+    let decode_rgb = |data: &[u8], format: Format, options: &DecodeOptions| {
+        let mut output = vec![0_u8; size.pixels() as usize * 3];
+        let view = ImageViewMut::new(output.as_mut_slice(), size, ColorFormat::RGB_U8).unwrap();
+        decode(&mut &data[..], view, format, options).map(|_| output)
+    };
+
+    // permissive: the partial macro-pixel is decoded and clipped
+    let yuy2: Vec<u8> = (0..24).collect();
+    let output = decode_rgb(&yuy2, Format::YUY2, &options).unwrap();
+    assert_eq!(&output[6..9], &[4, 5, 7]);
+    assert_eq!(&output[9..12], &[8, 9, 11]);
+
+    let nv12: Vec<u8> = (0..17).collect();
+    let output = decode_rgb(&nv12, Format::NV12, &options).unwrap();
+    assert_eq!(&output[24..27], &[8, 15, 16]);
+
+    // strict: odd sizes are rejected
+    options.strict_sub_sampling = true;
+    for (data, format) in [(&yuy2, Format::YUY2), (&nv12, Format::NV12)] {
+        assert!(matches!(
+            decode_rgb(data, format, &options),
+            Err(DecodeError::InvalidSize(_))
+        ));
+        let mut output = [0_u8; 3];
+        let result = dds::decode_rect(
+            &mut Cursor::new(data.as_slice()),
+            &mut output,
+            3,
+            ColorFormat::RGB_U8,
+            size,
+            Rect::new(0, 0, 1, 1),
+            format,
+            &options,
+        );
+        assert!(matches!(result, Err(DecodeError::InvalidSize(_))));
+    }
+
+    // formats without sub-sampling are unaffected
+    let data = [0_u8; 9 * 4];
+    let mut output = [0_u8; 9 * 4];
+    let view = ImageViewMut::new(&mut output[..], size, ColorFormat::RGBA_U8).unwrap();
+    decode(&mut &data[..], view, Format::R8G8B8A8_UNORM, &options).unwrap();
+}
+
+#[test]
+fn decode_keep_premultiplied_alpha() {
+    let size = Size::new(16, 16);
+    let mut rng = util::create_rng();
+    let mut data = vec![0_u8; 16 * 16];
+    rng.fill_bytes(&mut data);
+
+    let decode_rgba = |format: Format, options: &DecodeOptions| {
+        let mut output = vec![0_u8; size.pixels() as usize * 4];
+        let view = ImageViewMut::new(output.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+        decode(&mut data.as_slice(), view, format, options).unwrap();
+        output
+    };
+
+    let mut keep = DecodeOptions::default();
+    keep.keep_premultiplied_alpha = true;
+
+    for (premultiplied, straight) in [
+        (Format::BC2_UNORM_PREMULTIPLIED_ALPHA, Format::BC2_UNORM),
+        (Format::BC3_UNORM_PREMULTIPLIED_ALPHA, Format::BC3_UNORM),
+    ] {
+        let stored = decode_rgba(premultiplied, &keep);
+        assert_eq!(stored, decode_rgba(straight, &DecodeOptions::default()));
+
+        let unpremultiplied = decode_rgba(premultiplied, &DecodeOptions::default());
+        assert_ne!(stored, unpremultiplied);
+        for (s, u) in stored.chunks_exact(4).zip(unpremultiplied.chunks_exact(4)) {
+            assert_eq!(s[3], u[3]);
+            let alpha = if s[3] == 0 { 255 } else { s[3] as u16 };
+            for c in 0..3 {
+                let expected = (s[c] as u16 * 255 / alpha).min(255) as u8;
+                assert_eq!(u[c], expected, "{:?}", premultiplied);
+            }
+        }
+    }
+}
+
+#[test]
+fn decode_single_channel() {
+    let size = Size::new(37, 301);
+    let mut rng = util::create_rng();
+
+    for format in [
+        Format::BC3_UNORM,
+        Format::BC5_UNORM,
+        Format::BC4_UNORM,
+        Format::A8_UNORM,
+        Format::R8G8B8A8_UNORM,
+        Format::NV12,
+    ] {
+        let mut data = vec![0_u8; PixelInfo::from(format).surface_bytes(size).unwrap() as usize];
+        rng.fill_bytes(&mut data);
+
+        for precision in [U8, U16, F32] {
+            let rgba = ColorFormat::new(Channels::Rgba, precision);
+            let mut reference =
+                vec![0_u8; size.pixels() as usize * rgba.bytes_per_pixel() as usize];
+            let view = ImageViewMut::new(reference.as_mut_slice(), size, rgba).unwrap();
+            decode(
+                &mut data.as_slice(),
+                view,
+                format,
+                &DecodeOptions::default(),
+            )
+            .unwrap();
+
+            let value_size = precision.size() as usize;
+            for (i, channel) in [Channel::R, Channel::G, Channel::B, Channel::A]
+                .into_iter()
+                .enumerate()
+            {
+                let gray = ColorFormat::new(Channels::Grayscale, precision);
+                let mut actual = vec![0_u8; size.pixels() as usize * value_size];
+                let view = ImageViewMut::new(actual.as_mut_slice(), size, gray).unwrap();
+                let mut reader = data.as_slice();
+                decode_channel(
+                    &mut reader,
+                    view,
+                    format,
+                    channel,
+                    &DecodeOptions::default(),
+                )
+                .unwrap();
+                assert!(reader.is_empty());
+
+                let expected: Vec<u8> = reference
+                    .chunks_exact(value_size * 4)
+                    .flat_map(|p| p[i * value_size..(i + 1) * value_size].to_vec())
+                    .collect();
+                assert!(
+                    actual == expected,
+                    "Failed for {:?} {:?} {:?}",
+                    format,
+                    precision,
+                    channel
+                );
+            }
+        }
+    }
+
+    // the output must have a single channel
+    let mut output = vec![0_u8; 16 * 3];
+    let view = ImageViewMut::new(output.as_mut_slice(), Size::new(4, 4), ColorFormat::RGB_U8);
+    let result = decode_channel(
+        &mut [0_u8; 16].as_slice(),
+        view.unwrap(),
+        Format::BC3_UNORM,
+        Channel::A,
+        &DecodeOptions::default(),
+    );
+    assert!(matches!(result, Err(DecodeError::ExpectedSingleChannel)));
+}
+
+#[test]
+fn decode_surface_rows() {
+    let size = Size::new(37, 301);
+    let color = ColorFormat::RGBA_U8;
+    let row_bytes = size.width as usize * color.bytes_per_pixel() as usize;
+    let mut rng = util::create_rng();
+
+    for format in [
+        Format::BC1_UNORM,
+        Format::R8G8B8A8_UNORM,
+        Format::YUY2,
+        Format::NV12,
+    ] {
+        let mut data = vec![0_u8; PixelInfo::from(format).surface_bytes(size).unwrap() as usize];
+        rng.fill_bytes(&mut data);
+
+        let mut expected = vec![0_u8; size.height as usize * row_bytes];
+        let view = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+        decode(
+            &mut data.as_slice(),
+            view,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+
+        for buffer_rows in [1, 4, 7, 64, 301, 1000] {
+            let mut reader = data.as_slice();
+            let mut decoder =
+                SurfaceDecoder::new(&mut reader, format, size, DecodeOptions::default());
+            let mut buffer = vec![0_u8; buffer_rows * row_bytes];
+            let mut actual = Vec::new();
+
+            if buffer_rows < decoder.row_multiple() as usize {
+                let result = decoder.next_rows(&mut buffer, color);
+                assert!(matches!(
+                    result,
+                    Err(DecodeError::RectBufferTooSmall { .. })
+                ));
+                continue;
+            }
+
+            loop {
+                let rows = decoder.next_rows(&mut buffer, color).unwrap();
+                if rows == 0 {
+                    break;
+                }
+                assert!(rows as usize <= buffer_rows);
+                actual.extend_from_slice(&buffer[..rows as usize * row_bytes]);
+            }
+            assert_eq!(decoder.remaining_rows(), 0);
+            assert!(reader.is_empty());
+
+            assert!(
+                actual == expected,
+                "Failed for {:?} with {} rows",
+                format,
+                buffer_rows
+            );
+        }
+    }
+}
+
+#[test]
+fn decode_progress_and_cancellation() {
+    use std::sync::{Arc, Mutex};
+
+    let size = Size::new(37, 301);
+    let color = ColorFormat::RGBA_U8;
+    let row_bytes = size.width as usize * color.bytes_per_pixel() as usize;
+    let mut rng = util::create_rng();
+
+    let recording_options = || {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let mut options = DecodeOptions::default();
+        let reports_clone = reports.clone();
+        options.progress = Some(ProgressCallback::new(move |progress| {
+            reports_clone.lock().unwrap().push(progress.decoded_rows);
+        }));
+        (options, reports)
+    };
+
+    for format in [
+        Format::BC1_UNORM,
+        Format::R8G8B8A8_UNORM,
+        Format::BC6H_UF16,
+        Format::NV12,
+    ] {
+        let mut data = vec![0_u8; PixelInfo::from(format).surface_bytes(size).unwrap() as usize];
+        rng.fill_bytes(&mut data);
+
+        let mut expected = vec![0_u8; size.height as usize * row_bytes];
+        let view = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+        decode(&mut data.as_slice(), view, format, &Default::default()).unwrap();
+
+        // full decode
+        let (options, reports) = recording_options();
+        let mut actual = vec![0_u8; expected.len()];
+        let view = ImageViewMut::new(actual.as_mut_slice(), size, color).unwrap();
+        let mut reader = data.as_slice();
+        decode(&mut reader, view, format, &options).unwrap();
+        assert!(reader.is_empty());
+        assert!(actual == expected, "Failed for {:?}", format);
+
+        let reports = reports.lock().unwrap().clone();
+        assert!(!reports.is_empty() && reports.len() <= 101);
+        assert!(reports.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(*reports.last().unwrap(), size.height);
+
+        // rect decode
+        let rect = Rect::new(3, 5, 20, 250);
+        let (options, reports) = recording_options();
+        let rect_row_bytes = rect.width as usize * color.bytes_per_pixel() as usize;
+        let mut actual = vec![0_u8; rect.height as usize * rect_row_bytes];
+        let mut reader = Cursor::new(data.as_slice());
+        dds::decode_rect(
+            &mut reader,
+            &mut actual,
+            rect_row_bytes,
+            color,
+            size,
+            rect,
+            format,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(reader.position(), data.len() as u64);
+        for (y, row) in actual.chunks_exact(rect_row_bytes).enumerate() {
+            let start = (rect.y as usize + y) * row_bytes + rect.x as usize * 4;
+            assert!(row == &expected[start..start + rect_row_bytes]);
+        }
+        assert_eq!(*reports.lock().unwrap().last().unwrap(), rect.height);
+
+        // cancel after the first strip
+        let (mut options, reports) = recording_options();
+        let token = CancellationToken::new();
+        let token_clone = token.clone();
+        let reports_clone = reports.clone();
+        options.progress = Some(ProgressCallback::new(move |progress| {
+            reports_clone.lock().unwrap().push(progress.decoded_rows);
+            token_clone.cancel();
+        }));
+        options.cancellation = Some(token.clone());
+        let mut actual = vec![0_u8; expected.len()];
+        let view = ImageViewMut::new(actual.as_mut_slice(), size, color).unwrap();
+        let result = decode(&mut data.as_slice(), view, format, &options);
+        if format == Format::NV12 {
+            // bi-planar formats are decoded in one go
+            assert!(result.is_ok());
+        } else {
+            assert!(matches!(result, Err(DecodeError::Cancelled)));
+        }
+        assert!(token.is_cancelled());
+        assert_eq!(reports.lock().unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn decode_padded_rows() {
+    let size = Size::new(5, 3);
+    let format = Format::R32G32B32_FLOAT;
+    let mut rng = util::create_rng();
+
+    let header = Header::new_image(size.width, size.height, format).with_mipmaps();
+    let tight_layout = DataLayout::from_header(&header).unwrap();
+    let mut raw = header.to_raw();
+    // 5 pixels * 12 bytes = 60 bytes per row, padded to 64
+    raw.pitch_or_linear_size = 64;
+
+    // write all mipmaps with rows padded to 16 bytes
+    let mut tight = Vec::new();
+    let mut file = Header::MAGIC.to_vec();
+    raw.write(&mut file).unwrap();
+    let header_len = file.len();
+    for surface in tight_layout.texture().unwrap().iter_mips() {
+        let row_bytes = surface.width() as usize * 12;
+        for _ in 0..surface.height() {
+            let mut row = vec![0_u8; row_bytes];
+            rng.fill_bytes(&mut row);
+            tight.extend_from_slice(&row);
+            file.extend_from_slice(&row);
+            let padding = (16 - row_bytes % 16) % 16;
+            file.extend(std::iter::repeat(0xCD).take(padding));
+        }
+    }
+
+    // the layout is only detected when the file length is known
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+    let info = DdsInfo::read_with_options(&mut file.as_slice(), &options).unwrap();
+    assert_eq!(info.layout().row_alignment().get(), 16);
+    assert_eq!(info.layout().row_pitch(5), Some(64));
+    assert_eq!(info.layout().data_len(), (file.len() - header_len) as u64);
+    let info = DdsInfo::read_with_options(&mut file.as_slice(), &ParseOptions::default()).unwrap();
+    assert_eq!(info.layout().row_alignment().get(), 1);
+
+    // decode all mipmaps
+    let color = ColorFormat::RGB_F32;
+    let mut decoder = Decoder::new_with_options(Cursor::new(file.as_slice()), &options).unwrap();
+    let mut decoded = Vec::new();
+    while let Some(surface) = decoder.surface_info() {
+        let mut image = vec![0_u8; surface.size().pixels() as usize * 12];
+        let view = ImageViewMut::new(image.as_mut_slice(), surface.size(), color).unwrap();
+        decoder.read_surface(view).unwrap();
+        decoded.extend_from_slice(&image);
+    }
+    assert_eq!(decoder.into_reader().position(), file.len() as u64);
+    // R32G32B32_FLOAT is decoded as is on little-endian systems
+    if cfg!(target_endian = "little") {
+        assert_eq!(decoded, tight);
+    }
+
+    // decode a rectangle of the first mipmap and skip the rest
+    let mut decoder = Decoder::new_with_options(Cursor::new(file.as_slice()), &options).unwrap();
+    let rect = Rect::new(1, 1, 3, 2);
+    let mut image = vec![0_u8; rect.size().pixels() as usize * 12];
+    decoder
+        .read_surface_rect(&mut image, 3 * 12, rect, color)
+        .unwrap();
+    for y in 0..2 {
+        let expected_start = (y + 1) * 60 + 12;
+        assert_eq!(
+            &image[y * 36..(y + 1) * 36],
+            &decoded[expected_start..expected_start + 36]
+        );
+    }
+    decoder.skip_surface().unwrap();
+    let surface = decoder.surface_info().unwrap();
+    let mut image = vec![0_u8; surface.size().pixels() as usize * 12];
+    let view = ImageViewMut::new(image.as_mut_slice(), surface.size(), color).unwrap();
+    decoder.read_surface(view).unwrap();
+    assert_eq!(
+        image,
+        decoded[5 * 3 * 12 + 2 * 12..5 * 3 * 12 + 2 * 12 + 12]
+    );
+
+    // DWORD-aligned pitches of tightly packed files are not mistaken for padding
+    let header = Header::new_image(5, 3, Format::R8G8B8_UNORM);
+    let mut raw = header.to_raw();
+    raw.pitch_or_linear_size = 16;
+    let mut file = Header::MAGIC.to_vec();
+    raw.write(&mut file).unwrap();
+    file.extend_from_slice(&[0; 5 * 3 * 3]);
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+    let info = DdsInfo::read_with_options(&mut file.as_slice(), &options).unwrap();
+    assert_eq!(info.layout().row_alignment().get(), 1);
+}
+
+#[test]
+fn decode_legacy_block_sizes() {
+    // a 6x6 BC1 image with mipmaps, written by a tool that computes the
+    // number of blocks as max(1, size / 4) instead of rounding up
+    let header = Header::new_image(6, 6, Format::BC1_UNORM).with_mipmaps();
+    let white_block = [0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0];
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let header_len = file.len();
+    for _ in 0..3 {
+        file.extend_from_slice(&white_block);
+    }
+
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+    let info = DdsInfo::read_with_options(&mut file.as_slice(), &options).unwrap();
+    assert!(info.layout().has_legacy_block_sizes());
+    assert_eq!(info.layout().data_len(), (file.len() - header_len) as u64);
+    assert_eq!(info.header().mipmap_count().get(), 3);
+    let info = DdsInfo::read_with_options(&mut file.as_slice(), &ParseOptions::default()).unwrap();
+    assert!(!info.layout().has_legacy_block_sizes());
+
+    // missing blocks are decoded as if all of their bytes were zero
+    let color = ColorFormat::RGBA_U8;
+    let mut decoder = Decoder::new_with_options(Cursor::new(file.as_slice()), &options).unwrap();
+    let mut image = vec![0_u8; 6 * 6 * 4];
+    let view = ImageViewMut::new(image.as_mut_slice(), Size::new(6, 6), color).unwrap();
+    decoder.read_surface(view).unwrap();
+    for (i, pixel) in image.chunks(4).enumerate() {
+        let (x, y) = (i % 6, i / 6);
+        let expected = if x < 4 && y < 4 { 255 } else { 0 };
+        assert_eq!(
+            pixel,
+            [expected, expected, expected, 255],
+            "pixel ({x}, {y})"
+        );
+    }
+
+    // rectangles and remaining mipmaps read the stored data
+    let mut decoder = Decoder::new_with_options(Cursor::new(file.as_slice()), &options).unwrap();
+    let mut rect = vec![0_u8; 2 * 2 * 4];
+    decoder
+        .read_surface_rect(&mut rect, 2 * 4, Rect::new(3, 3, 2, 2), color)
+        .unwrap();
+    assert_eq!(&rect[..4], [255; 4]);
+    assert_eq!(&rect[4..8], [0, 0, 0, 255]);
+    decoder.skip_surface().unwrap();
+    let mut image = vec![0_u8; 4];
+    let view = ImageViewMut::new(image.as_mut_slice(), Size::new(1, 1), color).unwrap();
+    decoder.read_surface(view).unwrap();
+    assert_eq!(image, [255; 4]);
+    assert_eq!(decoder.into_reader().position(), file.len() as u64);
+}
+
 #[test]
 fn test_unaligned() {
     // dummy image data of the encoded image
@@ -416,3 +1092,1651 @@ fn test_unaligned() {
         }
     }
 }
+
+#[test]
+fn decode_zero_copy() {
+    assert!(Format::R8G8B8A8_UNORM.is_zero_copy(ColorFormat::RGBA_U8));
+    assert!(Format::R8_UNORM.is_zero_copy(ColorFormat::GRAYSCALE_U8));
+    assert!(!Format::R8G8B8A8_UNORM.is_zero_copy(ColorFormat::RGBA_U16));
+    assert!(!Format::B8G8R8A8_UNORM.is_zero_copy(ColorFormat::RGBA_U8));
+    assert!(!Format::R8G8B8A8_SNORM.is_zero_copy(ColorFormat::RGBA_U8));
+    assert!(!Format::BC1_UNORM.is_zero_copy(ColorFormat::RGBA_U8));
+    assert_eq!(
+        Format::R32G32B32A32_FLOAT.is_zero_copy(ColorFormat::RGBA_F32),
+        cfg!(target_endian = "little")
+    );
+
+    let size = Size::new(7, 5);
+    let format = Format::R8G8B8A8_UNORM;
+    let color = ColorFormat::RGBA_U8;
+    let mut rng = util::create_rng();
+    let mut data = vec![0_u8; size.pixels() as usize * 4 + 3];
+    rng.fill_bytes(&mut data);
+
+    // borrowed views are the encoded data itself
+    let borrowed = decode_borrowed(&data, size, format, color).unwrap();
+    let mut image = vec![0_u8; size.pixels() as usize * 4];
+    let view = ImageViewMut::new(image.as_mut_slice(), size, color).unwrap();
+    decode(
+        &mut data.as_slice(),
+        view,
+        format,
+        &DecodeOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(borrowed.data(), image.as_slice());
+    assert!(decode_borrowed(&data[..10], size, format, color).is_none());
+    assert!(decode_borrowed(&data, size, format, ColorFormat::RGB_U8).is_none());
+
+    // rectangles are read directly and the reader ends up after the surface
+    let rect = Rect::new(2, 1, 4, 3);
+    let row_pitch = rect.width as usize * 4 + 5;
+    let mut output = vec![0_u8; row_pitch * rect.height as usize];
+    let mut reader = Cursor::new(data.as_slice());
+    dds::decode_rect(
+        &mut reader,
+        &mut output,
+        row_pitch,
+        color,
+        size,
+        rect,
+        format,
+        &DecodeOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(reader.position(), size.pixels() * 4);
+    for y in 0..rect.height as usize {
+        let start = ((rect.y as usize + y) * size.width as usize + rect.x as usize) * 4;
+        assert_eq!(
+            &output[y * row_pitch..y * row_pitch + rect.width as usize * 4],
+            &image[start..start + rect.width as usize * 4]
+        );
+    }
+}
+
+#[test]
+fn decode_from_slice() {
+    let size = Size::new(13, 9);
+    let format = Format::BC1_UNORM;
+    let mut rng = util::create_rng();
+
+    let header = Header::new_image(size.width, size.height, format).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let mut file = Header::MAGIC.to_vec();
+    header.to_raw().write(&mut file).unwrap();
+    let header_len = file.len();
+    let mut data = vec![0_u8; layout.data_len() as usize];
+    rng.fill_bytes(&mut data);
+    file.extend_from_slice(&data);
+
+    // decoding all surfaces from a slice is the same as from a cursor
+    let color = ColorFormat::RGBA_U8;
+    let mut slice_decoder = Decoder::from_slice(&file).unwrap();
+    let mut cursor_decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    assert_eq!(slice_decoder.remaining_data(), data.as_slice());
+    let mut offset = 0;
+    while let Some(surface) = slice_decoder.surface_info() {
+        let surface_size = surface.size();
+        let len = surface_size.pixels() as usize * 4;
+
+        let mut expected = vec![0_u8; len];
+        let view = ImageViewMut::new(expected.as_mut_slice(), surface_size, color).unwrap();
+        cursor_decoder.read_surface(view).unwrap();
+
+        let mut actual = vec![0_u8; len];
+        let view = ImageViewMut::new(actual.as_mut_slice(), surface_size, color).unwrap();
+        slice_decoder.read_surface(view).unwrap();
+        assert_eq!(actual, expected);
+
+        let mut actual = vec![0_u8; len];
+        let view = ImageViewMut::new(actual.as_mut_slice(), surface_size, color).unwrap();
+        let options = DecodeOptions::default();
+        offset += format
+            .decode_from_slice(&data[offset..], view, &options)
+            .unwrap();
+        assert_eq!(actual, expected);
+    }
+    assert_eq!(offset, data.len());
+    assert!(slice_decoder.remaining_data().is_empty());
+    assert_eq!(header_len + offset, file.len());
+
+    // truncated data is rejected without touching the output
+    let mut image = vec![7_u8; size.pixels() as usize * 4];
+    let view = ImageViewMut::new(image.as_mut_slice(), size, color).unwrap();
+    let result = format.decode_from_slice(&data[..10], view, &DecodeOptions::default());
+    assert!(
+        matches!(result, Err(DecodeError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+    );
+    assert!(image.iter().all(|&b| b == 7));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn decode_async() {
+    let size = Size::new(21, 11);
+    let mut rng = util::create_rng();
+
+    for header_format in [Format::BC1_UNORM, Format::BC7_UNORM] {
+        let header = Header::new_image(size.width, size.height, header_format).with_mipmaps();
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mut file = Header::MAGIC.to_vec();
+        header.to_raw().write(&mut file).unwrap();
+        let mut data = vec![0_u8; layout.data_len() as usize];
+        rng.fill_bytes(&mut data);
+        file.extend_from_slice(&data);
+
+        let info = DdsInfo::read_async(&mut file.as_slice()).await.unwrap();
+        assert_eq!(info.format(), header_format);
+
+        let color = ColorFormat::RGBA_U8;
+        let mut sync_decoder = Decoder::new(file.as_slice()).unwrap();
+        let mut async_decoder = Decoder::new_async(file.as_slice()).await.unwrap();
+        let mut level = 0;
+        while let Some(surface) = sync_decoder.surface_info() {
+            let surface_size = surface.size();
+            let len = surface_size.pixels() as usize * 4;
+
+            let mut expected = vec![0_u8; len];
+            let view = ImageViewMut::new(expected.as_mut_slice(), surface_size, color).unwrap();
+            sync_decoder.read_surface(view).unwrap();
+
+            // skip every other surface
+            if level % 2 == 1 {
+                async_decoder.skip_surface_async().await.unwrap();
+            } else {
+                let mut actual = vec![0_u8; len];
+                let view = ImageViewMut::new(actual.as_mut_slice(), surface_size, color).unwrap();
+                async_decoder.read_surface_async(view).await.unwrap();
+                assert_eq!(actual, expected);
+            }
+            level += 1;
+        }
+        assert!(async_decoder.surface_info().is_none());
+        assert!(async_decoder.into_reader().is_empty());
+    }
+
+    // truncated files
+    let header = Header::new_image(size.width, size.height, Format::BC1_UNORM);
+    let mut file = Header::MAGIC.to_vec();
+    header.to_raw().write(&mut file).unwrap();
+    file.extend_from_slice(&[0; 10]);
+    assert!(DdsInfo::read_async(&mut &file[..50]).await.is_err());
+    let mut decoder = Decoder::new_async(file.as_slice()).await.unwrap();
+    let mut image = vec![0_u8; size.pixels() as usize * 4];
+    let view = ImageViewMut::new(image.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+    assert!(decoder.read_surface_async(view).await.is_err());
+}
+
+#[test]
+fn decode_u32() {
+    let size = Size::new(11, 6);
+    let mut rng = util::create_rng();
+
+    fn decode_as<T: Default + Copy>(
+        data: &[u8],
+        size: Size,
+        format: Format,
+        color: ColorFormat,
+    ) -> Vec<T>
+    where
+        [T]: AsBytes,
+    {
+        let mut output =
+            vec![T::default(); size.pixels() as usize * color.channels.count() as usize];
+        let view = ImageViewMut::new(output.as_mut_slice(), size, color).unwrap();
+        decode(&mut &data[..], view, format, &DecodeOptions::default()).unwrap();
+        output
+    }
+
+    for format in [
+        Format::R8G8B8A8_UNORM,
+        Format::R16G16B16A16_UNORM,
+        Format::B5G6R5_UNORM,
+        Format::BC1_UNORM,
+        Format::R32G32B32A32_FLOAT,
+        Format::R16G16B16A16_FLOAT,
+    ] {
+        let mut data = vec![0_u8; PixelInfo::from(format).surface_bytes(size).unwrap() as usize];
+        if format == Format::R32G32B32A32_FLOAT {
+            for chunk in data.chunks_exact_mut(4) {
+                let value = (rng.next_u32() % 3000) as f32 / 1000.0 - 1.0;
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+        } else if format == Format::R16G16B16A16_FLOAT {
+            // random finite positive halfs
+            for chunk in data.chunks_exact_mut(2) {
+                let value = (rng.next_u32() % 0x7800) as u16;
+                chunk.copy_from_slice(&value.to_le_bytes());
+            }
+        } else {
+            rng.fill_bytes(&mut data);
+        }
+
+        // U32 is widened from U16 or converted from F32
+        let actual: Vec<u32> = decode_as(&data, size, format, ColorFormat::RGBA_U32);
+        let expected: Vec<u32> = if format.precision() == Precision::F32 {
+            decode_as::<f32>(&data, size, format, ColorFormat::RGBA_F32)
+                .iter()
+                .map(|&x| (x.clamp(0.0, 1.0) as f64 * u32::MAX as f64 + 0.5) as u32)
+                .collect()
+        } else {
+            decode_as::<u16>(&data, size, format, ColorFormat::RGBA_U16)
+                .iter()
+                .map(|&x| x as u32 * 65537)
+                .collect()
+        };
+        assert_eq!(actual, expected, "Failed for {:?}", format);
+
+        // other channels
+        let gray: Vec<u32> = decode_as(&data, size, format, ColorFormat::GRAYSCALE_U32);
+        let gray_u16: Vec<u16> = decode_as(&data, size, format, ColorFormat::GRAYSCALE_U16);
+        for (&a, &b) in gray.iter().zip(gray_u16.iter()) {
+            assert!((a >> 16).abs_diff(b as u32) <= 1, "Failed for {:?}", format);
+        }
+
+        // rect decode
+        let rect = Rect::new(2, 1, 7, 4);
+        let row_pitch = rect.width as usize * 16 + 5;
+        let mut output = vec![0_u8; row_pitch * rect.height as usize];
+        let mut reader = Cursor::new(data.as_slice());
+        dds::decode_rect(
+            &mut reader,
+            &mut output,
+            row_pitch,
+            ColorFormat::RGBA_U32,
+            size,
+            rect,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(reader.position(), data.len() as u64);
+        for y in 0..rect.height as usize {
+            let row = &output[y * row_pitch..][..rect.width as usize * 16];
+            let start = (rect.y as usize + y) * size.width as usize + rect.x as usize;
+            let expected_row = &actual[start * 4..(start + rect.width as usize) * 4];
+            let expected_bytes: Vec<u8> =
+                expected_row.iter().flat_map(|v| v.to_ne_bytes()).collect();
+            assert_eq!(row, expected_bytes.as_slice(), "Failed for {:?}", format);
+        }
+    }
+}
+
+#[test]
+fn rect_utilities() {
+    let size = Size::new(13, 7);
+    let rect = Rect::new(3, 1, 20, 20);
+    assert!(!rect.is_within_bounds(size));
+    assert_eq!(rect.clamp_to(size), Rect::new(3, 1, 10, 6));
+    assert_eq!(
+        rect.intersection(Rect::from_size(size)),
+        Some(rect.clamp_to(size))
+    );
+    assert_eq!(rect.intersection(Rect::new(0, 0, 3, 3)), None);
+    assert!(Rect::new(13, 0, 4, 4).clamp_to(size).is_empty());
+
+    let small = Rect::new(5, 3, 1, 1);
+    assert_eq!(small.align_to_blocks(Format::R8G8B8A8_UNORM), small);
+    assert_eq!(
+        small.align_to_blocks(Format::BC1_UNORM),
+        Rect::new(4, 0, 4, 4)
+    );
+    assert_eq!(small.align_to_blocks(Format::YUY2), Rect::new(4, 3, 2, 1));
+    assert_eq!(small.align_to_blocks(Format::NV12), Rect::new(4, 2, 2, 2));
+    assert_eq!(
+        small.align_to_blocks(Format::R1_UNORM),
+        Rect::new(0, 3, 8, 1)
+    );
+    let far = Rect::new(u32::MAX - 1, 0, 1, 1).align_to_blocks(Format::BC1_UNORM);
+    assert_eq!(far.x, u32::MAX - 3);
+    assert_eq!(far.size(), Size::new(4, 4));
+
+    // tiles must cover the rect exactly once
+    let rect = Rect::new(2, 3, 11, 4);
+    let mut covered = [0_u8; 13 * 7];
+    for tile in rect.tiles(Size::new(4, 3)) {
+        assert!(!tile.is_empty());
+        assert_eq!(tile.intersection(rect), Some(tile));
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                covered[(y * 13 + x) as usize] += 1;
+            }
+        }
+    }
+    for y in 0..7 {
+        for x in 0..13 {
+            let expected = Rect::new(x, y, 1, 1).intersection(rect).is_some() as u8;
+            assert_eq!(covered[(y * 13 + x) as usize], expected);
+        }
+    }
+    assert_eq!(Rect::new(1, 1, 0, 5).tiles(Size::new(2, 2)).count(), 0);
+
+    // tiles stop at the largest representable coordinate
+    let far = Rect::new(u32::MAX - 4, u32::MAX - 2, u32::MAX, u32::MAX);
+    let tiles: Vec<Rect> = far.tiles(Size::new(4, 4)).collect();
+    assert_eq!(
+        tiles,
+        [
+            Rect::new(u32::MAX - 4, u32::MAX - 2, 4, 3),
+            Rect::new(u32::MAX, u32::MAX - 2, 1, 3),
+        ]
+    );
+    let tiles: Vec<Rect> = Rect::new(0, u32::MAX, u32::MAX, 1)
+        .tiles(Size::new(u32::MAX, 4))
+        .collect();
+    assert_eq!(tiles, [Rect::new(0, u32::MAX, u32::MAX, 1)]);
+}
+
+#[test]
+fn decode_rect_sub_sampled() {
+    let formats = [
+        Format::YUY2,
+        Format::UYVY,
+        Format::Y210,
+        Format::Y216,
+        Format::R8G8_B8G8_UNORM,
+        Format::G8R8_G8B8_UNORM,
+        Format::R1_UNORM,
+    ];
+    // odd sizes, so that the last block of each row is partially outside
+    let size = Size::new(37, 5);
+    let mut rng = util::create_rng();
+
+    for format in formats {
+        let pixel_info = PixelInfo::from(format);
+        let (block_width, _) = match pixel_info {
+            PixelInfo::Block(block) => block.size(),
+            _ => unreachable!(),
+        };
+        let bytes = pixel_info.surface_bytes(size).unwrap() as usize;
+        let mut data = vec![0_u8; bytes];
+        rng.fill_bytes(&mut data);
+
+        let color = ColorFormat::RGBA_U16;
+        let mut full = vec![0_u16; size.pixels() as usize * 4];
+        let view = ImageViewMut::new(full.as_mut_slice(), size, color).unwrap();
+        decode(
+            &mut data.as_slice(),
+            view,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+
+        // all rects that start and end at every offset within a block
+        let block_width = block_width as u32;
+        for x in 0..block_width * 2 {
+            for end_x in (x + 1)..=size.width {
+                if end_x - x > block_width * 2 && end_x < size.width - block_width {
+                    continue;
+                }
+                let rect = Rect::new(x, 1, end_x - x, 3);
+                let row_pitch = rect.width as usize * 8;
+                let mut actual = vec![0_u8; row_pitch * rect.height as usize];
+                let mut reader = Cursor::new(data.as_slice());
+                dds::decode_rect(
+                    &mut reader,
+                    &mut actual,
+                    row_pitch,
+                    color,
+                    size,
+                    rect,
+                    format,
+                    &DecodeOptions::default(),
+                )
+                .unwrap();
+                assert_eq!(reader.position(), data.len() as u64);
+
+                let actual: &[u16] = util::cast_slice(&actual);
+                for y in 0..rect.height as usize {
+                    let values = rect.width as usize * 4;
+                    let start = ((rect.y as usize + y) * size.width as usize + x as usize) * 4;
+                    assert!(
+                        actual[y * values..][..values] == full[start..][..values],
+                        "Failed for {:?} {:?}",
+                        format,
+                        rect
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn decode_rect_sequential() {
+    let size = Size::new(37, 21);
+    let rect = Rect::new(5, 6, 19, 9);
+    let color = ColorFormat::RGBA_U8;
+    let row_pitch = rect.width as usize * 4;
+    let mut rng = util::create_rng();
+
+    let mut adjusted = DecodeOptions::default();
+    adjusted.color_adjustment = ColorAdjustment::NONE.with_exposure(1.5);
+
+    for format in [
+        Format::R8G8B8A8_UNORM,
+        Format::BC1_UNORM,
+        Format::BC7_UNORM,
+        Format::YUY2,
+        Format::NV12,
+    ] {
+        let surface_len = PixelInfo::from(format).surface_bytes(size).unwrap() as usize;
+        let mut data = vec![0_u8; surface_len + 7];
+        rng.fill_bytes(&mut data);
+
+        for options in [DecodeOptions::default(), adjusted.clone()] {
+            let mut expected = vec![0_u8; row_pitch * rect.height as usize];
+            let mut reader = Cursor::new(data.as_slice());
+            dds::decode_rect(
+                &mut reader,
+                &mut expected,
+                row_pitch,
+                color,
+                size,
+                rect,
+                format,
+                &options,
+            )
+            .unwrap();
+
+            // a slice implements `Read`, but not `Seek`
+            let mut actual = vec![0_u8; row_pitch * rect.height as usize];
+            let mut remaining = data.as_slice();
+            dds::decode_rect_sequential(
+                &mut remaining,
+                &mut actual,
+                row_pitch,
+                color,
+                size,
+                rect,
+                format,
+                &options,
+            )
+            .unwrap();
+            assert!(actual == expected, "Failed for {:?}", format);
+            assert_eq!(remaining.len(), 7, "Failed for {:?}", format);
+        }
+
+        // buffering the surface respects the memory limit
+        let mut options = adjusted.clone();
+        options.memory_limit = surface_len - 1;
+        let mut actual = vec![0_u8; row_pitch * rect.height as usize];
+        let result = dds::decode_rect_sequential(
+            &mut data.as_slice(),
+            &mut actual,
+            row_pitch,
+            color,
+            size,
+            rect,
+            format,
+            &options,
+        );
+        assert!(matches!(result, Err(DecodeError::MemoryLimitExceeded)));
+    }
+
+    // truncated data is an EOF error
+    let result = dds::decode_rect_sequential(
+        &mut [0_u8; 100].as_slice(),
+        &mut [0_u8; 4],
+        4,
+        color,
+        size,
+        Rect::new(36, 20, 1, 1),
+        Format::R8G8B8A8_UNORM,
+        &DecodeOptions::default(),
+    );
+    assert!(matches!(result, Err(DecodeError::Io(_))));
+}
+
+#[test]
+fn decode_with_pitch() {
+    let size = Size::new(13, 6);
+    let color = ColorFormat::RGB_U16;
+    let row_bytes = size.width as usize * color.bytes_per_pixel() as usize;
+    let mut rng = util::create_rng();
+
+    for format in [Format::R8G8B8A8_UNORM, Format::BC1_UNORM, Format::YUY2] {
+        let surface_len = PixelInfo::from(format).surface_bytes(size).unwrap() as usize;
+        let mut data = vec![0_u8; surface_len + 5];
+        rng.fill_bytes(&mut data);
+
+        let mut expected = vec![0_u8; row_bytes * size.height as usize];
+        let image = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+        dds::decode(
+            &mut data.as_slice(),
+            image,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+
+        // tightly packed rows
+        let mut actual = vec![0_u8; expected.len()];
+        let mut remaining = data.as_slice();
+        dds::decode_with_pitch(
+            &mut remaining,
+            &mut actual,
+            row_bytes,
+            color,
+            size,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+        assert!(actual == expected, "Failed for {:?}", format);
+        assert_eq!(remaining.len(), 5, "Failed for {:?}", format);
+
+        // into a larger atlas, 3 pixels from the left
+        let offset = 3 * color.bytes_per_pixel() as usize;
+        let row_pitch = row_bytes + 8 * color.bytes_per_pixel() as usize;
+        let mut atlas = vec![0xAB_u8; row_pitch * size.height as usize];
+        let mut remaining = data.as_slice();
+        dds::decode_with_pitch(
+            &mut remaining,
+            &mut atlas[offset..],
+            row_pitch,
+            color,
+            size,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(remaining.len(), 5, "Failed for {:?}", format);
+        for (y, row) in atlas.chunks(row_pitch).enumerate() {
+            let expected_row = &expected[y * row_bytes..(y + 1) * row_bytes];
+            assert!(&row[offset..offset + row_bytes] == expected_row);
+            assert!(row[..offset].iter().all(|&b| b == 0xAB));
+            assert!(row[offset + row_bytes..].iter().all(|&b| b == 0xAB));
+        }
+    }
+
+    // the output buffer and row pitch are checked
+    let data = [0_u8; 13 * 6 * 4];
+    let mut output = vec![0_u8; row_bytes * 6 - 1];
+    let result = dds::decode_with_pitch(
+        &mut data.as_slice(),
+        &mut output,
+        row_bytes,
+        color,
+        size,
+        Format::R8G8B8A8_UNORM,
+        &DecodeOptions::default(),
+    );
+    assert!(matches!(
+        result,
+        Err(DecodeError::RectBufferTooSmall { .. })
+    ));
+    let result = dds::decode_with_pitch(
+        &mut data.as_slice(),
+        &mut output,
+        row_bytes - 1,
+        color,
+        size,
+        Format::R8G8B8A8_UNORM,
+        &DecodeOptions::default(),
+    );
+    assert!(matches!(result, Err(DecodeError::RowPitchTooSmall { .. })));
+}
+
+#[test]
+fn decode_top_rows() {
+    let size = Size::new(13, 11);
+    let color = ColorFormat::RGBA_U8;
+    let row_bytes = size.width as usize * 4;
+    let mut rng = util::create_rng();
+
+    // (format, number of rows read when decoding the top 6 rows)
+    for (format, read_rows) in [
+        (Format::R8G8B8A8_UNORM, 6),
+        (Format::BC1_UNORM, 8),
+        (Format::BC7_UNORM, 8),
+        (Format::YUY2, 6),
+        (Format::NV12, 11),
+    ] {
+        let surface_len = PixelInfo::from(format).surface_bytes(size).unwrap() as usize;
+        let mut data = vec![0_u8; surface_len];
+        rng.fill_bytes(&mut data);
+
+        let mut expected = vec![0_u8; row_bytes * size.height as usize];
+        let image = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+        dds::decode(
+            &mut data.as_slice(),
+            image,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+
+        for rows in [0, 4, 6, 11] {
+            let mut actual = vec![0_u8; row_bytes * rows];
+            let view = ImageViewMut::new(actual.as_mut_slice(), Size::new(13, rows as u32), color)
+                .unwrap();
+            let mut remaining = data.as_slice();
+            dds::decode_top_rows(
+                &mut remaining,
+                view,
+                size,
+                format,
+                &DecodeOptions::default(),
+            )
+            .unwrap();
+            assert!(
+                actual == expected[..actual.len()],
+                "Failed for {:?} with {} rows",
+                format,
+                rows
+            );
+
+            if rows == 6 {
+                let read = PixelInfo::from(format)
+                    .surface_bytes(Size::new(13, read_rows))
+                    .unwrap();
+                assert_eq!(
+                    (surface_len - remaining.len()) as u64,
+                    read,
+                    "Failed for {:?}",
+                    format
+                );
+            }
+        }
+    }
+
+    // the image must not be larger than the surface
+    let mut pixels = vec![0_u8; 13 * 12 * 4];
+    let view = ImageViewMut::new(pixels.as_mut_slice(), Size::new(13, 12), color).unwrap();
+    let result = dds::decode_top_rows(
+        &mut [0_u8; 0].as_slice(),
+        view,
+        size,
+        Format::BC1_UNORM,
+        &DecodeOptions::default(),
+    );
+    assert!(matches!(result, Err(DecodeError::UnexpectedSurfaceSize)));
+}
+
+#[test]
+fn decoder_read_texture() {
+    let format = Format::R8G8B8A8_UNORM;
+    let array: Header = match Header::new_image(8, 4, format).with_mipmaps() {
+        Header::Dx10(dx10) => dx10.with_array_size(2).into(),
+        Header::Dx9(_) => unreachable!(),
+    };
+    let volume = Header::new_volume(4, 4, 3, format).with_mipmaps();
+
+    let mut rng = util::create_rng();
+    for header in [array, volume] {
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mut file = Vec::new();
+        header.write(&mut file).unwrap();
+        let data_start = file.len();
+        file.resize(data_start + layout.data_len() as usize, 0);
+        rng.fill_bytes(&mut file[data_start..]);
+
+        // read all surfaces manually
+        let mut expected = Vec::new();
+        let mut decoder = Decoder::new(file.as_slice()).unwrap();
+        while let Some(info) = decoder.surface_info() {
+            let (size, level) = (info.size(), info.mipmap_level());
+            let mut data = vec![0_u16; size.pixels() as usize * 4];
+            let view = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U16).unwrap();
+            decoder.read_surface(view).unwrap();
+            expected.push((size, level, data));
+        }
+        let to_tuples = |surfaces: Vec<Surface<u16>>| {
+            surfaces
+                .into_iter()
+                .map(|s| {
+                    assert_eq!(s.color, ColorFormat::RGBA_U16);
+                    assert_eq!(s.view().size(), s.size);
+                    (s.size, s.mipmap_level, s.data)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut decoder = Decoder::new(file.as_slice()).unwrap();
+        let all = to_tuples(decoder.read_all().unwrap());
+        assert!(all == expected);
+        assert!(matches!(
+            decoder.read_texture::<u16>(),
+            Err(DecodeError::NoMoreSurfaces)
+        ));
+
+        // read_texture reads one element at a time
+        let mut decoder = Decoder::new(file.as_slice()).unwrap();
+        let mut textures = Vec::new();
+        while decoder.surface_info().is_some() {
+            textures.push(to_tuples(decoder.read_texture().unwrap()));
+        }
+        let mipmaps = header.mipmap_count().get() as usize;
+        if header.is_volume() {
+            assert_eq!(textures.len(), 1);
+            assert_eq!(textures[0].len(), 3 + 1 + 1);
+        } else {
+            assert_eq!(textures.len(), 2);
+            assert!(textures.iter().all(|t| t.len() == mipmaps));
+        }
+        assert!(textures.concat() == expected);
+    }
+}
+
+#[test]
+fn decoder_decode_layer_and_face() {
+    let format = Format::R8G8B8A8_UNORM;
+    let cube_array: Header = match Header::new_cube_map(8, 8, format).with_mipmaps() {
+        Header::Dx10(dx10) => dx10.with_array_size(2).into(),
+        Header::Dx9(_) => unreachable!(),
+    };
+    let partial_faces =
+        CubeMapFaces::POSITIVE_X | CubeMapFaces::POSITIVE_Y | CubeMapFaces::NEGATIVE_Z;
+    let partial: Header = Dx9Header::new_cube_map(8, 8, FourCC::DXT1.into())
+        .with_cube_map_faces(partial_faces)
+        .with_mipmap_count(std::num::NonZeroU32::new(2).unwrap())
+        .into();
+
+    let mut rng = util::create_rng();
+    for header in [cube_array, partial] {
+        for order in [MipOrder::LargestFirst, MipOrder::SmallestFirst] {
+            let layout = DataLayout::from_header(&header).unwrap();
+            let mut raw = header.to_raw();
+            order.apply_to_raw_header(&mut raw);
+            let mut file = Header::MAGIC.to_vec();
+            raw.write(&mut file).unwrap();
+            let data_start = file.len();
+            file.resize(data_start + layout.data_len() as usize, 0);
+            rng.fill_bytes(&mut file[data_start..]);
+
+            // read all surfaces in order, keyed by (layer, level)
+            let mipmaps = header.mipmap_count().get() as u8;
+            let mut expected = std::collections::HashMap::new();
+            let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+            let mut index = 0;
+            while let Some(info) = decoder.surface_info() {
+                let (size, level) = (info.size(), info.mipmap_level());
+                let mut data = vec![0_u8; size.pixels() as usize * 4];
+                let view =
+                    ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+                decoder.read_surface(view).unwrap();
+                expected.insert((index / mipmaps as usize, level), data);
+                index += 1;
+            }
+            let layers = index / mipmaps as usize;
+
+            let decode_layer = |decoder: &mut Decoder<Cursor<&[u8]>>, layer, level| {
+                let size = Size::new(8_u32 >> level, 8_u32 >> level);
+                let mut data = vec![0_u8; size.pixels() as usize * 4];
+                let view =
+                    ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+                decoder.decode_layer(layer, level, view).map(|_| data)
+            };
+
+            // random access in the middle of sequential reading
+            let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+            let size = decoder.surface_info().unwrap().size();
+            let mut data = vec![0_u8; size.pixels() as usize * 4];
+            let first = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8);
+            decoder.read_surface(first.unwrap()).unwrap();
+            for layer in (0..layers).rev() {
+                for level in 0..mipmaps {
+                    let actual = decode_layer(&mut decoder, layer, level).unwrap();
+                    assert!(actual == expected[&(layer, level)], "{:?}", (layer, level));
+                }
+            }
+            assert!(matches!(
+                decode_layer(&mut decoder, layers, 0),
+                Err(DecodeError::SurfaceNotFound)
+            ));
+            assert!(matches!(
+                decode_layer(&mut decoder, 0, mipmaps),
+                Err(DecodeError::SurfaceNotFound)
+            ));
+            // the decoder continues where it left off
+            let info = decoder.surface_info().unwrap();
+            let mut data = vec![0_u8; info.size().pixels() as usize * 4];
+            let level = info.mipmap_level();
+            let view =
+                ImageViewMut::new(data.as_mut_slice(), info.size(), ColorFormat::RGBA_U8).unwrap();
+            decoder.read_surface(view).unwrap();
+            let second = if order == MipOrder::LargestFirst {
+                1
+            } else {
+                mipmaps - 2
+            };
+            assert_eq!(level, second);
+            assert!(data == expected[&(0, level)]);
+
+            // faces
+            let faces: Vec<CubeFace> = if header.dx10().is_some() {
+                CubeFace::ALL.to_vec()
+            } else {
+                vec![
+                    CubeFace::PositiveX,
+                    CubeFace::PositiveY,
+                    CubeFace::NegativeZ,
+                ]
+            };
+            for (i, &face) in faces.iter().chain(faces.iter()).enumerate() {
+                let cube = i / faces.len();
+                let mut data = vec![0_u8; 8 * 8 * 4];
+                let view =
+                    ImageViewMut::new(data.as_mut_slice(), Size::new(8, 8), ColorFormat::RGBA_U8)
+                        .unwrap();
+                let result = decoder.decode_face(cube, face, 0, view);
+                if i < layers {
+                    result.unwrap();
+                    assert!(data == expected[&(i, 0)], "{:?}", (cube, face));
+                } else {
+                    assert!(matches!(result, Err(DecodeError::SurfaceNotFound)));
+                }
+            }
+            if header.dx10().is_none() {
+                let mut data = vec![0_u8; 8 * 8 * 4];
+                let view =
+                    ImageViewMut::new(data.as_mut_slice(), Size::new(8, 8), ColorFormat::RGBA_U8)
+                        .unwrap();
+                let result = decoder.decode_face(0, CubeFace::NegativeX, 0, view);
+                assert!(matches!(result, Err(DecodeError::SurfaceNotFound)));
+            }
+        }
+    }
+}
+
+#[test]
+fn decoder_decode_thumbnail() {
+    let mut rng = util::create_rng();
+    let header = Header::new_image(40, 24, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let data_start = file.len();
+    file.resize(data_start + layout.data_len() as usize, 0);
+    rng.fill_bytes(&mut file[data_start..]);
+
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    let mipmaps = decoder.read_texture::<u8>().unwrap();
+    assert_eq!(mipmaps.len(), 6);
+
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    for (max_size, level) in [
+        (Size::new(8, 8), 1),
+        (Size::new(10, 6), 2),
+        (Size::new(11, 6), 1),
+        (Size::new(1, 1), 5),
+        (Size::new(100, 100), 0),
+    ] {
+        assert_eq!(decoder.thumbnail_level(max_size), level, "{:?}", max_size);
+        let thumbnail = decoder.decode_thumbnail::<u8>(max_size).unwrap();
+        assert_eq!(thumbnail.mipmap_level, level);
+        assert_eq!(thumbnail.size, mipmaps[level as usize].size);
+        assert!(thumbnail.data == mipmaps[level as usize].data);
+    }
+    // the state of the decoder is unchanged
+    assert_eq!(decoder.surface_info().unwrap().mipmap_level(), 0);
+
+    // the first depth slice of volumes
+    let header = Header::new_volume(8, 8, 4, Format::R8_UNORM).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let data_start = file.len();
+    file.resize(data_start + layout.data_len() as usize, 0);
+    rng.fill_bytes(&mut file[data_start..]);
+    let level = layout.volume().unwrap().get(1).unwrap();
+    let slice = level.get_depth_slice(0).unwrap();
+    let offset = data_start + slice.data_offset() as usize;
+
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    let thumbnail = decoder.decode_thumbnail::<u8>(Size::new(3, 3)).unwrap();
+    assert_eq!(thumbnail.mipmap_level, 1);
+    assert_eq!(thumbnail.color, ColorFormat::GRAYSCALE_U8);
+    assert_eq!(thumbnail.data, file[offset..offset + 4 * 4]);
+
+    #[cfg(feature = "image")]
+    {
+        let decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+        let mut decoder = DdsDecoder::from_decoder(decoder);
+        let image = decoder.decode_thumbnail(Size::new(3, 3)).unwrap();
+        assert_eq!(image.color(), image::ColorType::L8);
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.as_bytes(), &file[offset..offset + 4 * 4]);
+    }
+}
+
+#[test]
+fn decoder_decode_depth_slices() {
+    let header = Header::new_volume(8, 4, 5, Format::BC1_UNORM).with_mipmaps();
+    let mut rng = util::create_rng();
+
+    for order in [MipOrder::LargestFirst, MipOrder::SmallestFirst] {
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mut raw = header.to_raw();
+        order.apply_to_raw_header(&mut raw);
+        let mut file = Header::MAGIC.to_vec();
+        raw.write(&mut file).unwrap();
+        let data_start = file.len();
+        file.resize(data_start + layout.data_len() as usize, 0);
+        rng.fill_bytes(&mut file[data_start..]);
+
+        // read all slices in order, keyed by level
+        let mut expected: Vec<Vec<Vec<u8>>> = vec![Vec::new(); 4];
+        let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+        while let Some(info) = decoder.surface_info() {
+            let (size, level) = (info.size(), info.mipmap_level());
+            let mut data = vec![0_u8; size.pixels() as usize * 4];
+            let view = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+            decoder.read_surface(view).unwrap();
+            expected[level as usize].push(data);
+        }
+
+        let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+        decoder.skip_surface().unwrap();
+        let volume = *decoder.layout().volume().unwrap();
+        for (level, slices) in expected.iter().enumerate() {
+            let size = volume.get(level as u8).unwrap().size();
+            let depth = slices.len() as u32;
+            for start in 0..depth {
+                for end in (start + 1)..=depth {
+                    let stacked = Size::new(size.width, size.height * (end - start));
+                    let mut data = vec![0_u8; stacked.pixels() as usize * 4];
+                    let view =
+                        ImageViewMut::new(data.as_mut_slice(), stacked, ColorFormat::RGBA_U8)
+                            .unwrap();
+                    decoder
+                        .decode_depth_slices(level as u8, start..end, view)
+                        .unwrap();
+                    assert!(data == slices[start as usize..end as usize].concat());
+                }
+            }
+
+            let mut data = vec![0_u8; size.pixels() as usize * 4];
+            let view = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+            let result = decoder.decode_depth_slices(level as u8, depth..depth + 1, view);
+            assert!(matches!(result, Err(DecodeError::SurfaceNotFound)));
+        }
+
+        // the decoder continues where it left off
+        let info = decoder.surface_info().unwrap();
+        let mut data = vec![0_u8; info.size().pixels() as usize * 4];
+        let level = info.mipmap_level() as usize;
+        let view =
+            ImageViewMut::new(data.as_mut_slice(), info.size(), ColorFormat::RGBA_U8).unwrap();
+        decoder.read_surface(view).unwrap();
+        assert!(data == expected[level][if level == 0 { 1 } else { 0 }]);
+    }
+
+    // not a volume
+    let file = {
+        let mut file = Vec::new();
+        Header::new_image(4, 4, Format::BC1_UNORM)
+            .write(&mut file)
+            .unwrap();
+        file.resize(file.len() + 8, 0);
+        file
+    };
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    let mut data = vec![0_u8; 4 * 4 * 4];
+    let view = ImageViewMut::new(data.as_mut_slice(), Size::new(4, 4), ColorFormat::RGBA_U8);
+    let result = decoder.decode_depth_slices(0, 0..1, view.unwrap());
+    assert!(matches!(result, Err(DecodeError::SurfaceNotFound)));
+}
+
+#[test]
+fn decode_truncated_fill() {
+    let mut rng = util::create_rng();
+    let fill = [1.0, 0.0, 0.5, 1.0];
+    let fill_u8 = [255_u8, 0, 128, 255];
+
+    for (format, size, cut) in [
+        // cut off within the second row
+        (Format::R8G8B8A8_UNORM, Size::new(5, 7), 5 * 4 + 3),
+        // cut off within the third row of blocks
+        (Format::BC1_UNORM, Size::new(8, 16), 2 * 16 + 5),
+        (Format::BC7_UNORM, Size::new(300, 300), 75 * 16 * 10),
+        // cut off before any data
+        (Format::BC3_UNORM, Size::new(4, 4), 0),
+        (Format::NV12, Size::new(4, 4), 20),
+    ] {
+        let pixel_info = PixelInfo::from(format);
+        let mut data = vec![0_u8; pixel_info.surface_bytes(size).unwrap() as usize];
+        rng.fill_bytes(&mut data);
+        let expected_rows = match pixel_info {
+            PixelInfo::Fixed { bytes_per_pixel } => {
+                (cut / (size.width as usize * bytes_per_pixel as usize)) as u32
+            }
+            PixelInfo::Block(block) => {
+                let block_width = block.size().0 as u32;
+                let blocks_per_row = ((size.width + block_width - 1) / block_width) as usize;
+                (cut / (blocks_per_row * block.bytes_per_block() as usize)) as u32 * 4
+            }
+            PixelInfo::BiPlanar(_) => 0,
+        };
+
+        let color = ColorFormat::RGBA_U8;
+        let mut expected = vec![0_u8; color.buffer_size(size).unwrap()];
+        let image = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+        dds::decode(
+            &mut data.as_slice(),
+            image,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+
+        // without the option, decoding fails
+        let mut output = vec![0_u8; expected.len()];
+        let image = ImageViewMut::new(output.as_mut_slice(), size, color).unwrap();
+        let result = dds::decode(&mut &data[..cut], image, format, &DecodeOptions::default());
+        assert!(matches!(result, Err(DecodeError::Io(_))));
+
+        let mut options = DecodeOptions::default();
+        options.truncated_fill = Some(fill);
+        let image = ImageViewMut::new(output.as_mut_slice(), size, color).unwrap();
+        let progress = dds::decode_partial(&mut &data[..cut], image, format, &options).unwrap();
+        assert_eq!(progress.decoded_rows, expected_rows, "{format:?}");
+        assert_eq!(progress.total_rows, size.height);
+
+        let split = expected_rows as usize * size.width as usize * 4;
+        assert!(output[..split] == expected[..split], "{format:?}");
+        for pixel in output[split..].chunks_exact(4) {
+            assert_eq!(pixel, fill_u8);
+        }
+
+        // full data decodes all rows
+        let image = ImageViewMut::new(output.as_mut_slice(), size, color).unwrap();
+        let progress = dds::decode_partial(&mut data.as_slice(), image, format, &options).unwrap();
+        assert_eq!(progress.decoded_rows, size.height);
+        assert!(output == expected);
+    }
+}
+
+#[test]
+fn decoder_truncated_fill() {
+    let header = Header::new_image(4, 8, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    // the first mipmap has 2 of its 8 rows
+    file.extend((0..2 * 4 * 4).map(|i| i as u8));
+
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    decoder.options.truncated_fill = Some([0.0; 4]);
+
+    let mut rows = Vec::new();
+    while let Some(info) = decoder.surface_info() {
+        let size = info.size();
+        let mut data = vec![1_u8; size.pixels() as usize * 4];
+        let image = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+        let progress = decoder.read_surface_partial(image).unwrap();
+        rows.push(progress.decoded_rows);
+
+        let split = progress.decoded_rows as usize * size.width as usize * 4;
+        assert!(data[split..].iter().all(|&b| b == 0));
+        if progress.decoded_rows > 0 {
+            assert_eq!(
+                data[..split],
+                (0..split).map(|i| i as u8).collect::<Vec<_>>()
+            );
+        }
+    }
+    assert_eq!(rows, [2, 0, 0, 0]);
+}
+
+#[test]
+fn bc7_decoders_agree() {
+    let mut rng = util::create_rng();
+    let size = Size::new(64, 64);
+    let mut data = vec![0_u8; 16 * 16 * 16];
+
+    for mode in 0..=8 {
+        rng.fill_bytes(&mut data);
+        for block in data.chunks_exact_mut(16) {
+            // set the mode bits
+            block[0] = if mode == 8 { 0 } else { (block[0] | 1) << mode };
+        }
+
+        for color in [
+            ColorFormat::RGBA_U8,
+            ColorFormat::RGB_U16,
+            ColorFormat::RGBA_F32,
+        ] {
+            let decode_with = |decoder: Bc7Decoder| {
+                let mut options = DecodeOptions::default();
+                options.bc7_decoder = decoder;
+                let mut output = vec![0_u8; color.buffer_size(size).unwrap()];
+                let image = ImageViewMut::new(output.as_mut_slice(), size, color).unwrap();
+                dds::decode(&mut data.as_slice(), image, Format::BC7_UNORM, &options).unwrap();
+                output
+            };
+
+            assert!(
+                decode_with(Bc7Decoder::TableDriven) == decode_with(Bc7Decoder::Reference),
+                "mode {mode} {color:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn decode_with_scratch_matches_decode() {
+    let mut rng = util::create_rng();
+    let mut scratch = DecodeScratch::new();
+
+    for format in [
+        Format::R8G8B8A8_UNORM,
+        Format::BC1_UNORM,
+        Format::BC7_UNORM,
+        Format::YUY2,
+        Format::NV12,
+        Format::P010,
+    ] {
+        for size in [Size::new(5, 3), Size::new(300, 200), Size::new(16, 16)] {
+            let pixel_info = PixelInfo::from(format);
+            let mut data = vec![0_u8; pixel_info.surface_bytes(size).unwrap() as usize];
+            rng.fill_bytes(&mut data);
+
+            for color in [
+                ColorFormat::RGBA_U8,
+                ColorFormat::RGB_U16,
+                ColorFormat::GRAYSCALE_F32,
+            ] {
+                let options = DecodeOptions::default();
+                let mut expected = vec![0_u8; color.buffer_size(size).unwrap()];
+                let image = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+                dds::decode(&mut data.as_slice(), image, format, &options).unwrap();
+
+                let mut output = vec![0_u8; expected.len()];
+                let image = ImageViewMut::new(output.as_mut_slice(), size, color).unwrap();
+                let mut reader = data.as_slice();
+                dds::decode_with_scratch(&mut reader, image, format, &options, &mut scratch)
+                    .unwrap();
+                assert!(reader.is_empty(), "{format:?} {size:?}");
+                assert!(output == expected, "{format:?} {size:?} {color:?}");
+            }
+        }
+    }
+
+    // enough scratch memory means no growth
+    let size = Size::new(256, 256);
+    let required = DecodeScratch::required_bytes(Format::NV12, size);
+    assert!(required >= 256 * 256);
+    let mut scratch = DecodeScratch::with_capacity(required);
+    let data = vec![0_u8; PixelInfo::from(Format::NV12).surface_bytes(size).unwrap() as usize];
+    let mut output = vec![0_u8; size.pixels() as usize * 4];
+    let image = ImageViewMut::new(output.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+    let options = DecodeOptions::default();
+    dds::decode_with_scratch(
+        &mut data.as_slice(),
+        image,
+        Format::NV12,
+        &options,
+        &mut scratch,
+    )
+    .unwrap();
+    assert_eq!(scratch.capacity(), required);
+
+    // uncompressed formats only need scratch memory for a single row
+    let required = DecodeScratch::required_bytes(Format::R8G8B8A8_UNORM, size);
+    assert_eq!(required, 256 * 4);
+}
+
+#[test]
+fn alpha_mode_round_trip() {
+    let size = Size::new(4, 4);
+    let pixels = vec![0_u8; 4 * 4 * 4];
+    let image = ImageView::new(pixels.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+
+    for mode in [
+        AlphaMode::Unknown,
+        AlphaMode::Straight,
+        AlphaMode::Premultiplied,
+        AlphaMode::Opaque,
+        AlphaMode::Custom,
+    ] {
+        let header = Header::new_image(size.width, size.height, Format::R8G8B8A8_UNORM)
+            .to_alpha_mode(mode)
+            .unwrap();
+        let mut file = Vec::new();
+        let mut encoder = Encoder::new(&mut file, Format::R8G8B8A8_UNORM, &header).unwrap();
+        encoder.write_surface(image).unwrap();
+        encoder.finish().unwrap();
+
+        let decoder = Decoder::new(file.as_slice()).unwrap();
+        assert_eq!(decoder.alpha_mode(), mode);
+    }
+
+    // DX9 headers are kept if possible
+    let dxt5: Header = Dx9Header::new_image(4, 4, FourCC::DXT5.into()).into();
+    let dxt4 = dxt5.to_alpha_mode(AlphaMode::Premultiplied).unwrap();
+    assert_eq!(dxt4.dx9().unwrap().pixel_format, FourCC::DXT4.into());
+    let custom = dxt5.to_alpha_mode(AlphaMode::Custom).unwrap();
+    assert_eq!(custom.dx10().unwrap().alpha_mode, AlphaMode::Custom);
+
+    // DX9-only formats can't declare alpha modes
+    let header = Header::new_image(4, 4, Format::R8G8B8_UNORM);
+    assert!(header.to_alpha_mode(AlphaMode::Opaque).is_none());
+}
+
+#[test]
+fn decoder_read_surface_aligned() {
+    let mut rng = util::create_rng();
+    let alignment = std::num::NonZeroU32::new(256).unwrap();
+
+    for format in [Format::R8G8B8_UNORM, Format::BC1_UNORM, Format::NV12] {
+        let size = Size::new(37, 9);
+        let header = Header::new_image(size.width, size.height, format);
+        let mut file = Vec::new();
+        header.write(&mut file).unwrap();
+        let mut data = vec![0_u8; PixelInfo::from(format).surface_bytes(size).unwrap() as usize];
+        rng.fill_bytes(&mut data);
+        file.extend_from_slice(&data);
+
+        for color in [ColorFormat::RGBA_U8, ColorFormat::RGB_F32] {
+            let mut expected = vec![0_u8; color.buffer_size(size).unwrap()];
+            let image = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+            Decoder::new(file.as_slice())
+                .unwrap()
+                .read_surface(image)
+                .unwrap();
+
+            let len = color.aligned_buffer_size(size, alignment).unwrap();
+            let mut output = vec![0xFF_u8; len];
+            let mut decoder = Decoder::new(file.as_slice()).unwrap();
+            let row_pitch = decoder
+                .read_surface_aligned(&mut output, color, alignment)
+                .unwrap();
+            assert_eq!(row_pitch % 256, 0);
+            assert_eq!(len, row_pitch * size.height as usize);
+            assert!(decoder.surface_info().is_none());
+
+            let row_bytes = size.width as usize * color.bytes_per_pixel() as usize;
+            for (row, expected) in output.chunks(row_pitch).zip(expected.chunks(row_bytes)) {
+                assert_eq!(&row[..row_bytes], expected, "{:?} {:?}", format, color);
+                assert!(row[row_bytes..].iter().all(|&b| b == 0));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn decode_image_crate() {
+    use image::{ColorType, ExtendedColorType, ImageDecoder};
+
+    let size = Size::new(7, 5);
+    let mut rng = util::create_rng();
+
+    let cases = [
+        (
+            Format::R8G8B8A8_UNORM,
+            ColorType::Rgba8,
+            ExtendedColorType::Rgba8,
+        ),
+        (
+            Format::BC1_UNORM,
+            ColorType::Rgba8,
+            ExtendedColorType::Rgba8,
+        ),
+        (Format::R8_UNORM, ColorType::L8, ExtendedColorType::L8),
+        (Format::A8_UNORM, ColorType::Rgba8, ExtendedColorType::A8),
+        (
+            Format::R16G16B16A16_UNORM,
+            ColorType::Rgba16,
+            ExtendedColorType::Rgba16,
+        ),
+        (
+            Format::BC6H_UF16,
+            ColorType::Rgb16,
+            ExtendedColorType::Rgb32F,
+        ),
+        (
+            Format::R32G32B32A32_FLOAT,
+            ColorType::Rgba16,
+            ExtendedColorType::Rgba32F,
+        ),
+    ];
+    for (format, color_type, original) in cases {
+        let header = Header::new_image(size.width, size.height, format);
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mut file = Vec::new();
+        header.write(&mut file).unwrap();
+        let mut data = vec![0_u8; layout.data_len() as usize];
+        rng.fill_bytes(&mut data);
+        file.extend_from_slice(&data);
+
+        let decoder = DdsDecoder::new(file.as_slice()).unwrap();
+        assert_eq!(decoder.dimensions(), (size.width, size.height));
+        assert_eq!(decoder.color_type(), color_type, "{:?}", format);
+        assert_eq!(decoder.original_color_type(), original, "{:?}", format);
+        let color = decoder.color();
+
+        let image = image::DynamicImage::from_decoder(decoder).unwrap();
+        assert_eq!(image.color(), color_type);
+
+        let mut expected = vec![0_u8; color.buffer_size(size).unwrap()];
+        let view = ImageViewMut::new(expected.as_mut_slice(), size, color).unwrap();
+        format
+            .decode_from_slice(&data, view, &DecodeOptions::default())
+            .unwrap();
+        assert_eq!(image.as_bytes(), expected.as_slice(), "{:?}", format);
+    }
+
+    // errors are converted to image errors
+    let header = Header::new_image(size.width, size.height, Format::BC1_UNORM);
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let decoder = DdsDecoder::new(file.as_slice()).unwrap();
+    let result = image::DynamicImage::from_decoder(decoder);
+    assert!(matches!(result, Err(image::ImageError::IoError(_))));
+}
+
+#[test]
+fn decoder_decode_all_parallel() {
+    let mut rng = util::create_rng();
+    let headers = [
+        Header::new_cube_map(12, 12, Format::BC1_UNORM).with_mipmaps(),
+        Header::new_volume(8, 6, 4, Format::R8G8B8A8_UNORM).with_mipmaps(),
+    ];
+    for header in headers {
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mut file = Vec::new();
+        header.write(&mut file).unwrap();
+        let data_start = file.len();
+        file.resize(data_start + layout.data_len() as usize, 0);
+        rng.fill_bytes(&mut file[data_start..]);
+
+        let mut decoder = Decoder::new(file.as_slice()).unwrap();
+        let expected = decoder.read_all::<u16>().unwrap();
+
+        let mut decoder = Decoder::from_slice(&file).unwrap();
+        let surfaces = decoder.decode_all_parallel::<u16>().unwrap();
+        assert_eq!(surfaces, expected);
+        assert!(decoder.surface_info().is_none());
+        assert!(decoder.remaining_data().is_empty());
+    }
+
+    // errors don't advance the decoder
+    let header = Header::new_image(4, 4, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    file.extend_from_slice(&[0; 4 * 4 * 4]);
+    let mut decoder = Decoder::from_slice(&file).unwrap();
+    assert!(decoder.decode_all_parallel::<u8>().is_err());
+    assert_eq!(decoder.surface_info().unwrap().mipmap_level(), 0);
+    assert_eq!(decoder.remaining_data().len(), 4 * 4 * 4);
+
+    // the maximum output size applies to all surfaces together
+    file.extend_from_slice(&[0; 2 * 2 * 4 + 4]);
+    let mut decoder = Decoder::from_slice(&file).unwrap();
+    decoder.options.max_output_size = 4 * 4 * 4 + 2 * 2 * 4;
+    let error = decoder.decode_all_parallel::<u8>().unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::OutputSizeExceeded
+    ));
+    assert_eq!(error.location().unwrap().mipmap_level, 2);
+    decoder.options.max_output_size = 4 * 4 * 4 + 2 * 2 * 4 + 4;
+    assert_eq!(decoder.decode_all_parallel::<u8>().unwrap().len(), 3);
+}
+
+#[test]
+fn decoder_error_location() {
+    // a cube map whose data is cut off in the mipmaps of the third face
+    let header = Header::new_cube_map(8, 8, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let face_len = layout.data_len() / 6;
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let data_len = face_len * 2 + 8 * 8 * 4 + 10;
+    file.resize(file.len() + data_len as usize, 0);
+
+    let check = |result: Result<Vec<Surface<u8>>, DecodeError>| {
+        let error = result.unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.element, 2);
+        assert_eq!(location.mipmap_level, 1);
+        assert_eq!(location.depth_slice, 0);
+        assert_eq!(location.offset, face_len * 2 + 8 * 8 * 4);
+        assert!(matches!(error.without_location(), DecodeError::Io(_)));
+        assert!(error.to_string().contains("element 2, mipmap level 1"));
+    };
+
+    let mut decoder = Decoder::new(file.as_slice()).unwrap();
+    check(decoder.read_all());
+    let mut decoder = Decoder::from_slice(&file).unwrap();
+    check(decoder.decode_all_parallel());
+
+    // single surfaces are returned without location
+    let mut decoder = Decoder::new(&file[..file.len() - data_len as usize]).unwrap();
+    let mut buffer = vec![0_u8; 8 * 8 * 4];
+    let image =
+        ImageViewMut::new(buffer.as_mut_slice(), Size::new(8, 8), ColorFormat::RGBA_U8).unwrap();
+    let error = decoder.read_surface(image).unwrap_err();
+    assert!(error.location().is_none());
+}
+
+#[test]
+fn decoder_read_all_skip_errors() {
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    /// A reader that fails to read the given range of bytes, like a file
+    /// with a bad sector.
+    struct BadSector {
+        inner: Cursor<Vec<u8>>,
+        bad: std::ops::Range<u64>,
+    }
+    impl Read for BadSector {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let start = self.inner.position();
+            let end = start + buf.len() as u64;
+            if start < self.bad.end && self.bad.start < end {
+                return Err(std::io::Error::new(std::io::ErrorKind::Other, "bad sector"));
+            }
+            self.inner.read(buf)
+        }
+    }
+    impl Seek for BadSector {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    let mut rng = util::create_rng();
+    let header = Header::new_texture_array(8, 8, 2, Format::R8G8B8A8_UNORM)
+        .unwrap()
+        .with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let data_start = file.len() as u64;
+    file.resize(file.len() + layout.data_len() as usize, 0);
+    rand::RngCore::fill_bytes(&mut rng, &mut file[data_start as usize..]);
+
+    let mut decoder = Decoder::new(file.as_slice()).unwrap();
+    let expected = decoder.read_all::<u8>().unwrap();
+
+    // without errors, everything is decoded
+    let mut decoder = Decoder::new(Cursor::new(file.clone())).unwrap();
+    let recovered = decoder.read_all_skip_errors::<u8>([0.0; 4]).unwrap();
+    assert!(recovered.is_complete());
+    assert_eq!(recovered.surfaces, expected);
+
+    // the level 0 surface of the second texture can't be read
+    let texture_len = layout.data_len() / 2;
+    let bad_start = data_start + texture_len + 10;
+    let reader = BadSector {
+        inner: Cursor::new(file.clone()),
+        bad: bad_start..bad_start + 1,
+    };
+    let mut decoder = Decoder::new(reader).unwrap();
+    let placeholder = [1.0, 0.0, 1.0, 1.0];
+    let recovered = decoder.read_all_skip_errors::<u8>(placeholder).unwrap();
+    assert_eq!(recovered.surfaces.len(), expected.len());
+    assert!(decoder.surface_info().is_none());
+
+    let damaged = expected.len() / 2;
+    assert_eq!(recovered.errors.len(), 1);
+    let (index, error) = &recovered.errors[0];
+    assert_eq!(*index, damaged);
+    let location = error.location().unwrap();
+    assert_eq!((location.element, location.mipmap_level), (1, 0));
+    assert!(matches!(error.without_location(), DecodeError::Io(_)));
+
+    for (i, (surface, expected)) in recovered.surfaces.iter().zip(&expected).enumerate() {
+        if i == damaged {
+            assert_eq!(surface.size, expected.size);
+            assert!(surface.data.chunks(4).all(|p| p == [255, 0, 255, 255]));
+        } else {
+            assert_eq!(surface, expected, "surface {}", i);
+        }
+    }
+
+    // truncated files fail all remaining surfaces
+    let cut = data_start as usize + texture_len as usize;
+    let mut decoder = Decoder::new(Cursor::new(file[..cut].to_vec())).unwrap();
+    let recovered = decoder.read_all_skip_errors::<u8>([0.0; 4]).unwrap();
+    assert_eq!(recovered.surfaces[..damaged], expected[..damaged]);
+    let indexes: Vec<usize> = recovered.errors.iter().map(|(i, _)| *i).collect();
+    assert_eq!(indexes, (damaged..expected.len()).collect::<Vec<_>>());
+
+    // the maximum output size applies to all surfaces together
+    let mut decoder = Decoder::new(Cursor::new(file.clone())).unwrap();
+    decoder.options.max_output_size = 8 * 8 * 4 + 4 * 4 * 4;
+    let error = decoder.read_all_skip_errors::<u8>([0.0; 4]).unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::OutputSizeExceeded
+    ));
+
+    // huge declared sizes fail instead of allocating, even without header limits
+    let header = Header::new_image(65536, 65536, Format::R32G32B32A32_FLOAT);
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let mut decoder =
+        Decoder::new_with_options(Cursor::new(file), &unlimited_parse_options()).unwrap();
+    let error = decoder.read_all_skip_errors::<f32>([0.0; 4]).unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::OutputSizeExceeded
+    ));
+}
+
+fn unlimited_parse_options() -> ParseOptions {
+    ParseOptions::default()
+        .with_max_width(u32::MAX)
+        .with_max_height(u32::MAX)
+        .with_max_pixels(u64::MAX)
+}
+
+#[test]
+fn decoder_owned_surfaces_max_output_size() {
+    // 8x8 + 4x4 + 2x2 + 1x1 RGBA8 pixels = 340 bytes
+    let header = Header::new_image(8, 8, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    file.resize(file.len() + layout.data_len() as usize, 0);
+
+    let read_all = |max_output_size: u64| {
+        let mut decoder = Decoder::new(file.as_slice()).unwrap();
+        decoder.options.max_output_size = max_output_size;
+        decoder.read_all::<u8>()
+    };
+    let read_texture = |max_output_size: u64| {
+        let mut decoder = Decoder::new(file.as_slice()).unwrap();
+        decoder.options.max_output_size = max_output_size;
+        decoder.read_texture::<u8>()
+    };
+
+    // the limit applies to all surfaces together
+    assert!(read_all(340).is_ok());
+    assert!(read_texture(340).is_ok());
+    for result in [read_all(300), read_texture(300)] {
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.without_location(),
+            DecodeError::OutputSizeExceeded
+        ));
+        assert_eq!(error.location().unwrap().mipmap_level, 1);
+    }
+
+    // huge declared sizes fail before allocating, even without header limits
+    let header = Header::new_image(65536, 65536, Format::R32G32B32A32_FLOAT);
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let mut decoder =
+        Decoder::new_with_options(file.as_slice(), &unlimited_parse_options()).unwrap();
+    let error = decoder.read_all::<f32>().unwrap_err();
+    assert!(matches!(
+        error.without_location(),
+        DecodeError::OutputSizeExceeded
+    ));
+}
+
+#[test]
+fn decoder_owned_surfaces_large() {
+    // a 4K RGBA8 surface (64 MiB) is larger than the default memory limit,
+    // but owned surfaces are only limited by the maximum output size
+    let header = Header::new_image(4096, 4096, Format::BC1_UNORM);
+    let layout = DataLayout::from_header(&header).unwrap();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    file.resize(file.len() + layout.data_len() as usize, 0);
+    let surface_len = 4096 * 4096 * 4;
+    assert!(surface_len > DecodeOptions::default().memory_limit);
+
+    let mut decoder = Decoder::new(file.as_slice()).unwrap();
+    let surfaces = decoder.read_texture::<u8>().unwrap();
+    assert_eq!(surfaces[0].data.len(), surface_len);
+
+    let mut decoder = Decoder::new(file.as_slice()).unwrap();
+    assert_eq!(decoder.read_all::<u8>().unwrap()[0].data.len(), surface_len);
+
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    let thumbnail = decoder.decode_thumbnail::<u8>(Size::new(128, 128)).unwrap();
+    assert_eq!(thumbnail.data.len(), surface_len);
+
+    let mut decoder = Decoder::new(Cursor::new(file.as_slice())).unwrap();
+    let recovered = decoder.read_all_skip_errors::<u8>([0.0; 4]).unwrap();
+    assert!(recovered.errors.is_empty());
+    assert_eq!(recovered.surfaces[0].data.len(), surface_len);
+
+    let mut decoder = Decoder::from_slice(&file).unwrap();
+    let surfaces = decoder.decode_all_parallel::<u8>().unwrap();
+    assert_eq!(surfaces[0].data.len(), surface_len);
+}
+
+#[test]
+fn decode_options_eq_hash() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let token = CancellationToken::new();
+    let options = DecodeOptions::default()
+        .with_tone_mapping(ToneMapping::new(ToneMapCurve::Aces).with_exposure(2.0))
+        .with_truncated_fill(Some([f32::NAN, 0.0, 0.0, 1.0]))
+        .with_cancellation(Some(token.clone()));
+
+    // floats are compared by bits, so NaN fills are equal to themselves
+    assert_eq!(options, options.clone());
+    assert_ne!(options, options.clone().with_truncated_fill(Some([0.0; 4])));
+    assert_ne!(
+        options,
+        options
+            .clone()
+            .with_truncated_fill(Some([f32::NAN, -0.0, 0.0, 1.0]))
+    );
+    // tokens are equal if they are clones of each other
+    assert_ne!(
+        options,
+        options
+            .clone()
+            .with_cancellation(Some(CancellationToken::new()))
+    );
+
+    let hash = |options: &DecodeOptions| {
+        let mut hasher = DefaultHasher::new();
+        options.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(hash(&options), hash(&options.clone()));
+    assert_ne!(hash(&options), hash(&DecodeOptions::default()));
+}
@@ -666,6 +666,216 @@ fn encode_mipmap() {
     summaries.snapshot_or_fail();
 }
 
+#[test]
+fn encode_mipmap_custom_filter() {
+    fn encode_with_filter(base: &Image<u8>, filter: ResizeFilter) -> Vec<u8> {
+        let format = Format::R8G8B8A8_UNORM;
+        let header = Header::new_image(base.size.width, base.size.height, format).with_mipmaps();
+        let options = WriteOptions {
+            generate_mipmaps: true,
+            resize_filter: filter,
+            ..WriteOptions::default()
+        };
+
+        let mut encoded = Vec::new();
+        let mut encoder = Encoder::new(&mut encoded, format, &header).unwrap();
+        encoder
+            .write_surface_with(base.view(), |_| {}, &options)
+            .unwrap();
+        encoder.finish().unwrap();
+        encoded
+    }
+
+    let base = util::read_png_u8(&get_sample("base.png")).unwrap();
+
+    // a custom triangle kernel must produce the same result as the built-in one
+    fn triangle(x: f32) -> f32 {
+        (1.0 - x.abs()).max(0.0)
+    }
+    let custom = ResizeFilter::Custom(FilterKernel::new(triangle, 1.0));
+    assert_eq!(
+        encode_with_filter(&base, custom),
+        encode_with_filter(&base, ResizeFilter::Triangle)
+    );
+
+    // Kaiser is a windowed sinc, so it should be close to, but not exactly, Lanczos
+    let kaiser = encode_with_filter(
+        &base,
+        ResizeFilter::Kaiser {
+            radius: 3.0,
+            alpha: 4.0,
+        },
+    );
+    let lanczos = encode_with_filter(&base, ResizeFilter::Lanczos3);
+    assert_eq!(kaiser.len(), lanczos.len());
+    assert_ne!(kaiser, lanczos);
+    let total_diff: u64 = kaiser
+        .iter()
+        .zip(&lanczos)
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum();
+    let mean_diff = total_diff as f64 / kaiser.len() as f64;
+    assert!(mean_diff < 1.0, "mean diff: {}", mean_diff);
+
+    // invalid parameters are rejected before any surface is written
+    let invalid = [
+        ResizeFilter::Kaiser {
+            radius: f32::NAN,
+            alpha: 4.0,
+        },
+        ResizeFilter::Kaiser {
+            radius: -1.0,
+            alpha: 4.0,
+        },
+        ResizeFilter::Kaiser {
+            radius: 3.0,
+            alpha: f32::INFINITY,
+        },
+        ResizeFilter::Custom(FilterKernel::new(triangle, f32::NAN)),
+        ResizeFilter::Custom(FilterKernel::new(triangle, -1.0)),
+    ];
+    let header =
+        Header::new_image(base.size.width, base.size.height, Format::R8G8B8A8_UNORM).with_mipmaps();
+    for filter in invalid {
+        assert!(!filter.is_valid());
+        let options = WriteOptions {
+            generate_mipmaps: true,
+            resize_filter: filter,
+            ..WriteOptions::default()
+        };
+        let mut encoded = Vec::new();
+        let mut encoder = Encoder::new(&mut encoded, Format::R8G8B8A8_UNORM, &header).unwrap();
+        let result = encoder.write_surface_with(base.view(), |_| {}, &options);
+        assert!(matches!(result, Err(EncodeError::InvalidResizeFilter)));
+    }
+}
+
+#[test]
+fn encode_linear_light_dithering() {
+    fn srgb_to_linear(c: f32) -> f32 {
+        if c >= 0.04045 {
+            ((c + 0.055) / 1.055).powf(2.4)
+        } else {
+            c / 12.92
+        }
+    }
+    fn mean_linear_error(linear_light: bool, value: f32) -> f32 {
+        let size = Size::new(64, 64);
+        let image = Image::new(vec![value; size.pixels() as usize * 3], Channels::Rgb, size);
+
+        let mut options = EncodeOptions::default();
+        options.dithering = Dithering::Color;
+        options.linear_light_dithering = linear_light;
+        let (_, decoded) = encode_decode(Format::B5G6R5_UNORM, &options, &image);
+
+        let expected = srgb_to_linear(value);
+        let mean = decoded.data.iter().map(|&c| srgb_to_linear(c)).sum::<f32>()
+            / decoded.data.len() as f32;
+        (mean - expected).abs()
+    }
+
+    // dark values between two quantization steps
+    for value in [0.05, 0.09, 0.15, 0.3] {
+        let srgb = mean_linear_error(false, value);
+        let linear = mean_linear_error(true, value);
+        assert!(
+            linear < srgb,
+            "value {}: linear error {} >= sRGB error {}",
+            value,
+            linear,
+            srgb
+        );
+    }
+}
+
+#[test]
+fn encode_dithering_per_channel() {
+    // smooth gradients in all channels, so dithering changes most pixels
+    let size = Size::new(32, 32);
+    let mut data = Vec::new();
+    for y in 0..size.height {
+        for x in 0..size.width {
+            let t = (x + y) as f32 / (size.width + size.height) as f32;
+            data.extend([t, 1.0 - t, t * 0.5, t * 0.8 + 0.1]);
+        }
+    }
+    let image = Image::new(data, Channels::Rgba, size);
+
+    let decode_with = |format: Format, dithering: Dithering| {
+        let mut options = EncodeOptions::default();
+        options.dithering = dithering;
+        encode_decode(format, &options, &image).1
+    };
+    let channel = |image: &Image<f32>, c: usize| -> Vec<f32> {
+        image.data.iter().skip(c).step_by(4).copied().collect()
+    };
+    let color = |image: &Image<f32>| -> Vec<f32> {
+        image
+            .data
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect()
+    };
+
+    for format in [
+        Format::B4G4R4A4_UNORM,
+        Format::B5G5R5A1_UNORM,
+        Format::BC1_UNORM,
+        Format::BC2_UNORM,
+        Format::BC3_UNORM,
+    ] {
+        let none = decode_with(format, Dithering::None);
+        let color_only = decode_with(format, Dithering::Color);
+        let alpha_only = decode_with(format, Dithering::Alpha);
+
+        // alpha dithering must not affect color and vice versa. BC1 is an
+        // exception, because transparent pixels are always black.
+        if format != Format::BC1_UNORM {
+            assert_eq!(color(&none), color(&alpha_only), "{format:?}");
+        }
+        assert_eq!(channel(&none, 3), channel(&color_only, 3), "{format:?}");
+
+        assert_ne!(color(&none), color(&color_only), "{format:?}");
+        assert_ne!(channel(&none, 3), channel(&alpha_only, 3), "{format:?}");
+    }
+}
+
+#[test]
+fn encode_extend_edges() {
+    let size = Size::new(5, 3);
+    let mut rng = util::create_rng();
+    let mut image = Image::new_empty(Channels::Rgb, size);
+    rng.fill(image.data.as_mut_slice());
+
+    for format in [Format::NV12, Format::P010, Format::P016] {
+        assert!(!size.is_multiple_of(format.size_multiple()));
+
+        let mut output = Vec::new();
+        let result = encode_image(&image, format, &mut output, &EncodeOptions::default());
+        assert!(matches!(result, Err(EncodeError::InvalidSize(_))));
+
+        let mut options = EncodeOptions::default();
+        options.extend_edges = true;
+        let (encoded, decoded) = encode_decode(format, &options, &image);
+
+        let header_len = Header::MAGIC.len() + Header::new_image(5, 3, format).byte_len();
+        let surface_bytes = PixelInfo::from(format).surface_bytes(size).unwrap();
+        assert_eq!(encoded.len(), header_len + surface_bytes as usize);
+
+        // luma is stored per pixel, so the decoded image should still be close
+        let max_luma_error = decoded
+            .data
+            .chunks_exact(3)
+            .zip(image.data.chunks_exact(3))
+            .map(|(a, b)| {
+                let luma = |p: &[f32]| 0.299 * p[0] + 0.587 * p[1] + 0.114 * p[2];
+                (luma(a) - luma(b)).abs()
+            })
+            .fold(0.0_f32, f32::max);
+        assert!(max_luma_error < 0.5, "{:?}: {}", format, max_luma_error);
+    }
+}
+
 #[test]
 fn test_unaligned() {
     // aligned and unaligned buffers
@@ -743,3 +953,1577 @@ fn test_unaligned() {
         }
     }
 }
+
+#[test]
+fn encode_smallest_first_mipmaps() {
+    let mut rng = util::create_rng();
+    let format = Format::R8G8B8A8_UNORM;
+
+    let array: Header = Header::new_image(16, 8, format).with_mipmaps();
+    let array = match array {
+        Header::Dx10(dx10) => dx10.with_array_size(3).into(),
+        Header::Dx9(_) => unreachable!(),
+    };
+    let volume = Header::new_volume(8, 4, 3, format).with_mipmaps();
+
+    for header in [array, volume] {
+        let encode_with = |order: MipOrder| {
+            let mut encoded = Vec::new();
+            let mut encoder =
+                Encoder::new_with_mip_order(&mut encoded, format, &header, order).unwrap();
+            let write_options = WriteOptions {
+                generate_mipmaps: true,
+                ..Default::default()
+            };
+
+            let mut rng = util::create_rng();
+            while let Some(info) = encoder.surface_info() {
+                let size = info.size();
+                let mut data = vec![0_u8; size.pixels() as usize * 4];
+                rng.fill_bytes(&mut data);
+                let image = ImageView::new(data.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+                encoder
+                    .write_surface_with(image, |_| {}, &write_options)
+                    .unwrap();
+            }
+            encoder.finish().unwrap();
+            encoded
+        };
+        let standard = encode_with(MipOrder::LargestFirst);
+        let reversed = encode_with(MipOrder::SmallestFirst);
+        assert_eq!(standard.len(), reversed.len());
+
+        // decode all surfaces in file order
+        let decode_all = |encoded: &[u8]| {
+            let mut decoder = Decoder::new(encoded).unwrap();
+            let mut surfaces = Vec::new();
+            while let Some(info) = decoder.surface_info() {
+                let level = info.mipmap_level();
+                let size = info.size();
+                let mut data = vec![0_u8; size.pixels() as usize * 4];
+                let view =
+                    ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+                decoder.read_surface(view).unwrap();
+                surfaces.push((level, data));
+            }
+            (decoder.info().mip_order(), surfaces)
+        };
+        let (standard_order, standard) = decode_all(&standard);
+        let (reversed_order, reversed) = decode_all(&reversed);
+        assert_eq!(standard_order, MipOrder::LargestFirst);
+        assert_eq!(reversed_order, MipOrder::SmallestFirst);
+
+        // levels are reversed within each texture/volume
+        let mipmaps = header.mipmap_count().get() as u8;
+        let element_len = standard.len() / header.array_size() as usize;
+        for (s, r) in standard
+            .chunks(element_len)
+            .zip(reversed.chunks(element_len))
+        {
+            assert_eq!(r[0].0, mipmaps - 1);
+            let mut r = r.to_vec();
+            r.sort_by_key(|(level, _)| *level);
+            assert!(r == s);
+        }
+    }
+
+    // skipping mipmaps jumps to the level 0 surface
+    let header = Header::new_image(16, 8, format).with_mipmaps();
+    let mut encoded = Vec::new();
+    let mut encoder =
+        Encoder::new_with_mip_order(&mut encoded, format, &header, MipOrder::SmallestFirst)
+            .unwrap();
+    let mut data = vec![0_u8; 16 * 8 * 4];
+    rng.fill_bytes(&mut data);
+    let image = ImageView::new(data.as_slice(), Size::new(16, 8), ColorFormat::RGBA_U8).unwrap();
+    let write_options = WriteOptions {
+        generate_mipmaps: true,
+        ..Default::default()
+    };
+    encoder
+        .write_surface_with(image, |_| {}, &write_options)
+        .unwrap();
+    encoder.finish().unwrap();
+
+    let mut decoder = Decoder::new(std::io::Cursor::new(encoded.as_slice())).unwrap();
+    decoder.skip_mipmaps().unwrap();
+    assert_eq!(decoder.surface_info().unwrap().mipmap_level(), 0);
+    let mut output = vec![0_u8; 16 * 8 * 4];
+    let view = ImageViewMut::new(
+        output.as_mut_slice(),
+        Size::new(16, 8),
+        ColorFormat::RGBA_U8,
+    )
+    .unwrap();
+    decoder.read_surface(view).unwrap();
+    assert_eq!(output, data);
+    assert!(decoder.surface_info().is_none());
+}
+
+#[test]
+fn encode_fallback() {
+    let mut rng = util::create_rng();
+    let mut image: Image<u8> = Image::new_empty(Channels::Rgba, Size::new(5, 3));
+    rng.fill(image.data.as_mut_slice());
+
+    let encode = |formats: &[Format], options: &EncodeOptions| {
+        let mut output = Vec::new();
+        let result = encode_with_fallback(&mut output, image.view(), formats, options);
+        (result, output)
+    };
+
+    // formats without encoders and with incompatible sizes are skipped
+    let formats = [Format::ASTC_4X4_UNORM, Format::NV12, Format::BC1_UNORM];
+    let (result, output) = encode(&formats, &EncodeOptions::default());
+    assert_eq!(result.unwrap(), Format::BC1_UNORM);
+    let mut expected = Vec::new();
+    encode_image(
+        &image,
+        Format::BC1_UNORM,
+        &mut expected,
+        &EncodeOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(output, expected);
+
+    // extending edges makes NV12 acceptable
+    let mut options = EncodeOptions::default();
+    options.extend_edges = true;
+    let (result, _) = encode(&formats, &options);
+    assert_eq!(result.unwrap(), Format::NV12);
+
+    // the error of the last format is returned if none accept the image
+    let (result, output) = encode(&[Format::NV12, Format::ASTC_4X4_UNORM], &Default::default());
+    assert!(matches!(
+        result,
+        Err(EncodeError::UnsupportedFormat(Format::ASTC_4X4_UNORM))
+    ));
+    assert!(output.is_empty());
+    let (result, output) = encode(&[Format::ASTC_4X4_UNORM, Format::P010], &Default::default());
+    assert!(matches!(result, Err(EncodeError::InvalidSize(_))));
+    assert!(output.is_empty());
+    let (result, output) = encode(&[], &Default::default());
+    assert!(matches!(result, Err(EncodeError::NoFormats)));
+    assert!(output.is_empty());
+
+    // formats that would clamp out-of-range values are skipped
+    let mut hdr: Image<f32> = Image::new_empty(Channels::Rgba, Size::new(4, 4));
+    hdr.data[0] = 2.0;
+    let mut options = EncodeOptions::default();
+    options.reject_out_of_range = true;
+    let formats = [Format::BC1_UNORM, Format::R16G16B16A16_FLOAT];
+    let mut output = Vec::new();
+    let result = encode_with_fallback(&mut output, hdr.view(), &formats, &options);
+    assert_eq!(result.unwrap(), Format::R16G16B16A16_FLOAT);
+    let mut output = Vec::new();
+    let result = encode_with_fallback(&mut output, hdr.view(), &formats[..1], &options);
+    assert!(matches!(result, Err(EncodeError::OutOfRange(_))));
+    assert!(output.is_empty());
+}
+
+#[test]
+fn encode_out_of_range() {
+    let size = Size::new(4, 4);
+    let mut image: Image<f32> = Image::new_empty(Channels::Rgba, size);
+    for (i, value) in image.data.iter_mut().enumerate() {
+        *value = i as f32 / 63.0;
+    }
+
+    // in-range images have no out-of-range values
+    let stats = OutOfRangeStats::collect(image.view());
+    assert!(stats.is_in_range());
+    assert_eq!((stats.min, stats.max), (0.0, 1.0));
+
+    image.data[5] = 2.5;
+    image.data[9] = -0.5;
+    image.data[11] = f32::NAN;
+    let stats = OutOfRangeStats::collect(image.view());
+    assert_eq!(stats.count, 3);
+    assert_eq!((stats.min, stats.max), (-0.5, 2.5));
+
+    // values are clamped by default
+    let mut output = Vec::new();
+    encode_image(
+        &image,
+        Format::R8G8B8A8_UNORM,
+        &mut output,
+        &EncodeOptions::default(),
+    )
+    .unwrap();
+
+    let mut options = EncodeOptions::default();
+    options.reject_out_of_range = true;
+    for format in [Format::R8G8B8A8_UNORM, Format::BC1_UNORM] {
+        let mut output = Vec::new();
+        let result = encode_image(&image, format, &mut output, &options);
+        assert!(matches!(result, Err(EncodeError::OutOfRange(s)) if s.count == 3));
+        assert!(output.is_empty());
+
+        let mut output = Vec::new();
+        let result = split_encode(&mut output, image.view(), format, &options);
+        assert!(matches!(result, Err(EncodeError::OutOfRange(_))));
+        assert!(output.is_empty());
+    }
+
+    // formats that store floats keep HDR values
+    let mut output = Vec::new();
+    encode_image(&image, Format::R16G16B16A16_FLOAT, &mut output, &options).unwrap();
+
+    // generated mipmaps are not checked
+    image.data[5] = 1.0;
+    image.data[9] = 0.0;
+    image.data[11] = 0.0;
+    let header = Header::new_image(size.width, size.height, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let mut encoder = Encoder::new(Vec::new(), Format::R8G8B8A8_UNORM, &header).unwrap();
+    encoder.options = options;
+    let write_options = WriteOptions {
+        generate_mipmaps: true,
+        resize_filter: ResizeFilter::Lanczos3,
+        ..Default::default()
+    };
+    encoder
+        .write_surface_with(image.view(), |_| {}, &write_options)
+        .unwrap();
+    encoder.finish().unwrap();
+}
+
+#[test]
+fn encode_u32() {
+    let size = Size::new(8, 4);
+    let mut rng = util::create_rng();
+    let mut image: Image<u16> = Image::new_empty(Channels::Rgba, size);
+    rng.fill(image.data.as_mut_slice());
+    let image_u32: Vec<u32> = image.data.iter().map(|&x| x as u32 * 65537).collect();
+    let view_u32 = ImageView::new(image_u32.as_slice(), size, ColorFormat::RGBA_U32).unwrap();
+
+    for format in [
+        Format::R16G16B16A16_UNORM,
+        Format::R8G8B8A8_UNORM,
+        Format::R32G32B32A32_FLOAT,
+        Format::BC1_UNORM,
+    ] {
+        let mut expected = Vec::new();
+        encode_image(&image, format, &mut expected, &EncodeOptions::default()).unwrap();
+        let mut actual = Vec::new();
+        encode(&mut actual, view_u32, format, &EncodeOptions::default()).unwrap();
+        assert_eq!(actual, expected, "Failed for {:?}", format);
+    }
+
+    // U32 images support mipmap generation
+    let header = Header::new_image(size.width, size.height, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let mut encoder = Encoder::new(Vec::new(), Format::R8G8B8A8_UNORM, &header).unwrap();
+    let options = WriteOptions {
+        generate_mipmaps: true,
+        ..Default::default()
+    };
+    encoder
+        .write_surface_with(view_u32, |_| {}, &options)
+        .unwrap();
+    encoder.finish().unwrap();
+}
+
+#[test]
+fn encode_single_blocks() {
+    let mut rng = util::create_rng();
+
+    for quality in [CompressionQuality::Fast, CompressionQuality::High] {
+        for dithering in [Dithering::None, Dithering::ColorAndAlpha] {
+            let mut options = EncodeOptions::default();
+            options.quality = quality;
+            options.dithering = dithering;
+
+            let mut pixels = [[0_u8; 4]; 16];
+            for _ in 0..20 {
+                pixels.iter_mut().for_each(|p| rng.fill_bytes(p));
+                let image =
+                    ImageView::new(pixels.as_slice(), Size::new(4, 4), ColorFormat::RGBA_U8)
+                        .unwrap();
+                let encode_image = |format: Format| {
+                    let mut output = Vec::new();
+                    dds::encode(&mut output, image, format, &options).unwrap();
+                    output
+                };
+
+                let rg = pixels.map(|[r, g, _, _]| [r, g]);
+                let r = pixels.map(|[r, _, _, _]| r);
+                let r_gray =
+                    ImageView::new(r.as_slice(), Size::new(4, 4), ColorFormat::GRAYSCALE_U8)
+                        .unwrap();
+                let encode_gray = |format: Format| {
+                    let mut output = Vec::new();
+                    dds::encode(&mut output, r_gray, format, &options).unwrap();
+                    output
+                };
+
+                assert_eq!(
+                    encode_bc1_block(&pixels, &options).as_slice(),
+                    encode_image(Format::BC1_UNORM)
+                );
+                assert_eq!(
+                    encode_bc2_block(&pixels, &options).as_slice(),
+                    encode_image(Format::BC2_UNORM)
+                );
+                assert_eq!(
+                    encode_bc3_block(&pixels, &options).as_slice(),
+                    encode_image(Format::BC3_UNORM)
+                );
+                assert_eq!(
+                    encode_bc4_block(&r, &options, false).as_slice(),
+                    encode_gray(Format::BC4_UNORM)
+                );
+                assert_eq!(
+                    encode_bc4_block(&r, &options, true).as_slice(),
+                    encode_gray(Format::BC4_SNORM)
+                );
+                assert_eq!(
+                    encode_bc5_block(&rg, &options, false).as_slice(),
+                    encode_image(Format::BC5_UNORM)
+                );
+                assert_eq!(
+                    encode_bc5_block(&rg, &options, true).as_slice(),
+                    encode_image(Format::BC5_SNORM)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn bc1_quality_levels() {
+    let mut rng = util::create_rng();
+
+    // smooth gradients with noise, where endpoint search matters
+    let blocks: Vec<[[u8; 4]; 16]> = (0..200)
+        .map(|_| {
+            let start: [f32; 3] = [rng.gen(), rng.gen(), rng.gen()];
+            let end: [f32; 3] = [rng.gen(), rng.gen(), rng.gen()];
+            std::array::from_fn(|i| {
+                let t = i as f32 / 15.0;
+                let [r, g, b] = std::array::from_fn(|c| {
+                    let noise = rng.gen_range(-0.05..0.05);
+                    ((start[c] * (1.0 - t) + end[c] * t + noise).clamp(0.0, 1.0) * 255.0) as u8
+                });
+                [r, g, b, 255]
+            })
+        })
+        .collect();
+
+    let total_error = |quality: CompressionQuality| {
+        let mut options = EncodeOptions::default();
+        options.quality = quality;
+
+        let mut error = 0_u64;
+        for pixels in &blocks {
+            let block = encode_bc1_block(pixels, &options);
+            let mut decoded = [0_u8; 64];
+            let image = ImageViewMut::new(
+                decoded.as_mut_slice(),
+                Size::new(4, 4),
+                ColorFormat::RGBA_U8,
+            )
+            .unwrap();
+            dds::decode(
+                &mut block.as_slice(),
+                image,
+                Format::BC1_UNORM,
+                &DecodeOptions::default(),
+            )
+            .unwrap();
+            for (a, b) in pixels.iter().zip(decoded.chunks_exact(4)) {
+                for c in 0..3 {
+                    let diff = a[c] as i64 - b[c] as i64;
+                    error += (diff * diff) as u64;
+                }
+            }
+        }
+        error
+    };
+
+    let fast = total_error(CompressionQuality::Fast);
+    let normal = total_error(CompressionQuality::Normal);
+    let high = total_error(CompressionQuality::High);
+    let unreasonable = total_error(CompressionQuality::Unreasonable);
+    assert!(normal <= fast, "{normal} > {fast}");
+    assert!(high <= normal, "{high} > {normal}");
+    assert!(unreasonable < high, "{unreasonable} >= {high}");
+}
+
+#[test]
+fn bc1_alpha_threshold() {
+    // alpha ramps from 0 to 255 across the block
+    let pixels: [[u8; 4]; 16] = std::array::from_fn(|i| [200, 100, 50, (i * 17) as u8]);
+
+    let transparent_pixels = |threshold: u8| {
+        let mut options = EncodeOptions::default();
+        options.bc1_alpha_threshold = threshold;
+        let block = encode_bc1_block(&pixels, &options);
+
+        let mut decoded = [0_u8; 64];
+        let image = ImageViewMut::new(
+            decoded.as_mut_slice(),
+            Size::new(4, 4),
+            ColorFormat::RGBA_U8,
+        )
+        .unwrap();
+        let decode_options = DecodeOptions::default();
+        dds::decode(
+            &mut block.as_slice(),
+            image,
+            Format::BC1_UNORM,
+            &decode_options,
+        )
+        .unwrap();
+
+        let mut transparent = Vec::new();
+        for (i, pixel) in decoded.chunks_exact(4).enumerate() {
+            match pixel[3] {
+                0 => {
+                    assert_eq!(pixel, [0, 0, 0, 0], "transparent pixels are black");
+                    transparent.push(i);
+                }
+                255 => {}
+                alpha => panic!("unexpected alpha {alpha}"),
+            }
+        }
+        transparent
+    };
+
+    assert_eq!(transparent_pixels(0), Vec::<usize>::new());
+    assert_eq!(transparent_pixels(1), vec![0]);
+    assert_eq!(transparent_pixels(64), vec![0, 1, 2, 3]);
+    assert_eq!(transparent_pixels(128), (0..8).collect::<Vec<_>>());
+    assert_eq!(transparent_pixels(255), (0..15).collect::<Vec<_>>());
+}
+
+#[test]
+fn error_metric_weights() {
+    let mut rng = util::create_rng();
+
+    // noisy colors that can't be represented exactly
+    let blocks: Vec<[[u8; 4]; 16]> = (0..100)
+        .map(|_| std::array::from_fn(|_| [rng.gen(), rng.gen(), rng.gen(), 255]))
+        .collect();
+
+    let channel_errors = |format: Format, error_metric: ErrorMetric| {
+        let mut options = EncodeOptions::default();
+        options.error_metric = error_metric;
+        let mut errors = [0_u64; 4];
+        let mut encoded = Vec::new();
+        for pixels in &blocks {
+            let block = match format {
+                Format::BC1_UNORM => encode_bc1_block(pixels, &options).to_vec(),
+                _ => encode_bc7_block(pixels, &options).to_vec(),
+            };
+            let mut decoded = [0_u8; 64];
+            let image = ImageViewMut::new(
+                decoded.as_mut_slice(),
+                Size::new(4, 4),
+                ColorFormat::RGBA_U8,
+            )
+            .unwrap();
+            dds::decode(
+                &mut block.as_slice(),
+                image,
+                format,
+                &DecodeOptions::default(),
+            )
+            .unwrap();
+            for (a, b) in pixels.iter().zip(decoded.chunks_exact(4)) {
+                for c in 0..4 {
+                    errors[c] += (a[c] as i64 - b[c] as i64).pow(2) as u64;
+                }
+            }
+            encoded.extend(block);
+        }
+        (errors, encoded)
+    };
+
+    for format in [Format::BC1_UNORM, Format::BC7_UNORM] {
+        let (uniform, uniform_data) = channel_errors(format, ErrorMetric::Uniform);
+
+        // only the ratios of the weights matter
+        let (_, same_data) = channel_errors(format, ErrorMetric::Weighted([7, 7, 7, 7]));
+        assert_eq!(uniform_data, same_data, "{format:?}");
+
+        // prioritize green
+        let (green, _) = channel_errors(format, ErrorMetric::Weighted([1, 255, 1, 255]));
+        assert!(
+            green[1] * 2 < uniform[1],
+            "{format:?}: {green:?} vs {uniform:?}"
+        );
+        assert!(
+            green[0] > uniform[0],
+            "{format:?}: {green:?} vs {uniform:?}"
+        );
+
+        // luma weights
+        let (luma, _) = channel_errors(format, ErrorMetric::LUMA);
+        assert!(luma[1] < uniform[1], "{format:?}: {luma:?} vs {uniform:?}");
+        assert!(luma[2] > uniform[2], "{format:?}: {luma:?} vs {uniform:?}");
+    }
+}
+
+#[test]
+fn bc7_encoder_options() {
+    let mut rng = util::create_rng();
+
+    // gradients with noise and varying alpha
+    let blocks: Vec<[[u8; 4]; 16]> = (0..100)
+        .map(|_| {
+            let start: [f32; 4] = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+            let end: [f32; 4] = [rng.gen(), rng.gen(), rng.gen(), rng.gen()];
+            std::array::from_fn(|i| {
+                let t = i as f32 / 15.0;
+                std::array::from_fn(|c| {
+                    let noise = rng.gen_range(-0.05..0.05);
+                    ((start[c] * (1.0 - t) + end[c] * t + noise).clamp(0.0, 1.0) * 255.0) as u8
+                })
+            })
+        })
+        .collect();
+
+    let decode_block = |block: &[u8], format: Format| {
+        let mut decoded = [0_u8; 64];
+        let image = ImageViewMut::new(
+            decoded.as_mut_slice(),
+            Size::new(4, 4),
+            ColorFormat::RGBA_U8,
+        )
+        .unwrap();
+        dds::decode(&mut &block[..], image, format, &DecodeOptions::default()).unwrap();
+        decoded
+    };
+    let block_error = |pixels: &[[u8; 4]; 16], decoded: &[u8; 64]| -> u64 {
+        let pixels = pixels.iter().flatten();
+        pixels
+            .zip(decoded)
+            .map(|(&a, &b)| (a as i64 - b as i64).pow(2) as u64)
+            .sum()
+    };
+    let total_error = |bc7: Option<Bc7Options>| {
+        let mut options = EncodeOptions::default();
+        options.bc7 = bc7;
+        let mut error = 0;
+        for pixels in &blocks {
+            let block = encode_bc7_block(pixels, &options);
+            error += block_error(pixels, &decode_block(&block, Format::BC7_UNORM));
+        }
+        error
+    };
+
+    // each mode can be used on its own
+    for mode in 0..8 {
+        let mut bc7 = Bc7Options::MAX_QUALITY;
+        bc7.modes = Bc7Modes::from_mode(mode);
+        bc7.partitions = 4;
+        let mut options = EncodeOptions::default();
+        options.bc7 = Some(bc7);
+        for pixels in &blocks[..10] {
+            let block = encode_bc7_block(pixels, &options);
+            assert_eq!(block[0].trailing_zeros(), mode as u32);
+            // modes 4 and 6 have enough precision to be close for all blocks
+            if mode == 6 {
+                let error = block_error(pixels, &decode_block(&block, Format::BC7_UNORM));
+                assert!(error < 16 * 4 * 100, "mode {mode}: {error}");
+            }
+        }
+    }
+
+    // more effort means less error
+    let fast_rgb = total_error(Some(Bc7Options::FAST_RGB));
+    let fast = total_error(Some(Bc7Options::from_quality(CompressionQuality::Fast)));
+    let normal = total_error(None);
+    let max = total_error(Some(Bc7Options::MAX_QUALITY));
+    assert!(normal <= fast, "{normal} > {fast}");
+    assert!(max < normal, "{max} >= {normal}");
+    assert!(normal < fast_rgb, "{normal} >= {fast_rgb}");
+
+    // BC7 beats BC3
+    let mut bc3 = 0;
+    for pixels in &blocks {
+        let block = encode_bc3_block(pixels, &EncodeOptions::default());
+        bc3 += block_error(pixels, &decode_block(&block, Format::BC3_UNORM));
+    }
+    assert!(normal < bc3, "{normal} >= {bc3}");
+
+    // solid colors are encoded almost exactly
+    for color in [
+        [0, 0, 0, 255],
+        [255; 4],
+        [12, 200, 77, 255],
+        [100, 50, 25, 128],
+    ] {
+        let pixels = [color; 16];
+        let block = encode_bc7_block(&pixels, &EncodeOptions::default());
+        let error = block_error(&pixels, &decode_block(&block, Format::BC7_UNORM));
+        assert!(error <= 16, "{color:?}: {error}");
+    }
+}
+
+#[test]
+fn bc6h_quality_levels() {
+    let mut rng = util::create_rng();
+    let size = Size::new(32, 32);
+
+    // HDR gradients with noise spanning several orders of magnitude
+    fn create_image(rng: &mut impl Rng, size: Size, signed: bool) -> Vec<f32> {
+        let mut data = Vec::new();
+        for _ in 0..size.pixels() / 16 {
+            let scale = 10_f32.powf(rng.gen_range(-2.0..3.0));
+            let sign: [f32; 3] =
+                std::array::from_fn(|_| if signed && rng.gen() { -scale } else { scale });
+            let start: [f32; 3] = std::array::from_fn(|c| rng.gen_range(0.25..1.0) * sign[c]);
+            let end: [f32; 3] = std::array::from_fn(|c| rng.gen_range(0.25..1.0) * sign[c]);
+            for i in 0..16 {
+                let t = i as f32 / 15.0;
+                for c in 0..3 {
+                    let noise = rng.gen_range(0.97..1.03);
+                    data.push((start[c] * (1.0 - t) + end[c] * t) * noise);
+                }
+                data.push(1.0);
+            }
+        }
+        data
+    }
+    // log error, which is roughly how HDR data is perceived
+    let total_error = |data: &[f32], format: Format, quality: CompressionQuality| -> f64 {
+        let image = ImageView::new(util::as_bytes(data), size, ColorFormat::RGBA_F32).unwrap();
+        let mut options = EncodeOptions::default();
+        options.quality = quality;
+        let mut encoded = Vec::new();
+        dds::encode(&mut encoded, image, format, &options).unwrap();
+        assert_eq!(encoded.len(), size.pixels() as usize);
+
+        let mut decoded = vec![0_f32; data.len()];
+        let image = ImageViewMut::new(
+            util::as_bytes_mut(decoded.as_mut_slice()),
+            size,
+            ColorFormat::RGBA_F32,
+        )
+        .unwrap();
+        dds::decode(
+            &mut encoded.as_slice(),
+            image,
+            format,
+            &DecodeOptions::default(),
+        )
+        .unwrap();
+
+        let log = |v: f32| (v.abs() as f64).ln_1p().copysign(v as f64);
+        data.iter()
+            .zip(&decoded)
+            .map(|(&a, &b)| (log(a) - log(b)).powi(2))
+            .sum::<f64>()
+            / data.len() as f64
+    };
+
+    for (format, signed) in [(Format::BC6H_UF16, false), (Format::BC6H_SF16, true)] {
+        let data = create_image(&mut rng, size, signed);
+        let fast = total_error(&data, format, CompressionQuality::Fast);
+        let normal = total_error(&data, format, CompressionQuality::Normal);
+        let high = total_error(&data, format, CompressionQuality::High);
+        assert!(normal <= fast, "{format:?}: {normal} > {fast}");
+        assert!(high <= normal, "{format:?}: {high} > {normal}");
+        assert!(fast < 2e-2, "{format:?}: {fast}");
+    }
+}
+
+#[test]
+fn encode_with_report_quality() {
+    let size = Size::new(16, 16);
+    let mut data = Vec::new();
+    for y in 0..size.height {
+        for x in 0..size.width {
+            let r = x as f32 / 15.0;
+            let g = y as f32 / 15.0;
+            data.push([r, g, (r + g) / 2.0, 1.0 - r]);
+        }
+    }
+    // 3 HDR pixels that can't be represented by UNORM formats
+    data[0] = [2.0, 0.5, 0.5, 1.0];
+    data[1] = [-1.0, 0.5, 0.5, 1.0];
+    data[2] = [0.5, 0.5, 0.5, f32::NAN];
+    let image = ImageView::new(data.as_slice(), size, ColorFormat::RGBA_F32).unwrap();
+
+    let report = |format: Format| {
+        let options = EncodeOptions::default();
+        let mut output = Vec::new();
+        let report = encode_with_report(&mut output, image, format, &options).unwrap();
+
+        let mut expected = Vec::new();
+        dds::encode(&mut expected, image, format, &options).unwrap();
+        assert_eq!(output, expected, "{format:?} output differs");
+
+        report
+    };
+
+    let rgba8 = report(Format::R8G8B8A8_UNORM);
+    assert_eq!(rgba8.clamped_pixels, 3);
+    assert_eq!(rgba8.max_error[0], 1.0);
+    assert!(rgba8.max_error[1] <= 0.5 / 255.0 + 1e-6);
+
+    let bc3 = report(Format::BC3_UNORM);
+    assert_eq!(bc3.clamped_pixels, 3);
+    assert_eq!(bc3.max_error[3], 1.0, "NaN is counted as an error of 1");
+    assert!(bc3.psnr[1] < rgba8.psnr[1]);
+    assert!(bc3.psnr[1] > 20.0, "{bc3:?}");
+
+    let rgba16f = report(Format::R16G16B16A16_FLOAT);
+    assert_eq!(rgba16f.clamped_pixels, 0);
+    assert!(rgba16f.max_error[0] <= 2e-3, "{rgba16f:?}");
+
+    // R8 only stores red, so green, blue, and alpha are compared against the
+    // grayscale values the decoder outputs
+    let r8 = report(Format::R8_UNORM);
+    assert_eq!(r8.psnr[0], r8.psnr[1]);
+    assert_eq!(r8.psnr[0], r8.psnr[2]);
+    assert_eq!(r8.psnr[3], f32::INFINITY);
+    assert_eq!(r8.max_error[3], 0.0);
+}
+
+#[test]
+fn surface_encoder_chunks() {
+    let size = Size::new(14, 22);
+    let mut rng = util::create_rng();
+    let mut image: Image<u8> = Image::new_empty(Channels::Rgba, size);
+    rng.fill(image.data.as_mut_slice());
+    let row_pitch = image.view().row_pitch();
+
+    let mut options = EncodeOptions::default();
+    options.dithering = Dithering::ColorAndAlpha;
+    options.quality = CompressionQuality::Fast;
+
+    for format in [
+        Format::R8G8B8A8_UNORM,
+        Format::B5G6R5_UNORM,
+        Format::B4G4R4A4_UNORM,
+        Format::BC1_UNORM,
+        Format::BC7_UNORM,
+        Format::YUY2,
+    ] {
+        let mut expected = Vec::new();
+        dds::encode(&mut expected, image.view(), format, &options).unwrap();
+
+        let split_height = format.encoding_support().unwrap().split_height().unwrap();
+        let chunk_height = split_height.get() as u32 * 2;
+
+        let mut encoder = SurfaceEncoder::new(Vec::new(), format, size, &options).unwrap();
+        while encoder.rows_remaining() > 0 {
+            let start = encoder.rows_written();
+            let height = chunk_height.min(encoder.rows_remaining());
+            let data =
+                &image.data[start as usize * row_pitch..(start + height) as usize * row_pitch];
+            let rows =
+                ImageView::new(data, Size::new(size.width, height), ColorFormat::RGBA_U8).unwrap();
+            encoder.push_rows(rows).unwrap();
+        }
+        let actual = encoder.finish().unwrap();
+
+        assert!(actual == expected, "Failed for {:?}", format);
+    }
+
+    // invalid pushes
+    let rows = |height: u32| {
+        ImageView::new(
+            &image.data[..height as usize * row_pitch],
+            Size::new(size.width, height),
+            ColorFormat::RGBA_U8,
+        )
+        .unwrap()
+    };
+    let mut encoder = SurfaceEncoder::new(Vec::new(), Format::BC1_UNORM, size, &options).unwrap();
+    assert!(matches!(
+        encoder.push_rows(rows(3)),
+        Err(EncodeError::InvalidSize(_))
+    ));
+    encoder.push_rows(rows(20)).unwrap();
+    assert!(matches!(
+        encoder.push_rows(rows(4)),
+        Err(EncodeError::UnexpectedSurfaceSize)
+    ));
+    assert!(matches!(encoder.finish(), Err(EncodeError::MissingRows)));
+
+    // bi-planar formats require the whole surface at once
+    let mut encoder = SurfaceEncoder::new(Vec::new(), Format::NV12, size, &options).unwrap();
+    assert!(matches!(
+        encoder.push_rows(rows(2)),
+        Err(EncodeError::UnexpectedSurfaceSize)
+    ));
+    encoder.push_rows(rows(size.height)).unwrap();
+    encoder.finish().unwrap();
+}
+
+#[test]
+fn encode_rect_patches_surface() {
+    let size = Size::new(22, 13);
+    let mut rng = util::create_rng();
+    let mut original: Image<u8> = Image::new_empty(Channels::Rgba, size);
+    rng.fill(original.data.as_mut_slice());
+
+    let options = EncodeOptions::default();
+
+    for (format, rect) in [
+        (Format::BC1_UNORM, Rect::new(4, 4, 8, 4)),
+        // rectangles at the edge may end with partial blocks
+        (Format::BC1_UNORM, Rect::new(16, 8, 6, 5)),
+        (Format::BC7_UNORM, Rect::new(0, 0, 4, 13)),
+        (Format::R8G8B8A8_UNORM, Rect::new(3, 5, 7, 2)),
+        (Format::YUY2, Rect::new(2, 1, 20, 3)),
+    ] {
+        // paint the rect
+        let mut painted = original.clone();
+        let mut patch = Vec::new();
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                let pixel = [x as u8 * 10, y as u8 * 10, 128, 255];
+                let i = (y * size.width + x) as usize * 4;
+                painted.data[i..i + 4].copy_from_slice(&pixel);
+                patch.extend_from_slice(&pixel);
+            }
+        }
+        let patch = ImageView::new(patch.as_slice(), rect.size(), ColorFormat::RGBA_U8).unwrap();
+
+        let mut surface = Vec::new();
+        dds::encode(&mut surface, original.view(), format, &options).unwrap();
+        let surface_len = surface.len() as u64;
+        let mut expected = Vec::new();
+        dds::encode(&mut expected, painted.view(), format, &options).unwrap();
+
+        let mut cursor = std::io::Cursor::new(surface.as_mut_slice());
+        encode_rect(&mut cursor, patch, size, rect, format, &options).unwrap();
+        assert_eq!(cursor.position(), surface_len);
+
+        assert!(surface == expected, "Failed for {:?} {:?}", format, rect);
+    }
+
+    // invalid rects
+    let mut surface = Vec::new();
+    dds::encode(&mut surface, original.view(), Format::BC1_UNORM, &options).unwrap();
+    let pixels = vec![0_u8; 8 * 8 * 4];
+    let patch = |size: Size| {
+        ImageView::new(
+            &pixels[..size.pixels() as usize * 4],
+            size,
+            ColorFormat::RGBA_U8,
+        )
+        .unwrap()
+    };
+    let mut cursor = std::io::Cursor::new(surface.as_mut_slice());
+    let mut encode_rect = |rect: Rect, format: Format| {
+        encode_rect(
+            &mut cursor,
+            patch(rect.size()),
+            size,
+            rect,
+            format,
+            &options,
+        )
+    };
+    assert!(matches!(
+        encode_rect(Rect::new(2, 0, 4, 4), Format::BC1_UNORM),
+        Err(EncodeError::UnalignedRect)
+    ));
+    assert!(matches!(
+        encode_rect(Rect::new(20, 8, 1, 4), Format::BC1_UNORM),
+        Err(EncodeError::UnalignedRect)
+    ));
+    assert!(matches!(
+        encode_rect(Rect::new(20, 8, 8, 4), Format::BC1_UNORM),
+        Err(EncodeError::RectOutOfBounds)
+    ));
+    assert!(matches!(
+        encode_rect(Rect::new(0, 0, 4, 4), Format::NV12),
+        Err(EncodeError::UnsupportedFormat(_))
+    ));
+    assert_eq!(cursor.position(), 0);
+}
+
+#[test]
+fn transcode_surfaces() {
+    let size = Size::new(16, 16);
+    let mut rng = util::create_rng();
+    let mut image: Image<u8> = Image::new_empty(Channels::Rgba, size);
+    rng.fill(image.data.as_mut_slice());
+
+    let write_options = WriteOptions {
+        generate_mipmaps: true,
+        ..Default::default()
+    };
+    let create = |format: Format, mipmaps: bool| {
+        let mut header = Header::new_image(size.width, size.height, format);
+        if mipmaps {
+            header = header.with_mipmaps();
+        }
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+        encoder
+            .write_surface_with(image.view(), |_| {}, &write_options)
+            .unwrap();
+        encoder.finish().unwrap();
+        output
+    };
+    let transcode_to = |source: &[u8], format: Format, mipmaps: bool| {
+        let mut decoder = Decoder::new(source).unwrap();
+        let mut header = Header::new_image(size.width, size.height, format);
+        if mipmaps {
+            header = header.with_mipmaps();
+        }
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+        transcode(&mut decoder, &mut encoder, &TranscodeOptions::default())?;
+        encoder.finish().unwrap();
+        Ok::<_, TranscodeError>(output)
+    };
+
+    // same format: data is copied verbatim
+    let bc1_mipmaps = create(Format::BC1_UNORM, true);
+    let output = transcode_to(&bc1_mipmaps, Format::BC1_UNORM, true).unwrap();
+    assert!(output == bc1_mipmaps);
+
+    // dropping mipmaps keeps the level 0 surface as is
+    let output = transcode_to(&bc1_mipmaps, Format::BC1_UNORM, false).unwrap();
+    let header_len = output.len() - 4 * 4 * 8;
+    assert!(output[header_len..] == bc1_mipmaps[header_len..output.len()]);
+
+    // different format: decoded and re-encoded
+    let rgba = create(Format::R8G8B8A8_UNORM, false);
+    let output = transcode_to(&rgba, Format::BC1_UNORM, false).unwrap();
+    assert!(output == create(Format::BC1_UNORM, false));
+
+    // missing mipmaps are generated
+    let output = transcode_to(&rgba, Format::R8G8B8A8_UNORM, true).unwrap();
+    assert!(output == create(Format::R8G8B8A8_UNORM, true));
+
+    // incompatible layouts
+    let mut decoder = Decoder::new(rgba.as_slice()).unwrap();
+    let mut output = Vec::new();
+    let header = Header::new_image(8, 16, Format::BC1_UNORM);
+    let mut encoder = Encoder::new(&mut output, Format::BC1_UNORM, &header).unwrap();
+    assert!(matches!(
+        transcode(&mut decoder, &mut encoder, &TranscodeOptions::default()),
+        Err(TranscodeError::IncompatibleLayout)
+    ));
+}
+
+#[test]
+fn transcode_reusing_blocks() {
+    let size = Size::new(32, 24);
+    let mut image: Image<u8> = Image::new_empty(Channels::Rgba, size);
+    for (i, pixel) in image.data.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i as u32 % size.width, i as u32 / size.width);
+        pixel.copy_from_slice(&[(x * 8) as u8, (y * 10) as u8, 128, (255 - x * 4) as u8]);
+    }
+
+    let encode_with = |format: Format| {
+        let header = Header::new_image(size.width, size.height, format);
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+        encoder.write_surface(image.view()).unwrap();
+        encoder.finish().unwrap();
+        output
+    };
+    let decode_f32 = |file: &[u8]| {
+        let mut decoder = Decoder::new(file).unwrap();
+        let mut output: Image<f32> = Image::new_empty(Channels::Rgba, size);
+        let view = ImageViewMut::new(output.data.as_mut_slice(), size, ColorFormat::RGBA_F32);
+        decoder.read_surface(view.unwrap()).unwrap();
+        output.data
+    };
+    let transcode_to = |source: &[u8], format: Format| {
+        let mut decoder = Decoder::new(source).unwrap();
+        let header = Header::new_image(size.width, size.height, format);
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+        let mut options = TranscodeOptions::default();
+        options.reuse_blocks = true;
+        transcode(&mut decoder, &mut encoder, &options).unwrap();
+        encoder.finish().unwrap();
+        output
+    };
+
+    // BC1 -> BC3 keeps the colors of opaque blocks
+    let bc1 = encode_with(Format::BC1_UNORM);
+    let bc3 = transcode_to(&bc1, Format::BC3_UNORM);
+    let psnr = metrics::psnr(&decode_f32(&bc1), &decode_f32(&bc3), size, Channels::Rgba);
+    assert!(psnr.iter().all(|&p| p == f64::INFINITY), "{:?}", psnr);
+
+    // BC3 -> BC7 is close to the source
+    let bc3 = encode_with(Format::BC3_UNORM);
+    let bc7 = transcode_to(&bc3, Format::BC7_UNORM);
+    let psnr = metrics::psnr(&decode_f32(&bc3), &decode_f32(&bc7), size, Channels::Rgba);
+    assert!(psnr.iter().all(|&p| p > 35.0), "{:?}", psnr);
+}
+
+#[test]
+fn merge_bc4_into_bc5_is_lossless() {
+    let size = Size::new(16, 8);
+    let mut rng = util::create_rng();
+    let mut encode_bc4 = |format: Format| {
+        let mut image: Image<u8> = Image::new_empty(Channels::Grayscale, size);
+        rng.fill(image.data.as_mut_slice());
+        let header = Header::new_image(size.width, size.height, format).with_mipmaps();
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+        let options = WriteOptions {
+            generate_mipmaps: true,
+            ..Default::default()
+        };
+        encoder
+            .write_surface_with(image.view(), |_| {}, &options)
+            .unwrap();
+        encoder.finish().unwrap();
+        output
+    };
+    let red = encode_bc4(Format::BC4_UNORM);
+    let green = encode_bc4(Format::BC4_UNORM);
+
+    let merge = |red: &[u8], green: &[u8], format: Format| {
+        let mut red = Decoder::new(red).unwrap();
+        let mut green = Decoder::new(green).unwrap();
+        let header = Header::new_image(size.width, size.height, format).with_mipmaps();
+        let mut output = Vec::new();
+        let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+        merge_bc4_into_bc5(&mut red, &mut green, &mut encoder)?;
+        encoder.finish().unwrap();
+        Ok::<_, TranscodeError>(output)
+    };
+    let bc5 = merge(&red, &green, Format::BC5_UNORM).unwrap();
+
+    let decode = |file: &[u8], color: ColorFormat| {
+        let mut decoder = Decoder::new(file).unwrap();
+        let mut surfaces = Vec::new();
+        while let Some(info) = decoder.surface_info() {
+            let mut data = vec![0_u8; color.buffer_size(info.size()).unwrap()];
+            let view = ImageViewMut::new(data.as_mut_slice(), info.size(), color).unwrap();
+            decoder.read_surface(view).unwrap();
+            surfaces.push(data);
+        }
+        surfaces
+    };
+    let red = decode(&red, ColorFormat::GRAYSCALE_U8);
+    let green = decode(&green, ColorFormat::GRAYSCALE_U8);
+    let bc5 = decode(&bc5, ColorFormat::RGB_U8);
+    assert_eq!(bc5.len(), red.len());
+    for ((bc5, red), green) in bc5.iter().zip(&red).zip(&green) {
+        let expected: Vec<u8> = red
+            .iter()
+            .zip(green)
+            .flat_map(|(&r, &g)| [r, g, 0])
+            .collect();
+        assert!(bc5 == &expected);
+    }
+
+    let bc4s = encode_bc4(Format::BC4_SNORM);
+    assert!(matches!(
+        merge(&bc4s, &bc4s, Format::BC5_UNORM),
+        Err(TranscodeError::IncompatibleFormats)
+    ));
+}
+
+#[test]
+fn write_volume_generates_mipmaps() {
+    let size = Size::new(8, 8);
+    let slices: Vec<Vec<u8>> = (0..4_u8)
+        .map(|k| [k * 60, 255 - k * 60, 100, 255].repeat(size.pixels() as usize))
+        .collect();
+    let slices: Vec<ImageView> = slices
+        .iter()
+        .map(|s| ImageView::new(s.as_slice(), size, ColorFormat::RGBA_U8).unwrap())
+        .collect();
+
+    let format = Format::R8G8B8A8_UNORM;
+    let header = Header::new_volume(size.width, size.height, 4, format).with_mipmaps();
+    let mut output = Vec::new();
+    let mut encoder = Encoder::new(&mut output, format, &header).unwrap();
+    // too few and too many slices
+    assert!(matches!(
+        encoder.write_volume(&slices[..3]),
+        Err(EncodeError::UnexpectedVolumeDepth)
+    ));
+    assert!(matches!(
+        encoder.write_volume(&[slices.as_slice(), &slices[..1]].concat()),
+        Err(EncodeError::UnexpectedVolumeDepth)
+    ));
+    let options = WriteOptions {
+        generate_mipmaps: true,
+        ..Default::default()
+    };
+    encoder
+        .write_volume_with(&slices, |_| {}, &options)
+        .unwrap();
+    encoder.finish().unwrap();
+
+    let mut decoder = Decoder::new(output.as_slice()).unwrap();
+    assert!(decoder.layout().volume().is_some());
+    let mut surfaces = Vec::new();
+    while let Some(info) = decoder.surface_info() {
+        let (level, size) = (info.mipmap_level(), info.size());
+        let mut pixel = vec![0_u8; size.pixels() as usize * 4];
+        let view = ImageViewMut::new(pixel.as_mut_slice(), size, ColorFormat::RGBA_U8);
+        decoder.read_surface(view.unwrap()).unwrap();
+        surfaces.push((level, pixel[..4].to_vec()));
+    }
+    let expected = [
+        (0, [0, 255, 100, 255]),
+        (0, [60, 195, 100, 255]),
+        (0, [120, 135, 100, 255]),
+        (0, [180, 75, 100, 255]),
+        (1, [30, 225, 100, 255]),
+        (1, [150, 105, 100, 255]),
+        (2, [90, 165, 100, 255]),
+        (3, [90, 165, 100, 255]),
+    ];
+    assert_eq!(surfaces.len(), expected.len());
+    for ((level, pixel), (expected_level, expected_pixel)) in surfaces.iter().zip(expected) {
+        assert_eq!(*level, expected_level);
+        assert_eq!(pixel.as_slice(), &expected_pixel);
+    }
+
+    // textures don't have depth slices
+    let header = Header::new_image(size.width, size.height, format);
+    let mut encoder = Encoder::new(Vec::new(), format, &header).unwrap();
+    assert!(matches!(
+        encoder.write_volume(&slices[..1]),
+        Err(EncodeError::UnexpectedVolumeDepth)
+    ));
+}
+
+#[test]
+fn srgb_mipmaps_in_linear_light() {
+    // a black and a white pixel with half-transparent alpha
+    let pixels = [0_u8, 0, 0, 128, 255, 255, 255, 128];
+    let image = ImageView::new(&pixels[..], Size::new(2, 1), ColorFormat::RGBA_U8).unwrap();
+    let format = Format::R8G8B8A8_UNORM;
+
+    let encode_mip = |header: Header, write_options: &WriteOptions| -> Vec<u8> {
+        let mut file = Vec::new();
+        let mut encoder = Encoder::new(&mut file, format, &header).unwrap();
+        if header.is_volume() {
+            encoder
+                .write_volume_with(&[image], |_| {}, write_options)
+                .unwrap();
+        } else {
+            encoder
+                .write_surface_with(image, |_| {}, write_options)
+                .unwrap();
+        }
+        encoder.finish().unwrap();
+        file[file.len() - 4..].to_vec()
+    };
+
+    let options = WriteOptions {
+        generate_mipmaps: true,
+        resize_straight_alpha: false,
+        ..Default::default()
+    };
+    let image_header = Header::new_image(2, 1, format).with_mipmaps();
+    let volume_header = Header::new_volume(2, 1, 1, format).with_mipmaps();
+
+    for header in [image_header, volume_header] {
+        // linear light: 50% gray is 188 in sRGB
+        let srgb = header.to_srgb().unwrap();
+        assert_eq!(encode_mip(srgb.clone(), &options), [188, 188, 188, 128]);
+        // not sRGB
+        assert_eq!(encode_mip(header.clone(), &options), [128, 128, 128, 128]);
+
+        let mut gamma_options = options;
+        gamma_options.resize_in_linear_light = false;
+        assert_eq!(
+            encode_mip(srgb.clone(), &gamma_options),
+            [128, 128, 128, 128]
+        );
+
+        // straight alpha doesn't change the color, since alpha is uniform
+        let mut straight_options = options;
+        straight_options.resize_straight_alpha = true;
+        assert_eq!(encode_mip(srgb, &straight_options), [188, 188, 188, 128]);
+    }
+}
+
+#[test]
+fn normal_map_mipmaps() {
+    // a normal pointing to +X and one pointing to +Z with a roughness of 0.2
+    let pixels = [1.0_f32, 0.5, 0.5, 0.2, 0.5, 0.5, 1.0, 0.2];
+    let format = Format::R32G32B32A32_FLOAT;
+
+    let encode_mip = |pixels: &[f32], header: Header, write_options: &WriteOptions| -> Vec<f32> {
+        let image = ImageView::new(pixels, Size::new(2, 1), ColorFormat::RGBA_F32).unwrap();
+        let mut file = Vec::new();
+        let mut encoder = Encoder::new(&mut file, format, &header).unwrap();
+        if header.is_volume() {
+            encoder
+                .write_volume_with(&[image], |_| {}, write_options)
+                .unwrap();
+        } else {
+            encoder
+                .write_surface_with(image, |_| {}, write_options)
+                .unwrap();
+        }
+        encoder.finish().unwrap();
+        file[file.len() - 16..]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    };
+    let assert_close = |actual: Vec<f32>, expected: [f32; 4]| {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+        }
+    };
+
+    let mut normal_map = NormalMapOptions::default();
+    let mut options = WriteOptions {
+        generate_mipmaps: true,
+        normal_map: Some(normal_map),
+        ..Default::default()
+    };
+    let image_header = Header::new_image(2, 1, format).with_mipmaps();
+    let volume_header = Header::new_volume(2, 1, 1, format).with_mipmaps();
+
+    // the average normal is (0.5, 0, 0.5), which is renormalized
+    let n = 0.5 + 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+    for header in [image_header.clone(), volume_header] {
+        assert_close(
+            encode_mip(&pixels, header.clone(), &options),
+            [n, 0.5, n, 0.2],
+        );
+    }
+
+    // without renormalization, the normal is just averaged
+    let plain = WriteOptions {
+        normal_map: None,
+        ..options
+    };
+    assert_close(
+        encode_mip(&pixels, image_header.clone(), &plain),
+        [0.75, 0.5, 0.75, 0.2],
+    );
+
+    // the variance of the normals increases roughness
+    normal_map.alpha_is_roughness = true;
+    options.normal_map = Some(normal_map);
+    let variance = (1.0 - std::f32::consts::FRAC_1_SQRT_2) / std::f32::consts::FRAC_1_SQRT_2;
+    let roughness = (0.2_f32 * 0.2 + variance).sqrt();
+    assert_close(
+        encode_mip(&pixels, image_header.clone(), &options),
+        [n, 0.5, n, roughness],
+    );
+
+    // Z is reconstructed from X and Y, ignoring the blue channel
+    normal_map.alpha_is_roughness = false;
+    normal_map.reconstruct_z = true;
+    options.normal_map = Some(normal_map);
+    let xy_only = [1.0_f32, 0.5, 0.0, 0.2, 0.5, 0.5, 0.0, 0.2];
+    assert_close(
+        encode_mip(&xy_only, image_header, &options),
+        [n, 0.5, n, 0.2],
+    );
+}
+
+#[test]
+fn equirect_to_cube_map() {
+    // an equirectangular image whose colors are the directions of its pixels
+    let size = Size::new(256, 128);
+    let mut pixels = Vec::new();
+    for y in 0..size.height {
+        let latitude = (0.5 - (y as f32 + 0.5) / size.height as f32) * std::f32::consts::PI;
+        for x in 0..size.width {
+            let longitude =
+                ((x as f32 + 0.5) / size.width as f32 - 0.5) * 2.0 * std::f32::consts::PI;
+            let dir = [
+                latitude.cos() * longitude.sin(),
+                latitude.sin(),
+                latitude.cos() * longitude.cos(),
+            ];
+            pixels.extend(dir.iter().map(|d| d * 0.5 + 0.5));
+        }
+    }
+    let image = ImageView::new(pixels.as_slice(), size, ColorFormat::RGB_F32).unwrap();
+
+    let mut options = EquirectOptions::default();
+    options.face_size = Some(9);
+    let faces = equirect_to_cube_faces(image, &options).unwrap();
+    assert_eq!(faces.len(), 6);
+
+    // the centers of the faces look along the axes
+    let center = |face: &Surface<f32>| {
+        let i = (4 * 9 + 4) * 4;
+        [face.data[i], face.data[i + 1], face.data[i + 2]]
+    };
+    let expected = [
+        [1.0, 0.5, 0.5],
+        [0.0, 0.5, 0.5],
+        [0.5, 1.0, 0.5],
+        [0.5, 0.0, 0.5],
+        [0.5, 0.5, 1.0],
+        [0.5, 0.5, 0.0],
+    ];
+    for (face, expected) in faces.iter().zip(expected) {
+        assert_eq!(face.size, Size::new(9, 9));
+        assert_eq!(face.color, ColorFormat::RGBA_F32);
+        let actual = center(face);
+        for c in 0..3 {
+            assert!((actual[c] - expected[c]).abs() < 0.02, "{:?}", actual);
+        }
+    }
+
+    // with seam fixup, the right edge of +Z is the left edge of +X
+    let column = |face: &Surface<f32>, x: usize| -> Vec<f32> {
+        (0..9)
+            .flat_map(|y| face.data[(y * 9 + x) * 4..(y * 9 + x) * 4 + 4].to_vec())
+            .collect()
+    };
+    let (positive_x, positive_z) = (&faces[0], &faces[4]);
+    assert!(column(positive_z, 8) != column(positive_x, 0));
+    options.fix_seams = true;
+    let faces = equirect_to_cube_faces(image, &options).unwrap();
+    let (positive_x, positive_z) = (&faces[0], &faces[4]);
+    assert_eq!(column(positive_z, 8), column(positive_x, 0));
+
+    // write a cube map with mipmaps
+    let mut file = Vec::new();
+    encode_equirect_cube_map(
+        &mut file,
+        image,
+        Format::R8G8B8A8_UNORM,
+        &EquirectOptions::default(),
+        &EncodeOptions::default(),
+    )
+    .unwrap();
+    let mut decoder = Decoder::new(file.as_slice()).unwrap();
+    assert!(decoder.info().header().is_cube_map());
+    assert_eq!(decoder.main_size(), Size::new(64, 64));
+    assert_eq!(decoder.info().header().mipmap_count().get(), 7);
+    let surfaces = decoder.read_all::<u8>().unwrap();
+    assert_eq!(surfaces.len(), 6 * 7);
+
+    // empty images and faces are rejected
+    options.face_size = Some(0);
+    assert!(matches!(
+        equirect_to_cube_faces(image, &options),
+        Err(EncodeError::EmptySurface)
+    ));
+}
+
+#[test]
+fn encode_srgb() {
+    // tagging the header
+    let header = Header::new_image(4, 4, Format::BC7_UNORM)
+        .to_srgb()
+        .unwrap();
+    assert!(header.is_srgb());
+    assert_eq!(Format::from_header(&header).unwrap(), Format::BC7_UNORM);
+    assert!(Header::new_image(4, 4, Format::BC4_UNORM)
+        .to_srgb()
+        .is_none());
+    assert!(Header::new_image(4, 4, Format::R8G8B8_UNORM)
+        .to_srgb()
+        .is_none());
+
+    // converting linear input
+    let size = Size::new(16, 1);
+    let linear: Vec<[f32; 4]> = (0..16)
+        .map(|i| {
+            let v = i as f32 / 15.0;
+            [v, v * 0.5, 0.0, v]
+        })
+        .collect();
+    let image = ImageView::new(linear.as_slice(), size, ColorFormat::RGBA_F32).unwrap();
+    let to_srgb = |c: f32| {
+        if c > 0.0031308 {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        } else {
+            12.92 * c
+        }
+    };
+    let expected: Vec<u8> = linear
+        .iter()
+        .flat_map(|&[r, g, b, a]| [to_srgb(r), to_srgb(g), to_srgb(b), a])
+        .map(|v| (v * 255.0).round() as u8)
+        .collect();
+
+    let mut options = EncodeOptions::default();
+    options.convert_to_srgb = true;
+    let mut output = Vec::new();
+    dds::encode(&mut output, image, Format::R8G8B8A8_UNORM, &options).unwrap();
+    assert_eq!(output, expected);
+
+    // U8 input is converted in F32, so no precision is lost before encoding
+    let linear_u8: Vec<u8> = linear
+        .iter()
+        .flatten()
+        .map(|&v| (v * 255.0).round() as u8)
+        .collect();
+    let expected: Vec<u8> = linear_u8
+        .chunks_exact(4)
+        .flat_map(|p| {
+            [
+                to_srgb(p[0] as f32 / 255.0),
+                to_srgb(p[1] as f32 / 255.0),
+                0.0,
+                p[3] as f32 / 255.0,
+            ]
+        })
+        .map(|v| (v * 255.0).round() as u8)
+        .collect();
+    let image = ImageView::new(linear_u8.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+    let mut output = Vec::new();
+    dds::encode(&mut output, image, Format::R8G8B8A8_UNORM, &options).unwrap();
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn encode_memory_limit() {
+    let size = Size::new(64, 64);
+    let mut pixels = vec![0_u8; size.pixels() as usize * 4];
+    rand::RngCore::fill_bytes(&mut util::create_rng(), &mut pixels);
+    let image = ImageView::new(pixels.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+
+    let encode_with_limit = |format: Format, memory_limit: usize, convert_to_srgb: bool| {
+        let mut options = EncodeOptions::default();
+        options.memory_limit = memory_limit;
+        options.convert_to_srgb = convert_to_srgb;
+        let mut output = Vec::new();
+        dds::encode(&mut output, image, format, &options)
+    };
+    let exceeded =
+        |result: Result<(), EncodeError>| matches!(result, Err(EncodeError::MemoryLimitExceeded));
+
+    // uncompressed formats without conversion don't need intermediate buffers
+    assert!(encode_with_limit(Format::R8G8B8A8_UNORM, 0, false).is_ok());
+    // converting to sRGB needs an f32 copy of the image
+    assert!(exceeded(encode_with_limit(
+        Format::R8G8B8A8_UNORM,
+        32 * 1024,
+        true
+    )));
+    assert!(encode_with_limit(Format::R8G8B8A8_UNORM, 128 * 1024, true).is_ok());
+
+    // block compression needs buffers for a row of blocks
+    assert!(exceeded(encode_with_limit(Format::BC1_UNORM, 1024, false)));
+    assert!(encode_with_limit(Format::BC1_UNORM, 64 * 1024, false).is_ok());
+    assert!(encode_with_limit(Format::BC1_UNORM, usize::MAX, false).is_ok());
+
+    // generating mipmaps needs buffers for resizing
+    let header = Header::new_image(64, 64, Format::R8G8B8A8_UNORM).with_mipmaps();
+    let write_options = WriteOptions {
+        generate_mipmaps: true,
+        ..Default::default()
+    };
+    let encode_mipmaps = |memory_limit: usize| {
+        let mut file = Vec::new();
+        let mut encoder = Encoder::new(&mut file, Format::R8G8B8A8_UNORM, &header)?;
+        encoder.options.memory_limit = memory_limit;
+        encoder.write_surface_with(image, |_| {}, &write_options)?;
+        encoder.finish()
+    };
+    assert!(exceeded(encode_mipmaps(1024)));
+    assert!(encode_mipmaps(64 * 1024).is_ok());
+}
+
+#[test]
+fn encode_options_builder() {
+    let size = Size::new(256, 256);
+    let mut pixels = vec![0_u8; size.pixels() as usize * 4];
+    rand::RngCore::fill_bytes(&mut util::create_rng(), &mut pixels);
+    let image = ImageView::new(pixels.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+
+    let options = EncodeOptions::default()
+        .with_quality(CompressionQuality::Fast)
+        .with_dithering(Dithering::ColorAndAlpha)
+        .with_bc1_alpha_threshold(0);
+    assert_eq!(options.quality, CompressionQuality::Fast);
+    assert_eq!(options.dithering, Dithering::ColorAndAlpha);
+    assert_eq!(options.bc1_alpha_threshold, 0);
+    assert!(options.parallel);
+
+    // encoding on a single thread produces the same output
+    for format in [Format::BC1_UNORM, Format::B5G6R5_UNORM] {
+        let encode_surface = |options: EncodeOptions| {
+            let header = Header::new_image(size.width, size.height, format);
+            let mut file = Vec::new();
+            let mut encoder = Encoder::new(&mut file, format, &header)
+                .unwrap()
+                .with_options(options);
+            encoder.write_surface(image).unwrap();
+            encoder.finish().unwrap();
+            file
+        };
+        assert_eq!(
+            encode_surface(options.clone()),
+            encode_surface(options.clone().with_parallel(false))
+        );
+    }
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn encode_image_crate() {
+    use image::DynamicImage;
+
+    let (width, height) = (13, 6);
+    let size = Size::new(width, height);
+    let mut rng = util::create_rng();
+    let mut random_image = |color: ColorFormat| {
+        let mut data = vec![0_u8; color.buffer_size(size).unwrap()];
+        match color.precision {
+            Precision::F32 => {
+                let floats: Vec<f32> = (0..data.len() / 4).map(|_| rng.gen()).collect();
+                data = util::as_bytes(&floats).to_vec();
+            }
+            _ => rng.fill_bytes(&mut data),
+        }
+        data
+    };
+
+    let luma8 = random_image(ColorFormat::GRAYSCALE_U8);
+    let rgb16 = random_image(ColorFormat::RGB_U16);
+    let rgba32f = random_image(ColorFormat::RGBA_F32);
+    let cases = [
+        (
+            DynamicImage::ImageLuma8(image::ImageBuffer::from_raw(width, height, luma8).unwrap()),
+            ColorFormat::GRAYSCALE_U8,
+        ),
+        (
+            DynamicImage::ImageRgb16(
+                image::ImageBuffer::from_raw(width, height, util::cast_slice(&rgb16).to_vec())
+                    .unwrap(),
+            ),
+            ColorFormat::RGB_U16,
+        ),
+        (
+            DynamicImage::ImageRgba32F(
+                image::ImageBuffer::from_raw(width, height, util::cast_slice(&rgba32f).to_vec())
+                    .unwrap(),
+            ),
+            ColorFormat::RGBA_F32,
+        ),
+    ];
+
+    let options = EncodeOptions::default();
+    for (image, color) in cases {
+        for format in [Format::R8G8B8A8_UNORM, Format::R16_UNORM, Format::BC1_UNORM] {
+            let mut actual = Vec::new();
+            dds::encode_image(&image, format, &options, &mut actual).unwrap();
+
+            let view = ImageView::new(image.as_bytes(), size, color).unwrap();
+            let mut expected = Vec::new();
+            encode(&mut expected, view, format, &options).unwrap();
+            assert_eq!(actual, expected, "{:?} {:?}", color, format);
+        }
+    }
+
+    // luma + alpha is converted to RGBA
+    let mut luma_alpha = vec![0_u16; (width * height * 2) as usize];
+    rng.fill(luma_alpha.as_mut_slice());
+    let luma_alpha = DynamicImage::ImageLumaA16(
+        image::ImageBuffer::from_raw(width, height, luma_alpha).unwrap(),
+    );
+    let rgba = luma_alpha.to_rgba16();
+    let view = ImageView::new(util::as_bytes(rgba.as_raw()), size, ColorFormat::RGBA_U16).unwrap();
+    let mut expected = Vec::new();
+    encode(&mut expected, view, Format::R8G8B8A8_UNORM, &options).unwrap();
+    let mut actual = Vec::new();
+    dds::encode_image(&luma_alpha, Format::R8G8B8A8_UNORM, &options, &mut actual).unwrap();
+    assert_eq!(actual, expected);
+}
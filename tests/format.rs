@@ -13,7 +13,8 @@ fn short_name(channels: Channels) -> &'static str {
 
 #[test]
 fn format_metadata() {
-    let mut table = util::PrettyTable::from_header(&["Format", "C", "P", "bpp", "Encoding"]);
+    let mut table =
+        util::PrettyTable::from_header(&["Format", "C", "P", "bpp", "Block", "Bits", "Encoding"]);
     table.add_empty_row();
     let mut conv_table = util::PrettyTable::from_header(&["Format", "DXGI", "FourCC", "Masked"]);
     conv_table.add_empty_row();
@@ -60,7 +61,18 @@ fn format_metadata() {
             format!("{:?}", format),
             short_name(format.channels()).to_string(),
             format!("{:?}", format.precision()),
-            format!("{:?}", PixelInfo::from(format).bits_per_pixel()),
+            format!("{:?}", format.bits_per_pixel()),
+            format!(
+                "{}x{} {}B",
+                format.block_dimensions().0,
+                format.block_dimensions().1,
+                format.bytes_per_block()
+            ),
+            if let Some(bits) = format.channel_bits() {
+                format!("{}:{}:{}:{}", bits.r, bits.g, bits.b, bits.a)
+            } else {
+                "-".to_string()
+            },
             encoding,
         ]);
 
@@ -125,3 +137,272 @@ fn format_conversion() {
         }
     }
 }
+
+#[test]
+fn format_dxgi_aliases() {
+    let mut total = 0;
+    for &format in util::ALL_FORMATS {
+        let aliases = format.dxgi_aliases();
+        for &dxgi in aliases {
+            assert_eq!(Format::from_dxgi(dxgi), Some(format), "{:?}", dxgi);
+        }
+        if let Ok(dxgi) = DxgiFormat::try_from(format) {
+            assert!(aliases.contains(&dxgi), "{:?}", format);
+        }
+        total += aliases.len();
+    }
+
+    // every supported DXGI format is the alias of exactly one format
+    let supported = (0..=255_u32)
+        .filter_map(|i| DxgiFormat::try_from(i).ok())
+        .filter(|&dxgi| Format::from_dxgi(dxgi).is_some())
+        .count();
+    assert_eq!(total, supported);
+}
+
+#[test]
+fn format_round_size() {
+    let sizes = [
+        Size::new(1, 1),
+        Size::new(5, 3),
+        Size::new(16, 9),
+        Size::new(u32::MAX, 7),
+    ];
+
+    for format in util::ALL_FORMATS.iter().copied() {
+        let multiple = format.size_multiple();
+        for size in sizes {
+            let up = format.round_size_up(size);
+            let down = format.round_size_down(size);
+
+            assert!(up.is_multiple_of(multiple), "{:?} {:?}", format, size);
+            assert!(down.is_multiple_of(multiple), "{:?} {:?}", format, size);
+            assert!(down.width <= size.width && down.height <= size.height);
+            if size.width <= u32::MAX - 8 {
+                assert!(up.width >= size.width && up.height >= size.height);
+            }
+
+            if size.is_multiple_of(multiple) {
+                assert_eq!(up, size);
+                assert_eq!(down, size);
+            }
+        }
+
+        // rounded sizes must be accepted by the encoder
+        if format.encoding_support().is_some() {
+            let size = format.round_size_up(Size::new(5, 3));
+            let data = vec![0_u8; size.pixels() as usize * 4];
+            let image = ImageView::new(data.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+            let mut output = Vec::new();
+            encode(&mut output, image, format, &EncodeOptions::default()).unwrap();
+        }
+    }
+}
+
+#[test]
+fn format_all() {
+    let all = Format::all();
+    for (i, format) in all.iter().enumerate() {
+        assert!(!all[..i].contains(format), "{:?} is listed twice", format);
+
+        let support = format.support();
+        assert!(support.decode);
+        assert_eq!(
+            support.encode.is_some(),
+            format.encoding_support().is_some()
+        );
+    }
+
+    // every format that can be detected is listed
+    for value in 0..=255_u32 {
+        if let Some(format) = DxgiFormat::try_from(value).ok().and_then(Format::from_dxgi) {
+            assert!(all.contains(&format), "{:?}", format);
+        }
+    }
+    for four_cc in [FourCC::DXT2, FourCC::DXT4, FourCC::RXGB, FourCC::UYVY] {
+        assert!(all.contains(&Format::from_four_cc(four_cc).unwrap()));
+    }
+}
+
+#[test]
+fn bc1_alpha_usage() {
+    let opaque = [0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF];
+    let three_color = |transparent: &[(u32, u32)]| {
+        let mut indexes = 0_u32;
+        for &(x, y) in transparent {
+            indexes |= 0b11 << ((y * 4 + x) * 2);
+        }
+        let i = indexes.to_le_bytes();
+        [0, 0, 0xFF, 0xFF, i[0], i[1], i[2], i[3]]
+    };
+    // pixels outside of the 6x6 and 1x1 surfaces
+    let outside_6x6 = three_color(&[(2, 0), (3, 1), (0, 2), (3, 3)]);
+    let outside_1x1 = three_color(&[(1, 0), (0, 1)]);
+
+    let header = Header::new_image(6, 6, Format::BC1_UNORM).with_mipmaps();
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let data_start = file.len();
+    for block in [
+        opaque,
+        opaque,
+        opaque,
+        outside_6x6,
+        three_color(&[]),
+        outside_1x1,
+    ] {
+        file.extend_from_slice(&block);
+    }
+
+    let mut reader = file.as_slice();
+    let info = DdsInfo::read(&mut reader).unwrap();
+    let usage = Bc1AlphaUsage::from_reader(&mut reader, &info).unwrap();
+    assert!(reader.is_empty());
+    assert_eq!(usage.blocks, 6);
+    assert_eq!(usage.three_color_blocks, 3);
+    assert_eq!(usage.transparent_blocks, 0);
+    assert!(!usage.uses_alpha());
+    assert_eq!(Format::BC1_UNORM.channels(), Channels::Rgba);
+    assert_eq!(
+        Format::BC1_UNORM.channels_with_bc1_alpha(&usage),
+        Channels::Rgb
+    );
+
+    // a single transparent pixel inside the 1x1 mipmap
+    file.truncate(file.len() - 8);
+    file.extend_from_slice(&three_color(&[(0, 0), (1, 1)]));
+    let mut reader = &file[data_start..];
+    let usage = Bc1AlphaUsage::from_reader(&mut reader, &info).unwrap();
+    assert_eq!(usage.transparent_blocks, 1);
+    assert_eq!(usage.transparent_pixels, 1);
+    assert_eq!(
+        Format::BC1_UNORM.channels_with_bc1_alpha(&usage),
+        Channels::Rgba
+    );
+
+    // other formats are not affected
+    assert_eq!(
+        Format::BC3_UNORM.channels_with_bc1_alpha(&usage),
+        Channels::Rgba
+    );
+    assert_eq!(
+        Format::BC4_UNORM.channels_with_bc1_alpha(&Bc1AlphaUsage::default()),
+        Channels::Grayscale
+    );
+}
+
+#[test]
+fn format_from_str() {
+    for format in util::ALL_FORMATS.iter().copied() {
+        let name = format.to_string();
+        assert_eq!(name, format!("{:?}", format));
+        assert_eq!(name.parse(), Ok(format));
+        assert_eq!(name.to_ascii_lowercase().parse(), Ok(format));
+    }
+
+    // aliases
+    assert_eq!("DXT1".parse(), Ok(Format::BC1_UNORM));
+    assert_eq!("DXT2".parse(), Ok(Format::BC2_UNORM_PREMULTIPLIED_ALPHA));
+    assert_eq!("dxt3".parse(), Ok(Format::BC2_UNORM));
+    assert_eq!("DXT5".parse(), Ok(Format::BC3_UNORM));
+    assert_eq!("ATI2".parse(), Ok(Format::BC5_UNORM));
+    assert_eq!("BC4S".parse(), Ok(Format::BC4_SNORM));
+    assert_eq!("BC6H".parse(), Ok(Format::BC6H_UF16));
+    assert_eq!("bc7".parse(), Ok(Format::BC7_UNORM));
+    assert_eq!("R8G8B8A8_UNORM_SRGB".parse(), Ok(Format::R8G8B8A8_UNORM));
+    assert_eq!("DXGI_FORMAT_BC7_TYPELESS".parse(), Ok(Format::BC7_UNORM));
+
+    for invalid in [
+        "",
+        "BC8",
+        "DXT6",
+        "DX10",
+        "R8G8B8A8",
+        "BC1_UNORM ",
+        "D32_FLOAT",
+    ] {
+        let error = invalid.parse::<Format>().unwrap_err();
+        assert_eq!(error.input(), invalid);
+    }
+}
+
+#[test]
+fn dxgi_format_from_str() {
+    let mut named = 0;
+    for value in 0..=255_u32 {
+        let format = match DxgiFormat::try_from(value) {
+            Ok(format) => format,
+            Err(_) => continue,
+        };
+        assert_eq!(format.to_string().parse(), Ok(format));
+        assert_eq!(value.to_string().parse(), Ok(format));
+
+        if let Some(name) = format.name() {
+            named += 1;
+            assert_eq!(format.to_string(), name);
+            assert_eq!(format!("DXGI_FORMAT_{}", name).parse(), Ok(format));
+            assert_eq!(name.to_ascii_lowercase().parse(), Ok(format));
+        }
+    }
+    assert!(named > 100);
+
+    assert_eq!(DxgiFormat::BC7_UNORM_SRGB.to_string(), "BC7_UNORM_SRGB");
+    assert!("BC7".parse::<DxgiFormat>().is_err());
+    assert!("200".parse::<DxgiFormat>().is_err());
+    assert!("DXGI_FORMAT_".parse::<DxgiFormat>().is_err());
+}
+
+#[test]
+fn four_cc_from_str() {
+    assert_eq!(FourCC::DXT1.to_string(), "DXT1");
+    assert_eq!("DXT1".parse(), Ok(FourCC::DXT1));
+    assert_eq!("BC4U".parse(), Ok(FourCC::BC4U));
+    assert_eq!(FourCC(0x0000_0071).to_string(), "0x71");
+    assert_eq!(format!("{:?}", FourCC::DX10), "FourCC(DX10)");
+
+    // FourCC codes are case-sensitive
+    assert_ne!("dxt1".parse(), Ok(FourCC::DXT1));
+    assert!("DXT".parse::<FourCC>().is_err());
+    assert!("DXT10".parse::<FourCC>().is_err());
+    assert!("DXTü".parse::<FourCC>().is_err());
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn format_wgpu() {
+    use wgpu_types::TextureFormat;
+
+    let mut supported = 0;
+    for &format in util::ALL_FORMATS {
+        if let Ok(texture_format) = TextureFormat::try_from(format) {
+            assert!(!texture_format.is_srgb(), "{:?}", format);
+            assert_eq!(Format::try_from(texture_format), Ok(format));
+            assert_eq!(
+                Format::try_from(texture_format.add_srgb_suffix()),
+                Ok(format)
+            );
+            supported += 1;
+        }
+    }
+    assert!(supported > 40, "{}", supported);
+
+    // sRGB is preserved when going through DXGI
+    for i in 0..=255_u32 {
+        if let Ok(dxgi) = DxgiFormat::try_from(i) {
+            if let Ok(texture_format) = TextureFormat::try_from(dxgi) {
+                assert_eq!(texture_format.is_srgb(), dxgi.is_srgb(), "{:?}", dxgi);
+                assert_eq!(DxgiFormat::try_from(texture_format), Ok(dxgi));
+            }
+        }
+    }
+
+    assert_eq!(
+        TextureFormat::try_from(DxgiFormat::BC7_UNORM_SRGB),
+        Ok(TextureFormat::Bc7RgbaUnormSrgb)
+    );
+    assert_eq!(TextureFormat::try_from(Format::B5G6R5_UNORM), Err(()));
+    assert_eq!(
+        TextureFormat::try_from(Format::BC3_UNORM_PREMULTIPLIED_ALPHA),
+        Err(())
+    );
+}
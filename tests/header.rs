@@ -402,6 +402,16 @@ fn weird_and_invalid_headers() {
         // invalid pixel format rgb bit count
         apply_edit(valid_dx9_masked(), |raw| raw.pixel_format.rgb_bit_count = 7),
         //
+        // FourCC and contradicting masks
+        apply_edit(valid_dx9_masked(), |raw| {
+            raw.pixel_format.flags |= PixelFormatFlags::FOURCC;
+            raw.pixel_format.four_cc = FourCC::DXT1;
+            raw.pixel_format.r_bit_mask = 0xff0000;
+            raw.pixel_format.g_bit_mask = 0xff00;
+            raw.pixel_format.b_bit_mask = 0xff;
+            raw.pixel_format.a_bit_mask = 0;
+        }),
+        //
         // invalid dxgi_format
         apply_edit_dx10(valid_dx10(), |dx10| dx10.dxgi_format = 0),
         apply_edit_dx10(valid_dx10(), |dx10| dx10.dxgi_format = 1234),
@@ -459,6 +469,208 @@ fn weird_and_invalid_headers() {
     util::compare_snapshot_text(&util::test_data_dir().join("header_parsing.txt"), output).unwrap()
 }
 
+#[test]
+fn ambiguous_pixel_format() {
+    let mut raw = Header::new_image(16, 16, Format::R8G8B8A8_UNORM)
+        .to_dx9()
+        .unwrap();
+    let masks = match raw.pixel_format {
+        Dx9PixelFormat::Mask(masks) => masks,
+        Dx9PixelFormat::FourCC(_) => unreachable!(),
+    };
+    raw.pixel_format = Dx9PixelFormat::FourCC(FourCC::DXT1);
+    let mut raw = Header::from(raw).to_raw();
+    raw.pixel_format.flags |= masks.flags;
+    raw.pixel_format.rgb_bit_count = masks.rgb_bit_count.into();
+    raw.pixel_format.r_bit_mask = masks.r_bit_mask;
+    raw.pixel_format.g_bit_mask = masks.g_bit_mask;
+    raw.pixel_format.b_bit_mask = masks.b_bit_mask;
+    raw.pixel_format.a_bit_mask = masks.a_bit_mask;
+    assert_eq!(raw.pixel_format.contradicting_masks(), Some(masks));
+
+    // strict parsing rejects the header
+    let result = Header::from_raw(&raw, &ParseOptions::default());
+    assert!(matches!(
+        result,
+        Err(HeaderError::AmbiguousPixelFormat(FourCC::DXT1))
+    ));
+
+    // permissive parsing prefers the FourCC code, unless only the masks
+    // match the file length
+    let parse = |file_len: Option<u64>| {
+        let header = Header::from_raw(&raw, &ParseOptions::new_permissive(file_len)).unwrap();
+        Format::from_header(&header).unwrap()
+    };
+    let header_len = 4 + 124;
+    assert_eq!(parse(None), Format::BC1_UNORM);
+    assert_eq!(parse(Some(header_len + 128)), Format::BC1_UNORM);
+    assert_eq!(parse(Some(header_len + 1024)), Format::R8G8B8A8_UNORM);
+    assert_eq!(parse(Some(header_len + 1)), Format::BC1_UNORM);
+
+    // the ambiguity is reported
+    let mut file = Header::MAGIC.to_vec();
+    raw.write(&mut file).unwrap();
+    file.resize(header_len as usize + 1024, 0);
+    let report = DdsInfoReport::from_reader(
+        &mut std::io::Cursor::new(&file),
+        &ParseOptions::new_permissive(None),
+    )
+    .unwrap();
+    assert_eq!(report.format, Format::R8G8B8A8_UNORM);
+    assert!(report.header_fixed);
+    assert_eq!(report.fixes, [HeaderFix::BitMasks]);
+    assert_eq!(
+        report.warnings,
+        [ReportWarning::AmbiguousPixelFormat {
+            four_cc: FourCC::DXT1,
+            used_masks: true
+        }]
+    );
+    assert!(report.warnings[0]
+        .to_string()
+        .contains("bit masks were used"));
+
+    // masks that agree with the FourCC code or don't describe a format are fine
+    raw.pixel_format.rgb_bit_count = 0;
+    assert_eq!(raw.pixel_format.contradicting_masks(), None);
+    assert!(Header::from_raw(&raw, &ParseOptions::default()).is_ok());
+}
+
+#[test]
+fn dimension_limits() {
+    let parse =
+        |header: Header, options: &ParseOptions| Header::from_raw(&header.to_raw(), options);
+    let defaults = ParseOptions::default();
+
+    // the defaults allow the largest textures of Direct3D 11 and 12
+    let huge_image = Header::new_image(16384, 16384, Format::BC1_UNORM);
+    assert!(parse(huge_image, &defaults).is_ok());
+    let volume = Header::new_volume(2048, 1024, 2048, Format::R8G8B8A8_UNORM);
+    assert!(parse(volume, &defaults).is_ok());
+    let array = Header::new_texture_array(16, 16, 2048, Format::BC1_UNORM).unwrap();
+    assert!(parse(array, &defaults).is_ok());
+    let cube_map = Header::new_cube_map(16384, 16384, Format::BC1_UNORM);
+    assert!(parse(cube_map, &defaults).is_ok());
+
+    // but not malicious headers
+    let mut file = Vec::new();
+    Header::new_volume(65535, 65535, 2048, Format::R8G8B8A8_UNORM)
+        .write(&mut file)
+        .unwrap();
+    assert!(matches!(
+        Header::read(&mut file.as_slice(), &defaults),
+        Err(HeaderError::WidthTooLarge {
+            width: 65535,
+            max_width: 16384
+        })
+    ));
+
+    let wide = Header::new_image(16385, 16, Format::BC1_UNORM);
+    assert!(matches!(
+        parse(wide.clone(), &defaults),
+        Err(HeaderError::WidthTooLarge {
+            width: 16385,
+            max_width: 16384
+        })
+    ));
+    let tall = Header::new_image(16, 16385, Format::BC1_UNORM);
+    assert!(matches!(
+        parse(tall, &defaults),
+        Err(HeaderError::HeightTooLarge { height: 16385, .. })
+    ));
+    let deep = Header::new_volume(16, 16, 2049, Format::R8G8B8A8_UNORM);
+    assert!(matches!(
+        parse(deep, &defaults),
+        Err(HeaderError::DepthTooLarge {
+            depth: 2049,
+            max_depth: 2048
+        })
+    ));
+    let mips = Header::new_image(16, 16, Format::BC1_UNORM)
+        .with_mipmap_count(std::num::NonZeroU32::new(33).unwrap());
+    assert!(matches!(
+        parse(mips, &defaults),
+        Err(HeaderError::MipmapCountTooLarge {
+            mipmap_count: 33,
+            max_mipmap_count: 32
+        })
+    ));
+    // mipmap counts the layout can't represent keep their layout error
+    let mips = Header::new_image(16, 16, Format::BC1_UNORM)
+        .with_mipmap_count(std::num::NonZeroU32::new(256).unwrap());
+    let mips = parse(mips, &defaults).unwrap();
+    assert!(matches!(
+        DataLayout::from_header(&mips),
+        Err(DecodeError::Layout(LayoutError::TooManyMipMaps(256)))
+    ));
+    let array = Header::new_texture_array(16, 16, 2049, Format::BC1_UNORM).unwrap();
+    assert!(matches!(
+        parse(array, &defaults),
+        Err(HeaderError::ArraySizeTooLarge {
+            array_size: 2049,
+            max_array_size: 2048
+        })
+    ));
+
+    // the total number of pixels is limited too
+    let big_volume = Header::new_volume(2048, 2048, 2048, Format::R8G8B8A8_UNORM);
+    assert!(matches!(
+        parse(big_volume, &defaults),
+        Err(HeaderError::TooManyPixels {
+            pixels: 0x2_0000_0000,
+            max_pixels: 0x1_0000_0000
+        })
+    ));
+    let big_array = Header::new_texture_array(16384, 16384, 17, Format::BC1_UNORM).unwrap();
+    assert!(matches!(
+        parse(big_array, &defaults),
+        Err(HeaderError::TooManyPixels { .. })
+    ));
+    let big_cube_maps = Header::new_cube_map_array(8192, 8192, 11, Format::BC1_UNORM).unwrap();
+    assert!(matches!(
+        parse(big_cube_maps, &defaults),
+        Err(HeaderError::TooManyPixels {
+            pixels: 0x1_0800_0000,
+            ..
+        })
+    ));
+
+    // permissive parsing still enforces limits
+    assert!(matches!(
+        parse(wide.clone(), &ParseOptions::new_permissive(None)),
+        Err(HeaderError::WidthTooLarge {
+            width: 16385,
+            max_width: 16384
+        })
+    ));
+
+    // limits are configurable
+    let mut options = ParseOptions::default();
+    options.max_width = u32::MAX;
+    assert!(parse(wide, &options).is_ok());
+    options.max_width = 8;
+    options.max_height = 8;
+    let image = Header::new_image(8, 8, Format::BC1_UNORM);
+    assert!(parse(image, &options).is_ok());
+    let image = Header::new_image(8, 9, Format::BC1_UNORM);
+    assert!(matches!(
+        parse(image, &options),
+        Err(HeaderError::HeightTooLarge {
+            height: 9,
+            max_height: 8
+        })
+    ));
+    let options = ParseOptions::default().with_max_pixels(63);
+    let image = Header::new_image(8, 8, Format::BC1_UNORM);
+    assert!(matches!(
+        parse(image, &options),
+        Err(HeaderError::TooManyPixels {
+            pixels: 64,
+            max_pixels: 63
+        })
+    ));
+}
+
 /// If we create a header with a certain format, then we should detect exactly
 /// this format from the header.
 #[test]
@@ -470,3 +682,700 @@ fn format_from_header() {
         assert_eq!(format, detect_format.unwrap());
     }
 }
+
+#[test]
+fn format_error_closest_match() {
+    let dx9 = Header::new_image(4, 4, Format::R8G8B8A8_UNORM)
+        .to_dx9()
+        .unwrap();
+    let rgba = match dx9.pixel_format.clone() {
+        Dx9PixelFormat::Mask(pixel_format) => pixel_format,
+        _ => panic!("expected masks"),
+    };
+    let closest = |pixel_format: Dx9PixelFormat| {
+        let header = Header::Dx9(dx9.clone().with_pixel_format(pixel_format));
+        let error = Format::from_header(&header).unwrap_err();
+        (error.closest_match(), error.to_string())
+    };
+
+    // missing alpha flag
+    let (closest_match, message) = closest(Dx9PixelFormat::Mask(MaskPixelFormat {
+        flags: PixelFormatFlags::RGB,
+        ..rgba.clone()
+    }));
+    assert_eq!(
+        closest_match,
+        Some((Format::R8G8B8A8_UNORM, FormatDifference::Flags))
+    );
+    assert!(message.contains("R 0x000000FF G 0x0000FF00 B 0x00FF0000 A 0xFF000000"));
+    // the message names the closest match
+    assert!(message.ends_with("; looks like R8G8B8A8_UNORM with different flags"));
+
+    // swapped masks
+    let (closest_match, _) = closest(Dx9PixelFormat::Mask(MaskPixelFormat {
+        r_bit_mask: rgba.a_bit_mask,
+        a_bit_mask: rgba.r_bit_mask,
+        ..rgba.clone()
+    }));
+    assert_eq!(
+        closest_match.map(|(_, difference)| difference),
+        Some(FormatDifference::SwappedMasks)
+    );
+
+    // the closest match is always one of the known pixel formats
+    let (closest_match, _) = closest(Dx9PixelFormat::Mask(MaskPixelFormat {
+        rgb_bit_count: RgbBitCount::Count24,
+        ..rgba.clone()
+    }));
+    let (format, difference) = closest_match.unwrap();
+    assert_eq!(difference, FormatDifference::BitCount);
+    assert!(Format::known_pixel_formats().any(|(_, known)| known == format));
+
+    // nothing similar
+    let (closest_match, message) = closest(Dx9PixelFormat::Mask(MaskPixelFormat {
+        r_bit_mask: 0x7,
+        ..rgba
+    }));
+    assert_eq!(closest_match, None);
+    assert!(!message.contains("looks like"));
+
+    // FourCC with the wrong byte order
+    let (closest_match, _) = closest(Dx9PixelFormat::FourCC(FourCC(u32::from_le_bytes(*b"1TXD"))));
+    assert_eq!(
+        closest_match,
+        Some((Format::BC1_UNORM, FormatDifference::FourCCSpelling))
+    );
+
+    // DXGI format with a different channel type
+    let error = FormatError::UnsupportedDxgiFormat(DxgiFormat::R16G16B16A16_SINT);
+    assert_eq!(
+        error.closest_match(),
+        Some((Format::R16G16B16A16_UNORM, FormatDifference::ChannelType))
+    );
+    assert_eq!(
+        FormatError::UnsupportedDxgiFormat(DxgiFormat::R32G8X24_TYPELESS).closest_match(),
+        None
+    );
+}
+
+#[test]
+fn array_constructors() {
+    let format = Format::BC1_UNORM;
+    let textures = Header::new_texture_array(8, 4, 3, format).unwrap();
+    let cube_maps = Header::new_cube_map_array(8, 8, 2, format).unwrap();
+
+    for (header, kind, len) in [
+        (textures, TextureArrayKind::Textures, 3),
+        (cube_maps, TextureArrayKind::CubeMaps, 12),
+    ] {
+        let mut bytes = Vec::new();
+        header.write(&mut bytes).unwrap();
+        let parsed = Header::read(&mut &bytes[..], &ParseOptions::default()).unwrap();
+        assert_eq!(header, parsed);
+
+        let array = match DataLayout::from_header(&parsed).unwrap() {
+            DataLayout::TextureArray(array) => array,
+            layout => panic!("unexpected layout {:?}", layout),
+        };
+        assert_eq!(array.kind(), kind);
+        assert_eq!(array.len(), len);
+    }
+
+    // DX9-only formats can't be stored in arrays
+    let format = Format::R8G8B8_UNORM;
+    assert!(Header::new_texture_array(8, 4, 3, format).is_none());
+    assert!(Header::new_cube_map_array(8, 8, 2, format).is_none());
+    assert_eq!(
+        Header::new_texture_array(8, 4, 1, format),
+        Some(Header::new_image(8, 4, format))
+    );
+    assert_eq!(
+        Header::new_cube_map_array(8, 8, 1, format),
+        Some(Header::new_cube_map(8, 8, format))
+    );
+}
+
+#[test]
+fn header_compat() {
+    for &format in util::ALL_FORMATS {
+        let header = Header::new_image(8, 8, format);
+        let dx9 = header.to_dx9();
+        let dx10 = header.to_dx10();
+
+        let prefer_dx9 = header.to_compat(HeaderCompat::PreferDx9).unwrap();
+        assert_eq!(prefer_dx9.dx9().is_some(), dx9.is_some(), "{:?}", format);
+        let prefer_dx10 = header.to_compat(HeaderCompat::PreferDx10).unwrap();
+        assert_eq!(prefer_dx10.dx10().is_some(), dx10.is_some(), "{:?}", format);
+
+        match header.to_compat(HeaderCompat::Dx9Only) {
+            Ok(header) => assert_eq!(Some(header), dx9.map(Header::Dx9)),
+            Err(HeaderError::UnsupportedHeaderCompat(HeaderCompat::Dx9Only)) => {
+                assert!(dx9.is_none())
+            }
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+        match header.to_compat(HeaderCompat::Dx10Only) {
+            Ok(header) => assert_eq!(Some(header), dx10.map(Header::Dx10)),
+            Err(HeaderError::UnsupportedHeaderCompat(HeaderCompat::Dx10Only)) => {
+                assert!(dx10.is_none())
+            }
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+
+        // the converted header describes the same format
+        assert_eq!(Format::from_header(&prefer_dx9).unwrap(), format);
+    }
+
+    // texture arrays require DX10 headers
+    let array = Header::new_texture_array(8, 8, 2, Format::BC1_UNORM).unwrap();
+    assert!(array.to_compat(HeaderCompat::Dx9Only).is_err());
+    assert_eq!(array.to_compat(HeaderCompat::PreferDx9).unwrap(), array);
+}
+
+#[test]
+fn reserved1_tags() {
+    let header = Header::new_image(4, 4, Format::R8G8B8A8_UNORM);
+    let ddsd = FourCC(u32::from_le_bytes(*b"DDSD"));
+
+    let tags = [
+        Reserved1Tag::Nvtt {
+            version: 2 << 16 | 8,
+        },
+        Reserved1Tag::Gimp { version: 3 },
+        Reserved1Tag::Custom {
+            id: ddsd,
+            version: 1,
+        },
+    ];
+    for tag in tags {
+        let mut file = Vec::new();
+        let mut encoder =
+            Encoder::new_with_reserved1_tag(&mut file, Format::R8G8B8A8_UNORM, &header, &tag)
+                .unwrap();
+        let image = [0_u8; 4 * 4 * 4];
+        let image = ImageView::new(&image[..], Size::new(4, 4), ColorFormat::RGBA_U8).unwrap();
+        encoder.write_surface(image).unwrap();
+        encoder.finish().unwrap();
+
+        let decoder = Decoder::new(file.as_slice()).unwrap();
+        assert_eq!(decoder.info().reserved1_tag(), Some(&tag));
+    }
+
+    // untagged headers
+    let raw = header.to_raw();
+    assert_eq!(Reserved1Tag::from_raw_header(&raw), None);
+
+    // arbitrary bytes
+    let mut bytes = [0; 44];
+    bytes[..8].copy_from_slice(b"DDSD\x02\0\0\0");
+    bytes[40..].copy_from_slice(b"\x01\x02\x03\x04");
+    let mut raw = header.to_raw();
+    Reserved1Tag::Bytes(bytes).apply_to_raw_header(&mut raw);
+    assert_eq!(raw.reserved1[10], 0x04030201);
+    assert_eq!(
+        Reserved1Tag::from_raw_header(&raw),
+        Some(Reserved1Tag::Custom {
+            id: ddsd,
+            version: 2
+        })
+    );
+}
+
+#[test]
+fn detect_writer() {
+    let read_raw = |path: &std::path::Path| {
+        let mut file = File::open(path).unwrap();
+        Header::read_magic(&mut file).unwrap();
+        RawHeader::read(&mut file).unwrap()
+    };
+    for entry in std::fs::read_dir(util::test_data_dir().join("images/nvtt")).unwrap() {
+        let path = entry.unwrap().path();
+        assert_eq!(
+            read_raw(&path).detect_writer(),
+            DdsWriter::Nvtt,
+            "{:?}",
+            path
+        );
+    }
+
+    let check = |format: Format, edit: fn(&mut RawHeader), expected: DdsWriter| {
+        let header = Header::new_image(4, 4, format);
+        let header = header.to_dx9().map(Header::Dx9).unwrap_or(header);
+        let mut raw = header.to_raw();
+        edit(&mut raw);
+        assert_eq!(raw.detect_writer(), expected, "{:?}", format);
+    };
+    check(
+        Format::BC1_UNORM,
+        |raw| Reserved1Tag::Gimp { version: 1 }.apply_to_raw_header(raw),
+        DdsWriter::Gimp,
+    );
+    check(Format::BC1_UNORM, |_| {}, DdsWriter::DirectXTex);
+    check(
+        Format::BC4_UNORM,
+        |raw| raw.flags.remove(DdsFlags::LINEAR_SIZE),
+        DdsWriter::DirectXTex,
+    );
+    check(
+        Format::BC1_UNORM,
+        |raw| raw.flags.remove(DdsFlags::MIPMAP_COUNT),
+        DdsWriter::PaintNet,
+    );
+    check(
+        Format::R8G8B8A8_UNORM,
+        |raw| raw.flags.remove(DdsFlags::PITCH),
+        DdsWriter::Unknown,
+    );
+    check(
+        Format::R8G8B8A8_UNORM,
+        |raw| raw.reserved1[3] = 1,
+        DdsWriter::Unknown,
+    );
+}
+
+#[test]
+fn pitch_or_linear_size() {
+    let cases = [
+        (Format::R8G8B8A8_UNORM, DdsFlags::PITCH, 5 * 4),
+        (Format::B5G6R5_UNORM, DdsFlags::PITCH, 5 * 2),
+        (Format::YUY2, DdsFlags::PITCH, 3 * 4),
+        (Format::NV12, DdsFlags::PITCH, 5),
+        (Format::BC1_UNORM, DdsFlags::LINEAR_SIZE, 2 * 8),
+        (Format::BC7_UNORM, DdsFlags::LINEAR_SIZE, 2 * 16),
+    ];
+    for (format, flag, value) in cases {
+        let header = Header::new_image(5, 3, format).with_mipmaps();
+        let raw = header.to_raw();
+        assert!(raw.flags.contains(flag), "{:?}", format);
+        assert!(
+            !raw.flags.contains(DdsFlags::PITCH | DdsFlags::LINEAR_SIZE),
+            "{:?}",
+            format
+        );
+        assert_eq!(raw.pitch_or_linear_size, value, "{:?}", format);
+
+        let mut options = WriteHeaderOptions::default();
+        options.pitch_or_linear_size = false;
+        let raw = header.to_raw_with(&options);
+        assert!(!raw
+            .flags
+            .intersects(DdsFlags::PITCH | DdsFlags::LINEAR_SIZE));
+        assert_eq!(raw.pitch_or_linear_size, 0);
+        assert_eq!(
+            Header::from_raw(&raw, &ParseOptions::default()).unwrap(),
+            header
+        );
+    }
+}
+
+#[test]
+fn validate_file() {
+    let validate_raw = |raw: &RawHeader, data_len: usize| {
+        let mut file = Header::MAGIC.to_vec();
+        raw.write(&mut file).unwrap();
+        file.resize(file.len() + data_len, 0);
+        validate(&mut std::io::Cursor::new(&file), &ParseOptions::default())
+    };
+    let kinds = |issues: &[ValidationIssue]| issues.iter().map(|i| i.kind).collect::<Vec<_>>();
+
+    // files written by this crate are valid
+    let header = Header::new_image(8, 8, Format::BC1_UNORM).with_mipmaps();
+    let raw = header.to_raw();
+    assert_eq!(validate_raw(&raw, 32 + 8 + 8 + 8), vec![]);
+    let cube: Header = Header::new_cube_map(4, 4, Format::R8G8B8A8_UNORM)
+        .to_dx9()
+        .unwrap()
+        .into();
+    assert_eq!(validate_raw(&cube.to_raw(), 6 * 64), vec![]);
+
+    // data length
+    let issues = validate_raw(&raw, 40);
+    assert_eq!(kinds(&issues), [IssueKind::DataTooShort]);
+    assert_eq!(issues[0].severity, Severity::Error);
+    let issues = validate_raw(&raw, 64);
+    assert_eq!(kinds(&issues), [IssueKind::TrailingData]);
+    assert_eq!(issues[0].severity, Severity::Warning);
+
+    // flags and caps
+    let mut bad = raw.clone();
+    bad.flags.remove(DdsFlags::MIPMAP_COUNT | DdsFlags::WIDTH);
+    bad.caps = Caps::TEXTURE;
+    bad.pitch_or_linear_size = 7;
+    assert_eq!(
+        kinds(&validate_raw(&bad, 56)),
+        [
+            IssueKind::MissingRequiredFlag,
+            IssueKind::MipmapCountFlag,
+            IssueKind::MissingCaps,
+            IssueKind::IncorrectPitch,
+            IssueKind::TrailingData,
+        ]
+    );
+
+    // depth flag without volume caps
+    let mut bad = raw.clone();
+    bad.flags |= DdsFlags::DEPTH;
+    let issues = validate_raw(&bad, 56);
+    assert_eq!(issues[0].kind, IssueKind::DepthFlag);
+
+    // cube map faces
+    let mut partial = cube.to_raw();
+    partial.caps2.remove(Caps2::CUBE_MAP_NEGATIVE_Z);
+    let issues = validate_raw(&partial, 5 * 64);
+    assert_eq!(kinds(&issues), [IssueKind::CubeMapFaces]);
+    assert_eq!(issues[0].severity, Severity::Info);
+    let mut no_cube = cube.to_raw();
+    no_cube.caps2.remove(Caps2::CUBE_MAP);
+    assert_eq!(
+        kinds(&validate_raw(&no_cube, 64)),
+        [IssueKind::CubeMapFaces]
+    );
+
+    // DX10 array size
+    let array = Header::new_texture_array(4, 4, 1, Format::BC1_UNORM).unwrap();
+    let mut bad = array.to_raw();
+    bad.dx10.as_mut().unwrap().array_size = 0;
+    let issues = validate_raw(&bad, 8);
+    assert_eq!(
+        kinds(&issues),
+        [IssueKind::ArraySize, IssueKind::TrailingData]
+    );
+
+    // invalid headers
+    let mut bad = raw.clone();
+    bad.size = 24;
+    let issues = validate_raw(&bad, 56);
+    assert_eq!(kinds(&issues), [IssueKind::InvalidHeaderSize]);
+    let mut bad = raw.clone();
+    bad.pixel_format.four_cc = "ABCD".parse().unwrap();
+    let issues = validate_raw(&bad, 56);
+    assert_eq!(kinds(&issues), [IssueKind::UnsupportedFormat]);
+
+    // truncated files and wrong magic bytes
+    let issues = validate(&mut std::io::Cursor::new(b"DDS \x7c"), &Default::default());
+    assert_eq!(kinds(&issues), [IssueKind::UnexpectedEndOfFile]);
+    let issues = validate(&mut std::io::Cursor::new(b"PNG 1234"), &Default::default());
+    assert_eq!(kinds(&issues), [IssueKind::InvalidMagic]);
+}
+
+#[test]
+fn permissive_fixes() {
+    let fixes_of = |raw: &RawHeader, data_len: u64| {
+        let header_len = if raw.dx10.is_some() {
+            4 + 124 + 20
+        } else {
+            4 + 124
+        };
+        let file_len = header_len + data_len;
+        let options = ParseOptions::new_permissive(Some(file_len));
+        let (_, fixes) = Header::from_raw_with_fixes(raw, &options).unwrap();
+        fixes
+    };
+
+    // valid headers don't need fixes
+    let bc1 = Header::new_image(8, 8, Format::BC1_UNORM).with_mipmaps();
+    assert_eq!(fixes_of(&bc1.to_raw(), 32 + 8 + 8 + 8), vec![]);
+
+    // wrong mipmap count
+    assert_eq!(
+        fixes_of(&bc1.to_raw(), 32),
+        vec![HeaderFix::MipmapCount {
+            declared: 4,
+            fixed: 1
+        }]
+    );
+
+    // array size of 0 and single cube maps with an array size of 6
+    let array = Header::new_texture_array(4, 4, 1, Format::BC1_UNORM).unwrap();
+    let mut raw = array.to_raw();
+    raw.dx10.as_mut().unwrap().array_size = 0;
+    assert_eq!(
+        fixes_of(&raw, 8),
+        vec![HeaderFix::ArraySize {
+            declared: 0,
+            fixed: 1
+        }]
+    );
+    let cube = Header::new_cube_map(4, 4, Format::BC1_UNORM);
+    let mut raw = cube.to_raw();
+    raw.dx10.as_mut().unwrap().array_size = 6;
+    assert_eq!(
+        fixes_of(&raw, 6 * 8),
+        vec![HeaderFix::ArraySize {
+            declared: 6,
+            fixed: 1
+        }]
+    );
+
+    // invalid sizes and alpha modes
+    let mut raw = array.to_raw();
+    raw.size = 24;
+    raw.dx10.as_mut().unwrap().misc_flags2 = 7;
+    assert_eq!(
+        fixes_of(&raw, 8),
+        vec![HeaderFix::HeaderSize(24), HeaderFix::AlphaMode(7)]
+    );
+
+    // strict parsing never fixes anything
+    let strict = Header::from_raw_with_fixes(&bc1.to_raw(), &ParseOptions::default());
+    assert_eq!(strict.unwrap().1, vec![]);
+
+    // the fixes are available on the decoder
+    let mut file = Vec::new();
+    bc1.write(&mut file).unwrap();
+    file.resize(file.len() + 32, 0);
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+    let decoder = Decoder::new_with_options(file.as_slice(), &options).unwrap();
+    assert_eq!(decoder.fixes(), decoder.info().fixes());
+    assert_eq!(decoder.fixes().len(), 1);
+    assert_eq!(
+        decoder.fixes()[0].to_string(),
+        "Changed mipmap count from 4 to 1"
+    );
+}
+
+#[test]
+fn sanitize_and_repair_header() {
+    // sanitized
+    let header = Header::new_image(8, 8, Format::BC1_UNORM).with_mipmaps();
+    let file_len = (4 + header.byte_len() + 32) as u64;
+    let pixel_info = PixelInfo::from_header(&header).unwrap();
+    let sanitized = header.sanitized(file_len, pixel_info);
+    assert_eq!(sanitized.mipmap_count().get(), 1);
+    assert_eq!(sanitized.sanitized(file_len, pixel_info), sanitized);
+    assert_eq!(header.sanitized(file_len + 24, pixel_info), header);
+
+    // repair in place
+    let array = Header::new_texture_array(4, 4, 1, Format::BC1_UNORM).unwrap();
+    let mut raw = array.to_raw();
+    raw.dx10.as_mut().unwrap().array_size = 0;
+    raw.pitch_or_linear_size = 1234;
+    raw.reserved1[0] = 42;
+    let mut file = Header::MAGIC.to_vec();
+    raw.write(&mut file).unwrap();
+    let header_len = file.len();
+    file.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let mut cursor = std::io::Cursor::new(&mut file);
+    let fixes = repair_header(&mut cursor, &ParseOptions::default()).unwrap();
+    assert_eq!(cursor.position(), header_len as u64);
+    assert_eq!(
+        fixes,
+        [
+            HeaderFix::ArraySize {
+                declared: 0,
+                fixed: 1
+            },
+            HeaderFix::PitchOrLinearSize {
+                declared: 1234,
+                expected: 8
+            }
+        ]
+    );
+    assert_eq!(file.len(), header_len + 8);
+    assert_eq!(&file[header_len..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let repaired = RawHeader::read(&mut &file[4..]).unwrap();
+    assert_eq!(repaired.dx10.as_ref().unwrap().array_size, 1);
+    assert_eq!(repaired.pitch_or_linear_size, 8);
+    assert_eq!(repaired.reserved1[0], 42);
+    let info = DdsInfo::read(&mut file.as_slice()).unwrap();
+    assert_eq!(info.header(), &array);
+
+    // valid files are not changed
+    let before = file.clone();
+    let fixes = repair_header(&mut std::io::Cursor::new(&mut file), &Default::default());
+    assert_eq!(fixes.unwrap(), []);
+    assert_eq!(file, before);
+}
+
+#[test]
+fn permissive_cube_map_flags() {
+    let cube: Header = Header::new_cube_map(4, 4, Format::BC1_UNORM)
+        .to_dx9()
+        .unwrap()
+        .into();
+    let mut file = Vec::new();
+    cube.write(&mut file).unwrap();
+    file.resize(file.len() + 6 * 8, 0);
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+
+    for caps2 in [Caps2::empty(), Caps2::CUBE_MAP] {
+        let mut raw = cube.to_raw();
+        raw.caps2 = caps2;
+
+        // strict mode reads a single texture (or an empty cube map)
+        let strict = Header::from_raw(&raw, &ParseOptions::default()).unwrap();
+        assert_ne!(strict, cube);
+
+        let (header, fixes) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+        assert_eq!(header, cube);
+        assert_eq!(fixes, [HeaderFix::CubeMap { declared: caps2 }]);
+        let info = DdsInfo::new(header).unwrap();
+        assert_eq!(info.layout().texture_array().unwrap().len(), 6);
+    }
+
+    // the data of a single face is left alone
+    let mut raw = cube.to_raw();
+    raw.caps2 = Caps2::empty();
+    let options = ParseOptions::new_permissive(Some(file.len() as u64 - 5 * 8));
+    let (header, fixes) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+    assert!(!header.is_cube_map());
+    assert_eq!(fixes, []);
+
+    // partial cube maps are valid
+    let mut raw = cube.to_raw();
+    raw.caps2.remove(Caps2::CUBE_MAP_NEGATIVE_Z);
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+    let (header, _) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+    assert_eq!(header.cube_map_faces().unwrap().count(), 5);
+}
+
+#[test]
+fn verify_pitch() {
+    let header = Header::new_image(3, 5, Format::R8G8B8A8_UNORM);
+    let mut raw = header.to_raw();
+    raw.pitch_or_linear_size = 3 * 4 + 1;
+
+    // ignored by default
+    assert_eq!(
+        Header::from_raw(&raw, &ParseOptions::default()).unwrap(),
+        header
+    );
+
+    // rejected in strict mode
+    let mut options = ParseOptions::default();
+    options.verify_pitch = true;
+    assert!(matches!(
+        Header::from_raw(&raw, &options),
+        Err(HeaderError::IncorrectPitchOrLinearSize {
+            declared: 13,
+            expected: 12
+        })
+    ));
+    assert!(Header::from_raw(&header.to_raw(), &options).is_ok());
+
+    // without a flag, the value is meaningless
+    let mut no_flag = raw.clone();
+    no_flag.flags.remove(DdsFlags::PITCH);
+    assert!(Header::from_raw(&no_flag, &options).is_ok());
+
+    // reported in permissive mode
+    let (_, fixes) =
+        Header::from_raw_with_fixes(&raw, &ParseOptions::new_permissive(None)).unwrap();
+    assert_eq!(
+        fixes,
+        [HeaderFix::PitchOrLinearSize {
+            declared: 13,
+            expected: 12
+        }]
+    );
+
+    // but padded rows are correct
+    raw.pitch_or_linear_size = 3 * 4 + 4;
+    let mut file = Header::MAGIC.to_vec();
+    raw.write(&mut file).unwrap();
+    file.resize(file.len() + 16 * 5, 0);
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+    let info = DdsInfo::read_with_options(&mut file.as_slice(), &options).unwrap();
+    assert_eq!(info.layout().data_len(), 16 * 5);
+    assert_eq!(info.fixes(), []);
+
+    // and are kept by repair_header
+    let before = file.clone();
+    let fixes = repair_header(&mut std::io::Cursor::new(&mut file), &Default::default());
+    assert_eq!(fixes.unwrap(), []);
+    assert_eq!(file, before);
+}
+
+#[test]
+fn permissive_mipmap_flags() {
+    let header = Header::new_image(4, 4, Format::R8G8B8A8_UNORM).with_mipmaps();
+    assert_eq!(header.mipmap_count().get(), 3);
+    let data_len = (16 + 4 + 1) * 4;
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    file.resize(file.len() + data_len, 0);
+    let options = ParseOptions::new_permissive(Some(file.len() as u64));
+
+    // MIPMAP_COUNT flag with a mipmap count of 0
+    let mut raw = header.to_raw();
+    raw.mipmap_count = 0;
+    let strict = Header::from_raw(&raw, &ParseOptions::default()).unwrap();
+    assert_eq!(strict.mipmap_count().get(), 1);
+    let (fixed, fixes) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+    assert_eq!(fixed, header);
+    assert_eq!(
+        fixes,
+        [HeaderFix::MipmapCount {
+            declared: 1,
+            fixed: 3
+        }]
+    );
+
+    // a mipmap count without the flag or mipmap caps
+    let header = Header::new_image(8, 4, Format::R8G8B8A8_UNORM)
+        .with_mipmap_count(std::num::NonZeroU32::new(2).unwrap());
+    let mut raw = header.to_raw();
+    raw.flags.remove(DdsFlags::MIPMAP_COUNT);
+    raw.caps = Caps::TEXTURE;
+    let strict = Header::from_raw(&raw, &ParseOptions::default()).unwrap();
+    assert_eq!(strict.mipmap_count().get(), 1);
+    let file_len = (4 + header.byte_len() + (32 + 8) * 4) as u64;
+    let options = ParseOptions::new_permissive(Some(file_len));
+    let (fixed, fixes) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+    assert_eq!(fixed, header);
+    assert_eq!(
+        fixes,
+        [HeaderFix::MipmapCount {
+            declared: 1,
+            fixed: 2
+        }]
+    );
+}
+
+#[test]
+fn permissive_depth_flag() {
+    let header = Header::new_image(4, 4, Format::BC1_UNORM);
+    let file_len = (4 + header.byte_len() + 8) as u64;
+    let options = ParseOptions::new_permissive(Some(file_len));
+
+    for depth in [0, 1] {
+        // DEPTH flag on a 2D texture
+        let mut raw = header.to_raw();
+        raw.flags |= DdsFlags::DEPTH;
+        raw.depth = depth;
+        let strict = Header::from_raw(&raw, &ParseOptions::default()).unwrap();
+        assert_eq!(strict.depth(), Some(depth));
+        let (fixed, fixes) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+        assert_eq!(fixed, header);
+        assert_eq!(fixes, [HeaderFix::Depth(depth)]);
+    }
+
+    // volumes with a depth of 0 can't be read at all
+    let dx9: Header = header.to_dx9().unwrap().into();
+    let mut raw = dx9.to_raw();
+    raw.flags |= DdsFlags::DEPTH;
+    raw.caps2 |= Caps2::VOLUME;
+    raw.depth = 0;
+    let strict = Header::from_raw(&raw, &ParseOptions::default()).unwrap();
+    assert!(DataLayout::from_header(&strict).is_err());
+    let options_dx9 = ParseOptions::new_permissive(Some((4 + dx9.byte_len() + 8) as u64));
+    let (fixed, fixes) = Header::from_raw_with_fixes(&raw, &options_dx9).unwrap();
+    assert_eq!(fixed, dx9);
+    assert_eq!(fixes, [HeaderFix::Depth(0)]);
+
+    let mut raw = Header::new_volume(4, 4, 1, Format::BC1_UNORM).to_raw();
+    raw.depth = 0;
+    let (fixed, fixes) = Header::from_raw_with_fixes(&raw, &options).unwrap();
+    assert_eq!(fixed, header);
+    assert_eq!(fixes, [HeaderFix::Depth(0)]);
+
+    // but volumes with a depth of 1 are valid
+    let volume = Header::new_volume(4, 4, 1, Format::BC1_UNORM);
+    let file_len = (4 + volume.byte_len() + 8) as u64;
+    let options = ParseOptions::new_permissive(Some(file_len));
+    let (fixed, fixes) = Header::from_raw_with_fixes(&volume.to_raw(), &options).unwrap();
+    assert_eq!(fixed, volume);
+    assert_eq!(fixes, []);
+}
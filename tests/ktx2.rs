@@ -0,0 +1,386 @@
+use dds::{ktx2::*, *};
+
+mod util;
+
+fn to_words(dfd: &[u8]) -> Vec<u32> {
+    assert_eq!(dfd.len() % 4, 0);
+    dfd.chunks_exact(4)
+        .map(|w| u32::from_le_bytes(w.try_into().unwrap()))
+        .collect()
+}
+
+#[test]
+fn ktx2_format_roundtrip() {
+    let mut supported = 0;
+    for &format in util::ALL_FORMATS {
+        for srgb in [false, true] {
+            let ktx2 = match Ktx2Format::new(format, srgb) {
+                Some(ktx2) => ktx2,
+                None => continue,
+            };
+            supported += 1;
+            assert_eq!(ktx2.format(), format);
+            assert_eq!(ktx2.is_srgb(), srgb);
+
+            let from_vk = Ktx2Format::from_vk_format(ktx2.vk_format()).unwrap();
+            assert_eq!(from_vk.vk_format(), ktx2.vk_format(), "{:?}", ktx2);
+            if !ktx2.is_premultiplied_alpha() {
+                assert_eq!(from_vk, ktx2);
+            }
+
+            // the DFD describes blocks of the right size
+            let dfd = to_words(&ktx2.data_format_descriptor());
+            assert_eq!(dfd[0] as usize, dfd.len() * 4);
+            assert_eq!(dfd[2] >> 16, dfd[0] - 4);
+            let (w, h) = format.block_dimensions();
+            assert_eq!(dfd[4], (w as u32 - 1) | (h as u32 - 1) << 8, "{:?}", ktx2);
+            assert_eq!(dfd[5], format.bytes_per_block() as u32, "{:?}", ktx2);
+            let transfer = (dfd[3] >> 16) & 0xFF;
+            assert_eq!(transfer, if srgb { 2 } else { 1 });
+        }
+    }
+    assert!(supported > 60, "{}", supported);
+
+    assert!(Ktx2Format::new(Format::NV12, false).is_none());
+    assert!(Ktx2Format::new(Format::BC4_UNORM, true).is_none());
+    assert!(Ktx2Format::from_vk_format(VkFormat::UNDEFINED).is_none());
+}
+
+#[test]
+fn ktx2_data_format_descriptor() {
+    let rgba = Ktx2Format::new(Format::R8G8B8A8_UNORM, true).unwrap();
+    assert_eq!(rgba.vk_format(), VkFormat::R8G8B8A8_SRGB);
+    assert_eq!(rgba.type_size(), 1);
+    #[rustfmt::skip]
+    assert_eq!(
+        to_words(&rgba.data_format_descriptor()),
+        [
+            92, 0, 0x0058_0002, 0x0002_0101, 0, 4, 0,
+            0x0007_0000, 0, 0, 255,
+            0x0107_0008, 0, 0, 255,
+            0x0207_0010, 0, 0, 255,
+            0x1F07_0018, 0, 0, 255,
+        ]
+    );
+
+    let packed = Ktx2Format::new(Format::B5G6R5_UNORM, false).unwrap();
+    assert_eq!(packed.vk_format(), VkFormat::R5G6B5_UNORM_PACK16);
+    assert_eq!(packed.type_size(), 2);
+    #[rustfmt::skip]
+    assert_eq!(
+        to_words(&packed.data_format_descriptor()),
+        [
+            76, 0, 0x0048_0002, 0x0001_0101, 0, 2, 0,
+            0x0204_0000, 0, 0, 31,
+            0x0105_0005, 0, 0, 63,
+            0x0004_000B, 0, 0, 31,
+        ]
+    );
+
+    let bc1 = Ktx2Format::new(Format::BC1_UNORM, false).unwrap();
+    assert_eq!(bc1.vk_format(), VkFormat::BC1_RGBA_UNORM_BLOCK);
+    #[rustfmt::skip]
+    assert_eq!(
+        to_words(&bc1.data_format_descriptor()),
+        [
+            44, 0, 0x0028_0002, 0x0001_0180, 0x0303, 8, 0,
+            0x013F_0000, 0, 0, u32::MAX,
+        ]
+    );
+
+    // premultiplied alpha is stored as a flag
+    let bc3 = Ktx2Format::new(Format::BC3_UNORM_PREMULTIPLIED_ALPHA, false).unwrap();
+    assert!(bc3.is_premultiplied_alpha());
+    assert_eq!(bc3.vk_format(), VkFormat::BC3_UNORM_BLOCK);
+    #[rustfmt::skip]
+    assert_eq!(
+        to_words(&bc3.data_format_descriptor()),
+        [
+            60, 0, 0x0038_0002, 0x0101_0182, 0x0303, 16, 0,
+            0x0F3F_0000, 0, 0, u32::MAX,
+            0x003F_0040, 0, 0, u32::MAX,
+        ]
+    );
+}
+
+/// Creates a KTX2 file without supercompression, key/value data, or
+/// alignment padding. `levels` contains the data of each mipmap level.
+fn create_ktx2(
+    format: Ktx2Format,
+    (width, height): (u32, u32),
+    layer_count: u32,
+    face_count: u32,
+    levels: &[Vec<u8>],
+) -> Vec<u8> {
+    let dfd = format.data_format_descriptor();
+    let dfd_offset = 80 + 24 * levels.len() as u32;
+    let mut data_offset = dfd_offset as u64 + dfd.len() as u64;
+
+    let mut file = Ktx2Header::IDENTIFIER.to_vec();
+    for value in [
+        format.vk_format().0,
+        format.type_size(),
+        width,
+        height,
+        0,
+        layer_count,
+        face_count,
+        levels.len() as u32,
+        0,
+        dfd_offset,
+        dfd.len() as u32,
+        0,
+        0,
+    ] {
+        file.extend_from_slice(&value.to_le_bytes());
+    }
+    file.extend_from_slice(&[0; 16]);
+    for level in levels {
+        let len = level.len() as u64;
+        for value in [data_offset, len, len] {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        data_offset += len;
+    }
+    file.extend_from_slice(&dfd);
+    for level in levels {
+        file.extend_from_slice(level);
+    }
+    file
+}
+
+#[test]
+fn ktx2_decode_rgba8_array() {
+    let format = Ktx2Format::new(Format::R8G8B8A8_UNORM, false).unwrap();
+    // 2 layers, 2 levels of a 2x2 texture; every pixel is unique
+    let level0: Vec<u8> = (0..2 * 4 * 4).collect();
+    let level1: Vec<u8> = (100..100 + 2 * 4).collect();
+    let file = create_ktx2(format, (2, 2), 2, 1, &[level0.clone(), level1.clone()]);
+
+    let mut decoder = Ktx2Decoder::new(std::io::Cursor::new(file)).unwrap();
+    assert_eq!(decoder.format(), format);
+    assert_eq!(decoder.level_count(), 2);
+    assert_eq!(decoder.layer_count(), 2);
+    assert_eq!(decoder.face_count(), 1);
+    assert_eq!(decoder.level_size(0), Some(Size::new(2, 2)));
+    assert_eq!(decoder.level_size(1), Some(Size::new(1, 1)));
+    assert_eq!(decoder.level_size(2), None);
+
+    let mut buffer = [0_u8; 16];
+    let image = ImageViewMut::new(&mut buffer[..], Size::new(2, 2), ColorFormat::RGBA_U8).unwrap();
+    decoder.decode_surface(0, 1, 0, 0, image).unwrap();
+    assert_eq!(&buffer[..], &level0[16..]);
+
+    // surfaces can be decoded in any order
+    let mut buffer = [0_u8; 4];
+    let image = ImageViewMut::new(&mut buffer[..], Size::new(1, 1), ColorFormat::RGBA_U8).unwrap();
+    decoder.decode_surface(1, 0, 0, 0, image).unwrap();
+    assert_eq!(&buffer[..], &level1[..4]);
+
+    let mut buffer = [0_u8; 16];
+    let image = ImageViewMut::new(&mut buffer[..], Size::new(2, 2), ColorFormat::RGBA_U8).unwrap();
+    decoder.decode_surface(0, 0, 0, 0, image).unwrap();
+    assert_eq!(&buffer[..], &level0[..16]);
+
+    // invalid surfaces
+    let mut buffer = [0_u8; 16];
+    let image = ImageViewMut::new(&mut buffer[..], Size::new(2, 2), ColorFormat::RGBA_U8).unwrap();
+    let result = decoder.decode_surface(0, 2, 0, 0, image);
+    assert!(matches!(result, Err(DecodeError::SurfaceNotFound)));
+    let image = ImageViewMut::new(&mut buffer[..], Size::new(2, 2), ColorFormat::RGBA_U8).unwrap();
+    let result = decoder.decode_surface(0, 0, 1, 0, image);
+    assert!(matches!(result, Err(DecodeError::SurfaceNotFound)));
+    let image = ImageViewMut::new(&mut buffer[..4], Size::new(1, 1), ColorFormat::RGBA_U8).unwrap();
+    let result = decoder.decode_surface(0, 0, 0, 0, image);
+    assert!(matches!(result, Err(DecodeError::UnexpectedSurfaceSize)));
+}
+
+#[test]
+fn ktx2_decode_bc1_matches_dds() {
+    use rand::Rng;
+
+    let mut rng = util::create_rng();
+    let mut blocks = vec![0_u8; 4 * 8];
+    rng.fill(&mut blocks[..]);
+
+    let size = Size::new(8, 8);
+    for premultiplied in [false, true] {
+        let dds_format = if premultiplied {
+            Format::BC3_UNORM_PREMULTIPLIED_ALPHA
+        } else {
+            Format::BC1_UNORM
+        };
+        let mut blocks = blocks.clone();
+        if premultiplied {
+            blocks.resize(4 * 16, 0);
+            rng.fill(&mut blocks[..]);
+        }
+
+        let format = Ktx2Format::new(dds_format, true).unwrap();
+        let file = create_ktx2(format, (8, 8), 0, 1, &[blocks.clone()]);
+        let mut decoder = Ktx2Decoder::new(std::io::Cursor::new(file)).unwrap();
+        assert_eq!(decoder.format(), format);
+
+        let mut expected = vec![0_u8; 8 * 8 * 4];
+        let image = ImageViewMut::new(&mut expected[..], size, ColorFormat::RGBA_U8).unwrap();
+        dds_format
+            .decode_from_slice(&blocks, image, &DecodeOptions::default())
+            .unwrap();
+
+        let mut actual = vec![0_u8; 8 * 8 * 4];
+        let image = ImageViewMut::new(&mut actual[..], size, ColorFormat::RGBA_U8).unwrap();
+        decoder.decode_surface(0, 0, 0, 0, image).unwrap();
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn ktx2_decode_errors() {
+    let format = Ktx2Format::new(Format::R8G8B8A8_UNORM, false).unwrap();
+    let valid = create_ktx2(format, (2, 2), 0, 1, &[vec![0; 16]]);
+    assert!(Ktx2Decoder::new(std::io::Cursor::new(valid.clone())).is_ok());
+
+    let new = |file: Vec<u8>| match Ktx2Decoder::new(std::io::Cursor::new(file)) {
+        Err(DecodeError::Ktx2(error)) => error,
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("expected an error"),
+    };
+    let with_u32 = |offset: usize, value: u32| {
+        let mut file = valid.clone();
+        file[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        file
+    };
+
+    let mut file = valid.clone();
+    file[0] = b'D';
+    assert!(matches!(new(file), Ktx2Error::InvalidIdentifier(_)));
+    // vkFormat
+    assert!(matches!(
+        new(with_u32(12, 0)),
+        Ktx2Error::UnsupportedVkFormat(VkFormat::UNDEFINED)
+    ));
+    // faceCount
+    assert!(matches!(
+        new(with_u32(36, 3)),
+        Ktx2Error::InvalidFaceCount(3)
+    ));
+    // supercompressionScheme
+    assert!(matches!(
+        new(with_u32(44, 2)),
+        Ktx2Error::UnsupportedSupercompression(2)
+    ));
+    // level 0 byteLength
+    assert!(matches!(
+        new(with_u32(88, 15)),
+        Ktx2Error::InvalidLevelLength {
+            level: 0,
+            length: 15
+        }
+    ));
+    assert!(matches!(new(valid[..70].to_vec()), Ktx2Error::Io(_)));
+}
+
+#[test]
+fn dds_to_ktx2_roundtrip() {
+    use dds::header::Header;
+    use rand::Rng;
+
+    let mut rng = util::create_rng();
+    let headers = [
+        Header::new_image(5, 3, Format::R8G8B8_UNORM).with_mipmaps(),
+        Header::new_image(12, 8, Format::BC1_UNORM)
+            .to_srgb()
+            .unwrap()
+            .with_mipmaps(),
+        Header::new_cube_map(4, 4, Format::BC3_UNORM_PREMULTIPLIED_ALPHA).with_mipmaps(),
+        Header::new_cube_map_array(2, 2, 3, Format::R8G8B8A8_UNORM)
+            .unwrap()
+            .with_mipmaps(),
+        Header::new_texture_array(4, 4, 3, Format::R32G32B32_FLOAT)
+            .unwrap()
+            .with_mipmaps(),
+        Header::new_volume(4, 2, 3, Format::R8G8B8A8_UNORM).with_mipmaps(),
+    ];
+
+    for header in headers {
+        let format = Format::from_header(&header).unwrap();
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mut data = vec![0_u8; layout.data_len() as usize];
+        rng.fill(&mut data[..]);
+
+        let mut ktx2 = Vec::new();
+        dds_to_ktx2(&header, &mut std::io::Cursor::new(&data), &mut ktx2).unwrap();
+
+        let mut decoder = Ktx2Decoder::new(std::io::Cursor::new(ktx2)).unwrap();
+        assert_eq!(decoder.format().format(), format);
+        assert_eq!(decoder.format().is_srgb(), header.is_srgb());
+        assert_eq!(decoder.level_count(), layout.mipmaps());
+        assert_eq!(decoder.level_size(0), Some(layout.main_size()));
+        let faces = if header.is_cube_map() { 6 } else { 1 };
+        assert_eq!(decoder.face_count(), faces);
+        assert_eq!(decoder.layer_count(), layout.array_slices() / faces);
+        for level in decoder.header().levels.iter() {
+            let alignment = lcm(format.bytes_per_block() as u64, 4);
+            assert_eq!(level.byte_offset % alignment, 0, "{:?}", header);
+        }
+
+        for (surface, kind) in layout.iter_surfaces() {
+            let size = surface.size();
+            let mut expected = vec![0_u8; size.pixels() as usize * 4];
+            let image = ImageViewMut::new(&mut expected[..], size, ColorFormat::RGBA_U8).unwrap();
+            let start = surface.data_offset() as usize;
+            format
+                .decode_from_slice(&data[start..], image, &DecodeOptions::default())
+                .unwrap();
+
+            let mut actual = vec![0_u8; size.pixels() as usize * 4];
+            let image = ImageViewMut::new(&mut actual[..], size, ColorFormat::RGBA_U8).unwrap();
+            let face = kind.face.map_or(0, |face| face as u32);
+            decoder
+                .decode_surface(kind.mipmap_level, kind.layer, face, kind.depth_slice, image)
+                .unwrap();
+            assert_eq!(actual, expected, "{:?} {:?}", header, kind);
+        }
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    let gcd = (1..=a.min(b))
+        .rev()
+        .find(|d| a % d == 0 && b % d == 0)
+        .unwrap();
+    a * b / gcd
+}
+
+#[test]
+fn dds_to_ktx2_errors() {
+    use dds::header::Header;
+
+    let convert = |header: Header, data_len: usize| {
+        let data = vec![0_u8; data_len];
+        let mut ktx2 = Vec::new();
+        dds_to_ktx2(&header, &mut std::io::Cursor::new(&data), &mut ktx2)
+    };
+
+    let header = Header::new_image(4, 4, Format::NV12);
+    assert!(matches!(
+        convert(header, 1024),
+        Err(DecodeError::Ktx2(Ktx2Error::UnsupportedFormat(
+            Format::NV12
+        )))
+    ));
+
+    let header = Header::new_cube_map(4, 4, Format::R8G8B8A8_UNORM);
+    let dx9 = header
+        .to_dx9()
+        .unwrap()
+        .with_cube_map_faces(CubeMapFaces::POSITIVE_X);
+    assert!(matches!(
+        convert(Header::Dx9(dx9), 1024),
+        Err(DecodeError::Ktx2(Ktx2Error::PartialCubeMap))
+    ));
+
+    // data section is too short
+    let header = Header::new_image(4, 4, Format::R8G8B8A8_UNORM);
+    assert!(matches!(convert(header, 63), Err(DecodeError::Io(_))));
+}
@@ -2,7 +2,7 @@ use dds::{header::*, *};
 
 use std::{
     fs::File,
-    io::Seek,
+    io::{Cursor, Seek},
     num::{NonZero, NonZeroU32},
     path::PathBuf,
 };
@@ -422,3 +422,769 @@ fn weird_and_invalid_headers() {
     )
     .unwrap();
 }
+
+#[test]
+fn info_report() {
+    for dds_path in util::example_dds_files() {
+        let mut file = File::open(&dds_path).expect("Failed to open file");
+        let file_len = file.metadata().unwrap().len();
+
+        // the file length is determined automatically
+        let options = ParseOptions::new_permissive(None);
+        let report = match DdsInfoReport::from_reader(&mut file, &options) {
+            Ok(report) => report,
+            Err(e) => panic!("Failed to read {}\nFile: {:?}", e, dds_path),
+        };
+        assert_eq!(file.stream_position().unwrap(), report.header_len);
+
+        file.rewind().unwrap();
+        let options = ParseOptions::new_permissive(Some(file_len));
+        let info = DdsInfo::read_with_options(&mut file, &options).unwrap();
+        assert_eq!(report.format, info.format());
+        assert_eq!(report.data_len, info.layout().data_len());
+
+        // elements and mipmaps cover the data section without gaps
+        let mut offset = 0;
+        assert_eq!(report.elements().count(), report.element_count);
+        for element in report.elements() {
+            assert_eq!(element.offset, offset, "File: {:?}", dds_path);
+            for mipmap in &element.mipmaps {
+                assert_eq!(mipmap.offset, offset, "File: {:?}", dds_path);
+                offset += mipmap.len;
+            }
+            assert_eq!(element.offset + element.len, offset, "File: {:?}", dds_path);
+        }
+        assert_eq!(offset, report.data_len, "File: {:?}", dds_path);
+
+        // the header was fixed iff strict parsing fails or differs
+        file.rewind().unwrap();
+        let strict = DdsInfo::read_with_options(&mut file, &ParseOptions::default());
+        let strict_matches = strict.map_or(false, |s| s.header() == info.header());
+        assert_eq!(report.header_fixed, !strict_matches, "File: {:?}", dds_path);
+    }
+
+    // truncated and over-long files are reported
+    let header = Header::new_image(4, 4, Format::R8G8B8A8_UNORM);
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    let header_len = file.len() as u64;
+    file.resize(file.len() + 32, 0);
+
+    let report =
+        DdsInfoReport::from_reader(&mut std::io::Cursor::new(&file), &Default::default()).unwrap();
+    assert_eq!(report.header_len, header_len);
+    assert_eq!(report.data_len, 64);
+    assert!(!report.header_fixed);
+    assert!(report.fixes.is_empty());
+    assert_eq!(
+        report.warnings,
+        [ReportWarning::TruncatedData {
+            expected: 64,
+            available: 32
+        }]
+    );
+    assert!(report.warnings[0].to_string().contains("truncated"));
+
+    file.resize(header_len as usize + 64, 0);
+    let report =
+        DdsInfoReport::from_reader(&mut std::io::Cursor::new(&file), &Default::default()).unwrap();
+    assert!(report.warnings.is_empty());
+
+    // texture arrays report each element
+    let header = Header::new_texture_array(8, 8, 3, Format::R8G8B8A8_UNORM)
+        .unwrap()
+        .with_mipmaps();
+    let report = DdsInfoReport::new(&DdsInfo::new(header.clone()).unwrap());
+    assert_eq!(report.element_count, 3);
+    assert_eq!(report.mipmaps.len(), 4);
+    let elements: Vec<_> = report.elements().collect();
+    assert_eq!(elements.len(), 3);
+    assert_eq!(elements[2].offset, 2 * report.element_len);
+    assert_eq!(elements[2].mipmaps[1].offset, 2 * report.element_len + 256);
+
+    // offsets are relative to the data section, which follows the magic
+    // bytes (4), the header (124), and the DX10 header extension (20)
+    let mut file = Vec::new();
+    header.write(&mut file).unwrap();
+    assert_eq!(report.header_len, 148);
+    assert_eq!(report.element_len, (64 + 16 + 4 + 1) * 4);
+    for (index, element) in report.elements().enumerate() {
+        for mipmap in &element.mipmaps {
+            let value = (index * 4 + mipmap.level as usize) as u8;
+            file.resize(file.len() + mipmap.len as usize, value);
+        }
+    }
+    let mipmap = &report.elements().nth(1).unwrap().mipmaps[2];
+    assert_eq!(mipmap.offset, 340 + 256 + 64);
+    let file_offset = (report.header_len + mipmap.offset) as usize;
+    assert_eq!(
+        file[file_offset..file_offset + mipmap.len as usize],
+        [4 + 2; 16]
+    );
+    assert_eq!(file[file_offset - 1], 4 + 1);
+}
+
+#[test]
+fn redundancy_report() {
+    let format = Format::R8G8B8A8_UNORM;
+    let header: Header = match Header::new_image(16, 16, format).with_mipmaps() {
+        Header::Dx10(dx10) => dx10.with_array_size(3).into(),
+        Header::Dx9(_) => unreachable!(),
+    };
+    assert_eq!(header.mipmap_count().get(), 5);
+
+    let mut rng = util::create_rng();
+    let mut level = |level: u8, solid: bool| {
+        let pixels = (16_usize >> level).pow(2);
+        if solid {
+            [255_u8, 0, 0, 255].repeat(pixels)
+        } else {
+            let mut data = vec![0_u8; pixels * 4];
+            rand::RngCore::fill_bytes(&mut rng, &mut data);
+            data
+        }
+    };
+    // texture 0 is solid from level 2, texture 1 is a copy, and texture 2 is
+    // solid from level 1
+    let texture_0: Vec<Vec<u8>> = (0..5).map(|l| level(l, l >= 2)).collect();
+    let texture_2: Vec<Vec<u8>> = (0..5).map(|l| level(l, l >= 1)).collect();
+    let textures = [texture_0.clone(), texture_0, texture_2];
+
+    for order in [MipOrder::LargestFirst, MipOrder::SmallestFirst] {
+        let mut file = Vec::new();
+        let mut raw = header.to_raw();
+        order.apply_to_raw_header(&mut raw);
+        file.extend_from_slice(&Header::MAGIC);
+        raw.write(&mut file).unwrap();
+        let header_len = file.len() as u64;
+        for texture in &textures {
+            let mut levels: Vec<&Vec<u8>> = texture.iter().collect();
+            if order == MipOrder::SmallestFirst {
+                levels.reverse();
+            }
+            levels.iter().for_each(|l| file.extend_from_slice(l));
+        }
+
+        let mut reader = Cursor::new(file.as_slice());
+        let info = DdsInfo::read(&mut reader).unwrap();
+        assert_eq!(info.mip_order(), order);
+        let report = RedundancyReport::from_reader(&mut reader, &info).unwrap();
+        assert_eq!(reader.position(), file.len() as u64);
+
+        let solid = |e: &ElementRedundancy| (e.solid_from_level, e.solid_color);
+        let red = Some([1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(solid(&report.elements[0]), (Some(2), red));
+        assert_eq!(solid(&report.elements[1]), (Some(2), red));
+        assert_eq!(solid(&report.elements[2]), (Some(1), red));
+        let duplicates: Vec<_> = report.elements.iter().map(|e| e.duplicate_of).collect();
+        assert_eq!(duplicates, [None, Some(0), None]);
+        assert_eq!(report.reduced_mipmap_count, 3);
+        assert_eq!(report.mipmap_savings, 3 * (4 + 1) * 4);
+        assert_eq!(report.duplicate_savings, report.elements[1].len);
+
+        // remove the redundant mipmaps
+        reader.set_position(header_len);
+        let mut truncated = Vec::new();
+        let count = NonZeroU32::new(report.reduced_mipmap_count).unwrap();
+        truncate_mipmaps(&mut reader, &mut truncated, &info, count).unwrap();
+        assert_eq!(reader.position(), file.len() as u64);
+        assert_eq!(
+            truncated.len() as u64,
+            file.len() as u64 - report.mipmap_savings
+        );
+
+        let mut decoder = Decoder::new(truncated.as_slice()).unwrap();
+        assert_eq!(decoder.info().header().mipmap_count(), count);
+        assert_eq!(decoder.info().mip_order(), order);
+        let mut surfaces = Vec::new();
+        while let Some(surface) = decoder.surface_info() {
+            let (level, size) = (surface.mipmap_level(), surface.size());
+            let mut data = vec![0_u8; size.pixels() as usize * 4];
+            let view = ImageViewMut::new(data.as_mut_slice(), size, ColorFormat::RGBA_U8).unwrap();
+            decoder.read_surface(view).unwrap();
+            surfaces.push((level, data));
+        }
+        let mut expected = Vec::new();
+        for texture in &textures {
+            let mut levels: Vec<_> = (0..3).map(|l| (l, texture[l as usize].clone())).collect();
+            if order == MipOrder::SmallestFirst {
+                levels.reverse();
+            }
+            expected.extend(levels);
+        }
+        assert!(surfaces == expected);
+    }
+}
+
+#[test]
+fn huge_texture_array() {
+    let header: Header = Dx10Header {
+        height: 4,
+        width: 4,
+        depth: None,
+        mipmap_count: NonZero::new(3).unwrap(),
+        dxgi_format: DxgiFormat::BC1_UNORM,
+        resource_dimension: ResourceDimension::Texture2D,
+        misc_flag: MiscFlags::empty(),
+        array_size: u32::MAX / 64,
+        alpha_mode: AlphaMode::Unknown,
+    }
+    .into();
+
+    let layout = DataLayout::from_header(&header).unwrap();
+    let array = layout.texture_array().unwrap();
+    assert_eq!(array.len(), u32::MAX as usize / 64);
+
+    // the last texture is computed directly without iterating the array
+    let texture_len = array.get(0).unwrap().data_len();
+    assert_eq!(texture_len, 8 * 3);
+    let last = array.get(array.len() - 1).unwrap();
+    assert_eq!(last.data_offset(), texture_len * (array.len() as u64 - 1));
+    assert_eq!(last.data_end(), layout.data_len());
+    assert!(array.get(array.len()).is_none());
+
+    let last_mip = last.get(2).unwrap();
+    assert_eq!(last_mip.data_end(), layout.data_len());
+    assert_eq!(last_mip.size(), Size::new(1, 1));
+}
+
+#[test]
+fn cube_atlas() {
+    let face_size = 8_u32;
+
+    for layout in [
+        CubeAtlasLayout::HorizontalCross,
+        CubeAtlasLayout::VerticalCross,
+        CubeAtlasLayout::Grid3x2,
+    ] {
+        let (columns, rows) = layout.grid_size();
+        let size = Size::new(face_size * columns, face_size * rows);
+        let atlas = CubeAtlas::new(layout, size).unwrap();
+        assert_eq!(atlas.size(), size);
+        assert_eq!(CubeAtlasLayout::guess_from_size(size), Some(layout));
+
+        // every pixel stores its face, x, and y
+        let mut data = vec![0_u8; size.pixels() as usize * 4];
+        for (i, face) in CubeFace::ALL.into_iter().enumerate() {
+            let rect = atlas.face_rect(face);
+            for y in 0..face_size {
+                for x in 0..face_size {
+                    let offset = ((rect.y + y) * size.width + rect.x + x) as usize * 4;
+                    data[offset..offset + 4].copy_from_slice(&[i as u8, x as u8, y as u8, 255]);
+                }
+            }
+        }
+
+        let write_file = |hint: Option<CubeAtlasLayout>| {
+            let header = Header::new_image(size.width, size.height, Format::R8G8B8A8_UNORM);
+            let mut raw = header.to_raw();
+            if let Some(hint) = hint {
+                hint.apply_to_raw_header(&mut raw);
+            }
+            let mut file = Header::MAGIC.to_vec();
+            raw.write(&mut file).unwrap();
+            file.extend_from_slice(&data);
+            file
+        };
+
+        // the hint is detected
+        let file = write_file(Some(layout));
+        let mut reader = Cursor::new(file.as_slice());
+        let info = DdsInfo::read(&mut reader).unwrap();
+        assert_eq!(info.cube_atlas_hint(), Some(layout));
+        assert_eq!(info.cube_atlas(false), Some(atlas));
+
+        let start = reader.position();
+        let row_pitch = face_size as usize * 4;
+        for (i, face) in CubeFace::ALL.into_iter().enumerate() {
+            reader.set_position(start);
+            let mut output = vec![0_u8; row_pitch * face_size as usize];
+            atlas
+                .decode_face(
+                    &mut reader,
+                    &mut output,
+                    row_pitch,
+                    ColorFormat::RGBA_U8,
+                    face,
+                    info.format(),
+                    &DecodeOptions::default(),
+                )
+                .unwrap();
+
+            for (p, pixel) in output.chunks_exact(4).enumerate() {
+                let (mut x, mut y) = (p as u32 % face_size, p as u32 / face_size);
+                if layout.is_rotated(face) {
+                    x = face_size - 1 - x;
+                    y = face_size - 1 - y;
+                }
+                assert_eq!(pixel, [i as u8, x as u8, y as u8, 255], "{:?}", face);
+            }
+        }
+
+        // without a hint, the layout is only guessed if requested
+        let file = write_file(None);
+        let info = DdsInfo::read(&mut file.as_slice()).unwrap();
+        assert_eq!(info.cube_atlas_hint(), None);
+        assert_eq!(info.cube_atlas(false), None);
+        assert_eq!(info.cube_atlas(true), Some(atlas));
+    }
+
+    // sizes that don't fit any layout
+    assert_eq!(CubeAtlasLayout::guess_from_size(Size::new(16, 16)), None);
+    assert_eq!(
+        CubeAtlasLayout::guess_from_size(Size::new(4, 3)),
+        Some(CubeAtlasLayout::HorizontalCross)
+    );
+    assert_eq!(
+        CubeAtlas::new(CubeAtlasLayout::Grid3x2, Size::new(30, 21)),
+        None
+    );
+    let cube = DdsInfo::new(Header::new_cube_map(8, 8, Format::R8G8B8A8_UNORM)).unwrap();
+    assert_eq!(cube.cube_atlas(true), None);
+}
+
+#[test]
+fn d3d12_footprints() {
+    let alignment = FootprintAlignment::D3D12;
+
+    // block-compressed cube map
+    let header = Header::new_cube_map(64, 64, Format::BC1_UNORM).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let footprints = layout.footprints(alignment).unwrap();
+    assert_eq!(footprints.subresources.len(), 6 * 7);
+
+    let mut end = 0;
+    for (index, footprint) in footprints.subresources.iter().enumerate() {
+        assert_eq!(footprint.subresource, index as u32);
+        assert_eq!(footprint.offset % 512, 0);
+        assert!(footprint.offset >= end);
+        assert_eq!(footprint.row_pitch % 256, 0);
+        assert_eq!(footprint.width % 4, 0);
+        assert_eq!(footprint.height, footprint.num_rows * 4);
+        end = footprint.offset + footprint.buffer_len();
+
+        let (face, level) = (index / 7, index % 7);
+        let surface = layout
+            .texture_array()
+            .unwrap()
+            .get(face)
+            .unwrap()
+            .get(level as u8)
+            .unwrap();
+        assert_eq!(footprint.data_offset, surface.data_offset());
+        assert_eq!(
+            footprint.row_size * footprint.num_rows as u64,
+            surface.data_len()
+        );
+    }
+    assert_eq!(footprints.total_bytes, end);
+
+    let first = footprints.subresources[0];
+    assert_eq!(
+        (first.row_pitch, first.num_rows, first.row_size),
+        (256, 16, 128)
+    );
+    assert_eq!(first.buffer_len(), 256 * 15 + 128);
+    assert_eq!(footprints.subresources[1].offset, 4096);
+    // the 2x2 and 1x1 mipmaps are a single block
+    let last = footprints.subresources[6];
+    assert_eq!((last.width, last.height, last.row_size), (4, 4, 8));
+
+    // volume with padded rows
+    let header = Header::new_volume(5, 3, 4, Format::R8G8B8_UNORM);
+    let layout = DataLayout::from_header_with_row_alignment(
+        &header,
+        PixelInfo::from(Format::R8G8B8_UNORM),
+        NonZeroU32::new(4).unwrap(),
+    )
+    .unwrap();
+    let footprints = layout.footprints(alignment).unwrap();
+    let volume = footprints.subresources[0];
+    assert_eq!((volume.width, volume.height, volume.depth), (5, 3, 4));
+    assert_eq!((volume.row_size, volume.data_row_pitch), (15, 16));
+    assert_eq!(footprints.total_bytes, 256 * 11 + 15);
+
+    // bi-planar formats have multiple planes
+    let header = Header::new_image(4, 4, Format::NV12);
+    let layout = DataLayout::from_header(&header).unwrap();
+    assert!(layout.footprints(alignment).is_none());
+}
+
+#[test]
+fn subresource_indexes() {
+    let headers = [
+        Header::new_image(16, 8, Format::R8G8B8A8_UNORM).with_mipmaps(),
+        Header::new_texture_array(16, 8, 3, Format::BC1_UNORM)
+            .unwrap()
+            .with_mipmaps(),
+        Header::new_cube_map(8, 8, Format::BC7_UNORM).with_mipmaps(),
+        Header::new_volume(8, 8, 4, Format::R8_UNORM).with_mipmaps(),
+    ];
+    for header in headers {
+        let layout = DataLayout::from_header(&header).unwrap();
+        let mipmaps = layout.mipmaps();
+        let slices = layout.array_slices();
+        assert_eq!(mipmaps as u32, header.mipmap_count().get());
+
+        let mut expected_index = 0;
+        for array_slice in 0..slices {
+            for level in 0..mipmaps {
+                let index = layout.subresource_index(level, array_slice).unwrap();
+                assert_eq!(index, expected_index);
+                expected_index += 1;
+                assert_eq!(
+                    layout.subresource_location(index),
+                    Some((level, array_slice))
+                );
+
+                let surface = layout.get_subresource(index);
+                match &layout {
+                    DataLayout::Texture(texture) => assert_eq!(surface, texture.get(level)),
+                    DataLayout::TextureArray(array) => {
+                        assert_eq!(surface, array.get(array_slice as usize).unwrap().get(level))
+                    }
+                    DataLayout::Volume(_) => assert_eq!(surface, None),
+                }
+            }
+        }
+
+        assert_eq!(layout.subresource_index(mipmaps, 0), None);
+        assert_eq!(layout.subresource_index(0, slices), None);
+        assert_eq!(layout.subresource_location(expected_index), None);
+        assert_eq!(layout.get_subresource(expected_index), None);
+    }
+}
+
+#[test]
+fn offset_of() {
+    // texture array
+    let header = Header::new_texture_array(8, 8, 3, Format::BC1_UNORM)
+        .unwrap()
+        .with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let array = layout.texture_array().unwrap();
+    for (layer, texture) in array.iter().enumerate() {
+        for (level, surface) in texture.iter_mips().enumerate() {
+            assert_eq!(surface.offset(), surface.data_offset());
+            assert_eq!(
+                layout.offset_of(layer, None, level as u8),
+                Some(surface.offset())
+            );
+        }
+    }
+    assert_eq!(layout.offset_of(3, None, 0), None);
+    assert_eq!(layout.offset_of(0, None, 4), None);
+    assert_eq!(layout.offset_of(0, Some(CubeFace::PositiveX), 0), None);
+
+    // cube map array
+    let header = Header::new_cube_map_array(4, 4, 2, Format::R8G8B8A8_UNORM).unwrap();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let array = layout.texture_array().unwrap();
+    for cube in 0..2 {
+        for (i, face) in CubeFace::ALL.into_iter().enumerate() {
+            let expected = array.get(cube * 6 + i).unwrap().main().offset();
+            assert_eq!(layout.offset_of(cube, Some(face), 0), Some(expected));
+        }
+    }
+    assert_eq!(layout.offset_of(2, Some(CubeFace::PositiveX), 0), None);
+    assert_eq!(layout.offset_of(0, None, 0), None);
+
+    // partial cube map
+    let header: Header = Dx9Header {
+        caps2: Caps2::CUBE_MAP | Caps2::CUBE_MAP_POSITIVE_Y | Caps2::CUBE_MAP_NEGATIVE_Z,
+        ..Dx9Header::new_image(4, 4, FourCC::DXT1.into())
+    }
+    .into();
+    let layout = DataLayout::from_header(&header).unwrap();
+    assert_eq!(layout.offset_of(0, Some(CubeFace::PositiveY), 0), Some(0));
+    assert_eq!(layout.offset_of(0, Some(CubeFace::NegativeZ), 0), Some(8));
+    assert_eq!(layout.offset_of(0, Some(CubeFace::PositiveX), 0), None);
+
+    // volume
+    let header = Header::new_volume(4, 4, 4, Format::R8_UNORM).with_mipmaps();
+    let layout = DataLayout::from_header(&header).unwrap();
+    let volume = layout.volume().unwrap();
+    for (level, mip) in volume.iter_mips().enumerate() {
+        assert_eq!(layout.offset_of(0, None, level as u8), Some(mip.offset()));
+    }
+    assert_eq!(layout.offset_of(1, None, 0), None);
+}
+
+#[test]
+fn bi_planar_planes() {
+    let cases = [
+        // (format, luma pitch, chroma size, chroma pitch) for a 5x3 surface
+        (Format::NV12, 5, Size::new(3, 2), 6),
+        (Format::P010, 10, Size::new(3, 2), 12),
+        (Format::P016, 10, Size::new(3, 2), 12),
+    ];
+    for (format, luma_pitch, chroma_size, chroma_pitch) in cases {
+        let header = Header::new_image(5, 3, format).with_mipmaps();
+        let layout = DataLayout::from_header(&header).unwrap();
+        for surface in layout.texture().unwrap().iter_mips() {
+            let [luma, chroma] = surface.planes(layout.pixel_info()).unwrap();
+            assert_eq!(luma.size(), surface.size());
+            assert_eq!(luma.offset(), surface.offset());
+            assert_eq!(chroma.offset(), luma.data_end());
+            assert_eq!(chroma.data_end(), surface.data_end());
+        }
+
+        let [luma, chroma] = layout
+            .texture()
+            .unwrap()
+            .main()
+            .planes(layout.pixel_info())
+            .unwrap();
+        assert_eq!(luma.row_pitch(), luma_pitch, "{:?}", format);
+        assert_eq!(chroma.size(), chroma_size, "{:?}", format);
+        assert_eq!(chroma.row_pitch(), chroma_pitch, "{:?}", format);
+    }
+
+    let header = Header::new_image(4, 4, Format::YUY2);
+    let layout = DataLayout::from_header(&header).unwrap();
+    let surface = layout.texture().unwrap().main();
+    assert!(surface.planes(layout.pixel_info()).is_none());
+}
+
+#[test]
+fn iter_surfaces() {
+    fn check(header: Header) -> Vec<SurfaceKind> {
+        let layout = DataLayout::from_header(&header).unwrap();
+        let surfaces: Vec<(SurfaceDescriptor, SurfaceKind)> = layout.iter_surfaces().collect();
+
+        // surfaces are contiguous and cover the entire data section
+        let mut offset = 0;
+        for (surface, kind) in &surfaces {
+            assert_eq!(surface.offset(), offset, "{:?}", kind);
+            offset = surface.data_end();
+        }
+        assert_eq!(offset, layout.data_len());
+
+        surfaces.into_iter().map(|(_, kind)| kind).collect()
+    }
+
+    let kinds = check(Header::new_image(4, 4, Format::R8G8B8A8_UNORM).with_mipmaps());
+    let levels: Vec<u8> = kinds.iter().map(|k| k.mipmap_level).collect();
+    assert_eq!(levels, [0, 1, 2]);
+
+    let kinds = check(
+        Header::new_texture_array(4, 4, 3, Format::BC1_UNORM)
+            .unwrap()
+            .with_mipmaps(),
+    );
+    let layers: Vec<(u32, u8)> = kinds.iter().map(|k| (k.layer, k.mipmap_level)).collect();
+    assert_eq!(
+        layers,
+        [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (2, 0),
+            (2, 1),
+            (2, 2)
+        ]
+    );
+    assert!(kinds.iter().all(|k| k.face.is_none() && k.depth_slice == 0));
+
+    let kinds = check(Header::new_cube_map_array(4, 4, 2, Format::BC7_UNORM).unwrap());
+    assert_eq!(kinds.len(), 12);
+    assert_eq!(kinds[7].layer, 1);
+    assert_eq!(kinds[7].face, Some(CubeFace::NegativeX));
+
+    let header: Header = Dx9Header {
+        caps2: Caps2::CUBE_MAP | Caps2::CUBE_MAP_NEGATIVE_X | Caps2::CUBE_MAP_POSITIVE_Z,
+        ..Dx9Header::new_image(4, 4, FourCC::DXT1.into())
+    }
+    .into();
+    let faces: Vec<Option<CubeFace>> = check(header).iter().map(|k| k.face).collect();
+    assert_eq!(
+        faces,
+        [Some(CubeFace::NegativeX), Some(CubeFace::PositiveZ)]
+    );
+
+    let kinds = check(Header::new_volume(4, 4, 4, Format::R8_UNORM).with_mipmaps());
+    let slices: Vec<(u8, u32)> = kinds
+        .iter()
+        .map(|k| (k.mipmap_level, k.depth_slice))
+        .collect();
+    assert_eq!(
+        slices,
+        [(0, 0), (0, 1), (0, 2), (0, 3), (1, 0), (1, 1), (2, 0)]
+    );
+}
+
+#[test]
+fn from_parts() {
+    fn check(header: Header, parts: Result<DataLayout, LayoutError>) {
+        assert_eq!(parts.unwrap(), DataLayout::from_header(&header).unwrap());
+    }
+
+    let format = Format::R8G8B8A8_UNORM;
+    check(
+        Header::new_image(13, 7, format),
+        DataLayout::from_parts(format, 13, 7, None, 1, 1, false),
+    );
+    check(
+        Header::new_image(13, 7, format).with_mipmaps(),
+        DataLayout::from_parts(format, 13, 7, None, 4, 1, false),
+    );
+    check(
+        Header::new_texture_array(13, 7, 3, format).unwrap(),
+        DataLayout::from_parts(format, 13, 7, None, 0, 3, false),
+    );
+    check(
+        Header::new_cube_map(8, 8, Format::BC7_UNORM),
+        DataLayout::from_parts(Format::BC7_UNORM, 8, 8, None, 1, 1, true),
+    );
+    check(
+        Header::new_volume(8, 4, 2, format).with_mipmaps(),
+        DataLayout::from_parts(format, 8, 4, Some(2), 4, 1, false),
+    );
+
+    // invalid parts
+    let from_parts = |depth, mipmaps, array_size, cube_map| {
+        DataLayout::from_parts(format, 4, 4, depth, mipmaps, array_size, cube_map).unwrap_err()
+    };
+    assert!(matches!(
+        from_parts(Some(0), 1, 1, false),
+        LayoutError::ZeroDimension
+    ));
+    assert!(matches!(
+        from_parts(Some(4), 1, 1, true),
+        LayoutError::InvalidCubeMapDimensions
+    ));
+    assert!(matches!(
+        from_parts(Some(4), 1, 2, false),
+        LayoutError::ArraySizeTooBig(2)
+    ));
+    assert!(matches!(
+        from_parts(None, 1, u32::MAX, true),
+        LayoutError::ArraySizeTooBig(u32::MAX)
+    ));
+    assert!(matches!(
+        from_parts(None, 256, 1, false),
+        LayoutError::TooManyMipMaps(256)
+    ));
+}
+
+#[test]
+fn partial_cube_map_faces() {
+    let faces = CubeMapFaces::POSITIVE_X | CubeMapFaces::POSITIVE_Y | CubeMapFaces::NEGATIVE_Z;
+    let header: Header = Dx9Header::new_image(4, 4, FourCC::DXT1.into())
+        .with_cube_map_faces(faces)
+        .into();
+    assert_eq!(header.cube_map_faces(), Some(faces));
+    assert_eq!(
+        faces.faces().collect::<Vec<_>>(),
+        [
+            CubeFace::PositiveX,
+            CubeFace::PositiveY,
+            CubeFace::NegativeZ
+        ]
+    );
+
+    let layout = DataLayout::from_header(&header).unwrap();
+    let array = layout.texture_array().unwrap();
+    assert_eq!(array.len(), 3);
+    assert_eq!(array.cube_map_faces(), Some(faces));
+    for face in CubeFace::ALL {
+        let index = array.face_index(0, face);
+        assert_eq!(index.is_some(), faces.contains_face(face));
+        if let Some(index) = index {
+            assert_eq!(array.face_of(index), Some((0, face)));
+        }
+    }
+    assert_eq!(array.face_of(3), None);
+
+    // full cube maps
+    let header = Header::new_cube_map_array(4, 4, 2, Format::BC1_UNORM).unwrap();
+    assert_eq!(header.cube_map_faces(), Some(CubeMapFaces::ALL));
+    let layout = DataLayout::from_header(&header).unwrap();
+    let array = layout.texture_array().unwrap();
+    assert_eq!(array.cube_map_faces(), Some(CubeMapFaces::ALL));
+    assert_eq!(array.face_of(8), Some((1, CubeFace::PositiveY)));
+
+    let header = Header::new_texture_array(4, 4, 2, Format::BC1_UNORM).unwrap();
+    assert_eq!(header.cube_map_faces(), None);
+    let layout = DataLayout::from_header(&header).unwrap();
+    let array = layout.texture_array().unwrap();
+    assert_eq!(array.cube_map_faces(), None);
+    assert_eq!(array.face_of(0), None);
+}
+
+#[test]
+fn diff_dds_files() {
+    let size = Size::new(8, 8);
+    let mut rng = util::create_rng();
+    let mut pixels = vec![0_u8; size.pixels() as usize * 4];
+    rand::RngCore::fill_bytes(&mut rng, &mut pixels);
+    let image = ImageView::new(pixels.as_slice(), size, ColorFormat::RGBA_U8).unwrap();
+
+    let encode_file = |format: Format, mipmaps: bool| -> Vec<u8> {
+        let mut header = Header::new_image(size.width, size.height, format);
+        if mipmaps {
+            header = header.with_mipmaps();
+        }
+        let mut file = Vec::new();
+        let mut encoder = Encoder::new(&mut file, format, &header).unwrap();
+        let options = WriteOptions {
+            generate_mipmaps: true,
+            ..Default::default()
+        };
+        encoder.write_surface_with(image, |_| {}, &options).unwrap();
+        encoder.finish().unwrap();
+        file
+    };
+    let diff_files = |a: &[u8], b: &[u8], options: &DiffOptions| -> DdsDiff {
+        let mut a_reader = Cursor::new(a);
+        let mut b_reader = Cursor::new(b);
+        let diff = diff_with_options(&mut a_reader, &mut b_reader, options).unwrap();
+        assert_eq!(a_reader.position(), a.len() as u64);
+        assert_eq!(b_reader.position(), b.len() as u64);
+        diff
+    };
+    let options = DiffOptions::default();
+
+    let rgba = encode_file(Format::R8G8B8A8_UNORM, true);
+    assert!(diff_files(&rgba, &rgba, &options).is_empty());
+
+    // change one byte of mipmap level 1
+    let mut changed = rgba.clone();
+    let level1_offset = changed.len() - (4 * 4 + 2 * 2 + 1) * 4;
+    changed[level1_offset] ^= 0xFF;
+    let diff = diff_files(&rgba, &changed, &options);
+    assert!(diff.header.is_empty());
+    assert!(!diff.layout_differs);
+    assert_eq!(diff.surfaces.len(), 1);
+    let surface = &diff.surfaces[0];
+    assert_eq!((surface.element, surface.level), (0, 1));
+    assert_eq!((surface.width, surface.height), (4, 4));
+    assert_eq!(surface.differing_bytes, Some(1));
+    assert_eq!(surface.psnr, None);
+
+    // different formats are compared by PSNR
+    let bc1 = encode_file(Format::BC1_UNORM, true);
+    let diff = diff_files(&rgba, &bc1, &options);
+    assert!(diff.header.iter().any(|d| d.field == "dx10.dxgi_format"));
+    assert!(!diff.layout_differs);
+    assert!(!diff.surfaces.is_empty());
+    for surface in &diff.surfaces {
+        assert_eq!(surface.differing_bytes, None);
+        let psnr = surface.psnr.as_ref().unwrap();
+        assert_eq!(psnr.len(), 4);
+        assert!(psnr.iter().all(|&p| p > 0.0));
+    }
+    let mut no_psnr = DiffOptions::default();
+    no_psnr.psnr = false;
+    assert!(diff_files(&rgba, &bc1, &no_psnr).surfaces.is_empty());
+
+    // missing mipmaps
+    let no_mipmaps = encode_file(Format::R8G8B8A8_UNORM, false);
+    let diff = diff_files(&rgba, &no_mipmaps, &options);
+    assert!(diff.layout_differs);
+    assert!(diff.header.iter().any(|d| d.field == "mipmap_count"));
+    assert!(diff.surfaces.is_empty());
+}
@@ -0,0 +1,67 @@
+use dds::*;
+
+use rand::Rng;
+
+mod util;
+
+#[test]
+fn psnr() {
+    let size = Size::new(4, 4);
+    let original = vec![0.5_f32; 16 * 3];
+    let mut other = original.clone();
+    // every value of the first channel is off by 0.1, so MSE = 0.01
+    for v in other.iter_mut().step_by(3) {
+        *v += 0.1;
+    }
+
+    let psnr = metrics::psnr(&original, &other, size, Channels::Rgb);
+    assert!((psnr[0] - 20.0).abs() < 1e-4, "{psnr:?}");
+    assert_eq!(psnr[1], f64::INFINITY);
+    assert_eq!(psnr[2], f64::INFINITY);
+}
+
+#[test]
+fn metrics_per_channel() {
+    let size = Size::new(32, 24);
+    let mut rng = util::create_rng();
+    let original: Vec<f32> = (0..size.pixels() * 4).map(|_| rng.gen()).collect();
+
+    // red: small noise, green: constant offset, blue: large noise, alpha: identical
+    let other: Vec<f32> = original
+        .chunks_exact(4)
+        .flat_map(|p| {
+            [
+                p[0] + rng.gen_range(-0.02..0.02),
+                p[1] + 0.05,
+                p[2] + rng.gen_range(-0.3..0.3),
+                p[3],
+            ]
+        })
+        .collect();
+
+    let psnr = metrics::psnr(&original, &other, size, Channels::Rgba);
+    assert!(psnr[0] > psnr[2], "{psnr:?}");
+    assert_eq!(psnr[3], f64::INFINITY);
+
+    let ssim = metrics::ssim(&original, &other, size, Channels::Rgba);
+    assert!(ssim[0] > ssim[2], "{ssim:?}");
+    assert!(ssim[2] > 0.0 && ssim[2] < 0.9, "{ssim:?}");
+    assert_eq!(ssim[3], 1.0);
+
+    let region_error = metrics::region_error(&original, &other, size, Channels::Rgba);
+    assert!((region_error[1] - 0.05).abs() < 1e-6, "{region_error:?}");
+    assert!(region_error[0] < 0.02, "{region_error:?}");
+    assert_eq!(region_error[3], 0.0);
+
+    // blurring removes most of the noise, but not the offset
+    let psnr_blurred = metrics::psnr_blurred(&original, &other, size, Channels::Rgba);
+    assert!(psnr_blurred[0] > psnr[0], "{psnr_blurred:?}");
+    assert!((psnr_blurred[1] - psnr[1]).abs() < 1e-3, "{psnr_blurred:?}");
+}
+
+#[test]
+#[should_panic]
+fn metrics_invalid_length() {
+    let original = vec![0.0_f32; 16 * 3];
+    metrics::psnr(&original, &original, Size::new(4, 4), Channels::Rgba);
+}
@@ -94,86 +94,7 @@ pub fn example_dds_files_in(parent_dir: &str) -> Vec<PathBuf> {
     .collect()
 }
 
-pub const ALL_FORMATS: &[Format] = &[
-    // uncompressed formats
-    Format::R8G8B8_UNORM,
-    Format::B8G8R8_UNORM,
-    Format::R8G8B8A8_UNORM,
-    Format::R8G8B8A8_SNORM,
-    Format::B8G8R8A8_UNORM,
-    Format::B8G8R8X8_UNORM,
-    Format::B5G6R5_UNORM,
-    Format::B5G5R5A1_UNORM,
-    Format::B4G4R4A4_UNORM,
-    Format::A4B4G4R4_UNORM,
-    Format::R8_SNORM,
-    Format::R8_UNORM,
-    Format::R8G8_UNORM,
-    Format::R8G8_SNORM,
-    Format::A8_UNORM,
-    Format::R16_UNORM,
-    Format::R16_SNORM,
-    Format::R16G16_UNORM,
-    Format::R16G16_SNORM,
-    Format::R16G16B16A16_UNORM,
-    Format::R16G16B16A16_SNORM,
-    Format::R10G10B10A2_UNORM,
-    Format::R11G11B10_FLOAT,
-    Format::R9G9B9E5_SHAREDEXP,
-    Format::R16_FLOAT,
-    Format::R16G16_FLOAT,
-    Format::R16G16B16A16_FLOAT,
-    Format::R32_FLOAT,
-    Format::R32G32_FLOAT,
-    Format::R32G32B32_FLOAT,
-    Format::R32G32B32A32_FLOAT,
-    Format::R10G10B10_XR_BIAS_A2_UNORM,
-    Format::AYUV,
-    Format::Y410,
-    Format::Y416,
-    // sub-sampled formats
-    Format::R1_UNORM,
-    Format::R8G8_B8G8_UNORM,
-    Format::G8R8_G8B8_UNORM,
-    Format::UYVY,
-    Format::YUY2,
-    Format::Y210,
-    Format::Y216,
-    // bi-planar formats
-    Format::NV12,
-    Format::P010,
-    Format::P016,
-    // block compression formats
-    Format::BC1_UNORM,
-    Format::BC2_UNORM,
-    Format::BC2_UNORM_PREMULTIPLIED_ALPHA,
-    Format::BC3_UNORM,
-    Format::BC3_UNORM_PREMULTIPLIED_ALPHA,
-    Format::BC4_UNORM,
-    Format::BC4_SNORM,
-    Format::BC5_UNORM,
-    Format::BC5_SNORM,
-    Format::BC6H_UF16,
-    Format::BC6H_SF16,
-    Format::BC7_UNORM,
-    // ASTC formats
-    Format::ASTC_4X4_UNORM,
-    Format::ASTC_5X4_UNORM,
-    Format::ASTC_5X5_UNORM,
-    Format::ASTC_6X5_UNORM,
-    Format::ASTC_6X6_UNORM,
-    Format::ASTC_8X5_UNORM,
-    Format::ASTC_8X6_UNORM,
-    Format::ASTC_8X8_UNORM,
-    Format::ASTC_10X5_UNORM,
-    Format::ASTC_10X6_UNORM,
-    Format::ASTC_10X8_UNORM,
-    Format::ASTC_10X10_UNORM,
-    Format::ASTC_12X10_UNORM,
-    Format::ASTC_12X12_UNORM,
-    // non-standard formats
-    Format::BC3_UNORM_RXGB,
-];
+pub const ALL_FORMATS: &[Format] = Format::all();
 
 pub const ALL_COLORS: &[ColorFormat] = &[
     ColorFormat::ALPHA_U8,
@@ -188,6 +109,10 @@ pub const ALL_COLORS: &[ColorFormat] = &[
     ColorFormat::GRAYSCALE_F32,
     ColorFormat::RGB_F32,
     ColorFormat::RGBA_F32,
+    ColorFormat::ALPHA_U32,
+    ColorFormat::GRAYSCALE_U32,
+    ColorFormat::RGB_U32,
+    ColorFormat::RGBA_U32,
 ];
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -250,13 +175,13 @@ impl<T> Image<T> {
         ColorFormat::new(self.channels, T::PRECISION)
     }
 
-    pub fn view(&self) -> ImageView
+    pub fn view(&self) -> ImageView<'_>
     where
         T: Castable + WithPrecision,
     {
         ImageView::new(self.as_bytes(), self.size, self.color()).unwrap()
     }
-    pub fn view_mut(&mut self) -> ImageViewMut
+    pub fn view_mut(&mut self) -> ImageViewMut<'_>
     where
         T: Castable + WithPrecision,
     {
@@ -329,6 +254,9 @@ impl WithPrecision for u16 {
 impl WithPrecision for f32 {
     const PRECISION: Precision = F32;
 }
+impl WithPrecision for u32 {
+    const PRECISION: Precision = U32;
+}
 
 #[derive(Clone, Copy)]
 pub struct ReadSettings {
@@ -1027,81 +955,6 @@ pub fn measure_compression_quality(org: &Image<f32>, compressed: &Image<f32>) ->
     let width = org.size.width as usize;
     let height = org.size.height as usize;
 
-    fn calculate_psnr<T, F>(org: &[T], compressed: &[T], get_value: F) -> f64
-    where
-        T: Copy,
-        F: Fn(T) -> f64,
-    {
-        let mut mse = 0.0;
-        for (&o, &c) in org.iter().zip(compressed.iter()) {
-            let diff = get_value(o) - get_value(c);
-            mse += diff * diff;
-        }
-        mse /= org.len() as f64;
-        -10.0 * mse.log10()
-    }
-    fn box_blur<T, F>(image: &[T], width: usize, height: usize, get_value: F) -> Vec<f64>
-    where
-        T: Copy,
-        F: Fn(T) -> f64,
-    {
-        let mut blurred: Vec<f64> = image.iter().map(|&x| get_value(x)).collect();
-
-        const GAUSS_WEIGHTS: [f64; 5] = {
-            let raw = [1.0, 4.0, 6.0, 4.0, 1.0];
-            let sum = raw[0] + raw[1] + raw[2] + raw[3] + raw[4];
-            [
-                raw[0] / sum,
-                raw[1] / sum,
-                raw[2] / sum,
-                raw[3] / sum,
-                raw[4] / sum,
-            ]
-        };
-        fn weigh(values: [f64; 5]) -> f64 {
-            values[0] * GAUSS_WEIGHTS[0]
-                + values[1] * GAUSS_WEIGHTS[1]
-                + values[2] * GAUSS_WEIGHTS[2]
-                + values[3] * GAUSS_WEIGHTS[3]
-                + values[4] * GAUSS_WEIGHTS[4]
-        }
-
-        // Pass 1: horizontal
-        for y in 0..height {
-            let index_base = y * width;
-            let mut prev_prev = blurred[index_base];
-            let mut prev = blurred[index_base];
-            let last_index = index_base + width - 1;
-            for x in 0..width {
-                let current = blurred[index_base + x];
-                let next = blurred[last_index.min(index_base + x + 1)];
-                let next_next = blurred[last_index.min(index_base + x + 2)];
-                let sum = weigh([prev_prev, prev, current, next, next_next]);
-                prev_prev = prev;
-                prev = current;
-                blurred[index_base + x] = sum;
-            }
-        }
-
-        // Pass 2: vertical
-        for x in 0..width {
-            let mut prev_prev = blurred[x];
-            let mut prev = blurred[x];
-            let last_index = (height - 1) * width + x;
-            for y in 0..height {
-                let index = y * width + x;
-                let current = blurred[index];
-                let next = blurred[last_index.min(index + width)];
-                let next_next = blurred[last_index.min(index + 2 * width)];
-                let sum = weigh([prev_prev, prev, current, next, next_next]);
-                prev_prev = prev;
-                prev = current;
-                blurred[index] = sum;
-            }
-        }
-
-        blurred
-    }
     fn calculate_metrics<T, F>(
         org: &[T],
         compressed: &[T],
@@ -1112,39 +965,18 @@ pub fn measure_compression_quality(org: &Image<f32>, compressed: &Image<f32>) ->
     ) -> Metrics
     where
         T: Copy,
-        F: Copy + Fn(T) -> f64,
+        F: Copy + Fn(T) -> f32,
     {
-        // PSNR
-        let psnr = calculate_psnr(org, compressed, get_value);
-
-        // blurred PSNR
-        let blurred_org = box_blur(org, width, height, get_value);
-        let blurred_compressed = box_blur(compressed, width, height, get_value);
-        let psnr_blur = calculate_psnr(&blurred_org, &blurred_compressed, |x| x);
-
-        // region error is just the absolute average error per 4x4 region
-        const REGION_SIZE: usize = 4;
-        let mut region_error = 0.0;
-        for region_y in 0..height / REGION_SIZE {
-            for region_x in 0..width / REGION_SIZE {
-                let mut region = 0.0;
-                for y in 0..REGION_SIZE {
-                    for x in 0..REGION_SIZE {
-                        let i = (region_y * REGION_SIZE + y) * width + region_x * REGION_SIZE + x;
-                        let diff = get_value(org[i]) - get_value(compressed[i]);
-                        region += diff;
-                    }
-                }
-                region_error += region.abs() / (REGION_SIZE * REGION_SIZE) as f64;
-            }
-        }
-        region_error /= (width / REGION_SIZE * height / REGION_SIZE) as f64;
+        let org: Vec<f32> = org.iter().map(|&x| get_value(x)).collect();
+        let compressed: Vec<f32> = compressed.iter().map(|&x| get_value(x)).collect();
+        let size = Size::new(width as u32, height as u32);
+        let gray = Channels::Grayscale;
 
         Metrics {
             channel,
-            psnr,
-            psnr_blur,
-            region_error,
+            psnr: metrics::psnr(&org, &compressed, size, gray)[0],
+            psnr_blur: metrics::psnr_blurred(&org, &compressed, size, gray)[0],
+            region_error: metrics::region_error(&org, &compressed, size, gray)[0],
         }
     }
 
@@ -1156,7 +988,7 @@ pub fn measure_compression_quality(org: &Image<f32>, compressed: &Image<f32>) ->
                 width,
                 height,
                 MetricChannel::L,
-                |x| x as f64,
+                |x| x,
             );
 
             vec![l]
@@ -1172,17 +1004,11 @@ pub fn measure_compression_quality(org: &Image<f32>, compressed: &Image<f32>) ->
                 width,
                 height,
                 MetricChannel::L,
-                |[r, g, b]| (r * 0.25 + g * 0.6 + b * 0.15) as f64,
+                |[r, g, b]| r * 0.25 + g * 0.6 + b * 0.15,
             );
-            let r = calculate_metrics(org, compressed, width, height, MetricChannel::R, |x| {
-                x[0] as f64
-            });
-            let g = calculate_metrics(org, compressed, width, height, MetricChannel::G, |x| {
-                x[1] as f64
-            });
-            let b = calculate_metrics(org, compressed, width, height, MetricChannel::B, |x| {
-                x[2] as f64
-            });
+            let r = calculate_metrics(org, compressed, width, height, MetricChannel::R, |x| x[0]);
+            let g = calculate_metrics(org, compressed, width, height, MetricChannel::G, |x| x[1]);
+            let b = calculate_metrics(org, compressed, width, height, MetricChannel::B, |x| x[2]);
 
             vec![l, r, g, b]
         }
@@ -1196,20 +1022,12 @@ pub fn measure_compression_quality(org: &Image<f32>, compressed: &Image<f32>) ->
                 width,
                 height,
                 MetricChannel::L,
-                |[r, g, b, a]| ((r * 0.25 + g * 0.6 + b * 0.15) * a) as f64,
+                |[r, g, b, a]| (r * 0.25 + g * 0.6 + b * 0.15) * a,
             );
-            let r = calculate_metrics(org, compressed, width, height, MetricChannel::R, |x| {
-                x[0] as f64
-            });
-            let g = calculate_metrics(org, compressed, width, height, MetricChannel::G, |x| {
-                x[1] as f64
-            });
-            let b = calculate_metrics(org, compressed, width, height, MetricChannel::B, |x| {
-                x[2] as f64
-            });
-            let a = calculate_metrics(org, compressed, width, height, MetricChannel::A, |x| {
-                x[3] as f64
-            });
+            let r = calculate_metrics(org, compressed, width, height, MetricChannel::R, |x| x[0]);
+            let g = calculate_metrics(org, compressed, width, height, MetricChannel::G, |x| x[1]);
+            let b = calculate_metrics(org, compressed, width, height, MetricChannel::B, |x| x[2]);
+            let a = calculate_metrics(org, compressed, width, height, MetricChannel::A, |x| x[3]);
 
             vec![l, r, g, b, a]
         }